@@ -274,7 +274,7 @@ impl BatchProductionManager {
         prompts: &[(String, String)],
         config: &BatchProductionConfig,
     ) -> Vec<QueuedTask> {
-        let mut queue = TaskQueue::with_max_concurrent(config.max_concurrent_tasks as usize);
+        let mut queue = TaskQueue::new();
         let mut tasks = Vec::new();
 
         for (scene_id, prompt) in prompts {
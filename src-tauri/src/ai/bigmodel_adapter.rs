@@ -60,6 +60,17 @@ struct BigModelStreamDelta {
     content: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct BigModelEmbeddingResponse {
+    data: Vec<BigModelEmbedding>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BigModelEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
 pub struct BigModelAdapter {
     api_key: String,
     base_url: String,
@@ -226,6 +237,35 @@ impl AIModel for BigModelAdapter {
 
         Ok(ModelStream::new(Box::new(item_stream)))
     }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        self.logger.info(&format!("Starting BigModel embedding with model: {}, {} texts", self.model, texts.len()));
+
+        let response = self
+            .client
+            .post(format!("{}/embeddings", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({ "model": self.model, "input": texts }))
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            self.logger.error(&format!("BigModel embedding API error: {} - {}", status, error_text));
+            return Err(format!("BigModel API error: {} - {}", status, error_text));
+        }
+
+        let mut response: BigModelEmbeddingResponse =
+            response.json().await.map_err(|e| format!("Failed to parse response: {}", e))?;
+        if response.data.len() != texts.len() {
+            return Err(format!("Expected {} embeddings, got {}", texts.len(), response.data.len()));
+        }
+        response.data.sort_by_key(|d| d.index);
+        Ok(response.data.into_iter().map(|d| d.embedding).collect())
+    }
 }
 
 impl BigModelAdapter {
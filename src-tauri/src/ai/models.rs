@@ -67,6 +67,9 @@ pub struct AICompletionRequest {
     pub worldview_context: Option<String>,
     pub project_id: Option<String>,
     pub chapter_mission_id: Option<String>,
+    /// 正在续写的章节，用于注入本章地点
+    #[serde(default)]
+    pub chapter_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
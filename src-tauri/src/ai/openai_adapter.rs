@@ -60,6 +60,17 @@ struct OpenAIStreamDelta {
     content: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct OpenAIEmbeddingResponse {
+    data: Vec<OpenAIEmbedding>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
 #[derive(Debug, Clone)]
 pub struct OpenAIAdapter {
     api_key: String,
@@ -293,4 +304,33 @@ impl AIModel for OpenAIAdapter {
 
         Ok(ModelStream::new(Box::new(item_stream)))
     }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        self.logger.info(&format!("Starting OpenAI embedding with model: {}, {} texts", self.model, texts.len()));
+
+        let response = self
+            .client
+            .post(format!("{}/embeddings", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({ "model": self.model, "input": texts }))
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            self.logger.error(&format!("OpenAI embedding API error: {} - {}", status, error_text));
+            return Err(format!("OpenAI API error: {} - {}", status, error_text));
+        }
+
+        let mut response: OpenAIEmbeddingResponse =
+            response.json().await.map_err(|e| format!("Failed to parse response: {}", e))?;
+        if response.data.len() != texts.len() {
+            return Err(format!("Expected {} embeddings, got {}", texts.len(), response.data.len()));
+        }
+        response.data.sort_by_key(|d| d.index);
+        Ok(response.data.into_iter().map(|d| d.embedding).collect())
+    }
 }
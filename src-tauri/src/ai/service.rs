@@ -18,6 +18,8 @@ use tokio::sync::RwLock;
 
 pub struct AIService {
     model_registry: ModelRegistry,
+    /// 向量嵌入模型，与对话模型分开，不出现在模型列表中
+    embedding_registry: ModelRegistry,
     prompt_manager: PromptManager,
     logger: Logger,
 }
@@ -26,6 +28,7 @@ impl AIService {
     pub fn new() -> Self {
         Self {
             model_registry: ModelRegistry::new(),
+            embedding_registry: ModelRegistry::new(),
             prompt_manager: PromptManager::new(),
             logger: Logger::new().with_feature("ai-service"),
         }
@@ -48,10 +51,19 @@ impl AIService {
         self.model_registry.register_model("glm-4-air".to_string(), glm4_air).await;
         self.model_registry.register_model("glm-4-flash".to_string(), glm4_flash).await;
         self.model_registry.register_model("glm-4-flashx".to_string(), glm4_flashx).await;
+        self.initialize_default_embedding_models().await;
 
         self.logger.info("Default BigModel models initialized successfully");
     }
 
+    /// 注册智谱的向量嵌入模型，API Key 变化后需重新调用
+    pub async fn initialize_default_embedding_models(&self) {
+        let api_key = std::env::var("BIGMODEL_API_KEY")
+            .unwrap_or_else(|_| "45913d02a609452b916a1706b8dc9702".to_string());
+        let embedding3 = Arc::new(BigModelAdapter::new(api_key, "embedding-3".to_string()));
+        self.embedding_registry.register_model("embedding-3".to_string(), embedding3).await;
+    }
+
     pub fn get_registry(&self) -> &ModelRegistry {
         &self.model_registry
     }
@@ -107,6 +119,16 @@ impl AIService {
         Ok(response.content)
     }
 
+    /// 文本向量化，`model_id` 须是支持嵌入的模型，如 embedding-3
+    pub async fn embed(&self, model_id: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let model = self
+            .embedding_registry
+            .get_model(model_id)
+            .await
+            .ok_or_else(|| format!("Embedding model not found: {}", model_id))?;
+        model.embed(texts).await
+    }
+
     pub async fn complete_stream(
         &self,
         model_id: &str,
@@ -3,6 +3,9 @@ use std::collections::{BinaryHeap, HashMap};
 use std::cmp::Ordering;
 use uuid::Uuid;
 use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum TaskType {
@@ -95,8 +98,6 @@ pub struct CreateTaskRequest {
 pub struct TaskQueue {
     tasks: HashMap<String, QueuedTask>,
     pending_queue: BinaryHeap<QueuedTask>,
-    max_concurrent: usize,
-    running_count: usize,
 }
 
 impl TaskQueue {
@@ -104,17 +105,6 @@ impl TaskQueue {
         Self {
             tasks: HashMap::new(),
             pending_queue: BinaryHeap::new(),
-            max_concurrent: 3,
-            running_count: 0,
-        }
-    }
-
-    pub fn with_max_concurrent(max_concurrent: usize) -> Self {
-        Self {
-            tasks: HashMap::new(),
-            pending_queue: BinaryHeap::new(),
-            max_concurrent,
-            running_count: 0,
         }
     }
 
@@ -151,17 +141,12 @@ impl TaskQueue {
     }
 
     pub fn get_next_task(&mut self) -> Option<QueuedTask> {
-        if self.running_count >= self.max_concurrent {
-            return None;
-        }
-
         while let Some(task) = self.pending_queue.pop() {
             if let Some(stored_task) = self.tasks.get_mut(&task.id) {
                 if stored_task.state == TaskState::Pending {
                     stored_task.state = TaskState::Running;
                     stored_task.started_at = Some(Utc::now().to_rfc3339());
                     stored_task.updated_at = Utc::now().to_rfc3339();
-                    self.running_count += 1;
                     return Some(stored_task.clone());
                 }
             }
@@ -177,7 +162,6 @@ impl TaskQueue {
                 task.progress = 100;
                 task.completed_at = Some(Utc::now().to_rfc3339());
                 task.updated_at = Utc::now().to_rfc3339();
-                self.running_count = self.running_count.saturating_sub(1);
                 return Some(task.clone());
             }
         }
@@ -193,11 +177,9 @@ impl TaskQueue {
                 if task.retry_count < task.max_retries {
                     task.retry_count += 1;
                     task.state = TaskState::Pending;
-                    self.running_count = self.running_count.saturating_sub(1);
                     self.pending_queue.push(task.clone());
                 } else {
                     task.state = TaskState::Failed;
-                    self.running_count = self.running_count.saturating_sub(1);
                 }
                 
                 return Some(task.clone());
@@ -211,9 +193,6 @@ impl TaskQueue {
             if task.state == TaskState::Pending || task.state == TaskState::Running {
                 task.state = TaskState::Cancelled;
                 task.updated_at = Utc::now().to_rfc3339();
-                if task.state == TaskState::Running {
-                    self.running_count = self.running_count.saturating_sub(1);
-                }
                 return Some(task.clone());
             }
         }
@@ -298,39 +277,319 @@ pub struct TaskQueueStats {
     pub cancelled: usize,
 }
 
+impl TaskType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TaskType::ImageGeneration => "image_generation",
+            TaskType::VideoGeneration => "video_generation",
+            TaskType::AudioGeneration => "audio_generation",
+            TaskType::ScriptGeneration => "script_generation",
+            TaskType::Custom => "custom",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "image_generation" => TaskType::ImageGeneration,
+            "video_generation" => TaskType::VideoGeneration,
+            "audio_generation" => TaskType::AudioGeneration,
+            "script_generation" => TaskType::ScriptGeneration,
+            _ => TaskType::Custom,
+        }
+    }
+}
+
+impl TaskPriority {
+    pub fn value(&self) -> i32 {
+        match self {
+            TaskPriority::Urgent => 20,
+            TaskPriority::High => 10,
+            TaskPriority::Normal => 5,
+            TaskPriority::Low => 1,
+        }
+    }
+
+    pub fn from_value(value: i32) -> Self {
+        match value {
+            v if v >= 20 => TaskPriority::Urgent,
+            v if v >= 10 => TaskPriority::High,
+            v if v >= 5 => TaskPriority::Normal,
+            _ => TaskPriority::Low,
+        }
+    }
+}
+
+impl TaskState {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TaskState::Pending => "pending",
+            TaskState::Running => "running",
+            TaskState::Completed => "completed",
+            TaskState::Failed => "failed",
+            TaskState::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "running" => TaskState::Running,
+            "completed" => TaskState::Completed,
+            "failed" => TaskState::Failed,
+            "cancelled" => TaskState::Cancelled,
+            _ => TaskState::Pending,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self, TaskState::Completed | TaskState::Failed | TaskState::Cancelled)
+    }
+}
+
+// ==================== 持久化（ai_task_queue 表） ====================
+
+const TASK_COLUMNS: &str = "id, project_id, task_type, priority, state, provider, input_data, output_data, error_message, retry_count, max_retries, progress, created_at, updated_at, started_at, completed_at";
+
+fn task_from_row(row: &rusqlite::Row) -> rusqlite::Result<QueuedTask> {
+    let task_type: String = row.get(2)?;
+    let state: String = row.get(4)?;
+    let input_data: String = row.get(6)?;
+    let output_data: Option<String> = row.get(7)?;
+    Ok(QueuedTask {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        task_type: TaskType::parse(&task_type),
+        priority: TaskPriority::from_value(row.get(3)?),
+        state: TaskState::parse(&state),
+        provider: row.get(5)?,
+        input_data: serde_json::from_str(&input_data).unwrap_or(serde_json::Value::Null),
+        output_data: output_data.and_then(|s| serde_json::from_str(&s).ok()),
+        error_message: row.get(8)?,
+        retry_count: row.get(9)?,
+        max_retries: row.get(10)?,
+        progress: row.get(11)?,
+        created_at: row.get(12)?,
+        updated_at: row.get(13)?,
+        started_at: row.get(14)?,
+        completed_at: row.get(15)?,
+    })
+}
+
+/// 写入或覆盖一条任务记录
+pub fn save_task(conn: &Connection, task: &QueuedTask) -> rusqlite::Result<()> {
+    conn.execute(
+        &format!("INSERT OR REPLACE INTO ai_task_queue ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)", TASK_COLUMNS),
+        params![
+            task.id,
+            task.project_id,
+            task.task_type.as_str(),
+            task.priority.value(),
+            task.state.as_str(),
+            task.provider,
+            task.input_data.to_string(),
+            task.output_data.as_ref().map(|v| v.to_string()),
+            task.error_message,
+            task.retry_count,
+            task.max_retries,
+            task.progress,
+            task.created_at,
+            task.updated_at,
+            task.started_at,
+            task.completed_at,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn load_task(conn: &Connection, id: &str) -> rusqlite::Result<Option<QueuedTask>> {
+    conn.query_row(
+        &format!("SELECT {} FROM ai_task_queue WHERE id = ?1", TASK_COLUMNS),
+        params![id],
+        task_from_row,
+    ).optional()
+}
+
+pub fn load_project_tasks(conn: &Connection, project_id: &str) -> rusqlite::Result<Vec<QueuedTask>> {
+    let mut stmt = conn.prepare(
+        &format!("SELECT {} FROM ai_task_queue WHERE project_id = ?1 ORDER BY created_at DESC", TASK_COLUMNS)
+    )?;
+    let tasks = stmt.query_map(params![project_id], task_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(tasks)
+}
+
+/// 更新任务状态；进入运行或结束状态时同时记录时间
+pub fn update_task_state(
+    conn: &Connection,
+    id: &str,
+    state: TaskState,
+    output_data: Option<&serde_json::Value>,
+    error_message: Option<&str>,
+) -> rusqlite::Result<()> {
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "UPDATE ai_task_queue SET
+            state = ?1,
+            output_data = COALESCE(?2, output_data),
+            error_message = COALESCE(?3, error_message),
+            progress = CASE WHEN ?1 = 'completed' THEN 100 ELSE progress END,
+            started_at = CASE WHEN ?1 = 'running' AND started_at IS NULL THEN ?4 ELSE started_at END,
+            completed_at = CASE WHEN ?1 IN ('completed', 'failed', 'cancelled') THEN ?4 ELSE completed_at END,
+            updated_at = ?4
+         WHERE id = ?5",
+        params![state.as_str(), output_data.map(|v| v.to_string()), error_message, now, id],
+    )?;
+    Ok(())
+}
+
+pub fn update_task_progress(conn: &Connection, id: &str, progress: u32) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE ai_task_queue SET progress = ?1, updated_at = ?2 WHERE id = ?3",
+        params![progress.min(100), Utc::now().to_rfc3339(), id],
+    )?;
+    Ok(())
+}
+
+/// 后台任务在每个步骤之间调用，用于响应用户取消
+pub fn is_task_cancelled(conn: &Connection, id: &str) -> bool {
+    conn.query_row(
+        "SELECT state FROM ai_task_queue WHERE id = ?1",
+        params![id],
+        |row| row.get::<_, String>(0),
+    ).map(|state| state == "cancelled").unwrap_or(false)
+}
+
+/// 原子地把任务置为运行中；任务已在运行时返回 false，避免同一任务被启动两次
+pub fn claim_task(conn: &Connection, id: &str) -> rusqlite::Result<bool> {
+    let changed = conn.execute(
+        "UPDATE ai_task_queue SET
+            state = 'running',
+            started_at = COALESCE(started_at, ?1),
+            completed_at = NULL,
+            updated_at = ?1
+         WHERE id = ?2 AND state != 'running'",
+        params![Utc::now().to_rfc3339(), id],
+    )?;
+    Ok(changed == 1)
+}
+
+/// 应用启动时调用：上次退出时仍在运行的任务已随进程中断，标记为失败以便重新开始
+pub fn interrupt_running_tasks(conn: &Connection) -> rusqlite::Result<usize> {
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "UPDATE ai_task_queue SET state = 'failed', error_message = '应用退出时任务中断', completed_at = ?1, updated_at = ?1
+         WHERE state = 'running'",
+        params![now],
+    )
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
+
+fn open_connection(app: &AppHandle) -> Result<Connection, String> {
+    let db_path = get_db_path(app)?;
+    crate::database::get_connection(&db_path).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
-pub async fn create_task(request: CreateTaskRequest) -> Result<QueuedTask, String> {
+pub async fn create_task(app: AppHandle, request: CreateTaskRequest) -> Result<QueuedTask, String> {
     let mut queue = TaskQueue::new();
-    Ok(queue.add_task(request))
+    let task = queue.add_task(request);
+    let conn = open_connection(&app)?;
+    save_task(&conn, &task).map_err(|e| e.to_string())?;
+    Ok(task)
 }
 
 #[tauri::command]
-pub async fn get_task(id: String) -> Result<Option<QueuedTask>, String> {
-    let queue = TaskQueue::new();
-    Ok(queue.get_task(&id).cloned())
+pub async fn get_task(app: AppHandle, id: String) -> Result<Option<QueuedTask>, String> {
+    let conn = open_connection(&app)?;
+    load_task(&conn, &id).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn get_project_tasks(project_id: String) -> Result<Vec<QueuedTask>, String> {
-    let queue = TaskQueue::new();
-    Ok(queue.get_tasks_for_project(&project_id))
+pub async fn get_project_tasks(app: AppHandle, project_id: String) -> Result<Vec<QueuedTask>, String> {
+    let conn = open_connection(&app)?;
+    load_project_tasks(&conn, &project_id).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn cancel_task(id: String) -> Result<Option<QueuedTask>, String> {
-    let mut queue = TaskQueue::new();
-    Ok(queue.cancel_task(&id))
+pub async fn cancel_task(app: AppHandle, id: String) -> Result<Option<QueuedTask>, String> {
+    let conn = open_connection(&app)?;
+    match load_task(&conn, &id).map_err(|e| e.to_string())? {
+        Some(task) if !task.state.is_finished() => {
+            update_task_state(&conn, &id, TaskState::Cancelled, None, None).map_err(|e| e.to_string())?;
+            load_task(&conn, &id).map_err(|e| e.to_string())
+        }
+        _ => Ok(None),
+    }
 }
 
 #[tauri::command]
-pub async fn get_queue_stats() -> Result<TaskQueueStats, String> {
-    let queue = TaskQueue::new();
-    Ok(queue.get_stats())
+pub async fn get_queue_stats(app: AppHandle) -> Result<TaskQueueStats, String> {
+    let conn = open_connection(&app)?;
+    let mut stmt = conn.prepare("SELECT state, COUNT(*) FROM ai_task_queue GROUP BY state")
+        .map_err(|e| e.to_string())?;
+    let counts = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, usize>(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut stats = TaskQueueStats::default();
+    for (state, count) in counts {
+        match TaskState::parse(&state) {
+            TaskState::Pending => stats.pending += count,
+            TaskState::Running => stats.running += count,
+            TaskState::Completed => stats.completed += count,
+            TaskState::Failed => stats.failed += count,
+            TaskState::Cancelled => stats.cancelled += count,
+        }
+        stats.total += count;
+    }
+    Ok(stats)
 }
 
 #[tauri::command]
-pub async fn clear_completed_tasks() -> Result<(), String> {
-    let mut queue = TaskQueue::new();
-    queue.clear_completed();
+pub async fn clear_completed_tasks(app: AppHandle) -> Result<(), String> {
+    let conn = open_connection(&app)?;
+    conn.execute(
+        "DELETE FROM ai_task_queue WHERE state IN ('completed', 'failed', 'cancelled')",
+        [],
+    ).map_err(|e| e.to_string())?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claim_and_interrupt_task() {
+        let (_dir, conn) = crate::test_support::project_db();
+        let task = TaskQueue::new().add_task(CreateTaskRequest {
+            project_id: "p1".to_string(),
+            task_type: TaskType::Custom,
+            priority: None,
+            provider: Some("url_import".to_string()),
+            input_data: serde_json::Value::Null,
+            max_retries: Some(0),
+        });
+        save_task(&conn, &task).unwrap();
+
+        assert!(claim_task(&conn, &task.id).unwrap());
+        assert!(!claim_task(&conn, &task.id).unwrap());
+
+        // 重启后运行中的任务被标记为失败，可以再次占用
+        assert_eq!(interrupt_running_tasks(&conn).unwrap(), 1);
+        assert_eq!(load_task(&conn, &task.id).unwrap().unwrap().state, TaskState::Failed);
+        assert!(claim_task(&conn, &task.id).unwrap());
+    }
+}
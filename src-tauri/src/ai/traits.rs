@@ -32,4 +32,9 @@ pub trait AIModel: Send + Sync {
     async fn complete(&self, request: AIRequest) -> Result<AIResponse, String>;
     
     async fn complete_stream(&self, request: AIRequest) -> Result<ModelStream, String>;
+
+    /// 文本向量化，结果与 `texts` 一一对应；不支持的模型返回错误
+    async fn embed(&self, _texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        Err(format!("Model {} does not support embeddings", self.get_name()))
+    }
 }
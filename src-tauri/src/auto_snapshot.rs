@@ -0,0 +1,147 @@
+use crate::logger::Logger;
+use crate::snapshot_store;
+use crate::version_control::VersionControlManager;
+use crate::version_control_commands::{load_version_config, save_project_snapshot};
+use crate::version_storage;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// 最后一次保存后等待这么久没有新的保存，才检查是否需要自动快照
+const DEBOUNCE: Duration = Duration::from_secs(30);
+/// 后台清理快照、统计存储占用的间隔
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 保存章节后的自动快照，按项目防抖
+#[derive(Clone)]
+pub struct AutoSnapshotState {
+    db_path: PathBuf,
+    /// 每个项目最近一次保存的序号，防抖等待结束时序号未变才继续
+    pending: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl AutoSnapshotState {
+    pub fn new(db_path: PathBuf) -> Self {
+        Self { db_path, pending: Arc::new(Mutex::new(HashMap::new())) }
+    }
+
+    /// 记录一次保存；防抖等待结束后，距上次自动快照超过间隔或字数变化超过阈值时生成快照
+    pub fn schedule(&self, project_id: String) {
+        let generation = {
+            let mut pending = self.pending.lock().unwrap();
+            let generation = pending.entry(project_id.clone()).or_insert(0);
+            *generation += 1;
+            *generation
+        };
+        let state = self.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(DEBOUNCE).await;
+            {
+                let mut pending = state.pending.lock().unwrap();
+                if pending.get(&project_id) != Some(&generation) {
+                    return;
+                }
+                pending.remove(&project_id);
+            }
+            let db_path = state.db_path.clone();
+            let result = tauri::async_runtime::spawn_blocking(move || {
+                let conn = crate::database::get_connection(&db_path).map_err(|e| e.to_string())?;
+                auto_snapshot(&conn, &project_id)
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result);
+            if let Err(e) = result {
+                Logger::new().with_feature("version_control").warn(&format!("Auto snapshot failed: {}", e));
+            }
+        });
+    }
+}
+
+/// 按配置判断并生成自动快照，返回是否生成了快照
+pub fn auto_snapshot(conn: &Connection, project_id: &str) -> Result<bool, String> {
+    let config = load_version_config(conn);
+    let last = conn
+        .query_row(
+            "SELECT timestamp, metadata_json FROM project_snapshots
+             WHERE project_id = ? AND auto_generated = 1 ORDER BY timestamp DESC LIMIT 1",
+            params![project_id],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .map(|(timestamp, metadata)| {
+            let words = serde_json::from_str::<serde_json::Value>(&metadata)
+                .ok()
+                .and_then(|m| m["total_words"].as_i64())
+                .unwrap_or_default();
+            (timestamp, words as i32)
+        });
+    let total_words: i32 = conn
+        .query_row("SELECT COALESCE(SUM(word_count), 0) FROM chapters WHERE project_id = ?", params![project_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+
+    let now = Utc::now();
+    if !VersionControlManager::should_auto_snapshot(&config, last, now.timestamp(), total_words) {
+        return Ok(false);
+    }
+    let version = now.format("auto-%Y%m%d-%H%M%S").to_string();
+    save_project_snapshot(conn, project_id, &version, "自动快照", true)?;
+    prune_auto_snapshots(conn, project_id)?;
+    Ok(true)
+}
+
+/// 按保留策略删除项目的自动快照，带标签的快照除外，返回删除的数量
+pub fn prune_auto_snapshots(conn: &Connection, project_id: &str) -> Result<usize, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, timestamp FROM project_snapshots WHERE project_id = ? AND auto_generated = 1
+             AND id NOT IN (SELECT snapshot_id FROM snapshot_tags)",
+        )
+        .map_err(|e| e.to_string())?;
+    let snapshots = stmt
+        .query_map(params![project_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let pruned = VersionControlManager::snapshots_to_prune(&snapshots, Utc::now().timestamp());
+    for id in &pruned {
+        conn.execute("DELETE FROM project_snapshots WHERE id = ?", params![id]).map_err(|e| e.to_string())?;
+    }
+    if !pruned.is_empty() {
+        snapshot_store::remove_orphan_chunks(conn)?;
+    }
+    Ok(pruned.len())
+}
+
+/// 启动后台任务，定期按配置的策略清理所有项目的快照并记录存储占用
+pub fn spawn_retention_task(db_path: PathBuf) {
+    tauri::async_runtime::spawn(async move {
+        let logger = Logger::new().with_feature("version_control");
+        let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let db_path = db_path.clone();
+            let result = tauri::async_runtime::spawn_blocking(move || {
+                let conn = crate::database::get_connection(&db_path).map_err(|e| e.to_string())?;
+                version_storage::run_maintenance(&conn)
+            })
+            .await;
+            match result {
+                Ok(Ok(reports)) => {
+                    let pruned: i64 = reports.iter().map(|r| r.last_pruned).sum();
+                    let total_bytes: i64 = reports.iter().map(|r| r.total_bytes).sum();
+                    if pruned > 0 {
+                        logger.info(&format!("Pruned {} snapshots", pruned));
+                    }
+                    logger.debug(&format!("Version storage: {} projects, {} bytes", reports.len(), total_bytes));
+                }
+                Ok(Err(e)) => logger.warn(&format!("Failed to prune snapshots: {}", e)),
+                Err(e) => logger.warn(&format!("Snapshot pruning task failed: {}", e)),
+            }
+        }
+    });
+}
@@ -0,0 +1,245 @@
+use crate::version_control_commands::save_restore_point;
+use chrono::Utc;
+use regex::Regex;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// 规则作用范围：global 对所有项目生效，project 只对所属项目生效
+pub const SCOPE_GLOBAL: &str = "global";
+pub const SCOPE_PROJECT: &str = "project";
+
+/// 自动更正规则。`is_regex` 为 false 时按原文匹配；为 true 时 `replacement` 可用 `$1` 引用捕获组
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutocorrectRule {
+    pub id: String,
+    pub project_id: Option<String>,
+    pub pattern: String,
+    pub replacement: String,
+    pub is_regex: bool,
+    pub scope: String,
+    pub enabled: bool,
+    pub created_at: String,
+}
+
+/// 一次更正，`position` 是在该规则处理前文本中的字符偏移
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Correction {
+    pub rule_id: String,
+    pub position: usize,
+    pub original: String,
+    pub corrected: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectCorrection {
+    pub restore_point_id: Option<String>,
+    pub corrections: usize,
+    pub chapters_changed: Vec<String>,
+}
+
+fn compile(rule: &AutocorrectRule) -> Result<Regex, String> {
+    let pattern = if rule.is_regex { rule.pattern.clone() } else { regex::escape(&rule.pattern) };
+    Regex::new(&pattern).map_err(|e| format!("Invalid pattern {}: {}", rule.pattern, e))
+}
+
+/// 对项目生效的所有规则（全局规则在前），包括停用的
+pub fn list_rules(conn: &Connection, project_id: &str) -> Result<Vec<AutocorrectRule>, String> {
+    conn.prepare(
+        "SELECT id, project_id, pattern, replacement, is_regex, scope, enabled, created_at FROM autocorrect_rules
+         WHERE scope = ?1 OR project_id = ?2 ORDER BY scope = ?1 DESC, created_at",
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![SCOPE_GLOBAL, project_id], |row| {
+        Ok(AutocorrectRule {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            pattern: row.get(2)?,
+            replacement: row.get(3)?,
+            is_regex: row.get::<_, i32>(4)? != 0,
+            scope: row.get(5)?,
+            enabled: row.get::<_, i32>(6)? != 0,
+            created_at: row.get(7)?,
+        })
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+/// 新建或修改规则（`id` 为空时新建），保存前检查正则是否有效
+pub fn save_rule(conn: &Connection, mut rule: AutocorrectRule) -> Result<AutocorrectRule, String> {
+    if rule.pattern.is_empty() {
+        return Err("Pattern cannot be empty".to_string());
+    }
+    match rule.scope.as_str() {
+        SCOPE_GLOBAL => rule.project_id = None,
+        SCOPE_PROJECT if rule.project_id.is_some() => {}
+        SCOPE_PROJECT => return Err("Project rules need a project_id".to_string()),
+        other => return Err(format!("Unknown scope: {}", other)),
+    }
+    compile(&rule)?;
+    if rule.id.is_empty() {
+        rule.id = uuid::Uuid::new_v4().to_string();
+        rule.created_at = Utc::now().to_rfc3339();
+    }
+    conn.execute(
+        "INSERT INTO autocorrect_rules (id, project_id, pattern, replacement, is_regex, scope, enabled, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(id) DO UPDATE SET project_id = excluded.project_id, pattern = excluded.pattern, replacement = excluded.replacement,
+             is_regex = excluded.is_regex, scope = excluded.scope, enabled = excluded.enabled",
+        params![
+            rule.id,
+            rule.project_id,
+            rule.pattern,
+            rule.replacement,
+            rule.is_regex as i32,
+            rule.scope,
+            rule.enabled as i32,
+            rule.created_at,
+        ],
+    )
+    .map_err(|e| format!("Failed to save autocorrect rule: {}", e))?;
+    Ok(rule)
+}
+
+pub fn delete_rule(conn: &Connection, rule_id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM autocorrect_rules WHERE id = ?", params![rule_id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 依次应用启用的规则，返回更正后的文本和每处更正
+pub fn apply_rules(text: &str, rules: &[AutocorrectRule]) -> Result<(String, Vec<Correction>), String> {
+    let mut text = text.to_string();
+    let mut corrections = Vec::new();
+    for rule in rules.iter().filter(|r| r.enabled) {
+        let re = compile(rule)?;
+        let mut result = String::with_capacity(text.len());
+        let (mut last, mut position) = (0, 0);
+        for caps in re.captures_iter(&text) {
+            let whole = caps.get(0).expect("capture 0 always exists");
+            let mut corrected = String::new();
+            if rule.is_regex {
+                caps.expand(&rule.replacement, &mut corrected);
+            } else {
+                corrected.push_str(&rule.replacement);
+            }
+            if corrected == whole.as_str() {
+                continue;
+            }
+            position += text[last..whole.start()].chars().count();
+            corrections.push(Correction {
+                rule_id: rule.id.clone(),
+                position,
+                original: whole.as_str().to_string(),
+                corrected: corrected.clone(),
+            });
+            position += whole.as_str().chars().count();
+            result.push_str(&text[last..whole.start()]);
+            result.push_str(&corrected);
+            last = whole.end();
+        }
+        result.push_str(&text[last..]);
+        text = result;
+    }
+    Ok((text, corrections))
+}
+
+/// 保存章节前调用：按章节所属项目的规则更正正文
+pub fn correct_on_save(conn: &Connection, chapter_id: &str, content: &str) -> Result<String, String> {
+    let project_id: String = conn
+        .query_row("SELECT project_id FROM chapters WHERE id = ?", params![chapter_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    let (corrected, _) = apply_rules(content, &list_rules(conn, &project_id)?)?;
+    Ok(corrected)
+}
+
+/// 对项目所有章节运行规则。有改动时先建立还原点，所有章节在一个事务中写入
+pub fn correct_project(conn: &Connection, project_id: &str) -> Result<ProjectCorrection, String> {
+    let rules = list_rules(conn, project_id)?;
+    let chapters: Vec<(String, String)> = conn
+        .prepare("SELECT id, COALESCE(content, '') FROM chapters WHERE project_id = ? ORDER BY sort_order, created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut result = ProjectCorrection::default();
+    let mut changes = Vec::new();
+    for (chapter_id, content) in chapters {
+        let (corrected, corrections) = apply_rules(&content, &rules)?;
+        if corrected != content {
+            result.corrections += corrections.len();
+            changes.push((chapter_id, content, corrected));
+        }
+    }
+    if changes.is_empty() {
+        return Ok(result);
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let restore_point = save_restore_point(&tx, project_id, "自动更正之前")?;
+    let now = Utc::now().to_rfc3339();
+    for (chapter_id, old_content, new_content) in &changes {
+        tx.execute(
+            "UPDATE chapters SET content = ?, word_count = ?, updated_at = ? WHERE id = ?",
+            params![new_content, new_content.chars().count() as i32, now, chapter_id],
+        )
+        .map_err(|e| format!("Failed to update chapter: {}", e))?;
+        crate::provenance::record_chapter_change(&tx, chapter_id, old_content, new_content, None)?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    result.restore_point_id = Some(restore_point.id);
+    result.chapters_changed = changes.into_iter().map(|(id, _, _)| id).collect();
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(project_id: Option<&str>, pattern: &str, replacement: &str, is_regex: bool, scope: &str) -> AutocorrectRule {
+        AutocorrectRule {
+            id: String::new(),
+            project_id: project_id.map(|p| p.to_string()),
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            is_regex,
+            scope: scope.to_string(),
+            enabled: true,
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_rules_apply_on_save_and_across_project() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO projects (id, name, created_at, updated_at) VALUES ('p2', '别处', 't0', 't0');
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '青岚宗的弟子在练剑。', 1, 't0', 't0'),
+                 ('c2', 'p1', '第二章', '他在也不想回去了。', 2, 't0', 't0'),
+                 ('c3', 'p2', '第一章', '青岚宗。', 1, 't0', 't0');",
+        )
+        .unwrap();
+
+        save_rule(&conn, rule(None, "在也", "再也", false, SCOPE_GLOBAL)).unwrap();
+        save_rule(&conn, rule(Some("p1"), "青(岚|兰)宗", "青澜宗", true, SCOPE_PROJECT)).unwrap();
+        let mut disabled = save_rule(&conn, rule(Some("p1"), "练剑", "习剑", false, SCOPE_PROJECT)).unwrap();
+        disabled.enabled = false;
+        save_rule(&conn, disabled).unwrap();
+        assert!(save_rule(&conn, rule(Some("p1"), "(", "", true, SCOPE_PROJECT)).is_err());
+        assert_eq!(list_rules(&conn, "p1").unwrap().len(), 3);
+        assert_eq!(list_rules(&conn, "p2").unwrap().len(), 1);
+
+        assert_eq!(correct_on_save(&conn, "c1", "青兰宗，在也不见。").unwrap(), "青澜宗，再也不见。");
+        assert_eq!(correct_on_save(&conn, "c3", "青兰宗，在也不见。").unwrap(), "青兰宗，再也不见。");
+
+        let result = correct_project(&conn, "p1").unwrap();
+        assert_eq!((result.corrections, result.chapters_changed.len()), (2, 2));
+        let content: String = conn.query_row("SELECT content FROM chapters WHERE id = 'c1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(content, "青澜宗的弟子在练剑。");
+        assert!(result.restore_point_id.is_some());
+    }
+}
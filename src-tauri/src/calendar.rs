@@ -0,0 +1,299 @@
+use crate::import::directory_import::{is_chinese_numeral, parse_chinese_number};
+use crate::numeral_style::to_chinese;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// 纪元，`start_year` 为该纪元元年对应的绝对年份
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Era {
+    pub name: String,
+    pub start_year: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Month {
+    pub name: String,
+    pub days: u32,
+}
+
+/// 故事中的重要日子，如建国日、节日
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KeyDate {
+    pub name: String,
+    pub date: CalendarDate,
+    pub description: Option<String>,
+}
+
+/// 世界内的日期，`year` 为绝对年份；只知道年份或月份时其余为 None
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CalendarDate {
+    pub year: i64,
+    pub month: Option<u32>,
+    pub day: Option<u32>,
+}
+
+/// 项目的历法，没有配置时为十二个月、每月三十天、没有纪元
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CalendarConfig {
+    pub eras: Vec<Era>,
+    pub months: Vec<Month>,
+    #[serde(default)]
+    pub key_dates: Vec<KeyDate>,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        let months = (1..=12).map(|m| Month { name: format!("{}月", to_chinese(m)), days: 30 }).collect();
+        CalendarConfig { eras: Vec::new(), months, key_dates: Vec::new() }
+    }
+}
+
+/// 日期换算结果，`day_number` 只在日期精确到月时给出
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DateConversion {
+    pub date: CalendarDate,
+    pub label: String,
+    pub day_number: Option<i64>,
+}
+
+/// 开头的阿拉伯或中文数字及其字节长度
+fn leading_number(text: &str) -> Option<(u64, usize)> {
+    let end = text.find(|c: char| !c.is_ascii_digit() && !is_chinese_numeral(c)).unwrap_or(text.len());
+    if end == 0 {
+        return None;
+    }
+    let digits = &text[..end];
+    let value = if digits.chars().all(|c| c.is_ascii_digit()) { digits.parse().ok()? } else { parse_chinese_number(digits) };
+    Some((value, end))
+}
+
+/// “初七”“十五”“廿三”“7日”一类的日子
+fn parse_day(text: &str) -> Option<u32> {
+    let text = text.trim_start();
+    let day = if let Some(rest) = text.strip_prefix('初') {
+        leading_number(rest)?.0
+    } else if let Some(rest) = text.strip_prefix('廿') {
+        20 + leading_number(rest).map(|(n, _)| n).unwrap_or(0)
+    } else {
+        let (n, len) = leading_number(text)?;
+        // 单独的数字后面必须跟“日”或“号”，免得把“五月三人”读成三日
+        if !text[len..].starts_with(['日', '号']) && !text[..len].contains('十') {
+            return None;
+        }
+        n
+    };
+    u32::try_from(day).ok().filter(|d| *d > 0)
+}
+
+fn day_label(day: u32) -> String {
+    match day {
+        1..=10 => format!("初{}", to_chinese(day as u64)),
+        21..=29 => format!("廿{}", to_chinese(day as u64 - 20)),
+        11..=30 => to_chinese(day as u64),
+        _ => format!("{}日", to_chinese(day as u64)),
+    }
+}
+
+impl CalendarConfig {
+    pub fn days_per_year(&self) -> i64 {
+        self.months.iter().map(|m| m.days as i64).sum()
+    }
+
+    /// 检查历法配置，并把纪元按起始年份排好
+    pub fn validate(&mut self) -> Result<(), String> {
+        if self.months.is_empty() {
+            return Err("历法至少需要一个月".to_string());
+        }
+        for month in &self.months {
+            if month.name.trim().is_empty() || month.days == 0 {
+                return Err("月份需要名称，且天数大于零".to_string());
+            }
+        }
+        for (i, era) in self.eras.iter().enumerate() {
+            if era.name.trim().is_empty() {
+                return Err("纪元名称不能为空".to_string());
+            }
+            if self.eras[..i].iter().any(|e| e.name == era.name) {
+                return Err(format!("纪元“{}”重复", era.name));
+            }
+        }
+        self.eras.sort_by_key(|e| e.start_year);
+        for key in &self.key_dates {
+            self.check_date(&key.date).map_err(|e| format!("{}：{}", key.name, e))?;
+        }
+        Ok(())
+    }
+
+    pub fn check_date(&self, date: &CalendarDate) -> Result<(), String> {
+        match (date.month, date.day) {
+            (None, Some(_)) => Err("有日子时必须有月份".to_string()),
+            (Some(m), _) if m == 0 || m as usize > self.months.len() => Err(format!("没有第{}个月", m)),
+            (Some(m), Some(d)) if d == 0 || d > self.months[m as usize - 1].days => {
+                Err(format!("{}没有{}", self.months[m as usize - 1].name, day_label(d)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 该年份所在的纪元和纪元内的年数
+    pub fn era_of(&self, year: i64) -> Option<(&Era, i64)> {
+        self.eras.iter().rev().find(|e| e.start_year <= year).map(|e| (e, year - e.start_year + 1))
+    }
+
+    /// 从第 1 年一月一日起算的天数，用来计算两个日期的间隔
+    pub fn day_number(&self, date: &CalendarDate) -> i64 {
+        let month = date.month.unwrap_or(1).clamp(1, self.months.len() as u32) as usize;
+        let before: i64 = self.months[..month - 1].iter().map(|m| m.days as i64).sum();
+        (date.year - 1) * self.days_per_year() + before + date.day.unwrap_or(1) as i64 - 1
+    }
+
+    /// 两个日期相差的天数，`to` 在前时为负
+    pub fn days_between(&self, from: &CalendarDate, to: &CalendarDate) -> i64 {
+        self.day_number(to) - self.day_number(from)
+    }
+
+    /// 把天数写成“三年二十天”的形式
+    pub fn describe_days(&self, days: i64) -> String {
+        let (years, rest) = (days.abs() / self.days_per_year(), days.abs() % self.days_per_year());
+        let mut out = String::new();
+        if years > 0 {
+            out.push_str(&format!("{}年", to_chinese(years as u64)));
+        }
+        if rest > 0 || years == 0 {
+            out.push_str(&format!("{}天", to_chinese(rest as u64)));
+        }
+        out
+    }
+
+    /// 写成“天启三年五月初七”；年份不在任何纪元内时用阿拉伯数字
+    pub fn format(&self, date: &CalendarDate) -> String {
+        let mut out = match self.era_of(date.year) {
+            Some((era, 1)) => format!("{}元年", era.name),
+            Some((era, year)) => format!("{}{}年", era.name, to_chinese(year as u64)),
+            None => format!("{}年", date.year),
+        };
+        if let Some(month) = date.month.and_then(|m| self.months.get(m as usize - 1)) {
+            out.push_str(&month.name);
+            if let Some(day) = date.day {
+                out.push_str(&day_label(day));
+            }
+        }
+        out
+    }
+
+    /// 从“天启三年五月初七”“1024年冬”“立国日”一类的描述中读出日期，读不出年份时返回 None
+    pub fn parse(&self, text: &str) -> Option<CalendarDate> {
+        let mut eras: Vec<&Era> = self.eras.iter().filter(|e| text.contains(e.name.as_str())).collect();
+        eras.sort_by_key(|e| std::cmp::Reverse(e.name.chars().count()));
+        let (year, rest) = match eras.first() {
+            Some(era) => {
+                let after = &text[text.find(era.name.as_str())? + era.name.len()..];
+                let (n, len) = if let Some(rest) = after.strip_prefix("元年") {
+                    (1, after.len() - rest.len())
+                } else {
+                    let (n, len) = leading_number(after)?;
+                    after[len..].starts_with('年').then_some((n as i64, len + '年'.len_utf8()))?
+                };
+                (era.start_year + n - 1, &after[len..])
+            }
+            None => match crate::timeline_check::year_at(text, 1) {
+                Some((year, end)) => (year, &text[end..]),
+                None => {
+                    let key = self.key_dates.iter().find(|k| !k.name.is_empty() && text.contains(k.name.as_str()))?;
+                    return Some(key.date);
+                }
+            },
+        };
+
+        let (month, rest) = self.parse_month(rest).map(|(m, r)| (Some(m), r)).unwrap_or((None, ""));
+        let day = month.and_then(|m| parse_day(rest).filter(|d| *d <= self.months[m as usize - 1].days));
+        Some(CalendarDate { year, month, day })
+    }
+
+    /// 开头的月份和之后的文字；先按历法中的月名匹配，再读“五月”“正月”“腊月”
+    fn parse_month<'a>(&self, text: &'a str) -> Option<(u32, &'a str)> {
+        let text = text.trim_start();
+        if let Some((i, month)) = self.months.iter().enumerate().filter(|(_, m)| text.starts_with(m.name.as_str())).max_by_key(|(_, m)| m.name.len()) {
+            return Some((i as u32 + 1, &text[month.name.len()..]));
+        }
+        let count = self.months.len() as u64;
+        let (n, len) = match text.chars().next()? {
+            '正' => (1, '正'.len_utf8()),
+            '冬' if count == 12 => (11, '冬'.len_utf8()),
+            '腊' if count == 12 => (12, '腊'.len_utf8()),
+            _ => leading_number(text)?,
+        };
+        let rest = text[len..].strip_prefix('月')?;
+        (1..=count).contains(&n).then_some((n as u32, rest))
+    }
+
+    pub fn convert(&self, date: CalendarDate) -> DateConversion {
+        DateConversion { date, label: self.format(&date), day_number: date.month.map(|_| self.day_number(&date)) }
+    }
+}
+
+pub fn load_calendar(conn: &Connection, project_id: &str) -> Result<CalendarConfig, String> {
+    let config: Option<String> = conn
+        .query_row("SELECT config_json FROM story_calendars WHERE project_id = ?", params![project_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match config {
+        Some(json) => serde_json::from_str(&json).map_err(|e| format!("历法配置损坏: {}", e)),
+        None => Ok(CalendarConfig::default()),
+    }
+}
+
+pub fn save_calendar(conn: &Connection, project_id: &str, mut config: CalendarConfig) -> Result<CalendarConfig, String> {
+    config.validate()?;
+    let json = serde_json::to_string(&config).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO story_calendars (project_id, config_json, updated_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(project_id) DO UPDATE SET config_json = excluded.config_json, updated_at = excluded.updated_at",
+        params![project_id, json, Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| format!("Failed to save calendar: {}", e))?;
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_format_and_intervals() {
+        let mut calendar = CalendarConfig {
+            eras: vec![Era { name: "永安".to_string(), start_year: 1020 }, Era { name: "天启".to_string(), start_year: 1000 }],
+            months: vec![
+                Month { name: "霜月".to_string(), days: 40 },
+                Month { name: "雪月".to_string(), days: 40 },
+                Month { name: "花月".to_string(), days: 40 },
+            ],
+            key_dates: vec![KeyDate {
+                name: "立国日".to_string(),
+                date: CalendarDate { year: 1000, month: Some(1), day: Some(1) },
+                description: None,
+            }],
+        };
+        calendar.validate().unwrap();
+        assert_eq!(calendar.eras[0].name, "天启");
+
+        let date = calendar.parse("天启三年雪月廿三，大雪").unwrap();
+        assert_eq!(date, CalendarDate { year: 1002, month: Some(2), day: Some(23) });
+        assert_eq!(calendar.format(&date), "天启三年雪月廿三");
+        assert_eq!(calendar.parse("永安元年").unwrap(), CalendarDate { year: 1020, month: None, day: None });
+        assert_eq!(calendar.format(&CalendarDate { year: 1025, month: Some(3), day: Some(40) }), "永安六年花月四十日");
+        assert_eq!(calendar.parse("1024年").unwrap().year, 1024);
+        assert_eq!(calendar.parse("立国日那天").unwrap().year, 1000);
+        assert_eq!(calendar.parse("三年后"), None);
+
+        let founding = calendar.key_dates[0].date;
+        assert_eq!(calendar.days_between(&founding, &date), 2 * 120 + 40 + 22);
+        assert_eq!(calendar.describe_days(calendar.days_between(&founding, &date)), "二年六十二天");
+        assert!(calendar.check_date(&CalendarDate { year: 1, month: Some(4), day: None }).is_err());
+
+        let default = CalendarConfig::default();
+        assert_eq!(default.parse("1000年腊月十五").unwrap(), CalendarDate { year: 1000, month: Some(12), day: Some(15) });
+        assert_eq!(default.format(&CalendarDate { year: 1000, month: Some(1), day: Some(7) }), "1000年一月初七");
+    }
+}
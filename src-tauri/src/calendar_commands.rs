@@ -0,0 +1,90 @@
+use crate::calendar::{self, CalendarConfig, CalendarDate};
+use crate::logger::Logger;
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+#[derive(Serialize)]
+struct DateInterval {
+    days: i64,
+    label: String,
+}
+
+/// 项目的历法，没有配置时返回默认的十二个月历法
+#[tauri::command]
+pub async fn get_story_calendar(app: AppHandle, project_id: String) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let config = calendar::load_calendar(&conn, &project_id)?;
+
+    serde_json::to_string(&config).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn save_story_calendar(app: AppHandle, project_id: String, config: CalendarConfig) -> Result<String, String> {
+    let logger = Logger::new().with_feature("calendar");
+    logger.info(&format!("Saving calendar for project {}", project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let config = calendar::save_calendar(&conn, &project_id, config)?;
+
+    serde_json::to_string(&config).map_err(|e| e.to_string())
+}
+
+/// 把时间描述（如“天启三年五月初七”）换算成结构化日期，读不出年份时返回 null
+#[tauri::command]
+pub async fn parse_story_date(app: AppHandle, project_id: String, text: String) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let config = calendar::load_calendar(&conn, &project_id)?;
+    let conversion = config.parse(&text).map(|date| config.convert(date));
+
+    serde_json::to_string(&conversion).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn format_story_date(app: AppHandle, project_id: String, date: CalendarDate) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let config = calendar::load_calendar(&conn, &project_id)?;
+    config.check_date(&date)?;
+
+    serde_json::to_string(&config.convert(date)).map_err(|e| e.to_string())
+}
+
+/// 两个日期相差的天数，`to` 在前时为负
+#[tauri::command]
+pub async fn story_date_interval(app: AppHandle, project_id: String, from: CalendarDate, to: CalendarDate) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let config = calendar::load_calendar(&conn, &project_id)?;
+    config.check_date(&from)?;
+    config.check_date(&to)?;
+    let days = config.days_between(&from, &to);
+
+    serde_json::to_string(&DateInterval { days, label: config.describe_days(days) }).map_err(|e| e.to_string())
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
@@ -0,0 +1,239 @@
+use crate::ai::generators::{GeneratedCharacter, GeneratedCharacterRelation};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// 默认生成的配角数量
+pub const DEFAULT_SUPPORTING: usize = 4;
+/// 一次最多生成的配角数量
+const MAX_SUPPORTING: usize = 12;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CastRequest {
+    pub project_id: String,
+    pub supporting_count: Option<usize>,
+    pub include_antagonist: Option<bool>,
+    /// 故事梗概或对阵容的要求
+    pub premise: Option<String>,
+}
+
+/// AI 生成、尚未写入的阵容，可以在界面上修改后再写入
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CastDraft {
+    pub characters: Vec<GeneratedCharacter>,
+    pub relations: Vec<GeneratedCharacterRelation>,
+}
+
+/// 写入结果，撤销时传回 `batch_id`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CastApplyResult {
+    pub batch_id: String,
+    pub character_ids: Vec<String>,
+    pub relations_created: usize,
+    /// 找不到对应角色而没有写入的关系，格式为“甲 → 乙”
+    pub skipped_relations: Vec<String>,
+}
+
+/// 生成阵容用的提示词，`existing` 是项目中已有的角色名，新角色不得重名
+pub fn build_prompt(request: &CastRequest, genre: &str, setting: &str, existing: &[String]) -> String {
+    let supporting = request.supporting_count.unwrap_or(DEFAULT_SUPPORTING).min(MAX_SUPPORTING);
+    let mut roles = vec!["1 名主角（role_type 为 protagonist）".to_string()];
+    if request.include_antagonist.unwrap_or(true) {
+        roles.push("1 名反派（antagonist）".to_string());
+    }
+    if supporting > 0 {
+        roles.push(format!("{} 名配角（supporting）", supporting));
+    }
+    let existing = if existing.is_empty() { "无".to_string() } else { existing.join("、") };
+    format!(
+        "为一部{}小说设计一组互相关联的角色：{}。\n\n背景设定：\n{}\n\n故事要求：{}\n\n已有角色（不要重名，可以与他们建立关系）：{}\n\n\
+         角色之间要有明确的动机冲突和互补，关系要覆盖每个新角色。\n\
+         返回 JSON 对象：{{\"characters\": [{{\"name\", \"role_type\", \"gender\", \"age\", \"appearance\", \"personality\", \"background\", \"skills\"}}], \
+         \"relations\": [{{\"from_character_name\", \"to_character_name\", \"relation_type\", \"description\"}}]}}，age 为整数。",
+        genre,
+        roles.join("、"),
+        if setting.is_empty() { "暂无" } else { setting },
+        request.premise.as_deref().filter(|p| !p.trim().is_empty()).unwrap_or("无"),
+        existing,
+    )
+}
+
+/// 解析 AI 返回的阵容：去掉无名、重名或与已有角色重名的角色，去掉两端不在阵容和已有角色中的关系；
+/// 没有角色标为主角时把第一个角色作为主角
+pub fn parse_cast(response: &str, existing: &[String]) -> Result<CastDraft, String> {
+    let start = response.find('{').unwrap_or(0);
+    let end = response.rfind('}').map(|i| i + 1).unwrap_or(response.len());
+    let parsed: CastDraft = serde_json::from_str(&response[start..end]).map_err(|e| format!("无法解析生成的阵容: {}", e))?;
+
+    let mut names: HashSet<String> = existing.iter().cloned().collect();
+    let mut characters = Vec::new();
+    for mut character in parsed.characters {
+        character.name = character.name.trim().to_string();
+        if character.name.is_empty() || !names.insert(character.name.clone()) {
+            continue;
+        }
+        characters.push(character);
+    }
+    if characters.is_empty() {
+        return Err("没有生成可用的角色".to_string());
+    }
+    if !characters.iter().any(|c| c.role_type.as_deref() == Some("protagonist")) {
+        characters[0].role_type = Some("protagonist".to_string());
+    }
+    let relations = parsed
+        .relations
+        .into_iter()
+        .filter(|r| r.from_character_name != r.to_character_name && names.contains(&r.from_character_name) && names.contains(&r.to_character_name))
+        .collect();
+    Ok(CastDraft { characters, relations })
+}
+
+/// 在一个事务中写入阵容及其关系，关系可以连到项目中已有的角色
+pub fn apply_cast(conn: &Connection, project_id: &str, draft: &CastDraft) -> Result<CastApplyResult, String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let mut result = CastApplyResult {
+        batch_id: Uuid::new_v4().to_string(),
+        character_ids: Vec::new(),
+        relations_created: 0,
+        skipped_relations: Vec::new(),
+    };
+    for c in &draft.characters {
+        let taken: bool = tx
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM characters WHERE project_id = ? AND name = ? AND merged_into IS NULL)",
+                params![project_id, c.name],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if taken {
+            return Err(format!("项目中已有角色 {}", c.name));
+        }
+        let id = Uuid::new_v4().to_string();
+        tx.execute(
+            "INSERT INTO characters (id, project_id, name, role_type, race, age, gender, birth_date, appearance, personality, background,
+                                     skills, status, bazi, ziwei, mbti, enneagram, items, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?19)",
+            params![
+                id, project_id, c.name, c.role_type, c.race, c.age, c.gender, c.birth_date, c.appearance, c.personality,
+                c.background, c.skills, c.status, c.bazi, c.ziwei, c.mbti, c.enneagram, c.items, now,
+            ],
+        )
+        .map_err(|e| format!("Failed to create character: {}", e))?;
+        result.character_ids.push(id);
+    }
+
+    let character_id = |name: &str| -> Result<Option<String>, String> {
+        tx.query_row(
+            "SELECT id FROM characters WHERE project_id = ? AND name = ? AND merged_into IS NULL",
+            params![project_id, name],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())
+    };
+    for relation in &draft.relations {
+        let (Some(from), Some(to)) = (character_id(&relation.from_character_name)?, character_id(&relation.to_character_name)?) else {
+            result.skipped_relations.push(format!("{} → {}", relation.from_character_name, relation.to_character_name));
+            continue;
+        };
+        result.relations_created += tx
+            .execute(
+                "INSERT OR IGNORE INTO character_relations (id, project_id, from_character_id, to_character_id, relation_type, description, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+                params![Uuid::new_v4().to_string(), project_id, from, to, relation.relation_type, relation.description, now],
+            )
+            .map_err(|e| e.to_string())?;
+    }
+
+    tx.execute(
+        "INSERT INTO cast_batches (id, project_id, character_ids, created_at) VALUES (?, ?, ?, ?)",
+        params![result.batch_id, project_id, serde_json::to_string(&result.character_ids).map_err(|e| e.to_string())?, now],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    let names: Vec<&str> = draft.characters.iter().map(|c| c.name.as_str()).collect();
+    crate::tokenizer::add_words(&names);
+    Ok(result)
+}
+
+/// 撤销一次阵容写入：删除这批角色，它们的关系随之删除；返回删除的角色数
+pub fn undo_cast(conn: &Connection, batch_id: &str) -> Result<usize, String> {
+    let character_ids: String = conn
+        .query_row("SELECT character_ids FROM cast_batches WHERE id = ?", params![batch_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "这批角色已撤销或不存在".to_string())?;
+    let character_ids: Vec<String> = serde_json::from_str(&character_ids).unwrap_or_default();
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut removed = 0;
+    for id in &character_ids {
+        removed += tx.execute("DELETE FROM characters WHERE id = ?", params![id]).map_err(|e| e.to_string())?;
+    }
+    tx.execute("DELETE FROM cast_batches WHERE id = ?", params![batch_id]).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(removed)
+}
+
+pub async fn generate_with_ai(
+    service: &crate::ai::AIService,
+    model_id: &str,
+    request: &CastRequest,
+    genre: &str,
+    setting: &str,
+    existing: &[String],
+) -> Result<CastDraft, String> {
+    let system_prompt = "你是一位经验丰富的小说人物设计师。只返回 JSON 对象，不要包含markdown代码块标记。";
+    let user_prompt = build_prompt(request, genre, setting, existing);
+    let response = service.complete(model_id, system_prompt, &user_prompt).await?;
+    parse_cast(&response, existing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_apply_and_undo_cast() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES ('r1', 'p1', '老周', 't0', 't0');",
+        )
+        .unwrap();
+        let existing = vec!["老周".to_string()];
+        let response = r#"好的：{"characters": [
+            {"name": "林舟", "role_type": "supporting", "age": 19},
+            {"name": "沈青", "role_type": "antagonist"},
+            {"name": "老周"},
+            {"name": "林舟"}
+        ], "relations": [
+            {"from_character_name": "沈青", "to_character_name": "林舟", "relation_type": "rival"},
+            {"from_character_name": "老周", "to_character_name": "林舟", "relation_type": "mentor", "description": "收留"},
+            {"from_character_name": "阿七", "to_character_name": "林舟", "relation_type": "friend"}
+        ]}"#;
+        let draft = parse_cast(response, &existing).unwrap();
+        let names: Vec<&str> = draft.characters.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["林舟", "沈青"]);
+        assert_eq!(draft.characters[0].role_type.as_deref(), Some("protagonist"));
+        assert_eq!(draft.relations.len(), 2);
+
+        let result = apply_cast(&conn, "p1", &draft).unwrap();
+        assert_eq!((result.character_ids.len(), result.relations_created), (2, 2));
+        assert!(apply_cast(&conn, "p1", &draft).is_err());
+
+        assert_eq!(undo_cast(&conn, &result.batch_id).unwrap(), 2);
+        let (characters, relations): (i64, i64) = conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM characters), (SELECT COUNT(*) FROM character_relations)",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((characters, relations), (1, 0));
+        assert!(undo_cast(&conn, &result.batch_id).is_err());
+    }
+}
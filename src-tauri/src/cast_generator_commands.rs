@@ -0,0 +1,104 @@
+use crate::cast_generator::{self, CastDraft, CastRequest};
+use crate::logger::Logger;
+use rusqlite::params;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// 一次生成主角、反派和若干配角及其关系；`review` 为 true 时只返回草稿供修改，否则直接写入并返回可撤销的结果
+#[tauri::command]
+pub async fn ai_generate_cast(
+    app: AppHandle,
+    request: CastRequest,
+    review: Option<bool>,
+    model_id: Option<String>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("cast_generator");
+    logger.info(&format!("Generating cast for project {}", request.project_id));
+
+    let (genre, setting, existing) = {
+        let db_path = get_db_path(&app)?;
+        let conn = crate::database::get_connection(&db_path)
+            .map_err(|e| format!("Failed to get database connection: {}", e))?;
+        project_context(&conn, &request.project_id)?
+    };
+
+    let draft = {
+        let ai_service = app.state::<std::sync::Arc<tokio::sync::RwLock<crate::ai::AIService>>>();
+        let service = ai_service.read().await;
+        let model_id = model_id.unwrap_or_else(|| "glm-4-flash".to_string());
+        cast_generator::generate_with_ai(&service, &model_id, &request, &genre, &setting, &existing)
+            .await
+            .map_err(|e| {
+                logger.error(&format!("Failed to generate cast: {}", e));
+                e
+            })?
+    };
+
+    if review.unwrap_or(false) {
+        return serde_json::to_string(&draft).map_err(|e| e.to_string());
+    }
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    let result = cast_generator::apply_cast(&conn, &request.project_id, &draft)?;
+
+    serde_json::to_string(&result).map_err(|e| e.to_string())
+}
+
+/// 写入审阅过的阵容草稿
+#[tauri::command]
+pub async fn apply_cast(
+    app: AppHandle,
+    project_id: String,
+    cast: CastDraft,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let result = cast_generator::apply_cast(&conn, &project_id, &cast)?;
+
+    serde_json::to_string(&result).map_err(|e| e.to_string())
+}
+
+/// 撤销一次阵容写入，返回删除的角色数
+#[tauri::command]
+pub async fn undo_cast(app: AppHandle, batch_id: String) -> Result<usize, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    cast_generator::undo_cast(&conn, &batch_id)
+}
+
+/// 项目题材、最重要的几条世界观设定和已有角色名
+fn project_context(conn: &rusqlite::Connection, project_id: &str) -> Result<(String, String, Vec<String>), String> {
+    let genre: String = conn
+        .query_row("SELECT COALESCE(genre, '小说') FROM projects WHERE id = ?", params![project_id], |row| row.get(0))
+        .map_err(|_| "项目不存在".to_string())?;
+    let setting: Vec<String> = conn
+        .prepare("SELECT category, title, content FROM world_views WHERE project_id = ? ORDER BY created_at DESC LIMIT 5")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| {
+            let (category, title, content): (String, String, String) = (row.get(0)?, row.get(1)?, row.get(2)?);
+            Ok(format!("[{}] {}: {}", category, title, content.chars().take(100).collect::<String>()))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let existing = crate::name_generator::existing_names(conn, project_id)?;
+    Ok((genre, setting.join("\n"), existing))
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
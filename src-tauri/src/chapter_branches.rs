@@ -0,0 +1,378 @@
+use crate::text_merge::{self, HunkResolution, MergeResult};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+/// 章节第一次建立分支时，原有正文所在的主线分支名
+pub const MAIN_BRANCH: &str = "main";
+
+/// 章节的一个分支。当前分支的正文保存在章节本身，`content` 是切换走时的正文
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterBranch {
+    pub id: String,
+    pub project_id: String,
+    pub chapter_id: String,
+    pub name: String,
+    /// 建立分支时所在的分支；主线分支为空
+    pub parent_id: Option<String>,
+    /// 与来源分支最近一次一致时的正文：建立分支时的正文，之后每次与来源分支合并时更新，合并时作为共同祖先
+    pub base_content: String,
+    pub content: String,
+    pub active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+    pub merged_at: Option<String>,
+}
+
+/// 分支上的一个快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchSnapshot {
+    pub id: String,
+    pub branch_id: String,
+    pub description: String,
+    pub content: String,
+    pub word_count: i64,
+    pub created_at: String,
+}
+
+/// 合并结果；有未处理的冲突时 `applied` 为 false，正文保持不变
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchMergeOutcome {
+    pub result: MergeResult,
+    pub applied: bool,
+    pub content: Option<String>,
+}
+
+const BRANCH_COLUMNS: &str = "id, project_id, chapter_id, name, base_content, content, active, created_at, updated_at, merged_at, parent_id";
+
+fn branch_from_row(row: &Row) -> rusqlite::Result<ChapterBranch> {
+    Ok(ChapterBranch {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        chapter_id: row.get(2)?,
+        name: row.get(3)?,
+        parent_id: row.get(10)?,
+        base_content: row.get(4)?,
+        content: row.get(5)?,
+        active: row.get::<_, i64>(6)? != 0,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+        merged_at: row.get(9)?,
+    })
+}
+
+fn chapter_content(conn: &Connection, chapter_id: &str) -> Result<(String, String), String> {
+    conn.query_row("SELECT project_id, content FROM chapters WHERE id = ?", params![chapter_id], |row| {
+        Ok((row.get(0)?, row.get::<_, Option<String>>(1)?.unwrap_or_default()))
+    })
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "章节不存在".to_string())
+}
+
+fn set_chapter_content(conn: &Connection, chapter_id: &str, content: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE chapters SET content = ?, word_count = ?, updated_at = ? WHERE id = ?",
+        params![content, content.chars().count() as i64, Utc::now().to_rfc3339(), chapter_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn get_branch(conn: &Connection, branch_id: &str) -> Result<ChapterBranch, String> {
+    conn.query_row(&format!("SELECT {} FROM chapter_branches WHERE id = ?", BRANCH_COLUMNS), params![branch_id], branch_from_row)
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "分支不存在".to_string())
+}
+
+/// 分支的最新正文：当前分支以章节正文为准
+fn branch_content(conn: &Connection, branch: &ChapterBranch) -> Result<String, String> {
+    if branch.active {
+        Ok(chapter_content(conn, &branch.chapter_id)?.1)
+    } else {
+        Ok(branch.content.clone())
+    }
+}
+
+pub fn list_branches(conn: &Connection, chapter_id: &str) -> Result<Vec<ChapterBranch>, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM chapter_branches WHERE chapter_id = ? ORDER BY created_at", BRANCH_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let branches = stmt
+        .query_map(params![chapter_id], branch_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    branches
+        .into_iter()
+        .map(|mut branch| {
+            branch.content = branch_content(conn, &branch)?;
+            Ok(branch)
+        })
+        .collect()
+}
+
+fn insert_branch(
+    conn: &Connection,
+    project_id: &str,
+    chapter_id: &str,
+    name: &str,
+    parent_id: Option<&str>,
+    content: &str,
+    active: bool,
+) -> Result<ChapterBranch, String> {
+    let now = Utc::now().to_rfc3339();
+    let branch = ChapterBranch {
+        id: uuid::Uuid::new_v4().to_string(),
+        project_id: project_id.to_string(),
+        chapter_id: chapter_id.to_string(),
+        name: name.to_string(),
+        parent_id: parent_id.map(str::to_string),
+        base_content: content.to_string(),
+        content: content.to_string(),
+        active,
+        created_at: now.clone(),
+        updated_at: now,
+        merged_at: None,
+    };
+    conn.execute(
+        &format!("INSERT INTO chapter_branches ({}) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)", BRANCH_COLUMNS),
+        params![
+            branch.id,
+            branch.project_id,
+            branch.chapter_id,
+            branch.name,
+            branch.base_content,
+            branch.content,
+            branch.active as i64,
+            branch.created_at,
+            branch.updated_at,
+            branch.merged_at,
+            branch.parent_id,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(branch)
+}
+
+/// 章节的主线分支，章节还没有分支时先建立
+pub(crate) fn main_branch(conn: &Connection, chapter_id: &str) -> Result<ChapterBranch, String> {
+    let existing = list_branches(conn, chapter_id)?;
+    if let Some(branch) = existing.iter().find(|b| b.name == MAIN_BRANCH) {
+        return Ok(branch.clone());
+    }
+    let (project_id, content) = chapter_content(conn, chapter_id)?;
+    insert_branch(conn, &project_id, chapter_id, MAIN_BRANCH, None, &content, existing.is_empty())
+}
+
+/// 用其他来源（如导入的历史）的正文在 `parent_id` 下建立一个非当前分支
+pub(crate) fn insert_inactive_branch(
+    conn: &Connection,
+    chapter_id: &str,
+    name: &str,
+    parent_id: &str,
+    base_content: &str,
+    content: &str,
+) -> Result<ChapterBranch, String> {
+    let (project_id, _) = chapter_content(conn, chapter_id)?;
+    let mut branch = insert_branch(conn, &project_id, chapter_id, name, Some(parent_id), base_content, false)?;
+    conn.execute("UPDATE chapter_branches SET content = ? WHERE id = ?", params![content, branch.id])
+        .map_err(|e| e.to_string())?;
+    branch.content = content.to_string();
+    Ok(branch)
+}
+
+/// 从章节当前正文建立名为 `name` 的分支。章节第一次建立分支时，原有正文成为主线分支
+pub fn create_branch(conn: &Connection, chapter_id: &str, name: &str) -> Result<ChapterBranch, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("分支名不能为空".to_string());
+    }
+    let (project_id, content) = chapter_content(conn, chapter_id)?;
+    let existing = list_branches(conn, chapter_id)?;
+    if existing.iter().any(|b| b.name == name) {
+        return Err(format!("分支“{}”已存在", name));
+    }
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    // 新分支从当前分支建立
+    let parent_id = if existing.is_empty() && name != MAIN_BRANCH {
+        Some(insert_branch(&tx, &project_id, chapter_id, MAIN_BRANCH, None, &content, true)?.id)
+    } else {
+        existing.iter().find(|b| b.active).map(|b| b.id.clone())
+    };
+    let branch =
+        insert_branch(&tx, &project_id, chapter_id, name, parent_id.as_deref(), &content, existing.is_empty() && name == MAIN_BRANCH)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(branch)
+}
+
+/// 切换章节的当前分支：当前正文保存回原分支，章节换成目标分支的正文
+pub fn switch_branch(conn: &Connection, branch_id: &str) -> Result<ChapterBranch, String> {
+    let target = get_branch(conn, branch_id)?;
+    if target.active {
+        return Ok(target);
+    }
+    let (_, current) = chapter_content(conn, &target.chapter_id)?;
+    let now = Utc::now().to_rfc3339();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE chapter_branches SET content = ?, active = 0, updated_at = ? WHERE chapter_id = ? AND active = 1",
+        params![current, now, target.chapter_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute("UPDATE chapter_branches SET active = 1 WHERE id = ?", params![target.id]).map_err(|e| e.to_string())?;
+    set_chapter_content(&tx, &target.chapter_id, &target.content)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    get_branch(conn, branch_id)
+}
+
+/// 为分支的最新正文保存快照
+pub fn create_branch_snapshot(conn: &Connection, branch_id: &str, description: &str) -> Result<BranchSnapshot, String> {
+    let branch = get_branch(conn, branch_id)?;
+    let content = branch_content(conn, &branch)?;
+    let snapshot = BranchSnapshot {
+        id: uuid::Uuid::new_v4().to_string(),
+        branch_id: branch.id,
+        description: description.to_string(),
+        word_count: content.chars().count() as i64,
+        content,
+        created_at: Utc::now().to_rfc3339(),
+    };
+    conn.execute(
+        "INSERT INTO chapter_branch_snapshots (id, branch_id, description, content, word_count, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+        params![snapshot.id, snapshot.branch_id, snapshot.description, snapshot.content, snapshot.word_count, snapshot.created_at],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(snapshot)
+}
+
+/// 分支的快照，按时间倒序
+pub fn list_branch_snapshots(conn: &Connection, branch_id: &str) -> Result<Vec<BranchSnapshot>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, branch_id, description, content, word_count, created_at
+             FROM chapter_branch_snapshots WHERE branch_id = ? ORDER BY created_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let snapshots = stmt
+        .query_map(params![branch_id], |row| {
+            Ok(BranchSnapshot {
+                id: row.get(0)?,
+                branch_id: row.get(1)?,
+                description: row.get(2)?,
+                content: row.get(3)?,
+                word_count: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    snapshots.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// 用三方合并把 `source_id` 合并进 `target_id`。两个分支须有一个是从另一个建立的，
+/// 以子分支与来源分支最近一次一致时的正文为共同祖先；合并后更新为这次合并进来的正文，
+/// 再次合并时只比较之后的修改。有冲突时需带上 `resolutions` 再次调用才会写入
+pub fn merge_branch(
+    conn: &Connection,
+    source_id: &str,
+    target_id: &str,
+    resolutions: Option<&[HunkResolution]>,
+) -> Result<BranchMergeOutcome, String> {
+    let source = get_branch(conn, source_id)?;
+    let target = get_branch(conn, target_id)?;
+    if source.chapter_id != target.chapter_id {
+        return Err("只能合并同一章节的分支".to_string());
+    }
+    if source.id == target.id {
+        return Err("不能把分支合并到自身".to_string());
+    }
+    let child = if source.parent_id.as_deref() == Some(target.id.as_str()) {
+        &source
+    } else if target.parent_id.as_deref() == Some(source.id.as_str()) {
+        &target
+    } else {
+        return Err("只能在分支和建立它的分支之间合并".to_string());
+    };
+    let source_content = branch_content(conn, &source)?;
+    let result = text_merge::merge_three_way(&child.base_content, &branch_content(conn, &target)?, &source_content);
+    let merged = match resolutions {
+        Some(resolutions) => text_merge::resolve_merge(&result, resolutions)?,
+        None if result.conflict_count == 0 => result.merged.clone(),
+        None => return Ok(BranchMergeOutcome { result, applied: false, content: None }),
+    };
+
+    let now = Utc::now().to_rfc3339();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    if target.active {
+        set_chapter_content(&tx, &target.chapter_id, &merged)?;
+    }
+    tx.execute("UPDATE chapter_branches SET content = ?, updated_at = ? WHERE id = ?", params![merged, now, target.id])
+        .map_err(|e| e.to_string())?;
+    tx.execute("UPDATE chapter_branches SET merged_at = ? WHERE id = ?", params![now, source.id])
+        .map_err(|e| e.to_string())?;
+    tx.execute("UPDATE chapter_branches SET base_content = ? WHERE id = ?", params![source_content, child.id])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(BranchMergeOutcome { result, applied: true, content: Some(merged) })
+}
+
+/// 删除分支及其快照，不能删除当前分支
+pub fn delete_branch(conn: &Connection, branch_id: &str) -> Result<(), String> {
+    let branch = get_branch(conn, branch_id)?;
+    if branch.active {
+        return Err("不能删除当前分支，请先切换到其他分支".to_string());
+    }
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM chapter_branch_snapshots WHERE branch_id = ?", params![branch_id]).map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM chapter_branches WHERE id = ?", params![branch_id]).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_branch_switch_and_merge() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute(
+            "INSERT INTO chapters (id, project_id, title, content, created_at, updated_at) VALUES ('c1', 'p1', '终章', '开头\n中段\n他离开了小镇。\n结尾', 't0', 't0')",
+            [],
+        )
+        .unwrap();
+        let content = |conn: &Connection| chapter_content(conn, "c1").unwrap().1;
+
+        let alternate = create_branch(&conn, "c1", "另一种结局").unwrap();
+        let branches = list_branches(&conn, "c1").unwrap();
+        let main = branches.iter().find(|b| b.name == MAIN_BRANCH).unwrap().clone();
+        assert!(main.active && !alternate.active);
+
+        // 主线修改开头，分支改写结局
+        set_chapter_content(&conn, "c1", "序幕\n中段\n他离开了小镇。\n结尾").unwrap();
+        switch_branch(&conn, &alternate.id).unwrap();
+        assert_eq!(content(&conn), "开头\n中段\n他离开了小镇。\n结尾");
+        set_chapter_content(&conn, "c1", "开头\n中段\n他留在了小镇。\n结尾").unwrap();
+        create_branch_snapshot(&conn, &alternate.id, "改写结局").unwrap();
+        assert_eq!(list_branch_snapshots(&conn, &alternate.id).unwrap()[0].content, "开头\n中段\n他留在了小镇。\n结尾");
+
+        switch_branch(&conn, &main.id).unwrap();
+        assert_eq!(content(&conn), "序幕\n中段\n他离开了小镇。\n结尾");
+        let outcome = merge_branch(&conn, &alternate.id, &main.id, None).unwrap();
+        assert!(outcome.applied);
+        assert_eq!(content(&conn), "序幕\n中段\n他留在了小镇。\n结尾");
+        assert!(get_branch(&conn, &alternate.id).unwrap().merged_at.is_some());
+        assert!(delete_branch(&conn, &main.id).is_err());
+
+        // 再次合并只比较上次合并之后的修改，主线改过的结局不会被当成冲突
+        set_chapter_content(&conn, "c1", "序幕\n中段\n他终于留在了小镇。\n结尾").unwrap();
+        let outcome = merge_branch(&conn, &alternate.id, &main.id, None).unwrap();
+        assert_eq!((outcome.applied, outcome.result.conflict_count), (true, 0));
+        assert_eq!(content(&conn), "序幕\n中段\n他终于留在了小镇。\n结尾");
+
+        // 不是从彼此建立的分支之间没有可靠的共同祖先
+        let sibling = create_branch(&conn, "c1", "番外").unwrap();
+        assert_eq!(sibling.parent_id.as_deref(), Some(main.id.as_str()));
+        assert!(merge_branch(&conn, &sibling.id, &alternate.id, None).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::logger::Logger;
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// 最后一次保存后等待这么久没有新的保存，才处理章节。连续输入时只处理最终内容
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// 自动更正改写了已保存的正文时发给前端。编辑器中仍是 `original` 时才替换，避免覆盖之后的输入
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChapterAutocorrected {
+    pub chapter_id: String,
+    pub original: String,
+    pub content: String,
+}
+
+struct PendingSave {
+    generation: u64,
+    /// 这一轮第一次保存之前的正文，溯源和字数统计都以它为起点
+    previous: String,
+}
+
+/// 保存章节后的自动更正、写作溯源、字数统计、实体索引和知识链接，按章节防抖后在后台执行
+#[derive(Clone)]
+pub struct ChapterPostSaveState {
+    db_path: PathBuf,
+    pending: Arc<Mutex<HashMap<String, PendingSave>>>,
+}
+
+impl ChapterPostSaveState {
+    pub fn new(db_path: PathBuf) -> Self {
+        Self { db_path, pending: Arc::new(Mutex::new(HashMap::new())) }
+    }
+
+    /// 记录一次保存，`previous` 为这次保存前的正文
+    pub fn schedule(&self, app: AppHandle, chapter_id: String, previous: String) {
+        let generation = {
+            let mut pending = self.pending.lock().unwrap();
+            let entry = pending.entry(chapter_id.clone()).or_insert(PendingSave { generation: 0, previous });
+            entry.generation += 1;
+            entry.generation
+        };
+        let state = self.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(DEBOUNCE).await;
+            let previous = {
+                let mut pending = state.pending.lock().unwrap();
+                if pending.get(&chapter_id).map(|p| p.generation) != Some(generation) {
+                    return;
+                }
+                pending.remove(&chapter_id).map(|p| p.previous).unwrap_or_default()
+            };
+            let db_path = state.db_path.clone();
+            let result = tauri::async_runtime::spawn_blocking(move || {
+                let conn = crate::database::get_connection(&db_path).map_err(|e| e.to_string())?;
+                process_saved_chapter(&conn, &chapter_id, &previous)
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result);
+            match result {
+                Ok(Some(corrected)) => {
+                    let _ = app.emit("chapter://autocorrected", corrected);
+                }
+                Ok(None) => {}
+                Err(e) => Logger::new().with_feature("chapter-service").warn(&format!("Post-save processing failed: {}", e)),
+            }
+        });
+    }
+}
+
+/// 处理已保存的章节。自动更正有改动且正文在此期间未被再次保存时写回，并返回这次改写；
+/// 其余各项失败只记录日志
+pub fn process_saved_chapter(
+    conn: &Connection,
+    chapter_id: &str,
+    previous: &str,
+) -> Result<Option<ChapterAutocorrected>, String> {
+    let logger = Logger::new().with_feature("chapter-service");
+    let (project_id, saved): (String, String) = conn
+        .query_row("SELECT project_id, COALESCE(content, '') FROM chapters WHERE id = ?", params![chapter_id], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut content = saved.clone();
+    match crate::autocorrect::correct_on_save(conn, chapter_id, &saved) {
+        Ok(corrected) if corrected != saved => {
+            let updated = conn
+                .execute(
+                    "UPDATE chapters SET content = ?, word_count = ?, updated_at = ? WHERE id = ? AND content = ?",
+                    params![corrected, corrected.chars().count() as i32, Utc::now().to_rfc3339(), chapter_id, saved],
+                )
+                .map_err(|e| e.to_string())?;
+            if updated == 1 {
+                content = corrected;
+            }
+        }
+        Ok(_) => {}
+        Err(e) => logger.warn(&format!("Failed to apply autocorrect rules: {}", e)),
+    }
+
+    if let Err(e) = crate::provenance::record_chapter_change(conn, chapter_id, previous, &content, None) {
+        logger.warn(&format!("Failed to record provenance: {}", e));
+    }
+    let today = chrono::Local::now().date_naive();
+    let (old_words, new_words) = (previous.chars().count() as i64, content.chars().count() as i64);
+    if let Err(e) = crate::writing_stats::record_delta(conn, &project_id, today, old_words, new_words) {
+        logger.warn(&format!("Failed to record writing stats: {}", e));
+    }
+    if let Err(e) = crate::entity_index::index_chapter(conn, chapter_id, None) {
+        logger.warn(&format!("Failed to index entities: {}", e));
+    }
+    if let Err(e) = crate::knowledge_links::link_chapter(conn, chapter_id) {
+        logger.warn(&format!("Failed to link knowledge mentions: {}", e));
+    }
+    Ok((content != saved).then(|| ChapterAutocorrected { chapter_id: chapter_id.to_string(), original: saved, content }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autocorrect::{save_rule, AutocorrectRule, SCOPE_GLOBAL};
+
+    #[test]
+    fn test_process_saved_chapter() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute(
+            "INSERT INTO chapters (id, project_id, title, content, created_at, updated_at) VALUES ('c1', 'p1', '第一章', '他在也不想回去了。', 't0', 't0')",
+            [],
+        )
+        .unwrap();
+        save_rule(
+            &conn,
+            AutocorrectRule {
+                id: String::new(),
+                project_id: None,
+                pattern: "在也".to_string(),
+                replacement: "再也".to_string(),
+                is_regex: false,
+                scope: SCOPE_GLOBAL.to_string(),
+                enabled: true,
+                created_at: String::new(),
+            },
+        )
+        .unwrap();
+
+        let corrected = process_saved_chapter(&conn, "c1", "他").unwrap().unwrap();
+        assert_eq!((corrected.original.as_str(), corrected.content.as_str()), ("他在也不想回去了。", "他再也不想回去了。"));
+        let content: String = conn.query_row("SELECT content FROM chapters WHERE id = 'c1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(content, "他再也不想回去了。");
+        let added: i64 = conn.query_row("SELECT words_added FROM writing_stats WHERE project_id = 'p1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(added, 8);
+
+        // 没有需要更正的内容时不改写正文
+        assert_eq!(process_saved_chapter(&conn, "c1", "他再也不想回去了。").unwrap(), None);
+    }
+}
@@ -0,0 +1,269 @@
+use crate::entity_index::locate_terms;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// 外号、昵称（小舟、阿青）
+pub const KIND_NICKNAME: &str = "nickname";
+/// 姓氏加称谓（林公子、沈姑娘）
+pub const KIND_TITLE: &str = "title";
+/// 单用姓氏或去掉姓氏的名字（无忌）
+pub const KIND_SURNAME: &str = "surname";
+const KINDS: &[&str] = &[KIND_NICKNAME, KIND_TITLE, KIND_SURNAME];
+
+/// 接在姓氏后面的常见称谓
+const TITLES: &[&str] = &[
+    "公子", "少爷", "小姐", "姑娘", "先生", "夫人", "大人", "老爷", "师兄", "师姐", "师弟", "师妹", "师父", "掌柜", "将军", "大哥", "大姐",
+    "兄", "伯", "叔", "婶", "姨", "娘", "爷",
+];
+/// 加在名字中某个字前面构成昵称
+const DIMINUTIVES: &[&str] = &["小", "老", "阿"];
+/// 出现少于这么多次的疑似别名不提示
+const MIN_OCCURRENCES: usize = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CharacterAlias {
+    pub alias: String,
+    /// nickname、title 或 surname
+    pub kind: String,
+}
+
+/// 疑似别名可能对应的角色
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AliasCandidate {
+    pub character_id: String,
+    pub name: String,
+    /// 建议登记的别名类型
+    pub kind: String,
+    pub reason: String,
+}
+
+/// 正文中出现、像是某个角色的称呼却没有登记的名字
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AliasSuggestion {
+    pub text: String,
+    pub occurrences: usize,
+    pub chapter_ids: Vec<String>,
+    pub candidates: Vec<AliasCandidate>,
+}
+
+pub fn list_aliases(conn: &Connection, character_id: &str) -> Result<Vec<CharacterAlias>, String> {
+    conn.prepare("SELECT alias, kind FROM character_aliases WHERE character_id = ? ORDER BY rowid")
+        .map_err(|e| e.to_string())?
+        .query_map(params![character_id], |row| Ok(CharacterAlias { alias: row.get(0)?, kind: row.get(1)? }))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 替换角色的全部别名：去掉空白和重复，未知类型记为 nickname；别名不能与项目中其他角色的名字或别名相同
+pub fn save_aliases(conn: &Connection, character_id: &str, aliases: &[CharacterAlias]) -> Result<Vec<CharacterAlias>, String> {
+    let (project_id, name): (String, String) = conn
+        .query_row("SELECT project_id, name FROM characters WHERE id = ?", params![character_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|_| "角色不存在".to_string())?;
+    let mut taken: HashMap<String, String> = HashMap::new();
+    for (alias, owner) in conn
+        .prepare(
+            "SELECT name, name FROM characters WHERE project_id = ?1 AND id != ?2 AND merged_into IS NULL
+             UNION ALL
+             SELECT a.alias, c.name FROM character_aliases a JOIN characters c ON c.id = a.character_id
+             WHERE c.project_id = ?1 AND c.id != ?2",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id, character_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?
+    {
+        taken.insert(alias, owner);
+    }
+
+    let mut cleaned: Vec<CharacterAlias> = Vec::new();
+    for entry in aliases {
+        let alias = entry.alias.trim();
+        if alias.is_empty() || alias == name || cleaned.iter().any(|a| a.alias == alias) {
+            continue;
+        }
+        if let Some(owner) = taken.get(alias) {
+            return Err(format!("“{}”已是角色 {} 的名字或别名", alias, owner));
+        }
+        let kind = if KINDS.contains(&entry.kind.as_str()) { entry.kind.as_str() } else { KIND_NICKNAME };
+        cleaned.push(CharacterAlias { alias: alias.to_string(), kind: kind.to_string() });
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM character_aliases WHERE character_id = ?", params![character_id]).map_err(|e| e.to_string())?;
+    for alias in &cleaned {
+        tx.execute(
+            "INSERT INTO character_aliases (character_id, alias, kind) VALUES (?, ?, ?)",
+            params![character_id, alias.alias, alias.kind],
+        )
+        .map_err(|e| format!("Failed to save alias: {}", e))?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    let words: Vec<&str> = cleaned.iter().map(|a| a.alias.as_str()).collect();
+    crate::tokenizer::add_words(&words);
+    Ok(cleaned)
+}
+
+/// `text` 像是 `name` 的哪种称呼，返回 (别名类型, 说明)
+fn variant_of(text: &str, name: &str) -> Option<(&'static str, String)> {
+    let name_chars: Vec<char> = name.chars().collect();
+    let text_chars: Vec<char> = text.chars().collect();
+    if text == name || text_chars.len() < 2 || name_chars.len() < 2 {
+        return None;
+    }
+    let surname = name_chars[0].to_string();
+    let given: String = name_chars[1..].iter().collect();
+    if name_chars.len() >= 3 && text == given {
+        return Some((KIND_SURNAME, format!("去掉姓氏“{}”的名字", surname)));
+    }
+    if let Some(title) = text.strip_prefix(surname.as_str()).filter(|t| TITLES.contains(t)) {
+        return Some((KIND_TITLE, format!("姓氏“{}”加称谓“{}”", surname, title)));
+    }
+    if text_chars.len() == 2
+        && DIMINUTIVES.iter().any(|d| text.starts_with(d))
+        && name_chars.contains(&text_chars[1])
+    {
+        return Some((KIND_NICKNAME, format!("由名字中的“{}”构成的昵称", text_chars[1])));
+    }
+    None
+}
+
+/// 按常见规律为角色生成可能的称呼：去姓的名字、姓氏加称谓、小/老/阿加名字中的字
+fn variants(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    if chars.len() < 2 {
+        return Vec::new();
+    }
+    let mut variants = Vec::new();
+    if chars.len() >= 3 {
+        variants.push(chars[1..].iter().collect());
+    }
+    variants.extend(TITLES.iter().map(|t| format!("{}{}", chars[0], t)));
+    for d in DIMINUTIVES {
+        variants.push(format!("{}{}", d, chars[0]));
+        variants.push(format!("{}{}", d, chars[chars.len() - 1]));
+    }
+    variants
+}
+
+/// 找出正文中疑似未登记的别名：按规律生成的称呼，以及实体索引中未登记、但形似某个角色称呼的人名；
+/// 已是角色名或别名的不提示，出现次数少于 `MIN_OCCURRENCES` 的不提示
+pub fn find_unregistered(conn: &Connection, project_id: &str) -> Result<Vec<AliasSuggestion>, String> {
+    let characters: Vec<(String, String)> = conn
+        .prepare("SELECT id, name FROM characters WHERE project_id = ? AND merged_into IS NULL ORDER BY created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut known: HashSet<String> = characters.iter().map(|(_, name)| name.clone()).collect();
+    known.extend(crate::character_presence::aliases_by_character(conn, project_id)?.into_values().flatten());
+
+    let indexed: Vec<String> = conn
+        .prepare("SELECT DISTINCT name FROM entity_mentions WHERE project_id = ? AND kind = 'person'")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut suspects: BTreeMap<String, Vec<AliasCandidate>> = BTreeMap::new();
+    for text in characters.iter().flat_map(|(_, name)| variants(name)).chain(indexed) {
+        if known.contains(&text) || suspects.contains_key(&text) {
+            continue;
+        }
+        let candidates: Vec<AliasCandidate> = characters
+            .iter()
+            .filter_map(|(id, name)| {
+                variant_of(&text, name).map(|(kind, reason)| AliasCandidate {
+                    character_id: id.clone(),
+                    name: name.clone(),
+                    kind: kind.to_string(),
+                    reason,
+                })
+            })
+            .collect();
+        if !candidates.is_empty() {
+            suspects.insert(text, candidates);
+        }
+    }
+    if suspects.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // 已登记的名字一起参与匹配，较长的优先，避免把“张无忌”中的“无忌”算作一次出现
+    let mut terms: Vec<&str> = known.iter().map(String::as_str).collect();
+    let first_suspect = terms.len();
+    terms.extend(suspects.keys().map(String::as_str));
+    let chapters: Vec<(String, String)> = conn
+        .prepare("SELECT id, content FROM chapters WHERE project_id = ? ORDER BY sort_order, created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get::<_, Option<String>>(1)?.unwrap_or_default())))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut found: HashMap<usize, (usize, Vec<String>)> = HashMap::new();
+    for (chapter_id, content) in &chapters {
+        for (_, _, i) in locate_terms(content, &terms).into_iter().filter(|(_, _, i)| *i >= first_suspect) {
+            let entry = found.entry(i).or_default();
+            entry.0 += 1;
+            if !entry.1.contains(chapter_id) {
+                entry.1.push(chapter_id.clone());
+            }
+        }
+    }
+
+    let mut suggestions: Vec<AliasSuggestion> = found
+        .into_iter()
+        .filter(|(_, (occurrences, _))| *occurrences >= MIN_OCCURRENCES)
+        .map(|(i, (occurrences, chapter_ids))| AliasSuggestion {
+            text: terms[i].to_string(),
+            occurrences,
+            chapter_ids,
+            candidates: suspects[terms[i]].clone(),
+        })
+        .collect();
+    suggestions.sort_by(|a, b| b.occurrences.cmp(&a.occurrences).then_with(|| a.text.cmp(&b.text)));
+    Ok(suggestions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_aliases_and_find_unregistered() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES
+                 ('r1', 'p1', '张无忌', 't0', 't0'), ('r2', 'p1', '沈青', 't0', 't0');
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '张无忌推门进来，阿青迎上去：“无忌，你回来了。”沈姑娘笑了。', 1, 't0', 't0'),
+                 ('c2', 'p1', '第二章', '无忌点头。阿青又问起沈姑娘的事。张公子只出现一次。', 2, 't0', 't0');",
+        )
+        .unwrap();
+
+        let saved = save_aliases(
+            &conn,
+            "r2",
+            &[
+                CharacterAlias { alias: " 阿青 ".to_string(), kind: "whatever".to_string() },
+                CharacterAlias { alias: "阿青".to_string(), kind: KIND_TITLE.to_string() },
+            ],
+        )
+        .unwrap();
+        assert_eq!((saved.len(), saved[0].kind.as_str()), (1, KIND_NICKNAME));
+        let taken = CharacterAlias { alias: "阿青".to_string(), kind: KIND_NICKNAME.to_string() };
+        assert!(save_aliases(&conn, "r1", &[taken]).is_err());
+
+        let suggestions = find_unregistered(&conn, "p1").unwrap();
+        let found: Vec<(&str, usize, &str)> = suggestions
+            .iter()
+            .map(|s| (s.text.as_str(), s.occurrences, s.candidates[0].kind.as_str()))
+            .collect();
+        assert_eq!(found, vec![("无忌", 2, KIND_SURNAME), ("沈姑娘", 2, KIND_TITLE)]);
+        assert_eq!(suggestions[0].candidates[0].character_id, "r1");
+        assert_eq!(suggestions[1].chapter_ids, vec!["c1", "c2"]);
+    }
+}
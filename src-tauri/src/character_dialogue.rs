@@ -26,6 +26,9 @@ pub struct CharacterInfo {
     pub role_type: Option<String>,
     pub personality: Option<String>,
     pub background: Option<String>,
+    /// 角色说话风格的提示词说明
+    #[serde(default)]
+    pub voice_profile: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -180,6 +183,10 @@ impl CharacterDialogueManager {
         let personality = character_info.personality.as_ref().map(|s| s.as_str()).unwrap_or("");
         let background = character_info.background.as_ref().map(|s| s.as_str()).unwrap_or("");
         let role = character_info.role_type.as_ref().map(|s| s.as_str()).unwrap_or("");
+        let voice_prompt = character_info.voice_profile
+            .as_ref()
+            .map(|v| format!("\n{}\n", v))
+            .unwrap_or_default();
 
         format!(
             "你是一个角色扮演助手。你现在扮演角色'{}'。
@@ -188,7 +195,7 @@ impl CharacterDialogueManager {
 - 角色类型: {}
 - 描述: {}
 - 性格: {}
-
+{}
 你的任务是根据角色的设定和性格特点，以角色的口吻和思维方式回应用户的消息。
 
 {}{}",
@@ -196,6 +203,7 @@ impl CharacterDialogueManager {
             role,
             background,
             personality,
+            voice_prompt,
             history_prompt,
             scene_prompt
         )
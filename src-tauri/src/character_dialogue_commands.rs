@@ -595,12 +595,17 @@ fn get_character_info(conn: &rusqlite::Connection, session_id: &str) -> Result<C
         },
     ).map_err(|e| e.to_string())?;
 
+    let voice_profile = crate::voice_profile::get_profile(conn, &character_id)?
+        .map(|profile| crate::voice_profile::prompt_section(&profile))
+        .filter(|section| !section.is_empty());
+
     Ok(CharacterInfo {
         id: character_id,
         name,
         role_type,
         personality,
         background,
+        voice_profile,
     })
 }
 
@@ -6,6 +6,16 @@ use crate::character_tags::{
     CharacterTagManager, CharacterTag, TagType, TagWeight, TagSource,
     CharacterTagCollection
 };
+use crate::character_aliases::{self, CharacterAlias};
+use crate::character_merge;
+use crate::character_portraits;
+use crate::character_presence;
+use crate::character_sheet;
+use crate::relation_states;
+use crate::status_check;
+use crate::timeline_check;
+use crate::voice_profile::{self, VoiceProfile};
+use crate::ai::comfyui_client::GeneratedImage;
 use crate::logger::Logger;
 use tauri::{AppHandle, Manager};
 use rusqlite::params;
@@ -257,6 +267,342 @@ pub async fn get_character_tags(
     serde_json::to_string(&collection).map_err(|e| e.to_string())
 }
 
+/// 设置角色的别名，出场统计时与角色名一起匹配
+#[tauri::command]
+pub async fn set_character_aliases(
+    app: AppHandle,
+    character_id: String,
+    aliases: Vec<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let aliases = character_presence::set_aliases(&conn, &character_id, &aliases)?;
+
+    serde_json::to_string(&aliases).map_err(|e| e.to_string())
+}
+
+/// 角色的别名及其类型（nickname、title、surname）
+#[tauri::command]
+pub async fn get_character_aliases(
+    app: AppHandle,
+    character_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let aliases = character_aliases::list_aliases(&conn, &character_id)?;
+
+    serde_json::to_string(&aliases).map_err(|e| e.to_string())
+}
+
+/// 按类型保存角色的全部别名，别名不能与其他角色的名字或别名重复
+#[tauri::command]
+pub async fn save_character_aliases(
+    app: AppHandle,
+    character_id: String,
+    aliases: Vec<CharacterAlias>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let aliases = character_aliases::save_aliases(&conn, &character_id, &aliases)?;
+
+    serde_json::to_string(&aliases).map_err(|e| e.to_string())
+}
+
+/// 找出正文中像是某个角色的称呼、却没有登记为别名的名字
+#[tauri::command]
+pub async fn find_unregistered_aliases(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("character_growth");
+    logger.info(&format!("Checking unregistered aliases for project {}", project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let suggestions = character_aliases::find_unregistered(&conn, &project_id)?;
+
+    serde_json::to_string(&suggestions).map_err(|e| e.to_string())
+}
+
+/// 角色的说话风格，没有设置时返回 null
+#[tauri::command]
+pub async fn get_voice_profile(
+    app: AppHandle,
+    character_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let profile = voice_profile::get_profile(&conn, &character_id)?;
+
+    serde_json::to_string(&profile).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn save_voice_profile(
+    app: AppHandle,
+    profile: VoiceProfile,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let saved = voice_profile::save_profile(&conn, &profile)?;
+
+    serde_json::to_string(&saved).map_err(|e| e.to_string())
+}
+
+/// 给归给各角色的台词对照其说话风格打分，列出用了禁用词、句长不符等台词
+#[tauri::command]
+pub async fn check_character_voices(
+    app: AppHandle,
+    project_id: String,
+    chapter_id: Option<String>,
+    character_id: Option<String>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("character_growth");
+    logger.info(&format!("Checking character voices for project {}", project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let checks = voice_profile::check_voices(&conn, &project_id, chapter_id.as_deref(), character_id.as_deref())?;
+
+    serde_json::to_string(&checks).map_err(|e| e.to_string())
+}
+
+/// 导出角色卡（资料、别名、说话风格、标签、关系、时间线、成长记录和设定条目），`format` 为 "markdown" 时导出 Markdown
+#[tauri::command]
+pub async fn export_character_sheet(
+    app: AppHandle,
+    character_id: String,
+    format: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let sheet = character_sheet::export_sheet(&conn, &character_id)?;
+    if matches!(format.as_deref(), Some("markdown" | "md")) {
+        return character_sheet::sheet_markdown(&sheet);
+    }
+    serde_json::to_string_pretty(&sheet).map_err(|e| e.to_string())
+}
+
+/// 把 JSON 或 Markdown 角色卡导入到项目中作为新角色
+#[tauri::command]
+pub async fn import_character_sheet(
+    app: AppHandle,
+    project_id: String,
+    sheet: String,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("character_growth");
+    logger.info(&format!("Importing character sheet into project {}", project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let sheet = character_sheet::parse_sheet(&sheet)?;
+    let result = character_sheet::import_sheet(&conn, &project_id, &sheet).map_err(|e| {
+        logger.error(&format!("Failed to import character sheet: {}", e));
+        e
+    })?;
+
+    serde_json::to_string(&result).map_err(|e| e.to_string())
+}
+
+/// 角色的立绘画廊及各画风下已有的变体，`style` 指定时只列这种画风
+#[tauri::command]
+pub async fn get_portrait_gallery(
+    app: AppHandle,
+    character_id: String,
+    style: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let gallery = character_portraits::gallery(&conn, &character_id, style.as_deref())?;
+
+    serde_json::to_string(&gallery).map_err(|e| e.to_string())
+}
+
+/// 把上传的图片或 ComfyUI 的生成结果加入角色画廊
+#[tauri::command]
+pub async fn save_character_portraits(
+    app: AppHandle,
+    character_id: String,
+    images: Vec<GeneratedImage>,
+    style: String,
+    variant: Option<String>,
+    prompt: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let saved = character_portraits::save_comfyui_images(
+        &conn,
+        &character_id,
+        &images,
+        &style,
+        variant.as_deref().unwrap_or("front"),
+        prompt.as_deref(),
+    )?;
+
+    serde_json::to_string(&saved).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_portrait_as_avatar(
+    app: AppHandle,
+    portrait_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let portrait = character_portraits::set_avatar(&conn, &portrait_id)?;
+
+    serde_json::to_string(&portrait).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_character_portrait(
+    app: AppHandle,
+    portrait_id: String,
+) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    character_portraits::delete_portrait(&conn, &portrait_id)
+}
+
+/// 把重复角色合并到 `primary_id`，相关记录改为指向主角色，重复角色归档
+#[tauri::command]
+pub async fn merge_characters(
+    app: AppHandle,
+    primary_id: String,
+    duplicate_ids: Vec<String>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("character_growth");
+    logger.info(&format!("Merging {} characters into {}", duplicate_ids.len(), primary_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let result = character_merge::merge_characters(&conn, &primary_id, &duplicate_ids).map_err(|e| {
+        logger.error(&format!("Failed to merge characters: {}", e));
+        e
+    })?;
+
+    serde_json::to_string(&result).map_err(|e| e.to_string())
+}
+
+/// 记录关系在某章的状态，同一章已有记录时覆盖
+#[tauri::command]
+pub async fn record_relation_state(
+    app: AppHandle,
+    relation_id: String,
+    chapter_id: String,
+    strength: i32,
+    sentiment: f64,
+    status: Option<String>,
+    note: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let state = relation_states::record_state(
+        &conn,
+        &relation_id,
+        &chapter_id,
+        strength,
+        sentiment,
+        status.as_deref(),
+        note.as_deref(),
+    )?;
+
+    serde_json::to_string(&state).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_relation_state(app: AppHandle, state_id: String) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    relation_states::delete_state(&conn, &state_id)
+}
+
+/// 截至 `chapter_id` 的人物关系图，每条边附带该章及之前最近的状态
+#[tauri::command]
+pub async fn get_character_graph_as_of(
+    app: AppHandle,
+    project_id: String,
+    chapter_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let graph = relation_states::graph_as_of(&conn, &project_id, &chapter_id)?;
+    serde_json::to_string(&graph).map_err(|e| e.to_string())
+}
+
+/// 关系在全书中的强度、好感和状态变化，供绘制曲线
+#[tauri::command]
+pub async fn get_relation_evolution(
+    app: AppHandle,
+    project_id: String,
+    relation_id: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let tracks = relation_states::evolution(&conn, &project_id, relation_id.as_deref())?;
+    serde_json::to_string(&tracks).map_err(|e| e.to_string())
+}
+
+/// 每个角色在每章被提及和说话的次数、首末次出场章节，主要角色连续缺席超过 `max_absence` 章的区间，
+/// 以及从未出场或只出场一次就消失的角色
+#[tauri::command]
+pub async fn get_character_presence(
+    app: AppHandle,
+    project_id: String,
+    max_absence: Option<usize>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("character_growth");
+    logger.info(&format!("Analyzing character presence for project {}", project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let matrix = character_presence::presence_matrix(
+        &conn,
+        &project_id,
+        max_absence.unwrap_or(character_presence::DEFAULT_MAX_ABSENCE),
+    )?;
+
+    serde_json::to_string(&matrix).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn delete_character_tag(
     app: AppHandle,
@@ -319,7 +665,7 @@ pub async fn search_tags(
         .map_err(|e| format!("Failed to collect tags: {}", e))?;
 
     let character_names: HashMap<String, String> = conn.prepare(
-        "SELECT id, name FROM characters WHERE project_id = ?1"
+        "SELECT id, name FROM characters WHERE project_id = ?1 AND merged_into IS NULL"
     ).map_err(|e| format!("Failed to prepare statement: {}", e))?
     .query_map(params![project_id], |row| {
         Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
@@ -394,7 +740,7 @@ pub async fn get_tag_statistics(
         .map_err(|e| format!("Failed to collect tags: {}", e))?;
 
     let character_count: i32 = conn.query_row(
-        "SELECT COUNT(*) FROM characters WHERE project_id = ?1",
+        "SELECT COUNT(*) FROM characters WHERE project_id = ?1 AND merged_into IS NULL",
         params![project_id],
         |row| row.get(0)
     ).unwrap_or(0);
@@ -404,6 +750,40 @@ pub async fn get_tag_statistics(
     serde_json::to_string(&statistics).map_err(|e| e.to_string())
 }
 
+/// 交叉检查角色、世界观时间线和正文中的年份、年龄、季节，返回不可能的先后顺序
+#[tauri::command]
+pub async fn check_timeline_consistency(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("character_growth");
+    logger.info(&format!("Checking timeline consistency for project {}", project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let report = timeline_check::check_project(&conn, &project_id)?;
+    serde_json::to_string(&report).map_err(|e| e.to_string())
+}
+
+/// 检查成长记录中标记为死亡、入狱或失踪的角色，在之后的章节里是否仍在说话或行动
+#[tauri::command]
+pub async fn check_character_status_consistency(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("character_growth");
+    logger.info(&format!("Checking character status consistency for project {}", project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let report = status_check::check_project(&conn, &project_id)?;
+    serde_json::to_string(&report).map_err(|e| e.to_string())
+}
+
 fn get_db_path(app: &AppHandle) -> Result<std::path::PathBuf, String> {
     if cfg!(debug_assertions) {
         let mut project_dir = std::env::current_dir()
@@ -0,0 +1,188 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// 主角色为空时从重复角色补上的字段
+const FILLABLE_FIELDS: &[&str] = &[
+    "role_type", "race", "age", "gender", "birth_date", "appearance", "personality", "background", "skills", "status", "avatar_url",
+];
+
+/// 合并结果，各计数是改为指向主角色的记录数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MergeResult {
+    pub primary_id: String,
+    pub merged_ids: Vec<String>,
+    /// 重复角色的名字和别名，合并后成为主角色的别名
+    pub aliases_added: Vec<String>,
+    pub relations: usize,
+    pub timeline_events: usize,
+    pub growth_records: usize,
+    pub tags: usize,
+    pub dialogue_sessions: usize,
+    pub knowledge_entries: usize,
+    pub mentions: usize,
+}
+
+/// 把重复角色合并到主角色：关系、时间线、成长记录、标签、对话会话、知识条目和正文提及改为指向主角色，
+/// 重复角色的名字和别名加为主角色的别名，主角色空着的资料用重复角色的补上；
+/// 重复角色不删除，只标记 `merged_into` 归档，不再出现在角色列表和分析中
+pub fn merge_characters(conn: &Connection, primary_id: &str, duplicate_ids: &[String]) -> Result<MergeResult, String> {
+    let (project_id, primary_name): (String, String) = conn
+        .query_row(
+            "SELECT project_id, name FROM characters WHERE id = ? AND merged_into IS NULL",
+            params![primary_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "主角色不存在或已被合并".to_string())?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let mut result = MergeResult { primary_id: primary_id.to_string(), ..Default::default() };
+    for duplicate_id in duplicate_ids.iter().filter(|id| id.as_str() != primary_id) {
+        let (duplicate_project, duplicate_name): (String, String) = tx
+            .query_row(
+                "SELECT project_id, name FROM characters WHERE id = ? AND merged_into IS NULL",
+                params![duplicate_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("角色不存在或已被合并: {}", duplicate_id))?;
+        if duplicate_project != project_id {
+            return Err(format!("角色 {} 不属于同一项目", duplicate_name));
+        }
+        let run = |sql: &str| tx.execute(sql, params![primary_id, duplicate_id]).map_err(|e| e.to_string());
+
+        // 主角色和重复角色之间的关系合并后没有意义；与主角色已有的同类关系重复时保留主角色的
+        tx.execute(
+            "DELETE FROM character_relations WHERE (from_character_id = ?1 AND to_character_id = ?2)
+                 OR (from_character_id = ?2 AND to_character_id = ?1)",
+            params![primary_id, duplicate_id],
+        )
+        .map_err(|e| e.to_string())?;
+        result.relations += run("UPDATE OR IGNORE character_relations SET from_character_id = ?1 WHERE from_character_id = ?2")?;
+        result.relations += run("UPDATE OR IGNORE character_relations SET to_character_id = ?1 WHERE to_character_id = ?2")?;
+        run("DELETE FROM character_relations WHERE from_character_id = ?2 OR to_character_id = ?2")?;
+        run("DELETE FROM character_kinship WHERE (from_id = ?1 AND to_id = ?2) OR (from_id = ?2 AND to_id = ?1)")?;
+        run("UPDATE OR IGNORE character_kinship SET from_id = ?1 WHERE from_id = ?2")?;
+        run("UPDATE OR IGNORE character_kinship SET to_id = ?1 WHERE to_id = ?2")?;
+        result.timeline_events += run("UPDATE character_timeline_events SET character_id = ?1 WHERE character_id = ?2")?;
+        result.growth_records += run("UPDATE character_growth_records SET character_id = ?1 WHERE character_id = ?2")?;
+        run(
+            "DELETE FROM character_tags WHERE character_id = ?2 AND EXISTS (
+                 SELECT 1 FROM character_tags p WHERE p.character_id = ?1 AND p.tag_type = character_tags.tag_type AND p.name = character_tags.name)",
+        )?;
+        result.tags += run("UPDATE character_tags SET character_id = ?1 WHERE character_id = ?2")?;
+        run("UPDATE OR IGNORE character_voice_profiles SET character_id = ?1 WHERE character_id = ?2")?;
+        run("UPDATE organization_members SET character_id = ?1 WHERE character_id = ?2")?;
+        run("UPDATE story_items SET initial_owner_id = ?1 WHERE initial_owner_id = ?2")?;
+        run("UPDATE item_events SET character_id = ?1 WHERE character_id = ?2")?;
+        run("UPDATE OR IGNORE character_power_progress SET character_id = ?1 WHERE character_id = ?2")?;
+        run("UPDATE wealth_snapshots SET character_id = ?1 WHERE character_id = ?2")?;
+        result.dialogue_sessions += run("UPDATE character_dialogue_sessions SET character_id = ?1 WHERE character_id = ?2")?;
+        result.knowledge_entries += run("UPDATE knowledge_entries SET source_id = ?1 WHERE source_type = 'character' AND source_id = ?2")?;
+        result.mentions += tx
+            .execute(
+                "UPDATE entity_mentions SET name = ? WHERE project_id = ? AND kind = 'person' AND name = ?",
+                params![primary_name, project_id, duplicate_name],
+            )
+            .map_err(|e| e.to_string())?;
+
+        let mut names: Vec<String> = tx
+            .prepare("SELECT alias FROM character_aliases WHERE character_id = ? ORDER BY rowid")
+            .map_err(|e| e.to_string())?
+            .query_map(params![duplicate_id], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+        names.insert(0, duplicate_name);
+        for name in names.into_iter().filter(|n| *n != primary_name) {
+            let added = tx
+                .execute("INSERT OR IGNORE INTO character_aliases (character_id, alias) VALUES (?, ?)", params![primary_id, name])
+                .map_err(|e| e.to_string())?;
+            if added > 0 {
+                result.aliases_added.push(name);
+            }
+        }
+        run("DELETE FROM character_aliases WHERE character_id = ?2")?;
+
+        for field in FILLABLE_FIELDS {
+            run(&format!(
+                "UPDATE characters SET {field} = (SELECT d.{field} FROM characters d WHERE d.id = ?2)
+                 WHERE id = ?1 AND ({field} IS NULL OR {field} = '')",
+                field = field
+            ))?;
+        }
+        tx.execute(
+            "UPDATE characters SET merged_into = ?, updated_at = ? WHERE id = ?",
+            params![primary_id, now, duplicate_id],
+        )
+        .map_err(|e| e.to_string())?;
+        result.merged_ids.push(duplicate_id.clone());
+    }
+    tx.execute("UPDATE characters SET updated_at = ? WHERE id = ?", params![now, primary_id]).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    crate::tokenizer::add_words(&result.aliases_added);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_repoints_records_and_archives_duplicates() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO projects (id, name, created_at, updated_at) VALUES ('p2', '别处', 't0', 't0');
+             INSERT INTO characters (id, project_id, name, personality, created_at, updated_at) VALUES
+                 ('r1', 'p1', '林舟', NULL, 't0', 't0'),
+                 ('r2', 'p1', '林洲', '沉默寡言', 't0', 't0'),
+                 ('r3', 'p1', '沈青', NULL, 't0', 't0'),
+                 ('r4', 'p2', '林舟', NULL, 't0', 't0');
+             INSERT INTO character_aliases (character_id, alias) VALUES ('r2', '小舟');
+             INSERT INTO character_relations (id, project_id, from_character_id, to_character_id, relation_type, created_at, updated_at) VALUES
+                 ('x1', 'p1', 'r1', 'r3', 'friend', 't0', 't0'),
+                 ('x2', 'p1', 'r2', 'r3', 'friend', 't0', 't0'),
+                 ('x3', 'p1', 'r3', 'r2', 'rival', 't0', 't0'),
+                 ('x4', 'p1', 'r1', 'r2', 'same', 't0', 't0');
+             INSERT INTO character_tags (id, character_id, tag_type, name, color, weight, source, created_at, updated_at) VALUES
+                 ('t1', 'r1', 'personality', '冷静', '#000', 'high', 'manual', 't0', 't0'),
+                 ('t2', 'r2', 'personality', '冷静', '#000', 'high', 'manual', 't0', 't0'),
+                 ('t3', 'r2', 'skill', '剑术', '#000', 'high', 'manual', 't0', 't0');
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES ('c1', 'p1', '第一章', '', 1, 't0', 't0');
+             INSERT INTO entity_mentions (id, project_id, chapter_id, name, text, kind, source, start_offset, end_offset, created_at) VALUES
+                 ('m1', 'p1', 'c1', '林洲', '林洲', 'person', 'rule', 0, 2, 't0');",
+        )
+        .unwrap();
+
+        assert!(merge_characters(&conn, "r1", &["r4".to_string()]).is_err());
+        let result = merge_characters(&conn, "r1", &["r2".to_string()]).unwrap();
+        assert_eq!(result.aliases_added, vec!["林洲", "小舟"]);
+        assert_eq!((result.relations, result.tags, result.mentions), (1, 1, 1));
+
+        let relations: Vec<(String, String, String)> = conn
+            .prepare("SELECT from_character_id, to_character_id, relation_type FROM character_relations ORDER BY id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let relations: Vec<(&str, &str, &str)> = relations.iter().map(|(a, b, c)| (a.as_str(), b.as_str(), c.as_str())).collect();
+        assert_eq!(relations, vec![("r1", "r3", "friend"), ("r3", "r1", "rival")]);
+
+        let (personality, tag_count): (String, i64) = conn
+            .query_row(
+                "SELECT personality, (SELECT COUNT(*) FROM character_tags WHERE character_id = 'r1') FROM characters WHERE id = 'r1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((personality.as_str(), tag_count), ("沉默寡言", 2));
+        let merged_into: Option<String> = conn.query_row("SELECT merged_into FROM characters WHERE id = 'r2'", [], |row| row.get(0)).unwrap();
+        assert_eq!(merged_into.as_deref(), Some("r1"));
+        assert!(merge_characters(&conn, "r1", &["r2".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,236 @@
+use crate::ai::comfyui_client::GeneratedImage;
+use crate::multimedia_generation::types::CharacterPortrait;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 角色画廊中的一张立绘，`variant` 是角度（front、side…）、表情（expression:开心）或 turnaround
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortraitAsset {
+    pub id: String,
+    pub character_id: String,
+    pub image: String,
+    pub style: String,
+    pub variant: String,
+    /// mmg、comfyui 或 upload
+    pub source: String,
+    pub prompt: Option<String>,
+    pub is_avatar: bool,
+    pub created_at: String,
+}
+
+/// 某一画风下已有的立绘变体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StyleVariants {
+    pub style: String,
+    pub count: usize,
+    pub variants: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortraitGallery {
+    pub character_id: String,
+    pub avatar_url: Option<String>,
+    pub styles: Vec<StyleVariants>,
+    pub portraits: Vec<PortraitAsset>,
+}
+
+fn get_portrait(conn: &Connection, portrait_id: &str) -> Result<Option<PortraitAsset>, String> {
+    conn.query_row(
+        "SELECT p.id, p.character_id, p.image, p.style, p.variant, p.source, p.prompt, p.created_at, c.avatar_url = p.image
+         FROM character_portraits p JOIN characters c ON c.id = p.character_id WHERE p.id = ?",
+        params![portrait_id],
+        portrait_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+fn portrait_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<PortraitAsset> {
+    Ok(PortraitAsset {
+        id: row.get(0)?,
+        character_id: row.get(1)?,
+        image: row.get(2)?,
+        style: row.get(3)?,
+        variant: row.get(4)?,
+        source: row.get(5)?,
+        prompt: row.get(6)?,
+        created_at: row.get(7)?,
+        is_avatar: row.get::<_, Option<bool>>(8)?.unwrap_or(false),
+    })
+}
+
+/// 把一张立绘加入角色画廊，同一张图片已在画廊中时返回原有记录
+pub fn add_portrait(
+    conn: &Connection,
+    character_id: &str,
+    image: &str,
+    style: &str,
+    variant: &str,
+    source: &str,
+    prompt: Option<&str>,
+) -> Result<PortraitAsset, String> {
+    if image.trim().is_empty() {
+        return Err("图片不能为空".to_string());
+    }
+    conn.execute(
+        "INSERT OR IGNORE INTO character_portraits (id, character_id, image, style, variant, source, prompt, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        params![Uuid::new_v4().to_string(), character_id, image, style, variant, source, prompt, Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| format!("Failed to save portrait: {}", e))?;
+    let id: String = conn
+        .query_row(
+            "SELECT id FROM character_portraits WHERE character_id = ? AND image = ?",
+            params![character_id, image],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    get_portrait(conn, &id)?.ok_or_else(|| "立绘不存在".to_string())
+}
+
+/// 保存 `mmg_generate_character_portrait` 生成的各角度、表情和转面图
+pub fn save_generated(conn: &Connection, portrait: &CharacterPortrait, style: &str) -> Result<Vec<PortraitAsset>, String> {
+    let images = portrait
+        .views
+        .iter()
+        .map(|v| (v.image.clone(), v.angle.clone()))
+        .chain(portrait.expressions.iter().map(|e| (e.image.clone(), format!("expression:{}", e.expression))))
+        .chain(std::iter::once((portrait.turnaround.clone(), "turnaround".to_string())))
+        .filter(|(image, _)| !image.is_empty());
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut saved = Vec::new();
+    for (image, variant) in images {
+        saved.push(add_portrait(&tx, &portrait.character_id, &image, style, &variant, "mmg", None)?);
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(saved)
+}
+
+/// 保存 ComfyUI 生成的图片，优先用图片地址，没有时用内嵌的 base64 数据
+pub fn save_comfyui_images(
+    conn: &Connection,
+    character_id: &str,
+    images: &[GeneratedImage],
+    style: &str,
+    variant: &str,
+    prompt: Option<&str>,
+) -> Result<Vec<PortraitAsset>, String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut saved = Vec::new();
+    for image in images {
+        let reference = match (&image.url, &image.base64_data) {
+            (Some(url), _) => url.clone(),
+            (None, Some(data)) => format!("data:image/png;base64,{}", data),
+            (None, None) => format!("comfyui://{}/{}/{}", image.image_type, image.subfolder, image.filename),
+        };
+        saved.push(add_portrait(&tx, character_id, &reference, style, variant, "comfyui", prompt)?);
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(saved)
+}
+
+/// 把画廊中的一张立绘设为角色头像
+pub fn set_avatar(conn: &Connection, portrait_id: &str) -> Result<PortraitAsset, String> {
+    let portrait = get_portrait(conn, portrait_id)?.ok_or_else(|| "立绘不存在".to_string())?;
+    conn.execute(
+        "UPDATE characters SET avatar_url = ?, updated_at = ? WHERE id = ?",
+        params![portrait.image, Utc::now().to_rfc3339(), portrait.character_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(PortraitAsset { is_avatar: true, ..portrait })
+}
+
+/// 从画廊删除一张立绘，它正被用作头像时一并清空头像
+pub fn delete_portrait(conn: &Connection, portrait_id: &str) -> Result<(), String> {
+    let portrait = get_portrait(conn, portrait_id)?.ok_or_else(|| "立绘不存在".to_string())?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    if portrait.is_avatar {
+        tx.execute(
+            "UPDATE characters SET avatar_url = NULL, updated_at = ? WHERE id = ?",
+            params![Utc::now().to_rfc3339(), portrait.character_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.execute("DELETE FROM character_portraits WHERE id = ?", params![portrait_id]).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// 角色的立绘画廊，按生成时间从新到旧，`style` 指定时只列这种画风
+pub fn gallery(conn: &Connection, character_id: &str, style: Option<&str>) -> Result<PortraitGallery, String> {
+    let avatar_url: Option<String> = conn
+        .query_row("SELECT avatar_url FROM characters WHERE id = ?", params![character_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "角色不存在".to_string())?;
+    let portraits: Vec<PortraitAsset> = conn
+        .prepare(
+            "SELECT p.id, p.character_id, p.image, p.style, p.variant, p.source, p.prompt, p.created_at, c.avatar_url = p.image
+             FROM character_portraits p JOIN characters c ON c.id = p.character_id
+             WHERE p.character_id = ?1 AND (?2 IS NULL OR p.style = ?2)
+             ORDER BY p.created_at DESC, p.rowid DESC",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![character_id, style], portrait_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut styles: Vec<StyleVariants> = Vec::new();
+    for portrait in &portraits {
+        let index = match styles.iter().position(|s| s.style == portrait.style) {
+            Some(index) => index,
+            None => {
+                styles.push(StyleVariants { style: portrait.style.clone(), count: 0, variants: Vec::new() });
+                styles.len() - 1
+            }
+        };
+        let entry = &mut styles[index];
+        entry.count += 1;
+        if !entry.variants.contains(&portrait.variant) {
+            entry.variants.push(portrait.variant.clone());
+        }
+    }
+    Ok(PortraitGallery { character_id: character_id.to_string(), avatar_url, styles, portraits })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::multimedia_generation::types::{CharacterExpression, CharacterView};
+
+    #[test]
+    fn test_gallery_and_avatar() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES ('r1', 'p1', '林舟', 't0', 't0');",
+        )
+        .unwrap();
+        let generated = CharacterPortrait {
+            character_id: "r1".to_string(),
+            views: vec![
+                CharacterView { angle: "front".to_string(), image: "front.png".to_string(), embedding: None },
+                CharacterView { angle: "side".to_string(), image: "side.png".to_string(), embedding: None },
+            ],
+            expressions: vec![CharacterExpression { expression: "开心".to_string(), image: "happy.png".to_string() }],
+            turnaround: String::new(),
+        };
+        let saved = save_generated(&conn, &generated, "anime").unwrap();
+        assert_eq!(saved.len(), 3);
+        assert_eq!(save_generated(&conn, &generated, "anime").unwrap()[0].id, saved[0].id);
+        add_portrait(&conn, "r1", "oil.png", "oil_painting", "front", "upload", None).unwrap();
+
+        set_avatar(&conn, &saved[0].id).unwrap();
+        let all = gallery(&conn, "r1", None).unwrap();
+        assert_eq!(all.avatar_url.as_deref(), Some("front.png"));
+        assert_eq!(all.portraits.len(), 4);
+        assert_eq!(all.portraits.iter().filter(|p| p.is_avatar).count(), 1);
+        let anime = all.styles.iter().find(|s| s.style == "anime").unwrap();
+        assert_eq!(anime.variants, vec!["expression:开心", "side", "front"]);
+        assert_eq!(gallery(&conn, "r1", Some("oil_painting")).unwrap().portraits.len(), 1);
+
+        delete_portrait(&conn, &saved[0].id).unwrap();
+        assert_eq!(gallery(&conn, "r1", None).unwrap().avatar_url, None);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::entity_index::locate_terms;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 默认连续缺席超过这么多章才提示
+pub const DEFAULT_MAX_ABSENCE: usize = 5;
+/// 这些身份的角色算作主要角色
+const MAIN_ROLES: &[&str] = &["protagonist", "deuteragonist", "antagonist", "主角", "第二主角", "反派"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterRef {
+    pub chapter_id: String,
+    pub title: String,
+}
+
+/// 角色连续没有出场的章节，`from`/`to` 是章节序号（含）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Absence {
+    pub character_id: String,
+    pub name: String,
+    pub from: usize,
+    pub to: usize,
+    pub length: usize,
+}
+
+/// 一个角色在各章的出场，`mentions`/`dialogue_lines` 与 `PresenceMatrix::chapters` 一一对应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CharacterPresence {
+    pub character_id: String,
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub role_type: Option<String>,
+    pub main: bool,
+    pub mentions: Vec<usize>,
+    pub dialogue_lines: Vec<usize>,
+    pub total_mentions: usize,
+    pub total_dialogue_lines: usize,
+    /// 首次和最后一次出场的章节序号，从未出场时为 None
+    pub first_appearance: Option<usize>,
+    pub last_appearance: Option<usize>,
+    /// 出场的章节序号
+    pub chapters_present: Vec<usize>,
+}
+
+/// 出场提示：never_appears 设定了但正文中从未出现；single_appearance 只在一章出场，
+/// 之后已经过去超过上限的章节仍未再出现
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppearanceWarning {
+    pub character_id: String,
+    pub name: String,
+    pub kind: String,
+    pub chapter: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresenceMatrix {
+    pub chapters: Vec<ChapterRef>,
+    pub characters: Vec<CharacterPresence>,
+    /// 主要角色出场后连续缺席超过上限的区间
+    pub vanished: Vec<Absence>,
+    pub warnings: Vec<AppearanceWarning>,
+}
+
+/// 设置角色的别名（外号、简称等），返回去重后的别名
+pub fn set_aliases(conn: &Connection, character_id: &str, aliases: &[String]) -> Result<Vec<String>, String> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for alias in aliases.iter().map(|a| a.trim()).filter(|a| !a.is_empty()) {
+        if !cleaned.iter().any(|a| a == alias) {
+            cleaned.push(alias.to_string());
+        }
+    }
+    // 保留的别名沿用原来的类型
+    let kinds: HashMap<String, String> = conn
+        .prepare("SELECT alias, kind FROM character_aliases WHERE character_id = ?")
+        .map_err(|e| e.to_string())?
+        .query_map(params![character_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM character_aliases WHERE character_id = ?", params![character_id]).map_err(|e| e.to_string())?;
+    for alias in &cleaned {
+        let kind = kinds.get(alias).map(String::as_str).unwrap_or(crate::character_aliases::KIND_NICKNAME);
+        tx.execute("INSERT INTO character_aliases (character_id, alias, kind) VALUES (?, ?, ?)", params![character_id, alias, kind])
+            .map_err(|e| format!("Failed to save alias: {}", e))?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    crate::tokenizer::add_words(&cleaned);
+    Ok(cleaned)
+}
+
+pub(crate) fn aliases_by_character(conn: &Connection, project_id: &str) -> Result<HashMap<String, Vec<String>>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT a.character_id, a.alias FROM character_aliases a JOIN characters c ON c.id = a.character_id
+             WHERE c.project_id = ? ORDER BY a.rowid",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![project_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+    let mut grouped: HashMap<String, Vec<String>> = HashMap::new();
+    for row in rows {
+        let (character_id, alias) = row.map_err(|e| e.to_string())?;
+        grouped.entry(character_id).or_default().push(alias);
+    }
+    Ok(grouped)
+}
+
+/// 统计 `terms` 在文本中不重叠出现的次数，较长的词优先，避免“林舟”同时算作“舟”
+fn count_terms(text: &str, terms: &[String]) -> usize {
+    let terms: Vec<&str> = terms.iter().map(String::as_str).collect();
+    locate_terms(text, &terms).len()
+}
+
+/// 一行对话的说话人：引号前最靠近引号的名字，没有时取引号后第一个名字
+pub(crate) fn speaker<'a>(line: &str, characters: &'a [(String, Vec<String>)]) -> Option<&'a str> {
+    let open = line.find(['“', '「', '"'])?;
+    let before = &line[..open];
+    let after = line[open..].rfind(['”', '」', '"']).map(|i| &line[open + i..]).unwrap_or("");
+    let nearest = |text: &str, last: bool| {
+        characters
+            .iter()
+            .filter_map(|(id, terms)| {
+                let positions = terms.iter().filter_map(|t| if last { text.rfind(t) } else { text.find(t) });
+                let position = if last { positions.max() } else { positions.min() };
+                position.map(|p| (p, id.as_str()))
+            })
+            .reduce(|a, b| if (b.0 > a.0) == last { b } else { a })
+            .map(|(_, id)| id)
+    };
+    nearest(before, true).or_else(|| nearest(after, false))
+}
+
+/// 统计项目中每个角色在每章被提及和说话的次数，主要角色连续缺席超过 `max_absence` 章时提示
+pub fn presence_matrix(conn: &Connection, project_id: &str, max_absence: usize) -> Result<PresenceMatrix, String> {
+    let chapters: Vec<(String, String, String)> = conn
+        .prepare("SELECT id, title, content FROM chapters WHERE project_id = ? ORDER BY sort_order, created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, Option<String>>(2)?.unwrap_or_default())))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let characters: Vec<(String, String, Option<String>)> = conn
+        .prepare("SELECT id, name, role_type FROM characters WHERE project_id = ? AND merged_into IS NULL ORDER BY created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut aliases = aliases_by_character(conn, project_id)?;
+
+    let mut presence: Vec<CharacterPresence> = characters
+        .into_iter()
+        .map(|(character_id, name, role_type)| CharacterPresence {
+            aliases: aliases.remove(&character_id).unwrap_or_default(),
+            main: role_type.as_deref().is_some_and(|r| MAIN_ROLES.contains(&r)),
+            character_id,
+            name,
+            role_type,
+            mentions: Vec::with_capacity(chapters.len()),
+            dialogue_lines: Vec::with_capacity(chapters.len()),
+            total_mentions: 0,
+            total_dialogue_lines: 0,
+            first_appearance: None,
+            last_appearance: None,
+            chapters_present: Vec::new(),
+        })
+        .collect();
+    let terms: Vec<(String, Vec<String>)> = presence
+        .iter()
+        .map(|p| (p.character_id.clone(), std::iter::once(&p.name).chain(&p.aliases).cloned().collect()))
+        .collect();
+
+    for (_, _, content) in &chapters {
+        let mut speakers: HashMap<&str, usize> = HashMap::new();
+        for line in content.lines() {
+            if let Some(id) = speaker(line, &terms) {
+                *speakers.entry(id).or_default() += 1;
+            }
+        }
+        for (p, (id, character_terms)) in presence.iter_mut().zip(&terms) {
+            p.mentions.push(count_terms(content, character_terms));
+            p.dialogue_lines.push(speakers.get(id.as_str()).copied().unwrap_or(0));
+        }
+    }
+
+    let mut vanished = Vec::new();
+    let mut warnings = Vec::new();
+    for p in &mut presence {
+        p.total_mentions = p.mentions.iter().sum();
+        p.total_dialogue_lines = p.dialogue_lines.iter().sum();
+        p.chapters_present = p.mentions.iter().enumerate().filter(|(_, &m)| m > 0).map(|(i, _)| i).collect();
+        p.first_appearance = p.chapters_present.first().copied();
+        p.last_appearance = p.chapters_present.last().copied();
+        let warning = |kind: &str, chapter: Option<usize>| AppearanceWarning {
+            character_id: p.character_id.clone(),
+            name: p.name.clone(),
+            kind: kind.to_string(),
+            chapter,
+        };
+        match p.chapters_present.as_slice() {
+            [] if !chapters.is_empty() => warnings.push(warning("never_appears", None)),
+            &[only] if chapters.len() - 1 - only > max_absence => warnings.push(warning("single_appearance", Some(only))),
+            _ => {}
+        }
+        if !p.main {
+            continue;
+        }
+        let Some(first) = p.mentions.iter().position(|&m| m > 0) else { continue };
+        let mut run_start = None;
+        for (i, &m) in p.mentions.iter().enumerate().skip(first).chain(std::iter::once((p.mentions.len(), &1))) {
+            match (m, run_start) {
+                (0, None) => run_start = Some(i),
+                (m, Some(start)) if m > 0 => {
+                    if i - start > max_absence {
+                        vanished.push(Absence { character_id: p.character_id.clone(), name: p.name.clone(), from: start, to: i - 1, length: i - start });
+                    }
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(PresenceMatrix {
+        chapters: chapters.into_iter().map(|(chapter_id, title, _)| ChapterRef { chapter_id, title }).collect(),
+        characters: presence,
+        vanished,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presence_matrix_flags_vanished_main_character() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, role_type, created_at, updated_at) VALUES
+                 ('r1', 'p1', '林舟', 'protagonist', 't0', 't0'),
+                 ('r2', 'p1', '沈青', 'supporting', 't1', 't1');",
+        )
+        .unwrap();
+        let contents = [
+            "林舟推开门。\n小舟笑道：“你来了。”\n沈青没有回答。",
+            "沈青说：“他走了。”",
+            "雨下了一夜。",
+            "“回来了。”林舟说。",
+        ];
+        for (i, content) in contents.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES (?, 'p1', ?, ?, ?, 't0', 't0')",
+                params![format!("c{}", i), format!("第{}章", i + 1), content, i as i64],
+            )
+            .unwrap();
+        }
+        assert_eq!(set_aliases(&conn, "r1", &[" 小舟 ".to_string(), "小舟".to_string()]).unwrap(), vec!["小舟"]);
+
+        let matrix = presence_matrix(&conn, "p1", 1).unwrap();
+        let lin = &matrix.characters[0];
+        assert_eq!(lin.mentions, vec![2, 0, 0, 1]);
+        assert_eq!(lin.dialogue_lines, vec![1, 0, 0, 1]);
+        assert_eq!(matrix.characters[1].dialogue_lines, vec![0, 1, 0, 0]);
+        assert_eq!(matrix.vanished.len(), 1);
+        assert_eq!((matrix.vanished[0].from, matrix.vanished[0].to, matrix.vanished[0].length), (1, 2, 2));
+        assert!(presence_matrix(&conn, "p1", 2).unwrap().vanished.is_empty());
+
+        assert_eq!((lin.first_appearance, lin.last_appearance, lin.chapters_present.clone()), (Some(0), Some(3), vec![0, 3]));
+        conn.execute(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES ('r3', 'p1', '阿七', 't2', 't2'), ('r4', 'p1', '老周', 't3', 't3')",
+            [],
+        )
+        .unwrap();
+        conn.execute("UPDATE chapters SET content = content || '阿七跟在后面。' WHERE id = 'c1'", []).unwrap();
+        let warnings: Vec<(String, Option<usize>)> = presence_matrix(&conn, "p1", 1)
+            .unwrap()
+            .warnings
+            .into_iter()
+            .map(|w| (w.kind, w.chapter))
+            .collect();
+        assert_eq!(warnings, vec![("single_appearance".to_string(), Some(1)), ("never_appears".to_string(), None)]);
+        assert_eq!(presence_matrix(&conn, "p1", 2).unwrap().warnings.len(), 1);
+    }
+}
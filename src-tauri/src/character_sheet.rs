@@ -0,0 +1,504 @@
+use crate::models::Character;
+use crate::voice_profile::{self, VoiceProfile};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 角色卡格式版本，字段不兼容地变化时加一
+const SHEET_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SheetTag {
+    pub tag_type: String,
+    pub name: String,
+    pub value: Option<String>,
+    pub description: Option<String>,
+    pub color: String,
+    pub weight: String,
+    pub source: String,
+}
+
+/// 关系的另一方按名字记录，导入时在目标项目中按名字或别名重新对应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SheetRelation {
+    pub other: String,
+    /// outgoing 表示本角色指向对方
+    pub direction: String,
+    pub relation_type: String,
+    pub description: Option<String>,
+}
+
+/// 时间线事件和成长记录的章节按标题记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SheetTimelineEvent {
+    pub event_type: String,
+    pub event_title: String,
+    pub event_description: Option<String>,
+    pub story_time: Option<String>,
+    pub chapter_title: Option<String>,
+    pub emotional_state: Option<String>,
+    pub state_changes: Option<String>,
+    pub sort_order: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SheetGrowthRecord {
+    pub chapter_title: String,
+    pub position: i64,
+    pub changes: serde_json::Value,
+    pub notes: Option<String>,
+}
+
+/// 角色设定集中来源为该角色的知识条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SheetBibleEntry {
+    pub entry_type: String,
+    pub title: String,
+    pub content: String,
+    pub keywords: Option<String>,
+    pub importance: i64,
+}
+
+/// 可以在项目之间搬运的角色卡
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CharacterSheet {
+    pub version: u32,
+    pub exported_at: String,
+    pub character: Character,
+    pub aliases: Vec<String>,
+    pub voice_profile: Option<VoiceProfile>,
+    pub tags: Vec<SheetTag>,
+    pub relations: Vec<SheetRelation>,
+    pub timeline: Vec<SheetTimelineEvent>,
+    pub growth: Vec<SheetGrowthRecord>,
+    pub bible: Vec<SheetBibleEntry>,
+}
+
+/// 导入结果，目标项目中找不到对方角色的关系、找不到章节的成长记录会被跳过
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SheetImportResult {
+    pub character_id: String,
+    pub relations_imported: usize,
+    pub skipped_relations: Vec<String>,
+    pub timeline_events: usize,
+    pub growth_records: usize,
+    pub skipped_growth: usize,
+    pub tags: usize,
+    pub bible_entries: usize,
+}
+
+fn collect<T, F>(conn: &Connection, sql: &str, id: &str, map: F) -> Result<Vec<T>, String>
+where
+    F: FnMut(&rusqlite::Row<'_>) -> rusqlite::Result<T>,
+{
+    conn.prepare(sql)
+        .map_err(|e| e.to_string())?
+        .query_map(params![id], map)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+pub fn export_sheet(conn: &Connection, character_id: &str) -> Result<CharacterSheet, String> {
+    let character = conn
+        .query_row(
+            "SELECT id, project_id, name, role_type, race, age, gender, birth_date, appearance, personality, background,
+                    skills, status, bazi, ziwei, mbti, enneagram, items, avatar_url, created_at, updated_at
+             FROM characters WHERE id = ?",
+            params![character_id],
+            |row| {
+                Ok(Character {
+                    id: row.get(0)?,
+                    project_id: row.get(1)?,
+                    name: row.get(2)?,
+                    role_type: row.get(3)?,
+                    race: row.get(4)?,
+                    age: row.get(5)?,
+                    gender: row.get(6)?,
+                    birth_date: row.get(7)?,
+                    appearance: row.get(8)?,
+                    personality: row.get(9)?,
+                    background: row.get(10)?,
+                    skills: row.get(11)?,
+                    status: row.get(12)?,
+                    bazi: row.get(13)?,
+                    ziwei: row.get(14)?,
+                    mbti: row.get(15)?,
+                    enneagram: row.get(16)?,
+                    items: row.get(17)?,
+                    avatar_url: row.get(18)?,
+                    created_at: row.get(19)?,
+                    updated_at: row.get(20)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "角色不存在".to_string())?;
+
+    let aliases = collect(conn, "SELECT alias FROM character_aliases WHERE character_id = ? ORDER BY rowid", character_id, |row| row.get(0))?;
+    let tags = collect(
+        conn,
+        "SELECT tag_type, name, value, description, color, weight, source FROM character_tags WHERE character_id = ? ORDER BY created_at",
+        character_id,
+        |row| {
+            Ok(SheetTag {
+                tag_type: row.get(0)?,
+                name: row.get(1)?,
+                value: row.get(2)?,
+                description: row.get(3)?,
+                color: row.get(4)?,
+                weight: row.get(5)?,
+                source: row.get(6)?,
+            })
+        },
+    )?;
+    let relations = collect(
+        conn,
+        "SELECT CASE WHEN r.from_character_id = ?1 THEN t.name ELSE f.name END,
+                CASE WHEN r.from_character_id = ?1 THEN 'outgoing' ELSE 'incoming' END,
+                r.relation_type, r.description
+         FROM character_relations r
+         JOIN characters f ON f.id = r.from_character_id
+         JOIN characters t ON t.id = r.to_character_id
+         WHERE r.from_character_id = ?1 OR r.to_character_id = ?1
+         ORDER BY r.created_at",
+        character_id,
+        |row| {
+            Ok(SheetRelation {
+                other: row.get(0)?,
+                direction: row.get(1)?,
+                relation_type: row.get(2)?,
+                description: row.get(3)?,
+            })
+        },
+    )?;
+    let timeline = collect(
+        conn,
+        "SELECT e.event_type, e.event_title, e.event_description, e.story_time, c.title, e.emotional_state, e.state_changes, e.sort_order
+         FROM character_timeline_events e LEFT JOIN chapters c ON c.id = e.real_chapter_id
+         WHERE e.character_id = ? ORDER BY e.sort_order, e.created_at",
+        character_id,
+        |row| {
+            Ok(SheetTimelineEvent {
+                event_type: row.get(0)?,
+                event_title: row.get(1)?,
+                event_description: row.get(2)?,
+                story_time: row.get(3)?,
+                chapter_title: row.get(4)?,
+                emotional_state: row.get(5)?,
+                state_changes: row.get(6)?,
+                sort_order: row.get::<_, Option<i64>>(7)?.unwrap_or(0),
+            })
+        },
+    )?;
+    let growth = collect(
+        conn,
+        "SELECT c.title, g.position, g.changes_json, g.notes
+         FROM character_growth_records g JOIN chapters c ON c.id = g.chapter_id
+         WHERE g.character_id = ? ORDER BY c.sort_order, g.position",
+        character_id,
+        |row| {
+            Ok(SheetGrowthRecord {
+                chapter_title: row.get(0)?,
+                position: row.get(1)?,
+                changes: serde_json::from_str(&row.get::<_, String>(2)?).unwrap_or(serde_json::Value::Null),
+                notes: row.get(3)?,
+            })
+        },
+    )?;
+    let bible = collect(
+        conn,
+        "SELECT entry_type, title, content, keywords, importance FROM knowledge_entries
+         WHERE source_type = 'character' AND source_id = ? ORDER BY importance DESC, created_at",
+        character_id,
+        |row| {
+            Ok(SheetBibleEntry {
+                entry_type: row.get(0)?,
+                title: row.get(1)?,
+                content: row.get(2)?,
+                keywords: row.get(3)?,
+                importance: row.get::<_, Option<i64>>(4)?.unwrap_or(0),
+            })
+        },
+    )?;
+
+    Ok(CharacterSheet {
+        version: SHEET_VERSION,
+        exported_at: Utc::now().to_rfc3339(),
+        voice_profile: voice_profile::get_profile(conn, character_id)?,
+        character,
+        aliases,
+        tags,
+        relations,
+        timeline,
+        growth,
+        bible,
+    })
+}
+
+/// Markdown 版角色卡，末尾附带完整 JSON，可以直接再导入
+pub fn sheet_markdown(sheet: &CharacterSheet) -> Result<String, String> {
+    let c = &sheet.character;
+    let mut output = format!("# {}\n", c.name);
+    if !sheet.aliases.is_empty() {
+        output.push_str(&format!("\n别名：{}\n", sheet.aliases.join("、")));
+    }
+    let age = c.age.map(|a| a.to_string());
+    let fields = [
+        ("身份", &c.role_type),
+        ("种族", &c.race),
+        ("年龄", &age),
+        ("性别", &c.gender),
+        ("生日", &c.birth_date),
+        ("外貌", &c.appearance),
+        ("性格", &c.personality),
+        ("背景", &c.background),
+        ("技能", &c.skills),
+        ("状态", &c.status),
+        ("MBTI", &c.mbti),
+        ("九型人格", &c.enneagram),
+        ("物品", &c.items),
+    ];
+    output.push_str("\n## 基本资料\n\n");
+    for (label, value) in fields.iter().filter_map(|(label, value)| value.as_deref().filter(|v| !v.is_empty()).map(|v| (label, v))) {
+        output.push_str(&format!("- **{}**：{}\n", label, value));
+    }
+    if let Some(profile) = &sheet.voice_profile {
+        let section = voice_profile::prompt_section(profile);
+        if !section.is_empty() {
+            output.push_str(&format!("\n## 说话风格\n\n{}\n", section.lines().skip(1).collect::<Vec<_>>().join("\n")));
+        }
+    }
+    if !sheet.tags.is_empty() {
+        output.push_str("\n## 标签\n\n");
+        for tag in &sheet.tags {
+            output.push_str(&format!("- {}（{}）\n", tag.name, tag.tag_type));
+        }
+    }
+    if !sheet.relations.is_empty() {
+        output.push_str("\n## 关系\n\n");
+        for relation in &sheet.relations {
+            let arrow = if relation.direction == "outgoing" { "→" } else { "←" };
+            let description = relation.description.as_deref().map(|d| format!("：{}", d)).unwrap_or_default();
+            output.push_str(&format!("- {} {}（{}）{}\n", arrow, relation.other, relation.relation_type, description));
+        }
+    }
+    if !sheet.timeline.is_empty() {
+        output.push_str("\n## 时间线\n\n");
+        for event in &sheet.timeline {
+            let when = event.story_time.as_deref().or(event.chapter_title.as_deref()).map(|w| format!("[{}] ", w)).unwrap_or_default();
+            let description = event.event_description.as_deref().map(|d| format!("：{}", d)).unwrap_or_default();
+            output.push_str(&format!("- {}{}{}\n", when, event.event_title, description));
+        }
+    }
+    if !sheet.growth.is_empty() {
+        output.push_str("\n## 成长记录\n\n");
+        for record in &sheet.growth {
+            let notes = record.notes.as_deref().filter(|n| !n.is_empty()).map(|n| format!("：{}", n)).unwrap_or_default();
+            output.push_str(&format!("- {}{}\n", record.chapter_title, notes));
+        }
+    }
+    if !sheet.bible.is_empty() {
+        output.push_str("\n## 设定\n");
+        for entry in &sheet.bible {
+            output.push_str(&format!("\n### {}\n\n{}\n", entry.title, entry.content));
+        }
+    }
+    let json = serde_json::to_string_pretty(sheet).map_err(|e| e.to_string())?;
+    output.push_str(&format!("\n<!-- 角色卡数据，导入时使用 -->\n```json\n{}\n```\n", json));
+    Ok(output)
+}
+
+/// 解析 JSON 角色卡，或 `sheet_markdown` 导出的 Markdown 中附带的 JSON
+pub fn parse_sheet(text: &str) -> Result<CharacterSheet, String> {
+    let json = match text.find("```json") {
+        Some(start) => {
+            let body = &text[start + "```json".len()..];
+            &body[..body.find("```").ok_or_else(|| "角色卡数据不完整".to_string())?]
+        }
+        None => text,
+    };
+    let sheet: CharacterSheet = serde_json::from_str(json.trim()).map_err(|e| format!("无法解析角色卡: {}", e))?;
+    if sheet.version > SHEET_VERSION {
+        return Err(format!("角色卡版本 {} 高于当前支持的版本 {}", sheet.version, SHEET_VERSION));
+    }
+    Ok(sheet)
+}
+
+/// 把角色卡导入到项目中，作为一个新角色；项目中已有同名角色时拒绝导入
+pub fn import_sheet(conn: &Connection, project_id: &str, sheet: &CharacterSheet) -> Result<SheetImportResult, String> {
+    let c = &sheet.character;
+    let exists: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM characters WHERE project_id = ? AND name = ? AND merged_into IS NULL)",
+            params![project_id, c.name],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if exists {
+        return Err(format!("项目中已有角色 {}", c.name));
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let character_id = Uuid::new_v4().to_string();
+    tx.execute(
+        "INSERT INTO characters (id, project_id, name, role_type, race, age, gender, birth_date, appearance, personality, background,
+                                 skills, status, bazi, ziwei, mbti, enneagram, items, avatar_url, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?20)",
+        params![
+            character_id, project_id, c.name, c.role_type, c.race, c.age, c.gender, c.birth_date, c.appearance, c.personality,
+            c.background, c.skills, c.status, c.bazi, c.ziwei, c.mbti, c.enneagram, c.items, c.avatar_url, now,
+        ],
+    )
+    .map_err(|e| format!("Failed to create character: {}", e))?;
+    let mut result = SheetImportResult {
+        character_id: character_id.clone(),
+        relations_imported: 0,
+        skipped_relations: Vec::new(),
+        timeline_events: 0,
+        growth_records: 0,
+        skipped_growth: 0,
+        tags: 0,
+        bible_entries: 0,
+    };
+
+    for alias in &sheet.aliases {
+        tx.execute("INSERT OR IGNORE INTO character_aliases (character_id, alias) VALUES (?, ?)", params![character_id, alias])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(profile) = &sheet.voice_profile {
+        voice_profile::save_profile(&tx, &VoiceProfile { character_id: character_id.clone(), ..profile.clone() })?;
+    }
+    for tag in &sheet.tags {
+        tx.execute(
+            "INSERT INTO character_tags (id, character_id, tag_type, name, value, description, color, weight, auto_assigned, source, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 0, ?9, ?10, ?10)",
+            params![Uuid::new_v4().to_string(), character_id, tag.tag_type, tag.name, tag.value, tag.description, tag.color, tag.weight, tag.source, now],
+        )
+        .map_err(|e| e.to_string())?;
+        result.tags += 1;
+    }
+    for relation in &sheet.relations {
+        let other: Option<String> = tx
+            .query_row(
+                "SELECT id FROM characters WHERE project_id = ?1 AND merged_into IS NULL AND id != ?3
+                   AND (name = ?2 OR id IN (SELECT character_id FROM character_aliases WHERE alias = ?2))
+                 ORDER BY name = ?2 DESC LIMIT 1",
+                params![project_id, relation.other, character_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let Some(other) = other else {
+            result.skipped_relations.push(relation.other.clone());
+            continue;
+        };
+        let (from, to) = if relation.direction == "outgoing" { (&character_id, &other) } else { (&other, &character_id) };
+        result.relations_imported += tx
+            .execute(
+                "INSERT OR IGNORE INTO character_relations (id, project_id, from_character_id, to_character_id, relation_type, description, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+                params![Uuid::new_v4().to_string(), project_id, from, to, relation.relation_type, relation.description, now],
+            )
+            .map_err(|e| e.to_string())?;
+    }
+    let chapter_by_title = |title: &str| -> Result<Option<String>, String> {
+        tx.query_row(
+            "SELECT id FROM chapters WHERE project_id = ? AND title = ? ORDER BY sort_order LIMIT 1",
+            params![project_id, title],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())
+    };
+    for event in &sheet.timeline {
+        let chapter_id = match &event.chapter_title {
+            Some(title) => chapter_by_title(title)?,
+            None => None,
+        };
+        tx.execute(
+            "INSERT INTO character_timeline_events (id, character_id, event_type, event_title, event_description, story_time,
+                                                    real_chapter_id, emotional_state, state_changes, sort_order, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?11)",
+            params![
+                Uuid::new_v4().to_string(), character_id, event.event_type, event.event_title, event.event_description,
+                event.story_time, chapter_id, event.emotional_state, event.state_changes, event.sort_order, now,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        result.timeline_events += 1;
+    }
+    for record in &sheet.growth {
+        let Some(chapter_id) = chapter_by_title(&record.chapter_title)? else {
+            result.skipped_growth += 1;
+            continue;
+        };
+        tx.execute(
+            "INSERT INTO character_growth_records (id, character_id, chapter_id, position, changes_json, auto_detected, notes, created_at)
+             VALUES (?, ?, ?, ?, ?, 0, ?, ?)",
+            params![Uuid::new_v4().to_string(), character_id, chapter_id, record.position, record.changes.to_string(), record.notes, now],
+        )
+        .map_err(|e| e.to_string())?;
+        result.growth_records += 1;
+    }
+    for entry in &sheet.bible {
+        tx.execute(
+            "INSERT INTO knowledge_entries (id, project_id, entry_type, title, content, source_type, source_id, keywords, importance, is_verified, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, 'character', ?6, ?7, ?8, 0, ?9, ?9)",
+            params![Uuid::new_v4().to_string(), project_id, entry.entry_type, entry.title, entry.content, character_id, entry.keywords, entry.importance, now],
+        )
+        .map_err(|e| e.to_string())?;
+        result.bible_entries += 1;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    let mut words = vec![c.name.clone()];
+    words.extend(sheet.aliases.iter().cloned());
+    crate::tokenizer::add_words(&words);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sheet_round_trip_between_projects() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO projects (id, name, created_at, updated_at) VALUES ('p2', '续集', 't0', 't0');
+             INSERT INTO characters (id, project_id, name, personality, created_at, updated_at) VALUES
+                 ('r1', 'p1', '林舟', '沉默寡言', 't0', 't0'), ('r2', 'p1', '沈青', NULL, 't0', 't0'),
+                 ('r3', 'p1', '阿七', NULL, 't0', 't0'), ('r9', 'p2', '沈青', NULL, 't0', 't0');
+             INSERT INTO character_aliases (character_id, alias) VALUES ('r1', '小舟');
+             INSERT INTO character_relations (id, project_id, from_character_id, to_character_id, relation_type, created_at, updated_at) VALUES
+                 ('x1', 'p1', 'r2', 'r1', 'mentor', 't0', 't0'), ('x2', 'p1', 'r1', 'r3', 'friend', 't0', 't0');
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '', 1, 't0', 't0'), ('c2', 'p1', '第二章', '', 2, 't0', 't0'),
+                 ('c9', 'p2', '第一章', '', 1, 't0', 't0');
+             INSERT INTO character_growth_records (id, character_id, chapter_id, position, changes_json, created_at) VALUES
+                 ('g1', 'r1', 'c1', 0, '[]', 't0'), ('g2', 'r1', 'c2', 0, '[]', 't0');
+             INSERT INTO knowledge_entries (id, project_id, entry_type, title, content, source_type, source_id, created_at, updated_at) VALUES
+                 ('k1', 'p1', 'character', '林舟的剑', '一把断剑', 'character', 'r1', 't0', 't0');",
+        )
+        .unwrap();
+
+        let markdown = sheet_markdown(&export_sheet(&conn, "r1").unwrap()).unwrap();
+        assert!(markdown.starts_with("# 林舟\n\n别名：小舟\n"));
+        assert!(markdown.contains("- ← 沈青（mentor）\n"));
+
+        let sheet = parse_sheet(&markdown).unwrap();
+        let result = import_sheet(&conn, "p2", &sheet).unwrap();
+        assert_eq!(result.relations_imported, 1);
+        assert_eq!(result.skipped_relations, vec!["阿七"]);
+        assert_eq!((result.growth_records, result.skipped_growth, result.bible_entries), (1, 1, 1));
+        let mentor: String = conn
+            .query_row("SELECT from_character_id FROM character_relations WHERE to_character_id = ?", params![result.character_id], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mentor, "r9");
+        assert!(import_sheet(&conn, "p2", &sheet).is_err());
+    }
+}
@@ -0,0 +1,1273 @@
+use super::provider::{is_offline_error, SyncProvider};
+use super::{ConflictResolutionStrategy, SyncConflict, SyncDataClass, SyncFileResult, SyncResult, SyncSelection};
+use crate::text_merge::{merge_three_way, resolve_merge, HunkResolution, MergeResult};
+use base64::Engine as _;
+use chrono::Utc;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+
+/// 按项目同步的数据表及其数据类型，每行对应一条同步记录 `{table}/{id}.json`
+pub const SYNC_TABLES: &[(&str, SyncDataClass)] = &[
+    ("chapters", SyncDataClass::Manuscript),
+    ("characters", SyncDataClass::Manuscript),
+    ("world_views", SyncDataClass::Manuscript),
+    ("plot_points", SyncDataClass::Manuscript),
+    ("character_relations", SyncDataClass::Manuscript),
+    ("plot_nodes", SyncDataClass::Manuscript),
+    ("foreshadowings", SyncDataClass::Manuscript),
+    ("project_snapshots", SyncDataClass::Snapshots),
+    ("version_diffs", SyncDataClass::Snapshots),
+    ("script_scenes", SyncDataClass::Media),
+    ("character_bibles", SyncDataClass::Media),
+    ("blueprints", SyncDataClass::Settings),
+];
+
+const PROJECT_FILE: &str = "project.json";
+const JOURNAL_DIR: &str = "journal";
+/// 单个变更批次最多包含的记录数
+const MAX_BATCH_CHANGES: usize = 200;
+const DEVICE_ID_KEY: &str = "sync_device_id";
+
+/// 一条记录在某一侧的版本；hash 为 None 表示不存在或已删除，此时 updated_at 为删除时间
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileState {
+    pub hash: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalRecord {
+    pub row: Map<String, Value>,
+    pub state: FileState,
+}
+
+/// 变更日志中的一条记录，data 为空表示删除（墓碑）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalChange {
+    pub path: String,
+    #[serde(flatten)]
+    pub state: FileState,
+    pub data: Option<Map<String, Value>>,
+}
+
+/// 远端 `projects/{project_id}/journal/{seq}-{device_id}.json` 中保存的一批变更
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalBatch {
+    pub device_id: String,
+    pub created_at: String,
+    pub changes: Vec<JournalChange>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncAction {
+    Unchanged,
+    Push,
+    Pull,
+    DeleteRemote,
+    DeleteLocal,
+    /// 两侧修改已自动合并，合并结果写入本地并上传
+    Merged,
+    Conflict,
+}
+
+/// 同步路径所属的数据类型；`project.json` 是其他数据的父记录，始终随正文同步
+pub fn data_class(path: &str) -> SyncDataClass {
+    let table = path.split_once('/').map_or(path, |(table, _)| table);
+    SYNC_TABLES
+        .iter()
+        .find(|(t, _)| *t == table)
+        .map_or(SyncDataClass::Manuscript, |(_, class)| *class)
+}
+
+pub fn project_dir(project_id: &str) -> String {
+    format!("projects/{}", project_id)
+}
+
+fn hash_bytes(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 以上次同步的哈希为基线判断哪一侧发生了修改；两侧都改了才按冲突策略处理
+pub fn plan_file_action(
+    local: &FileState,
+    remote: &FileState,
+    base: Option<&str>,
+    strategy: ConflictResolutionStrategy,
+) -> SyncAction {
+    if local.hash == remote.hash {
+        return SyncAction::Unchanged;
+    }
+
+    let take_local = if local.hash.is_some() { SyncAction::Push } else { SyncAction::DeleteRemote };
+    let take_remote = if remote.hash.is_some() { SyncAction::Pull } else { SyncAction::DeleteLocal };
+    let local_changed = local.hash.as_deref() != base;
+    let remote_changed = remote.hash.as_deref() != base;
+    match (local_changed, remote_changed) {
+        (true, false) => take_local,
+        (false, true) => take_remote,
+        _ => match strategy {
+            ConflictResolutionStrategy::PreferLocal => take_local,
+            ConflictResolutionStrategy::PreferRemote => take_remote,
+            // 删除以墓碑中的删除时间参与比较
+            ConflictResolutionStrategy::TimestampBased => {
+                if local.updated_at >= remote.updated_at {
+                    take_local
+                } else {
+                    take_remote
+                }
+            }
+            ConflictResolutionStrategy::AskUser | ConflictResolutionStrategy::Merge => SyncAction::Conflict,
+        },
+    }
+}
+
+fn sql_to_json(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => Value::from(f),
+        ValueRef::Text(t) => Value::String(String::from_utf8_lossy(t).into_owned()),
+        ValueRef::Blob(b) => Value::String(base64::engine::general_purpose::STANDARD.encode(b)),
+    }
+}
+
+fn json_to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+fn query_rows(conn: &Connection, sql: &str, project_id: &str) -> Result<Vec<Map<String, Value>>, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let names: Vec<String> = stmt.column_names().iter().map(|n| n.to_string()).collect();
+    let rows = stmt
+        .query_map(params![project_id], |row| {
+            let mut object = Map::new();
+            for (i, name) in names.iter().enumerate() {
+                object.insert(name.clone(), sql_to_json(row.get_ref(i)?));
+            }
+            Ok(object)
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rows)
+}
+
+fn record_state(row: &Map<String, Value>) -> Result<FileState, String> {
+    let data = serde_json::to_vec(row).map_err(|e| e.to_string())?;
+    Ok(FileState {
+        hash: Some(hash_bytes(&data)),
+        updated_at: row
+            .get("updated_at")
+            .or_else(|| row.get("created_at"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+    })
+}
+
+/// 读取项目的全部同步记录：`project.json` 加每张表每行一条
+pub fn export_project_files(conn: &Connection, project_id: &str) -> Result<BTreeMap<String, LocalRecord>, String> {
+    let mut files = BTreeMap::new();
+    for row in query_rows(conn, "SELECT * FROM projects WHERE id = ?", project_id)? {
+        let state = record_state(&row)?;
+        files.insert(PROJECT_FILE.to_string(), LocalRecord { row, state });
+    }
+    for (table, _) in SYNC_TABLES {
+        for mut row in query_rows(conn, &format!("SELECT * FROM {} WHERE project_id = ?", table), project_id)? {
+            if *table == "project_snapshots" {
+                crate::snapshot_store::inline_sync_row(conn, &mut row)?;
+            }
+            let Some(id) = row.get("id").and_then(|v| v.as_str()).map(str::to_string) else { continue };
+            let state = record_state(&row)?;
+            files.insert(format!("{}/{}.json", table, id), LocalRecord { row, state });
+        }
+    }
+    Ok(files)
+}
+
+/// 同步记录路径对应的表和行 ID
+fn resolve_path(path: &str) -> Result<(&'static str, Option<String>), String> {
+    if path == PROJECT_FILE {
+        return Ok(("projects", None));
+    }
+    let (table, file) = path.split_once('/').ok_or_else(|| format!("无效的同步路径: {}", path))?;
+    let (table, _) = SYNC_TABLES
+        .iter()
+        .find(|(t, _)| *t == table)
+        .ok_or_else(|| format!("未知的同步数据: {}", table))?;
+    let id = file.strip_suffix(".json").ok_or_else(|| format!("无效的同步路径: {}", path))?;
+    Ok((table, Some(id.to_string())))
+}
+
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table)).map_err(|e| e.to_string())?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(columns)
+}
+
+/// 把远端记录写入本地数据库；只写入本地表中存在的列，兼容不同版本的表结构
+pub fn apply_remote_file(conn: &Connection, project_id: &str, path: &str, row: &Map<String, Value>) -> Result<(), String> {
+    let (table, id) = resolve_path(path)?;
+    let row_id = row.get("id").and_then(|v| v.as_str()).ok_or_else(|| format!("{} 缺少 id", path))?;
+    let owner = if table == "projects" { Some(row_id) } else { row.get("project_id").and_then(|v| v.as_str()) };
+    if owner != Some(project_id) || id.as_deref().is_some_and(|id| id != row_id) {
+        return Err(format!("{} 不属于项目 {}", path, project_id));
+    }
+
+    let columns: Vec<String> = table_columns(conn, table)?.into_iter().filter(|c| row.contains_key(c)).collect();
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    let updates: Vec<String> = columns.iter().filter(|c| *c != "id").map(|c| format!("{} = excluded.{}", c, c)).collect();
+    let sql = if updates.is_empty() {
+        format!("INSERT OR IGNORE INTO {} ({}) VALUES ({})", table, columns.join(", "), placeholders)
+    } else {
+        // 不用 INSERT OR REPLACE：替换项目行会级联删除它的章节
+        format!(
+            "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT(id) DO UPDATE SET {}",
+            table,
+            columns.join(", "),
+            placeholders,
+            updates.join(", ")
+        )
+    };
+    let values: Vec<SqlValue> = columns.iter().map(|c| json_to_sql(&row[c])).collect();
+    conn.execute(&sql, params_from_iter(values)).map_err(|e| format!("写入 {} 失败: {}", path, e))?;
+    Ok(())
+}
+
+pub fn delete_local_file(conn: &Connection, project_id: &str, path: &str) -> Result<(), String> {
+    match resolve_path(path)? {
+        (table, Some(id)) => conn.execute(&format!("DELETE FROM {} WHERE id = ? AND project_id = ?", table), params![id, project_id]),
+        (_, None) => conn.execute("DELETE FROM projects WHERE id = ?", params![project_id]),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn load_base_state(conn: &Connection, provider: &str, project_id: &str) -> Result<HashMap<String, FileState>, String> {
+    let mut stmt = conn
+        .prepare("SELECT path, hash, updated_at FROM sync_file_state WHERE provider = ? AND project_id = ?")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![provider, project_id], |row| {
+            Ok((row.get::<_, String>(0)?, FileState { hash: Some(row.get(1)?), updated_at: row.get(2)? }))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<HashMap<_, _>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rows)
+}
+
+/// 保存同步基线。章节同时保存记录内容，作为三方合并时的共同祖先
+fn save_base_state(
+    conn: &Connection,
+    provider: &str,
+    project_id: &str,
+    base: &HashMap<String, FileState>,
+    rows: &HashMap<String, Map<String, Value>>,
+) -> Result<(), String> {
+    let existing: Vec<String> = {
+        let mut stmt = conn
+            .prepare("SELECT path FROM sync_file_state WHERE provider = ? AND project_id = ?")
+            .map_err(|e| e.to_string())?;
+        let paths = stmt
+            .query_map(params![provider, project_id], |row| row.get::<_, String>(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        paths
+    };
+    for path in existing.iter().filter(|p| base.get(*p).is_none_or(|s| s.hash.is_none())) {
+        conn.execute(
+            "DELETE FROM sync_file_state WHERE provider = ? AND project_id = ? AND path = ?",
+            params![provider, project_id, path],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    upsert_base_entries(conn, provider, project_id, base, rows)
+}
+
+fn upsert_base_entries(
+    conn: &Connection,
+    provider: &str,
+    project_id: &str,
+    base: &HashMap<String, FileState>,
+    rows: &HashMap<String, Map<String, Value>>,
+) -> Result<(), String> {
+    let now = Utc::now().to_rfc3339();
+    for (path, state) in base {
+        let Some(hash) = &state.hash else { continue };
+        let data = match rows.get(path) {
+            Some(row) if is_mergeable(path) => Some(serde_json::to_string(row).map_err(|e| e.to_string())?),
+            _ => None,
+        };
+        conn.execute(
+            "INSERT INTO sync_file_state (provider, project_id, path, hash, updated_at, synced_at, data) VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(provider, project_id, path) DO UPDATE SET
+                 hash = excluded.hash, updated_at = excluded.updated_at, synced_at = excluded.synced_at,
+                 data = COALESCE(excluded.data, sync_file_state.data)",
+            params![provider, project_id, path, hash, state.updated_at, now, data],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// 只有章节按正文做三方合并
+fn is_mergeable(path: &str) -> bool {
+    path.starts_with("chapters/")
+}
+
+fn load_base_row(conn: &Connection, provider: &str, project_id: &str, path: &str) -> Result<Option<Map<String, Value>>, String> {
+    let data: Option<Option<String>> = conn
+        .query_row(
+            "SELECT data FROM sync_file_state WHERE provider = ? AND project_id = ? AND path = ?",
+            params![provider, project_id, path],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match data.flatten() {
+        Some(json) => serde_json::from_str(&json).map(Some).map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// 未解决的冲突，保存远端版本以免对应批次被标记为已应用后丢失
+#[derive(Debug, Clone)]
+pub struct PendingConflict {
+    pub provider: String,
+    pub project_id: String,
+    pub path: String,
+    pub remote: JournalChange,
+}
+
+fn pending_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<PendingConflict> {
+    let path: String = row.get(2)?;
+    let data: Option<String> = row.get(5)?;
+    Ok(PendingConflict {
+        provider: row.get(0)?,
+        project_id: row.get(1)?,
+        remote: JournalChange {
+            path: path.clone(),
+            state: FileState { hash: row.get(3)?, updated_at: row.get(4)? },
+            data: data.and_then(|d| serde_json::from_str(&d).ok()),
+        },
+        path,
+    })
+}
+
+const PENDING_COLUMNS: &str = "provider, project_id, path, remote_hash, remote_updated_at, remote_data";
+
+fn load_pending_conflicts(conn: &Connection, provider: &str, project_id: &str) -> Result<HashMap<String, PendingConflict>, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM sync_conflicts WHERE provider = ? AND project_id = ?", PENDING_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![provider, project_id], pending_from_row)
+        .map_err(|e| e.to_string())?
+        .map(|r| r.map(|c| (c.path.clone(), c)))
+        .collect::<Result<HashMap<_, _>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rows)
+}
+
+pub fn load_pending_conflict(conn: &Connection, conflict_id: &str) -> Result<PendingConflict, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM sync_conflicts WHERE id = ?", PENDING_COLUMNS),
+        params![conflict_id],
+        pending_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("冲突不存在: {}", conflict_id))
+}
+
+/// 列出待解决的冲突（含逐段合并报告），`project_id` 为空时列出全部
+pub fn list_pending_conflicts(conn: &Connection, project_id: Option<&str>) -> Result<Vec<SyncConflict>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, project_id, path, conflict_type, remote_updated_at, merge FROM sync_conflicts
+             WHERE ?1 IS NULL OR project_id = ?1 ORDER BY created_at",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![project_id], |row| {
+            let merge: Option<String> = row.get(5)?;
+            Ok(SyncConflict {
+                conflict_id: Some(row.get(0)?),
+                project_id: row.get(1)?,
+                file_path: row.get(2)?,
+                conflict_type: row.get(3)?,
+                local_updated_at: None,
+                remote_updated_at: row.get(4)?,
+                merge: merge.and_then(|m| serde_json::from_str(&m).ok()),
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rows)
+}
+
+/// 记录或更新冲突，返回冲突 ID
+fn save_pending_conflict(
+    conn: &Connection,
+    provider: &str,
+    project_id: &str,
+    conflict: &SyncConflict,
+    remote: &JournalChange,
+) -> Result<String, String> {
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT id FROM sync_conflicts WHERE provider = ? AND project_id = ? AND path = ?",
+            params![provider, project_id, conflict.file_path],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let id = existing.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let remote_data = remote.data.as_ref().map(serde_json::to_string).transpose().map_err(|e| e.to_string())?;
+    let merge = conflict.merge.as_ref().map(serde_json::to_string).transpose().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO sync_conflicts (id, provider, project_id, path, conflict_type, remote_hash, remote_updated_at, remote_data, merge, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            id,
+            provider,
+            project_id,
+            conflict.file_path,
+            conflict.conflict_type,
+            remote.state.hash,
+            remote.state.updated_at,
+            remote_data,
+            merge,
+            Utc::now().to_rfc3339(),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// 字段级三方合并章节记录：只有一侧修改的字段取修改后的值，两侧都改的字段以本地为准，
+/// 正文按段落合并
+pub fn merge_chapter_rows(
+    base: &Map<String, Value>,
+    local: &Map<String, Value>,
+    remote: &Map<String, Value>,
+) -> (Map<String, Value>, MergeResult) {
+    let text = |row: &Map<String, Value>| row.get("content").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+    let content = merge_three_way(&text(base), &text(local), &text(remote));
+
+    let mut merged = local.clone();
+    for (key, remote_value) in remote {
+        if local.get(key) == base.get(key) {
+            merged.insert(key.clone(), remote_value.clone());
+        }
+    }
+    merged.insert("content".to_string(), Value::String(content.merged.clone()));
+    merged.insert("word_count".to_string(), Value::from(content.merged.chars().count() as i64));
+    merged.insert("updated_at".to_string(), Value::String(Utc::now().to_rfc3339()));
+    (merged, content)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "strategy")]
+pub enum ConflictChoice {
+    Local,
+    Remote,
+    /// 按段落处理合并冲突
+    Merge { resolutions: Vec<HunkResolution> },
+}
+
+/// 解决冲突并写入本地数据库。基线更新为远端版本，下次同步时解决结果会作为本地修改上传。
+/// 返回最终的记录，记录被删除时返回 None
+pub fn resolve_pending_conflict(
+    conn: &Connection,
+    conflict_id: &str,
+    choice: &ConflictChoice,
+) -> Result<Option<Map<String, Value>>, String> {
+    let pending = load_pending_conflict(conn, conflict_id)?;
+    let (project_id, path) = (pending.project_id.as_str(), pending.path.as_str());
+    let local = export_project_files(conn, project_id)?.remove(path).map(|r| r.row);
+
+    let result = match choice {
+        ConflictChoice::Local => local.clone(),
+        ConflictChoice::Remote => pending.remote.data.clone(),
+        ConflictChoice::Merge { resolutions } => {
+            let (Some(local_row), Some(remote_row)) = (&local, &pending.remote.data) else {
+                return Err("删除冲突只能选择保留本地或远端".to_string());
+            };
+            let base = load_base_row(conn, &pending.provider, project_id, path)?.unwrap_or_default();
+            let (mut merged, content) = merge_chapter_rows(&base, local_row, remote_row);
+            let text = resolve_merge(&content, resolutions)?;
+            merged.insert("word_count".to_string(), Value::from(text.chars().count() as i64));
+            merged.insert("content".to_string(), Value::String(text));
+            Some(merged)
+        }
+    };
+
+    match &result {
+        Some(row) if Some(row) != local.as_ref() => {
+            let mut row = row.clone();
+            row.insert("updated_at".to_string(), Value::String(Utc::now().to_rfc3339()));
+            apply_remote_file(conn, project_id, path, &row)?;
+        }
+        Some(_) => {}
+        None => delete_local_file(conn, project_id, path)?,
+    }
+
+    let mut base = HashMap::new();
+    let mut rows = HashMap::new();
+    if let Some(row) = &pending.remote.data {
+        rows.insert(path.to_string(), row.clone());
+    }
+    match &pending.remote.state.hash {
+        Some(_) => {
+            base.insert(path.to_string(), pending.remote.state.clone());
+            upsert_base_entries(conn, &pending.provider, project_id, &base, &rows)?;
+        }
+        None => {
+            conn.execute(
+                "DELETE FROM sync_file_state WHERE provider = ? AND project_id = ? AND path = ?",
+                params![pending.provider, project_id, path],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    conn.execute("DELETE FROM sync_conflicts WHERE id = ?", params![conflict_id]).map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+/// 本地删除记录的时间（由删除触发器写入 sync_tombstones）
+fn load_tombstones(conn: &Connection, project_id: &str) -> Result<HashMap<String, String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT table_name, row_id, deleted_at FROM sync_tombstones WHERE project_id = ?")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![project_id], |row| {
+            let (table, id, deleted_at): (String, String, String) = (row.get(0)?, row.get(1)?, row.get(2)?);
+            let path = if table == "projects" { PROJECT_FILE.to_string() } else { format!("{}/{}.json", table, id) };
+            Ok((path, deleted_at))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<HashMap<_, _>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rows)
+}
+
+fn load_applied_batches(conn: &Connection, provider: &str, project_id: &str) -> Result<HashSet<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT batch FROM sync_applied_batches WHERE provider = ? AND project_id = ?")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![provider, project_id], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<HashSet<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rows)
+}
+
+/// 清空已应用批次的记录，下次同步时重新读取全部远端批次。
+/// 新选中的数据类型此前被跳过的远端变更借此补上；已同步的记录与基线一致，不会重复应用
+pub fn reset_applied_batches(conn: &Connection) -> Result<(), String> {
+    conn.execute("DELETE FROM sync_applied_batches", []).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 离线队列中的记录及其排队时间
+fn load_outbox(conn: &Connection, provider: &str, project_id: &str) -> Result<HashMap<String, String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT path, queued_at FROM sync_outbox WHERE provider = ? AND project_id = ?")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![provider, project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<HashMap<_, _>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rows)
+}
+
+/// 离线队列中有变更的项目，按最早排队时间排序
+fn outbox_project_ids(conn: &Connection, provider: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT project_id FROM sync_outbox WHERE provider = ? GROUP BY project_id ORDER BY MIN(queued_at)")
+        .map_err(|e| e.to_string())?;
+    let ids = stmt
+        .query_map(params![provider], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(ids)
+}
+
+pub fn outbox_len(conn: &Connection, provider: &str) -> Result<usize, String> {
+    conn.query_row("SELECT COUNT(*) FROM sync_outbox WHERE provider = ?", params![provider], |row| row.get::<_, i64>(0))
+        .map(|n| n as usize)
+        .map_err(|e| e.to_string())
+}
+
+/// 把本地相对基线的变更记入离线队列，返回队列中的记录总数。
+/// 已排队的记录保留最初的排队时间，变更已撤销（与基线一致）的记录移出队列
+pub fn queue_offline_changes(
+    conn: &Connection,
+    provider: &str,
+    selection: &SyncSelection,
+    project_ids: Option<&[String]>,
+) -> Result<usize, String> {
+    let project_ids = match project_ids {
+        Some(ids) => ids.to_vec(),
+        None => local_project_ids(conn)?,
+    };
+    let now = Utc::now().to_rfc3339();
+    for project_id in project_ids.iter().filter(|id| selection.includes_project(id)) {
+        let local = export_project_files(conn, project_id)?;
+        let base = load_base_state(conn, provider, project_id)?;
+        let tombstones = load_tombstones(conn, project_id)?;
+        let mut changed = HashSet::new();
+        for path in local.keys().chain(base.keys()).filter(|p| selection.includes(data_class(p))) {
+            let state = match local.get(path) {
+                Some(record) => record.state.clone(),
+                None => FileState { hash: None, updated_at: tombstones.get(path).cloned() },
+            };
+            if state.hash == base.get(path).and_then(|s| s.hash.clone()) || !changed.insert(path.clone()) {
+                continue;
+            }
+            conn.execute(
+                "INSERT INTO sync_outbox (provider, project_id, path, hash, updated_at, queued_at) VALUES (?, ?, ?, ?, ?, ?)
+                 ON CONFLICT(provider, project_id, path) DO UPDATE SET hash = excluded.hash, updated_at = excluded.updated_at",
+                params![provider, project_id, path, state.hash, state.updated_at, now],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        for path in load_outbox(conn, provider, project_id)?.into_keys().filter(|p| !changed.contains(p)) {
+            conn.execute(
+                "DELETE FROM sync_outbox WHERE provider = ? AND project_id = ? AND path = ?",
+                params![provider, project_id, path],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    outbox_len(conn, provider)
+}
+
+/// 本机的同步设备 ID，首次同步时生成
+pub fn device_id(conn: &Connection) -> Result<String, String> {
+    let saved: Option<String> = conn
+        .query_row("SELECT value FROM app_settings WHERE key = ?", params![DEVICE_ID_KEY], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some(id) = saved {
+        return Ok(id);
+    }
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    conn.execute(
+        "INSERT OR REPLACE INTO app_settings (key, value, updated_at) VALUES (?, ?, ?)",
+        params![DEVICE_ID_KEY, id, Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// 批次文件名 `{seq}-{device_id}.json` 中的序号
+fn batch_seq(name: &str) -> Option<u64> {
+    name.split('-').next()?.parse().ok()
+}
+
+fn open(db_path: &Path) -> Result<Connection, String> {
+    crate::database::get_connection(db_path).map_err(|e| format!("Failed to get database connection: {}", e))
+}
+
+fn local_project_ids(conn: &Connection) -> Result<Vec<String>, String> {
+    let mut stmt = conn.prepare("SELECT id FROM projects ORDER BY created_at").map_err(|e| e.to_string())?;
+    let ids = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(ids)
+}
+
+/// 同步指定项目；`project_ids` 为空时同步本地和远端的所有项目。
+/// 只同步 `selection` 中选中的项目和数据类型；单个项目失败不影响其他项目，错误记录在结果中
+pub async fn run_sync(
+    db_path: &Path,
+    provider: &dyn SyncProvider,
+    strategy: ConflictResolutionStrategy,
+    selection: &SyncSelection,
+    project_ids: Option<Vec<String>>,
+) -> Result<SyncResult, String> {
+    let provider_key = provider.name();
+    if let Err(e) = provider.check().await {
+        if !is_offline_error(&e) {
+            return Err(e);
+        }
+        let conn = open(db_path)?;
+        let queued_changes = queue_offline_changes(&conn, &provider_key, selection, project_ids.as_deref())?;
+        return Ok(SyncResult { offline: true, queued_changes, ..Default::default() });
+    }
+
+    // 离线队列中的项目按排队顺序最先同步
+    let queued = outbox_project_ids(&open(db_path)?, &provider_key)?;
+    let mut project_ids = match project_ids {
+        Some(ids) => ids,
+        None => {
+            let mut ids = queued.clone();
+            ids.extend(local_project_ids(&open(db_path)?)?);
+            for entry in provider.list("projects").await? {
+                if entry.is_dir {
+                    ids.push(entry.path.trim_start_matches("projects/").to_string());
+                }
+            }
+            ids
+        }
+    };
+    project_ids.sort_by_key(|id| queued.iter().position(|q| q == id).unwrap_or(usize::MAX));
+    let mut seen = HashSet::new();
+    project_ids.retain(|id| selection.includes_project(id) && seen.insert(id.clone()));
+
+    let mut result = SyncResult { success: true, ..Default::default() };
+    for (i, project_id) in project_ids.iter().enumerate() {
+        if let Err(e) = sync_project(db_path, provider, strategy, selection, project_id, &mut result).await {
+            result.success = false;
+            // 同步中途断网：剩余项目的变更转入离线队列
+            if is_offline_error(&e) {
+                result.offline = true;
+                queue_offline_changes(&open(db_path)?, &provider_key, selection, Some(&project_ids[i..]))?;
+            }
+            result.files.push(SyncFileResult {
+                project_id: project_id.clone(),
+                path: String::new(),
+                action: SyncAction::Unchanged,
+                error: Some(e),
+            });
+            if result.offline {
+                break;
+            }
+        }
+    }
+    if !result.offline {
+        if let Err(e) = provider.finish(db_path, &project_ids).await {
+            result.success = false;
+            result.offline = is_offline_error(&e);
+            result.files.push(SyncFileResult {
+                project_id: String::new(),
+                path: String::new(),
+                action: SyncAction::Unchanged,
+                error: Some(e),
+            });
+        }
+    }
+    result.queued_changes = outbox_len(&open(db_path)?, &provider_key)?;
+    result.synced_files = result
+        .files
+        .iter()
+        .filter(|f| f.error.is_none() && f.action != SyncAction::Conflict && !f.path.is_empty())
+        .map(|f| format!("{}/{}", f.project_id, f.path))
+        .collect();
+    Ok(result)
+}
+
+/// 增量同步一个项目：只下载未应用过的远端变更批次，只上传相对基线有变化的记录
+async fn sync_project(
+    db_path: &Path,
+    provider: &dyn SyncProvider,
+    strategy: ConflictResolutionStrategy,
+    selection: &SyncSelection,
+    project_id: &str,
+    result: &mut SyncResult,
+) -> Result<(), String> {
+    let provider_key = provider.name();
+    let (local, mut base, tombstones, applied, pending, queued, device_id) = {
+        let conn = open(db_path)?;
+        (
+            export_project_files(&conn, project_id)?,
+            load_base_state(&conn, &provider_key, project_id)?,
+            load_tombstones(&conn, project_id)?,
+            load_applied_batches(&conn, &provider_key, project_id)?,
+            load_pending_conflicts(&conn, &provider_key, project_id)?,
+            load_outbox(&conn, &provider_key, project_id)?,
+            device_id(&conn)?,
+        )
+    };
+
+    let journal_dir = format!("{}/{}", project_dir(project_id), JOURNAL_DIR);
+    let mut batch_names: Vec<String> = provider
+        .list(&journal_dir)
+        .await?
+        .into_iter()
+        .filter(|e| !e.is_dir)
+        .filter_map(|e| e.path.rsplit('/').next().map(str::to_string))
+        .filter(|name| name.ends_with(".json") && batch_seq(name).is_some())
+        .collect();
+    batch_names.sort_by_key(|name| (batch_seq(name), name.clone()));
+    let next_seq = batch_names.iter().filter_map(|n| batch_seq(n)).max().map_or(1, |s| s + 1);
+
+    // 未解决冲突中保存的远端版本在前，再按顺序合并未应用的远端批次，同一记录以最后一次变更为准
+    let mut remote_changes: BTreeMap<String, JournalChange> =
+        pending.values().map(|c| (c.path.clone(), c.remote.clone())).collect();
+    let mut new_batches = Vec::new();
+    for name in batch_names.into_iter().filter(|n| !applied.contains(n)) {
+        let data = provider
+            .download(&format!("{}/{}", journal_dir, name))
+            .await?
+            .ok_or_else(|| format!("变更批次 {} 缺失", name))?;
+        let batch: JournalBatch = serde_json::from_slice(&data).map_err(|e| format!("变更批次 {} 损坏: {}", name, e))?;
+        new_batches.push(name);
+        if batch.device_id == device_id {
+            continue;
+        }
+        for change in batch.changes {
+            remote_changes.insert(change.path.clone(), change);
+        }
+    }
+
+    let local_state = |path: &str| -> FileState {
+        match local.get(path) {
+            Some(record) => record.state.clone(),
+            None => FileState { hash: None, updated_at: tombstones.get(path).cloned() },
+        }
+    };
+    // 未选中的数据类型不做处理，基线和远端批次中的记录保持不变
+    let included = |path: &str| selection.includes(data_class(path));
+    let mut paths: Vec<String> = local
+        .keys()
+        .chain(base.keys())
+        .filter(|p| local_state(p).hash != base.get(*p).and_then(|s| s.hash.clone()))
+        .chain(remote_changes.keys())
+        .filter(|p| included(p))
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    // 先处理项目本身，再处理它下面的数据
+    paths.sort_by_key(|p| (p != PROJECT_FILE, p.clone()));
+
+    let mut base_rows: HashMap<String, Map<String, Value>> = HashMap::new();
+    let mut outgoing: Vec<JournalChange> = Vec::new();
+    let mut pulled: Vec<(String, Map<String, Value>)> = Vec::new();
+    let mut deleted: Vec<String> = Vec::new();
+    let mut conflicts: Vec<(SyncConflict, JournalChange)> = Vec::new();
+    {
+        let conn = open(db_path)?;
+        for path in paths {
+            let local_version = local_state(&path);
+            let local_row = local.get(&path).map(|r| &r.row);
+            let base_hash = base.get(&path).and_then(|s| s.hash.clone());
+            let remote_change = remote_changes.get(&path);
+            let remote_version = match remote_change {
+                Some(change) => change.state.clone(),
+                None => base.get(&path).cloned().unwrap_or_default(),
+            };
+            let mut action = plan_file_action(&local_version, &remote_version, base_hash.as_deref(), strategy);
+            let mut error = None;
+            match action {
+                SyncAction::Unchanged => {
+                    match (&local_version.hash, local_row) {
+                        (Some(_), Some(row)) => {
+                            base.insert(path.clone(), local_version);
+                            base_rows.insert(path.clone(), row.clone());
+                        }
+                        _ => {
+                            base.remove(&path);
+                        }
+                    }
+                    continue;
+                }
+                SyncAction::Push | SyncAction::DeleteRemote | SyncAction::Merged => outgoing.push(JournalChange {
+                    path: path.clone(),
+                    state: local_version,
+                    data: local_row.cloned(),
+                }),
+                SyncAction::Pull => match remote_change.and_then(|c| c.data.clone()) {
+                    Some(row) => {
+                        pulled.push((path.clone(), row.clone()));
+                        base.insert(path.clone(), remote_version);
+                        base_rows.insert(path.clone(), row);
+                    }
+                    None => error = Some("远端变更缺少数据".to_string()),
+                },
+                SyncAction::DeleteLocal => {
+                    deleted.push(path.clone());
+                    base.remove(&path);
+                }
+                SyncAction::Conflict => {
+                    let remote_row = remote_change.and_then(|c| c.data.as_ref());
+                    let merge = match (local_row, remote_row) {
+                        (Some(local_row), Some(remote_row)) if is_mergeable(&path) && strategy == ConflictResolutionStrategy::Merge => {
+                            let base_row = load_base_row(&conn, &provider_key, project_id, &path)?.unwrap_or_default();
+                            Some(merge_chapter_rows(&base_row, local_row, remote_row))
+                        }
+                        _ => None,
+                    };
+                    match merge {
+                        // 自动合并成功：写入本地并作为新版本上传
+                        Some((row, content)) if content.conflict_count == 0 => {
+                            action = SyncAction::Merged;
+                            pulled.push((path.clone(), row.clone()));
+                            outgoing.push(JournalChange { path: path.clone(), state: record_state(&row)?, data: Some(row) });
+                        }
+                        merge => {
+                            let conflict = SyncConflict {
+                                file_path: path.clone(),
+                                conflict_type: if local_version.hash.is_none() || remote_version.hash.is_none() {
+                                    "delete_modify".to_string()
+                                } else {
+                                    "both_modified".to_string()
+                                },
+                                project_id: project_id.to_string(),
+                                local_updated_at: local_version.updated_at,
+                                remote_updated_at: remote_version.updated_at.clone(),
+                                conflict_id: None,
+                                merge: merge.map(|(_, content)| content),
+                            };
+                            let remote = remote_change.cloned().unwrap_or(JournalChange {
+                                path: path.clone(),
+                                state: remote_version,
+                                data: None,
+                            });
+                            conflicts.push((conflict, remote));
+                        }
+                    }
+                }
+            }
+            if error.is_some() {
+                result.success = false;
+            }
+            result.files.push(SyncFileResult { project_id: project_id.to_string(), path, action, error });
+        }
+    }
+
+    let mut conn = open(db_path)?;
+    if !pulled.is_empty() || !deleted.is_empty() {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        for (path, row) in &pulled {
+            apply_remote_file(&tx, project_id, path, row)?;
+        }
+        for path in &deleted {
+            delete_local_file(&tx, project_id, path)?;
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+
+    // 离线期间排队的变更按排队顺序最先上传
+    outgoing.sort_by_key(|c| (!queued.contains_key(&c.path), queued.get(&c.path).cloned()));
+    let now = Utc::now().to_rfc3339();
+    for (i, chunk) in outgoing.chunks(MAX_BATCH_CHANGES).enumerate() {
+        let name = format!("{:08}-{}.json", next_seq + i as u64, device_id);
+        let batch = JournalBatch { device_id: device_id.clone(), created_at: now.clone(), changes: chunk.to_vec() };
+        let data = serde_json::to_vec(&batch).map_err(|e| e.to_string())?;
+        provider.upload(&format!("{}/{}", journal_dir, name), data).await?;
+        new_batches.push(name);
+        for change in chunk {
+            match (&change.state.hash, &change.data) {
+                (Some(_), Some(row)) => {
+                    base.insert(change.path.clone(), change.state.clone());
+                    base_rows.insert(change.path.clone(), row.clone());
+                }
+                _ => {
+                    base.remove(&change.path);
+                }
+            }
+        }
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    save_base_state(&tx, &provider_key, project_id, &base, &base_rows)?;
+    for name in &new_batches {
+        tx.execute(
+            "INSERT OR IGNORE INTO sync_applied_batches (provider, project_id, batch, applied_at) VALUES (?, ?, ?, ?)",
+            params![provider_key, project_id, name, now],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    let mut conflicted = HashSet::new();
+    for (mut conflict, remote) in conflicts {
+        conflict.conflict_id = Some(save_pending_conflict(&tx, &provider_key, project_id, &conflict, &remote)?);
+        conflicted.insert(conflict.file_path.clone());
+        result.conflicts.push(conflict);
+    }
+    for path in pending.keys().filter(|p| included(p) && !conflicted.contains(*p)) {
+        tx.execute(
+            "DELETE FROM sync_conflicts WHERE provider = ? AND project_id = ? AND path = ?",
+            params![provider_key, project_id, path],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.execute(
+        "DELETE FROM sync_outbox WHERE provider = ? AND project_id = ?",
+        params![provider_key, project_id],
+    )
+    .map_err(|e| e.to_string())?;
+    // 墓碑已经同步（或因同步删除而产生），冲突中的保留到下次
+    for path in tombstones.keys().chain(deleted.iter()).filter(|p| included(p) && !conflicted.contains(*p)) {
+        if let Ok((table, id)) = resolve_path(path) {
+            tx.execute(
+                "DELETE FROM sync_tombstones WHERE table_name = ? AND row_id = ?",
+                params![table, id.unwrap_or_else(|| project_id.to_string())],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(hash: &str, updated_at: &str) -> FileState {
+        FileState { hash: Some(hash.to_string()), updated_at: Some(updated_at.to_string()) }
+    }
+
+    fn tombstone(deleted_at: &str) -> FileState {
+        FileState { hash: None, updated_at: Some(deleted_at.to_string()) }
+    }
+
+    #[test]
+    fn test_plan_file_action() {
+        use ConflictResolutionStrategy::*;
+        let a = state("a", "2024-01-01");
+        let b = state("b", "2024-02-01");
+        let c = state("c", "2024-03-01");
+        let none = FileState::default();
+
+        assert_eq!(plan_file_action(&a, &a, None, AskUser), SyncAction::Unchanged);
+        assert_eq!(plan_file_action(&b, &a, Some("a"), AskUser), SyncAction::Push);
+        assert_eq!(plan_file_action(&a, &b, Some("a"), AskUser), SyncAction::Pull);
+        assert_eq!(plan_file_action(&a, &none, None, AskUser), SyncAction::Push);
+        assert_eq!(plan_file_action(&none, &a, None, AskUser), SyncAction::Pull);
+        assert_eq!(plan_file_action(&tombstone("2024-04-01"), &a, Some("a"), AskUser), SyncAction::DeleteRemote);
+        assert_eq!(plan_file_action(&a, &tombstone("2024-04-01"), Some("a"), AskUser), SyncAction::DeleteLocal);
+
+        assert_eq!(plan_file_action(&b, &c, Some("a"), AskUser), SyncAction::Conflict);
+        assert_eq!(plan_file_action(&b, &c, Some("a"), PreferLocal), SyncAction::Push);
+        assert_eq!(plan_file_action(&b, &c, Some("a"), TimestampBased), SyncAction::Pull);
+        // 远端修改早于本地删除时，删除生效
+        assert_eq!(plan_file_action(&tombstone("2024-04-01"), &c, Some("a"), TimestampBased), SyncAction::DeleteRemote);
+    }
+
+    #[test]
+    fn test_export_and_apply_roundtrip() {
+        let source = Connection::open_in_memory().unwrap();
+        let target = Connection::open_in_memory().unwrap();
+        for conn in [&source, &target] {
+            conn.execute_batch(
+                "CREATE TABLE projects (id TEXT PRIMARY KEY, name TEXT NOT NULL, created_at TEXT, updated_at TEXT);
+                 CREATE TABLE chapters (id TEXT PRIMARY KEY, project_id TEXT NOT NULL, title TEXT, word_count INTEGER, updated_at TEXT);",
+            )
+            .unwrap();
+        }
+        for (table, _) in SYNC_TABLES.iter().filter(|(t, _)| *t != "chapters") {
+            for conn in [&source, &target] {
+                conn.execute_batch(&format!("CREATE TABLE {} (id TEXT PRIMARY KEY, project_id TEXT, updated_at TEXT);", table))
+                    .unwrap();
+            }
+        }
+        source.execute("INSERT INTO projects VALUES ('p1', '长夜', 't0', 't1')", []).unwrap();
+        source.execute("INSERT INTO chapters VALUES ('c1', 'p1', '第一章', 1200, 't2')", []).unwrap();
+
+        let files = export_project_files(&source, "p1").unwrap();
+        assert_eq!(files.keys().cloned().collect::<Vec<_>>(), vec!["chapters/c1.json", "project.json"]);
+        for (path, record) in &files {
+            apply_remote_file(&target, "p1", path, &record.row).unwrap();
+        }
+        assert_eq!(export_project_files(&target, "p1").unwrap(), files);
+
+        let row = &files["chapters/c1.json"].row;
+        assert!(apply_remote_file(&target, "p2", "chapters/c1.json", row).is_err());
+        delete_local_file(&target, "p1", "chapters/c1.json").unwrap();
+        assert_eq!(export_project_files(&target, "p1").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_data_class_selection() {
+        assert_eq!(data_class(PROJECT_FILE), SyncDataClass::Manuscript);
+        assert_eq!(data_class("chapters/c1.json"), SyncDataClass::Manuscript);
+        assert_eq!(data_class("project_snapshots/s1.json"), SyncDataClass::Snapshots);
+        assert_eq!(data_class("script_scenes/s1.json"), SyncDataClass::Media);
+
+        // 旧版本保存的配置没有 selection，默认不同步快照和媒体
+        let config: super::super::SyncConfig = serde_json::from_value(serde_json::json!({
+            "provider_type": "WebDAV",
+            "credentials": {},
+            "sync_interval_seconds": 300,
+            "auto_sync": false,
+            "conflict_resolution": "Merge"
+        }))
+        .unwrap();
+        assert!(config.selection.includes_project("p1"));
+        assert!(config.selection.includes(SyncDataClass::Manuscript));
+        assert!(!config.selection.includes(SyncDataClass::Media));
+        assert!(!config.selection.includes(SyncDataClass::Snapshots));
+    }
+
+    #[test]
+    fn test_batch_seq() {
+        assert_eq!(batch_seq("00000012-3f2a.json"), Some(12));
+        assert_eq!(batch_seq("manifest.json"), None);
+    }
+
+    #[test]
+    fn test_merge_chapter_rows_and_resolve() {
+        let row = |title: &str, content: &str| -> Map<String, Value> {
+            serde_json::from_value(serde_json::json!({
+                "id": "c1", "project_id": "p1", "title": title, "content": content,
+                "word_count": 0, "sort_order": 0, "status": "draft",
+                "created_at": "t0", "updated_at": "t1", "summary": null
+            }))
+            .unwrap()
+        };
+        let base = row("第一章", "开头\n他走进房间。\n结尾");
+        let local = row("第一章", "开头\n他推门走进房间。\n结尾");
+        let remote = row("第一章 雨夜", "开头\n她走进房间。\n结尾");
+        let (merged, content) = merge_chapter_rows(&base, &local, &remote);
+        assert_eq!(merged["title"], "第一章 雨夜");
+        assert_eq!(content.conflict_count, 1);
+
+        let (_dir, conn) = crate::test_support::project_db();
+        apply_remote_file(&conn, "p1", "chapters/c1.json", &local).unwrap();
+        let base_state = HashMap::from([("chapters/c1.json".to_string(), record_state(&base).unwrap())]);
+        let base_rows = HashMap::from([("chapters/c1.json".to_string(), base)]);
+        save_base_state(&conn, "webdav", "p1", &base_state, &base_rows).unwrap();
+
+        let remote_change = JournalChange {
+            path: "chapters/c1.json".into(),
+            state: record_state(&remote).unwrap(),
+            data: Some(remote.clone()),
+        };
+        let conflict = SyncConflict {
+            file_path: "chapters/c1.json".into(),
+            conflict_type: "both_modified".into(),
+            project_id: "p1".into(),
+            local_updated_at: None,
+            remote_updated_at: None,
+            conflict_id: None,
+            merge: Some(content.clone()),
+        };
+        let id = save_pending_conflict(&conn, "webdav", "p1", &conflict, &remote_change).unwrap();
+        assert_eq!(list_pending_conflicts(&conn, Some("p1")).unwrap().len(), 1);
+
+        let hunk = content.hunks.iter().find(|h| h.kind == crate::text_merge::MergeHunkKind::Conflict).unwrap().index;
+        let choice = ConflictChoice::Merge {
+            resolutions: vec![HunkResolution { hunk, choice: crate::text_merge::HunkChoice::Remote, text: None }],
+        };
+        let resolved = resolve_pending_conflict(&conn, &id, &choice).unwrap().unwrap();
+        assert_eq!(resolved["content"], "开头\n她走进房间。\n结尾");
+        assert!(list_pending_conflicts(&conn, None).unwrap().is_empty());
+
+        // 基线更新为远端版本，本地的解决结果会在下次同步时上传
+        let base = load_base_state(&conn, "webdav", "p1").unwrap();
+        assert_eq!(base["chapters/c1.json"], remote_change.state);
+        let local_now = export_project_files(&conn, "p1").unwrap().remove("chapters/c1.json").unwrap();
+        assert_eq!(local_now.row["title"], "第一章 雨夜");
+        assert_ne!(Some(&local_now.state.hash), Some(&remote_change.state.hash));
+    }
+    /// 内存中的同步服务，可模拟断网
+    struct MemoryProvider {
+        files: std::sync::Mutex<BTreeMap<String, Vec<u8>>>,
+        online: std::sync::atomic::AtomicBool,
+    }
+
+    impl MemoryProvider {
+        fn ensure_online(&self) -> Result<(), String> {
+            if self.online.load(std::sync::atomic::Ordering::SeqCst) {
+                Ok(())
+            } else {
+                Err(super::super::provider::offline_error("connection refused"))
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl SyncProvider for MemoryProvider {
+        fn name(&self) -> String {
+            "memory".to_string()
+        }
+
+        async fn check(&self) -> Result<(), String> {
+            self.ensure_online()
+        }
+
+        async fn list(&self, dir: &str) -> Result<Vec<super::super::provider::RemoteEntry>, String> {
+            self.ensure_online()?;
+            let prefix = format!("{}/", dir);
+            Ok(self
+                .files
+                .lock()
+                .unwrap()
+                .keys()
+                .filter_map(|p| p.strip_prefix(&prefix).filter(|rest| !rest.contains('/')).map(|_| p.clone()))
+                .map(|path| super::super::provider::RemoteEntry { path, is_dir: false, size: None, etag: None, modified: None })
+                .collect())
+        }
+
+        async fn upload(&self, path: &str, data: Vec<u8>) -> Result<(), String> {
+            self.ensure_online()?;
+            self.files.lock().unwrap().insert(path.to_string(), data);
+            Ok(())
+        }
+
+        async fn download(&self, path: &str) -> Result<Option<Vec<u8>>, String> {
+            self.ensure_online()?;
+            Ok(self.files.lock().unwrap().get(path).cloned())
+        }
+
+        async fn delete(&self, path: &str) -> Result<(), String> {
+            self.ensure_online()?;
+            self.files.lock().unwrap().remove(path);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_offline_queue_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("offline.db");
+        {
+            let conn = crate::test_support::init_project_db(&db_path);
+            conn.execute(
+                "INSERT INTO chapters (id, project_id, title, content, created_at, updated_at) VALUES ('c1', 'p1', '第一章', '雨夜', 't0', 't1')",
+                [],
+            )
+            .unwrap();
+        }
+        let provider = MemoryProvider { files: Default::default(), online: false.into() };
+        let selection = SyncSelection::default();
+
+        let result = run_sync(&db_path, &provider, ConflictResolutionStrategy::TimestampBased, &selection, None).await.unwrap();
+        assert!(result.offline);
+        assert_eq!(result.queued_changes, 2);
+        // 再次离线同步不会重复排队
+        let result = run_sync(&db_path, &provider, ConflictResolutionStrategy::TimestampBased, &selection, None).await.unwrap();
+        assert_eq!(result.queued_changes, 2);
+
+        provider.online.store(true, std::sync::atomic::Ordering::SeqCst);
+        let result = run_sync(&db_path, &provider, ConflictResolutionStrategy::TimestampBased, &selection, None).await.unwrap();
+        assert!(result.success && !result.offline);
+        assert_eq!(result.queued_changes, 0);
+        assert_eq!(result.synced_files, vec!["p1/project.json", "p1/chapters/c1.json"]);
+
+        let files = provider.files.lock().unwrap();
+        let (_, data) = files.iter().find(|(p, _)| p.starts_with("projects/p1/journal/")).unwrap();
+        let batch: JournalBatch = serde_json::from_slice(data).unwrap();
+        assert_eq!(batch.changes.len(), 2);
+    }
+}
@@ -0,0 +1,367 @@
+use super::lan::LocalDirProvider;
+use super::provider::{offline_error, RemoteEntry, SyncProvider};
+use async_trait::async_trait;
+use git2::{
+    Cred, ErrorClass, ErrorCode, FetchOptions, FileFavor, IndexAddOption, MergeOptions, Oid, PushOptions,
+    RemoteCallbacks, Repository, ResetType, Signature,
+};
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_BRANCH: &str = "main";
+const DEFAULT_AUTHOR: &str = "AI Novel Studio";
+const DEFAULT_EMAIL: &str = "sync@ainovelstudio.local";
+
+/// 远端仓库的连接参数，git 操作都是阻塞的，在 spawn_blocking 中执行
+#[derive(Debug, Clone)]
+struct GitRemote {
+    repo_dir: PathBuf,
+    url: String,
+    branch: String,
+    username: String,
+    password: String,
+    author_name: String,
+    author_email: String,
+}
+
+fn git_error(e: git2::Error) -> String {
+    match (e.code(), e.class()) {
+        (ErrorCode::Auth, _) => format!("Git 认证失败: {}", e.message()),
+        (_, ErrorClass::Net | ErrorClass::Os | ErrorClass::Http | ErrorClass::Ssh) => offline_error(e.message()),
+        _ => format!("Git 操作失败: {}", e.message()),
+    }
+}
+
+impl GitRemote {
+    fn local_ref(&self) -> String {
+        format!("refs/heads/{}", self.branch)
+    }
+
+    fn remote_ref(&self) -> String {
+        format!("refs/remotes/origin/{}", self.branch)
+    }
+
+    fn callbacks(&self) -> RemoteCallbacks<'_> {
+        let mut callbacks = RemoteCallbacks::new();
+        // libgit2 在凭据被拒绝后会不断重新询问，同一次操作只提供一次，之后按认证失败返回
+        let mut attempts = 0;
+        callbacks.credentials(move |_url, username_from_url, allowed| {
+            attempts += 1;
+            if attempts > 1 {
+                return Err(git2::Error::new(ErrorCode::Auth, ErrorClass::Callback, "凭据被拒绝"));
+            }
+            if allowed.is_ssh_key() {
+                return Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"));
+            }
+            let username = if self.username.is_empty() { username_from_url.unwrap_or("git") } else { &self.username };
+            Cred::userpass_plaintext(username, &self.password)
+        });
+        callbacks
+    }
+
+    /// 打开本地仓库，不存在时初始化并关联远端
+    fn open(&self) -> Result<Repository, String> {
+        let repo = match Repository::open(&self.repo_dir) {
+            Ok(repo) => repo,
+            Err(_) => {
+                std::fs::create_dir_all(&self.repo_dir).map_err(|e| e.to_string())?;
+                let repo = Repository::init(&self.repo_dir).map_err(git_error)?;
+                repo.set_head(&self.local_ref()).map_err(git_error)?;
+                repo
+            }
+        };
+        match repo.find_remote("origin") {
+            Ok(remote) if remote.url() == Some(self.url.as_str()) => {}
+            Ok(_) => repo.remote_set_url("origin", &self.url).map_err(git_error)?,
+            Err(_) => {
+                repo.remote("origin", &self.url).map_err(git_error)?;
+            }
+        }
+        Ok(repo)
+    }
+
+    fn signature(&self) -> Result<Signature<'static>, String> {
+        Signature::now(&self.author_name, &self.author_email).map_err(git_error)
+    }
+
+    /// 提交工作区的全部改动，没有改动时返回 None
+    fn commit_all(&self, repo: &Repository, message: &str) -> Result<Option<Oid>, String> {
+        let mut index = repo.index().map_err(git_error)?;
+        index.add_all(["*"].iter(), IndexAddOption::DEFAULT, None).map_err(git_error)?;
+        index.update_all(["*"].iter(), None).map_err(git_error)?;
+        index.write().map_err(git_error)?;
+        let tree_id = index.write_tree().map_err(git_error)?;
+
+        let parent = repo.refname_to_id(&self.local_ref()).ok().map(|oid| repo.find_commit(oid)).transpose().map_err(git_error)?;
+        if parent.as_ref().is_some_and(|p| p.tree_id() == tree_id) {
+            return Ok(None);
+        }
+        let tree = repo.find_tree(tree_id).map_err(git_error)?;
+        let signature = self.signature()?;
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        let oid = repo
+            .commit(Some(&self.local_ref()), &signature, &signature, message, &tree, &parents)
+            .map_err(git_error)?;
+        Ok(Some(oid))
+    }
+
+    fn fast_forward(&self, repo: &Repository, oid: Oid) -> Result<(), String> {
+        repo.reference(&self.local_ref(), oid, true, "sync: fast-forward").map_err(git_error)?;
+        repo.set_head(&self.local_ref()).map_err(git_error)?;
+        let object = repo.find_object(oid, None).map_err(git_error)?;
+        repo.reset(&object, ResetType::Hard, None).map_err(git_error)
+    }
+
+    /// 拉取远端分支并与本地合并。变更日志的文件名按设备区分不会冲突，
+    /// Markdown 导出每次同步都会重新生成，合并时以远端为准
+    fn pull(&self) -> Result<Repository, String> {
+        let repo = self.open()?;
+        self.commit_all(&repo, "同步：保存未提交的记录")?;
+
+        let mut fetch_options = FetchOptions::new();
+        fetch_options.remote_callbacks(self.callbacks());
+        let refspec = format!("+{}:{}", self.local_ref(), self.remote_ref());
+        repo.find_remote("origin")
+            .map_err(git_error)?
+            .fetch(&[&refspec], Some(&mut fetch_options), None)
+            .map_err(git_error)?;
+
+        let local = repo.refname_to_id(&self.local_ref()).ok();
+        let Ok(remote) = repo.refname_to_id(&self.remote_ref()) else {
+            // 远端还没有这个分支
+            return Ok(repo);
+        };
+        match local {
+            None => self.fast_forward(&repo, remote)?,
+            Some(local) if local == remote || repo.graph_descendant_of(local, remote).map_err(git_error)? => {}
+            Some(local) if repo.graph_descendant_of(remote, local).map_err(git_error)? => self.fast_forward(&repo, remote)?,
+            Some(local) => {
+                let ours = repo.find_commit(local).map_err(git_error)?;
+                let theirs = repo.find_commit(remote).map_err(git_error)?;
+                let mut options = MergeOptions::new();
+                options.file_favor(FileFavor::Theirs);
+                let mut index = repo.merge_commits(&ours, &theirs, Some(&options)).map_err(git_error)?;
+                if index.has_conflicts() {
+                    return Err("Git 合并冲突，请手动处理同步仓库".to_string());
+                }
+                let tree = repo.find_tree(index.write_tree_to(&repo).map_err(git_error)?).map_err(git_error)?;
+                let signature = self.signature()?;
+                let merged = repo
+                    .commit(None, &signature, &signature, "同步：合并远端记录", &tree, &[&ours, &theirs])
+                    .map_err(git_error)?;
+                self.fast_forward(&repo, merged)?;
+            }
+        }
+        Ok(repo)
+    }
+
+    /// 本地分支领先远端时推送
+    fn push(&self, repo: &Repository) -> Result<(), String> {
+        let Ok(local) = repo.refname_to_id(&self.local_ref()) else { return Ok(()) };
+        if repo.refname_to_id(&self.remote_ref()).ok() == Some(local) {
+            return Ok(());
+        }
+        let rejected = RefCell::new(None);
+        {
+            let mut callbacks = self.callbacks();
+            callbacks.push_update_reference(|_refname, status| {
+                *rejected.borrow_mut() = status.map(str::to_string);
+                Ok(())
+            });
+            let mut push_options = PushOptions::new();
+            push_options.remote_callbacks(callbacks);
+            let refspec = format!("{}:{}", self.local_ref(), self.local_ref());
+            repo.find_remote("origin")
+                .map_err(git_error)?
+                .push(&[&refspec], Some(&mut push_options))
+                .map_err(git_error)?;
+        }
+        if let Some(reason) = rejected.into_inner() {
+            return Err(format!("Git 推送被拒绝: {}", reason));
+        }
+        repo.reference(&self.remote_ref(), local, true, "sync: push").map_err(git_error)?;
+        Ok(())
+    }
+}
+
+/// 把同步记录保存在 Git 仓库中，并为每个项目导出按章节拆分的 Markdown 和清单，
+/// 推送到 GitHub、Gitea 等远端后既是异地备份也是完整的修改历史。
+/// 凭据字段：`url`，可选 `username`、`password`（访问令牌）、`branch`、`author_name`、`author_email`
+pub struct GitProvider {
+    remote: GitRemote,
+    store: LocalDirProvider,
+}
+
+impl GitProvider {
+    /// `data_dir` 下保存远端仓库的本地副本
+    pub fn from_credentials(credentials: &HashMap<String, String>, data_dir: &Path) -> Result<Self, String> {
+        let url = credentials
+            .get("url")
+            .map(|u| u.trim().to_string())
+            .filter(|u| !u.is_empty())
+            .ok_or("Git 仓库地址未配置")?;
+        let field = |key: &str, default: &str| {
+            credentials
+                .get(key)
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| default.to_string())
+        };
+        let repo_key = format!("{:x}", Sha256::digest(url.as_bytes()));
+        let repo_dir = data_dir.join("git_sync").join(&repo_key[..16]);
+        Ok(Self {
+            store: LocalDirProvider::new("git", repo_dir.clone()),
+            remote: GitRemote {
+                repo_dir,
+                url,
+                branch: field("branch", DEFAULT_BRANCH),
+                username: field("username", ""),
+                password: field("password", ""),
+                author_name: field("author_name", DEFAULT_AUTHOR),
+                author_email: field("author_email", DEFAULT_EMAIL),
+            },
+        })
+    }
+
+    async fn blocking<T, F>(&self, task: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(GitRemote) -> Result<T, String> + Send + 'static,
+    {
+        let remote = self.remote.clone();
+        tokio::task::spawn_blocking(move || task(remote)).await.map_err(|e| e.to_string())?
+    }
+}
+
+/// 导出项目的章节 Markdown 和清单到仓库中的 `projects/{id}/`
+fn export_manuscript(db_path: &Path, repo_dir: &Path, project_id: &str) -> Result<(), String> {
+    let conn = crate::database::get_connection(db_path).map_err(|e| e.to_string())?;
+    let content = match crate::export::load_project_content(&conn, project_id, None) {
+        Ok(content) => content,
+        // 项目已删除，同步记录中的删除会体现在日志里
+        Err(_) => return Ok(()),
+    };
+    let project_dir = repo_dir.join(super::engine::project_dir(project_id));
+    let manuscript_dir = project_dir.join("manuscript");
+    if manuscript_dir.exists() {
+        std::fs::remove_dir_all(&manuscript_dir).map_err(|e| e.to_string())?;
+    }
+    crate::export::export_as_md_folder(&content, &manuscript_dir).map_err(|e| e.to_string())?;
+
+    let manifest = serde_json::json!({
+        "project_id": project_id,
+        "metadata": content.metadata,
+        "chapters": content
+            .chapters
+            .iter()
+            .map(|c| serde_json::json!({ "id": c.id, "number": c.number, "title": c.title, "status": c.status }))
+            .collect::<Vec<_>>(),
+    });
+    let data = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+    std::fs::write(project_dir.join("manifest.json"), data).map_err(|e| e.to_string())
+}
+
+#[async_trait]
+impl SyncProvider for GitProvider {
+    fn name(&self) -> String {
+        "git".to_string()
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        self.blocking(|remote| remote.pull().map(|_| ())).await
+    }
+
+    async fn list(&self, dir: &str) -> Result<Vec<RemoteEntry>, String> {
+        self.store.list(dir).await
+    }
+
+    async fn upload(&self, path: &str, data: Vec<u8>) -> Result<(), String> {
+        self.store.upload(path, data).await
+    }
+
+    async fn download(&self, path: &str) -> Result<Option<Vec<u8>>, String> {
+        self.store.download(path).await
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), String> {
+        self.store.delete(path).await
+    }
+
+    /// 导出 Markdown，提交并推送；推送失败时提交保留在本地，下次同步时一并推送
+    async fn finish(&self, db_path: &Path, project_ids: &[String]) -> Result<(), String> {
+        let db_path = db_path.to_path_buf();
+        let project_ids = project_ids.to_vec();
+        self.blocking(move |remote| {
+            for project_id in &project_ids {
+                export_manuscript(&db_path, &remote.repo_dir, project_id)?;
+            }
+            let repo = remote.open()?;
+            let message = format!("同步 {} 个项目 ({})", project_ids.len(), chrono::Local::now().format("%Y-%m-%d %H:%M"));
+            remote.commit_all(&repo, &message)?;
+            if let Err(e) = remote.push(&repo) {
+                if !e.starts_with("Git 推送被拒绝") {
+                    return Err(e);
+                }
+                // 推送期间远端有新提交：合并后重试一次
+                let repo = remote.pull()?;
+                remote.push(&repo)?;
+            }
+            Ok(())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cloud_sync::engine::run_sync;
+    use crate::cloud_sync::{ConflictResolutionStrategy, SyncSelection};
+    use rusqlite::Connection;
+
+    #[tokio::test]
+    async fn test_sync_through_bare_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let bare = dir.path().join("remote.git");
+        Repository::init_bare(&bare).unwrap();
+        let credentials = HashMap::from([("url".to_string(), bare.to_string_lossy().to_string())]);
+
+        let db_a = dir.path().join("a.db");
+        let db_b = dir.path().join("b.db");
+        crate::database::init_database(&db_b).unwrap();
+        {
+            let conn = crate::test_support::init_project_db(&db_a);
+            conn.execute(
+                "INSERT INTO chapters (id, project_id, title, content, created_at, updated_at) VALUES ('c1', 'p1', '第一章', '雨夜', 't0', 't1')",
+                [],
+            )
+            .unwrap();
+        }
+
+        let provider_a = GitProvider::from_credentials(&credentials, &dir.path().join("device_a")).unwrap();
+        let result = run_sync(&db_a, &provider_a, ConflictResolutionStrategy::TimestampBased, &SyncSelection::default(), None)
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.files);
+
+        // 远端仓库中有章节的 Markdown 导出
+        let remote = Repository::open_bare(&bare).unwrap();
+        let head = remote.find_reference("refs/heads/main").unwrap().peel_to_commit().unwrap();
+        let tree = head.tree().unwrap();
+        assert!(tree.get_path(Path::new("projects/p1/manuscript/chapters/001-第一章.md")).is_ok());
+        assert!(tree.get_path(Path::new("projects/p1/manifest.json")).is_ok());
+
+        let provider_b = GitProvider::from_credentials(&credentials, &dir.path().join("device_b")).unwrap();
+        let result = run_sync(&db_b, &provider_b, ConflictResolutionStrategy::TimestampBased, &SyncSelection::default(), None)
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.files);
+        let title: String = Connection::open(&db_b)
+            .unwrap()
+            .query_row("SELECT title FROM chapters WHERE id = 'c1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(title, "第一章");
+    }
+}
@@ -0,0 +1,200 @@
+use super::{SyncAction, SyncFileResult, SyncResult};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// 默认保留的同步记录条数
+pub const MAX_HISTORY_ENTRIES: i64 = 500;
+
+/// 一次同步运行的记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncHistoryEntry {
+    pub id: String,
+    pub provider: String,
+    /// 指定同步的项目，为空表示全部项目
+    pub project_ids: Option<Vec<String>>,
+    pub started_at: String,
+    pub duration_ms: i64,
+    pub success: bool,
+    pub pushed: i64,
+    pub pulled: i64,
+    pub conflicts: i64,
+    pub errors: Vec<String>,
+    /// 发生变化的文件及其处理方式
+    pub files: Vec<SyncFileResult>,
+}
+
+impl SyncHistoryEntry {
+    /// 由同步结果生成记录；`outcome` 为 Err 时整次同步失败
+    pub fn new(
+        provider: &str,
+        project_ids: Option<Vec<String>>,
+        started_at: DateTime<Utc>,
+        outcome: &Result<SyncResult, String>,
+    ) -> Self {
+        let duration_ms = (Utc::now() - started_at).num_milliseconds();
+        let mut entry = Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            provider: provider.to_string(),
+            project_ids,
+            started_at: started_at.to_rfc3339(),
+            duration_ms,
+            success: false,
+            pushed: 0,
+            pulled: 0,
+            conflicts: 0,
+            errors: Vec::new(),
+            files: Vec::new(),
+        };
+        match outcome {
+            Ok(result) => {
+                entry.success = result.success;
+                entry.conflicts = result.conflicts.len() as i64;
+                for file in &result.files {
+                    match (&file.error, file.action) {
+                        (Some(e), _) if file.project_id.is_empty() => entry.errors.push(e.clone()),
+                        (Some(e), _) if file.path.is_empty() => entry.errors.push(format!("{}: {}", file.project_id, e)),
+                        (Some(e), _) => entry.errors.push(format!("{}/{}: {}", file.project_id, file.path, e)),
+                        (None, SyncAction::Push | SyncAction::DeleteRemote) => entry.pushed += 1,
+                        (None, SyncAction::Pull | SyncAction::DeleteLocal) => entry.pulled += 1,
+                        (None, SyncAction::Merged) => {
+                            entry.pushed += 1;
+                            entry.pulled += 1;
+                        }
+                        _ => {}
+                    }
+                }
+                entry.files = result.files.clone();
+                if result.offline {
+                    entry.errors.push(format!("同步服务不可达，{} 条变更已进入离线队列", result.queued_changes));
+                }
+            }
+            Err(e) => entry.errors.push(e.clone()),
+        }
+        entry
+    }
+}
+
+/// 保存一条同步记录，并只保留最近 `MAX_HISTORY_ENTRIES` 条
+pub fn record_sync_run(conn: &Connection, entry: &SyncHistoryEntry) -> Result<(), String> {
+    let project_ids = entry.project_ids.as_ref().map(serde_json::to_string).transpose().map_err(|e| e.to_string())?;
+    let errors = serde_json::to_string(&entry.errors).map_err(|e| e.to_string())?;
+    let files = serde_json::to_string(&entry.files).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO sync_history (id, provider, project_ids, started_at, duration_ms, success, pushed, pulled, conflicts, errors, files)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            entry.id,
+            entry.provider,
+            project_ids,
+            entry.started_at,
+            entry.duration_ms,
+            entry.success,
+            entry.pushed,
+            entry.pulled,
+            entry.conflicts,
+            errors,
+            files,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM sync_history WHERE id NOT IN (SELECT id FROM sync_history ORDER BY started_at DESC LIMIT ?)",
+        params![MAX_HISTORY_ENTRIES],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 按时间倒序返回同步记录；指定 `project_id` 时只返回涉及该项目的记录
+pub fn list_sync_history(conn: &Connection, project_id: Option<&str>, limit: i64) -> Result<Vec<SyncHistoryEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, provider, project_ids, started_at, duration_ms, success, pushed, pulled, conflicts, errors, files
+             FROM sync_history ORDER BY started_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            let project_ids: Option<String> = row.get(2)?;
+            let errors: Option<String> = row.get(9)?;
+            let files: Option<String> = row.get(10)?;
+            Ok(SyncHistoryEntry {
+                id: row.get(0)?,
+                provider: row.get(1)?,
+                project_ids: project_ids.and_then(|s| serde_json::from_str(&s).ok()),
+                started_at: row.get(3)?,
+                duration_ms: row.get(4)?,
+                success: row.get(5)?,
+                pushed: row.get(6)?,
+                pulled: row.get(7)?,
+                conflicts: row.get(8)?,
+                errors: errors.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default(),
+                files: files.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default(),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        let mut entry = row.map_err(|e| e.to_string())?;
+        if let Some(project_id) = project_id {
+            entry.files.retain(|f| f.project_id == project_id);
+            // 同步全部项目时，只保留该项目有变化或整次失败的记录
+            let relevant = match &entry.project_ids {
+                Some(ids) => ids.iter().any(|id| id == project_id),
+                None => !entry.files.is_empty() || !entry.success,
+            };
+            if !relevant {
+                continue;
+            }
+        }
+        entries.push(entry);
+        if entries.len() as i64 >= limit {
+            break;
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_entry_counts() {
+        let file = |path: &str, action: SyncAction, error: Option<&str>| SyncFileResult {
+            project_id: "p1".into(),
+            path: path.into(),
+            action,
+            error: error.map(str::to_string),
+        };
+        let result = SyncResult {
+            files: vec![
+                file("chapters/c1.json", SyncAction::Push, None),
+                file("chapters/c2.json", SyncAction::DeleteLocal, None),
+                file("characters/a.json", SyncAction::Pull, Some("写入失败")),
+            ],
+            ..Default::default()
+        };
+        let entry = SyncHistoryEntry::new("webdav", None, Utc::now(), &Ok(result));
+        assert_eq!((entry.pushed, entry.pulled), (1, 1));
+        assert_eq!(entry.errors, vec!["p1/characters/a.json: 写入失败"]);
+        assert_eq!(entry.files.len(), 3);
+
+        let failed = SyncHistoryEntry::new("webdav", Some(vec!["p1".into()]), Utc::now(), &Err("认证失败".into()));
+        assert!(!failed.success);
+        assert_eq!(failed.errors, vec!["认证失败"]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("history.db");
+        crate::database::init_database(&db_path).unwrap();
+        let conn = Connection::open(&db_path).unwrap();
+        record_sync_run(&conn, &entry).unwrap();
+        record_sync_run(&conn, &failed).unwrap();
+        assert_eq!(list_sync_history(&conn, None, 10).unwrap().len(), 2);
+        assert_eq!(list_sync_history(&conn, Some("p1"), 10).unwrap().len(), 2);
+        assert_eq!(list_sync_history(&conn, Some("p2"), 10).unwrap().len(), 1);
+        assert_eq!(list_sync_history(&conn, None, 1).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,712 @@
+use super::engine::{device_id, run_sync};
+use super::lan_crypto::{Role, SecureChannel, Spake2};
+use super::provider::{offline_error, RemoteEntry, SyncProvider};
+use super::{ConflictResolutionStrategy, SyncSelection};
+use crate::logger::Logger;
+use async_trait::async_trait;
+use base64::Engine as _;
+use chrono::Utc;
+use futures::{SinkExt, StreamExt};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+/// 局域网同步在 mDNS 上注册的服务类型
+pub const SERVICE_TYPE: &str = "_ainovelsync._tcp.local.";
+/// 已配对设备保存在 app_settings 中的键
+const PEERS_KEY: &str = "lan_sync_peers";
+/// 主机端同步存储使用的 provider 名称
+pub const HOST_STORE_NAME: &str = "lan-host";
+/// 配对码连续输错的次数上限，超过后本次开启的主机拒绝继续配对
+const MAX_PAIRING_FAILURES: usize = 5;
+
+/// 局域网内发现的设备
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanPeer {
+    pub device_id: String,
+    pub device_name: String,
+    pub host: String,
+    pub port: u16,
+    pub paired: bool,
+}
+
+/// 已配对的设备。token 由双方从配对时 SPAKE2 协商出的会话密钥各自派生，之后代替配对码参与协商
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairedDevice {
+    pub device_id: String,
+    pub device_name: String,
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub token: String,
+    pub paired_at: String,
+}
+
+pub fn load_paired_devices(conn: &Connection) -> Result<HashMap<String, PairedDevice>, String> {
+    let saved: Option<String> = conn
+        .query_row("SELECT value FROM app_settings WHERE key = ?", params![PEERS_KEY], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match saved {
+        Some(json) => serde_json::from_str(&json).map_err(|e| format!("已配对设备列表损坏: {}", e)),
+        None => Ok(HashMap::new()),
+    }
+}
+
+fn save_paired_devices(conn: &Connection, devices: &HashMap<String, PairedDevice>) -> Result<(), String> {
+    let json = serde_json::to_string(devices).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO app_settings (key, value, updated_at) VALUES (?, ?, ?)",
+        params![PEERS_KEY, json, Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn save_paired_device(conn: &Connection, device: &PairedDevice) -> Result<(), String> {
+    let mut devices = load_paired_devices(conn)?;
+    devices.insert(device.device_id.clone(), device.clone());
+    save_paired_devices(conn, &devices)
+}
+
+pub fn remove_paired_device(conn: &Connection, device_id: &str) -> Result<(), String> {
+    let mut devices = load_paired_devices(conn)?;
+    devices.remove(device_id);
+    save_paired_devices(conn, &devices)
+}
+
+/// 主机端显示给用户的 6 位配对码
+pub fn generate_pairing_code() -> String {
+    format!("{:06}", rand::random::<u32>() % 1_000_000)
+}
+
+/// 握手消息。双方用共享密钥（配对码或配对后的 token）做 SPAKE2 协商，再互发确认码；
+/// 之后的请求和响应都经 [`SecureChannel`] 加密
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Handshake {
+    Hello { device_id: String, device_name: String, paired: bool },
+    Challenge { device_id: String, device_name: String, spake: String },
+    Auth { spake: String, proof: String },
+    Welcome { proof: String },
+    Rejected { reason: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum LanOp {
+    /// 主机先把本地数据同步到存储中
+    Prepare,
+    List,
+    Upload,
+    Download,
+    Delete,
+    /// 客户端同步完成，主机把存储中的新变更应用到本地
+    Finish,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LanRequest {
+    op: LanOp,
+    #[serde(default)]
+    path: String,
+    /// base64 编码的文件内容
+    #[serde(default)]
+    data: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LanResponse {
+    ok: bool,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    entries: Vec<RemoteEntry>,
+    #[serde(default)]
+    data: Option<String>,
+}
+
+async fn send_json<S, T>(socket: &mut WebSocketStream<S>, value: &T) -> Result<(), String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let text = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    socket.send(Message::Text(text)).await.map_err(offline_error)
+}
+
+async fn recv_json<S, T>(socket: &mut WebSocketStream<S>) -> Result<T, String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: for<'de> Deserialize<'de>,
+{
+    while let Some(message) = socket.next().await {
+        match message.map_err(offline_error)? {
+            Message::Text(text) => return serde_json::from_str(&text).map_err(|e| format!("局域网同步消息无效: {}", e)),
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    Err(offline_error("连接已关闭"))
+}
+
+async fn send_sealed<S, T>(socket: &mut WebSocketStream<S>, channel: &mut SecureChannel, value: &T) -> Result<(), String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let json = serde_json::to_vec(value).map_err(|e| e.to_string())?;
+    socket.send(Message::Binary(channel.seal(&json)?)).await.map_err(offline_error)
+}
+
+async fn recv_sealed<S, T>(socket: &mut WebSocketStream<S>, channel: &mut SecureChannel) -> Result<T, String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: for<'de> Deserialize<'de>,
+{
+    while let Some(message) = socket.next().await {
+        match message.map_err(offline_error)? {
+            Message::Binary(sealed) => {
+                let json = channel.open(&sealed)?;
+                return serde_json::from_slice(&json).map_err(|e| format!("局域网同步消息无效: {}", e));
+            }
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    Err(offline_error("连接已关闭"))
+}
+
+/// 以本地目录作为同步存储，局域网主机用它保存变更日志
+pub struct LocalDirProvider {
+    name: String,
+    root: PathBuf,
+}
+
+impl LocalDirProvider {
+    pub fn new(name: &str, root: PathBuf) -> Self {
+        Self { name: name.to_string(), root }
+    }
+
+    fn resolve(&self, path: &str) -> Result<PathBuf, String> {
+        let relative = Path::new(path.trim_matches('/'));
+        if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+            return Err(format!("无效的同步路径: {}", path));
+        }
+        Ok(self.root.join(relative))
+    }
+}
+
+#[async_trait]
+impl SyncProvider for LocalDirProvider {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        tokio::fs::create_dir_all(&self.root).await.map_err(|e| format!("无法创建同步目录: {}", e))
+    }
+
+    async fn list(&self, dir: &str) -> Result<Vec<RemoteEntry>, String> {
+        let dir = dir.trim_matches('/');
+        let mut reader = match tokio::fs::read_dir(self.resolve(dir)?).await {
+            Ok(reader) => reader,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.to_string()),
+        };
+        let mut entries = Vec::new();
+        while let Some(entry) = reader.next_entry().await.map_err(|e| e.to_string())? {
+            let metadata = entry.metadata().await.map_err(|e| e.to_string())?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            entries.push(RemoteEntry {
+                path: if dir.is_empty() { name } else { format!("{}/{}", dir, name) },
+                is_dir: metadata.is_dir(),
+                size: metadata.is_file().then_some(metadata.len()),
+                etag: None,
+                modified: metadata.modified().ok().map(|t| chrono::DateTime::<Utc>::from(t).to_rfc3339()),
+            });
+        }
+        Ok(entries)
+    }
+
+    async fn upload(&self, path: &str, data: Vec<u8>) -> Result<(), String> {
+        let target = self.resolve(path)?;
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| e.to_string())?;
+        }
+        tokio::fs::write(&target, data).await.map_err(|e| e.to_string())
+    }
+
+    async fn download(&self, path: &str) -> Result<Option<Vec<u8>>, String> {
+        match tokio::fs::read(self.resolve(path)?).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), String> {
+        match tokio::fs::remove_file(self.resolve(path)?).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 主机端的运行参数
+pub struct LanHostContext {
+    pub db_path: PathBuf,
+    pub store: LocalDirProvider,
+    pub device_id: String,
+    pub device_name: String,
+    pub pairing_code: String,
+    pub strategy: ConflictResolutionStrategy,
+    pub selection: SyncSelection,
+    pairing_failures: AtomicUsize,
+    /// 同一时间只允许一次主机端同步
+    sync_lock: tokio::sync::Mutex<()>,
+}
+
+impl LanHostContext {
+    pub fn new(
+        db_path: PathBuf,
+        store_dir: PathBuf,
+        device_name: &str,
+        strategy: ConflictResolutionStrategy,
+        selection: SyncSelection,
+    ) -> Result<Self, String> {
+        let conn = crate::database::get_connection(&db_path).map_err(|e| e.to_string())?;
+        Ok(Self {
+            device_id: device_id(&conn)?,
+            db_path,
+            store: LocalDirProvider::new(HOST_STORE_NAME, store_dir),
+            device_name: device_name.to_string(),
+            pairing_code: generate_pairing_code(),
+            strategy,
+            selection,
+            pairing_failures: AtomicUsize::new(0),
+            sync_lock: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// 在主机本地数据和同步存储之间同步一次
+    async fn sync_store(&self) -> Result<(), String> {
+        let _guard = self.sync_lock.lock().await;
+        let result = run_sync(&self.db_path, &self.store, self.strategy, &self.selection, None).await?;
+        if result.success {
+            Ok(())
+        } else {
+            let errors: Vec<String> = result.files.iter().filter_map(|f| f.error.clone()).collect();
+            Err(format!("主机同步失败: {}", errors.join("; ")))
+        }
+    }
+}
+
+/// 正在运行的局域网同步主机
+pub struct LanHost {
+    pub port: u16,
+    pub device_id: String,
+    pub device_name: String,
+    pub pairing_code: String,
+    shutdown: Option<oneshot::Sender<()>>,
+    mdns: Option<ServiceDaemon>,
+}
+
+impl LanHost {
+    pub fn stop(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(mdns) = self.mdns.take() {
+            let _ = mdns.shutdown();
+        }
+    }
+}
+
+/// 开启局域网同步主机：监听随机端口，`advertise` 为 true 时通过 mDNS 广播
+pub async fn start_host(ctx: LanHostContext, advertise: bool) -> Result<LanHost, String> {
+    let logger = Logger::new().with_feature("lan_sync");
+    ctx.store.check().await?;
+    let listener = TcpListener::bind("0.0.0.0:0").await.map_err(|e| format!("无法开启局域网同步: {}", e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+
+    let mdns = if advertise {
+        let daemon = ServiceDaemon::new().map_err(|e| format!("mDNS 启动失败: {}", e))?;
+        let properties = [("id", ctx.device_id.as_str()), ("name", ctx.device_name.as_str())];
+        let info = ServiceInfo::new(
+            SERVICE_TYPE,
+            &ctx.device_id,
+            &format!("{}.local.", ctx.device_id),
+            "",
+            port,
+            &properties[..],
+        )
+        .map_err(|e| format!("mDNS 服务信息无效: {}", e))?
+        .enable_addr_auto();
+        daemon.register(info).map_err(|e| format!("mDNS 注册失败: {}", e))?;
+        Some(daemon)
+    } else {
+        None
+    };
+
+    let host = LanHost {
+        port,
+        device_id: ctx.device_id.clone(),
+        device_name: ctx.device_name.clone(),
+        pairing_code: ctx.pairing_code.clone(),
+        shutdown: None,
+        mdns,
+    };
+    let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+    let ctx = Arc::new(ctx);
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = &mut shutdown_rx => break,
+                accepted = listener.accept() => {
+                    let Ok((stream, addr)) = accepted else { continue };
+                    let ctx = ctx.clone();
+                    let logger = logger.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = serve_connection(stream, ctx).await {
+                            logger.warn(&format!("LAN sync session with {} ended: {}", addr, e));
+                        }
+                    });
+                }
+            }
+        }
+    });
+    Ok(LanHost { shutdown: Some(shutdown_tx), ..host })
+}
+
+async fn reject<S>(socket: &mut WebSocketStream<S>, paired: bool) -> Result<(), String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let reason = if paired { "设备未配对或配对已失效" } else { "配对码错误" };
+    send_json(socket, &Handshake::Rejected { reason: reason.to_string() }).await?;
+    Err(reason.to_string())
+}
+
+async fn serve_connection(stream: TcpStream, ctx: Arc<LanHostContext>) -> Result<(), String> {
+    let mut socket = tokio_tungstenite::accept_async(stream).await.map_err(offline_error)?;
+    let Handshake::Hello { device_id: peer_id, device_name: peer_name, paired } = recv_json(&mut socket).await? else {
+        return Err("握手消息无效".to_string());
+    };
+
+    // 配对尝试先计入失败次数，验证通过后再退回，并发连接也无法绕过上限
+    let secret = if paired {
+        let conn = crate::database::get_connection(&ctx.db_path).map_err(|e| e.to_string())?;
+        load_paired_devices(&conn)?.get(&peer_id).map(|d| d.token.clone())
+    } else if ctx
+        .pairing_failures
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < MAX_PAIRING_FAILURES).then_some(n + 1))
+        .is_ok()
+    {
+        Some(ctx.pairing_code.clone())
+    } else {
+        None
+    };
+    let Some(secret) = secret else {
+        return reject(&mut socket, paired).await;
+    };
+    let spake = Spake2::start(Role::Host, &secret);
+    send_json(
+        &mut socket,
+        &Handshake::Challenge { device_id: ctx.device_id.clone(), device_name: ctx.device_name.clone(), spake: spake.message() },
+    )
+    .await?;
+
+    let Handshake::Auth { spake: client_message, proof } = recv_json(&mut socket).await? else {
+        return Err("握手消息无效".to_string());
+    };
+    let keys = spake.finish(&client_message, &peer_id, &ctx.device_id)?;
+    if !keys.verify_proof(Role::Client, &proof) {
+        return reject(&mut socket, paired).await;
+    }
+    send_json(&mut socket, &Handshake::Welcome { proof: keys.proof(Role::Host) }).await?;
+    if !paired {
+        ctx.pairing_failures.fetch_sub(1, Ordering::SeqCst);
+        let conn = crate::database::get_connection(&ctx.db_path).map_err(|e| e.to_string())?;
+        save_paired_device(
+            &conn,
+            &PairedDevice {
+                device_id: peer_id,
+                device_name: peer_name,
+                token: keys.pairing_token(),
+                paired_at: Utc::now().to_rfc3339(),
+            },
+        )?;
+    }
+
+    let mut channel = keys.channel(Role::Host);
+    loop {
+        let request: LanRequest = match recv_sealed(&mut socket, &mut channel).await {
+            Ok(request) => request,
+            // 客户端断开
+            Err(_) => return Ok(()),
+        };
+        let response = match handle_request(&ctx, request).await {
+            Ok(response) => response,
+            Err(e) => LanResponse { ok: false, error: Some(e), ..Default::default() },
+        };
+        send_sealed(&mut socket, &mut channel, &response).await?;
+    }
+}
+
+async fn handle_request(ctx: &LanHostContext, request: LanRequest) -> Result<LanResponse, String> {
+    let mut response = LanResponse { ok: true, ..Default::default() };
+    match request.op {
+        LanOp::Prepare | LanOp::Finish => ctx.sync_store().await?,
+        LanOp::List => response.entries = ctx.store.list(&request.path).await?,
+        LanOp::Upload => {
+            let data = base64::engine::general_purpose::STANDARD
+                .decode(request.data.unwrap_or_default())
+                .map_err(|e| e.to_string())?;
+            ctx.store.upload(&request.path, data).await?
+        }
+        LanOp::Download => {
+            response.data = ctx
+                .store
+                .download(&request.path)
+                .await?
+                .map(|data| base64::engine::general_purpose::STANDARD.encode(data))
+        }
+        LanOp::Delete => ctx.store.delete(&request.path).await?,
+    }
+    Ok(response)
+}
+
+struct PeerSession {
+    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    channel: SecureChannel,
+}
+
+/// 连接局域网主机的同步服务
+pub struct LanPeerProvider {
+    pub peer: PairedDevice,
+    session: tokio::sync::Mutex<PeerSession>,
+}
+
+impl LanPeerProvider {
+    /// 连接并验证主机；提供 `pairing_code` 时进行首次配对，否则使用已保存的配对信息
+    pub async fn connect(
+        db_path: &Path,
+        host: &str,
+        port: u16,
+        device_name: &str,
+        pairing_code: Option<&str>,
+    ) -> Result<Self, String> {
+        let (own_id, paired_devices) = {
+            let conn = crate::database::get_connection(db_path).map_err(|e| e.to_string())?;
+            (device_id(&conn)?, load_paired_devices(&conn)?)
+        };
+        let address = if host.contains(':') { format!("[{}]", host) } else { host.to_string() };
+        let (mut socket, _) = tokio::time::timeout(
+            Duration::from_secs(10),
+            tokio_tungstenite::connect_async(format!("ws://{}:{}", address, port)),
+        )
+        .await
+        .map_err(|_| offline_error("连接超时"))?
+        .map_err(offline_error)?;
+
+        send_json(
+            &mut socket,
+            &Handshake::Hello {
+                device_id: own_id.clone(),
+                device_name: device_name.to_string(),
+                paired: pairing_code.is_none(),
+            },
+        )
+        .await?;
+        let (host_id, host_name, host_message) = match recv_json(&mut socket).await? {
+            Handshake::Challenge { device_id, device_name, spake } => (device_id, device_name, spake),
+            Handshake::Rejected { reason } => return Err(reason),
+            _ => return Err("握手消息无效".to_string()),
+        };
+        let secret = match pairing_code {
+            Some(code) => code.trim().to_string(),
+            None => paired_devices
+                .get(&host_id)
+                .map(|d| d.token.clone())
+                .ok_or_else(|| format!("尚未与设备 {} 配对", host_name))?,
+        };
+        let spake = Spake2::start(Role::Client, &secret);
+        let client_message = spake.message();
+        let keys = spake.finish(&host_message, &own_id, &host_id)?;
+        send_json(&mut socket, &Handshake::Auth { spake: client_message, proof: keys.proof(Role::Client) }).await?;
+        match recv_json(&mut socket).await? {
+            Handshake::Welcome { proof } if keys.verify_proof(Role::Host, &proof) => {}
+            Handshake::Welcome { .. } => return Err("对方设备验证失败".to_string()),
+            Handshake::Rejected { reason } => return Err(reason),
+            _ => return Err("握手消息无效".to_string()),
+        }
+
+        let peer = PairedDevice {
+            device_id: host_id,
+            device_name: host_name,
+            token: match pairing_code {
+                Some(_) => keys.pairing_token(),
+                None => secret,
+            },
+            paired_at: Utc::now().to_rfc3339(),
+        };
+        if pairing_code.is_some() {
+            let conn = crate::database::get_connection(db_path).map_err(|e| e.to_string())?;
+            save_paired_device(&conn, &peer)?;
+        }
+        let channel = keys.channel(Role::Client);
+        Ok(Self { peer, session: tokio::sync::Mutex::new(PeerSession { socket, channel }) })
+    }
+
+    async fn call(&self, op: LanOp, path: &str, data: Option<Vec<u8>>) -> Result<LanResponse, String> {
+        let request = LanRequest {
+            op,
+            path: path.to_string(),
+            data: data.map(|d| base64::engine::general_purpose::STANDARD.encode(d)),
+        };
+        let mut session = self.session.lock().await;
+        let PeerSession { socket, channel } = &mut *session;
+        send_sealed(socket, channel, &request).await?;
+        let response: LanResponse = recv_sealed(socket, channel).await?;
+        if response.ok {
+            Ok(response)
+        } else {
+            Err(response.error.unwrap_or_else(|| "局域网同步请求失败".to_string()))
+        }
+    }
+}
+
+#[async_trait]
+impl SyncProvider for LanPeerProvider {
+    fn name(&self) -> String {
+        format!("lan:{}", self.peer.device_id)
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        self.call(LanOp::Prepare, "", None).await.map(|_| ())
+    }
+
+    async fn list(&self, dir: &str) -> Result<Vec<RemoteEntry>, String> {
+        Ok(self.call(LanOp::List, dir, None).await?.entries)
+    }
+
+    async fn upload(&self, path: &str, data: Vec<u8>) -> Result<(), String> {
+        self.call(LanOp::Upload, path, Some(data)).await.map(|_| ())
+    }
+
+    async fn download(&self, path: &str) -> Result<Option<Vec<u8>>, String> {
+        self.call(LanOp::Download, path, None)
+            .await?
+            .data
+            .map(|d| base64::engine::general_purpose::STANDARD.decode(d).map_err(|e| e.to_string()))
+            .transpose()
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), String> {
+        self.call(LanOp::Delete, path, None).await.map(|_| ())
+    }
+
+    /// 通知主机应用本次上传的变更，然后断开
+    async fn finish(&self, _db_path: &Path, _project_ids: &[String]) -> Result<(), String> {
+        self.call(LanOp::Finish, "", None).await?;
+        let _ = self.session.lock().await.socket.close(None).await;
+        Ok(())
+    }
+}
+
+/// 在局域网内查找开启了同步主机的设备
+pub async fn discover_peers(timeout: Duration, own_device_id: &str) -> Result<Vec<LanPeer>, String> {
+    let daemon = ServiceDaemon::new().map_err(|e| format!("mDNS 启动失败: {}", e))?;
+    let receiver = daemon.browse(SERVICE_TYPE).map_err(|e| format!("mDNS 查找失败: {}", e))?;
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut peers: Vec<LanPeer> = Vec::new();
+    while let Ok(Ok(event)) = tokio::time::timeout_at(deadline, receiver.recv_async()).await {
+        let ServiceEvent::ServiceResolved(info) = event else { continue };
+        let Some(device_id) = info.get_property_val_str("id").map(str::to_string) else { continue };
+        // 优先使用 IPv4 地址
+        let mut addresses: Vec<_> = info.get_addresses().iter().collect();
+        addresses.sort_by_key(|a| !a.is_ipv4());
+        let Some(address) = addresses.first() else { continue };
+        if device_id == own_device_id || peers.iter().any(|p| p.device_id == device_id) {
+            continue;
+        }
+        peers.push(LanPeer {
+            device_name: info.get_property_val_str("name").unwrap_or(&device_id).to_string(),
+            device_id,
+            host: address.to_string(),
+            port: info.get_port(),
+            paired: false,
+        });
+    }
+    let _ = daemon.stop_browse(SERVICE_TYPE);
+    let _ = daemon.shutdown();
+    Ok(peers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_db(dir: &Path, name: &str) -> PathBuf {
+        let db_path = dir.join(name);
+        crate::database::init_database(&db_path).unwrap();
+        db_path
+    }
+
+    #[tokio::test]
+    async fn test_pair_and_sync_over_loopback() {
+        let dir = tempfile::tempdir().unwrap();
+        let host_db = dir.path().join("host.db");
+        let client_db = setup_db(dir.path(), "client.db");
+        {
+            let conn = crate::test_support::init_project_db(&host_db);
+            conn.execute(
+                "INSERT INTO chapters (id, project_id, title, content, created_at, updated_at) VALUES ('c1', 'p1', '第一章', '雨夜', 't0', 't1')",
+                [],
+            )
+            .unwrap();
+        }
+
+        let ctx = LanHostContext::new(
+            host_db.clone(),
+            dir.path().join("store"),
+            "台式机",
+            ConflictResolutionStrategy::TimestampBased,
+            SyncSelection::default(),
+        )
+        .unwrap();
+        let host = start_host(ctx, false).await.unwrap();
+
+        let wrong = LanPeerProvider::connect(&client_db, "127.0.0.1", host.port, "笔记本", Some("000000x")).await;
+        assert_eq!(wrong.err().as_deref(), Some("配对码错误"));
+
+        let peer = LanPeerProvider::connect(&client_db, "127.0.0.1", host.port, "笔记本", Some(&host.pairing_code))
+            .await
+            .unwrap();
+        assert_eq!(peer.peer.device_name, "台式机");
+        let result = run_sync(&client_db, &peer, ConflictResolutionStrategy::TimestampBased, &SyncSelection::default(), None)
+            .await
+            .unwrap();
+        assert!(result.success);
+        let title: String = Connection::open(&client_db)
+            .unwrap()
+            .query_row("SELECT title FROM chapters WHERE id = 'c1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(title, "第一章");
+
+        // 配对后不再需要配对码，两边保存的 token 一致
+        let again = LanPeerProvider::connect(&client_db, "127.0.0.1", host.port, "笔记本", None).await.unwrap();
+        let host_side = load_paired_devices(&Connection::open(&host_db).unwrap()).unwrap();
+        assert_eq!(host_side.values().next().unwrap().token, again.peer.token);
+        host.stop();
+    }
+}
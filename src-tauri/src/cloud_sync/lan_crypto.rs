@@ -0,0 +1,248 @@
+//! 局域网同步的配对认证和传输加密
+//!
+//! 6 位配对码只有一百万种可能，由它直接算出的证明一旦被抓包就能离线穷举。握手因此使用
+//! SPAKE2（Ristretto255）：双方各发一个用共享密钥遮蔽的临时公钥，窃听者无法据此验证猜测，
+//! 冒充的一方每次连接也只能试一个配对码。协商出的会话密钥再派生确认码、配对 token
+//! 以及两个方向各自的 AES-256-GCM 传输密钥。
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+
+const PROTOCOL: &[u8] = b"ai-novel-studio/lan-sync/spake2-ristretto255/v1";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 握手中的角色：发起连接的客户端或局域网主机
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Client,
+    Host,
+}
+
+impl Role {
+    /// 该角色遮蔽临时公钥用的点，离散对数未知
+    fn blinding(self) -> RistrettoPoint {
+        let label: &[u8] = match self {
+            Role::Client => b"M",
+            Role::Host => b"N",
+        };
+        let mut bytes = [0u8; 64];
+        bytes.copy_from_slice(&Sha512::new().chain_update(PROTOCOL).chain_update(label).finalize());
+        RistrettoPoint::from_uniform_bytes(&bytes)
+    }
+
+    fn peer(self) -> Role {
+        match self {
+            Role::Client => Role::Host,
+            Role::Host => Role::Client,
+        }
+    }
+}
+
+fn secret_scalar(secret: &str) -> Scalar {
+    let mut bytes = [0u8; 64];
+    bytes.copy_from_slice(
+        &Sha512::new().chain_update(PROTOCOL).chain_update(b"secret").chain_update(secret.as_bytes()).finalize(),
+    );
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+fn random_scalar() -> Scalar {
+    let mut bytes = [0u8; 64];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+/// 一次 SPAKE2 协商中本端的状态
+pub struct Spake2 {
+    role: Role,
+    secret: Scalar,
+    ephemeral: Scalar,
+    message: [u8; 32],
+}
+
+impl Spake2 {
+    /// 以共享密钥（配对码或配对 token）开始协商
+    pub fn start(role: Role, secret: &str) -> Self {
+        let secret = secret_scalar(secret);
+        let ephemeral = random_scalar();
+        let point = RistrettoPoint::mul_base(&ephemeral) + role.blinding() * secret;
+        Self { role, secret, ephemeral, message: point.compress().to_bytes() }
+    }
+
+    /// 发给对方的消息（base64）
+    pub fn message(&self) -> String {
+        STANDARD.encode(self.message)
+    }
+
+    /// 用对方的消息完成协商。密钥不一致时这里不会报错，要靠双方交换确认码发现
+    pub fn finish(self, peer_message: &str, client_id: &str, host_id: &str) -> Result<SessionKeys, String> {
+        let invalid = || "握手消息无效".to_string();
+        let peer_bytes = STANDARD.decode(peer_message).map_err(|_| invalid())?;
+        let peer_point = CompressedRistretto::from_slice(&peer_bytes)
+            .map_err(|_| invalid())?
+            .decompress()
+            .ok_or_else(invalid)?;
+        if peer_point.is_identity() {
+            return Err(invalid());
+        }
+        let shared = (peer_point - self.role.peer().blinding() * self.secret) * self.ephemeral;
+        if shared.is_identity() {
+            return Err(invalid());
+        }
+
+        let (client_message, host_message) = match self.role {
+            Role::Client => (&self.message[..], &peer_bytes[..]),
+            Role::Host => (&peer_bytes[..], &self.message[..]),
+        };
+        let shared = shared.compress().to_bytes();
+        let mut transcript = Sha256::new();
+        for part in [
+            PROTOCOL,
+            client_id.as_bytes(),
+            host_id.as_bytes(),
+            client_message,
+            host_message,
+            &shared[..],
+            self.secret.as_bytes(),
+        ] {
+            transcript.update((part.len() as u64).to_be_bytes());
+            transcript.update(part);
+        }
+        Ok(SessionKeys { hkdf: Hkdf::new(None, &transcript.finalize()) })
+    }
+}
+
+/// 协商出的会话密钥
+pub struct SessionKeys {
+    hkdf: Hkdf<Sha256>,
+}
+
+impl SessionKeys {
+    fn expand(&self, label: &str) -> [u8; 32] {
+        let mut key = [0u8; 32];
+        self.hkdf.expand(label.as_bytes(), &mut key).expect("32 bytes is within the HKDF output limit");
+        key
+    }
+
+    fn confirmation(&self, role: Role) -> HmacSha256 {
+        let key = self.expand(match role {
+            Role::Client => "confirm client",
+            Role::Host => "confirm host",
+        });
+        let mut mac = <HmacSha256 as Mac>::new_from_slice(&key).expect("HMAC accepts keys of any length");
+        mac.update(PROTOCOL);
+        mac
+    }
+
+    /// `role` 一方证明自己得到了相同会话密钥的确认码
+    pub fn proof(&self, role: Role) -> String {
+        STANDARD.encode(self.confirmation(role).finalize().into_bytes())
+    }
+
+    /// 以常数时间校验 `role` 一方发来的确认码
+    pub fn verify_proof(&self, role: Role, proof: &str) -> bool {
+        STANDARD.decode(proof).is_ok_and(|bytes| self.confirmation(role).verify_slice(&bytes).is_ok())
+    }
+
+    /// 首次配对后双方各自保存的 token，之后代替配对码参与协商
+    pub fn pairing_token(&self) -> String {
+        self.expand("pairing token").iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// 握手之后的加密通道
+    pub fn channel(&self, role: Role) -> SecureChannel {
+        let client_to_host = self.expand("client to host");
+        let host_to_client = self.expand("host to client");
+        let (outbound, inbound) = match role {
+            Role::Client => (client_to_host, host_to_client),
+            Role::Host => (host_to_client, client_to_host),
+        };
+        SecureChannel {
+            sealer: Aes256Gcm::new(&outbound.into()),
+            opener: Aes256Gcm::new(&inbound.into()),
+            sent: 0,
+            received: 0,
+        }
+    }
+}
+
+/// 每个方向使用独立密钥，nonce 取消息序号，被篡改、重放或乱序的消息都无法解密
+pub struct SecureChannel {
+    sealer: Aes256Gcm,
+    opener: Aes256Gcm,
+    sent: u64,
+    received: u64,
+}
+
+fn nonce(counter: u64) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[4..].copy_from_slice(&counter.to_be_bytes());
+    nonce
+}
+
+impl SecureChannel {
+    pub fn seal(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let sealed = self
+            .sealer
+            .encrypt(Nonce::from_slice(&nonce(self.sent)), plaintext)
+            .map_err(|_| "局域网同步消息加密失败".to_string())?;
+        self.sent += 1;
+        Ok(sealed)
+    }
+
+    pub fn open(&mut self, sealed: &[u8]) -> Result<Vec<u8>, String> {
+        let plaintext = self
+            .opener
+            .decrypt(Nonce::from_slice(&nonce(self.received)), sealed)
+            .map_err(|_| "局域网同步消息校验失败".to_string())?;
+        self.received += 1;
+        Ok(plaintext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn negotiate(client_secret: &str, host_secret: &str) -> (SessionKeys, SessionKeys) {
+        let client = Spake2::start(Role::Client, client_secret);
+        let host = Spake2::start(Role::Host, host_secret);
+        let (client_message, host_message) = (client.message(), host.message());
+        (
+            client.finish(&host_message, "laptop", "desktop").unwrap(),
+            host.finish(&client_message, "laptop", "desktop").unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_spake2_keys_and_channel() {
+        let (client, host) = negotiate("123456", "123456");
+        assert!(host.verify_proof(Role::Client, &client.proof(Role::Client)));
+        assert!(client.verify_proof(Role::Host, &host.proof(Role::Host)));
+        // 确认码区分方向，不能被反射回去
+        assert!(!host.verify_proof(Role::Host, &client.proof(Role::Client)));
+        assert_eq!(client.pairing_token(), host.pairing_token());
+
+        let mut outbound = client.channel(Role::Client);
+        let mut inbound = host.channel(Role::Host);
+        let first = outbound.seal(b"list").unwrap();
+        assert_ne!(&first[..], b"list");
+        assert_eq!(inbound.open(&first).unwrap(), b"list");
+        // 重放的消息序号不对，解密失败
+        assert!(inbound.open(&first).is_err());
+
+        let (client, host) = negotiate("123456", "654321");
+        assert!(!host.verify_proof(Role::Client, &client.proof(Role::Client)));
+        assert!(Spake2::start(Role::Client, "123456").finish("AAAA", "laptop", "desktop").is_err());
+    }
+}
@@ -1,6 +1,18 @@
+pub mod provider;
+pub mod webdav;
+pub mod engine;
+pub mod git;
+pub mod history;
+pub mod lan;
+pub mod lan_crypto;
+
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+pub use engine::SyncAction;
+pub use history::SyncHistoryEntry;
+pub use provider::{create_provider, SyncProvider};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncConfig {
     pub provider_type: ProviderType,
@@ -8,25 +20,113 @@ pub struct SyncConfig {
     pub sync_interval_seconds: u64,
     pub auto_sync: bool,
     pub conflict_resolution: ConflictResolutionStrategy,
+    /// 参与同步的项目和数据类型
+    #[serde(default)]
+    pub selection: SyncSelection,
+}
+
+/// 可单独开关同步的数据类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SyncDataClass {
+    /// 章节、角色、世界观、大纲等正文数据
+    Manuscript,
+    /// 版本快照和版本差异
+    Snapshots,
+    /// 分镜场景、角色设定图等生成的图片视频
+    Media,
+    /// 创作蓝图等项目设置
+    Settings,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncSelection {
+    /// 为空表示同步全部项目
+    #[serde(default)]
+    pub project_ids: Option<Vec<String>>,
+    #[serde(default = "default_data_classes")]
+    pub data_classes: Vec<SyncDataClass>,
+}
+
+fn default_data_classes() -> Vec<SyncDataClass> {
+    vec![SyncDataClass::Manuscript, SyncDataClass::Settings]
+}
+
+impl Default for SyncSelection {
+    fn default() -> Self {
+        Self { project_ids: None, data_classes: default_data_classes() }
+    }
+}
+
+impl SyncSelection {
+    pub fn includes_project(&self, project_id: &str) -> bool {
+        self.project_ids.as_ref().is_none_or(|ids| ids.iter().any(|id| id == project_id))
+    }
+
+    pub fn includes(&self, class: SyncDataClass) -> bool {
+        self.data_classes.contains(&class)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SyncStatus {
     Idle,
     Syncing,
+    /// 同步服务不可达，本地变更已进入离线队列
+    Offline,
     Error(String),
 }
 
+/// `cloud_sync_get_status` 的返回值
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncStatusReport {
+    pub status: SyncStatus,
+    /// 离线队列中等待上传的记录数
+    pub queued_changes: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SyncResult {
     pub success: bool,
     pub synced_files: Vec<String>,
+    /// 每个发生变化的文件的处理结果
+    #[serde(default)]
+    pub files: Vec<SyncFileResult>,
+    #[serde(default)]
+    pub conflicts: Vec<SyncConflict>,
+    /// 同步服务不可达，本地变更已进入离线队列
+    #[serde(default)]
+    pub offline: bool,
+    /// 离线队列中等待上传的记录数
+    #[serde(default)]
+    pub queued_changes: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncFileResult {
+    pub project_id: String,
+    /// 相对于项目同步目录的路径，为空表示整个项目同步失败
+    pub path: String,
+    pub action: SyncAction,
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncConflict {
     pub file_path: String,
+    /// both_modified / delete_modify
     pub conflict_type: String,
+    #[serde(default)]
+    pub project_id: String,
+    #[serde(default)]
+    pub local_updated_at: Option<String>,
+    #[serde(default)]
+    pub remote_updated_at: Option<String>,
+    /// 保存后的冲突 ID，用于 `cloud_sync_resolve_conflict`
+    #[serde(default)]
+    pub conflict_id: Option<String>,
+    /// Merge 策略下章节正文的逐段合并报告
+    #[serde(default)]
+    pub merge: Option<crate::text_merge::MergeResult>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -45,6 +145,8 @@ pub enum ProviderType {
     OneDrive,
     iCloud,
     WebDAV,
+    /// 任意 Git 远端（GitHub、Gitea 等），章节以 Markdown 形式纳入版本历史
+    Git,
     Custom,
 }
 
@@ -56,6 +158,7 @@ impl Default for SyncConfig {
             sync_interval_seconds: 300,
             auto_sync: true,
             conflict_resolution: ConflictResolutionStrategy::AskUser,
+            selection: SyncSelection::default(),
         }
     }
 }
@@ -0,0 +1,63 @@
+use super::git::GitProvider;
+use super::webdav::WebDavProvider;
+use super::{ProviderType, SyncConfig};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 远端目录中的一个条目
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteEntry {
+    /// 相对于同步根目录的路径，不以 `/` 开头或结尾
+    pub path: String,
+    pub is_dir: bool,
+    pub size: Option<u64>,
+    pub etag: Option<String>,
+    pub modified: Option<String>,
+}
+
+/// 同步传输层，路径都相对于同步根目录，用 `/` 分隔
+#[async_trait]
+pub trait SyncProvider: Send + Sync {
+    fn name(&self) -> String;
+
+    /// 检查连接和认证是否可用
+    async fn check(&self) -> Result<(), String>;
+
+    /// 列出目录的直接子项，目录不存在时返回空列表
+    async fn list(&self, dir: &str) -> Result<Vec<RemoteEntry>, String>;
+
+    /// 上传文件，自动创建父目录
+    async fn upload(&self, path: &str, data: Vec<u8>) -> Result<(), String>;
+
+    /// 下载文件，文件不存在时返回 None
+    async fn download(&self, path: &str) -> Result<Option<Vec<u8>>, String>;
+
+    /// 删除文件，文件不存在时视为成功
+    async fn delete(&self, path: &str) -> Result<(), String>;
+
+    /// 一次同步结束后调用，`project_ids` 为本次同步的项目；用于提交、推送或通知对端
+    async fn finish(&self, _db_path: &Path, _project_ids: &[String]) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// 网络不可达时 provider 返回的错误前缀，同步引擎据此把本地变更转入离线队列
+pub const OFFLINE_ERROR: &str = "网络不可用";
+
+pub fn offline_error(detail: impl std::fmt::Display) -> String {
+    format!("{}: {}", OFFLINE_ERROR, detail)
+}
+
+pub fn is_offline_error(error: &str) -> bool {
+    error.starts_with(OFFLINE_ERROR)
+}
+
+/// `data_dir` 为应用数据目录，需要本地工作副本的 provider 在其中存放数据
+pub fn create_provider(config: &SyncConfig, data_dir: &Path) -> Result<Box<dyn SyncProvider>, String> {
+    match config.provider_type {
+        ProviderType::WebDAV => Ok(Box::new(WebDavProvider::from_credentials(&config.credentials)?)),
+        ProviderType::Git => Ok(Box::new(GitProvider::from_credentials(&config.credentials, data_dir)?)),
+        other => Err(format!("暂不支持的同步服务: {:?}", other)),
+    }
+}
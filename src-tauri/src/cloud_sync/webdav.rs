@@ -0,0 +1,330 @@
+use super::provider::{offline_error, RemoteEntry, SyncProvider};
+use async_trait::async_trait;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use reqwest::{Method, StatusCode, Url};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// 未配置 `root` 时在 WebDAV 上使用的目录
+const DEFAULT_ROOT: &str = "AINovelStudio";
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop><d:resourcetype/><d:getcontentlength/><d:getetag/><d:getlastmodified/></d:prop>
+</d:propfind>"#;
+
+/// WebDAV 同步服务（坚果云、Nextcloud、群晖等）。
+/// 凭据字段：`url`、`username`、`password`，可选 `root`
+pub struct WebDavProvider {
+    client: reqwest::Client,
+    /// 同步根目录的地址，以 `/` 结尾
+    root: Url,
+    username: String,
+    password: String,
+}
+
+impl WebDavProvider {
+    pub fn from_credentials(credentials: &HashMap<String, String>) -> Result<Self, String> {
+        let url = credentials
+            .get("url")
+            .map(|u| u.trim())
+            .filter(|u| !u.is_empty())
+            .ok_or("WebDAV 地址未配置")?;
+        let mut root = Url::parse(url).map_err(|e| format!("WebDAV 地址无效: {}", e))?;
+        if !matches!(root.scheme(), "http" | "https") {
+            return Err(format!("WebDAV 地址必须是 http(s): {}", url));
+        }
+        let root_dir = credentials.get("root").map(|r| r.trim_matches('/')).unwrap_or(DEFAULT_ROOT);
+        {
+            let mut segments = root.path_segments_mut().map_err(|_| "WebDAV 地址无效".to_string())?;
+            segments.pop_if_empty();
+            segments.extend(root_dir.split('/').filter(|s| !s.is_empty()));
+            segments.push("");
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            client,
+            root,
+            username: credentials.get("username").cloned().unwrap_or_default(),
+            password: credentials.get("password").cloned().unwrap_or_default(),
+        })
+    }
+
+    fn url(&self, path: &str, is_dir: bool) -> Url {
+        let mut url = self.root.clone();
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.pop_if_empty();
+            segments.extend(path.split('/').filter(|s| !s.is_empty()));
+            if is_dir {
+                segments.push("");
+            }
+        }
+        url
+    }
+
+    fn request(&self, method: Method, url: Url) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, url);
+        if self.username.is_empty() {
+            request
+        } else {
+            request.basic_auth(&self.username, Some(&self.password))
+        }
+    }
+
+    async fn propfind(&self, path: &str, depth: &str) -> Result<Option<Vec<RemoteEntry>>, String> {
+        let method = Method::from_bytes(b"PROPFIND").map_err(|e| e.to_string())?;
+        let response = self
+            .request(method, self.url(path, true))
+            .header("Depth", depth)
+            .header(reqwest::header::CONTENT_TYPE, "application/xml; charset=utf-8")
+            .body(PROPFIND_BODY)
+            .send()
+            .await
+            .map_err(request_error)?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.as_u16() == 207 => {
+                let body = response.text().await.map_err(|e| e.to_string())?;
+                Ok(Some(parse_multistatus(&body, self.root.path())?))
+            }
+            status => Err(status_error("PROPFIND", path, status)),
+        }
+    }
+
+    /// 逐级创建目录，已存在（405）视为成功
+    async fn ensure_dir(&self, dir: &str) -> Result<(), String> {
+        let method = Method::from_bytes(b"MKCOL").map_err(|e| e.to_string())?;
+        let mut current = String::new();
+        let root_and_parts = std::iter::once("").chain(dir.split('/').filter(|s| !s.is_empty()));
+        for part in root_and_parts {
+            if !part.is_empty() {
+                if !current.is_empty() {
+                    current.push('/');
+                }
+                current.push_str(part);
+            }
+            let response = self
+                .request(method.clone(), self.url(&current, true))
+                .send()
+                .await
+                .map_err(request_error)?;
+            let status = response.status();
+            if !(status.is_success() || status == StatusCode::METHOD_NOT_ALLOWED) {
+                return Err(status_error("MKCOL", &current, status));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 连接失败或超时视为离线，其余为请求错误
+fn request_error(e: reqwest::Error) -> String {
+    if e.is_connect() || e.is_timeout() {
+        offline_error(e)
+    } else {
+        format!("WebDAV 请求失败: {}", e)
+    }
+}
+
+fn status_error(method: &str, path: &str, status: StatusCode) -> String {
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => format!("WebDAV 认证失败 ({})", status),
+        StatusCode::INSUFFICIENT_STORAGE => "WebDAV 空间不足".to_string(),
+        _ => format!("WebDAV {} {} 失败: {}", method, path, status),
+    }
+}
+
+#[async_trait]
+impl SyncProvider for WebDavProvider {
+    fn name(&self) -> String {
+        "webdav".to_string()
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        if self.propfind("", "0").await?.is_none() {
+            self.ensure_dir("").await?;
+        }
+        Ok(())
+    }
+
+    async fn list(&self, dir: &str) -> Result<Vec<RemoteEntry>, String> {
+        let dir = dir.trim_matches('/');
+        let entries = self.propfind(dir, "1").await?.unwrap_or_default();
+        Ok(entries.into_iter().filter(|e| e.path != dir).collect())
+    }
+
+    async fn upload(&self, path: &str, data: Vec<u8>) -> Result<(), String> {
+        let response = self
+            .request(Method::PUT, self.url(path, false))
+            .body(data.clone())
+            .send()
+            .await
+            .map_err(request_error)?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        // 父目录不存在时服务端返回 409，建好目录后重试一次
+        if status == StatusCode::CONFLICT || status == StatusCode::NOT_FOUND {
+            if let Some((parent, _)) = path.rsplit_once('/') {
+                self.ensure_dir(parent).await?;
+                let response = self
+                    .request(Method::PUT, self.url(path, false))
+                    .body(data)
+                    .send()
+                    .await
+                    .map_err(request_error)?;
+                if response.status().is_success() {
+                    return Ok(());
+                }
+                return Err(status_error("PUT", path, response.status()));
+            }
+        }
+        Err(status_error("PUT", path, status))
+    }
+
+    async fn download(&self, path: &str) -> Result<Option<Vec<u8>>, String> {
+        let response = self
+            .request(Method::GET, self.url(path, false))
+            .send()
+            .await
+            .map_err(request_error)?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(Some(response.bytes().await.map_err(|e| e.to_string())?.to_vec())),
+            status => Err(status_error("GET", path, status)),
+        }
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), String> {
+        let response = self
+            .request(Method::DELETE, self.url(path, false))
+            .send()
+            .await
+            .map_err(request_error)?;
+        match response.status() {
+            status if status.is_success() || status == StatusCode::NOT_FOUND => Ok(()),
+            status => Err(status_error("DELETE", path, status)),
+        }
+    }
+}
+
+/// 解析 PROPFIND 返回的 multistatus，`root_path` 为同步根目录的 URL 路径，
+/// 返回的路径相对于它。不同服务端的命名空间前缀不同，只按本地名匹配
+pub fn parse_multistatus(xml: &str, root_path: &str) -> Result<Vec<RemoteEntry>, String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let root_path = urlencoding::decode(root_path).map(|p| p.into_owned()).unwrap_or_else(|_| root_path.to_string());
+    let root_path = root_path.trim_end_matches('/');
+
+    let mut entries = Vec::new();
+    let mut current: Option<RemoteEntry> = None;
+    let mut element = String::new();
+    loop {
+        match reader.read_event().map_err(|e| format!("WebDAV 响应解析失败: {}", e))? {
+            Event::Start(e) => {
+                element = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                match element.as_str() {
+                    "response" => {
+                        current = Some(RemoteEntry { path: String::new(), is_dir: false, size: None, etag: None, modified: None })
+                    }
+                    "collection" => {
+                        if let Some(entry) = current.as_mut() {
+                            entry.is_dir = true;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Empty(e) if e.local_name().as_ref() == b"collection" => {
+                if let Some(entry) = current.as_mut() {
+                    entry.is_dir = true;
+                }
+            }
+            Event::Text(t) => {
+                let Some(entry) = current.as_mut() else { continue };
+                let text = t.unescape().map_err(|e| e.to_string())?.trim().to_string();
+                match element.as_str() {
+                    "href" => {
+                        // href 可能是完整 URL，也可能只有路径
+                        let path = match Url::parse(&text) {
+                            Ok(url) => url.path().to_string(),
+                            Err(_) => text,
+                        };
+                        let path = urlencoding::decode(&path).map(|p| p.into_owned()).unwrap_or(path);
+                        let relative = path.strip_prefix(root_path).unwrap_or(&path);
+                        entry.path = relative.trim_matches('/').to_string();
+                    }
+                    "getcontentlength" => entry.size = text.parse().ok(),
+                    "getetag" => entry.etag = Some(text.trim_matches('"').to_string()),
+                    "getlastmodified" => entry.modified = Some(text),
+                    _ => {}
+                }
+            }
+            Event::End(e) => {
+                if e.local_name().as_ref() == b"response" {
+                    if let Some(entry) = current.take() {
+                        entries.push(entry);
+                    }
+                }
+                element.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_multistatus() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:multistatus xmlns:D="DAV:">
+  <D:response>
+    <D:href>/dav/AINovelStudio/projects/</D:href>
+    <D:propstat><D:prop><D:resourcetype><D:collection/></D:resourcetype></D:prop></D:propstat>
+  </D:response>
+  <D:response>
+    <D:href>https://dav.example.com/dav/AINovelStudio/projects/%E7%AC%AC%E4%B8%80.json</D:href>
+    <D:propstat><D:prop>
+      <D:resourcetype/>
+      <D:getcontentlength>42</D:getcontentlength>
+      <D:getetag>"abc"</D:getetag>
+      <D:getlastmodified>Tue, 01 Oct 2024 08:00:00 GMT</D:getlastmodified>
+    </D:prop></D:propstat>
+  </D:response>
+</D:multistatus>"#;
+        let entries = parse_multistatus(xml, "/dav/AINovelStudio/").unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].path, "projects");
+        assert!(entries[0].is_dir);
+        assert_eq!(entries[1].path, "projects/第一.json");
+        assert!(!entries[1].is_dir);
+        assert_eq!(entries[1].size, Some(42));
+        assert_eq!(entries[1].etag.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn test_urls_under_root() {
+        let credentials = HashMap::from([
+            ("url".to_string(), "https://dav.example.com/dav".to_string()),
+            ("root".to_string(), "小说/备份".to_string()),
+        ]);
+        let provider = WebDavProvider::from_credentials(&credentials).unwrap();
+        assert_eq!(
+            provider.url("projects/p1/manifest.json", false).as_str(),
+            "https://dav.example.com/dav/%E5%B0%8F%E8%AF%B4/%E5%A4%87%E4%BB%BD/projects/p1/manifest.json"
+        );
+        assert!(provider.url("projects", true).as_str().ends_with("/projects/"));
+    }
+}
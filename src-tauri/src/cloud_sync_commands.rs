@@ -1,12 +1,42 @@
-use crate::cloud_sync::{SyncConfig, SyncStatus, SyncResult, ConflictResolutionStrategy, ProviderType};
+use crate::cloud_sync::engine::{
+    list_pending_conflicts, outbox_len, reset_applied_batches, resolve_pending_conflict, run_sync, ConflictChoice,
+};
+use crate::cloud_sync::history::{list_sync_history, record_sync_run};
+use crate::cloud_sync::lan::{
+    discover_peers, load_paired_devices, remove_paired_device, start_host, LanHost, LanHostContext, LanPeer, LanPeerProvider,
+    PairedDevice,
+};
+use crate::cloud_sync::{
+    create_provider, SyncConfig, SyncConflict, SyncHistoryEntry, SyncProvider, SyncStatus, SyncStatusReport,
+};
 use crate::logger::Logger;
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+
+/// 同步配置保存在 app_settings 中的键
+const SYNC_CONFIG_KEY: &str = "cloud_sync_config";
 
 #[derive(Clone)]
-pub struct CloudSyncState;
+pub struct CloudSyncState {
+    config: Arc<Mutex<Option<SyncConfig>>>,
+    status: Arc<Mutex<SyncStatus>>,
+    lan_host: Arc<Mutex<Option<LanHost>>>,
+}
 
 impl CloudSyncState {
     pub fn new() -> Self {
-        Self
+        Self {
+            config: Arc::new(Mutex::new(None)),
+            status: Arc::new(Mutex::new(SyncStatus::Idle)),
+            lan_host: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    fn set_status(&self, status: SyncStatus) {
+        *self.status.lock().unwrap() = status;
     }
 }
 
@@ -16,56 +46,194 @@ impl Default for CloudSyncState {
     }
 }
 
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
+
+fn get_data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))
+}
+
+/// 读取同步配置：优先使用内存中的，其次是数据库中保存的
+fn load_config(app: &AppHandle, state: &CloudSyncState) -> Result<SyncConfig, String> {
+    if let Some(config) = state.config.lock().unwrap().clone() {
+        return Ok(config);
+    }
+    let conn = crate::database::get_connection(&get_db_path(app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    let saved: Option<String> = conn
+        .query_row("SELECT value FROM app_settings WHERE key = ?", params![SYNC_CONFIG_KEY], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let config = match saved {
+        Some(json) => serde_json::from_str(&json).map_err(|e| format!("同步配置损坏: {}", e))?,
+        None => SyncConfig::default(),
+    };
+    *state.config.lock().unwrap() = Some(config.clone());
+    Ok(config)
+}
+
+fn save_config(app: &AppHandle, state: &CloudSyncState, config: &SyncConfig) -> Result<(), String> {
+    let conn = crate::database::get_connection(&get_db_path(app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    let json = serde_json::to_string(config).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO app_settings (key, value, updated_at) VALUES (?, ?, ?)",
+        params![SYNC_CONFIG_KEY, json, Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| e.to_string())?;
+    *state.config.lock().unwrap() = Some(config.clone());
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn cloud_sync_configure(
-    _config: SyncConfig,
-    _state: tauri::State<'_, CloudSyncState>,
+    app: AppHandle,
+    config: SyncConfig,
+    state: tauri::State<'_, CloudSyncState>,
 ) -> Result<(), String> {
     let logger = Logger::new().with_feature("cloud_sync");
-    logger.info("Configure cloud sync - placeholder");
+    logger.info(&format!("Configure cloud sync: {:?}", config.provider_type));
+    let previous = load_config(&app, &state)?.selection;
+    save_config(&app, &state, &config)?;
+
+    // 新加入同步的项目或数据类型需要重新读取远端批次
+    let selection = &config.selection;
+    let widened = selection.data_classes.iter().any(|c| !previous.includes(*c))
+        || match (&previous.project_ids, &selection.project_ids) {
+            (Some(_), None) => true,
+            (Some(before), Some(after)) => after.iter().any(|id| !before.contains(id)),
+            _ => false,
+        };
+    if widened {
+        let conn = crate::database::get_connection(&get_db_path(&app)?)
+            .map_err(|e| format!("Failed to get database connection: {}", e))?;
+        reset_applied_batches(&conn)?;
+        logger.info("Sync selection widened, remote journal will be replayed");
+    }
     Ok(())
 }
 
 #[tauri::command]
 pub async fn cloud_sync_get_config(
-    _state: tauri::State<'_, CloudSyncState>,
+    app: AppHandle,
+    state: tauri::State<'_, CloudSyncState>,
 ) -> Result<SyncConfig, String> {
-    Ok(SyncConfig {
-        provider_type: ProviderType::Dropbox,
-        credentials: std::collections::HashMap::new(),
-        sync_interval_seconds: 300,
-        auto_sync: true,
-        conflict_resolution: ConflictResolutionStrategy::AskUser,
-    })
+    load_config(&app, &state)
 }
 
+/// 合并传入的凭据并测试连接，成功后保存到配置
 #[tauri::command]
 pub async fn cloud_sync_authenticate(
-    _credentials: serde_json::Value,
-    _state: tauri::State<'_, CloudSyncState>,
+    app: AppHandle,
+    credentials: serde_json::Value,
+    state: tauri::State<'_, CloudSyncState>,
 ) -> Result<String, String> {
     let logger = Logger::new().with_feature("cloud_sync");
-    logger.info("Authenticate - placeholder");
-    Ok("token_placeholder".to_string())
+    let mut config = load_config(&app, &state)?;
+    if let Some(object) = credentials.as_object() {
+        for (key, value) in object {
+            let value = value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string());
+            config.credentials.insert(key.clone(), value);
+        }
+    }
+
+    let provider = create_provider(&config, &get_data_dir(&app)?)?;
+    provider.check().await.map_err(|e| {
+        logger.error(&format!("Authenticate failed: {}", e));
+        e
+    })?;
+    save_config(&app, &state, &config)?;
+    logger.info(&format!("Authenticated with {}", provider.name()));
+    Ok(provider.name())
 }
 
+/// 同步指定项目（为空时同步全部项目），返回序列化的 SyncResult
 #[tauri::command]
 pub async fn cloud_sync_start(
-    _state: tauri::State<'_, CloudSyncState>,
+    app: AppHandle,
+    project_ids: Option<Vec<String>>,
+    state: tauri::State<'_, CloudSyncState>,
 ) -> Result<String, String> {
     let logger = Logger::new().with_feature("cloud_sync");
-    logger.info("Start sync - placeholder");
-    Ok(serde_json::to_string(&SyncResult {
-        success: true,
-        synced_files: vec![],
-    }).unwrap())
+    if matches!(*state.status.lock().unwrap(), SyncStatus::Syncing) {
+        return Err("同步正在进行中".to_string());
+    }
+
+    let config = load_config(&app, &state)?;
+    let provider = create_provider(&config, &get_data_dir(&app)?)?;
+    let db_path = get_db_path(&app)?;
+    state.set_status(SyncStatus::Syncing);
+    logger.info(&format!("Start sync with {}", provider.name()));
+
+    let started_at = Utc::now();
+    let outcome = run_sync(
+        &db_path,
+        provider.as_ref(),
+        config.conflict_resolution,
+        &config.selection,
+        project_ids.clone(),
+    )
+    .await;
+    let entry = SyncHistoryEntry::new(&provider.name(), project_ids, started_at, &outcome);
+    if let Err(e) = crate::database::get_connection(&db_path)
+        .map_err(|e| e.to_string())
+        .and_then(|conn| record_sync_run(&conn, &entry))
+    {
+        logger.error(&format!("Failed to record sync history: {}", e));
+    }
+
+    match outcome {
+        Ok(result) if result.offline => {
+            state.set_status(SyncStatus::Offline);
+            logger.warn(&format!("Sync service unreachable, {} changes queued", result.queued_changes));
+            serde_json::to_string(&result).map_err(|e| e.to_string())
+        }
+        Ok(result) => {
+            state.set_status(SyncStatus::Idle);
+            logger.info(&format!(
+                "Sync finished: {} files, {} conflicts, success: {}",
+                result.synced_files.len(),
+                result.conflicts.len(),
+                result.success
+            ));
+            serde_json::to_string(&result).map_err(|e| e.to_string())
+        }
+        Err(e) => {
+            logger.error(&format!("Sync failed: {}", e));
+            state.set_status(SyncStatus::Error(e.clone()));
+            Err(e)
+        }
+    }
 }
 
+/// 当前同步状态和离线队列长度
 #[tauri::command]
 pub async fn cloud_sync_get_status(
-    _state: tauri::State<'_, CloudSyncState>,
-) -> Result<SyncStatus, String> {
-    Ok(SyncStatus::Idle)
+    app: AppHandle,
+    state: tauri::State<'_, CloudSyncState>,
+) -> Result<SyncStatusReport, String> {
+    let config = load_config(&app, &state)?;
+    let queued_changes = match create_provider(&config, &get_data_dir(&app)?) {
+        Ok(provider) => {
+            let conn = crate::database::get_connection(&get_db_path(&app)?)
+                .map_err(|e| format!("Failed to get database connection: {}", e))?;
+            outbox_len(&conn, &provider.name())?
+        }
+        Err(_) => 0,
+    };
+    Ok(SyncStatusReport { status: state.status.lock().unwrap().clone(), queued_changes })
 }
 
 #[tauri::command]
@@ -86,13 +254,214 @@ pub async fn cloud_sync_stop_auto(
     Ok(())
 }
 
+#[tauri::command]
+pub async fn cloud_sync_get_conflicts(
+    app: AppHandle,
+    project_id: Option<String>,
+) -> Result<Vec<SyncConflict>, String> {
+    let conn = crate::database::get_connection(&get_db_path(&app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    list_pending_conflicts(&conn, project_id.as_deref())
+}
+
+/// 最近的同步记录，按时间倒序；可按项目过滤
+#[tauri::command]
+pub async fn cloud_sync_get_history(
+    app: AppHandle,
+    project_id: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<SyncHistoryEntry>, String> {
+    let conn = crate::database::get_connection(&get_db_path(&app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    list_sync_history(&conn, project_id.as_deref(), limit.unwrap_or(50))
+}
+
+/// 解决同步冲突。`conflict_data` 包含 `conflict_id`，strategy 为 merge 时还需要逐段的 `resolutions`；
+/// strategy 取 local / remote / merge。返回最终写入的记录，记录被删除时返回 null
 #[tauri::command]
 pub async fn cloud_sync_resolve_conflict(
-    _conflict_data: serde_json::Value,
-    _strategy: String,
+    app: AppHandle,
+    conflict_data: serde_json::Value,
+    strategy: String,
     _state: tauri::State<'_, CloudSyncState>,
 ) -> Result<serde_json::Value, String> {
     let logger = Logger::new().with_feature("cloud_sync");
-    logger.info("Resolve conflict - placeholder");
-    Ok(serde_json::Value::Null)
+    let conflict_id = conflict_data
+        .get("conflict_id")
+        .and_then(|v| v.as_str())
+        .ok_or("缺少 conflict_id")?
+        .to_string();
+    let choice = match strategy.to_lowercase().as_str() {
+        "local" | "preferlocal" | "prefer_local" => ConflictChoice::Local,
+        "remote" | "preferremote" | "prefer_remote" => ConflictChoice::Remote,
+        "merge" => ConflictChoice::Merge {
+            resolutions: serde_json::from_value(conflict_data.get("resolutions").cloned().unwrap_or_default())
+                .map_err(|e| format!("resolutions 格式错误: {}", e))?,
+        },
+        other => return Err(format!("未知的冲突处理方式: {}", other)),
+    };
+
+    let mut conn = crate::database::get_connection(&get_db_path(&app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let row = resolve_pending_conflict(&tx, &conflict_id, &choice)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    logger.info(&format!("Resolved conflict {} with {}", conflict_id, strategy));
+    Ok(row.map(serde_json::Value::Object).unwrap_or(serde_json::Value::Null))
+}
+
+/// 局域网同步中本机显示的名称
+fn default_device_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "AI Novel Studio".to_string())
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LanHostInfo {
+    pub device_id: String,
+    pub device_name: String,
+    pub port: u16,
+    /// 在另一台设备上输入的配对码
+    pub pairing_code: String,
+}
+
+/// 开启局域网同步主机并通过 mDNS 广播，返回端口和配对码
+#[tauri::command]
+pub async fn lan_sync_start_host(
+    app: AppHandle,
+    device_name: Option<String>,
+    state: tauri::State<'_, CloudSyncState>,
+) -> Result<LanHostInfo, String> {
+    let logger = Logger::new().with_feature("lan_sync");
+    if let Some(host) = state.lan_host.lock().unwrap().take() {
+        host.stop();
+    }
+    let config = load_config(&app, &state)?;
+    let db_path = get_db_path(&app)?;
+    let store_dir = get_data_dir(&app)?.join("lan_sync");
+    let ctx = LanHostContext::new(
+        db_path,
+        store_dir,
+        &device_name.unwrap_or_else(default_device_name),
+        config.conflict_resolution,
+        config.selection,
+    )?;
+    let host = start_host(ctx, true).await?;
+    let info = LanHostInfo {
+        device_id: host.device_id.clone(),
+        device_name: host.device_name.clone(),
+        port: host.port,
+        pairing_code: host.pairing_code.clone(),
+    };
+    logger.info(&format!("LAN sync host listening on port {}", info.port));
+    *state.lan_host.lock().unwrap() = Some(host);
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn lan_sync_stop_host(
+    state: tauri::State<'_, CloudSyncState>,
+) -> Result<(), String> {
+    if let Some(host) = state.lan_host.lock().unwrap().take() {
+        host.stop();
+        Logger::new().with_feature("lan_sync").info("LAN sync host stopped");
+    }
+    Ok(())
+}
+
+/// 查找局域网内的同步主机，默认等待 3 秒
+#[tauri::command]
+pub async fn lan_sync_discover(
+    app: AppHandle,
+    timeout_ms: Option<u64>,
+) -> Result<Vec<LanPeer>, String> {
+    let (own_id, paired) = {
+        let conn = crate::database::get_connection(&get_db_path(&app)?)
+            .map_err(|e| format!("Failed to get database connection: {}", e))?;
+        (crate::cloud_sync::engine::device_id(&conn)?, load_paired_devices(&conn)?)
+    };
+    let mut peers = discover_peers(std::time::Duration::from_millis(timeout_ms.unwrap_or(3000)), &own_id).await?;
+    for peer in &mut peers {
+        peer.paired = paired.contains_key(&peer.device_id);
+    }
+    Ok(peers)
+}
+
+/// 用主机上显示的配对码与其配对
+#[tauri::command]
+pub async fn lan_sync_pair(
+    app: AppHandle,
+    host: String,
+    port: u16,
+    pairing_code: String,
+    device_name: Option<String>,
+) -> Result<PairedDevice, String> {
+    let db_path = get_db_path(&app)?;
+    let name = device_name.unwrap_or_else(default_device_name);
+    let peer = LanPeerProvider::connect(&db_path, &host, port, &name, Some(&pairing_code)).await?;
+    Logger::new().with_feature("lan_sync").info(&format!("Paired with {}", peer.peer.device_name));
+    Ok(PairedDevice { token: String::new(), ..peer.peer.clone() })
+}
+
+#[tauri::command]
+pub async fn lan_sync_list_paired(app: AppHandle) -> Result<Vec<PairedDevice>, String> {
+    let conn = crate::database::get_connection(&get_db_path(&app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    Ok(load_paired_devices(&conn)?
+        .into_values()
+        .map(|d| PairedDevice { token: String::new(), ..d })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn lan_sync_unpair(app: AppHandle, device_id: String) -> Result<(), String> {
+    let conn = crate::database::get_connection(&get_db_path(&app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    remove_paired_device(&conn, &device_id)
+}
+
+/// 与已配对的局域网主机同步，返回序列化的 SyncResult
+#[tauri::command]
+pub async fn lan_sync_start(
+    app: AppHandle,
+    host: String,
+    port: u16,
+    project_ids: Option<Vec<String>>,
+    device_name: Option<String>,
+    state: tauri::State<'_, CloudSyncState>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("lan_sync");
+    if matches!(*state.status.lock().unwrap(), SyncStatus::Syncing) {
+        return Err("同步正在进行中".to_string());
+    }
+    let config = load_config(&app, &state)?;
+    let db_path = get_db_path(&app)?;
+    let name = device_name.unwrap_or_else(default_device_name);
+    let peer = LanPeerProvider::connect(&db_path, &host, port, &name, None).await?;
+
+    state.set_status(SyncStatus::Syncing);
+    let started_at = Utc::now();
+    let outcome = run_sync(&db_path, &peer, config.conflict_resolution, &config.selection, project_ids.clone()).await;
+    let entry = SyncHistoryEntry::new(&peer.name(), project_ids, started_at, &outcome);
+    if let Err(e) = crate::database::get_connection(&db_path)
+        .map_err(|e| e.to_string())
+        .and_then(|conn| record_sync_run(&conn, &entry))
+    {
+        logger.error(&format!("Failed to record sync history: {}", e));
+    }
+
+    match outcome {
+        Ok(result) => {
+            state.set_status(SyncStatus::Idle);
+            logger.info(&format!("LAN sync with {} finished: {} files", peer.peer.device_name, result.synced_files.len()));
+            serde_json::to_string(&result).map_err(|e| e.to_string())
+        }
+        Err(e) => {
+            logger.error(&format!("LAN sync failed: {}", e));
+            state.set_status(SyncStatus::Error(e.clone()));
+            Err(e)
+        }
+    }
 }
@@ -1,8 +1,28 @@
+use crate::collaboration_activity::{self, ActivityEntry, ActivityKind, ActivityThrottle};
+use crate::collaboration_auth::{self, CollabRole};
+use crate::comments::ChapterComment;
+use crate::crdt::{CrdtOp, StateVector, TextDocument};
+use crate::logger::Logger;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast;
 
+/// 由章节已有正文生成初始 CRDT 操作时使用的客户端标识前缀
+const SEED_CLIENT: &str = "seed";
+
+/// 初始操作的客户端标识带上正文的摘要：相同正文生成相同的操作，
+/// 不同正文生成的操作不会因为标识相同被当成重复操作丢弃
+fn seed_client(content: &str) -> String {
+    let digest = Sha256::digest(content.as_bytes());
+    let hex: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}", SEED_CLIENT, hex)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     pub id: String,
@@ -34,17 +54,128 @@ pub enum OperationType {
     Replace { position: usize, length: usize, text: String },
 }
 
+/// 编辑产生的 CRDT 操作，会话内各实例之间传输的是它而不是按位置描述的 Operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterChanges {
+    pub chapter_id: String,
+    /// 产生这批操作的用户；重新连接时补发的历史操作为空
+    pub user_id: String,
+    pub ops: Vec<CrdtOp>,
+}
+
+/// 会话中除编辑操作以外的变化，用于在多个实例间同步成员和光标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SessionEvent {
+    Joined { user: User, role: CollabRole },
+    Left { user_id: String },
+    Cursor { cursor: CursorPosition },
+    /// 批注被创建、回复或解决，`user_id` 为执行操作的成员
+    Comment { user_id: String, comment: Box<ChapterComment> },
+    CommentDeleted { user_id: String, comment_id: String },
+}
+
+impl SessionEvent {
+    /// 触发事件的用户
+    pub fn user_id(&self) -> &str {
+        match self {
+            SessionEvent::Joined { user, .. } => &user.id,
+            SessionEvent::Left { user_id } => user_id,
+            SessionEvent::Cursor { cursor } => &cursor.user_id,
+            SessionEvent::Comment { user_id, .. } | SessionEvent::CommentDeleted { user_id, .. } => user_id,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CollaborationSession {
     pub id: String,
     pub project_id: String,
     pub users: Vec<User>,
     pub active_cursors: HashMap<String, CursorPosition>,
+    /// 成员的权限，按用户 ID 索引
+    #[serde(default)]
+    pub roles: HashMap<String, CollabRole>,
+}
+
+struct ChapterDocument {
+    project_id: String,
+    doc: TextDocument,
+}
+
+/// 读取章节的 CRDT 文档。还没有操作时，主机（`seed` 为 true）由正文生成初始操作，
+/// 加入远端会话的一端返回空文档，等待主机补发操作；
+/// 章节正文在协作之外被修改过时，以本设备的身份生成一次替换操作
+fn load_document(conn: &Connection, project_id: &str, chapter_id: &str, seed: bool) -> Result<TextDocument, String> {
+    let mut doc = TextDocument::new();
+    let mut stmt = conn
+        .prepare("SELECT op FROM chapter_crdt_ops WHERE chapter_id = ? ORDER BY lamport, client_id, seq")
+        .map_err(|e| e.to_string())?;
+    let ops = stmt
+        .query_map(params![chapter_id], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?;
+    let mut has_ops = false;
+    for op in ops {
+        let op: CrdtOp = serde_json::from_str(&op.map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+        doc.apply(op);
+        has_ops = true;
+    }
+
+    let content: Option<String> = conn
+        .query_row("SELECT content FROM chapters WHERE id = ?", params![chapter_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(content) = content else { return Ok(doc) };
+    let ops = if !has_ops && !seed {
+        return Ok(doc);
+    } else if !has_ops {
+        doc.insert(&seed_client(&content), 0, &content)
+    } else if doc.text() != content {
+        let device_id = crate::cloud_sync::engine::device_id(conn)?;
+        doc.replace_text(&device_id, &content)
+    } else {
+        Vec::new()
+    };
+    save_ops(conn, project_id, chapter_id, None, &ops)?;
+    Ok(doc)
+}
+
+/// `session_id` 为产生或收到这些操作的协作会话，用于回放
+fn save_ops(conn: &Connection, project_id: &str, chapter_id: &str, session_id: Option<&str>, ops: &[CrdtOp]) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare(
+            "INSERT OR IGNORE INTO chapter_crdt_ops (project_id, chapter_id, client_id, seq, lamport, op, session_id, timestamp)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .map_err(|e| e.to_string())?;
+    for op in ops {
+        let data = serde_json::to_string(op).map_err(|e| e.to_string())?;
+        stmt.execute(params![
+            project_id,
+            chapter_id,
+            op.id.client,
+            op.id.seq as i64,
+            op.lamport as i64,
+            data,
+            session_id,
+            op.timestamp,
+        ])
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
 }
 
 pub struct CollaborationManager {
     sessions: Arc<Mutex<HashMap<String, CollaborationSession>>>,
-    operation_channels: Arc<Mutex<HashMap<String, broadcast::Sender<Operation>>>>,
+    operation_channels: Arc<Mutex<HashMap<String, broadcast::Sender<ChapterChanges>>>>,
+    event_channels: Arc<Mutex<HashMap<String, broadcast::Sender<SessionEvent>>>>,
+    /// 按章节 ID 缓存的 CRDT 文档
+    documents: Arc<Mutex<HashMap<String, ChapterDocument>>>,
+    /// 从远端主机加入的会话，章节的初始操作以主机为准
+    remote_sessions: Arc<Mutex<HashSet<String>>>,
+    activity_throttle: Arc<Mutex<ActivityThrottle>>,
+    /// 为空时文档只保存在内存中，也不记录动态
+    db_path: Option<PathBuf>,
 }
 
 impl CollaborationManager {
@@ -52,76 +183,340 @@ impl CollaborationManager {
         Self {
             sessions: Arc::new(Mutex::new(HashMap::new())),
             operation_channels: Arc::new(Mutex::new(HashMap::new())),
+            event_channels: Arc::new(Mutex::new(HashMap::new())),
+            documents: Arc::new(Mutex::new(HashMap::new())),
+            remote_sessions: Arc::new(Mutex::new(HashSet::new())),
+            activity_throttle: Arc::new(Mutex::new(ActivityThrottle::default())),
+            db_path: None,
+        }
+    }
+
+    /// CRDT 操作保存到数据库，合并后的正文写回章节
+    pub fn with_db_path(db_path: PathBuf) -> Self {
+        Self { db_path: Some(db_path), ..Self::new() }
+    }
+
+    fn connection(&self) -> Result<Option<Connection>, String> {
+        self.db_path
+            .as_ref()
+            .map(|path| crate::database::get_connection(path).map_err(|e| e.to_string()))
+            .transpose()
+    }
+
+    fn is_remote(&self, session_id: &str) -> bool {
+        self.remote_sessions.lock().unwrap().contains(session_id)
+    }
+
+    fn session_project(&self, session_id: &str) -> Result<String, String> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions.get(session_id).map(|s| s.project_id.clone()).ok_or_else(|| "Session not found".to_string())
+    }
+
+    /// 在章节文档上执行 `edit`，保存新产生的操作和合并后的正文
+    fn edit_document(
+        &self,
+        session_id: &str,
+        project_id: &str,
+        chapter_id: &str,
+        edit: impl FnOnce(&mut TextDocument) -> Vec<CrdtOp>,
+    ) -> Result<Vec<CrdtOp>, String> {
+        let conn = self.connection()?;
+        let mut documents = self.documents.lock().unwrap();
+        if !documents.contains_key(chapter_id) {
+            let doc = match &conn {
+                Some(conn) => load_document(conn, project_id, chapter_id, !self.is_remote(session_id))?,
+                None => TextDocument::new(),
+            };
+            documents.insert(chapter_id.to_string(), ChapterDocument { project_id: project_id.to_string(), doc });
+        }
+        let document = documents.get_mut(chapter_id).unwrap();
+        let ops = edit(&mut document.doc);
+        if let (Some(conn), false) = (&conn, ops.is_empty()) {
+            let text = document.doc.text();
+            let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+            save_ops(&tx, project_id, chapter_id, Some(session_id), &ops)?;
+            tx.execute(
+                "UPDATE chapters SET content = ?, word_count = ?, updated_at = ? WHERE id = ?",
+                params![text, text.chars().count() as i64, Utc::now().to_rfc3339(), chapter_id],
+            )
+            .map_err(|e| e.to_string())?;
+            tx.commit().map_err(|e| e.to_string())?;
+        }
+        Ok(ops)
+    }
+
+    /// 记录会话动态；光标移动和编辑按用户、章节节流。记录失败只写日志
+    fn record_activity(&self, session_id: &str, user_id: &str, user_name: Option<String>, kind: ActivityKind, chapter_id: Option<&str>) {
+        if self.db_path.is_none() || user_id.is_empty() {
+            return;
+        }
+        if matches!(kind, ActivityKind::Cursor | ActivityKind::Edit) {
+            let mut throttle = self.activity_throttle.lock().unwrap();
+            if !throttle.should_record(user_id, chapter_id.unwrap_or_default(), kind, Utc::now()) {
+                return;
+            }
+        }
+        let Some((project_id, session_user_name)) = self.get_session(session_id).map(|s| {
+            let name = s.users.iter().find(|u| u.id == user_id).map(|u| u.name.clone());
+            (s.project_id, name)
+        }) else {
+            return;
+        };
+        let entry = ActivityEntry::new(&project_id, session_id, user_id, user_name.or(session_user_name), kind, chapter_id);
+        let result = self
+            .connection()
+            .and_then(|conn| conn.map_or(Ok(()), |conn| collaboration_activity::record_activity(&conn, &entry)));
+        if let Err(e) = result {
+            Logger::new().with_feature("collaboration").warn(&format!("Failed to record activity: {}", e));
         }
     }
 
-    pub fn create_session(&self, project_id: String) -> String {
+    fn publish(&self, session_id: &str, changes: ChapterChanges) -> Result<(), String> {
+        let channels = self.operation_channels.lock().unwrap();
+        let tx = channels.get(session_id).ok_or("Session not found")?;
+        let _ = tx.send(changes);
+        Ok(())
+    }
+
+    /// 创建会话，创建者 `owner` 是会话唯一的所有者
+    pub fn create_session(&self, project_id: String, owner: User) -> String {
         let session_id = format!("session_{}", uuid::Uuid::new_v4());
         let session = CollaborationSession {
             id: session_id.clone(),
             project_id,
-            users: vec![],
+            users: vec![owner.clone()],
             active_cursors: HashMap::new(),
+            roles: HashMap::from([(owner.id.clone(), CollabRole::Owner)]),
         };
 
+        self.open_session(session);
+        self.record_activity(&session_id, &owner.id, Some(owner.name), ActivityKind::Joined, None);
+        session_id
+    }
+
+    /// 加入远端主机的会话时，用主机返回的会话内容替换本地副本
+    pub fn insert_session(&self, session: CollaborationSession) {
+        self.remote_sessions.lock().unwrap().insert(session.id.clone());
+        self.open_session(session);
+    }
+
+    fn open_session(&self, session: CollaborationSession) {
+        let session_id = session.id.clone();
         let mut sessions = self.sessions.lock().unwrap();
         sessions.insert(session_id.clone(), session);
 
-        let (tx, _) = broadcast::channel(100);
         let mut channels = self.operation_channels.lock().unwrap();
-        channels.insert(session_id.clone(), tx);
+        channels.entry(session_id.clone()).or_insert_with(|| broadcast::channel(100).0);
+        let mut events = self.event_channels.lock().unwrap();
+        events.entry(session_id).or_insert_with(|| broadcast::channel(100).0);
+    }
 
-        session_id
+    fn emit_event(&self, session_id: &str, event: SessionEvent) {
+        if let Some(tx) = self.event_channels.lock().unwrap().get(session_id) {
+            let _ = tx.send(event);
+        }
     }
 
-    pub fn join_session(&self, session_id: &str, user: User) -> Result<(), String> {
+    /// 以 `role` 的权限把用户加入会话，调用前应已完成身份校验。
+    /// 已在会话中的用户不能再次加入，避免覆盖其他成员（包括所有者）的权限
+    pub fn join_session(&self, session_id: &str, user: User, role: CollabRole) -> Result<(), String> {
         let mut sessions = self.sessions.lock().unwrap();
-        if let Some(session) = sessions.get_mut(session_id) {
-            if !session.users.iter().any(|u| u.id == user.id) {
-                session.users.push(user);
-            }
-            Ok(())
-        } else {
-            Err("Session not found".to_string())
+        let session = sessions.get_mut(session_id).ok_or("Session not found")?;
+        if session.roles.contains_key(&user.id) || session.users.iter().any(|u| u.id == user.id) {
+            return Err("用户已在会话中".to_string());
+        }
+        session.roles.insert(user.id.clone(), role);
+        session.users.push(user.clone());
+        drop(sessions);
+        self.record_activity(session_id, &user.id, Some(user.name.clone()), ActivityKind::Joined, None);
+        self.emit_event(session_id, SessionEvent::Joined { user, role });
+        Ok(())
+    }
+
+    /// 凭邀请令牌加入会话，返回令牌授予的权限
+    pub fn join_with_invite(&self, session_id: &str, user: User, token: &str) -> Result<CollabRole, String> {
+        let project_id = self.session_project(session_id)?;
+        let conn = self.connection()?.ok_or("邀请令牌需要在主机上校验")?;
+        let role = collaboration_auth::verify_invite(&conn, token, &project_id)?.role;
+        self.join_session(session_id, user, role)?;
+        Ok(role)
+    }
+
+    pub fn role_of(&self, session_id: &str, user_id: &str) -> Option<CollabRole> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions.get(session_id).and_then(|s| s.roles.get(user_id).copied())
+    }
+
+    /// 检查用户是会话成员且权限满足 `allowed`
+    pub fn authorize(&self, session_id: &str, user_id: &str, allowed: fn(&CollabRole) -> bool) -> Result<(), String> {
+        match self.role_of(session_id, user_id) {
+            Some(role) if allowed(&role) => Ok(()),
+            Some(_) => Err("没有执行该操作的权限".to_string()),
+            None => Err("用户不在该会话中".to_string()),
         }
     }
 
     pub fn leave_session(&self, session_id: &str, user_id: &str) -> Result<(), String> {
         let mut sessions = self.sessions.lock().unwrap();
         if let Some(session) = sessions.get_mut(session_id) {
+            let user_name = session.users.iter().find(|u| u.id == user_id).map(|u| u.name.clone());
             session.users.retain(|u| u.id != user_id);
             session.active_cursors.remove(user_id);
+            session.roles.remove(user_id);
+            if user_name.is_some() {
+                drop(sessions);
+                self.record_activity(session_id, user_id, user_name, ActivityKind::Left, None);
+                self.emit_event(session_id, SessionEvent::Left { user_id: user_id.to_string() });
+            }
             Ok(())
         } else {
             Err("Session not found".to_string())
         }
     }
 
+    /// 把本地用户按位置描述的编辑转换为 CRDT 操作，保存后发给会话中的其他实例
     pub fn broadcast_operation(&self, session_id: &str, operation: Operation) -> Result<(), String> {
-        let channels = self.operation_channels.lock().unwrap();
-        if let Some(tx) = channels.get(session_id) {
-            let _ = tx.send(operation);
-            Ok(())
-        } else {
-            Err("Session not found".to_string())
+        let project_id = self.session_project(session_id)?;
+        self.authorize(session_id, &operation.user_id, CollabRole::can_edit)?;
+        let user_id = operation.user_id.clone();
+        let ops = self.edit_document(session_id, &project_id, &operation.chapter_id, |doc| match operation.op_type {
+            OperationType::Insert { position, text } => doc.insert(&user_id, position, &text),
+            OperationType::Delete { position, length } => doc.delete(&user_id, position, length),
+            OperationType::Replace { position, length, text } => {
+                let mut ops = doc.delete(&user_id, position, length);
+                ops.extend(doc.insert(&user_id, position, &text));
+                ops
+            }
+        })?;
+        if ops.is_empty() {
+            return Ok(());
+        }
+        self.record_activity(session_id, &user_id, None, ActivityKind::Edit, Some(&operation.chapter_id));
+        self.publish(session_id, ChapterChanges { chapter_id: operation.chapter_id, user_id, ops })
+    }
+
+    /// 应用其他实例发来的操作，返回是否有新的操作；新操作会继续转发给会话中的其他连接
+    pub fn apply_changes(&self, session_id: &str, changes: ChapterChanges) -> Result<bool, String> {
+        let project_id = self.session_project(session_id)?;
+        let ChapterChanges { chapter_id, user_id, ops } = changes;
+        let ops = self.edit_document(session_id, &project_id, &chapter_id, |doc| {
+            ops.into_iter().filter(|op| doc.apply(op.clone())).collect()
+        })?;
+        if ops.is_empty() {
+            return Ok(false);
+        }
+        self.record_activity(session_id, &user_id, None, ActivityKind::Edit, Some(&chapter_id));
+        self.publish(session_id, ChapterChanges { chapter_id, user_id, ops })?;
+        Ok(true)
+    }
+
+    /// 章节合并后的正文
+    pub fn chapter_text(&self, session_id: &str, chapter_id: &str) -> Result<String, String> {
+        let project_id = self.session_project(session_id)?;
+        self.edit_document(session_id, &project_id, chapter_id, |_| Vec::new())?;
+        let documents = self.documents.lock().unwrap();
+        Ok(documents.get(chapter_id).map(|d| d.doc.text()).unwrap_or_default())
+    }
+
+    /// 会话所属项目中各章节的状态向量，重新连接时用来找出双方缺少的操作。
+    /// 主机会先为所有章节生成初始操作，加入的一端由此拿到每一章的完整操作
+    pub fn versions(&self, session_id: &str) -> Result<HashMap<String, StateVector>, String> {
+        let project_id = self.session_project(session_id)?;
+        if let Some(conn) = self.connection()? {
+            let sql = if self.is_remote(session_id) {
+                "SELECT DISTINCT chapter_id FROM chapter_crdt_ops WHERE project_id = ?"
+            } else {
+                "SELECT DISTINCT chapter_id FROM chapter_crdt_ops WHERE project_id = ?1 UNION SELECT id FROM chapters WHERE project_id = ?1"
+            };
+            let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+            let chapter_ids = stmt
+                .query_map(params![project_id], |row| row.get::<_, String>(0))
+                .map_err(|e| e.to_string())?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?;
+            for chapter_id in chapter_ids {
+                self.edit_document(session_id, &project_id, &chapter_id, |_| Vec::new())?;
+            }
         }
+        let documents = self.documents.lock().unwrap();
+        Ok(documents
+            .iter()
+            .filter(|(_, d)| d.project_id == project_id)
+            .map(|(chapter_id, d)| (chapter_id.clone(), d.doc.state_vector().clone()))
+            .collect())
     }
 
-    pub fn subscribe_operations(&self, session_id: &str) -> Option<broadcast::Receiver<Operation>> {
+    /// 对端（状态向量为 `versions`）缺少的操作
+    pub fn changes_since(
+        &self,
+        session_id: &str,
+        versions: &HashMap<String, StateVector>,
+        user_id: &str,
+    ) -> Result<Vec<ChapterChanges>, String> {
+        self.versions(session_id)?;
+        let project_id = self.session_project(session_id)?;
+        let empty = StateVector::new();
+        let documents = self.documents.lock().unwrap();
+        Ok(documents
+            .iter()
+            .filter(|(_, d)| d.project_id == project_id)
+            .filter_map(|(chapter_id, d)| {
+                let ops = d.doc.ops_since(versions.get(chapter_id).unwrap_or(&empty));
+                (!ops.is_empty()).then(|| ChapterChanges { chapter_id: chapter_id.clone(), user_id: user_id.to_string(), ops })
+            })
+            .collect())
+    }
+
+    pub fn subscribe_operations(&self, session_id: &str) -> Option<broadcast::Receiver<ChapterChanges>> {
         let channels = self.operation_channels.lock().unwrap();
         channels.get(session_id).map(|tx| tx.subscribe())
     }
 
+    pub fn subscribe_events(&self, session_id: &str) -> Option<broadcast::Receiver<SessionEvent>> {
+        let channels = self.event_channels.lock().unwrap();
+        channels.get(session_id).map(|tx| tx.subscribe())
+    }
+
     pub fn update_cursor(&self, session_id: &str, cursor: CursorPosition) -> Result<(), String> {
         let mut sessions = self.sessions.lock().unwrap();
         if let Some(session) = sessions.get_mut(session_id) {
-            session.active_cursors.insert(cursor.user_id.clone(), cursor);
+            let previous = session.active_cursors.insert(cursor.user_id.clone(), cursor.clone());
+            drop(sessions);
+            let kind = match previous {
+                Some(previous) if previous.chapter_id == cursor.chapter_id => ActivityKind::Cursor,
+                _ => ActivityKind::ChapterSwitch,
+            };
+            self.record_activity(session_id, &cursor.user_id, None, kind, Some(&cursor.chapter_id));
+            self.emit_event(session_id, SessionEvent::Cursor { cursor });
             Ok(())
         } else {
             Err("Session not found".to_string())
         }
     }
 
+    /// 把本地的批注变化通知会话中的其他成员
+    pub fn share_comment_event(&self, session_id: &str, event: SessionEvent) -> Result<(), String> {
+        self.session_project(session_id)?;
+        self.emit_event(session_id, event);
+        Ok(())
+    }
+
+    /// 保存其他成员发来的批注变化，并继续转发给会话中的其他连接。
+    /// 本地没有对应章节时保存会失败，只记录日志，不影响会话
+    pub fn apply_comment_event(&self, session_id: &str, event: SessionEvent) -> Result<(), String> {
+        if let Some(conn) = self.connection()? {
+            let saved = match &event {
+                SessionEvent::Comment { comment, .. } => crate::comments::save_comment(&conn, comment),
+                SessionEvent::CommentDeleted { comment_id, .. } => crate::comments::delete_comment(&conn, comment_id),
+                _ => Ok(()),
+            };
+            if let Err(e) = saved {
+                Logger::new().with_feature("collaboration").warn(&format!("Failed to save shared comment: {}", e));
+            }
+        }
+        self.share_comment_event(session_id, event)
+    }
+
     pub fn get_session(&self, session_id: &str) -> Option<CollaborationSession> {
         let sessions = self.sessions.lock().unwrap();
         sessions.get(session_id).cloned()
@@ -141,3 +536,115 @@ impl Default for CollaborationManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operations_persist_and_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("collab.db");
+        {
+            let conn = crate::test_support::init_project_db(&db_path);
+            conn.execute(
+                "INSERT INTO chapters (id, project_id, title, content, created_at, updated_at) VALUES ('c1', 'p1', '第一章', '雨夜', 't0', 't0')",
+                [],
+            )
+            .unwrap();
+        }
+
+        let manager = CollaborationManager::with_db_path(db_path.clone());
+        let owner = User { id: "owner".to_string(), name: "作者".to_string(), color: "#4ECDC4".to_string() };
+        let session_id = manager.create_session("p1".to_string(), owner.clone());
+        let alice = User { id: "alice".to_string(), name: "阿青".to_string(), color: "#FF6B6B".to_string() };
+        manager.join_session(&session_id, alice.clone(), CollabRole::Editor).unwrap();
+        // 已在会话中的用户不能重新加入，所有者的权限也不会被覆盖
+        assert!(manager.join_session(&session_id, alice, CollabRole::Owner).is_err());
+        assert!(manager.join_session(&session_id, owner, CollabRole::Viewer).is_err());
+        assert_eq!(manager.role_of(&session_id, "owner"), Some(CollabRole::Owner));
+        let operation = Operation {
+            id: "o1".to_string(),
+            user_id: "alice".to_string(),
+            chapter_id: "c1".to_string(),
+            op_type: OperationType::Replace { position: 1, length: 1, text: "后的街".to_string() },
+            timestamp: 0,
+        };
+        manager.broadcast_operation(&session_id, operation).unwrap();
+        assert_eq!(manager.chapter_text(&session_id, "c1").unwrap(), "雨后的街");
+
+        let conn = Connection::open(&db_path).unwrap();
+        let content: String = conn.query_row("SELECT content FROM chapters WHERE id = 'c1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(content, "雨后的街");
+
+        // 重新加载时按操作重放，不会重复生成初始操作
+        let reloaded = CollaborationManager::with_db_path(db_path);
+        let owner = User { id: "owner".to_string(), name: "作者".to_string(), color: "#4ECDC4".to_string() };
+        let session_id = reloaded.create_session("p1".to_string(), owner);
+        assert_eq!(reloaded.chapter_text(&session_id, "c1").unwrap(), "雨后的街");
+        assert_eq!(reloaded.versions(&session_id).unwrap()["c1"]["alice"], 4);
+    }
+
+    #[test]
+    fn test_joining_peer_adopts_host_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = |name: &str, content: &str| {
+            let db_path = dir.path().join(name);
+            let conn = crate::test_support::init_project_db(&db_path);
+            conn.execute(
+                "INSERT INTO chapters (id, project_id, title, content, created_at, updated_at) VALUES ('c1', 'p1', '第一章', ?, 't0', 't0')",
+                params![content],
+            )
+            .unwrap();
+            CollaborationManager::with_db_path(db_path)
+        };
+        let host = manager("host.db", "雨夜");
+        let guest = manager("guest.db", "晴天");
+        let owner = User { id: "owner".to_string(), name: "作者".to_string(), color: "#4ECDC4".to_string() };
+        let session_id = host.create_session("p1".to_string(), owner);
+        guest.insert_session(host.get_session(&session_id).unwrap());
+
+        // 加入的一端不以本地正文生成初始操作，补发主机的操作后两端一致
+        assert!(guest.versions(&session_id).unwrap().is_empty());
+        for changes in host.changes_since(&session_id, &HashMap::new(), "").unwrap() {
+            guest.apply_changes(&session_id, changes).unwrap();
+        }
+        assert_eq!(guest.chapter_text(&session_id, "c1").unwrap(), "雨夜");
+        assert_eq!(seed_client("雨夜"), seed_client("雨夜"));
+        assert_ne!(seed_client("雨夜"), seed_client("晴天"));
+    }
+
+    #[test]
+    fn test_activity_feed() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("activity.db");
+        crate::database::init_database(&db_path).unwrap();
+        let manager = CollaborationManager::with_db_path(db_path.clone());
+        let user = User { id: "u1".to_string(), name: "阿青".to_string(), color: "#FF6B6B".to_string() };
+        let session_id = manager.create_session("p1".to_string(), user);
+        let cursor = |chapter_id: &str, line: usize| CursorPosition {
+            user_id: "u1".to_string(),
+            chapter_id: chapter_id.to_string(),
+            line,
+            column: 0,
+        };
+        manager.update_cursor(&session_id, cursor("c1", 1)).unwrap();
+        // 同一章内的光标移动被节流
+        manager.update_cursor(&session_id, cursor("c1", 2)).unwrap();
+        manager.update_cursor(&session_id, cursor("c1", 3)).unwrap();
+        manager.update_cursor(&session_id, cursor("c2", 1)).unwrap();
+        manager.leave_session(&session_id, "u1").unwrap();
+
+        let conn = Connection::open(&db_path).unwrap();
+        let mut kinds: Vec<ActivityKind> =
+            collaboration_activity::list_activity(&conn, "p1", None, 100).unwrap().into_iter().map(|e| e.kind).collect();
+        kinds.sort_by_key(|k| format!("{:?}", k));
+        assert_eq!(
+            kinds,
+            vec![ActivityKind::ChapterSwitch, ActivityKind::ChapterSwitch, ActivityKind::Cursor, ActivityKind::Joined, ActivityKind::Left]
+        );
+        let entries = collaboration_activity::list_activity(&conn, "p1", None, 100).unwrap();
+        assert!(entries.iter().all(|e| e.user_name.as_deref() == Some("阿青")));
+        assert!(collaboration_activity::list_activity(&conn, "p1", Some("9999"), 100).unwrap().is_empty());
+    }
+}
\ No newline at end of file
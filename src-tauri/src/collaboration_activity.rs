@@ -0,0 +1,159 @@
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 每个项目保留的动态条数
+pub const MAX_ACTIVITY_PER_PROJECT: i64 = 5000;
+/// 同一用户在同一章节的光标移动和编辑，在这段时间内只记录一次
+pub const ACTIVITY_THROTTLE_SECS: i64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    Joined,
+    Left,
+    /// 光标移到了另一章
+    ChapterSwitch,
+    Cursor,
+    Edit,
+}
+
+impl ActivityKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ActivityKind::Joined => "joined",
+            ActivityKind::Left => "left",
+            ActivityKind::ChapterSwitch => "chapter_switch",
+            ActivityKind::Cursor => "cursor",
+            ActivityKind::Edit => "edit",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "joined" => ActivityKind::Joined,
+            "left" => ActivityKind::Left,
+            "chapter_switch" => ActivityKind::ChapterSwitch,
+            "cursor" => ActivityKind::Cursor,
+            "edit" => ActivityKind::Edit,
+            _ => return None,
+        })
+    }
+}
+
+/// 协作会话中的一条动态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityEntry {
+    pub id: String,
+    pub project_id: String,
+    pub session_id: String,
+    pub user_id: String,
+    pub user_name: Option<String>,
+    pub kind: ActivityKind,
+    pub chapter_id: Option<String>,
+    pub created_at: String,
+}
+
+impl ActivityEntry {
+    pub fn new(project_id: &str, session_id: &str, user_id: &str, user_name: Option<String>, kind: ActivityKind, chapter_id: Option<&str>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            project_id: project_id.to_string(),
+            session_id: session_id.to_string(),
+            user_id: user_id.to_string(),
+            user_name,
+            kind,
+            chapter_id: chapter_id.map(str::to_string),
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// 记录光标和编辑动态的节流状态，只保存在内存中
+#[derive(Debug, Default)]
+pub struct ActivityThrottle {
+    last: HashMap<(String, String, ActivityKind), DateTime<Utc>>,
+}
+
+impl ActivityThrottle {
+    /// 距离上次记录同一用户、章节、类型的动态已超过节流时间时返回 true，并更新记录时间
+    pub fn should_record(&mut self, user_id: &str, chapter_id: &str, kind: ActivityKind, now: DateTime<Utc>) -> bool {
+        let key = (user_id.to_string(), chapter_id.to_string(), kind);
+        match self.last.get(&key) {
+            Some(last) if now - *last < Duration::seconds(ACTIVITY_THROTTLE_SECS) => false,
+            _ => {
+                self.last.insert(key, now);
+                true
+            }
+        }
+    }
+}
+
+pub fn record_activity(conn: &Connection, entry: &ActivityEntry) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO collab_activity (id, project_id, session_id, user_id, user_name, kind, chapter_id, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            entry.id,
+            entry.project_id,
+            entry.session_id,
+            entry.user_id,
+            entry.user_name,
+            entry.kind.as_str(),
+            entry.chapter_id,
+            entry.created_at,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM collab_activity WHERE project_id = ?1 AND id NOT IN
+         (SELECT id FROM collab_activity WHERE project_id = ?1 ORDER BY created_at DESC LIMIT ?2)",
+        params![entry.project_id, MAX_ACTIVITY_PER_PROJECT],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 项目在 `since`（RFC 3339）之后的动态，按时间倒序
+pub fn list_activity(conn: &Connection, project_id: &str, since: Option<&str>, limit: i64) -> Result<Vec<ActivityEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, project_id, session_id, user_id, user_name, kind, chapter_id, created_at
+             FROM collab_activity WHERE project_id = ? AND created_at > ? ORDER BY created_at DESC LIMIT ?",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![project_id, since.unwrap_or(""), limit], |row| {
+            let kind: String = row.get(5)?;
+            let Some(kind) = ActivityKind::parse(&kind) else { return Ok(None) };
+            Ok(Some(ActivityEntry {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                session_id: row.get(2)?,
+                user_id: row.get(3)?,
+                user_name: row.get(4)?,
+                kind,
+                chapter_id: row.get(6)?,
+                created_at: row.get(7)?,
+            }))
+        })
+        .map_err(|e| e.to_string())?;
+    rows.filter_map(|row| row.transpose()).collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle() {
+        let mut throttle = ActivityThrottle::default();
+        let now = Utc::now();
+        assert!(throttle.should_record("u1", "c1", ActivityKind::Edit, now));
+        assert!(!throttle.should_record("u1", "c1", ActivityKind::Edit, now + Duration::seconds(60)));
+        assert!(throttle.should_record("u1", "c2", ActivityKind::Edit, now + Duration::seconds(60)));
+        assert!(throttle.should_record("u1", "c1", ActivityKind::Cursor, now + Duration::seconds(60)));
+        assert!(throttle.should_record("u1", "c1", ActivityKind::Edit, now + Duration::seconds(ACTIVITY_THROTTLE_SECS + 1)));
+    }
+}
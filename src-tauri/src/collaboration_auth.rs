@@ -0,0 +1,216 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+const INVITE_SECRET_KEY: &str = "collab_invite_secret";
+
+/// 协作成员的权限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollabRole {
+    /// 会话的创建者，可以邀请他人
+    Owner,
+    Editor,
+    /// 只能查看和批注
+    Commenter,
+    Viewer,
+}
+
+impl CollabRole {
+    pub fn can_edit(&self) -> bool {
+        matches!(self, CollabRole::Owner | CollabRole::Editor)
+    }
+
+    pub fn can_comment(&self) -> bool {
+        !matches!(self, CollabRole::Viewer)
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            CollabRole::Owner => "owner",
+            CollabRole::Editor => "editor",
+            CollabRole::Commenter => "commenter",
+            CollabRole::Viewer => "viewer",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "owner" => CollabRole::Owner,
+            "editor" => CollabRole::Editor,
+            "commenter" => CollabRole::Commenter,
+            "viewer" => CollabRole::Viewer,
+            _ => return None,
+        })
+    }
+}
+
+/// 邀请令牌中签名的内容
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InviteClaims {
+    pub id: String,
+    pub project_id: String,
+    pub role: CollabRole,
+    /// 过期时间（Unix 秒）
+    pub expires_at: i64,
+}
+
+/// 主机签发过的邀请，`revoked_at` 不为空表示已吊销
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InviteRecord {
+    #[serde(flatten)]
+    pub claims: InviteClaims,
+    pub created_at: String,
+    pub revoked_at: Option<String>,
+}
+
+/// 新签发的邀请，`token` 交给被邀请人
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invite {
+    pub token: String,
+    pub claims: InviteClaims,
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 本机签发邀请用的密钥，首次使用时生成
+fn invite_secret(conn: &Connection) -> Result<Vec<u8>, String> {
+    let saved: Option<String> = conn
+        .query_row("SELECT value FROM app_settings WHERE key = ?", params![INVITE_SECRET_KEY], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some(secret) = saved {
+        return URL_SAFE_NO_PAD.decode(secret).map_err(|e| e.to_string());
+    }
+    let secret: [u8; 32] = rand::random();
+    conn.execute(
+        "INSERT OR REPLACE INTO app_settings (key, value, updated_at) VALUES (?, ?, ?)",
+        params![INVITE_SECRET_KEY, URL_SAFE_NO_PAD.encode(secret), Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(secret.to_vec())
+}
+
+fn mac(secret: &[u8], payload: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+/// 为项目签发一个 `ttl_secs` 秒后过期的邀请，令牌格式为 `载荷.签名`
+pub fn create_invite(conn: &Connection, project_id: &str, role: CollabRole, ttl_secs: i64) -> Result<Invite, String> {
+    if role == CollabRole::Owner {
+        return Err("不能邀请他人成为所有者".to_string());
+    }
+    if ttl_secs <= 0 {
+        return Err("邀请有效期必须大于 0".to_string());
+    }
+    let claims = InviteClaims {
+        id: uuid::Uuid::new_v4().to_string(),
+        project_id: project_id.to_string(),
+        role,
+        expires_at: Utc::now().timestamp() + ttl_secs,
+    };
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).map_err(|e| e.to_string())?);
+    let signature = URL_SAFE_NO_PAD.encode(mac(&invite_secret(conn)?, &payload).finalize().into_bytes());
+    conn.execute(
+        "INSERT INTO collab_invites (id, project_id, role, expires_at, created_at) VALUES (?, ?, ?, ?, ?)",
+        params![claims.id, claims.project_id, role.as_str(), claims.expires_at, Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(Invite { token: format!("{}.{}", payload, signature), claims })
+}
+
+/// 校验令牌的签名、有效期、吊销状态，以及是否属于 `project_id`
+pub fn verify_invite(conn: &Connection, token: &str, project_id: &str) -> Result<InviteClaims, String> {
+    let invalid = || "邀请令牌无效".to_string();
+    let (payload, signature) = token.trim().split_once('.').ok_or_else(invalid)?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| invalid())?;
+    mac(&invite_secret(conn)?, payload).verify_slice(&signature).map_err(|_| invalid())?;
+    let claims: InviteClaims =
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid())?).map_err(|_| invalid())?;
+
+    if claims.project_id != project_id {
+        return Err("邀请令牌不属于该项目".to_string());
+    }
+    if claims.expires_at <= Utc::now().timestamp() {
+        return Err("邀请已过期".to_string());
+    }
+    let revoked: Option<Option<String>> = conn
+        .query_row("SELECT revoked_at FROM collab_invites WHERE id = ?", params![claims.id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match revoked {
+        Some(None) => Ok(claims),
+        Some(Some(_)) => Err("邀请已被吊销".to_string()),
+        None => Err(invalid()),
+    }
+}
+
+pub fn revoke_invite(conn: &Connection, invite_id: &str) -> Result<(), String> {
+    let updated = conn
+        .execute(
+            "UPDATE collab_invites SET revoked_at = ? WHERE id = ? AND revoked_at IS NULL",
+            params![Utc::now().to_rfc3339(), invite_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("邀请不存在或已被吊销".to_string());
+    }
+    Ok(())
+}
+
+/// 项目签发过的邀请，按签发时间倒序
+pub fn list_invites(conn: &Connection, project_id: &str) -> Result<Vec<InviteRecord>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, project_id, role, expires_at, created_at, revoked_at
+             FROM collab_invites WHERE project_id = ? ORDER BY created_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![project_id], |row| {
+            let role: String = row.get(2)?;
+            let Some(role) = CollabRole::parse(&role) else { return Ok(None) };
+            Ok(Some(InviteRecord {
+                claims: InviteClaims { id: row.get(0)?, project_id: row.get(1)?, role, expires_at: row.get(3)? },
+                created_at: row.get(4)?,
+                revoked_at: row.get(5)?,
+            }))
+        })
+        .map_err(|e| e.to_string())?;
+    rows.filter_map(|row| row.transpose()).collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invite_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("invites.db");
+        crate::database::init_database(&db_path).unwrap();
+        let conn = Connection::open(&db_path).unwrap();
+
+        let invite = create_invite(&conn, "p1", CollabRole::Commenter, 3600).unwrap();
+        assert_eq!(verify_invite(&conn, &invite.token, "p1").unwrap(), invite.claims);
+        assert!(verify_invite(&conn, &invite.token, "p2").is_err());
+
+        // 篡改角色后签名不再匹配
+        let (_, signature) = invite.token.split_once('.').unwrap();
+        let forged = InviteClaims { role: CollabRole::Editor, ..invite.claims.clone() };
+        let forged = format!("{}.{}", URL_SAFE_NO_PAD.encode(serde_json::to_vec(&forged).unwrap()), signature);
+        assert!(verify_invite(&conn, &forged, "p1").is_err());
+
+        revoke_invite(&conn, &invite.claims.id).unwrap();
+        assert_eq!(verify_invite(&conn, &invite.token, "p1").unwrap_err(), "邀请已被吊销");
+        assert!(list_invites(&conn, "p1").unwrap()[0].revoked_at.is_some());
+
+        assert!(create_invite(&conn, "p1", CollabRole::Owner, 3600).is_err());
+    }
+}
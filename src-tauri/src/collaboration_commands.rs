@@ -1,16 +1,61 @@
 use crate::collaboration::{CollaborationManager, User, CursorPosition, Operation, CollaborationSession};
+use crate::collaboration_activity::{self, ActivityEntry};
+use crate::collaboration_auth::{self, CollabRole, Invite, InviteRecord};
+use crate::collaboration_replay::{self, ReplayState, TimelineFrame};
+use crate::collaboration_transport::{self, CollabClient, CollabServer, RemoteListener};
 use crate::logger::Logger;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
 
 #[derive(Clone)]
 pub struct CollaborationState {
     manager: Arc<CollaborationManager>,
+    server: Arc<Mutex<Option<CollabServer>>>,
+    clients: Arc<Mutex<HashMap<String, CollabClient>>>,
 }
 
 impl CollaborationState {
     pub fn new() -> Self {
         Self {
             manager: Arc::new(CollaborationManager::new()),
+            server: Arc::new(Mutex::new(None)),
+            clients: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
+
+/// 其他实例的操作、光标和成员变化通过 `collab://remote` 事件通知前端
+fn remote_listener(app: AppHandle) -> RemoteListener {
+    Arc::new(move |update| {
+        let _ = app.emit("collab://remote", update);
+    })
+}
+
+impl CollaborationState {
+    pub fn manager(&self) -> &Arc<CollaborationManager> {
+        &self.manager
+    }
+
+    /// 协作编辑的 CRDT 操作和合并后的正文保存到数据库
+    pub fn with_db_path(db_path: PathBuf) -> Self {
+        Self {
+            manager: Arc::new(CollaborationManager::with_db_path(db_path)),
+            ..Self::new()
         }
     }
 }
@@ -21,28 +66,66 @@ impl Default for CollaborationState {
     }
 }
 
+/// 创建会话，`owner` 作为所有者加入
 #[tauri::command]
 pub async fn collab_create_session(
     project_id: String,
+    owner: User,
     state: tauri::State<'_, CollaborationState>,
 ) -> Result<String, String> {
     let logger = Logger::new().with_feature("collaboration");
     logger.info(&format!("Creating collaboration session for project {}", project_id));
 
-    let session_id = state.manager.create_session(project_id);
+    let session_id = state.manager.create_session(project_id, owner);
     Ok(session_id)
 }
 
+/// 凭主机签发的邀请令牌加入会话，返回授予的权限
 #[tauri::command]
 pub async fn collab_join_session(
     session_id: String,
     user: User,
+    invite_token: String,
     state: tauri::State<'_, CollaborationState>,
-) -> Result<(), String> {
+) -> Result<CollabRole, String> {
     let logger = Logger::new().with_feature("collaboration");
     logger.info(&format!("User {} joining session {}", user.id, session_id));
 
-    state.manager.join_session(&session_id, user)
+    state.manager.join_with_invite(&session_id, user, &invite_token)
+}
+
+/// 签发项目的邀请令牌，`ttl_secs` 默认 7 天
+#[tauri::command]
+pub async fn collab_create_invite(
+    app: AppHandle,
+    project_id: String,
+    role: CollabRole,
+    ttl_secs: Option<i64>,
+) -> Result<Invite, String> {
+    let logger = Logger::new().with_feature("collaboration");
+    logger.info(&format!("Creating {:?} invite for project {}", role, project_id));
+
+    let conn = crate::database::get_connection(&get_db_path(&app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    collaboration_auth::create_invite(&conn, &project_id, role, ttl_secs.unwrap_or(7 * 24 * 3600))
+}
+
+/// 吊销邀请，之后凭该令牌的加入请求都会被拒绝
+#[tauri::command]
+pub async fn collab_revoke_invite(app: AppHandle, invite_id: String) -> Result<(), String> {
+    let logger = Logger::new().with_feature("collaboration");
+    logger.info(&format!("Revoking invite {}", invite_id));
+
+    let conn = crate::database::get_connection(&get_db_path(&app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    collaboration_auth::revoke_invite(&conn, &invite_id)
+}
+
+#[tauri::command]
+pub async fn collab_list_invites(app: AppHandle, project_id: String) -> Result<Vec<InviteRecord>, String> {
+    let conn = crate::database::get_connection(&get_db_path(&app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    collaboration_auth::list_invites(&conn, &project_id)
 }
 
 #[tauri::command]
@@ -69,6 +152,16 @@ pub async fn collab_broadcast_operation(
     state.manager.broadcast_operation(&session_id, operation)
 }
 
+/// 章节在协作会话中合并后的正文，前端收到远端变化或重新连接后用它刷新编辑器
+#[tauri::command]
+pub async fn collab_get_chapter_text(
+    session_id: String,
+    chapter_id: String,
+    state: tauri::State<'_, CollaborationState>,
+) -> Result<String, String> {
+    state.manager.chapter_text(&session_id, &chapter_id)
+}
+
 #[tauri::command]
 pub async fn collab_update_cursor(
     session_id: String,
@@ -104,6 +197,118 @@ pub async fn collab_get_user_cursors(
     Ok(serde_json::to_value(&cursors).unwrap_or(serde_json::Value::Null))
 }
 
+/// 开启内嵌协作主机，返回实际监听的端口；`port` 为空时使用随机端口
+#[tauri::command]
+pub async fn collab_start_server(
+    app: AppHandle,
+    port: Option<u16>,
+    state: tauri::State<'_, CollaborationState>,
+) -> Result<u16, String> {
+    let logger = Logger::new().with_feature("collaboration");
+    if let Some(server) = state.server.lock().unwrap().take() {
+        server.stop();
+    }
+    let server = collaboration_transport::start_server(state.manager.clone(), port.unwrap_or(0), remote_listener(app)).await?;
+    let port = server.port;
+    *state.server.lock().unwrap() = Some(server);
+    logger.info(&format!("Collaboration server listening on port {}", port));
+    Ok(port)
+}
+
+#[tauri::command]
+pub async fn collab_stop_server(state: tauri::State<'_, CollaborationState>) -> Result<(), String> {
+    let logger = Logger::new().with_feature("collaboration");
+    if let Some(server) = state.server.lock().unwrap().take() {
+        server.stop();
+        logger.info("Collaboration server stopped");
+    }
+    Ok(())
+}
+
+/// 凭邀请令牌连接其他实例（局域网主机或中继，`url` 为 `ws://` 或 `wss://` 地址）上的会话，
+/// 之后本地用户的 collab_broadcast_operation 和 collab_update_cursor 会实时发往对端
+#[tauri::command]
+pub async fn collab_connect(
+    app: AppHandle,
+    url: String,
+    session_id: String,
+    invite_token: String,
+    user: User,
+    state: tauri::State<'_, CollaborationState>,
+) -> Result<CollaborationSession, String> {
+    let logger = Logger::new().with_feature("collaboration");
+    logger.info(&format!("User {} connecting to session {} at {}", user.id, session_id, url));
+
+    if let Some(client) = state.clients.lock().unwrap().remove(&session_id) {
+        client.stop();
+    }
+    let (client, session) =
+        collaboration_transport::connect(state.manager.clone(), &url, &session_id, &invite_token, user, remote_listener(app))
+            .await?;
+    state.clients.lock().unwrap().insert(session_id, client);
+    Ok(session)
+}
+
+#[tauri::command]
+pub async fn collab_disconnect(
+    session_id: String,
+    state: tauri::State<'_, CollaborationState>,
+) -> Result<(), String> {
+    let logger = Logger::new().with_feature("collaboration");
+    logger.info(&format!("Disconnecting from session {}", session_id));
+
+    if let Some(client) = state.clients.lock().unwrap().remove(&session_id) {
+        client.stop();
+    }
+    Ok(())
+}
+
+/// 项目在 `since`（RFC 3339，为空时不限）之后的协作动态，按时间倒序，最多 `limit` 条（默认 200）
+#[tauri::command]
+pub async fn collab_get_activity(
+    app: AppHandle,
+    project_id: String,
+    since: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<ActivityEntry>, String> {
+    let logger = Logger::new().with_feature("collaboration");
+    logger.info(&format!("Getting collaboration activity for project {}", project_id));
+
+    let conn = crate::database::get_connection(&get_db_path(&app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    collaboration_activity::list_activity(&conn, &project_id, since.as_deref(), limit.unwrap_or(200))
+}
+
+/// 重放章节记录的编辑操作，得到 `at`（RFC 3339）时刻的正文及各作者字数
+#[tauri::command]
+pub async fn collab_replay_chapter(
+    app: AppHandle,
+    chapter_id: String,
+    at: String,
+) -> Result<ReplayState, String> {
+    let logger = Logger::new().with_feature("collaboration");
+    logger.info(&format!("Replaying chapter {} at {}", chapter_id, at));
+
+    let at = chrono::DateTime::parse_from_rfc3339(&at)
+        .map_err(|e| format!("Invalid timestamp {}: {}", at, e))?
+        .timestamp_millis();
+    let conn = crate::database::get_connection(&get_db_path(&app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    collaboration_replay::replay_chapter(&conn, &chapter_id, at)
+}
+
+/// 章节的编辑时间线，可限定在某个会话内，用于延时回放
+#[tauri::command]
+pub async fn collab_get_chapter_timeline(
+    app: AppHandle,
+    chapter_id: String,
+    session_id: Option<String>,
+) -> Result<Vec<TimelineFrame>, String> {
+    let conn = crate::database::get_connection(&get_db_path(&app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    collaboration_replay::chapter_timeline(&conn, &chapter_id, session_id.as_deref())
+}
+
 #[tauri::command]
 pub async fn collab_generate_user_id() -> Result<String, String> {
     let user_id = format!("user_{}", uuid::Uuid::new_v4());
@@ -0,0 +1,156 @@
+use crate::crdt::{CrdtOp, CrdtOpKind, TextDocument};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 同一客户端相隔不超过这个时间（毫秒）的连续操作合并为时间线上的一帧
+const FRAME_GAP_MS: i64 = 2000;
+
+/// 章节在某一时刻的状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayState {
+    pub chapter_id: String,
+    /// 回放到的时间（Unix 毫秒）
+    pub at: i64,
+    pub content: String,
+    pub word_count: usize,
+    /// 每个作者写下、此时仍可见的字数
+    pub authors: HashMap<String, usize>,
+    pub op_count: usize,
+}
+
+/// 时间线上的一帧：同一作者在一段连续时间内的编辑
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineFrame {
+    pub started_at: i64,
+    pub ended_at: i64,
+    pub author: String,
+    pub session_id: Option<String>,
+    pub inserted: usize,
+    pub deleted: usize,
+}
+
+fn load_ops(conn: &Connection, chapter_id: &str, until: Option<i64>) -> Result<Vec<(CrdtOp, Option<String>)>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT op, session_id FROM chapter_crdt_ops WHERE chapter_id = ? AND timestamp <= ?
+             ORDER BY timestamp, lamport, client_id, seq",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![chapter_id, until.unwrap_or(i64::MAX)], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    let mut ops = Vec::new();
+    for row in rows {
+        let (op, session_id) = row.map_err(|e| e.to_string())?;
+        ops.push((serde_json::from_str(&op).map_err(|e| e.to_string())?, session_id));
+    }
+    Ok(ops)
+}
+
+/// 重放 `at` 之前（含）记录的全部操作，得到章节当时的正文
+pub fn replay_chapter(conn: &Connection, chapter_id: &str, at: i64) -> Result<ReplayState, String> {
+    let mut doc = TextDocument::new();
+    let ops = load_ops(conn, chapter_id, Some(at))?;
+    let op_count = ops.len();
+    for (op, _) in ops {
+        doc.apply(op);
+    }
+    let content = doc.text();
+    Ok(ReplayState {
+        chapter_id: chapter_id.to_string(),
+        at,
+        word_count: content.chars().count(),
+        content,
+        authors: doc.authorship(),
+        op_count,
+    })
+}
+
+/// 章节的编辑时间线，按时间排序；指定 `session_id` 时只包含该会话的操作。
+/// 依次回放每帧的 `ended_at` 即可得到延时播放的效果
+pub fn chapter_timeline(conn: &Connection, chapter_id: &str, session_id: Option<&str>) -> Result<Vec<TimelineFrame>, String> {
+    let mut frames: Vec<TimelineFrame> = Vec::new();
+    for (op, op_session) in load_ops(conn, chapter_id, None)? {
+        if session_id.is_some() && op_session.as_deref() != session_id {
+            continue;
+        }
+        let (inserted, deleted) = match op.kind {
+            CrdtOpKind::Insert { .. } => (1, 0),
+            CrdtOpKind::Delete { .. } => (0, 1),
+        };
+        match frames.last_mut() {
+            Some(frame)
+                if frame.author == op.id.client
+                    && frame.session_id == op_session
+                    && op.timestamp - frame.ended_at <= FRAME_GAP_MS =>
+            {
+                frame.ended_at = op.timestamp;
+                frame.inserted += inserted;
+                frame.deleted += deleted;
+            }
+            _ => frames.push(TimelineFrame {
+                started_at: op.timestamp,
+                ended_at: op.timestamp,
+                author: op.id.client.clone(),
+                session_id: op_session,
+                inserted,
+                deleted,
+            }),
+        }
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collaboration::{CollaborationManager, Operation, OperationType, User};
+    use crate::collaboration_auth::CollabRole;
+
+    #[test]
+    fn test_replay_at_timestamp() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("replay.db");
+        {
+            let conn = crate::test_support::init_project_db(&db_path);
+            conn.execute(
+                "INSERT INTO chapters (id, project_id, title, content, created_at, updated_at) VALUES ('c1', 'p1', '第一章', '', 't0', 't0')",
+                [],
+            )
+            .unwrap();
+        }
+        let manager = CollaborationManager::with_db_path(db_path.clone());
+        let user = |id: &str| User { id: id.to_string(), name: id.to_string(), color: "#FF6B6B".to_string() };
+        let session_id = manager.create_session("p1".to_string(), user("alice"));
+        manager.join_session(&session_id, user("bob"), CollabRole::Editor).unwrap();
+        let edit = |user_id: &str, op_type: OperationType| Operation {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            chapter_id: "c1".to_string(),
+            op_type,
+            timestamp: 0,
+        };
+        manager.broadcast_operation(&session_id, edit("alice", OperationType::Insert { position: 0, text: "雨夜".into() })).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let middle = chrono::Utc::now().timestamp_millis();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        manager
+            .broadcast_operation(&session_id, edit("bob", OperationType::Replace { position: 1, length: 1, text: "后".into() }))
+            .unwrap();
+
+        let conn = Connection::open(&db_path).unwrap();
+        let then = replay_chapter(&conn, "c1", middle).unwrap();
+        assert_eq!(then.content, "雨夜");
+        assert_eq!(then.authors["alice"], 2);
+        let now = replay_chapter(&conn, "c1", i64::MAX).unwrap();
+        assert_eq!(now.content, "雨后");
+        assert_eq!((now.authors["alice"], now.authors["bob"]), (1, 1));
+
+        let frames = chapter_timeline(&conn, "c1", Some(&session_id)).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!((frames[1].author.as_str(), frames[1].inserted, frames[1].deleted), ("bob", 1, 1));
+    }
+}
@@ -0,0 +1,451 @@
+use crate::collaboration::{ChapterChanges, CollaborationManager, CollaborationSession, SessionEvent, User};
+use crate::collaboration_auth::CollabRole;
+use crate::crdt::StateVector;
+use crate::logger::Logger;
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, watch};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+/// 协作连接上传输的消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WireMessage {
+    /// 客户端连接后发送的第一条消息，`token` 为主机签发的邀请令牌
+    Join { session_id: String, user: User, token: String },
+    /// 主机对 Join 的应答，附带会话当前的成员、光标和各章节的状态向量
+    Welcome {
+        session: CollaborationSession,
+        #[serde(default)]
+        versions: HashMap<String, StateVector>,
+    },
+    /// 请求对端补发 `versions` 之后的操作，客户端重新连接后发送
+    SyncRequest { versions: HashMap<String, StateVector> },
+    /// 本端转发时漏掉了操作，请对端回复 SyncRequest 以便补发
+    Resync,
+    Changes { changes: ChapterChanges },
+    Event { event: SessionEvent },
+    Error { message: String },
+}
+
+/// 从其他实例收到的变化，转发给前端
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RemoteUpdate {
+    /// 章节有新的操作，`content` 为合并后的正文
+    Changes { session_id: String, chapter_id: String, user_id: String, content: String },
+    Event { session_id: String, event: SessionEvent },
+}
+
+pub type RemoteListener = Arc<dyn Fn(RemoteUpdate) + Send + Sync>;
+
+async fn send_json<S, T>(socket: &mut WebSocketStream<S>, value: &T) -> Result<(), String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let text = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    socket.send(Message::Text(text)).await.map_err(|e| format!("协作连接已断开: {}", e))
+}
+
+async fn recv_json<S>(socket: &mut WebSocketStream<S>) -> Result<WireMessage, String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    while let Some(message) = socket.next().await {
+        match message.map_err(|e| format!("协作连接已断开: {}", e))? {
+            Message::Text(text) => return serde_json::from_str(&text).map_err(|e| format!("协作消息无效: {}", e)),
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    Err("协作连接已关闭".to_string())
+}
+
+/// 主机检查客户端 `peer` 发来的消息：只能以自己的身份发送，且权限足够
+fn authorize_peer(manager: &CollaborationManager, session_id: &str, peer: &str, message: &WireMessage) -> Result<(), String> {
+    let (user_id, allowed): (&str, fn(&CollabRole) -> bool) = match message {
+        WireMessage::Changes { changes } => (&changes.user_id, CollabRole::can_edit),
+        WireMessage::Event { event: event @ SessionEvent::Cursor { .. } } => (event.user_id(), |_| true),
+        WireMessage::Event { event: event @ (SessionEvent::Comment { .. } | SessionEvent::CommentDeleted { .. }) } => {
+            (event.user_id(), CollabRole::can_comment)
+        }
+        WireMessage::Event { .. } => return Err("客户端不能发送成员变化".to_string()),
+        _ => return Ok(()),
+    };
+    if user_id != peer {
+        return Err("不能以其他成员的身份发送消息".to_string());
+    }
+    manager.authorize(session_id, peer, allowed)
+}
+
+/// 把对端发来的消息应用到本地会话，返回需要回复的消息。
+/// 主机一端的 `peer` 为连接的客户端用户，消息先经过权限检查
+fn apply_remote(
+    manager: &CollaborationManager,
+    session_id: &str,
+    peer: Option<&str>,
+    message: WireMessage,
+    listener: &RemoteListener,
+) -> Result<Vec<WireMessage>, String> {
+    if let Some(peer) = peer {
+        authorize_peer(manager, session_id, peer, &message)?;
+    }
+    match message {
+        WireMessage::Changes { changes } => {
+            let chapter_id = changes.chapter_id.clone();
+            let user_id = changes.user_id.clone();
+            if manager.apply_changes(session_id, changes)? {
+                let content = manager.chapter_text(session_id, &chapter_id)?;
+                listener(RemoteUpdate::Changes { session_id: session_id.to_string(), chapter_id, user_id, content });
+            }
+        }
+        WireMessage::SyncRequest { versions } => {
+            return Ok(manager
+                .changes_since(session_id, &versions, "")?
+                .into_iter()
+                .map(|changes| WireMessage::Changes { changes })
+                .collect());
+        }
+        WireMessage::Resync => return Ok(vec![WireMessage::SyncRequest { versions: manager.versions(session_id)? }]),
+        WireMessage::Event { event } => {
+            match &event {
+                // 欢迎消息中的成员列表可能已经包含该用户
+                SessionEvent::Joined { user, role } if manager.role_of(session_id, &user.id).is_none() => {
+                    manager.join_session(session_id, user.clone(), *role)?
+                }
+                SessionEvent::Joined { .. } => {}
+                SessionEvent::Left { user_id } => manager.leave_session(session_id, user_id)?,
+                SessionEvent::Cursor { cursor } => manager.update_cursor(session_id, cursor.clone())?,
+                SessionEvent::Comment { .. } | SessionEvent::CommentDeleted { .. } => {
+                    manager.apply_comment_event(session_id, event.clone())?
+                }
+            }
+            listener(RemoteUpdate::Event { session_id: session_id.to_string(), event });
+        }
+        WireMessage::Error { message } => return Err(message),
+        WireMessage::Join { .. } | WireMessage::Welcome { .. } => {}
+    }
+    Ok(Vec::new())
+}
+
+/// 本地会话的变化订阅，在握手完成前建立，避免遗漏握手期间的操作
+struct Subscription {
+    operations: broadcast::Receiver<ChapterChanges>,
+    events: broadcast::Receiver<SessionEvent>,
+}
+
+impl Subscription {
+    fn new(manager: &CollaborationManager, session_id: &str) -> Result<Self, String> {
+        Ok(Self {
+            operations: manager.subscribe_operations(session_id).ok_or("Session not found")?,
+            events: manager.subscribe_events(session_id).ok_or("Session not found")?,
+        })
+    }
+}
+
+/// 连接中本地一端的身份
+#[derive(Clone, Copy)]
+enum Endpoint<'a> {
+    /// 主机，对端是已加入会话的客户端用户 `peer`
+    Host { peer: &'a str },
+    /// 客户端，本地用户为 `user`
+    Client { user: &'a str },
+}
+
+impl Endpoint<'_> {
+    /// 是否把 `user_id` 产生的变化发给对端，避免回传
+    fn forward(&self, user_id: &str) -> bool {
+        match self {
+            Endpoint::Host { peer } => user_id != *peer,
+            Endpoint::Client { user } => user_id == *user,
+        }
+    }
+
+    fn peer(&self) -> Option<&str> {
+        match self {
+            Endpoint::Host { peer } => Some(peer),
+            Endpoint::Client { .. } => None,
+        }
+    }
+}
+
+/// 在本地会话和一个连接之间双向转发
+async fn relay<S>(
+    socket: WebSocketStream<S>,
+    manager: &CollaborationManager,
+    session_id: &str,
+    endpoint: Endpoint<'_>,
+    subscription: Subscription,
+    listener: &RemoteListener,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<(), String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let Subscription { mut operations, mut events } = subscription;
+    let (mut sink, mut stream) = socket.split();
+    let mut outgoing = Vec::new();
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => {
+                let _ = sink.close().await;
+                return Ok(());
+            }
+            message = stream.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let message = serde_json::from_str(&text).map_err(|e| format!("协作消息无效: {}", e))?;
+                    outgoing = apply_remote(manager, session_id, endpoint.peer(), message, listener)?;
+                }
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(format!("协作连接已断开: {}", e)),
+            },
+            operation = operations.recv() => match operation {
+                Ok(changes) if endpoint.forward(&changes.user_id) => outgoing.push(WireMessage::Changes { changes }),
+                Ok(_) => {}
+                // 转发跟不上时缓冲区里的操作被跳过，按对端的状态向量补发
+                Err(broadcast::error::RecvError::Lagged(_)) => outgoing.push(WireMessage::Resync),
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            event = events.recv() => match event {
+                Ok(event) if endpoint.forward(event.user_id()) => outgoing.push(WireMessage::Event { event }),
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+        }
+        for message in outgoing.drain(..) {
+            let text = serde_json::to_string(&message).map_err(|e| e.to_string())?;
+            sink.send(Message::Text(text)).await.map_err(|e| format!("协作连接已断开: {}", e))?;
+        }
+    }
+}
+
+/// 内嵌的协作主机，其他实例通过 `ws://<地址>:<port>` 加入本机的会话
+pub struct CollabServer {
+    pub port: u16,
+    shutdown: watch::Sender<bool>,
+}
+
+impl CollabServer {
+    /// 停止监听并断开所有连接
+    pub fn stop(self) {
+        let _ = self.shutdown.send(true);
+    }
+}
+
+/// 开启协作主机，`port` 为 0 时使用随机端口
+pub async fn start_server(manager: Arc<CollaborationManager>, port: u16, listener: RemoteListener) -> Result<CollabServer, String> {
+    let logger = Logger::new().with_feature("collaboration");
+    let tcp = TcpListener::bind(("0.0.0.0", port)).await.map_err(|e| format!("无法开启协作服务: {}", e))?;
+    let port = tcp.local_addr().map_err(|e| e.to_string())?.port();
+    let (shutdown_tx, mut shutdown_rx) = watch::channel(false);
+
+    let connection_shutdown = shutdown_rx.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.changed() => break,
+                accepted = tcp.accept() => {
+                    let Ok((stream, addr)) = accepted else { continue };
+                    let manager = manager.clone();
+                    let listener = listener.clone();
+                    let shutdown = connection_shutdown.clone();
+                    let logger = logger.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = serve_connection(stream, manager, listener, shutdown).await {
+                            logger.warn(&format!("Collaboration connection from {} ended: {}", addr, e));
+                        }
+                    });
+                }
+            }
+        }
+    });
+    Ok(CollabServer { port, shutdown: shutdown_tx })
+}
+
+async fn serve_connection(
+    stream: TcpStream,
+    manager: Arc<CollaborationManager>,
+    listener: RemoteListener,
+    shutdown: watch::Receiver<bool>,
+) -> Result<(), String> {
+    let mut socket = tokio_tungstenite::accept_async(stream).await.map_err(|e| e.to_string())?;
+    let WireMessage::Join { session_id, user, token } = recv_json(&mut socket).await? else {
+        return Err("协作握手消息无效".to_string());
+    };
+    let role = match manager.join_with_invite(&session_id, user.clone(), &token) {
+        Ok(role) => role,
+        Err(e) => {
+            send_json(&mut socket, &WireMessage::Error { message: e.clone() }).await?;
+            return Err(e);
+        }
+    };
+    listener(RemoteUpdate::Event { session_id: session_id.clone(), event: SessionEvent::Joined { user: user.clone(), role } });
+    let subscription = Subscription::new(&manager, &session_id)?;
+    let session = manager.get_session(&session_id).ok_or("Session not found")?;
+    let versions = manager.versions(&session_id)?;
+    send_json(&mut socket, &WireMessage::Welcome { session, versions }).await?;
+
+    let result = relay(socket, &manager, &session_id, Endpoint::Host { peer: &user.id }, subscription, &listener, shutdown).await;
+    // 连接断开即视为离开会话
+    if manager.leave_session(&session_id, &user.id).is_ok() {
+        listener(RemoteUpdate::Event { session_id, event: SessionEvent::Left { user_id: user.id } });
+    }
+    result
+}
+
+/// 加入远端主机会话的客户端连接
+pub struct CollabClient {
+    shutdown: watch::Sender<bool>,
+}
+
+impl CollabClient {
+    pub fn stop(self) {
+        let _ = self.shutdown.send(true);
+    }
+}
+
+/// 以 `user` 身份、凭邀请令牌 `token` 连接 `url`（`ws://` 或 `wss://`，可以是局域网主机或中继）上的会话，
+/// 成功后本地保存会话副本，本地用户的操作自动发往主机，主机转发的操作进入本地会话
+pub async fn connect(
+    manager: Arc<CollaborationManager>,
+    url: &str,
+    session_id: &str,
+    token: &str,
+    user: User,
+    listener: RemoteListener,
+) -> Result<(CollabClient, CollaborationSession), String> {
+    let (mut socket, _) = tokio_tungstenite::connect_async(url).await.map_err(|e| format!("无法连接协作主机: {}", e))?;
+    let join = WireMessage::Join { session_id: session_id.to_string(), user: user.clone(), token: token.to_string() };
+    send_json(&mut socket, &join).await?;
+    let (session, host_versions) = match recv_json(&mut socket).await? {
+        WireMessage::Welcome { session, versions } => (session, versions),
+        WireMessage::Error { message } => return Err(message),
+        _ => return Err("协作握手消息无效".to_string()),
+    };
+    manager.insert_session(session.clone());
+    let subscription = Subscription::new(&manager, &session.id)?;
+
+    // 离线期间的编辑发给主机，并请求主机补发本地缺少的操作
+    for changes in manager.changes_since(&session.id, &host_versions, &user.id)? {
+        send_json(&mut socket, &WireMessage::Changes { changes }).await?;
+    }
+    let versions = manager.versions(&session.id)?;
+    send_json(&mut socket, &WireMessage::SyncRequest { versions }).await?;
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let session_id = session.id.clone();
+    let task_session_id = session_id.clone();
+    tokio::spawn(async move {
+        let logger = Logger::new().with_feature("collaboration");
+        let result = relay(socket, &manager, &task_session_id, Endpoint::Client { user: &user.id }, subscription, &listener, shutdown_rx).await;
+        match result {
+            Ok(()) => logger.info(&format!("Left remote collaboration session {}", task_session_id)),
+            Err(e) => logger.warn(&format!("Remote collaboration session {} disconnected: {}", task_session_id, e)),
+        }
+    });
+    Ok((CollabClient { shutdown: shutdown_tx }, session))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collaboration::{CursorPosition, Operation, OperationType};
+    use std::time::Duration;
+
+    fn user(id: &str) -> User {
+        User { id: id.to_string(), name: id.to_string(), color: "#FF6B6B".to_string() }
+    }
+
+    fn insert(user_id: &str, text: &str) -> Operation {
+        Operation {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            chapter_id: "c1".to_string(),
+            op_type: OperationType::Insert { position: 0, text: text.to_string() },
+            timestamp: 0,
+        }
+    }
+
+    /// 等待条件成立，最多 1 秒
+    async fn eventually(condition: impl Fn() -> bool) -> bool {
+        for _ in 0..50 {
+            if condition() {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        false
+    }
+
+    #[tokio::test]
+    async fn test_edits_converge_between_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("host.db");
+        let conn = crate::test_support::init_project_db(&db_path);
+        conn.execute(
+            "INSERT INTO chapters (id, project_id, title, content, created_at, updated_at) VALUES ('c1', 'p1', '第一章', '', 't0', 't0')",
+            [],
+        )
+        .unwrap();
+        let invite = crate::collaboration_auth::create_invite(&conn, "p1", CollabRole::Editor, 3600).unwrap();
+        let viewer_invite = crate::collaboration_auth::create_invite(&conn, "p1", CollabRole::Viewer, 3600).unwrap();
+
+        let host = Arc::new(CollaborationManager::with_db_path(db_path));
+        let session_id = host.create_session("p1".to_string(), user("alice"));
+        // 邀请令牌不能用来顶替已在会话中的所有者
+        assert!(host.join_with_invite(&session_id, user("alice"), &invite.token).is_err());
+        assert_eq!(host.role_of(&session_id, "alice"), Some(CollabRole::Owner));
+        let listener: RemoteListener = Arc::new(|_| {});
+        let server = start_server(host.clone(), 0, listener.clone()).await.unwrap();
+
+        let guest = Arc::new(CollaborationManager::new());
+        let url = format!("ws://127.0.0.1:{}", server.port);
+        assert!(connect(guest.clone(), &url, &session_id, "forged", user("bob"), listener.clone()).await.is_err());
+        let (client, session) = connect(guest.clone(), &url, &session_id, &invite.token, user("bob"), listener.clone()).await.unwrap();
+        assert_eq!(session.users.len(), 2);
+        assert_eq!(session.roles["bob"], CollabRole::Editor);
+
+        // 只读成员不能编辑
+        let reader = Arc::new(CollaborationManager::new());
+        let (reader_client, _) =
+            connect(reader.clone(), &url, &session_id, &viewer_invite.token, user("carol"), listener.clone()).await.unwrap();
+        assert!(reader.broadcast_operation(&session_id, insert("carol", "风")).is_err());
+        reader_client.stop();
+        assert!(eventually(|| host.get_session(&session_id).unwrap().users.len() == 2).await);
+
+        // 两端同时在开头输入
+        guest.broadcast_operation(&session_id, insert("bob", "雨")).unwrap();
+        host.broadcast_operation(&session_id, insert("alice", "夜")).unwrap();
+        let text = |m: &CollaborationManager| m.chapter_text(&session_id, "c1").unwrap();
+        assert!(eventually(|| text(&host).chars().count() == 2 && text(&host) == text(&guest)).await);
+
+        let cursor = CursorPosition { user_id: "bob".to_string(), chapter_id: "c1".to_string(), line: 1, column: 2 };
+        guest.update_cursor(&session_id, cursor).unwrap();
+        assert!(eventually(|| host.get_user_cursors(&session_id).contains_key("bob")).await);
+
+        client.stop();
+        assert!(eventually(|| host.get_session(&session_id).unwrap().users.len() == 1).await);
+
+        // 断开期间两端各自编辑，重新连接后合并
+        guest.broadcast_operation(&session_id, insert("bob", "长")).unwrap();
+        host.broadcast_operation(&session_id, insert("alice", "的")).unwrap();
+        let (client, _) = connect(guest.clone(), &url, &session_id, &invite.token, user("bob"), listener).await.unwrap();
+        assert!(eventually(|| text(&host).chars().count() == 4 && text(&host) == text(&guest)).await);
+
+        // 连续的操作超出转发缓冲区，跳过的部分通过重新同步补齐
+        for _ in 0..150 {
+            host.broadcast_operation(&session_id, insert("alice", "雨")).unwrap();
+        }
+        assert!(eventually(|| text(&guest).chars().count() == 154).await);
+
+        client.stop();
+        server.stop();
+    }
+}
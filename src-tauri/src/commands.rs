@@ -1,6 +1,7 @@
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Emitter, Manager};
 use crate::models::{*, AIParams, APIKeyInfo, ModelInfo};
 use crate::database::get_connection;
+use crate::repository::ProjectRepository;
 use crate::logger::{Logger, log_command_start, log_command_success, log_command_error};
 use crate::ai::{ModelConfig, PromptTemplate};
 use crate::ai::models::{
@@ -14,8 +15,27 @@ use crate::ai::{
     GeneratedCharacter, GeneratedCharacterRelation,
     GeneratedWorldView, GeneratedPlotPoint, GeneratedStoryboard,
 };
-use crate::export::{ExportFormat, ExportMetadata, ExportContent};
-use crate::import::{ImportFormat, ImportResult, import_from_txt, import_from_markdown, import_from_docx};
+use crate::ai::task_queue::{
+    claim_task, is_task_cancelled, load_task, save_task, update_task_progress, update_task_state, CreateTaskRequest,
+    QueuedTask, TaskQueue, TaskState, TaskType,
+};
+use crate::export::ExportFormat;
+use crate::import::{
+    apply_import_mapping, list_import_files, ChapterSplitDryRun, ChapterSplitRules, DirectoryFileResult,
+    DirectoryImportMode, DirectoryImportResult, ImportChapterMapping, ImportDirectoryRequest, ImportFormat,
+    ImportMonitor, ImportPreview, ImportResult, ImportState, ImportedChapter,
+};
+use crate::import::table_import::{
+    map_table_rows, read_table, TableImportPreview, TableImportRequest, TableImportResult, TableImportTarget, TableRowError,
+};
+use crate::import::dedupe::{
+    find_duplicates, plan_chapter_actions, ChapterImportAction, DuplicateChapter, DuplicateStrategy, ExistingChapter,
+};
+use crate::import::{import_from_docx, import_from_epub, import_from_html, import_from_markdown, import_from_pdf, import_from_txt};
+use crate::import::url_import::{
+    extract_chapter_links, load_url_import_items, save_url_import_items, update_url_import_item,
+    PoliteFetcher, UrlImportItem, UrlImportItemStatus, UrlImportProgress, UrlImportRequest,
+};
 use uuid::Uuid;
 use chrono::Utc;
 use serde::{Serialize, Deserialize};
@@ -96,39 +116,12 @@ pub async fn get_projects(app: AppHandle) -> Result<Vec<Project>, String> {
             e.to_string()
         })?;
 
-    let mut stmt = conn
-        .prepare("SELECT id, name, description, genre, template, status, created_at, updated_at FROM projects ORDER BY updated_at DESC")
-        .map_err(|e| {
-            logger.error(&format!("Failed to prepare statement: {}", e));
-            e.to_string()
-        })?;
-
-    let projects_iter = stmt
-        .query_map([], |row| {
-            Ok(Project {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                genre: row.get(3)?,
-                template: row.get(4)?,
-                status: row.get(5)?,
-                created_at: row.get(6)?,
-                updated_at: row.get(7)?,
-            })
-        })
+    let projects = ProjectRepository::list_projects(&conn)
         .map_err(|e| {
-            logger.error(&format!("Failed to execute query: {}", e));
+            logger.error(&format!("Failed to query projects: {}", e));
             e.to_string()
         })?;
 
-    let mut projects = Vec::new();
-    for project in projects_iter {
-        projects.push(project.map_err(|e| {
-            logger.error(&format!("Failed to map project: {}", e));
-            e.to_string()
-        })?);
-    }
-
     log_command_success(&logger, "get_projects", &format!("Retrieved {} projects", projects.len()));
     Ok(projects)
 }
@@ -287,44 +280,12 @@ pub async fn get_chapters(app: AppHandle, projectId: String) -> Result<Vec<Chapt
             e.to_string()
         })?;
 
-    let mut stmt = conn
-        .prepare("SELECT id, project_id, title, content, word_count, sort_order, status, created_at, updated_at, summary FROM chapters WHERE project_id = ? ORDER BY sort_order ASC")
-        .map_err(|e| {
-            logger.error(&format!("Failed to prepare statement: {}", e));
-            e.to_string()
-        })?;
-
-    let chapters_iter = stmt
-        .query_map(&[&projectId], |row| {
-            Ok(Chapter {
-                id: row.get(0)?,
-                project_id: row.get(1)?,
-                title: row.get(2)?,
-                content: row.get(3)?,
-                word_count: row.get(4)?,
-                sort_order: row.get(5)?,
-                status: row.get(6)?,
-                created_at: row.get(7)?,
-                updated_at: row.get(8)?,
-                versions: None,
-                evaluation: None,
-                generation_status: None,
-                summary: row.get(9).ok(),
-            })
-        })
+    let chapters = ProjectRepository::list_chapters(&conn, &projectId)
         .map_err(|e| {
-            logger.error(&format!("Failed to execute query: {}", e));
+            logger.error(&format!("Failed to query chapters: {}", e));
             e.to_string()
         })?;
 
-    let mut chapters = Vec::new();
-    for chapter in chapters_iter {
-        chapters.push(chapter.map_err(|e| {
-            logger.error(&format!("Failed to map chapter: {}", e));
-            e.to_string()
-        })?);
-    }
-
     log_command_success(&logger, "get_chapters", &format!("Retrieved {} chapters", chapters.len()));
     Ok(chapters)
 }
@@ -342,34 +303,14 @@ pub async fn get_chapter(app: AppHandle, chapterId: String) -> Result<Chapter, S
             e.to_string()
         })?;
 
-    let mut stmt = conn
-        .prepare("SELECT id, project_id, title, content, word_count, sort_order, status, created_at, updated_at, summary FROM chapters WHERE id = ?")
-        .map_err(|e| {
-            logger.error(&format!("Failed to prepare statement: {}", e));
-            e.to_string()
-        })?;
-
-    let chapter = stmt
-        .query_row(&[&chapterId], |row| {
-            Ok(Chapter {
-                id: row.get(0)?,
-                project_id: row.get(1)?,
-                title: row.get(2)?,
-                content: row.get(3)?,
-                word_count: row.get(4)?,
-                sort_order: row.get(5)?,
-                status: row.get(6)?,
-                created_at: row.get(7)?,
-                updated_at: row.get(8)?,
-                versions: None,
-                evaluation: None,
-                generation_status: None,
-                summary: row.get(9).ok(),
-            })
-        })
+    let chapter = ProjectRepository::get_chapter(&conn, &chapterId)
         .map_err(|e| {
             log_command_error(&logger, "get_chapter", &format!("Failed to execute query: {}", e));
             e.to_string()
+        })?
+        .ok_or_else(|| {
+            log_command_error(&logger, "get_chapter", &format!("Chapter not found: {}", chapterId));
+            format!("Chapter not found: {}", chapterId)
         })?;
 
     log_command_success(&logger, "get_chapter", &format!("Retrieved chapter: {}", chapterId));
@@ -387,7 +328,6 @@ pub async fn update_chapter(
     log_command_start(&logger, "update_chapter", &format!("chapterId: {}", chapterId));
 
     let now = Utc::now().to_rfc3339();
-    let word_count = content.as_ref().map(|c| c.chars().count() as i32);
 
     let db_path = get_db_path(&app)?;
 
@@ -397,6 +337,15 @@ pub async fn update_chapter(
             e.to_string()
         })?;
 
+    let word_count = content.as_ref().map(|c| c.chars().count() as i32);
+
+    let previous_content: Option<String> = match content {
+        Some(_) => conn
+            .query_row("SELECT COALESCE(content, '') FROM chapters WHERE id = ?", params![chapterId], |row| row.get(0))
+            .ok(),
+        None => None,
+    };
+
     conn.execute(
         "UPDATE chapters SET title = COALESCE(?, title), content = COALESCE(?, content), word_count = COALESCE(?, word_count), updated_at = ? WHERE id = ?",
         params![title, content, word_count, now, chapterId],
@@ -435,6 +384,16 @@ pub async fn update_chapter(
             e.to_string()
         })?;
 
+    if let Some(previous) = previous_content {
+        // 自动更正、溯源、统计和索引在后台防抖执行，不拖慢保存
+        if let Some(post_save) = app.try_state::<crate::chapter_post_save::ChapterPostSaveState>() {
+            post_save.schedule(app.clone(), chapterId.clone(), previous);
+        }
+        if let Some(auto_snapshot) = app.try_state::<crate::auto_snapshot::AutoSnapshotState>() {
+            auto_snapshot.schedule(chapter.project_id.clone());
+        }
+    }
+
     log_command_success(&logger, "update_chapter", &format!("Updated chapter: {}", chapterId));
     Ok(chapter)
 }
@@ -533,6 +492,7 @@ pub async fn create_character(app: AppHandle, request: CreateCharacterRequest) -
         logger.error(&format!("Failed to insert character: {}", e));
         e.to_string()
     })?;
+    crate::tokenizer::add_words(&[&character.name]);
 
     log_command_success(&logger, "create_character", &format!("Created character: {}", character.id));
     Ok(character)
@@ -552,7 +512,7 @@ pub async fn get_characters(app: AppHandle, projectId: String) -> Result<Vec<Cha
         })?;
 
     let mut stmt = conn
-        .prepare("SELECT id, project_id, name, role_type, race, age, gender, birth_date, appearance, personality, background, skills, status, bazi, ziwei, mbti, enneagram, items, avatar_url, created_at, updated_at FROM characters WHERE project_id = ? ORDER BY created_at DESC")
+        .prepare("SELECT id, project_id, name, role_type, race, age, gender, birth_date, appearance, personality, background, skills, status, bazi, ziwei, mbti, enneagram, items, avatar_url, created_at, updated_at FROM characters WHERE project_id = ? AND merged_into IS NULL ORDER BY created_at DESC")
         .map_err(|e| {
             logger.error(&format!("Failed to prepare statement: {}", e));
             e.to_string()
@@ -1122,6 +1082,7 @@ pub async fn create_world_view(app: AppHandle, request: CreateWorldViewRequest)
         logger.error(&format!("Failed to insert world view: {}", e));
         e.to_string()
     })?;
+    crate::tokenizer::add_words(&[&world_view.title]);
 
     log_command_success(&logger, "create_world_view", &format!("Created world view: {}", world_view.id));
     Ok(world_view)
@@ -1231,6 +1192,12 @@ pub async fn update_world_view(app: AppHandle, request: UpdateWorldViewRequest)
             e.to_string()
         })?;
 
+    crate::worldview_history::record_revision(&conn, &request.id, Some("修改前"))
+        .map_err(|e| {
+            logger.error(&format!("Failed to save world view revision: {}", e));
+            e
+        })?;
+
     conn.execute(
         "UPDATE world_views SET category = COALESCE(?, category), title = COALESCE(?, title), content = COALESCE(?, content), tags = COALESCE(?, tags), status = COALESCE(?, status), updated_at = ? WHERE id = ?",
         params![request.category, request.title, request.content, request.tags, request.status, now, request.id],
@@ -1282,6 +1249,12 @@ pub async fn delete_world_view(app: AppHandle, id: String) -> Result<(), String>
             e.to_string()
         })?;
 
+    crate::worldview_history::record_revision(&conn, &id, Some("删除前"))
+        .map_err(|e| {
+            logger.error(&format!("Failed to save world view revision: {}", e));
+            e
+        })?;
+
     conn.execute(
         "DELETE FROM world_views WHERE id = ?",
         [&id],
@@ -1311,7 +1284,7 @@ pub async fn get_character_graph(
         })?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, project_id, name, avatar_url FROM characters WHERE project_id = ?"
+        "SELECT id, project_id, name, avatar_url FROM characters WHERE project_id = ? AND merged_into IS NULL"
     )
     .map_err(|e| {
         logger.error(&format!("Failed to prepare statement: {}", e));
@@ -1518,7 +1491,7 @@ pub async fn ai_continue_novel(
             let mut stmt = conn
                 .prepare(
                     "SELECT name, role_type, race, gender, age, personality, skills, status
-                     FROM characters WHERE project_id = ?"
+                     FROM characters WHERE project_id = ? AND merged_into IS NULL"
                 )
                 .map_err(|e| e.to_string())?;
 
@@ -1581,6 +1554,33 @@ pub async fn ai_continue_novel(
 
             request.worldview_context = Some(worldviews.join("\n\n"));
         }
+
+        // 注入术语表，让续写使用规范写法
+        match crate::glossary::prompt_section(&conn, project_id) {
+            Ok(Some(glossary)) => request.instruction = format!("{}\n\n{}", request.instruction, glossary),
+            Ok(None) => {}
+            Err(e) => logger.warn(&format!("Failed to load glossary: {}", e)),
+        }
+
+        // 注入本章地点；没有指定章节时取导演脚本所属的章节
+        let chapter_id = request.chapter_id.clone().or_else(|| {
+            let mission_id = request.chapter_mission_id.as_ref()?;
+            conn.query_row("SELECT chapter_id FROM chapter_missions WHERE id = ?", [mission_id], |row| row.get(0)).ok()
+        });
+        if let Some(chapter_id) = &chapter_id {
+            match crate::locations::prompt_section(&conn, chapter_id) {
+                Ok(Some(locations)) => request.instruction = format!("{}\n\n{}", request.instruction, locations),
+                Ok(None) => {}
+                Err(e) => logger.warn(&format!("Failed to load chapter locations: {}", e)),
+            }
+        }
+
+        // 注入货币、物价和人物此时的身家
+        match crate::economy::prompt_section(&conn, project_id, chapter_id.as_deref()) {
+            Ok(Some(economy)) => request.instruction = format!("{}\n\n{}", request.instruction, economy),
+            Ok(None) => {}
+            Err(e) => logger.warn(&format!("Failed to load economy context: {}", e)),
+        }
     }
 
     // L3写作层：信息可见性过滤
@@ -1621,11 +1621,16 @@ pub async fn ai_continue_novel(
     let ai_service = app.state::<std::sync::Arc<tokio::sync::RwLock<AIService>>>();
     let service = ai_service.read().await;
 
+    let (model_id, project_id) = (request.model_id.clone(), request.project_id.clone());
     let result = service.continue_novel(request, None).await.map_err(|e| {
         logger.error(&format!("Failed to continue novel: {}", e));
         e
     })?;
 
+    if let Err(e) = crate::provenance::record_generation(&conn, project_id.as_deref(), crate::provenance::ContentSource::AiContinue, &model_id, &result) {
+        logger.warn(&format!("Failed to record AI generation: {}", e));
+    }
+
     log_command_success(&logger, "ai_continue_novel", "Novel continuation completed");
     Ok(result)
 }
@@ -1641,11 +1646,19 @@ pub async fn ai_rewrite_content(
     let ai_service = app.state::<std::sync::Arc<tokio::sync::RwLock<AIService>>>();
     let service = ai_service.read().await;
     
+    let model_id = request.model_id.clone();
     let result = service.rewrite_content(request).await.map_err(|e| {
         logger.error(&format!("Failed to rewrite content: {}", e));
         e
     })?;
 
+    let recorded = get_db_path(&app)
+        .and_then(|db_path| get_connection(&db_path).map_err(|e| e.to_string()))
+        .and_then(|conn| crate::provenance::record_generation(&conn, None, crate::provenance::ContentSource::AiRewrite, &model_id, &result));
+    if let Err(e) = recorded {
+        logger.warn(&format!("Failed to record AI generation: {}", e));
+    }
+
     log_command_success(&logger, "ai_rewrite_content", "Content rewrite completed");
     Ok(result)
 }
@@ -1718,6 +1731,7 @@ pub async fn set_bigmodel_api_key(
     let ai_service = app.state::<std::sync::Arc<tokio::sync::RwLock<AIService>>>();
     let service = ai_service.read().await;
     service.get_registry().initialize_default_bigmodel_models().await;
+    service.initialize_default_embedding_models().await;
 
     log_command_success(&logger, "set_bigmodel_api_key", "BigModel API key updated successfully");
     Ok(())
@@ -1843,7 +1857,7 @@ pub async fn ai_generate_character(
 
         // 获取已有角色
         let mut stmt = conn
-            .prepare("SELECT name, gender, age, personality FROM characters WHERE project_id = ?")
+            .prepare("SELECT name, gender, age, personality FROM characters WHERE project_id = ? AND merged_into IS NULL")
             .map_err(|e| e.to_string())?;
         
         let existing_characters: Vec<(String, Option<String>, Option<i32>, Option<String>)> = stmt
@@ -1924,7 +1938,7 @@ pub async fn ai_generate_character_relations(
 
         // 获取项目中的所有角色
         let mut stmt = conn
-            .prepare("SELECT id, project_id, name, role_type, race, age, gender, birth_date, appearance, personality, background, skills, status, bazi, ziwei, mbti, enneagram, items, avatar_url, created_at, updated_at FROM characters WHERE project_id = ?")
+            .prepare("SELECT id, project_id, name, role_type, race, age, gender, birth_date, appearance, personality, background, skills, status, bazi, ziwei, mbti, enneagram, items, avatar_url, created_at, updated_at FROM characters WHERE project_id = ? AND merged_into IS NULL")
             .map_err(|e| {
                 logger.error(&format!("Failed to prepare statement: {}", e));
                 e.to_string()
@@ -2056,7 +2070,7 @@ pub async fn ai_generate_worldview(
 
         // 获取角色信息
         let mut stmt = conn
-            .prepare("SELECT name, gender, age, personality, background FROM characters WHERE project_id = ?")
+            .prepare("SELECT name, gender, age, personality, background FROM characters WHERE project_id = ? AND merged_into IS NULL")
             .map_err(|e| e.to_string())?;
         
         let characters: Vec<(String, Option<String>, Option<i32>, Option<String>, Option<String>)> = stmt
@@ -2196,7 +2210,7 @@ pub async fn ai_generate_plot_points(
 
         // 获取角色信息
         let mut stmt = conn
-            .prepare("SELECT name, gender, age, personality FROM characters WHERE project_id = ?")
+            .prepare("SELECT name, gender, age, personality FROM characters WHERE project_id = ? AND merged_into IS NULL")
             .map_err(|e| e.to_string())?;
         
         let characters: Vec<(String, Option<String>, Option<i32>, Option<String>)> = stmt
@@ -2561,6 +2575,7 @@ pub async fn set_api_key(app: AppHandle, provider: String, apiKey: String) -> Re
         let ai_service = app.state::<std::sync::Arc<tokio::sync::RwLock<AIService>>>();
         let service = ai_service.read().await;
         service.get_registry().initialize_default_bigmodel_models().await;
+        service.initialize_default_embedding_models().await;
     }
 
     log_command_success(&logger, "set_api_key", &format!("API key set for: {}", provider));
@@ -2633,7 +2648,7 @@ pub async fn generate_writing_choices(
 
         // 获取角色
         let mut stmt = conn
-            .prepare("SELECT id, project_id, name, role_type, race, age, gender, birth_date, appearance, personality, background, skills, status, bazi, ziwei, mbti, enneagram, items, avatar_url, created_at, updated_at FROM characters WHERE project_id = ?")
+            .prepare("SELECT id, project_id, name, role_type, race, age, gender, birth_date, appearance, personality, background, skills, status, bazi, ziwei, mbti, enneagram, items, avatar_url, created_at, updated_at FROM characters WHERE project_id = ? AND merged_into IS NULL")
             .map_err(|e| e.to_string())?;
         let characters: Vec<Character> = stmt
             .query_map([&request.project_id], |row| {
@@ -2743,7 +2758,7 @@ pub async fn validate_writing(
 
         // 获取角色
         let mut stmt = conn
-            .prepare("SELECT id, project_id, name, role_type, race, age, gender, birth_date, appearance, personality, background, skills, status, bazi, ziwei, mbti, enneagram, items, avatar_url, created_at, updated_at FROM characters WHERE project_id = ?")
+            .prepare("SELECT id, project_id, name, role_type, race, age, gender, birth_date, appearance, personality, background, skills, status, bazi, ziwei, mbti, enneagram, items, avatar_url, created_at, updated_at FROM characters WHERE project_id = ? AND merged_into IS NULL")
             .map_err(|e| e.to_string())?;
         let characters: Vec<Character> = stmt
             .query_map([&request.project_id], |row| {
@@ -2989,12 +3004,13 @@ pub async fn create_character_timeline_event(
     let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
 
     let sort_order = request.sort_order.unwrap_or(0);
+    let story_date = request.story_date.as_ref().and_then(|date| serde_json::to_string(date).ok());
 
     conn.execute(
         "INSERT INTO character_timeline_events 
         (id, character_id, event_type, event_title, event_description, story_time, 
-         real_chapter_id, emotional_state, state_changes, sort_order, created_at, updated_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+         real_chapter_id, emotional_state, state_changes, sort_order, created_at, updated_at, story_date)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         params![
             id,
             request.character_id,
@@ -3008,6 +3024,7 @@ pub async fn create_character_timeline_event(
             sort_order,
             now,
             now,
+            story_date,
         ],
     ).map_err(|e| e.to_string())?;
 
@@ -3018,6 +3035,7 @@ pub async fn create_character_timeline_event(
         event_title: request.event_title,
         event_description: request.event_description,
         story_time: request.story_time,
+        story_date: request.story_date,
         real_chapter_id: request.real_chapter_id,
         emotional_state: request.emotional_state,
         state_changes: request.state_changes,
@@ -3043,7 +3061,7 @@ pub async fn get_character_timeline(app: AppHandle, character_id: String) -> Res
         .prepare(
             "SELECT id, character_id, event_type, event_title, event_description, 
                     story_time, real_chapter_id, emotional_state, state_changes, 
-                    sort_order, created_at, updated_at
+                    sort_order, created_at, updated_at, story_date
              FROM character_timeline_events 
              WHERE character_id = ? 
              ORDER BY sort_order ASC, created_at ASC"
@@ -3059,6 +3077,7 @@ pub async fn get_character_timeline(app: AppHandle, character_id: String) -> Res
                 event_title: row.get(3)?,
                 event_description: row.get(4)?,
                 story_time: row.get(5)?,
+                story_date: row.get::<_, Option<String>>(12)?.and_then(|json| serde_json::from_str(&json).ok()),
                 real_chapter_id: row.get(6)?,
                 emotional_state: row.get(7)?,
                 state_changes: row.get(8)?,
@@ -3096,6 +3115,7 @@ pub async fn update_character_timeline_event(
          event_title = COALESCE(?, event_title),
          event_description = COALESCE(?, event_description),
          story_time = COALESCE(?, story_time),
+         story_date = COALESCE(?, story_date),
          real_chapter_id = COALESCE(?, real_chapter_id),
          emotional_state = COALESCE(?, emotional_state),
          state_changes = COALESCE(?, state_changes),
@@ -3107,6 +3127,7 @@ pub async fn update_character_timeline_event(
             request.event_title,
             request.event_description,
             request.story_time,
+            request.story_date.as_ref().and_then(|date| serde_json::to_string(date).ok()),
             request.real_chapter_id,
             request.emotional_state,
             request.state_changes,
@@ -3120,7 +3141,7 @@ pub async fn update_character_timeline_event(
         .prepare(
             "SELECT id, character_id, event_type, event_title, event_description, 
                     story_time, real_chapter_id, emotional_state, state_changes, 
-                    sort_order, created_at, updated_at
+                    sort_order, created_at, updated_at, story_date
              FROM character_timeline_events WHERE id = ?"
         )
         .map_err(|e| e.to_string())?;
@@ -3134,6 +3155,7 @@ pub async fn update_character_timeline_event(
                 event_title: row.get(3)?,
                 event_description: row.get(4)?,
                 story_time: row.get(5)?,
+                story_date: row.get::<_, Option<String>>(12)?.and_then(|json| serde_json::from_str(&json).ok()),
                 real_chapter_id: row.get(6)?,
                 emotional_state: row.get(7)?,
                 state_changes: row.get(8)?,
@@ -3182,12 +3204,13 @@ pub async fn create_worldview_timeline_event(
     let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
 
     let sort_order = request.sort_order.unwrap_or(0);
+    let story_date = request.story_date.as_ref().and_then(|date| serde_json::to_string(date).ok());
 
     conn.execute(
         "INSERT INTO worldview_timeline_events 
         (id, worldview_id, event_type, event_title, event_description, story_time, 
-         impact_scope, related_characters, sort_order, created_at, updated_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+         impact_scope, related_characters, sort_order, created_at, updated_at, story_date)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         params![
             id,
             request.worldview_id,
@@ -3200,6 +3223,7 @@ pub async fn create_worldview_timeline_event(
             sort_order,
             now,
             now,
+            story_date,
         ],
     ).map_err(|e| e.to_string())?;
 
@@ -3210,6 +3234,7 @@ pub async fn create_worldview_timeline_event(
         event_title: request.event_title,
         event_description: request.event_description,
         story_time: request.story_time,
+        story_date: request.story_date,
         impact_scope: request.impact_scope,
         related_characters: request.related_characters,
         sort_order,
@@ -3233,7 +3258,7 @@ pub async fn get_worldview_timeline(app: AppHandle, worldview_id: String) -> Res
     let mut stmt = conn
         .prepare(
             "SELECT id, worldview_id, event_type, event_title, event_description, 
-                    story_time, impact_scope, related_characters, sort_order, created_at, updated_at
+                    story_time, impact_scope, related_characters, sort_order, created_at, updated_at, story_date
              FROM worldview_timeline_events 
              WHERE worldview_id = ? 
              ORDER BY sort_order ASC, created_at ASC"
@@ -3249,6 +3274,7 @@ pub async fn get_worldview_timeline(app: AppHandle, worldview_id: String) -> Res
                 event_title: row.get(3)?,
                 event_description: row.get(4)?,
                 story_time: row.get(5)?,
+                story_date: row.get::<_, Option<String>>(11)?.and_then(|json| serde_json::from_str(&json).ok()),
                 impact_scope: row.get(6)?,
                 related_characters: row.get(7)?,
                 sort_order: row.get(8)?,
@@ -3285,6 +3311,7 @@ pub async fn update_worldview_timeline_event(
          event_title = COALESCE(?, event_title),
          event_description = COALESCE(?, event_description),
          story_time = COALESCE(?, story_time),
+         story_date = COALESCE(?, story_date),
          impact_scope = COALESCE(?, impact_scope),
          related_characters = COALESCE(?, related_characters),
          sort_order = COALESCE(?, sort_order),
@@ -3295,6 +3322,7 @@ pub async fn update_worldview_timeline_event(
             request.event_title,
             request.event_description,
             request.story_time,
+            request.story_date.as_ref().and_then(|date| serde_json::to_string(date).ok()),
             request.impact_scope,
             request.related_characters,
             request.sort_order,
@@ -3306,7 +3334,7 @@ pub async fn update_worldview_timeline_event(
     let mut stmt = conn
         .prepare(
             "SELECT id, worldview_id, event_type, event_title, event_description, 
-                    story_time, impact_scope, related_characters, sort_order, created_at, updated_at
+                    story_time, impact_scope, related_characters, sort_order, created_at, updated_at, story_date
              FROM worldview_timeline_events WHERE id = ?"
         )
         .map_err(|e| e.to_string())?;
@@ -3320,6 +3348,7 @@ pub async fn update_worldview_timeline_event(
                 event_title: row.get(3)?,
                 event_description: row.get(4)?,
                 story_time: row.get(5)?,
+                story_date: row.get::<_, Option<String>>(11)?.and_then(|json| serde_json::from_str(&json).ok()),
                 impact_scope: row.get(6)?,
                 related_characters: row.get(7)?,
                 sort_order: row.get(8)?,
@@ -3387,6 +3416,10 @@ pub async fn create_knowledge_entry(
         ],
     ).map_err(|e| e.to_string())?;
 
+    if let Err(e) = crate::knowledge_links::link_entry(&conn, &id) {
+        logger.warn(&format!("Failed to link knowledge mentions: {}", e));
+    }
+
     let entry = KnowledgeEntry {
         id,
         project_id: request.project_id,
@@ -3513,6 +3546,12 @@ pub async fn update_knowledge_entry(
     let now = Utc::now().to_rfc3339();
     let is_verified = request.is_verified.map(|v| if v { 1 } else { 0 });
 
+    crate::knowledge_history::record_revision(&conn, &request.id, Some("修改前"))
+        .map_err(|e| {
+            logger.error(&format!("Failed to save knowledge revision: {}", e));
+            e
+        })?;
+
     conn.execute(
         "UPDATE knowledge_entries SET 
          entry_type = COALESCE(?, entry_type),
@@ -3562,6 +3601,12 @@ pub async fn update_knowledge_entry(
         })
         .map_err(|e| e.to_string())?;
 
+    if request.title.is_some() || request.keywords.is_some() {
+        if let Err(e) = crate::knowledge_links::link_entry(&conn, &request.id) {
+            logger.warn(&format!("Failed to link knowledge mentions: {}", e));
+        }
+    }
+
     log_command_success(&logger, "update_knowledge_entry", &request.id);
     Ok(entry)
 }
@@ -3575,6 +3620,12 @@ pub async fn delete_knowledge_entry(app: AppHandle, entry_id: String) -> Result<
     let db_path = get_db_path(&app)?;
     let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
 
+    crate::knowledge_history::record_revision(&conn, &entry_id, Some("删除前"))
+        .map_err(|e| {
+            logger.error(&format!("Failed to save knowledge revision: {}", e));
+            e
+        })?;
+
     conn.execute("DELETE FROM knowledge_entries WHERE id = ?", [&entry_id])
         .map_err(|e| e.to_string())?;
 
@@ -3582,7 +3633,42 @@ pub async fn delete_knowledge_entry(app: AppHandle, entry_id: String) -> Result<
     Ok(())
 }
 
-/// 搜索知识条目
+/// 补齐项目中过期的知识条目向量，并返回查询文本的向量；向量服务不可用时返回 None，由调用方退回关键词检索
+async fn knowledge_query_embedding(app: &AppHandle, logger: &Logger, project_id: &str, text: &str) -> Option<Vec<f32>> {
+    use crate::knowledge_search::{self, EMBEDDING_BATCH_SIZE, EMBEDDING_MODEL};
+
+    let result: Result<Vec<f32>, String> = async {
+        let db_path = get_db_path(app)?;
+        let pending = {
+            let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+            knowledge_search::stale_entries(&conn, project_id, EMBEDDING_MODEL)?
+        };
+        let ai_service = app.state::<std::sync::Arc<tokio::sync::RwLock<AIService>>>();
+        let service = ai_service.read().await;
+        for batch in pending.chunks(EMBEDDING_BATCH_SIZE) {
+            let texts: Vec<String> = batch.iter().map(|p| p.text.clone()).collect();
+            let vectors = service.embed(EMBEDDING_MODEL, &texts).await?;
+            let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+            knowledge_search::store_embeddings(&conn, EMBEDDING_MODEL, batch, &vectors)?;
+        }
+        service
+            .embed(EMBEDDING_MODEL, &[text.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| "向量服务未返回结果".to_string())
+    }
+    .await;
+
+    match result {
+        Ok(vector) => Some(vector),
+        Err(e) => {
+            logger.warn(&format!("Semantic search unavailable, falling back to keywords: {}", e));
+            None
+        }
+    }
+}
+
+/// 搜索知识条目：优先按语义相似度检索，向量服务不可用时退回关键词匹配
 #[tauri::command]
 pub async fn search_knowledge(
     app: AppHandle,
@@ -3591,101 +3677,49 @@ pub async fn search_knowledge(
     let logger = Logger::new().with_feature("knowledge");
     log_command_start(&logger, "search_knowledge", &request.query);
 
+    let embedding = knowledge_query_embedding(&app, &logger, &request.project_id, &request.query).await;
     let db_path = get_db_path(&app)?;
     let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    let results = crate::knowledge_search::search(
+        &conn,
+        &request.project_id,
+        &request.query,
+        embedding.as_deref(),
+        request.entry_types.as_deref(),
+        request.limit.unwrap_or(20).max(0) as usize,
+    )?;
 
-    let limit = request.limit.unwrap_or(20);
-    let search_pattern = format!("%{}%", request.query);
-
-    let sql = if let Some(ref types) = request.entry_types {
-        let placeholders: Vec<String> = types.iter().map(|_| "?".to_string()).collect();
-        format!(
-            "SELECT id, project_id, entry_type, title, content, source_type, source_id, 
-                    keywords, importance, is_verified, created_at, updated_at
-             FROM knowledge_entries 
-             WHERE project_id = ? AND entry_type IN ({}) AND (title LIKE ? OR content LIKE ? OR keywords LIKE ?)
-             ORDER BY importance DESC
-             LIMIT ?",
-            placeholders.join(",")
-        )
-    } else {
-        "SELECT id, project_id, entry_type, title, content, source_type, source_id, 
-                keywords, importance, is_verified, created_at, updated_at
-         FROM knowledge_entries 
-         WHERE project_id = ? AND (title LIKE ? OR content LIKE ? OR keywords LIKE ?)
-         ORDER BY importance DESC
-         LIMIT ?".to_string()
-    };
-
-    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
-
-    let results = if let Some(ref types) = request.entry_types {
-        let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![
-            Box::new(request.project_id.clone()),
-            Box::new(search_pattern.clone()),
-            Box::new(search_pattern.clone()),
-            Box::new(search_pattern.clone()),
-        ];
-        for t in types {
-            params_vec.push(Box::new(t.clone()));
-        }
-        params_vec.push(Box::new(limit));
+    log_command_success(&logger, "search_knowledge", &format!("Found {} results", results.len()));
+    Ok(results)
+}
 
-        let params: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
-        
-        stmt.query_map(params.as_slice(), |row| {
-            Ok(KnowledgeSearchResult {
-                entry: KnowledgeEntry {
-                    id: row.get(0)?,
-                    project_id: row.get(1)?,
-                    entry_type: row.get(2)?,
-                    title: row.get(3)?,
-                    content: row.get(4)?,
-                    source_type: row.get(5)?,
-                    source_id: row.get(6)?,
-                    keywords: row.get(7)?,
-                    importance: row.get(8)?,
-                    is_verified: row.get::<_, i32>(9)? != 0,
-                    created_at: row.get(10)?,
-                    updated_at: row.get(11)?,
-                },
-                relevance_score: 1.0,
-                match_type: "keyword".to_string(),
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string())?
-    } else {
-        stmt.query_map(
-            params![&request.project_id, &search_pattern, &search_pattern, &search_pattern, limit],
-            |row| {
-                Ok(KnowledgeSearchResult {
-                    entry: KnowledgeEntry {
-                        id: row.get(0)?,
-                        project_id: row.get(1)?,
-                        entry_type: row.get(2)?,
-                        title: row.get(3)?,
-                        content: row.get(4)?,
-                        source_type: row.get(5)?,
-                        source_id: row.get(6)?,
-                        keywords: row.get(7)?,
-                        importance: row.get(8)?,
-                        is_verified: row.get::<_, i32>(9)? != 0,
-                        created_at: row.get(10)?,
-                        updated_at: row.get(11)?,
-                    },
-                    relevance_score: 1.0,
-                    match_type: "keyword".to_string(),
-                })
-            },
-        )
-        .map_err(|e| e.to_string())?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string())?
-    };
+/// 查找与一段正文相关的所有知识条目
+#[tauri::command]
+pub async fn find_related_knowledge(
+    app: AppHandle,
+    project_id: String,
+    paragraph: String,
+    limit: Option<i32>,
+) -> Result<Vec<KnowledgeSearchResult>, String> {
+    let logger = Logger::new().with_feature("knowledge");
+    log_command_start(&logger, "find_related_knowledge", &project_id);
 
-    log_command_success(&logger, "search_knowledge", &format!("Found {} results", results.len()));
+    if paragraph.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let embedding = knowledge_query_embedding(&app, &logger, &project_id, &paragraph).await;
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    let results = crate::knowledge_search::search(
+        &conn,
+        &project_id,
+        &paragraph,
+        embedding.as_deref(),
+        None,
+        limit.unwrap_or(10).max(0) as usize,
+    )?;
+
+    log_command_success(&logger, "find_related_knowledge", &format!("Found {} results", results.len()));
     Ok(results)
 }
 
@@ -3807,13 +3841,16 @@ pub async fn build_knowledge_context(
     let include_worldview = request.include_worldview.unwrap_or(true);
     let include_plot = request.include_plot.unwrap_or(true);
     let include_timeline = request.include_timeline.unwrap_or(true);
+    let include_organizations = request.include_organizations.unwrap_or(true);
+    let include_facts = request.include_facts.unwrap_or(true);
 
     // 构建角色摘要
     let characters_summary = if include_characters {
         let mut stmt = conn
             .prepare(
-                "SELECT name, role_type, race, gender, age, personality, skills, status
-                 FROM characters WHERE project_id = ?"
+                "SELECT name, role_type, race, gender, age, personality, skills, status,
+                        (SELECT GROUP_CONCAT(alias, '、') FROM character_aliases WHERE character_id = characters.id)
+                 FROM characters WHERE project_id = ? AND merged_into IS NULL"
             )
             .map_err(|e| e.to_string())?;
 
@@ -3827,9 +3864,11 @@ pub async fn build_knowledge_context(
                 let personality: Option<String> = row.get(5)?;
                 let skills: Option<String> = row.get(6)?;
                 let status: Option<String> = row.get(7)?;
+                let aliases: Option<String> = row.get(8)?;
 
                 let mut parts = vec![name];
                 if let Some(r) = role_type { parts.push(format!("[{}]", r)); }
+                if let Some(a) = aliases { parts.push(format!("又称:{}", a)); }
                 if let Some(r) = race { parts.push(format!("种族:{}", r)); }
                 if let Some(g) = gender { parts.push(format!("性别:{}", g)); }
                 if let Some(a) = age { parts.push(format!("年龄:{}", a)); }
@@ -3922,10 +3961,54 @@ pub async fn build_knowledge_context(
         vec![]
     };
 
+    // 构建组织摘要
+    let organizations_summary = if include_organizations {
+        crate::organizations::context_summary(&conn, &request.project_id, request.chapter_id.as_deref())?
+    } else {
+        String::new()
+    };
+
+    // 知识库事实，冲突的说法优先采用定论；知道光标位置时只取光标附近提到的条目
+    let facts = if include_facts {
+        let nearby = match (&request.chapter_id, request.cursor_offset) {
+            (Some(chapter_id), Some(cursor)) => Some(
+                crate::knowledge_links::entries_near(&conn, chapter_id, cursor, crate::knowledge_links::DEFAULT_WINDOW)?
+                    .into_iter()
+                    .map(|e| e.entry_id)
+                    .collect::<Vec<_>>(),
+            ),
+            _ => None,
+        };
+        crate::knowledge_facts::context_facts(&conn, &request.project_id, nearby.as_deref())?
+    } else {
+        Vec::new()
+    };
+    let facts_summary = crate::knowledge_facts::format_context(&facts);
+
+    // 记下这次上下文用到的条目版本，便于事后排查提示词
+    let generation_run_id = if facts.is_empty() {
+        None
+    } else {
+        let entry_ids: Vec<String> = facts.iter().map(|f| f.entry_id.clone()).collect();
+        match crate::knowledge_history::record_generation_run(
+            &conn,
+            &request.project_id,
+            "knowledge_context",
+            request.chapter_id.as_deref(),
+            &entry_ids,
+        ) {
+            Ok(run_id) => Some(run_id),
+            Err(e) => {
+                logger.warn(&format!("Failed to record knowledge generation run: {}", e));
+                None
+            }
+        }
+    };
+
     // 获取活跃角色
     let active_characters: Vec<String> = conn
         .query_row(
-            "SELECT GROUP_CONCAT(name, ',') FROM characters WHERE project_id = ? AND role_type IN ('protagonist', 'deuteragonist')",
+            "SELECT GROUP_CONCAT(name, ',') FROM characters WHERE project_id = ? AND merged_into IS NULL AND role_type IN ('protagonist', 'deuteragonist')",
             [&request.project_id],
             |row| row.get(0),
         )
@@ -3944,6 +4027,9 @@ pub async fn build_knowledge_context(
         active_characters,
         current_location: None,
         timeline_context: String::new(),
+        organizations_summary,
+        facts_summary,
+        generation_run_id,
     };
 
     log_command_success(&logger, "build_knowledge_context", "Context built");
@@ -4015,6 +4101,12 @@ pub async fn sync_character_to_knowledge(
 
     if let Some(existing) = existing_id {
         // 更新现有条目
+        crate::knowledge_history::record_revision(&conn, &existing, Some("修改前"))
+            .map_err(|e| {
+                logger.error(&format!("Failed to save knowledge revision: {}", e));
+                e
+            })?;
+
         conn.execute(
             "UPDATE knowledge_entries SET title = ?, content = ?, keywords = ?, updated_at = ? WHERE id = ?",
             params![&name, &content, &keywords, &now, &existing],
@@ -4121,6 +4213,12 @@ pub async fn sync_worldview_to_knowledge(
     let now = Utc::now().to_rfc3339();
 
     if let Some(existing) = existing_id {
+        crate::knowledge_history::record_revision(&conn, &existing, Some("修改前"))
+            .map_err(|e| {
+                logger.error(&format!("Failed to save knowledge revision: {}", e));
+                e
+            })?;
+
         conn.execute(
             "UPDATE knowledge_entries SET title = ?, content = ?, keywords = ?, updated_at = ? WHERE id = ?",
             params![&title, &content, &keywords, &now, &existing],
@@ -4352,6 +4450,9 @@ pub struct ComicPanel {
     pub dialogue: Vec<ComicDialogue>,
     pub sound_effects: Option<Vec<String>>,
     pub visual_prompt: Option<String>,
+    /// 生成后的分格图片，本地路径或 data URL
+    #[serde(default)]
+    pub image_path: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -4748,6 +4849,9 @@ pub struct ExportProjectRequest {
     pub project_id: String,
     pub format: String,
     pub output_path: Option<String>,
+    /// 覆盖设置中的作者署名
+    #[serde(default)]
+    pub author: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -4755,6 +4859,9 @@ pub struct ExportChapterRequest {
     pub chapter_id: String,
     pub format: String,
     pub output_path: Option<String>,
+    /// 覆盖设置中的作者署名
+    #[serde(default)]
+    pub author: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -4771,6 +4878,7 @@ pub fn format_from_str(format_str: &str) -> Result<ExportFormat, String> {
         "pdf" => Ok(ExportFormat::Pdf),
         "epub" => Ok(ExportFormat::Epub),
         "txt" | "text" => Ok(ExportFormat::Txt),
+        "md-folder" | "md_folder" | "obsidian" => Ok(ExportFormat::MdFolder),
         _ => Err(format!("不支持的导出格式: {}", format_str)),
     }
 }
@@ -4788,23 +4896,17 @@ pub async fn export_project(
     let db_path = get_db_path(&app)?;
     let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
 
-    let project: (String, String, String, String) = conn
-        .query_row(
-            "SELECT id, title, description, author FROM projects WHERE id = ?",
-            [&request.project_id],
-            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
-        )
+    let content = crate::export::load_project_content(&conn, &request.project_id, request.author.as_deref())
         .map_err(|e| e.to_string())?;
 
-    let chapters: Vec<(String, String, i32, String)> = conn
-        .prepare("SELECT id, title, chapter_number, content FROM chapters WHERE project_id = ? ORDER BY chapter_number")
-        .map_err(|e| e.to_string())?
-        .query_map([&request.project_id], |row| {
-            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string())?;
+    let validation = crate::export::validate_project_export(&content, Some(export_format));
+    if !validation.ready {
+        let errors: Vec<String> = validation.issues.iter()
+            .filter(|i| i.severity == crate::export::ExportIssueSeverity::Error)
+            .map(|i| i.message.clone())
+            .collect();
+        return Err(format!("导出前检查未通过: {}", errors.join("；")));
+    }
 
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     let export_dir = app_data_dir.join("exports");
@@ -4813,32 +4915,15 @@ pub async fn export_project(
         std::fs::create_dir_all(&export_dir).map_err(|e| e.to_string())?;
     }
 
-    let filename = format!("{}_{}.{}", sanitize_filename(&project.1), Utc::now().format("%Y%m%d_%H%M%S"), export_format.extension());
+    let filename = format!("{}_{}.{}", sanitize_filename(&content.metadata.title), Utc::now().format("%Y%m%d_%H%M%S"), export_format.extension());
     let output_path = if let Some(path) = request.output_path {
         PathBuf::from(path)
+    } else if export_format == ExportFormat::MdFolder {
+        export_dir.join(format!("{}_{}", sanitize_filename(&content.metadata.title), Utc::now().format("%Y%m%d_%H%M%S")))
     } else {
         export_dir.join(&filename)
     };
 
-    let metadata = ExportMetadata {
-        title: project.1.clone(),
-        author: project.3.clone(),
-        description: Some(project.2.clone()),
-        created_at: Utc::now().to_rfc3339(),
-        word_count: chapters.iter().map(|c| c.3.chars().count()).sum(),
-        chapter_count: chapters.len(),
-    };
-
-    let content = ExportContent {
-        metadata,
-        chapters: chapters.iter().map(|c| crate::export::ChapterContent {
-            id: c.0.clone(),
-            title: c.1.clone(),
-            number: c.2 as usize,
-            content: c.3.clone(),
-        }).collect(),
-    };
-
     match export_format {
         ExportFormat::Docx => {
             crate::export::export_as_docx(&content, &output_path).map_err(|e| e.to_string())?;
@@ -4855,9 +4940,12 @@ pub async fn export_project(
         ExportFormat::Md => {
             crate::export::export_as_md(&content, &output_path).map_err(|e| e.to_string())?;
         }
+        ExportFormat::MdFolder => {
+            crate::export::export_as_md_folder(&content, &output_path).map_err(|e| e.to_string())?;
+        }
     }
 
-    let file_size = std::fs::metadata(&output_path).map_err(|e| e.to_string())?.len();
+    let file_size = exported_size(&output_path)?;
 
     let result = ExportResult {
         success: true,
@@ -4883,13 +4971,9 @@ pub async fn export_chapter(
     let db_path = get_db_path(&app)?;
     let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
 
-    let chapter: (String, String, String, i32, String, String) = conn
-        .query_row(
-            "SELECT c.id, c.title, c.content, c.chapter_number, p.title, p.author FROM chapters c JOIN projects p ON c.project_id = p.id WHERE c.id = ?",
-            [&request.chapter_id],
-            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
-        )
+    let content = crate::export::load_chapter_content(&conn, &request.chapter_id, request.author.as_deref())
         .map_err(|e| e.to_string())?;
+    let chapter = &content.chapters[0];
 
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     let export_dir = app_data_dir.join("exports");
@@ -4898,32 +4982,15 @@ pub async fn export_chapter(
         std::fs::create_dir_all(&export_dir).map_err(|e| e.to_string())?;
     }
 
-    let filename = format!("{}_{}.{}", sanitize_filename(&chapter.1), chapter.3, export_format.extension());
+    let filename = format!("{}_{}.{}", sanitize_filename(&chapter.title), chapter.number, export_format.extension());
     let output_path = if let Some(path) = request.output_path {
         PathBuf::from(path)
+    } else if export_format == ExportFormat::MdFolder {
+        export_dir.join(format!("{}_{}", sanitize_filename(&chapter.title), chapter.number))
     } else {
         export_dir.join(&filename)
     };
 
-    let metadata = ExportMetadata {
-        title: chapter.1.clone(),
-        author: chapter.5.clone(),
-        description: None,
-        created_at: Utc::now().to_rfc3339(),
-        word_count: chapter.2.chars().count(),
-        chapter_count: 1,
-    };
-
-    let content = ExportContent {
-        metadata,
-        chapters: vec![crate::export::ChapterContent {
-            id: chapter.0.clone(),
-            title: chapter.1.clone(),
-            number: chapter.3 as usize,
-            content: chapter.2.clone(),
-        }],
-    };
-
     match export_format {
         ExportFormat::Docx => {
             crate::export::export_as_docx(&content, &output_path).map_err(|e| e.to_string())?;
@@ -4940,9 +5007,12 @@ pub async fn export_chapter(
         ExportFormat::Md => {
             crate::export::export_as_md(&content, &output_path).map_err(|e| e.to_string())?;
         }
+        ExportFormat::MdFolder => {
+            crate::export::export_as_md_folder(&content, &output_path).map_err(|e| e.to_string())?;
+        }
     }
 
-    let file_size = std::fs::metadata(&output_path).map_err(|e| e.to_string())?.len();
+    let file_size = exported_size(&output_path)?;
 
     let result = ExportResult {
         success: true,
@@ -4962,27 +5032,239 @@ pub async fn get_export_formats() -> Result<Vec<String>, String> {
         "pdf".to_string(),
         "epub".to_string(),
         "txt".to_string(),
+        "md-folder".to_string(),
     ])
 }
 
-fn sanitize_filename(filename: &str) -> String {
-    filename
-        .chars()
-        .map(|c| match c {
-            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
-            _ => c,
-        })
-        .collect()
-}
+/// 导出前检查：报告缺失的书名、作者、简介以及空章节，不写入任何文件
+#[tauri::command]
+pub async fn validate_export(
+    app: AppHandle,
+    project_id: String,
+    format: Option<String>,
+    author: Option<String>,
+) -> Result<crate::export::ExportValidation, String> {
+    let logger = Logger::new().with_feature("export");
+    log_command_start(&logger, "validate_export", &format!("project: {}, format: {:?}", project_id, format));
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ImportFileRequest {
-    pub file_path: String,
-    pub format: String,
-}
+    let export_format = match &format {
+        Some(f) => Some(format_from_str(f)?),
+        None => None,
+    };
 
-#[tauri::command]
-pub async fn import_file(
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+
+    let content = crate::export::load_project_content(&conn, &project_id, author.as_deref())
+        .map_err(|e| e.to_string())?;
+    let validation = crate::export::validate_project_export(&content, export_format);
+
+    log_command_success(&logger, "validate_export", &format!("ready: {}, issues: {}", validation.ready, validation.issues.len()));
+    Ok(validation)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportCharacterGraphRequest {
+    pub project_id: String,
+    pub format: String,
+    pub output_path: Option<String>,
+}
+
+#[tauri::command]
+pub async fn export_character_graph(
+    app: AppHandle,
+    request: ExportCharacterGraphRequest,
+) -> Result<ExportResult, String> {
+    let logger = Logger::new().with_feature("export");
+    log_command_start(&logger, "export_character_graph", &format!("project: {}, format: {}", request.project_id, request.format));
+
+    let graph_format = crate::export::GraphExportFormat::from_name(&request.format)
+        .ok_or_else(|| format!("不支持的关系图导出格式: {}", request.format))?;
+
+    let graph = get_character_graph(app.clone(), request.project_id.clone()).await?;
+
+    let output_path = if let Some(path) = request.output_path {
+        PathBuf::from(path)
+    } else {
+        let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+        let export_dir = app_data_dir.join("exports");
+        if !export_dir.exists() {
+            std::fs::create_dir_all(&export_dir).map_err(|e| e.to_string())?;
+        }
+        export_dir.join(format!("character_graph_{}{}", Utc::now().format("%Y%m%d_%H%M%S"), graph_format.extension()))
+    };
+
+    match graph_format {
+        crate::export::GraphExportFormat::GraphML => {
+            crate::export::export_graph_as_graphml(&graph, &output_path).map_err(|e| e.to_string())?;
+        }
+        crate::export::GraphExportFormat::Mermaid => {
+            crate::export::export_graph_as_mermaid(&graph, &output_path).map_err(|e| e.to_string())?;
+        }
+        crate::export::GraphExportFormat::Svg => {
+            crate::export::export_graph_as_svg(&graph, &output_path).map_err(|e| e.to_string())?;
+        }
+        crate::export::GraphExportFormat::Png => {
+            crate::export::export_graph_as_png(&graph, &output_path).map_err(|e| e.to_string())?;
+        }
+    }
+
+    let file_size = exported_size(&output_path)?;
+
+    let result = ExportResult {
+        success: true,
+        output_path: output_path.to_string_lossy().to_string(),
+        file_size,
+        format: graph_format.extension().to_string(),
+    };
+
+    log_command_success(&logger, "export_character_graph", &result.output_path);
+    Ok(result)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportWorldviewWikiRequest {
+    pub project_id: String,
+    /// 不填时导出到固定目录，便于增量生成
+    pub output_dir: Option<String>,
+}
+
+#[tauri::command]
+pub async fn export_worldview_wiki(
+    app: AppHandle,
+    request: ExportWorldviewWikiRequest,
+) -> Result<crate::export::WikiExportResult, String> {
+    let logger = Logger::new().with_feature("export");
+    log_command_start(&logger, "export_worldview_wiki", &format!("project: {}", request.project_id));
+
+    let output_dir = if let Some(dir) = request.output_dir {
+        PathBuf::from(dir)
+    } else {
+        let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+        app_data_dir.join("exports").join(format!("wiki_{}", request.project_id))
+    };
+
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    let result = crate::export::export_wiki(&conn, &request.project_id, &output_dir).map_err(|e| e.to_string())?;
+
+    log_command_success(&logger, "export_worldview_wiki", &format!("{}: {} written, {} unchanged, {} removed",
+        result.output_dir, result.written.len(), result.unchanged, result.removed.len()));
+    Ok(result)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportComicRequest {
+    pub comic: ComicResult,
+    pub format: String,
+    #[serde(default)]
+    pub credits: crate::export::ComicCredits,
+    pub output_path: Option<String>,
+}
+
+#[tauri::command]
+pub async fn export_comic(
+    app: AppHandle,
+    request: ExportComicRequest,
+) -> Result<ExportResult, String> {
+    let logger = Logger::new().with_feature("export");
+    log_command_start(&logger, "export_comic", &format!("comic: {}, format: {}", request.comic.id, request.format));
+
+    let comic_format = crate::export::ComicExportFormat::from_name(&request.format)
+        .ok_or_else(|| format!("不支持的漫画导出格式: {}", request.format))?;
+
+    if request.comic.pages.is_empty() {
+        return Err("漫画分镜没有任何页面".to_string());
+    }
+
+    let output_path = if let Some(path) = request.output_path {
+        PathBuf::from(path)
+    } else {
+        let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+        let export_dir = app_data_dir.join("exports");
+        if !export_dir.exists() {
+            std::fs::create_dir_all(&export_dir).map_err(|e| e.to_string())?;
+        }
+        export_dir.join(format!("{}_{}{}", sanitize_filename(&request.comic.title), Utc::now().format("%Y%m%d_%H%M%S"), comic_format.extension()))
+    };
+
+    match comic_format {
+        crate::export::ComicExportFormat::Cbz => {
+            crate::export::export_comic_as_cbz(&request.comic, &request.credits, &output_path).map_err(|e| e.to_string())?;
+        }
+        crate::export::ComicExportFormat::Pdf => {
+            crate::export::export_comic_as_pdf(&request.comic, &request.credits, &output_path).map_err(|e| e.to_string())?;
+        }
+    }
+
+    let file_size = exported_size(&output_path)?;
+
+    let result = ExportResult {
+        success: true,
+        output_path: output_path.to_string_lossy().to_string(),
+        file_size,
+        format: comic_format.extension().to_string(),
+    };
+
+    log_command_success(&logger, "export_comic", &result.output_path);
+    Ok(result)
+}
+
+/// 计算导出结果大小；文件夹导出时累加其中所有文件
+fn exported_size(path: &std::path::Path) -> Result<u64, String> {
+    let metadata = std::fs::metadata(path).map_err(|e| e.to_string())?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut total = 0;
+    for entry in std::fs::read_dir(path).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        total += exported_size(&entry.path())?;
+    }
+    Ok(total)
+}
+
+fn sanitize_filename(filename: &str) -> String {
+    filename
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            _ => c,
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportFileRequest {
+    pub file_path: String,
+    pub format: String,
+    /// 手动指定 TXT 文件编码，为空时自动检测
+    #[serde(default)]
+    pub encoding: Option<String>,
+    /// 本次导入使用的分章规则，为空时使用已保存的规则
+    #[serde(default)]
+    pub split_rules: Option<ChapterSplitRules>,
+    /// 由前端生成，用于匹配 `import://progress` 事件和 cancel_import
+    #[serde(default)]
+    pub import_id: Option<String>,
+}
+
+fn load_chapter_split_rules(conn: &rusqlite::Connection) -> Result<Option<ChapterSplitRules>, String> {
+    let json: Option<String> = conn
+        .query_row(
+            "SELECT value FROM app_settings WHERE key = 'chapter_split_rules'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    Ok(json.and_then(|j| serde_json::from_str(&j).ok()))
+}
+
+#[tauri::command]
+pub async fn import_file(
+    app: AppHandle,
     request: ImportFileRequest,
 ) -> Result<ImportResult, String> {
     let logger = Logger::new().with_feature("import");
@@ -4992,6 +5274,9 @@ pub async fn import_file(
         "txt" => ImportFormat::Txt,
         "md" | "markdown" => ImportFormat::Md,
         "docx" => ImportFormat::Docx,
+        "epub" => ImportFormat::Epub,
+        "pdf" => ImportFormat::Pdf,
+        "html" | "htm" | "mht" | "mhtml" => ImportFormat::Html,
         _ => return Err(format!("不支持的导入格式: {}", request.format)),
     };
 
@@ -5000,55 +5285,720 @@ pub async fn import_file(
         return Err(format!("文件不存在: {}", request.file_path));
     }
 
-    let result: ImportResult = match format {
-        ImportFormat::Txt => import_from_txt(path).map_err(|e: anyhow::Error| e.to_string())?,
-        ImportFormat::Md => import_from_markdown(path).map_err(|e: anyhow::Error| e.to_string())?,
-        ImportFormat::Docx => import_from_docx(path).map_err(|e: anyhow::Error| e.to_string())?,
+    let split_rules = match request.split_rules.clone() {
+        Some(rules) => Some(rules),
+        None => {
+            let db_path = get_db_path(&app)?;
+            let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+            load_chapter_split_rules(&conn)?
+        }
     };
 
+    // 解析放到阻塞线程池中执行，进度通过 import://progress 推送
+    let import_id = request.import_id.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+    let cancelled = app.state::<ImportState>().register(&import_id);
+    let event_app = app.clone();
+    let monitor = ImportMonitor::new(&import_id, cancelled.clone(), Box::new(move |progress| {
+        let _ = event_app.emit("import://progress", progress);
+    }));
+    let file_path = path.to_path_buf();
+    let encoding = request.encoding.clone();
+    let outcome = tauri::async_runtime::spawn_blocking(move || {
+        let result = import_path(&file_path, format, encoding.as_deref(), split_rules.as_ref(), &monitor);
+        if let Ok(result) = &result {
+            monitor.report("done", 0, result.chapter_count);
+        }
+        result
+    })
+    .await;
+    app.state::<ImportState>().finish(&import_id);
+
+    if cancelled.load(std::sync::atomic::Ordering::Relaxed) {
+        log_command_error(&logger, "import_file", "cancelled");
+        return Err("导入已取消".to_string());
+    }
+    let result = outcome.map_err(|e| e.to_string())??;
+
     log_command_success(&logger, "import_file", &format!("{} chapters, {} words", result.chapter_count, result.word_count));
     Ok(result)
 }
 
+fn import_path(
+    path: &std::path::Path,
+    format: ImportFormat,
+    encoding: Option<&str>,
+    split_rules: Option<&ChapterSplitRules>,
+    monitor: &ImportMonitor,
+) -> Result<ImportResult, String> {
+    let result = match format {
+        ImportFormat::Txt => import_from_txt(path, encoding, split_rules, monitor),
+        ImportFormat::Md => import_from_markdown(path, monitor),
+        ImportFormat::Docx => import_from_docx(path, monitor),
+        ImportFormat::Epub => import_from_epub(path, monitor),
+        ImportFormat::Pdf => import_from_pdf(path, monitor),
+        ImportFormat::Html => import_from_html(path, monitor),
+    };
+    result.map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// 将导入的章节写入项目，sort_order 从 `first_sort_order` 开始递增
+fn insert_imported_chapters(
+    conn: &rusqlite::Connection,
+    project_id: &str,
+    chapters: &[ImportedChapter],
+    first_sort_order: i32,
+) -> Result<(), String> {
+    for (index, chapter) in chapters.iter().enumerate() {
+        insert_imported_chapter(conn, project_id, chapter, first_sort_order + index as i32)?;
+    }
+    touch_project(conn, project_id)
+}
+
+/// 写入一个导入的章节并记录导入来源，返回新章节的 ID
+fn insert_imported_chapter(
+    conn: &rusqlite::Connection,
+    project_id: &str,
+    chapter: &ImportedChapter,
+    sort_order: i32,
+) -> Result<String, String> {
+    let chapter_id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO chapters (id, project_id, title, content, word_count, sort_order, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            &chapter_id,
+            project_id,
+            &chapter.title,
+            &chapter.content,
+            chapter.word_count as i32,
+            sort_order,
+            Utc::now().to_rfc3339(),
+            Utc::now().to_rfc3339()
+        ],
+    ).map_err(|e| format!("创建章节失败: {}", e))?;
+    crate::provenance::record_chapter_change(conn, &chapter_id, "", &chapter.content, Some(&crate::provenance::Origin::import()))?;
+    Ok(chapter_id)
+}
+
+fn touch_project(conn: &rusqlite::Connection, project_id: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE projects SET updated_at = ? WHERE id = ?",
+        params![Utc::now().to_rfc3339(), project_id],
+    ).map_err(|e| format!("更新项目时间失败: {}", e))?;
+    Ok(())
+}
+
+fn load_existing_chapters(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec<ExistingChapter>, String> {
+    let chapters = ProjectRepository::list_chapters(conn, project_id).map_err(|e| e.to_string())?;
+    Ok(chapters
+        .into_iter()
+        .map(|c| ExistingChapter { id: c.id, title: c.title, content: c.content })
+        .collect())
+}
+
+/// 取消正在进行的导入，返回 false 表示导入已结束或不存在
+#[tauri::command]
+pub async fn cancel_import(app: AppHandle, import_id: String) -> Result<bool, String> {
+    let logger = Logger::new().with_feature("import");
+    log_command_start(&logger, "cancel_import", &import_id);
+    let cancelled = app.state::<ImportState>().cancel(&import_id);
+    log_command_success(&logger, "cancel_import", &cancelled.to_string());
+    Ok(cancelled)
+}
+
+/// 预览导入结果：返回识别出的章节标题和字数，不写入数据库
+#[tauri::command]
+pub async fn preview_import(
+    app: AppHandle,
+    request: ImportFileRequest,
+) -> Result<ImportPreview, String> {
+    let logger = Logger::new().with_feature("import");
+    log_command_start(&logger, "preview_import", &request.file_path);
+
+    let import_result = import_file(app, request).await?;
+    let preview = ImportPreview::from(&import_result);
+
+    log_command_success(&logger, "preview_import", &format!("{} chapters", preview.chapter_count));
+    Ok(preview)
+}
+
+async fn import_with_mapping(
+    app: &AppHandle,
+    request: ImportFileRequest,
+    mapping: Option<Vec<ImportChapterMapping>>,
+) -> Result<ImportResult, String> {
+    let mut import_result = import_file(app.clone(), request).await?;
+    if let Some(mapping) = mapping.filter(|m| !m.is_empty()) {
+        let chapters = std::mem::take(&mut import_result.chapters);
+        import_result.chapters = apply_import_mapping(chapters, &mapping).map_err(|e| e.to_string())?;
+        import_result.chapter_count = import_result.chapters.len();
+        import_result.word_count = import_result.chapters.iter().map(|c| c.word_count).sum();
+    }
+    Ok(import_result)
+}
+
+/// 检查导入内容中哪些章节已存在于项目中（标题相似或正文相同），不写入数据库
+#[tauri::command]
+pub async fn detect_duplicate_chapters(
+    app: AppHandle,
+    request: ImportFileRequest,
+    project_id: String,
+    mapping: Option<Vec<ImportChapterMapping>>,
+) -> Result<Vec<DuplicateChapter>, String> {
+    let logger = Logger::new().with_feature("import");
+    log_command_start(&logger, "detect_duplicate_chapters", &format!("project: {}, path: {}", project_id, request.file_path));
+
+    let import_result = import_with_mapping(&app, request, mapping).await?;
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    let existing = load_existing_chapters(&conn, &project_id)?;
+    let duplicates = find_duplicates(&import_result.chapters, &existing);
+
+    log_command_success(&logger, "detect_duplicate_chapters", &format!("{} duplicates", duplicates.len()));
+    Ok(duplicates)
+}
+
+/// `mapping` 为用户在预览中调整过的章节（重命名、合并、跳过）；
+/// `duplicate_strategy` 决定标题重复章节的处理方式，为空时仍追加；正文完全相同的章节总是跳过
 #[tauri::command]
 pub async fn import_to_project(
     app: AppHandle,
     request: ImportFileRequest,
     project_id: String,
+    mapping: Option<Vec<ImportChapterMapping>>,
+    duplicate_strategy: Option<DuplicateStrategy>,
 ) -> Result<ImportResult, String> {
     let logger = Logger::new().with_feature("import");
     log_command_start(&logger, "import_to_project", &format!("project: {}, path: {}", project_id, request.file_path));
 
-    let import_result = import_file(request).await?;
-    
+    let mut import_result = import_with_mapping(&app, request, mapping).await?;
+
+    let db_path = get_db_path(&app)?;
+    let mut conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    let existing = load_existing_chapters(&conn, &project_id)?;
+    let duplicates = find_duplicates(&import_result.chapters, &existing);
+    let actions = plan_chapter_actions(
+        import_result.chapters.len(),
+        &duplicates,
+        duplicate_strategy.unwrap_or(DuplicateStrategy::Append),
+    );
+
+    // 新章节排在项目已有章节之后，整个导入在一个事务中完成
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut next_sort_order: i32 = tx
+        .query_row(
+            "SELECT COALESCE(MAX(sort_order), 0) + 1 FROM chapters WHERE project_id = ?1",
+            params![&project_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let (mut skipped, mut replaced) = (0, 0);
+    for (chapter, action) in import_result.chapters.iter().zip(&actions) {
+        match action {
+            ChapterImportAction::Insert => {
+                insert_imported_chapter(&tx, &project_id, chapter, next_sort_order)?;
+                next_sort_order += 1;
+            }
+            ChapterImportAction::Skip { .. } => skipped += 1,
+            ChapterImportAction::Replace { existing_id } => {
+                let previous: String = tx
+                    .query_row("SELECT content FROM chapters WHERE id = ?", params![existing_id], |row| row.get(0))
+                    .map_err(|e| format!("读取章节失败: {}", e))?;
+                tx.execute(
+                    "UPDATE chapters SET title = ?, content = ?, word_count = ?, updated_at = ? WHERE id = ?",
+                    params![&chapter.title, &chapter.content, chapter.word_count as i32, Utc::now().to_rfc3339(), existing_id],
+                ).map_err(|e| format!("更新章节失败: {}", e))?;
+                crate::provenance::record_chapter_change(&tx, existing_id, &previous, &chapter.content, Some(&crate::provenance::Origin::import()))?;
+                replaced += 1;
+            }
+        }
+    }
+    touch_project(&tx, &project_id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    if skipped > 0 || replaced > 0 {
+        import_result.warnings.push(format!("{} 个章节已存在被跳过，{} 个章节被覆盖", skipped, replaced));
+    }
+
+    log_command_success(&logger, "import_to_project", &format!(
+        "imported {} chapters ({} skipped, {} replaced)",
+        import_result.chapter_count, skipped, replaced
+    ));
+    Ok(import_result)
+}
+
+fn load_table_preview(request: &TableImportRequest, project_id: &str) -> Result<TableImportPreview, String> {
+    let rows = read_table(
+        std::path::Path::new(&request.file_path),
+        request.sheet.as_deref(),
+        request.encoding.as_deref(),
+        request.delimiter,
+    )
+    .map_err(|e| e.to_string())?;
+    map_table_rows(project_id, &rows, request).map_err(|e| e.to_string())
+}
+
+/// 按列映射解析 CSV/XLSX 表格，返回将要创建的角色或世界观条目，不写入数据库
+#[tauri::command]
+pub async fn preview_table_import(
+    request: TableImportRequest,
+    project_id: String,
+) -> Result<TableImportPreview, String> {
+    let logger = Logger::new().with_feature("import");
+    log_command_start(&logger, "preview_table_import", &format!("project: {}, path: {}", project_id, request.file_path));
+
+    let preview = tauri::async_runtime::spawn_blocking(move || load_table_preview(&request, &project_id))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    log_command_success(&logger, "preview_table_import", &format!(
+        "{} characters, {} world views, {} errors",
+        preview.characters.len(), preview.world_views.len(), preview.errors.len()
+    ));
+    Ok(preview)
+}
+
+/// 从表格批量创建角色或世界观条目；项目中已有同名角色（同标题设定）的行会被跳过
+#[tauri::command]
+pub async fn import_table(app: AppHandle, request: TableImportRequest, project_id: String) -> Result<TableImportResult, String> {
+    let logger = Logger::new().with_feature("import");
+    log_command_start(&logger, "import_table", &format!("project: {}, path: {}", project_id, request.file_path));
+
+    let target = request.target;
+    let preview_project_id = project_id.clone();
+    let preview = tauri::async_runtime::spawn_blocking(move || load_table_preview(&request, &preview_project_id))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let db_path = get_db_path(&app)?;
+    let mut conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    let existing_sql = match target {
+        TableImportTarget::Character => "SELECT name FROM characters WHERE project_id = ?",
+        TableImportTarget::WorldView => "SELECT title FROM world_views WHERE project_id = ?",
+    };
+    let mut existing: std::collections::HashSet<String> = conn
+        .prepare(existing_sql)
+        .and_then(|mut stmt| {
+            stmt.query_map(params![&project_id], |row| row.get::<_, String>(0))?
+                .collect::<Result<_, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+
+    let now = Utc::now().to_rfc3339();
+    let mut result = TableImportResult { created: 0, skipped: Vec::new(), errors: preview.errors };
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let keys: Vec<String> = match target {
+        TableImportTarget::Character => preview.characters.iter().map(|c| c.name.clone()).collect(),
+        TableImportTarget::WorldView => preview.world_views.iter().map(|w| w.title.clone()).collect(),
+    };
+    for (index, key) in keys.iter().enumerate() {
+        if !existing.insert(key.clone()) {
+            result.skipped.push(TableRowError { row: preview.rows[index], message: format!("已存在: {}", key) });
+            continue;
+        }
+        let id = Uuid::new_v4().to_string();
+        match target {
+            TableImportTarget::Character => {
+                let c = &preview.characters[index];
+                tx.execute(
+                    "INSERT INTO characters (id, project_id, name, role_type, race, age, gender, birth_date, appearance, personality, background, skills, status, bazi, ziwei, mbti, enneagram, items, avatar_url, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                    params![
+                        id, project_id, c.name, c.role_type, c.race, c.age, c.gender, c.birth_date, c.appearance,
+                        c.personality, c.background, c.skills, c.status, c.bazi, c.ziwei, c.mbti, c.enneagram,
+                        c.items, Option::<String>::None, now, now,
+                    ],
+                )
+            }
+            TableImportTarget::WorldView => {
+                let w = &preview.world_views[index];
+                tx.execute(
+                    "INSERT INTO world_views (id, project_id, category, title, content, tags, status, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                    params![id, project_id, w.category, w.title, w.content, w.tags, "draft", now, now],
+                )
+            }
+        }
+        .map_err(|e| format!("写入失败: {}", e))?;
+        result.created += 1;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    log_command_success(&logger, "import_table", &format!(
+        "created {}, skipped {}, errors {}",
+        result.created, result.skipped.len(), result.errors.len()
+    ));
+    Ok(result)
+}
+
+/// 批量导入目录中的文件：按文件名自然排序后合并为同一项目的章节，或每个文件创建一个项目。
+/// 单个文件失败不会中断其他文件，失败原因记录在 warnings 中
+#[tauri::command]
+pub async fn import_directory(app: AppHandle, request: ImportDirectoryRequest) -> Result<DirectoryImportResult, String> {
+    let logger = Logger::new().with_feature("import");
+    log_command_start(&logger, "import_directory", &format!("path: {}, pattern: {:?}", request.path, request.pattern));
+
+    let dir = std::path::Path::new(&request.path);
+    if !dir.is_dir() {
+        return Err(format!("目录不存在: {}", request.path));
+    }
+    let files = list_import_files(dir, request.pattern.as_deref()).map_err(|e| e.to_string())?;
+    if files.is_empty() {
+        return Err("目录中没有可导入的文件".to_string());
+    }
+
+    let db_path = get_db_path(&app)?;
+    let mut conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    let split_rules = match request.split_rules.clone() {
+        Some(rules) => Some(rules),
+        None => load_chapter_split_rules(&conn)?,
+    };
+
+    let mut next_sort_order = match (&request.mode, &request.project_id) {
+        (DirectoryImportMode::Chapters, Some(project_id)) => conn
+            .query_row(
+                "SELECT COALESCE(MAX(sort_order), 0) + 1 FROM chapters WHERE project_id = ?1",
+                params![project_id],
+                |row| row.get::<_, i32>(0),
+            )
+            .map_err(|e| e.to_string())?,
+        (DirectoryImportMode::Chapters, None) => return Err("按章节导入需要指定目标项目".to_string()),
+        (DirectoryImportMode::ProjectPerFile, _) => 1,
+    };
+
+    // 解析文件比较耗时，放到阻塞线程中进行
+    let encoding = request.encoding.clone();
+    let parse_files = files.clone();
+    let parsed = tauri::async_runtime::spawn_blocking(move || {
+        parse_files
+            .iter()
+            .map(|file| {
+                let format = file
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .and_then(ImportFormat::from_extension);
+                match format {
+                    Some(format) => import_path(file, format, encoding.as_deref(), split_rules.as_ref(), &ImportMonitor::default()),
+                    None => Err("不支持的文件格式".to_string()),
+                }
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut all_chapters = Vec::new();
+    let mut file_results = Vec::new();
+    let mut warnings = Vec::new();
+
+    // 所有文件在一个事务中写入，中途出错不会留下导入了一半的章节
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for (file, imported) in files.iter().zip(parsed) {
+        let file_name = file.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+        let mut result = match imported {
+            Ok(result) => result,
+            Err(e) => {
+                warnings.push(format!("{}: {}", file_name, e));
+                file_results.push(DirectoryFileResult {
+                    file_name,
+                    chapter_count: 0,
+                    word_count: 0,
+                    project_id: None,
+                    error: Some(e),
+                });
+                continue;
+            }
+        };
+        warnings.extend(result.warnings.iter().map(|w| format!("{}: {}", file_name, w)));
+
+        // 没有章节标记的文件整体作为一章，以文件名为标题
+        if result.chapters.len() == 1 && matches!(result.chapters[0].title.as_str(), "正文" | "序章") {
+            result.chapters[0].title = result.title.clone();
+        }
+
+        let project_id = match request.mode {
+            DirectoryImportMode::Chapters => {
+                let project_id = request.project_id.clone().unwrap_or_default();
+                insert_imported_chapters(&tx, &project_id, &result.chapters, next_sort_order)?;
+                next_sort_order += result.chapters.len() as i32;
+                project_id
+            }
+            DirectoryImportMode::ProjectPerFile => {
+                let project_id = Uuid::new_v4().to_string();
+                let now = Utc::now().to_rfc3339();
+                tx.execute(
+                    "INSERT INTO projects (id, name, status, created_at, updated_at) VALUES (?, ?, 'active', ?, ?)",
+                    params![&project_id, &result.title, &now, &now],
+                ).map_err(|e| format!("创建项目失败: {}", e))?;
+                insert_imported_chapters(&tx, &project_id, &result.chapters, 1)?;
+                project_id
+            }
+        };
+
+        file_results.push(DirectoryFileResult {
+            file_name,
+            chapter_count: result.chapters.len(),
+            word_count: result.chapters.iter().map(|c| c.word_count).sum(),
+            project_id: Some(project_id),
+            error: None,
+        });
+        all_chapters.extend(result.chapters);
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    let imported_files = file_results.iter().filter(|f| f.error.is_none()).count();
+    let chapter_count = all_chapters.len();
+    let word_count = all_chapters.iter().map(|c| c.word_count).sum();
+    let title = dir.file_name().and_then(|n| n.to_str()).unwrap_or("未命名").to_string();
+    let result = ImportResult {
+        success: imported_files > 0,
+        title,
+        content: String::new(),
+        chapter_count,
+        word_count,
+        chapters: all_chapters,
+        message: Some(format!(
+            "成功导入 {}/{} 个文件，共 {} 个章节",
+            imported_files,
+            files.len(),
+            chapter_count
+        )),
+        warnings,
+        encoding: request.encoding.clone(),
+    };
+
+    log_command_success(&logger, "import_directory", &format!("{} files, {} chapters", imported_files, chapter_count));
+    Ok(DirectoryImportResult { result, files: file_results })
+}
+
+/// 获取分章规则，未保存过时返回内置规则
+#[tauri::command]
+pub async fn get_chapter_split_rules(app: AppHandle) -> Result<ChapterSplitRules, String> {
+    let logger = Logger::new().with_feature("import");
+    log_command_start(&logger, "get_chapter_split_rules", "");
+
     let db_path = get_db_path(&app)?;
     let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    let rules = load_chapter_split_rules(&conn)?.unwrap_or_default();
 
-    for (index, chapter) in import_result.chapters.iter().enumerate() {
-        let chapter_id = Uuid::new_v4().to_string();
-        let sort_order = (index + 1) as i32;
-        
-        conn.execute(
-            "INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
-            params![
-                &chapter_id,
-                &project_id,
-                &chapter.title,
-                &chapter.content,
-                sort_order,
-                Utc::now().to_rfc3339(),
-                Utc::now().to_rfc3339()
-            ],
-        ).map_err(|e| format!("创建章节失败: {}", e))?;
+    log_command_success(&logger, "get_chapter_split_rules", &format!("{} rules", rules.rules.len()));
+    Ok(rules)
+}
+
+#[tauri::command]
+pub async fn save_chapter_split_rules(app: AppHandle, rules: ChapterSplitRules) -> Result<(), String> {
+    let logger = Logger::new().with_feature("import");
+    log_command_start(&logger, "save_chapter_split_rules", &format!("{} rules", rules.rules.len()));
+
+    rules.compile().map_err(|e| e.to_string())?;
+
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    let rules_json = serde_json::to_string(&rules).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO app_settings (key, value, updated_at) VALUES ('chapter_split_rules', ?, ?)",
+        params![rules_json, Utc::now().to_rfc3339()],
+    ).map_err(|e| format!("保存分章规则失败: {}", e))?;
+
+    log_command_success(&logger, "save_chapter_split_rules", "saved");
+    Ok(())
+}
+
+/// 试运行分章规则：报告每条规则能切出多少章，不写入数据库
+#[tauri::command]
+pub async fn preview_chapter_split_rules(
+    file_path: String,
+    rules: ChapterSplitRules,
+    encoding: Option<String>,
+) -> Result<ChapterSplitDryRun, String> {
+    let logger = Logger::new().with_feature("import");
+    log_command_start(&logger, "preview_chapter_split_rules", &file_path);
+
+    let bytes = std::fs::read(&file_path).map_err(|e| format!("无法读取文件: {}", e))?;
+    let (content, _, _) = crate::import::txt_import::decode_text(&bytes, encoding.as_deref())
+        .map_err(|e| e.to_string())?;
+    let report = rules.dry_run(&content);
+
+    log_command_success(&logger, "preview_chapter_split_rules", &format!("{} chapters", report.chapter_count));
+    Ok(report)
+}
+
+/// 从网页目录导入连载章节：抓取目录页找出章节链接后，在后台按间隔逐章抓取并写入项目。
+/// 每章的抓取状态记录在 url_import_items 中，中断后可用 resume_url_import 继续
+#[tauri::command]
+pub async fn start_url_import(app: AppHandle, request: UrlImportRequest) -> Result<QueuedTask, String> {
+    let logger = Logger::new().with_feature("import");
+    log_command_start(&logger, "start_url_import", &request.index_url);
+
+    let mut fetcher = PoliteFetcher::for_site(&request.index_url, request.delay_ms).await?;
+    let index_html = fetcher.fetch(&request.index_url).await?;
+    let mut links = extract_chapter_links(&index_html, &request.index_url);
+    if let Some(max) = request.max_chapters {
+        links.truncate(max);
+    }
+    if links.is_empty() {
+        return Err("目录页中没有找到章节链接".to_string());
     }
 
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    let base_order: i32 = conn
+        .query_row(
+            "SELECT COALESCE(MAX(sort_order), 0) FROM chapters WHERE project_id = ?1",
+            params![request.project_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let task = TaskQueue::new().add_task(CreateTaskRequest {
+        project_id: request.project_id.clone(),
+        task_type: TaskType::Custom,
+        priority: None,
+        provider: Some("url_import".to_string()),
+        input_data: serde_json::to_value(&request).map_err(|e| e.to_string())?,
+        max_retries: Some(0),
+    });
+    save_task(&conn, &task).map_err(|e| e.to_string())?;
+
+    let items: Vec<UrlImportItem> = links
+        .into_iter()
+        .enumerate()
+        .map(|(index, link)| UrlImportItem {
+            id: Uuid::new_v4().to_string(),
+            task_id: task.id.clone(),
+            project_id: request.project_id.clone(),
+            status: if fetcher.is_allowed(&link.url) { UrlImportItemStatus::Pending } else { UrlImportItemStatus::Disallowed },
+            url: link.url,
+            title: link.title,
+            sort_order: base_order + index as i32 + 1,
+            chapter_id: None,
+            error: None,
+        })
+        .collect();
+    save_url_import_items(&conn, &items).map_err(|e| e.to_string())?;
+
+    claim_task(&conn, &task.id).map_err(|e| e.to_string())?;
+    spawn_url_import_job(app, db_path, task.id.clone(), fetcher);
+
+    log_command_success(&logger, "start_url_import", &format!("task {}: {} chapters", task.id, items.len()));
+    Ok(task)
+}
+
+/// 继续未完成的网页导入，已导入的章节会跳过，失败的章节会重试
+#[tauri::command]
+pub async fn resume_url_import(app: AppHandle, task_id: String) -> Result<QueuedTask, String> {
+    let logger = Logger::new().with_feature("import");
+    log_command_start(&logger, "resume_url_import", &task_id);
+
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    let task = load_task(&conn, &task_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("任务不存在: {}", task_id))?;
+    if task.state == TaskState::Running {
+        return Err("导入任务正在运行".to_string());
+    }
+    let request: UrlImportRequest = serde_json::from_value(task.input_data.clone())
+        .map_err(|e| format!("不是网页导入任务: {}", e))?;
+
+    let fetcher = PoliteFetcher::for_site(&request.index_url, request.delay_ms).await?;
+    // 以条件更新占用任务，连续点击继续时只有一次能启动
+    if !claim_task(&conn, &task_id).map_err(|e| e.to_string())? {
+        return Err("导入任务正在运行".to_string());
+    }
+    spawn_url_import_job(app, db_path, task_id.clone(), fetcher);
+
+    log_command_success(&logger, "resume_url_import", &task_id);
+    load_task(&conn, &task_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("任务不存在: {}", task_id))
+}
+
+#[tauri::command]
+pub async fn get_url_import_items(app: AppHandle, task_id: String) -> Result<Vec<UrlImportItem>, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    load_url_import_items(&conn, &task_id).map_err(|e| e.to_string())
+}
+
+fn spawn_url_import_job(app: AppHandle, db_path: PathBuf, task_id: String, fetcher: PoliteFetcher) {
+    tauri::async_runtime::spawn(async move {
+        let logger = Logger::new().with_feature("import");
+        let outcome = run_url_import_job(&app, &db_path, &task_id, fetcher).await;
+        let Ok(conn) = get_connection(&db_path) else {
+            return;
+        };
+        match outcome {
+            Ok(Some(summary)) => {
+                let _ = update_task_state(&conn, &task_id, TaskState::Completed, Some(&summary), None);
+                logger.info(&format!("URL import {} completed", task_id));
+            }
+            Ok(None) => logger.info(&format!("URL import {} cancelled", task_id)),
+            Err(e) => {
+                let _ = update_task_state(&conn, &task_id, TaskState::Failed, None, Some(&e));
+                logger.error(&format!("URL import {} failed: {}", task_id, e));
+            }
+        }
+    });
+}
+
+/// 逐章抓取；单章失败只记录在对应条目上，不中断整个任务。返回 Ok(None) 表示任务被取消
+async fn run_url_import_job(
+    app: &AppHandle,
+    db_path: &std::path::Path,
+    task_id: &str,
+    mut fetcher: PoliteFetcher,
+) -> Result<Option<serde_json::Value>, String> {
+    let conn = get_connection(db_path).map_err(|e| e.to_string())?;
+    let items = load_url_import_items(&conn, task_id).map_err(|e| e.to_string())?;
+    let total = items.len();
+    let mut completed = items.iter().filter(|i| i.status == UrlImportItemStatus::Done).count();
+    let mut failed = 0;
+
+    for item in items.iter().filter(|i| matches!(i.status, UrlImportItemStatus::Pending | UrlImportItemStatus::Failed)) {
+        if is_task_cancelled(&conn, task_id) {
+            return Ok(None);
+        }
+
+        match fetcher.fetch_chapter(&item.title, &item.url).await {
+            Ok((title, content)) => {
+                // 章节和条目状态一起提交，中断后继续任务时不会重复导入
+                let chapter = ImportedChapter { title, word_count: content.chars().count(), content };
+                let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+                let chapter_id = insert_imported_chapter(&tx, &item.project_id, &chapter, item.sort_order)?;
+                update_url_import_item(&tx, &item.id, UrlImportItemStatus::Done, Some(&chapter_id), None)
+                    .map_err(|e| e.to_string())?;
+                tx.commit().map_err(|e| e.to_string())?;
+                completed += 1;
+            }
+            Err(e) => {
+                update_url_import_item(&conn, &item.id, UrlImportItemStatus::Failed, None, Some(&e))
+                    .map_err(|e| e.to_string())?;
+                failed += 1;
+            }
+        }
+
+        let progress = ((completed + failed) * 100 / total.max(1)) as u32;
+        let _ = update_task_progress(&conn, task_id, progress);
+        let _ = app.emit("url-import://progress", UrlImportProgress {
+            task_id: task_id.to_string(),
+            completed,
+            total,
+            current_title: item.title.clone(),
+            failed,
+        });
+    }
+
+    let project_id = items.first().map(|i| i.project_id.clone()).unwrap_or_default();
     conn.execute(
         "UPDATE projects SET updated_at = ? WHERE id = ?",
         params![Utc::now().to_rfc3339(), &project_id],
     ).map_err(|e| format!("更新项目时间失败: {}", e))?;
 
-    log_command_success(&logger, "import_to_project", &format!("imported {} chapters", import_result.chapter_count));
-    Ok(import_result)
+    let disallowed = items.iter().filter(|i| i.status == UrlImportItemStatus::Disallowed).count();
+    Ok(Some(serde_json::json!({
+        "imported": completed,
+        "failed": failed,
+        "disallowed": disallowed,
+        "total": total,
+    })))
 }
 
 #[tauri::command]
@@ -5108,6 +6058,7 @@ pub async fn generate_chapter_versions(
             worldview_context: None,
             project_id: Some(request.project_id.clone()),
             chapter_mission_id: None,
+            chapter_id: None,
         };
 
         match ai_service.continue_novel(ai_request, None).await {
@@ -5272,6 +6223,7 @@ pub async fn evaluate_chapter(
         worldview_context: None,
         project_id: Some(request.project_id.clone()),
         chapter_mission_id: None,
+        chapter_id: None,
     };
 
     let evaluation_result = ai_service.continue_novel(ai_request, None).await
@@ -5542,17 +6494,11 @@ pub async fn get_foreshadowing_stats(
     Ok(stats)
 }
 
-#[tauri::command]
-pub async fn calculate_emotion_curve(
-    app: AppHandle,
-    request: EmotionCurveRequest,
-) -> Result<EmotionCurveResponse, String> {
-    let logger = Logger::new().with_feature("emotion-curve");
-    log_command_start(&logger, "calculate_emotion_curve", &request.arc_type);
-
-    let db_path = get_db_path(&app)?;
-    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
-
+/// 按情绪弧线类型计算每章的目标情绪，返回总章数和（章节 ID, 目标）列表
+fn emotion_curve_targets(
+    conn: &rusqlite::Connection,
+    request: &EmotionCurveRequest,
+) -> Result<(i32, Vec<(String, EmotionCurveData)>), String> {
     let chapters: Vec<(String, String, i32)> = conn.prepare(
         "SELECT id, title, sort_order FROM chapters WHERE project_id = ?1 ORDER BY sort_order ASC"
     )
@@ -5571,7 +6517,7 @@ pub async fn calculate_emotion_curve(
     let total_chapters = if request.total_chapters > 0 { request.total_chapters } else { chapters.len() as i32 };
 
     let arc_type = request.arc_type.as_str();
-    let mut curve_data = Vec::new();
+    let mut curve_data = Vec::with_capacity(chapters.len());
 
     for (i, (id, title, _)) in chapters.iter().enumerate() {
         let chapter_num = (i + 1) as i32;
@@ -5656,7 +6602,7 @@ pub async fn calculate_emotion_curve(
             vec![]
         };
 
-        curve_data.push(EmotionCurveData {
+        curve_data.push((id.clone(), EmotionCurveData {
             chapter_number: chapter_num,
             chapter_title: title.clone(),
             position,
@@ -5667,9 +6613,26 @@ pub async fn calculate_emotion_curve(
             thrill_density,
             dialogue_ratio,
             recommendations,
-        });
+        }));
     }
 
+    Ok((total_chapters, curve_data))
+}
+
+#[tauri::command]
+pub async fn calculate_emotion_curve(
+    app: AppHandle,
+    request: EmotionCurveRequest,
+) -> Result<EmotionCurveResponse, String> {
+    let logger = Logger::new().with_feature("emotion-curve");
+    log_command_start(&logger, "calculate_emotion_curve", &request.arc_type);
+
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+
+    let (total_chapters, targets) = emotion_curve_targets(&conn, &request)?;
+    let curve_data: Vec<EmotionCurveData> = targets.into_iter().map(|(_, data)| data).collect();
+
     let emotions: Vec<f32> = curve_data.iter().map(|d| d.emotion_target).collect();
     let avg_emotion = if emotions.is_empty() { 0.0 } else { emotions.iter().sum::<f32>() / emotions.len() as f32 };
 
@@ -5705,6 +6668,74 @@ pub async fn calculate_emotion_curve(
     Ok(response)
 }
 
+/// 把每章实际的情绪强度与目标曲线对比，返回偏差序列和最需要调整的章节
+#[tauri::command]
+pub async fn compare_emotion_curve(
+    app: AppHandle,
+    request: EmotionCurveRequest,
+    top_n: Option<usize>,
+) -> Result<EmotionCurveComparison, String> {
+    let logger = Logger::new().with_feature("emotion-curve");
+    log_command_start(&logger, "compare_emotion_curve", &request.arc_type);
+
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+
+    let (_, targets) = emotion_curve_targets(&conn, &request)?;
+    let mut chapters = Vec::with_capacity(targets.len());
+    for (chapter_id, target) in targets {
+        let content: String = conn
+            .query_row("SELECT COALESCE(content, '') FROM chapters WHERE id = ?1", params![&chapter_id], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        let actual_intensity = crate::text_analysis::TextAnalyzer::emotion_intensity(&content);
+        let (min, max) = target.emotion_range;
+        let within_range = actual_intensity >= min as f32 && actual_intensity <= max as f32;
+        let suggestion = if within_range {
+            None
+        } else if actual_intensity < min as f32 {
+            Some(format!("{}的情绪低于目标，可以加强冲突或情感爆发", target.phase_name))
+        } else {
+            Some(format!("{}的情绪高于目标，可以加入缓冲或日常描写", target.phase_name))
+        };
+        chapters.push(ChapterEmotionDeviation {
+            chapter_id,
+            chapter_number: target.chapter_number,
+            chapter_title: target.chapter_title,
+            phase_name: target.phase_name,
+            emotion_target: target.emotion_target,
+            emotion_range: target.emotion_range,
+            actual_intensity,
+            deviation: actual_intensity - target.emotion_target,
+            within_range,
+            suggestion,
+        });
+    }
+
+    let deviation_series: Vec<f32> = chapters.iter().map(|c| c.deviation).collect();
+    let mean_absolute_deviation =
+        deviation_series.iter().map(|d| d.abs()).sum::<f32>() / deviation_series.len().max(1) as f32;
+    // 按超出目标区间的距离排序
+    let mut outside: Vec<(f32, i32)> = chapters
+        .iter()
+        .filter(|c| !c.within_range)
+        .map(|c| {
+            let (min, max) = c.emotion_range;
+            ((min as f32 - c.actual_intensity).max(c.actual_intensity - max as f32), c.chapter_number)
+        })
+        .collect();
+    outside.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+    let needs_adjustment = outside.into_iter().take(top_n.unwrap_or(5)).map(|(_, number)| number).collect();
+
+    log_command_success(&logger, "compare_emotion_curve", &format!("平均偏差{:.1}", mean_absolute_deviation));
+    Ok(EmotionCurveComparison {
+        arc_type: request.arc_type,
+        chapters,
+        deviation_series,
+        mean_absolute_deviation,
+        needs_adjustment,
+    })
+}
+
 #[tauri::command]
 pub async fn optimize_chapter(
     app: AppHandle,
@@ -6068,6 +7099,7 @@ pub async fn optimize_chapter(
         worldview_context: None,
         project_id: None,
         chapter_mission_id: None,
+        chapter_id: None,
     };
 
     let ai_response = ai_service.continue_novel(ai_request, None).await.map_err(|e| {
@@ -6293,6 +7325,7 @@ pub async fn create_blueprint(
         worldview_context: None,
         project_id: None,
         chapter_mission_id: None,
+        chapter_id: None,
     };
 
     let ai_response = ai_service.continue_novel(ai_request, None).await.map_err(|e| {
@@ -6875,6 +7908,7 @@ pub async fn generate_chapter_mission_with_ai(
         worldview_context: None,
         project_id: None,
         chapter_mission_id: None,
+        chapter_id: None,
     };
 
     let ai_response = ai_service.continue_novel(ai_request, None).await.map_err(|e| {
@@ -0,0 +1,487 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+/// 锚点前后保存的上下文字数，正文改动后用来重新定位
+const CONTEXT_CHARS: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommentStatus {
+    Open,
+    Resolved,
+}
+
+impl CommentStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CommentStatus::Open => "open",
+            CommentStatus::Resolved => "resolved",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        if s == "resolved" {
+            CommentStatus::Resolved
+        } else {
+            CommentStatus::Open
+        }
+    }
+}
+
+/// 批注锚定的正文范围，偏移量按字符计
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextAnchor {
+    pub start: usize,
+    pub end: usize,
+    /// 创建时选中的文字
+    pub quote: String,
+    pub prefix: String,
+    pub suffix: String,
+}
+
+impl TextAnchor {
+    pub fn new(text: &str, start: usize, end: usize) -> Result<Self, String> {
+        let chars: Vec<char> = text.chars().collect();
+        if start > end || end > chars.len() {
+            return Err(format!("批注范围无效: {}..{}", start, end));
+        }
+        Ok(Self::from_chars(&chars, start, end))
+    }
+
+    fn from_chars(chars: &[char], start: usize, end: usize) -> Self {
+        Self {
+            start,
+            end,
+            quote: chars[start..end].iter().collect(),
+            prefix: chars[start.saturating_sub(CONTEXT_CHARS)..start].iter().collect(),
+            suffix: chars[end..(end + CONTEXT_CHARS).min(chars.len())].iter().collect(),
+        }
+    }
+}
+
+fn find_all(haystack: &[char], needle: &[char]) -> Vec<usize> {
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return Vec::new();
+    }
+    (0..=haystack.len() - needle.len()).filter(|&i| haystack[i..i + needle.len()] == *needle).collect()
+}
+
+/// 位置 `start..end` 两侧与原上下文相同的字数
+fn context_score(chars: &[char], start: usize, end: usize, prefix: &[char], suffix: &[char]) -> usize {
+    let before = chars[..start].iter().rev().zip(prefix.iter().rev()).take_while(|(a, b)| a == b).count();
+    let after = chars[end..].iter().zip(suffix).take_while(|(a, b)| a == b).count();
+    before + after
+}
+
+/// 正文修改后重新定位锚点：原位置文字未变时保持不动；否则在全文中查找选中的文字，
+/// 多处匹配时取上下文最接近、离原位置最近的一处；选中的文字本身被改动时，
+/// 用前后文夹出新的范围。找不到时返回 None，批注标记为脱离原文
+pub fn reanchor(text: &str, anchor: &TextAnchor) -> Option<TextAnchor> {
+    let chars: Vec<char> = text.chars().collect();
+    let quote: Vec<char> = anchor.quote.chars().collect();
+    let prefix: Vec<char> = anchor.prefix.chars().collect();
+    let suffix: Vec<char> = anchor.suffix.chars().collect();
+
+    if chars.get(anchor.start..anchor.end) == Some(&quote[..]) {
+        return Some(TextAnchor::from_chars(&chars, anchor.start, anchor.end));
+    }
+
+    let best = find_all(&chars, &quote).into_iter().max_by_key(|&start| {
+        let score = context_score(&chars, start, start + quote.len(), &prefix, &suffix);
+        (score, std::cmp::Reverse(start.abs_diff(anchor.start)))
+    });
+    if let Some(start) = best {
+        return Some(TextAnchor::from_chars(&chars, start, start + quote.len()));
+    }
+
+    if prefix.is_empty() && suffix.is_empty() {
+        return None;
+    }
+    let starts: Vec<usize> = if prefix.is_empty() {
+        vec![0]
+    } else {
+        find_all(&chars, &prefix).into_iter().map(|p| p + prefix.len()).collect()
+    };
+    let start = starts.into_iter().min_by_key(|p| p.abs_diff(anchor.start))?;
+    let end = if suffix.is_empty() {
+        chars.len()
+    } else {
+        find_all(&chars[start..], &suffix).first().map(|p| start + p)?
+    };
+    // 夹出的范围比原选区大很多时，多半是定位到了别处
+    if end - start > quote.len() * 2 + CONTEXT_CHARS {
+        return None;
+    }
+    Some(TextAnchor::from_chars(&chars, start, end))
+}
+
+/// 章节正文上的批注；回复的 `parent_id` 指向被回复的批注，不单独锚定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterComment {
+    pub id: String,
+    pub project_id: String,
+    pub chapter_id: String,
+    pub parent_id: Option<String>,
+    pub author_id: String,
+    pub author_name: String,
+    pub body: String,
+    pub anchor: Option<TextAnchor>,
+    /// 锚定的文字已被删除，无法重新定位
+    pub detached: bool,
+    pub status: CommentStatus,
+    pub resolved_by: Option<String>,
+    pub resolved_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub replies: Vec<ChapterComment>,
+}
+
+const COMMENT_COLUMNS: &str = "id, project_id, chapter_id, parent_id, author_id, author_name, body, anchor_start, anchor_end, quote, prefix, suffix, detached, status, resolved_by, resolved_at, created_at, updated_at";
+
+fn comment_from_row(row: &Row) -> rusqlite::Result<ChapterComment> {
+    let start: Option<i64> = row.get(7)?;
+    let end: Option<i64> = row.get(8)?;
+    let anchor = match (start, end) {
+        (Some(start), Some(end)) => Some(TextAnchor {
+            start: start as usize,
+            end: end as usize,
+            quote: row.get::<_, Option<String>>(9)?.unwrap_or_default(),
+            prefix: row.get::<_, Option<String>>(10)?.unwrap_or_default(),
+            suffix: row.get::<_, Option<String>>(11)?.unwrap_or_default(),
+        }),
+        _ => None,
+    };
+    Ok(ChapterComment {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        chapter_id: row.get(2)?,
+        parent_id: row.get(3)?,
+        author_id: row.get(4)?,
+        author_name: row.get(5)?,
+        body: row.get(6)?,
+        anchor,
+        detached: row.get(12)?,
+        status: CommentStatus::parse(&row.get::<_, String>(13)?),
+        resolved_by: row.get(14)?,
+        resolved_at: row.get(15)?,
+        created_at: row.get(16)?,
+        updated_at: row.get(17)?,
+        replies: Vec::new(),
+    })
+}
+
+/// 保存批注，已存在时覆盖（协作会话中收到其他成员的批注时也用它）。
+/// 锚点范围须在章节正文之内；已脱离原文的批注保留原来的位置，不再检查是否越界
+pub fn save_comment(conn: &Connection, comment: &ChapterComment) -> Result<(), String> {
+    let anchor = comment.anchor.as_ref();
+    if let Some(a) = anchor {
+        let len = if comment.detached {
+            a.end
+        } else {
+            chapter_content(conn, &comment.chapter_id)?.map(|(_, content)| content.chars().count()).unwrap_or(0)
+        };
+        if a.start > a.end || a.end > len {
+            return Err(format!("批注范围无效: {}..{}", a.start, a.end));
+        }
+    }
+    conn.execute(
+        &format!("INSERT OR REPLACE INTO chapter_comments ({}) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)", COMMENT_COLUMNS),
+        params![
+            comment.id,
+            comment.project_id,
+            comment.chapter_id,
+            comment.parent_id,
+            comment.author_id,
+            comment.author_name,
+            comment.body,
+            anchor.map(|a| a.start as i64),
+            anchor.map(|a| a.end as i64),
+            anchor.map(|a| a.quote.as_str()),
+            anchor.map(|a| a.prefix.as_str()),
+            anchor.map(|a| a.suffix.as_str()),
+            comment.detached,
+            comment.status.as_str(),
+            comment.resolved_by,
+            comment.resolved_at,
+            comment.created_at,
+            comment.updated_at,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn get_comment(conn: &Connection, comment_id: &str) -> Result<Option<ChapterComment>, String> {
+    conn.query_row(&format!("SELECT {} FROM chapter_comments WHERE id = ?", COMMENT_COLUMNS), params![comment_id], comment_from_row)
+        .optional()
+        .map_err(|e| e.to_string())
+}
+
+fn chapter_content(conn: &Connection, chapter_id: &str) -> Result<Option<(String, String)>, String> {
+    conn.query_row("SELECT project_id, content FROM chapters WHERE id = ?", params![chapter_id], |row| {
+        Ok((row.get(0)?, row.get(1)?))
+    })
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCommentRequest {
+    pub chapter_id: String,
+    /// 选区起止位置，按字符计
+    pub start: usize,
+    pub end: usize,
+    pub author_id: String,
+    pub author_name: String,
+    pub body: String,
+    /// 在协作会话中时，批注同步给会话中的其他成员
+    pub session_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplyCommentRequest {
+    pub comment_id: String,
+    pub author_id: String,
+    pub author_name: String,
+    pub body: String,
+    pub session_id: Option<String>,
+}
+
+/// 在章节的选区上创建批注
+pub fn create_comment(conn: &Connection, request: &CreateCommentRequest) -> Result<ChapterComment, String> {
+    let (project_id, content) = chapter_content(conn, &request.chapter_id)?.ok_or("章节不存在")?;
+    let now = Utc::now().to_rfc3339();
+    let comment = ChapterComment {
+        id: uuid::Uuid::new_v4().to_string(),
+        project_id,
+        chapter_id: request.chapter_id.clone(),
+        parent_id: None,
+        author_id: request.author_id.clone(),
+        author_name: request.author_name.clone(),
+        body: request.body.clone(),
+        anchor: Some(TextAnchor::new(&content, request.start, request.end)?),
+        detached: false,
+        status: CommentStatus::Open,
+        resolved_by: None,
+        resolved_at: None,
+        created_at: now.clone(),
+        updated_at: now,
+        replies: Vec::new(),
+    };
+    save_comment(conn, &comment)?;
+    Ok(comment)
+}
+
+/// 回复批注；回复回复时挂到最上层的批注下
+pub fn reply_comment(conn: &Connection, request: &ReplyCommentRequest) -> Result<ChapterComment, String> {
+    let mut parent = get_comment(conn, &request.comment_id)?.ok_or("批注不存在")?;
+    if let Some(root_id) = &parent.parent_id {
+        parent = get_comment(conn, root_id)?.ok_or("批注不存在")?;
+    }
+    let now = Utc::now().to_rfc3339();
+    let reply = ChapterComment {
+        id: uuid::Uuid::new_v4().to_string(),
+        project_id: parent.project_id.clone(),
+        chapter_id: parent.chapter_id.clone(),
+        parent_id: Some(parent.id.clone()),
+        author_id: request.author_id.clone(),
+        author_name: request.author_name.clone(),
+        body: request.body.clone(),
+        anchor: None,
+        detached: false,
+        status: CommentStatus::Open,
+        resolved_by: None,
+        resolved_at: None,
+        created_at: now.clone(),
+        updated_at: now.clone(),
+        replies: Vec::new(),
+    };
+    save_comment(conn, &reply)?;
+    // 已解决的讨论有新回复时重新打开
+    if parent.status == CommentStatus::Resolved {
+        set_resolved(conn, &parent.id, None)?;
+    }
+    Ok(reply)
+}
+
+/// `resolved_by` 为 None 时重新打开批注
+pub fn set_resolved(conn: &Connection, comment_id: &str, resolved_by: Option<&str>) -> Result<ChapterComment, String> {
+    let mut comment = get_comment(conn, comment_id)?.ok_or("批注不存在")?;
+    if comment.parent_id.is_some() {
+        return Err("只能解决顶层批注".to_string());
+    }
+    let now = Utc::now().to_rfc3339();
+    comment.status = if resolved_by.is_some() { CommentStatus::Resolved } else { CommentStatus::Open };
+    comment.resolved_by = resolved_by.map(str::to_string);
+    comment.resolved_at = resolved_by.map(|_| now.clone());
+    comment.updated_at = now;
+    save_comment(conn, &comment)?;
+    Ok(comment)
+}
+
+pub fn delete_comment(conn: &Connection, comment_id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM chapter_comments WHERE id = ?1 OR parent_id = ?1", params![comment_id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 把 `comments` 中的回复挂到各自的批注下，返回顶层批注
+fn into_threads(comments: Vec<ChapterComment>) -> Vec<ChapterComment> {
+    let (mut roots, replies): (Vec<_>, Vec<_>) = comments.into_iter().partition(|c| c.parent_id.is_none());
+    for reply in replies {
+        if let Some(root) = roots.iter_mut().find(|r| Some(&r.id) == reply.parent_id.as_ref()) {
+            root.replies.push(reply);
+        }
+    }
+    roots
+}
+
+/// 章节的批注及回复，按正文位置排序。列出前按当前正文重新定位锚点，位置有变化的写回数据库
+pub fn list_chapter_comments(conn: &Connection, chapter_id: &str, include_resolved: bool) -> Result<Vec<ChapterComment>, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM chapter_comments WHERE chapter_id = ? ORDER BY created_at", COMMENT_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let comments = stmt
+        .query_map(params![chapter_id], comment_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let content = chapter_content(conn, chapter_id)?.map(|(_, content)| content).unwrap_or_default();
+
+    let mut threads = into_threads(comments);
+    for comment in threads.iter_mut() {
+        let Some(anchor) = &comment.anchor else { continue };
+        let (anchor, detached) = match reanchor(&content, anchor) {
+            Some(moved) => (moved, false),
+            None => (anchor.clone(), true),
+        };
+        if comment.anchor.as_ref() != Some(&anchor) || comment.detached != detached {
+            comment.anchor = Some(anchor);
+            comment.detached = detached;
+            let replies = std::mem::take(&mut comment.replies);
+            save_comment(conn, comment)?;
+            comment.replies = replies;
+        }
+    }
+    threads.retain(|c| include_resolved || c.status == CommentStatus::Open);
+    threads.sort_by_key(|c| (c.detached, c.anchor.as_ref().map(|a| a.start).unwrap_or(0)));
+    Ok(threads)
+}
+
+/// 把项目中未解决的批注导出为 Markdown，按章节顺序分组，供编辑或试读者汇总意见
+pub fn export_open_comments_markdown(conn: &Connection, project_id: &str) -> Result<String, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title FROM chapters WHERE project_id = ?
+             AND id IN (SELECT chapter_id FROM chapter_comments WHERE status = 'open' AND parent_id IS NULL)
+             ORDER BY sort_order",
+        )
+        .map_err(|e| e.to_string())?;
+    let chapters = stmt
+        .query_map(params![project_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut output = String::from("# 未解决的批注\n");
+    for (chapter_id, title) in chapters {
+        output.push_str(&format!("\n## {}\n", title));
+        for comment in list_chapter_comments(conn, &chapter_id, false)? {
+            output.push('\n');
+            if let Some(anchor) = &comment.anchor {
+                let note = if comment.detached { "（原文已删除）" } else { "" };
+                output.push_str(&format!("> {}{}\n\n", anchor.quote.replace('\n', "\n> "), note));
+            }
+            output.push_str(&format!("- **{}**（{}）：{}\n", comment.author_name, comment.created_at, comment.body));
+            for reply in &comment.replies {
+                output.push_str(&format!("  - **{}**：{}\n", reply.author_name, reply.body));
+            }
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reanchor_after_edits() {
+        let text = "雨下了一夜。他推开门，看见院子里的桂花落了一地。";
+        let anchor = TextAnchor::new(text, 13, 19).unwrap();
+        assert_eq!(anchor.quote, "院子里的桂花");
+
+        // 前面插入文字，锚点随之后移
+        let edited = format!("天亮时，{}", text);
+        let moved = reanchor(&edited, &anchor).unwrap();
+        assert_eq!((moved.start, moved.end), (17, 23));
+
+        // 选中的文字被改动，用前后文重新夹出范围
+        let rewritten = text.replace("院子里的桂花", "院中的桂花树");
+        let moved = reanchor(&rewritten, &anchor).unwrap();
+        assert_eq!(moved.quote, "院中的桂花树");
+
+        // 整句删除后无法定位
+        assert!(reanchor("雨下了一夜。", &anchor).is_none());
+
+        // 起止颠倒的范围不会越界访问
+        let reversed = TextAnchor { start: 19, end: 13, ..anchor };
+        assert_eq!(reanchor(text, &reversed).unwrap().start, 13);
+    }
+
+    #[test]
+    fn test_comment_threads() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute(
+            "INSERT INTO chapters (id, project_id, title, content, created_at, updated_at) VALUES ('c1', 'p1', '第一章', '他推开门，看见桂花落了一地。', 't0', 't0')",
+            [],
+        )
+        .unwrap();
+
+        let request = CreateCommentRequest {
+            chapter_id: "c1".into(),
+            start: 7,
+            end: 9,
+            author_id: "u1".into(),
+            author_name: "编辑".into(),
+            body: "这里可以写得更具体".into(),
+            session_id: None,
+        };
+        let comment = create_comment(&conn, &request).unwrap();
+        // 其他成员发来的批注范围无效时拒绝保存
+        let mut invalid = comment.clone();
+        invalid.id = "remote".into();
+        for (start, end) in [(9, 7), (7, 99)] {
+            invalid.anchor = Some(TextAnchor { start, end, ..comment.anchor.clone().unwrap() });
+            assert!(save_comment(&conn, &invalid).is_err());
+        }
+        let reply = |comment_id: &str, author: &str, body: &str| ReplyCommentRequest {
+            comment_id: comment_id.into(),
+            author_id: author.into(),
+            author_name: author.into(),
+            body: body.into(),
+            session_id: None,
+        };
+        let first = reply_comment(&conn, &reply(&comment.id, "作者", "好的")).unwrap();
+        reply_comment(&conn, &reply(&first.id, "编辑", "谢谢")).unwrap();
+
+        conn.execute("UPDATE chapters SET content = '清晨，他推开门，看见桂花落了一地。' WHERE id = 'c1'", []).unwrap();
+        let threads = list_chapter_comments(&conn, "c1", false).unwrap();
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0].replies.len(), 2);
+        assert_eq!(threads[0].anchor.as_ref().unwrap().start, 10);
+
+        let markdown = export_open_comments_markdown(&conn, "p1").unwrap();
+        assert!(markdown.contains("> 桂花") && markdown.contains("谢谢"));
+
+        set_resolved(&conn, &comment.id, Some("u2")).unwrap();
+        assert!(list_chapter_comments(&conn, "c1", false).unwrap().is_empty());
+        assert_eq!(list_chapter_comments(&conn, "c1", true).unwrap().len(), 1);
+        assert!(!export_open_comments_markdown(&conn, "p1").unwrap().contains("桂花"));
+
+        delete_comment(&conn, &comment.id).unwrap();
+        assert!(list_chapter_comments(&conn, "c1", true).unwrap().is_empty());
+    }
+}
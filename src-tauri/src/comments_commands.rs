@@ -0,0 +1,137 @@
+use crate::collaboration::SessionEvent;
+use crate::collaboration_auth::CollabRole;
+use crate::collaboration_commands::CollaborationState;
+use crate::comments::{self, ChapterComment, CreateCommentRequest, ReplyCommentRequest};
+use crate::logger::Logger;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
+
+fn open_connection(app: &AppHandle) -> Result<rusqlite::Connection, String> {
+    crate::database::get_connection(&get_db_path(app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))
+}
+
+/// 在协作会话中时，检查成员是否有批注权限
+fn authorize(state: &CollaborationState, session_id: Option<&str>, user_id: &str) -> Result<(), String> {
+    session_id.map_or(Ok(()), |session_id| state.manager().authorize(session_id, user_id, CollabRole::can_comment))
+}
+
+/// 在协作会话中时，把批注变化发给其他成员
+fn share(state: &CollaborationState, session_id: Option<&str>, user_id: &str, comments: &[&ChapterComment]) -> Result<(), String> {
+    let Some(session_id) = session_id else { return Ok(()) };
+    for comment in comments {
+        let event = SessionEvent::Comment { user_id: user_id.to_string(), comment: Box::new((*comment).clone()) };
+        state.manager().share_comment_event(session_id, event)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn comment_create(
+    app: AppHandle,
+    request: CreateCommentRequest,
+    state: tauri::State<'_, CollaborationState>,
+) -> Result<ChapterComment, String> {
+    let logger = Logger::new().with_feature("comments");
+    logger.info(&format!("Creating comment on chapter {}", request.chapter_id));
+
+    authorize(&state, request.session_id.as_deref(), &request.author_id)?;
+    let conn = open_connection(&app)?;
+    let comment = comments::create_comment(&conn, &request)?;
+    share(&state, request.session_id.as_deref(), &request.author_id, &[&comment])?;
+    Ok(comment)
+}
+
+#[tauri::command]
+pub async fn comment_reply(
+    app: AppHandle,
+    request: ReplyCommentRequest,
+    state: tauri::State<'_, CollaborationState>,
+) -> Result<ChapterComment, String> {
+    let logger = Logger::new().with_feature("comments");
+    logger.info(&format!("Replying to comment {}", request.comment_id));
+
+    authorize(&state, request.session_id.as_deref(), &request.author_id)?;
+    let conn = open_connection(&app)?;
+    let reply = comments::reply_comment(&conn, &request)?;
+    // 回复可能重新打开了已解决的批注，一并同步
+    let parent = reply.parent_id.as_deref().map(|id| comments::get_comment(&conn, id)).transpose()?.flatten();
+    let mut changed = vec![&reply];
+    changed.extend(parent.as_ref());
+    share(&state, request.session_id.as_deref(), &request.author_id, &changed)?;
+    Ok(reply)
+}
+
+/// 解决批注；`resolved` 为 false 时重新打开
+#[tauri::command]
+pub async fn comment_resolve(
+    app: AppHandle,
+    comment_id: String,
+    user_id: String,
+    resolved: bool,
+    session_id: Option<String>,
+    state: tauri::State<'_, CollaborationState>,
+) -> Result<ChapterComment, String> {
+    let logger = Logger::new().with_feature("comments");
+    logger.info(&format!("Setting comment {} resolved: {}", comment_id, resolved));
+
+    authorize(&state, session_id.as_deref(), &user_id)?;
+    let conn = open_connection(&app)?;
+    let comment = comments::set_resolved(&conn, &comment_id, resolved.then_some(user_id.as_str()))?;
+    share(&state, session_id.as_deref(), &user_id, &[&comment])?;
+    Ok(comment)
+}
+
+#[tauri::command]
+pub async fn comment_delete(
+    app: AppHandle,
+    comment_id: String,
+    user_id: String,
+    session_id: Option<String>,
+    state: tauri::State<'_, CollaborationState>,
+) -> Result<(), String> {
+    let logger = Logger::new().with_feature("comments");
+    logger.info(&format!("Deleting comment {}", comment_id));
+
+    authorize(&state, session_id.as_deref(), &user_id)?;
+    let conn = open_connection(&app)?;
+    comments::delete_comment(&conn, &comment_id)?;
+    if let Some(session_id) = session_id {
+        state.manager().share_comment_event(&session_id, SessionEvent::CommentDeleted { user_id, comment_id })?;
+    }
+    Ok(())
+}
+
+/// 章节的批注及回复，锚点已按当前正文重新定位
+#[tauri::command]
+pub async fn comment_list(
+    app: AppHandle,
+    chapter_id: String,
+    include_resolved: Option<bool>,
+) -> Result<Vec<ChapterComment>, String> {
+    let conn = open_connection(&app)?;
+    comments::list_chapter_comments(&conn, &chapter_id, include_resolved.unwrap_or(false))
+}
+
+/// 导出项目中未解决的批注为 Markdown
+#[tauri::command]
+pub async fn comment_export_open(app: AppHandle, project_id: String) -> Result<String, String> {
+    let logger = Logger::new().with_feature("comments");
+    logger.info(&format!("Exporting open comments for project {}", project_id));
+
+    let conn = open_connection(&app)?;
+    comments::export_open_comments_markdown(&conn, &project_id)
+}
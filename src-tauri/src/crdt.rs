@@ -0,0 +1,247 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// 操作的全局唯一标识：产生操作的客户端和该客户端内连续递增的序号
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct OpId {
+    pub client: String,
+    pub seq: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CrdtOpKind {
+    /// 在 `origin` 之后插入一个字符，`origin` 为空表示插在开头
+    Insert { origin: Option<OpId>, ch: char },
+    /// 删除某个插入操作产生的字符，删除后保留为墓碑
+    Delete { target: OpId },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrdtOp {
+    pub id: OpId,
+    /// Lamport 时钟，并发插入同一位置时按 (lamport, client) 从大到小排列
+    pub lamport: u64,
+    /// 产生操作的时间（Unix 毫秒），只用于回放，不参与排序
+    #[serde(default)]
+    pub timestamp: i64,
+    #[serde(flatten)]
+    pub kind: CrdtOpKind,
+}
+
+/// 每个客户端已收到的最大序号
+pub type StateVector = HashMap<String, u64>;
+
+#[derive(Debug, Clone)]
+struct Item {
+    id: OpId,
+    lamport: u64,
+    ch: char,
+    deleted: bool,
+}
+
+impl Item {
+    fn key(&self) -> (u64, &str) {
+        (self.lamport, &self.id.client)
+    }
+}
+
+/// 一章正文的序列 CRDT（RGA）。同一组操作无论以什么顺序应用，得到的文本都相同；
+/// 依赖的字符尚未到达的操作先缓存，等依赖到达后再应用
+#[derive(Debug, Clone, Default)]
+pub struct TextDocument {
+    items: Vec<Item>,
+    lamport: u64,
+    state: StateVector,
+    seen: HashSet<OpId>,
+    pending: Vec<CrdtOp>,
+    log: Vec<CrdtOp>,
+}
+
+impl TextDocument {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(&self) -> String {
+        self.items.iter().filter(|item| !item.deleted).map(|item| item.ch).collect()
+    }
+
+    /// 每个客户端写下、目前仍可见的字数
+    pub fn authorship(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for item in self.items.iter().filter(|item| !item.deleted) {
+            *counts.entry(item.id.client.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    pub fn state_vector(&self) -> &StateVector {
+        &self.state
+    }
+
+    /// 对端缺少的操作，按应用顺序排列
+    pub fn ops_since(&self, state: &StateVector) -> Vec<CrdtOp> {
+        self.log
+            .iter()
+            .chain(&self.pending)
+            .filter(|op| op.id.seq > state.get(&op.id.client).copied().unwrap_or(0))
+            .cloned()
+            .collect()
+    }
+
+    fn next_id(&mut self, client: &str) -> (OpId, u64) {
+        let seq = self.state.get(client).copied().unwrap_or(0) + 1;
+        (OpId { client: client.to_string(), seq }, self.lamport + 1)
+    }
+
+    /// 第 `position` 个可见字符在 items 中的下标
+    fn visible_index(&self, position: usize) -> Option<usize> {
+        self.items.iter().enumerate().filter(|(_, item)| !item.deleted).nth(position).map(|(i, _)| i)
+    }
+
+    fn find(&self, id: &OpId) -> Option<usize> {
+        // 连续输入时依赖的通常是最近插入的字符，从后往前找
+        self.items.iter().rposition(|item| &item.id == id)
+    }
+
+    /// 在第 `position` 个可见字符前插入文本，返回需要广播的操作
+    pub fn insert(&mut self, client: &str, position: usize, text: &str) -> Vec<CrdtOp> {
+        let mut origin = position.checked_sub(1).and_then(|p| self.visible_index(p)).map(|i| self.items[i].id.clone());
+        let mut ops = Vec::new();
+        for ch in text.chars() {
+            let (id, lamport) = self.next_id(client);
+            let op = CrdtOp { id: id.clone(), lamport, timestamp: Utc::now().timestamp_millis(), kind: CrdtOpKind::Insert { origin, ch } };
+            self.apply(op.clone());
+            ops.push(op);
+            origin = Some(id);
+        }
+        ops
+    }
+
+    /// 删除从第 `position` 个可见字符开始的 `length` 个字符，返回需要广播的操作
+    pub fn delete(&mut self, client: &str, position: usize, length: usize) -> Vec<CrdtOp> {
+        let targets: Vec<OpId> =
+            self.items.iter().filter(|item| !item.deleted).skip(position).take(length).map(|item| item.id.clone()).collect();
+        targets
+            .into_iter()
+            .map(|target| {
+                let (id, lamport) = self.next_id(client);
+                let op = CrdtOp { id, lamport, timestamp: Utc::now().timestamp_millis(), kind: CrdtOpKind::Delete { target } };
+                self.apply(op.clone());
+                op
+            })
+            .collect()
+    }
+
+    /// 把正文改成 `text`：只替换首尾相同部分之间的内容，返回需要广播的操作
+    pub fn replace_text(&mut self, client: &str, text: &str) -> Vec<CrdtOp> {
+        let old: Vec<char> = self.text().chars().collect();
+        let new: Vec<char> = text.chars().collect();
+        let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+        let mut ops = self.delete(client, prefix, old.len() - prefix - suffix);
+        let inserted: String = new[prefix..new.len() - suffix].iter().collect();
+        ops.extend(self.insert(client, prefix, &inserted));
+        ops
+    }
+
+    /// 应用一个操作，已经见过的操作返回 false
+    pub fn apply(&mut self, op: CrdtOp) -> bool {
+        if self.seen.contains(&op.id) {
+            return false;
+        }
+        self.seen.insert(op.id.clone());
+        self.pending.push(op);
+        // 应用所有依赖已满足的操作，直到没有进展
+        loop {
+            let before = self.pending.len();
+            let pending = std::mem::take(&mut self.pending);
+            for op in pending {
+                if !self.integrate(&op) {
+                    self.pending.push(op);
+                }
+            }
+            if self.pending.is_empty() || self.pending.len() == before {
+                break;
+            }
+        }
+        true
+    }
+
+    fn integrate(&mut self, op: &CrdtOp) -> bool {
+        match &op.kind {
+            CrdtOpKind::Insert { origin, ch } => {
+                let mut index = match origin {
+                    None => 0,
+                    Some(origin) => match self.find(origin) {
+                        Some(i) => i + 1,
+                        None => return false,
+                    },
+                };
+                // 跳过同一位置上更晚的并发插入及其后续字符
+                let key = (op.lamport, op.id.client.as_str());
+                while index < self.items.len() && self.items[index].key() > key {
+                    index += 1;
+                }
+                self.items.insert(index, Item { id: op.id.clone(), lamport: op.lamport, ch: *ch, deleted: false });
+            }
+            CrdtOpKind::Delete { target } => match self.find(target) {
+                Some(i) => self.items[i].deleted = true,
+                None => return false,
+            },
+        }
+        self.lamport = self.lamport.max(op.lamport);
+        let seq = self.state.entry(op.id.client.clone()).or_insert(0);
+        *seq = (*seq).max(op.id.seq);
+        self.log.push(op.clone());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concurrent_edits_converge() {
+        // 两端由同一段正文生成的初始操作相同
+        let mut a = TextDocument::new();
+        a.insert("seed", 0, "雨夜");
+        let mut b = TextDocument::new();
+        b.insert("seed", 0, "雨夜");
+        assert_eq!(a.state_vector(), b.state_vector());
+
+        let mut ops_a = a.insert("alice", 1, "后的");
+        ops_a.extend(a.delete("alice", 0, 1));
+        let mut ops_b = b.insert("bob", 1, "中");
+        ops_b.extend(b.insert("bob", 3, "，很长"));
+
+        for op in &ops_b {
+            a.apply(op.clone());
+        }
+        // 乱序到达：依赖缺失的操作先缓存
+        for op in ops_a.iter().rev() {
+            b.apply(op.clone());
+        }
+        assert_eq!(a.text(), b.text());
+        assert!(!a.apply(ops_b[0].clone()));
+
+        let mut c = TextDocument::new();
+        for op in a.ops_since(&StateVector::new()) {
+            c.apply(op);
+        }
+        assert_eq!(c.text(), a.text());
+        assert!(b.ops_since(a.state_vector()).is_empty());
+    }
+
+    #[test]
+    fn test_replace_text_keeps_common_parts() {
+        let mut doc = TextDocument::new();
+        doc.insert("seed", 0, "他走进雨夜");
+        let ops = doc.replace_text("alice", "他缓缓走进雨夜");
+        assert_eq!(doc.text(), "他缓缓走进雨夜");
+        assert_eq!(ops.len(), 2);
+    }
+}
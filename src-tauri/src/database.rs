@@ -43,6 +43,12 @@ pub fn init_database(db_path: &Path) -> SqlResult<()> {
         [],
     ).ok();
 
+    // 目标字数，由大纲节点生成章节时填入
+    conn.execute(
+        "ALTER TABLE chapters ADD COLUMN target_word_count INTEGER",
+        [],
+    ).ok();
+
     // 创建角色表
     conn.execute(
         "CREATE TABLE IF NOT EXISTS characters (
@@ -320,6 +326,12 @@ pub fn init_database(db_path: &Path) -> SqlResult<()> {
         [],
     )?;
 
+    // 定论标记：同一对象的说法冲突时，AI 上下文优先采用定论
+    conn.execute(
+        "ALTER TABLE knowledge_entries ADD COLUMN is_canonical INTEGER DEFAULT 0",
+        [],
+    ).ok();
+
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_knowledge_entries_project ON knowledge_entries(project_id)",
         [],
@@ -335,6 +347,101 @@ pub fn init_database(db_path: &Path) -> SqlResult<()> {
         [],
     )?;
 
+    // 知识条目的向量，content_hash 变化或换了模型时重新生成
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS knowledge_embeddings (
+            entry_id TEXT PRIMARY KEY,
+            model TEXT NOT NULL,
+            content_hash TEXT NOT NULL,
+            embedding BLOB NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (entry_id) REFERENCES knowledge_entries(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 知识条目出自哪些章节，自动提取的事实可对照原文一键核实
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS knowledge_sources (
+            entry_id TEXT NOT NULL,
+            chapter_id TEXT NOT NULL,
+            excerpt TEXT,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (entry_id, chapter_id),
+            FOREIGN KEY (entry_id) REFERENCES knowledge_entries(id) ON DELETE CASCADE,
+            FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_knowledge_sources_chapter ON knowledge_sources(chapter_id)",
+        [],
+    )?;
+
+    // 已提取过知识的章节正文哈希，正文未变的章节不再重复提取
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS knowledge_extraction_scans (
+            chapter_id TEXT PRIMARY KEY,
+            content_hash TEXT NOT NULL,
+            scanned_at TEXT NOT NULL,
+            FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 知识条目之间的事实冲突，status 为 open、resolved 或 dismissed
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS knowledge_conflicts (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            entity TEXT NOT NULL,
+            attribute TEXT NOT NULL,
+            entry_ids TEXT NOT NULL,
+            description TEXT NOT NULL,
+            severity TEXT NOT NULL,
+            detected_by TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'open',
+            canonical_entry_id TEXT,
+            created_at TEXT NOT NULL,
+            resolved_at TEXT,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_knowledge_conflicts_project ON knowledge_conflicts(project_id, status)",
+        [],
+    )?;
+
+    // 知识条目的标题或关键词在章节正文中的出现位置，偏移为字符下标
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS knowledge_mentions (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            entry_id TEXT NOT NULL,
+            chapter_id TEXT NOT NULL,
+            text TEXT NOT NULL,
+            start_offset INTEGER NOT NULL,
+            end_offset INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (entry_id) REFERENCES knowledge_entries(id) ON DELETE CASCADE,
+            FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_knowledge_mentions_chapter ON knowledge_mentions(chapter_id, start_offset)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_knowledge_mentions_entry ON knowledge_mentions(entry_id)",
+        [],
+    )?;
+
     // 创建知识库关系表
     conn.execute(
         "CREATE TABLE IF NOT EXISTS knowledge_relations (
@@ -398,6 +505,12 @@ pub fn init_database(db_path: &Path) -> SqlResult<()> {
         [],
     )?;
 
+    // 检查并添加storage列（数据库迁移）
+    conn.execute(
+        "ALTER TABLE project_snapshots ADD COLUMN storage TEXT NOT NULL DEFAULT 'inline'",
+        [],
+    ).ok();
+
     // 创建版本差异表
     conn.execute(
         "CREATE TABLE IF NOT EXISTS version_diffs (
@@ -506,6 +619,12 @@ pub fn init_database(db_path: &Path) -> SqlResult<()> {
         [],
     )?;
 
+    // 检查并添加auto_save_word_delta列（数据库迁移）
+    conn.execute(
+        "ALTER TABLE version_control_config ADD COLUMN auto_save_word_delta INTEGER DEFAULT 500",
+        [],
+    ).ok();
+
     // 创建伏笔追踪表
     conn.execute(
         "CREATE TABLE IF NOT EXISTS foreshadowings (
@@ -836,6 +955,1003 @@ pub fn init_database(db_path: &Path) -> SqlResult<()> {
         [],
     )?;
 
+    // 网页导入进度表（按章节记录抓取状态，支持断点续传）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS url_import_items (
+            id TEXT PRIMARY KEY,
+            task_id TEXT NOT NULL,
+            project_id TEXT NOT NULL,
+            url TEXT NOT NULL,
+            title TEXT NOT NULL,
+            sort_order INTEGER NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            chapter_id TEXT,
+            error TEXT,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_url_import_items_task ON url_import_items(task_id)",
+        [],
+    )?;
+
+    // 云同步基线表：记录每个文件上次同步时的哈希，用于判断本地/远端哪一侧发生了修改
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_file_state (
+            provider TEXT NOT NULL,
+            project_id TEXT NOT NULL,
+            path TEXT NOT NULL,
+            hash TEXT NOT NULL,
+            updated_at TEXT,
+            synced_at TEXT NOT NULL,
+            data TEXT,
+            PRIMARY KEY (provider, project_id, path)
+        )",
+        [],
+    )?;
+
+    // 检查并添加data列（数据库迁移），保存章节的上次同步内容作为三方合并的共同祖先
+    conn.execute(
+        "ALTER TABLE sync_file_state ADD COLUMN data TEXT",
+        [],
+    ).ok();
+
+    // 待用户解决的同步冲突，保存远端版本和逐段合并报告
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_conflicts (
+            id TEXT PRIMARY KEY,
+            provider TEXT NOT NULL,
+            project_id TEXT NOT NULL,
+            path TEXT NOT NULL,
+            conflict_type TEXT NOT NULL,
+            remote_hash TEXT,
+            remote_updated_at TEXT,
+            remote_data TEXT,
+            merge TEXT,
+            created_at TEXT NOT NULL,
+            UNIQUE (provider, project_id, path)
+        )",
+        [],
+    )?;
+
+    // 已应用的远端变更批次，增量同步时只下载不在此表中的批次
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_applied_batches (
+            provider TEXT NOT NULL,
+            project_id TEXT NOT NULL,
+            batch TEXT NOT NULL,
+            applied_at TEXT NOT NULL,
+            PRIMARY KEY (provider, project_id, batch)
+        )",
+        [],
+    )?;
+
+    // 删除墓碑：记录本地删除的时间，同步后清除
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_tombstones (
+            table_name TEXT NOT NULL,
+            row_id TEXT NOT NULL,
+            project_id TEXT NOT NULL,
+            deleted_at TEXT NOT NULL,
+            PRIMARY KEY (table_name, row_id)
+        )",
+        [],
+    )?;
+
+    // 离线时积压的本地变更，联网后按 queued_at 顺序上传
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_outbox (
+            provider TEXT NOT NULL,
+            project_id TEXT NOT NULL,
+            path TEXT NOT NULL,
+            hash TEXT,
+            updated_at TEXT,
+            queued_at TEXT NOT NULL,
+            PRIMARY KEY (provider, project_id, path)
+        )",
+        [],
+    )?;
+
+    // 每次同步运行的记录，用于排查数据丢失等问题
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_history (
+            id TEXT PRIMARY KEY,
+            provider TEXT NOT NULL,
+            project_ids TEXT,
+            started_at TEXT NOT NULL,
+            duration_ms INTEGER NOT NULL DEFAULT 0,
+            success INTEGER NOT NULL DEFAULT 0,
+            pushed INTEGER NOT NULL DEFAULT 0,
+            pulled INTEGER NOT NULL DEFAULT 0,
+            conflicts INTEGER NOT NULL DEFAULT 0,
+            errors TEXT,
+            files TEXT
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_sync_history_started_at ON sync_history(started_at)",
+        [],
+    )?;
+
+    // 协作编辑的 CRDT 操作，按章节重放即可得到合并后的正文；按 timestamp 截取可回放任意时刻的状态
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS chapter_crdt_ops (
+            project_id TEXT NOT NULL,
+            chapter_id TEXT NOT NULL,
+            client_id TEXT NOT NULL,
+            seq INTEGER NOT NULL,
+            lamport INTEGER NOT NULL,
+            op TEXT NOT NULL,
+            session_id TEXT,
+            timestamp INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (chapter_id, client_id, seq)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_chapter_crdt_ops_project ON chapter_crdt_ops(project_id)",
+        [],
+    )?;
+
+    // 锚定在章节正文上的批注和回复，偏移量按字符计
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS chapter_comments (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            chapter_id TEXT NOT NULL,
+            parent_id TEXT,
+            author_id TEXT NOT NULL,
+            author_name TEXT NOT NULL,
+            body TEXT NOT NULL,
+            anchor_start INTEGER,
+            anchor_end INTEGER,
+            quote TEXT,
+            prefix TEXT,
+            suffix TEXT,
+            detached INTEGER NOT NULL DEFAULT 0,
+            status TEXT NOT NULL DEFAULT 'open',
+            resolved_by TEXT,
+            resolved_at TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_chapter_comments_chapter ON chapter_comments(chapter_id)",
+        [],
+    )?;
+
+    // 协作会话中的加入、离开、切换章节和编辑动态
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS collab_activity (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            user_name TEXT,
+            kind TEXT NOT NULL,
+            chapter_id TEXT,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_collab_activity_project ON collab_activity(project_id, created_at)",
+        [],
+    )?;
+
+    // 主机签发的协作邀请，吊销后保留记录
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS collab_invites (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            role TEXT NOT NULL,
+            expires_at INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            revoked_at TEXT
+        )",
+        [],
+    )?;
+
+    // 章节分支，当前分支的正文保存在 chapters 中
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS chapter_branches (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            chapter_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            base_content TEXT NOT NULL,
+            content TEXT NOT NULL,
+            active INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            merged_at TEXT,
+            parent_id TEXT,
+            UNIQUE (chapter_id, name),
+            FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS chapter_branch_snapshots (
+            id TEXT PRIMARY KEY,
+            branch_id TEXT NOT NULL,
+            description TEXT NOT NULL,
+            content TEXT NOT NULL,
+            word_count INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (branch_id) REFERENCES chapter_branches(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_chapter_branch_snapshots_branch ON chapter_branch_snapshots(branch_id, created_at)",
+        [],
+    )?;
+
+    // 快照正文的数据块，按内容哈希去重、zstd 压缩
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS snapshot_blobs (
+            hash TEXT PRIMARY KEY,
+            data BLOB NOT NULL,
+            size INTEGER NOT NULL,
+            compressed_size INTEGER NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS snapshot_tags (
+            id TEXT PRIMARY KEY,
+            snapshot_id TEXT NOT NULL,
+            project_id TEXT NOT NULL,
+            tag TEXT NOT NULL,
+            note TEXT,
+            milestone INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            UNIQUE (snapshot_id, tag),
+            FOREIGN KEY (snapshot_id) REFERENCES project_snapshots(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_snapshot_tags_project ON snapshot_tags(project_id, tag)",
+        [],
+    )?;
+
+    // AI 生成结果，保存章节时用来判断段落是否来自 AI
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS ai_generations (
+            id TEXT PRIMARY KEY,
+            project_id TEXT,
+            source TEXT NOT NULL,
+            model_id TEXT NOT NULL,
+            text TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_ai_generations_created ON ai_generations(created_at)",
+        [],
+    )?;
+
+    // 章节每个段落最后一次改动的来源
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS chapter_provenance (
+            chapter_id TEXT PRIMARY KEY,
+            paragraphs_json TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS chapter_provenance_log (
+            id TEXT PRIMARY KEY,
+            chapter_id TEXT NOT NULL,
+            source TEXT NOT NULL,
+            model_id TEXT,
+            paragraphs INTEGER NOT NULL,
+            inserted_chars INTEGER NOT NULL,
+            deleted_chars INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_chapter_provenance_log_chapter ON chapter_provenance_log(chapter_id, created_at)",
+        [],
+    )?;
+
+    // 后台版本存储维护任务每个项目最近一次的清理结果
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS version_storage_reports (
+            project_id TEXT PRIMARY KEY,
+            pruned INTEGER NOT NULL,
+            stored_bytes INTEGER NOT NULL,
+            reported_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 分词用户词典：自创的人名、地名、术语等
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS user_dictionary (
+            word TEXT PRIMARY KEY,
+            project_id TEXT,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 章节节奏指标缓存，正文哈希变化时重新计算
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pacing_cache (
+            chapter_id TEXT PRIMARY KEY,
+            content_hash TEXT NOT NULL,
+            metrics_json TEXT NOT NULL,
+            computed_at TEXT NOT NULL,
+            FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 角色的别名（外号、称谓、姓氏等），用于统计出场、识别提及和构建写作上下文
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS character_aliases (
+            character_id TEXT NOT NULL,
+            alias TEXT NOT NULL,
+            PRIMARY KEY (character_id, alias),
+            FOREIGN KEY (character_id) REFERENCES characters(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 章节中人物、地点、物品的出现位置（字符偏移）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS entity_mentions (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            chapter_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            text TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            source TEXT NOT NULL,
+            start_offset INTEGER NOT NULL,
+            end_offset INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_entity_mentions_name ON entity_mentions(project_id, name)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_entity_mentions_chapter ON entity_mentions(chapter_id)",
+        [],
+    )?;
+
+    // 词汇分析的项目词表：忽略词（ignore）和自定义赘词（crutch）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS vocabulary_word_lists (
+            project_id TEXT NOT NULL,
+            list TEXT NOT NULL,
+            word TEXT NOT NULL,
+            PRIMARY KEY (project_id, list, word),
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 写作工具的套话词表：项目自定义的短语（custom）和不再提示的短语（suppressed）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS cliche_phrases (
+            project_id TEXT NOT NULL,
+            phrase TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            PRIMARY KEY (project_id, phrase, kind),
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 用户管理的敏感词库（内置词库在代码中维护，不在此表）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sensitive_dictionaries (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT,
+            enabled_by_default INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sensitive_dictionary_words (
+            dictionary_id TEXT NOT NULL,
+            word TEXT NOT NULL,
+            severity TEXT NOT NULL,
+            PRIMARY KEY (dictionary_id, word),
+            FOREIGN KEY (dictionary_id) REFERENCES sensitive_dictionaries(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 项目对各敏感词库的启用设置，没有记录时按词库的默认设置；内置词库的 id 为 builtin
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sensitive_dictionary_projects (
+            project_id TEXT NOT NULL,
+            dictionary_id TEXT NOT NULL,
+            enabled INTEGER NOT NULL,
+            PRIMARY KEY (project_id, dictionary_id),
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 项目的敏感词白名单
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sensitive_whitelist (
+            project_id TEXT NOT NULL,
+            word TEXT NOT NULL,
+            PRIMARY KEY (project_id, word),
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 自动更正规则：scope 为 global 时对所有项目生效，为 project 时只对 project_id 生效
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS autocorrect_rules (
+            id TEXT PRIMARY KEY,
+            project_id TEXT,
+            pattern TEXT NOT NULL,
+            replacement TEXT NOT NULL,
+            is_regex INTEGER NOT NULL DEFAULT 0,
+            scope TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 每个项目每天新增和删除的字数，由章节保存时的字数变化累计
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS writing_stats (
+            project_id TEXT NOT NULL,
+            date TEXT NOT NULL,
+            words_added INTEGER NOT NULL DEFAULT 0,
+            words_deleted INTEGER NOT NULL DEFAULT 0,
+            updated_at TEXT NOT NULL,
+            PRIMARY KEY (project_id, date),
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 项目的写作目标
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS writing_goals (
+            project_id TEXT PRIMARY KEY,
+            daily_words INTEGER NOT NULL DEFAULT 0,
+            weekly_words INTEGER NOT NULL DEFAULT 0,
+            target_total_words INTEGER,
+            deadline TEXT,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 限时写作冲刺，进行中时 baseline_json 保存开始时各章正文，结束后清空
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS writing_sprints (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            project_id TEXT NOT NULL,
+            planned_minutes INTEGER NOT NULL,
+            started_at TEXT NOT NULL,
+            ended_at TEXT,
+            status TEXT NOT NULL,
+            words_written INTEGER NOT NULL DEFAULT 0,
+            words_deleted INTEGER NOT NULL DEFAULT 0,
+            baseline_json TEXT,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_writing_sprints_user ON writing_sprints(user_id, started_at)",
+        [],
+    )?;
+
+    // 项目的中西文混排设置
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS typography_settings (
+            project_id TEXT PRIMARY KEY,
+            cjk_latin_spacing INTEGER NOT NULL DEFAULT 1,
+            smart_quotes INTEGER NOT NULL DEFAULT 1,
+            normalize_units INTEGER NOT NULL DEFAULT 1,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 术语表：规范写法及不应出现的变体（JSON 数组）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS glossary_terms (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            term TEXT NOT NULL,
+            variants TEXT NOT NULL DEFAULT '[]',
+            note TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            UNIQUE (project_id, term),
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 角色的说话风格：用词、口头禅（JSON 数组）、句长范围和禁用词（JSON 数组）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS character_voice_profiles (
+            character_id TEXT PRIMARY KEY,
+            diction TEXT,
+            catchphrases TEXT NOT NULL DEFAULT '[]',
+            min_sentence_length INTEGER,
+            max_sentence_length INTEGER,
+            forbidden_words TEXT NOT NULL DEFAULT '[]',
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (character_id) REFERENCES characters(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 角色立绘画廊：生成或上传的图片及其画风和变体（角度、表情）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS character_portraits (
+            id TEXT PRIMARY KEY,
+            character_id TEXT NOT NULL,
+            image TEXT NOT NULL,
+            style TEXT NOT NULL,
+            variant TEXT NOT NULL,
+            source TEXT NOT NULL,
+            prompt TEXT,
+            created_at TEXT NOT NULL,
+            UNIQUE (character_id, image),
+            FOREIGN KEY (character_id) REFERENCES characters(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 一次生成写入的一批角色（JSON 数组），用于撤销
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS cast_batches (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            character_ids TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 人物关系在各章的状态（强度、好感、疏远/结盟等），用于按章查看关系图和绘制关系变化
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS character_relation_states (
+            id TEXT PRIMARY KEY,
+            relation_id TEXT NOT NULL,
+            chapter_id TEXT NOT NULL,
+            strength INTEGER NOT NULL DEFAULT 50,
+            sentiment REAL NOT NULL DEFAULT 0,
+            status TEXT,
+            note TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            UNIQUE (relation_id, chapter_id),
+            FOREIGN KEY (relation_id) REFERENCES character_relations(id) ON DELETE CASCADE,
+            FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 结构化的亲属关系（parent 为 from 是 to 的父母；sibling、spouse 双向），与自由填写的人物关系分开
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS character_kinship (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            from_id TEXT NOT NULL,
+            to_id TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            note TEXT,
+            created_at TEXT NOT NULL,
+            UNIQUE (from_id, to_id, kind),
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
+            FOREIGN KEY (from_id) REFERENCES characters(id) ON DELETE CASCADE,
+            FOREIGN KEY (to_id) REFERENCES characters(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 势力和组织，parent_id 指向上级组织
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS organizations (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            org_type TEXT,
+            parent_id TEXT,
+            description TEXT,
+            goals TEXT,
+            worldview_id TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
+            FOREIGN KEY (parent_id) REFERENCES organizations(id) ON DELETE SET NULL,
+            FOREIGN KEY (worldview_id) REFERENCES world_views(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+
+    // 组织成员及其职位，加入、离开章节为空表示故事开始前加入、一直没有离开
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS organization_members (
+            id TEXT PRIMARY KEY,
+            organization_id TEXT NOT NULL,
+            character_id TEXT NOT NULL,
+            role TEXT,
+            join_chapter_id TEXT,
+            leave_chapter_id TEXT,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (organization_id) REFERENCES organizations(id) ON DELETE CASCADE,
+            FOREIGN KEY (character_id) REFERENCES characters(id) ON DELETE CASCADE,
+            FOREIGN KEY (join_chapter_id) REFERENCES chapters(id) ON DELETE SET NULL,
+            FOREIGN KEY (leave_chapter_id) REFERENCES chapters(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+
+    // 地点，parent_id 指向上级地点（世界 → 地区 → 城市 → 建筑）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS locations (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            level TEXT NOT NULL DEFAULT 'other',
+            parent_id TEXT,
+            description TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
+            FOREIGN KEY (parent_id) REFERENCES locations(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+
+    // 两地之间的路程，双向，from_location_id 为较小的 id
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS location_routes (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            from_location_id TEXT NOT NULL,
+            to_location_id TEXT NOT NULL,
+            travel_time TEXT NOT NULL,
+            note TEXT,
+            created_at TEXT NOT NULL,
+            UNIQUE (from_location_id, to_location_id),
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
+            FOREIGN KEY (from_location_id) REFERENCES locations(id) ON DELETE CASCADE,
+            FOREIGN KEY (to_location_id) REFERENCES locations(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 章节发生的地点，position 为 0 的是主要地点
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS chapter_locations (
+            chapter_id TEXT NOT NULL,
+            location_id TEXT NOT NULL,
+            position INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (chapter_id, location_id),
+            FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE,
+            FOREIGN KEY (location_id) REFERENCES locations(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 项目的历法（纪元、月份、重要日子），config_json 为 CalendarConfig
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS story_calendars (
+            project_id TEXT PRIMARY KEY,
+            config_json TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 物品和法宝，initial_owner_id 为故事开始时的持有者，之后的归属看 item_events
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS story_items (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            description TEXT,
+            abilities_json TEXT NOT NULL DEFAULT '[]',
+            location_id TEXT,
+            first_chapter_id TEXT,
+            initial_owner_id TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            UNIQUE (project_id, name),
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
+            FOREIGN KEY (location_id) REFERENCES locations(id) ON DELETE SET NULL,
+            FOREIGN KEY (first_chapter_id) REFERENCES chapters(id) ON DELETE SET NULL,
+            FOREIGN KEY (initial_owner_id) REFERENCES characters(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+
+    // 物品的流转（转交、遗失、被毁、修复），position 为同一章内的先后
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS item_events (
+            id TEXT PRIMARY KEY,
+            item_id TEXT NOT NULL,
+            chapter_id TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            character_id TEXT,
+            note TEXT,
+            position INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (item_id) REFERENCES story_items(id) ON DELETE CASCADE,
+            FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE,
+            FOREIGN KEY (character_id) REFERENCES characters(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+
+    // 力量体系（修炼、魔法等），costs/constraints 为代价和硬性限制
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS power_systems (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            description TEXT,
+            costs TEXT,
+            constraints TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 力量体系的境界，rank 越大越高
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS power_levels (
+            id TEXT PRIMARY KEY,
+            system_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            rank INTEGER NOT NULL DEFAULT 0,
+            description TEXT,
+            breakthrough TEXT,
+            abilities TEXT,
+            FOREIGN KEY (system_id) REFERENCES power_systems(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 角色达到某个境界的章节，chapter_id 为空表示故事开始前
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS character_power_progress (
+            id TEXT PRIMARY KEY,
+            character_id TEXT NOT NULL,
+            level_id TEXT NOT NULL,
+            chapter_id TEXT,
+            note TEXT,
+            created_at TEXT NOT NULL,
+            UNIQUE (character_id, level_id),
+            FOREIGN KEY (character_id) REFERENCES characters(id) ON DELETE CASCADE,
+            FOREIGN KEY (level_id) REFERENCES power_levels(id) ON DELETE CASCADE,
+            FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+
+    // 世界观条目的历史版本，内容存在 snapshot_blobs 中；条目删除后保留，便于找回
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS worldview_revisions (
+            id TEXT PRIMARY KEY,
+            worldview_id TEXT NOT NULL,
+            project_id TEXT NOT NULL,
+            blob_hash TEXT NOT NULL,
+            title TEXT NOT NULL,
+            note TEXT,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_worldview_revisions_worldview ON worldview_revisions(worldview_id, created_at)",
+        [],
+    )?;
+
+    // 知识条目的历史版本，内容存在快照数据块中
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS knowledge_revisions (
+            id TEXT PRIMARY KEY,
+            entry_id TEXT NOT NULL,
+            project_id TEXT NOT NULL,
+            blob_hash TEXT NOT NULL,
+            title TEXT NOT NULL,
+            note TEXT,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_knowledge_revisions_entry ON knowledge_revisions(entry_id, created_at)",
+        [],
+    )?;
+
+    // 一次 AI 生成用到了哪些知识条目版本，便于排查提示词
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS knowledge_generation_runs (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            chapter_id TEXT,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS knowledge_run_revisions (
+            run_id TEXT NOT NULL,
+            revision_id TEXT NOT NULL,
+            entry_id TEXT NOT NULL,
+            PRIMARY KEY (run_id, revision_id),
+            FOREIGN KEY (run_id) REFERENCES knowledge_generation_runs(id) ON DELETE CASCADE,
+            FOREIGN KEY (revision_id) REFERENCES knowledge_revisions(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_knowledge_run_revisions_entry ON knowledge_run_revisions(entry_id)",
+        [],
+    )?;
+
+    // 起名文化，rules_json 为 NamingRules；角色和地点通过 naming_culture_id 归属某个文化
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS naming_cultures (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            description TEXT,
+            rules_json TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 货币，value 为一单位折合多少基准单位
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS currencies (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            value REAL NOT NULL,
+            description TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 物价参考
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS price_references (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            amount REAL NOT NULL,
+            currency_id TEXT NOT NULL,
+            note TEXT,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
+            FOREIGN KEY (currency_id) REFERENCES currencies(id)
+        )",
+        [],
+    )?;
+
+    // 角色在某章时的身家，chapter_id 为空表示故事开始时
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS wealth_snapshots (
+            id TEXT PRIMARY KEY,
+            character_id TEXT NOT NULL,
+            chapter_id TEXT,
+            amount REAL NOT NULL,
+            currency_id TEXT NOT NULL,
+            note TEXT,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (character_id) REFERENCES characters(id) ON DELETE CASCADE,
+            FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE,
+            FOREIGN KEY (currency_id) REFERENCES currencies(id)
+        )",
+        [],
+    )?;
+
+    // 项目批量分析的结果，正文哈希变化时重新分析
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS analysis_results (
+            chapter_id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            content_hash TEXT NOT NULL,
+            results_json TEXT NOT NULL,
+            computed_at TEXT NOT NULL,
+            FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    let project_column = |table: &str| if table == "projects" { "OLD.id" } else { "OLD.project_id" };
+    let sync_tables = crate::cloud_sync::engine::SYNC_TABLES.iter().map(|(table, _)| table);
+    for table in std::iter::once(&"projects").chain(sync_tables) {
+        conn.execute(
+            &format!(
+                "CREATE TRIGGER IF NOT EXISTS sync_tombstone_{table} AFTER DELETE ON {table}
+                 BEGIN
+                     INSERT OR REPLACE INTO sync_tombstones (table_name, row_id, project_id, deleted_at)
+                     VALUES ('{table}', OLD.id, {project}, strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now'));
+                 END",
+                table = table,
+                project = project_column(table),
+            ),
+            [],
+        )?;
+    }
+
     // 数据库迁移：为 characters 表添加新列（如果不存在）
     let migrations = vec![
         "ALTER TABLE characters ADD COLUMN role_type TEXT",
@@ -848,6 +1964,12 @@ pub fn init_database(db_path: &Path) -> SqlResult<()> {
         "ALTER TABLE characters ADD COLUMN mbti TEXT",
         "ALTER TABLE characters ADD COLUMN enneagram TEXT",
         "ALTER TABLE characters ADD COLUMN items TEXT",
+        "ALTER TABLE characters ADD COLUMN merged_into TEXT",
+        "ALTER TABLE character_aliases ADD COLUMN kind TEXT NOT NULL DEFAULT 'nickname'",
+        "ALTER TABLE character_timeline_events ADD COLUMN story_date TEXT",
+        "ALTER TABLE worldview_timeline_events ADD COLUMN story_date TEXT",
+        "ALTER TABLE characters ADD COLUMN naming_culture_id TEXT",
+        "ALTER TABLE locations ADD COLUMN naming_culture_id TEXT",
     ];
 
     for migration in migrations {
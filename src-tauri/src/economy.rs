@@ -0,0 +1,457 @@
+use crate::character_presence::aliases_by_character;
+use crate::status_check::acts_in;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 表示花钱购买的词，句子里同时出现人物和价目表中的东西时才检查
+const PURCHASE_WORDS: &[&str] = &["买", "购", "置办", "盘下", "拍下", "付了", "花了", "掏出"];
+
+/// 货币，`value` 为一单位折合多少基准单位（基准货币的 value 为 1），如 文=1、两=1000
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Currency {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub value: f64,
+    pub description: Option<String>,
+}
+
+/// 物价参考，如“一座庄园 5000 两”
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceEntry {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub amount: f64,
+    pub currency_id: String,
+    pub currency_name: String,
+    pub note: Option<String>,
+}
+
+/// 角色在某章时的身家；`chapter_id` 为空表示故事开始时
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WealthSnapshot {
+    pub id: String,
+    pub character_id: String,
+    pub character_name: String,
+    pub chapter_id: Option<String>,
+    pub chapter_title: Option<String>,
+    pub amount: f64,
+    pub currency_id: String,
+    pub currency_name: String,
+    pub note: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveCurrencyRequest {
+    pub id: Option<String>,
+    pub project_id: String,
+    pub name: String,
+    pub value: f64,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavePriceRequest {
+    pub id: Option<String>,
+    pub project_id: String,
+    pub name: String,
+    pub amount: f64,
+    pub currency_id: String,
+    pub note: Option<String>,
+}
+
+/// 角色买了身家负担不起的东西
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpendingIssue {
+    pub character_id: String,
+    pub character_name: String,
+    pub chapter_id: String,
+    pub chapter_title: String,
+    pub price_name: String,
+    pub price: String,
+    pub wealth: String,
+    pub excerpt: String,
+    /// 价格超过身家十倍为 high，否则为 medium
+    pub severity: String,
+}
+
+pub fn list_currencies(conn: &Connection, project_id: &str) -> Result<Vec<Currency>, String> {
+    conn.prepare("SELECT id, project_id, name, value, description FROM currencies WHERE project_id = ? ORDER BY value DESC, name")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| {
+            Ok(Currency { id: row.get(0)?, project_id: row.get(1)?, name: row.get(2)?, value: row.get(3)?, description: row.get(4)? })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+fn check_amount(amount: f64, label: &str) -> Result<(), String> {
+    if !amount.is_finite() || amount < 0.0 {
+        return Err(format!("{}不能为负数", label));
+    }
+    Ok(())
+}
+
+pub fn save_currency(conn: &Connection, request: &SaveCurrencyRequest) -> Result<Currency, String> {
+    let name = request.name.trim();
+    if name.is_empty() {
+        return Err("货币名称不能为空".to_string());
+    }
+    if !request.value.is_finite() || request.value <= 0.0 {
+        return Err("货币折算值必须大于 0".to_string());
+    }
+    let now = Utc::now().to_rfc3339();
+    let id = match &request.id {
+        Some(id) => {
+            let updated = conn
+                .execute(
+                    "UPDATE currencies SET name = ?, value = ?, description = ?, updated_at = ? WHERE id = ?",
+                    params![name, request.value, request.description, now, id],
+                )
+                .map_err(|e| e.to_string())?;
+            if updated == 0 {
+                return Err("货币不存在".to_string());
+            }
+            id.clone()
+        }
+        None => {
+            let id = Uuid::new_v4().to_string();
+            conn.execute(
+                "INSERT INTO currencies (id, project_id, name, value, description, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                params![id, request.project_id, name, request.value, request.description, now, now],
+            )
+            .map_err(|e| e.to_string())?;
+            id
+        }
+    };
+    list_currencies(conn, &request.project_id)?.into_iter().find(|c| c.id == id).ok_or_else(|| "货币不存在".to_string())
+}
+
+/// 删除货币；仍有物价或身家记录使用它时拒绝删除
+pub fn delete_currency(conn: &Connection, id: &str) -> Result<(), String> {
+    let used: i64 = conn
+        .query_row(
+            "SELECT (SELECT COUNT(*) FROM price_references WHERE currency_id = ?1) + (SELECT COUNT(*) FROM wealth_snapshots WHERE currency_id = ?1)",
+            params![id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if used > 0 {
+        return Err(format!("还有 {} 条物价或身家记录使用这种货币", used));
+    }
+    conn.execute("DELETE FROM currencies WHERE id = ?", params![id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn list_prices(conn: &Connection, project_id: &str) -> Result<Vec<PriceEntry>, String> {
+    conn.prepare(
+        "SELECT p.id, p.project_id, p.name, p.amount, p.currency_id, c.name, p.note FROM price_references p
+         JOIN currencies c ON c.id = p.currency_id WHERE p.project_id = ? ORDER BY p.amount * c.value, p.name",
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![project_id], |row| {
+        Ok(PriceEntry {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            name: row.get(2)?,
+            amount: row.get(3)?,
+            currency_id: row.get(4)?,
+            currency_name: row.get(5)?,
+            note: row.get(6)?,
+        })
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+pub fn save_price(conn: &Connection, request: &SavePriceRequest) -> Result<PriceEntry, String> {
+    let name = request.name.trim();
+    if name.is_empty() {
+        return Err("物品名称不能为空".to_string());
+    }
+    check_amount(request.amount, "价格")?;
+    let id = match &request.id {
+        Some(id) => {
+            let updated = conn
+                .execute(
+                    "UPDATE price_references SET name = ?, amount = ?, currency_id = ?, note = ? WHERE id = ?",
+                    params![name, request.amount, request.currency_id, request.note, id],
+                )
+                .map_err(|e| e.to_string())?;
+            if updated == 0 {
+                return Err("物价条目不存在".to_string());
+            }
+            id.clone()
+        }
+        None => {
+            let id = Uuid::new_v4().to_string();
+            conn.execute(
+                "INSERT INTO price_references (id, project_id, name, amount, currency_id, note, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                params![id, request.project_id, name, request.amount, request.currency_id, request.note, Utc::now().to_rfc3339()],
+            )
+            .map_err(|e| e.to_string())?;
+            id
+        }
+    };
+    list_prices(conn, &request.project_id)?.into_iter().find(|p| p.id == id).ok_or_else(|| "物价条目不存在".to_string())
+}
+
+pub fn delete_price(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM price_references WHERE id = ?", params![id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+const WEALTH_QUERY: &str = "SELECT w.id, w.character_id, ch.name, w.chapter_id, c.title, w.amount, w.currency_id, cur.name, w.note, w.created_at
+     FROM wealth_snapshots w
+     JOIN characters ch ON ch.id = w.character_id
+     JOIN currencies cur ON cur.id = w.currency_id
+     LEFT JOIN chapters c ON c.id = w.chapter_id";
+
+fn wealth_from_row(row: &rusqlite::Row) -> rusqlite::Result<WealthSnapshot> {
+    Ok(WealthSnapshot {
+        id: row.get(0)?,
+        character_id: row.get(1)?,
+        character_name: row.get(2)?,
+        chapter_id: row.get(3)?,
+        chapter_title: row.get(4)?,
+        amount: row.get(5)?,
+        currency_id: row.get(6)?,
+        currency_name: row.get(7)?,
+        note: row.get(8)?,
+        created_at: row.get(9)?,
+    })
+}
+
+/// 记录角色在某章时的身家，同一章的旧记录被替换
+pub fn record_wealth(
+    conn: &Connection,
+    character_id: &str,
+    chapter_id: Option<&str>,
+    amount: f64,
+    currency_id: &str,
+    note: Option<&str>,
+) -> Result<WealthSnapshot, String> {
+    check_amount(amount, "身家")?;
+    conn.execute("DELETE FROM wealth_snapshots WHERE character_id = ? AND chapter_id IS ?", params![character_id, chapter_id])
+        .map_err(|e| e.to_string())?;
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO wealth_snapshots (id, character_id, chapter_id, amount, currency_id, note, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        params![id, character_id, chapter_id, amount, currency_id, note, Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.query_row(&format!("{} WHERE w.id = ?", WEALTH_QUERY), params![id], wealth_from_row).map_err(|e| e.to_string())
+}
+
+pub fn delete_wealth(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM wealth_snapshots WHERE id = ?", params![id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 角色的身家变化，按章节顺序，故事开始时的记录在前
+pub fn character_wealth(conn: &Connection, character_id: &str) -> Result<Vec<WealthSnapshot>, String> {
+    conn.prepare(&format!("{} WHERE w.character_id = ? ORDER BY c.sort_order IS NOT NULL, c.sort_order", WEALTH_QUERY))
+        .map_err(|e| e.to_string())?
+        .query_map(params![character_id], wealth_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+fn to_base(currencies: &[Currency], amount: f64, currency_id: &str) -> f64 {
+    amount * currencies.iter().find(|c| c.id == currency_id).map(|c| c.value).unwrap_or(1.0)
+}
+
+fn format_amount(amount: f64, currency: &str) -> String {
+    let amount = (amount * 100.0).round() / 100.0;
+    format!("{}{}", amount, currency)
+}
+
+/// 到 `sort_order` 这一章为止（含该章）各角色最近的身家及其折合的基准单位，为空时取全部记录
+fn wealth_as_of(conn: &Connection, project_id: &str, sort_order: Option<i64>, currencies: &[Currency]) -> Result<Vec<(WealthSnapshot, f64)>, String> {
+    let snapshots: Vec<WealthSnapshot> = conn
+        .prepare(&format!(
+            "{} WHERE ch.project_id = ?1 AND ch.merged_into IS NULL AND (w.chapter_id IS NULL OR c.sort_order <= ?2)
+             ORDER BY c.sort_order IS NOT NULL, c.sort_order",
+            WEALTH_QUERY
+        ))
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id, sort_order.unwrap_or(i64::MAX)], wealth_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut latest: Vec<(WealthSnapshot, f64)> = Vec::new();
+    for snapshot in snapshots {
+        let base = to_base(currencies, snapshot.amount, &snapshot.currency_id);
+        latest.retain(|(s, _)| s.character_id != snapshot.character_id);
+        latest.push((snapshot, base));
+    }
+    Ok(latest)
+}
+
+/// 找出角色买下远超自己身家的东西的句子
+pub fn check_project(conn: &Connection, project_id: &str) -> Result<Vec<SpendingIssue>, String> {
+    let currencies = list_currencies(conn, project_id)?;
+    let prices = list_prices(conn, project_id)?;
+    if prices.is_empty() {
+        return Ok(Vec::new());
+    }
+    let chapters: Vec<(String, String, String, i64)> = conn
+        .prepare("SELECT id, title, COALESCE(content, ''), sort_order FROM chapters WHERE project_id = ? ORDER BY sort_order, created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let aliases = aliases_by_character(conn, project_id)?;
+
+    let mut issues = Vec::new();
+    for (chapter_id, chapter_title, content, sort_order) in &chapters {
+        // 本章记录的身家可能是进账之后的，取本章之前和本章记录中较多的一个
+        let before = wealth_as_of(conn, project_id, Some(sort_order - 1), &currencies)?;
+        let here = wealth_as_of(conn, project_id, Some(*sort_order), &currencies)?;
+        let mut wealth: Vec<(WealthSnapshot, f64, Vec<String>)> = Vec::new();
+        for (snapshot, base) in here {
+            let (snapshot, base) = match before.iter().find(|(b, _)| b.character_id == snapshot.character_id) {
+                Some((b, b_base)) if *b_base > base => (b.clone(), *b_base),
+                _ => (snapshot, base),
+            };
+            let mut terms = vec![snapshot.character_name.clone()];
+            terms.extend(aliases.get(&snapshot.character_id).cloned().unwrap_or_default());
+            wealth.push((snapshot, base, terms));
+        }
+        if wealth.is_empty() {
+            continue;
+        }
+        for sentence in content.split(['。', '！', '？', '；', '\n']).map(str::trim) {
+            if !PURCHASE_WORDS.iter().any(|w| sentence.contains(w)) {
+                continue;
+            }
+            // 句中出现多个价目时按最贵的算
+            let Some((price, price_base)) = prices
+                .iter()
+                .filter(|p| sentence.contains(p.name.as_str()))
+                .map(|p| (p, to_base(&currencies, p.amount, &p.currency_id)))
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+            else {
+                continue;
+            };
+            let Some((snapshot, base, _)) = wealth.iter().find(|(_, _, terms)| acts_in(sentence, terms)) else { continue };
+            if price_base <= *base {
+                continue;
+            }
+            if issues.iter().any(|i: &SpendingIssue| i.chapter_id == *chapter_id && i.character_id == snapshot.character_id && i.price_name == price.name) {
+                continue;
+            }
+            issues.push(SpendingIssue {
+                character_id: snapshot.character_id.clone(),
+                character_name: snapshot.character_name.clone(),
+                chapter_id: chapter_id.clone(),
+                chapter_title: chapter_title.clone(),
+                price_name: price.name.clone(),
+                price: format_amount(price.amount, &price.currency_name),
+                wealth: format_amount(snapshot.amount, &snapshot.currency_name),
+                excerpt: sentence.to_string(),
+                severity: if price_base > base * 10.0 { "high" } else { "medium" }.to_string(),
+            });
+        }
+    }
+    Ok(issues)
+}
+
+/// 续写提示词中的货币、物价和人物身家；`chapter_id` 为空时取最新的身家
+pub fn prompt_section(conn: &Connection, project_id: &str, chapter_id: Option<&str>) -> Result<Option<String>, String> {
+    let currencies = list_currencies(conn, project_id)?;
+    if currencies.is_empty() {
+        return Ok(None);
+    }
+    let mut lines = vec!["【货币与物价】".to_string()];
+    if currencies.len() > 1 {
+        let smallest = currencies.last().unwrap();
+        let rates: Vec<String> = currencies[..currencies.len() - 1]
+            .iter()
+            .map(|c| format!("1{} = {}", c.name, format_amount(c.value / smallest.value, &smallest.name)))
+            .collect();
+        lines.push(format!("货币：{}", rates.join("；")));
+    }
+    for price in list_prices(conn, project_id)?.iter().take(30) {
+        let mut line = format!("- {}：{}", price.name, format_amount(price.amount, &price.currency_name));
+        if let Some(note) = price.note.as_deref().filter(|n| !n.trim().is_empty()) {
+            line.push_str(&format!("（{}）", note.trim()));
+        }
+        lines.push(line);
+    }
+    let sort_order: Option<i64> = match chapter_id {
+        Some(id) => conn
+            .query_row("SELECT sort_order FROM chapters WHERE id = ?", params![id], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?,
+        None => None,
+    };
+    let wealth = wealth_as_of(conn, project_id, sort_order, &currencies)?;
+    if !wealth.is_empty() {
+        lines.push("人物身家：".to_string());
+        for (snapshot, _) in wealth.iter().take(20) {
+            let mut line = format!("- {}：{}", snapshot.character_name, format_amount(snapshot.amount, &snapshot.currency_name));
+            if let Some(note) = snapshot.note.as_deref().filter(|n| !n.trim().is_empty()) {
+                line.push_str(&format!("（{}）", note.trim()));
+            }
+            lines.push(line);
+        }
+    }
+    Ok(Some(lines.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wealth_and_spending_check() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES
+                 ('r1', 'p1', '林舟', 't0', 't0'), ('r2', 'p1', '沈青', 't0', 't0');
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '林舟买了两个馒头。林舟当场买下城东的庄园。沈青买下一座庄园。', 1, 't0', 't0'),
+                 ('c2', 'p1', '第二章', '林舟买下城东的庄园。', 2, 't0', 't0');",
+        )
+        .unwrap();
+        let currency = |name: &str, value: f64| {
+            save_currency(&conn, &SaveCurrencyRequest { id: None, project_id: "p1".to_string(), name: name.to_string(), value, description: None })
+                .unwrap()
+        };
+        let wen = currency("文", 1.0);
+        let liang = currency("两", 1000.0);
+        let price = |name: &str, amount: f64, currency: &Currency| {
+            save_price(
+                &conn,
+                &SavePriceRequest { id: None, project_id: "p1".to_string(), name: name.to_string(), amount, currency_id: currency.id.clone(), note: None },
+            )
+            .unwrap()
+        };
+        price("馒头", 2.0, &wen);
+        price("庄园", 5000.0, &liang);
+        record_wealth(&conn, "r1", None, 30.0, &wen.id, Some("身无分文的乞丐")).unwrap();
+        record_wealth(&conn, "r1", Some("c2"), 8000.0, &liang.id, Some("继承遗产")).unwrap();
+        assert!(delete_currency(&conn, &wen.id).is_err());
+
+        let issues = check_project(&conn, "p1").unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].chapter_id.as_str(), issues[0].severity.as_str()), ("c1", "high"));
+        assert_eq!((issues[0].price.as_str(), issues[0].wealth.as_str()), ("5000两", "30文"));
+
+        let section = prompt_section(&conn, "p1", Some("c1")).unwrap().unwrap();
+        assert!(section.contains("1两 = 1000文"));
+        assert!(section.contains("- 庄园：5000两"));
+        assert!(section.contains("- 林舟：30文（身无分文的乞丐）"));
+        assert!(prompt_section(&conn, "p1", None).unwrap().unwrap().contains("- 林舟：8000两"));
+        assert_eq!(character_wealth(&conn, "r1").unwrap().len(), 2);
+    }
+}
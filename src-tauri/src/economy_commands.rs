@@ -0,0 +1,98 @@
+use crate::economy::{self, SaveCurrencyRequest, SavePriceRequest};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+#[tauri::command]
+pub async fn save_currency(app: AppHandle, request: SaveCurrencyRequest) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let currency = economy::save_currency(&conn, &request)?;
+    serde_json::to_string(&currency).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_currencies(app: AppHandle, project_id: String) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let currencies = economy::list_currencies(&conn, &project_id)?;
+    serde_json::to_string(&currencies).map_err(|e| e.to_string())
+}
+
+/// 删除货币，仍被物价或身家记录使用时失败
+#[tauri::command]
+pub async fn delete_currency(app: AppHandle, id: String) -> Result<(), String> {
+    let conn = open_connection(&app)?;
+    economy::delete_currency(&conn, &id)
+}
+
+#[tauri::command]
+pub async fn save_price_reference(app: AppHandle, request: SavePriceRequest) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let price = economy::save_price(&conn, &request)?;
+    serde_json::to_string(&price).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_price_references(app: AppHandle, project_id: String) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let prices = economy::list_prices(&conn, &project_id)?;
+    serde_json::to_string(&prices).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_price_reference(app: AppHandle, id: String) -> Result<(), String> {
+    let conn = open_connection(&app)?;
+    economy::delete_price(&conn, &id)
+}
+
+/// 记录角色在某章时的身家，`chapter_id` 为空表示故事开始时
+#[tauri::command]
+pub async fn record_character_wealth(
+    app: AppHandle,
+    character_id: String,
+    chapter_id: Option<String>,
+    amount: f64,
+    currency_id: String,
+    note: Option<String>,
+) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let snapshot = economy::record_wealth(&conn, &character_id, chapter_id.as_deref(), amount, &currency_id, note.as_deref())?;
+    serde_json::to_string(&snapshot).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_character_wealth(app: AppHandle, id: String) -> Result<(), String> {
+    let conn = open_connection(&app)?;
+    economy::delete_wealth(&conn, &id)
+}
+
+#[tauri::command]
+pub async fn get_character_wealth(app: AppHandle, character_id: String) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let history = economy::character_wealth(&conn, &character_id)?;
+    serde_json::to_string(&history).map_err(|e| e.to_string())
+}
+
+/// 找出角色买下远超身家的东西的地方
+#[tauri::command]
+pub async fn check_spending_consistency(app: AppHandle, project_id: String) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let issues = economy::check_project(&conn, &project_id)?;
+    serde_json::to_string(&issues).map_err(|e| e.to_string())
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
+
+fn open_connection(app: &AppHandle) -> Result<rusqlite::Connection, String> {
+    crate::database::get_connection(&get_db_path(app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))
+}
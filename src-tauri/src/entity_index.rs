@@ -0,0 +1,353 @@
+use crate::tokenizer;
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// 规则识别：已有角色、别名、世界观条目，以及分词器标注的人名、地名
+pub const SOURCE_RULE: &str = "rule";
+/// AI 从正文中识别出的名字
+pub const SOURCE_AI: &str = "ai";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    Person,
+    Place,
+    Item,
+}
+
+impl EntityKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntityKind::Person => "person",
+            EntityKind::Place => "place",
+            EntityKind::Item => "item",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "person" => Some(EntityKind::Person),
+            "place" => Some(EntityKind::Place),
+            "item" => Some(EntityKind::Item),
+            _ => None,
+        }
+    }
+
+    /// 按世界观分类推断条目是地点还是物品，其他分类不进入索引
+    fn from_world_view_category(category: &str) -> Option<Self> {
+        let category = category.to_lowercase();
+        if ["地", "城", "国", "域", "place", "location", "geography"].iter().any(|k| category.contains(k)) {
+            Some(EntityKind::Place)
+        } else if ["物", "道具", "法宝", "武器", "装备", "item", "artifact", "weapon"].iter().any(|k| category.contains(k)) {
+            Some(EntityKind::Item)
+        } else {
+            None
+        }
+    }
+}
+
+/// 实体在章节中的一次出现，`start`/`end` 是字符偏移
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityMention {
+    pub id: String,
+    pub project_id: String,
+    pub chapter_id: String,
+    /// 规范名：别名出现时记为角色名
+    pub name: String,
+    pub text: String,
+    pub kind: EntityKind,
+    pub source: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterMentions {
+    pub chapter_id: String,
+    pub count: usize,
+    pub first_offset: usize,
+}
+
+/// 实体索引中的一项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityEntry {
+    pub name: String,
+    pub kind: EntityKind,
+    /// 是否对应已有的角色或世界观条目；未登记的名字可能需要补充设定
+    pub known: bool,
+    pub total: usize,
+    pub chapters: Vec<ChapterMentions>,
+}
+
+struct Term {
+    text: String,
+    name: String,
+    kind: EntityKind,
+    source: &'static str,
+}
+
+fn known_terms(conn: &Connection, project_id: &str) -> Result<Vec<Term>, String> {
+    let mut terms = Vec::new();
+    let mut stmt = conn
+        .prepare(
+            "SELECT c.name, c.name FROM characters c WHERE c.project_id = ?1 AND c.merged_into IS NULL
+             UNION ALL
+             SELECT a.alias, c.name FROM character_aliases a JOIN characters c ON c.id = a.character_id WHERE c.project_id = ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![project_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        let (text, name) = row.map_err(|e| e.to_string())?;
+        terms.push(Term { text, name, kind: EntityKind::Person, source: SOURCE_RULE });
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT title, category FROM world_views WHERE project_id = ?")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![project_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        let (title, category) = row.map_err(|e| e.to_string())?;
+        if let Some(kind) = EntityKind::from_world_view_category(&category) {
+            terms.push(Term { text: title.clone(), name: title, kind, source: SOURCE_RULE });
+        }
+    }
+    terms.retain(|t| !t.text.trim().is_empty());
+    Ok(terms)
+}
+
+/// `terms` 在文本中不重叠的出现位置（字节区间和词的下标），较长的词优先
+pub(crate) fn locate_terms(text: &str, terms: &[&str]) -> Vec<(usize, usize, usize)> {
+    let mut order: Vec<usize> = (0..terms.len()).filter(|&i| !terms[i].is_empty()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(terms[i].len()));
+    let mut used = vec![false; text.len()];
+    let mut found = Vec::new();
+    for i in order {
+        for (start, _) in text.match_indices(terms[i]) {
+            let end = start + terms[i].len();
+            if used[start..end].iter().all(|u| !u) {
+                used[start..end].iter_mut().for_each(|u| *u = true);
+                found.push((start, end, i));
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+/// 重新索引一章。`ai_entities` 为 AI 识别出的名字；为 `None` 时沿用这一章上次 AI 识别的结果
+pub fn index_chapter(conn: &Connection, chapter_id: &str, ai_entities: Option<&[(String, EntityKind)]>) -> Result<usize, String> {
+    let (project_id, content): (String, String) = conn
+        .query_row("SELECT project_id, COALESCE(content, '') FROM chapters WHERE id = ?", params![chapter_id], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .map_err(|_| "章节不存在".to_string())?;
+
+    let mut terms = known_terms(conn, &project_id)?;
+    let ai_entities = match ai_entities {
+        Some(entities) => entities.to_vec(),
+        None => {
+            let mut stmt = conn
+                .prepare("SELECT DISTINCT name, kind FROM entity_mentions WHERE chapter_id = ? AND source = ?")
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![chapter_id, SOURCE_AI], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+                .map_err(|e| e.to_string())?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?;
+            rows.into_iter().filter_map(|(name, kind)| EntityKind::parse(&kind).map(|k| (name, k))).collect()
+        }
+    };
+    for (name, kind) in ai_entities {
+        let name = name.trim().to_string();
+        if !name.is_empty() && !terms.iter().any(|t| t.text == name) {
+            terms.push(Term { text: name.clone(), name, kind, source: SOURCE_AI });
+        }
+    }
+
+    // 字节偏移到字符偏移
+    let mut char_offsets = vec![0; content.len() + 1];
+    for (chars, (byte, c)) in content.char_indices().enumerate() {
+        char_offsets[byte..byte + c.len_utf8()].iter_mut().for_each(|o| *o = chars);
+        char_offsets[byte + c.len_utf8()] = chars + 1;
+    }
+
+    let texts: Vec<&str> = terms.iter().map(|t| t.text.as_str()).collect();
+    let mut mentions: Vec<(usize, usize, String, String, EntityKind, &str)> = locate_terms(&content, &texts)
+        .into_iter()
+        .map(|(start, end, i)| {
+            let term = &terms[i];
+            (char_offsets[start], char_offsets[end], term.name.clone(), term.text.clone(), term.kind, term.source)
+        })
+        .collect();
+
+    let covered: HashSet<usize> = mentions.iter().flat_map(|m| m.0..m.1).collect();
+    let mut offset = 0;
+    for (word, tag) in tokenizer::tag(&content) {
+        let len = word.chars().count();
+        let kind = match tag.as_str() {
+            "nr" => Some(EntityKind::Person),
+            "ns" => Some(EntityKind::Place),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            if len > 1 && !(offset..offset + len).any(|o| covered.contains(&o)) {
+                mentions.push((offset, offset + len, word.clone(), word, kind, SOURCE_RULE));
+            }
+        }
+        offset += len;
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM entity_mentions WHERE chapter_id = ?", params![chapter_id]).map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    for (start, end, name, text, kind, source) in &mentions {
+        tx.execute(
+            "INSERT INTO entity_mentions (id, project_id, chapter_id, name, text, kind, source, start_offset, end_offset, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![uuid::Uuid::new_v4().to_string(), project_id, chapter_id, name, text, kind.as_str(), source, *start as i64, *end as i64, now],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(mentions.len())
+}
+
+fn read_mention(row: &rusqlite::Row) -> rusqlite::Result<EntityMention> {
+    let kind: String = row.get(5)?;
+    Ok(EntityMention {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        chapter_id: row.get(2)?,
+        name: row.get(3)?,
+        text: row.get(4)?,
+        kind: EntityKind::parse(&kind).unwrap_or(EntityKind::Person),
+        source: row.get(6)?,
+        start: row.get::<_, i64>(7)? as usize,
+        end: row.get::<_, i64>(8)? as usize,
+    })
+}
+
+/// 项目的实体索引，按出现次数从多到少排序
+pub fn entity_index(conn: &Connection, project_id: &str) -> Result<Vec<EntityEntry>, String> {
+    let known: HashSet<String> = known_terms(conn, project_id)?.into_iter().map(|t| t.name).collect();
+    let mut stmt = conn
+        .prepare(
+            "SELECT m.name, m.kind, m.chapter_id, COUNT(*), MIN(m.start_offset)
+             FROM entity_mentions m JOIN chapters c ON c.id = m.chapter_id
+             WHERE m.project_id = ? GROUP BY m.name, m.kind, m.chapter_id ORDER BY c.sort_order, c.created_at",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![project_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, i64>(3)?, row.get::<_, i64>(4)?))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut entries: BTreeMap<(String, String), EntityEntry> = BTreeMap::new();
+    for row in rows {
+        let (name, kind, chapter_id, count, first_offset) = row.map_err(|e| e.to_string())?;
+        let Some(entity_kind) = EntityKind::parse(&kind) else { continue };
+        let entry = entries.entry((name.clone(), kind)).or_insert_with(|| EntityEntry {
+            known: known.contains(&name),
+            name,
+            kind: entity_kind,
+            total: 0,
+            chapters: Vec::new(),
+        });
+        entry.total += count as usize;
+        entry.chapters.push(ChapterMentions { chapter_id, count: count as usize, first_offset: first_offset as usize });
+    }
+    let mut entries: Vec<EntityEntry> = entries.into_values().collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.total));
+    Ok(entries)
+}
+
+/// 实体在项目中的全部出现位置，按章节顺序
+pub fn find_mentions(conn: &Connection, project_id: &str, name: &str) -> Result<Vec<EntityMention>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT m.id, m.project_id, m.chapter_id, m.name, m.text, m.kind, m.source, m.start_offset, m.end_offset
+             FROM entity_mentions m JOIN chapters c ON c.id = m.chapter_id
+             WHERE m.project_id = ? AND m.name = ? ORDER BY c.sort_order, c.created_at, m.start_offset",
+        )
+        .map_err(|e| e.to_string())?;
+    let mentions = stmt
+        .query_map(params![project_id, name], read_mention)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(mentions)
+}
+
+/// 解析 AI 返回的 `{"people": [], "places": [], "items": []}`
+pub fn parse_ai_entities(response: &str) -> Vec<(String, EntityKind)> {
+    let start = response.find('{').unwrap_or(0);
+    let end = response.rfind('}').map(|i| i + 1).unwrap_or(response.len());
+    let parsed: serde_json::Value = serde_json::from_str(&response[start..end]).unwrap_or_default();
+    let mut entities: HashMap<String, EntityKind> = HashMap::new();
+    for (key, kind) in [("people", EntityKind::Person), ("places", EntityKind::Place), ("items", EntityKind::Item)] {
+        for name in parsed[key].as_array().into_iter().flatten().filter_map(|v| v.as_str()) {
+            let name = name.trim();
+            if name.chars().count() > 1 {
+                entities.entry(name.to_string()).or_insert(kind);
+            }
+        }
+    }
+    let mut entities: Vec<(String, EntityKind)> = entities.into_iter().collect();
+    entities.sort();
+    entities
+}
+
+/// 让 AI 找出正文中的人名、地名和物品名
+pub async fn extract_with_ai(service: &crate::ai::AIService, model_id: &str, content: &str) -> Result<Vec<(String, EntityKind)>, String> {
+    let system_prompt = "你是一位专业的小说编辑，请找出文本中出现的专有名词。只返回 JSON 对象，不要包含markdown代码块标记。";
+    let user_prompt = format!(
+        "请列出以下小说片段中出现的人物名、地名和重要物品名（法宝、武器、信物等），返回格式：\
+         {{\"people\": [\"人名\"], \"places\": [\"地名\"], \"items\": [\"物品名\"]}}。名字必须与原文完全一致。\n\n{}",
+        content.chars().take(6000).collect::<String>()
+    );
+    let response = service.complete(model_id, system_prompt, &user_prompt).await?;
+    Ok(parse_ai_entities(&response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_chapter_with_known_and_ai_entities() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES ('r1', 'p1', '林舟', 't0', 't0');
+             INSERT INTO character_aliases (character_id, alias) VALUES ('r1', '小舟');
+             INSERT INTO world_views (id, project_id, category, title, content, created_at, updated_at)
+                 VALUES ('w1', 'p1', '地理', '青云城', '', 't0', 't0');
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at)
+                 VALUES ('c1', 'p1', '第一章', '小舟回到青云城，腰间挂着玄铁令。林舟笑了。', 1, 't0', 't0');",
+        )
+        .unwrap();
+
+        let ai = parse_ai_entities("```json\n{\"people\": [\"林舟\"], \"items\": [\"玄铁令\", \"令\"]}\n```");
+        assert_eq!(ai, vec![("林舟".to_string(), EntityKind::Person), ("玄铁令".to_string(), EntityKind::Item)]);
+        index_chapter(&conn, "c1", Some(&ai)).unwrap();
+
+        let lin = find_mentions(&conn, "p1", "林舟").unwrap();
+        assert_eq!(lin.iter().map(|m| (m.text.as_str(), m.start, m.end)).collect::<Vec<_>>(), vec![("小舟", 0, 2), ("林舟", 16, 18)]);
+        let token = find_mentions(&conn, "p1", "玄铁令").unwrap();
+        assert_eq!((token[0].source.as_str(), token[0].kind, token[0].start), (SOURCE_AI, EntityKind::Item, 12));
+
+        // 保存章节后重新索引时沿用之前 AI 识别的名字
+        conn.execute("UPDATE chapters SET content = '玄铁令不见了。' WHERE id = 'c1'", []).unwrap();
+        index_chapter(&conn, "c1", None).unwrap();
+        let index = entity_index(&conn, "p1").unwrap();
+        let token = index.iter().find(|e| e.name == "玄铁令").unwrap();
+        assert_eq!((token.total, token.known, token.chapters[0].first_offset), (1, false, 0));
+        assert!(index.iter().all(|e| e.name != "林舟"));
+    }
+}
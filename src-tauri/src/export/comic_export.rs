@@ -0,0 +1,268 @@
+use crate::commands::{ComicPanel, ComicResult};
+use anyhow::{Context, Result};
+use base64::Engine;
+use genpdf::{elements, style, Alignment, Element};
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+use std::io::{Cursor, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+
+/// A4 页面，150 DPI
+const PAGE_WIDTH: u32 = 1240;
+const PAGE_HEIGHT: u32 = 1754;
+const PAGE_MARGIN: u32 = 60;
+const PANEL_GUTTER: u32 = 24;
+const BORDER_WIDTH: u32 = 4;
+/// 一页最多排的分格数，再多每格会小到看不清，多出的分格接着排到下一页
+const MAX_PANELS_PER_PAGE: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComicExportFormat {
+    Cbz,
+    Pdf,
+}
+
+impl ComicExportFormat {
+    pub fn from_name(format_str: &str) -> Option<Self> {
+        match format_str.to_lowercase().as_str() {
+            "cbz" => Some(ComicExportFormat::Cbz),
+            "pdf" => Some(ComicExportFormat::Pdf),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &str {
+        match self {
+            ComicExportFormat::Cbz => ".cbz",
+            ComicExportFormat::Pdf => ".pdf",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ComicCredits {
+    pub writer: Option<String>,
+    pub artist: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// 导出为 CBZ 漫画包：每页一张 PNG，附带 ComicInfo.xml 记录页码和署名
+pub fn export_comic_as_cbz(
+    comic: &ComicResult,
+    credits: &ComicCredits,
+    output_path: &Path,
+) -> Result<()> {
+    let file = std::fs::File::create(output_path)
+        .with_context(|| format!("无法创建导出文件: {:?}", output_path))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+
+    let pages = page_layouts(comic);
+    for (index, panels) in pages.iter().enumerate() {
+        let png = encode_png(&compose_page(panels))?;
+        zip.start_file(format!("page_{:03}.png", index + 1), options)?;
+        zip.write_all(&png)?;
+    }
+
+    zip.start_file("ComicInfo.xml", options)?;
+    zip.write_all(comic_info_xml(comic, credits, pages.len()).as_bytes())?;
+
+    zip.finish()
+        .with_context(|| format!("无法保存文件: {:?}", output_path))?;
+
+    Ok(())
+}
+
+/// 导出为可打印的 PDF：封面、逐页分镜（页眉带页码）以及结尾的署名页
+pub fn export_comic_as_pdf(
+    comic: &ComicResult,
+    credits: &ComicCredits,
+    output_path: &Path,
+) -> Result<()> {
+    let font_family = genpdf::fonts::from_files(
+        "/System/Library/Fonts",
+        "Helvetica",
+        None
+    ).map_err(|e| anyhow::anyhow!("无法加载字体: {:?}", e))?;
+
+    let mut doc = genpdf::Document::new(font_family);
+    doc.set_title(&comic.title);
+    doc.set_paper_size(genpdf::PaperSize::A4);
+
+    let mut decorator = genpdf::SimplePageDecorator::new();
+    decorator.set_margins(10);
+    decorator.set_header(|page| {
+        elements::Paragraph::new(format!("- {} -", page))
+            .aligned(Alignment::Center)
+            .styled(style::Style::new().with_font_size(8))
+    });
+    doc.set_page_decorator(decorator);
+
+    doc.push(elements::Break::new(8));
+    doc.push(elements::Paragraph::new(&comic.title)
+        .aligned(Alignment::Center)
+        .styled(style::Style::new().with_font_size(28).bold()));
+    doc.push(elements::Paragraph::new(format!("风格: {}", comic.style))
+        .aligned(Alignment::Center)
+        .styled(style::Style::new().with_font_size(12)));
+
+    for panels in page_layouts(comic) {
+        doc.push(elements::PageBreak::new());
+        let png = encode_png(&compose_page(&panels))?;
+        let image = elements::Image::from_reader(Cursor::new(png))
+            .map_err(|e| anyhow::anyhow!("无法嵌入漫画页: {:?}", e))?
+            .with_alignment(Alignment::Center)
+            .with_dpi(170.0);
+        doc.push(image);
+    }
+
+    doc.push(elements::PageBreak::new());
+    doc.push(elements::Paragraph::new("制作人员")
+        .styled(style::Style::new().with_font_size(20).bold()));
+    doc.push(elements::Break::new(1));
+    for line in credit_lines(comic, credits) {
+        doc.push(elements::Paragraph::new(line)
+            .styled(style::Style::new().with_font_size(11)));
+    }
+
+    doc.render_to_file(output_path)
+        .map_err(|e| anyhow::anyhow!("无法生成 PDF: {:?}", e))?;
+
+    Ok(())
+}
+
+/// 按页码和格号排好的输出页，每页的分格。分格超过 `MAX_PANELS_PER_PAGE` 的页拆成连续的几页
+fn page_layouts(comic: &ComicResult) -> Vec<Vec<&ComicPanel>> {
+    let mut pages: Vec<_> = comic.pages.iter().collect();
+    pages.sort_by_key(|p| p.page_number);
+    let mut layouts = Vec::new();
+    for page in pages {
+        let mut panels: Vec<&ComicPanel> = page.panels.iter().collect();
+        panels.sort_by_key(|p| p.panel_number);
+        if panels.is_empty() {
+            layouts.push(panels);
+        } else {
+            layouts.extend(panels.chunks(MAX_PANELS_PER_PAGE).map(<[_]>::to_vec));
+        }
+    }
+    layouts
+}
+
+/// 按分格数量排成网格，把每格的图片裁切填充进去；缺少图片的分格留白
+fn compose_page(panels: &[&ComicPanel]) -> RgbImage {
+    let mut canvas = RgbImage::from_pixel(PAGE_WIDTH, PAGE_HEIGHT, Rgb([255, 255, 255]));
+
+    if panels.is_empty() {
+        return canvas;
+    }
+
+    let count = panels.len() as u32;
+    let columns = if count == 1 { 1 } else { 2 };
+    let rows = count.div_ceil(columns);
+    let cell_width = (PAGE_WIDTH - PAGE_MARGIN * 2).saturating_sub(PANEL_GUTTER * (columns - 1)) / columns;
+    let cell_height = (PAGE_HEIGHT - PAGE_MARGIN * 2).saturating_sub(PANEL_GUTTER * (rows - 1)) / rows;
+
+    for (index, panel) in panels.iter().enumerate() {
+        let index = index as u32;
+        let row = index / columns;
+        // 奇数格的最后一格占满整行
+        let spans_row = index == count - 1 && count % columns == 1 && columns > 1;
+        let (x, width) = if spans_row {
+            (PAGE_MARGIN, PAGE_WIDTH - PAGE_MARGIN * 2)
+        } else {
+            (PAGE_MARGIN + (index % columns) * (cell_width + PANEL_GUTTER), cell_width)
+        };
+        let y = PAGE_MARGIN + row * (cell_height + PANEL_GUTTER);
+
+        if let Some(source) = panel.image_path.as_deref().and_then(load_panel_image) {
+            let filled = source
+                .resize_to_fill(width, cell_height, image::imageops::FilterType::Triangle)
+                .to_rgb8();
+            image::imageops::overlay(&mut canvas, &filled, x as i64, y as i64);
+        }
+        draw_border(&mut canvas, x, y, width, cell_height);
+    }
+
+    canvas
+}
+
+fn load_panel_image(source: &str) -> Option<DynamicImage> {
+    if let Some(data) = source.strip_prefix("data:") {
+        let (_, encoded) = data.split_once(";base64,")?;
+        let bytes = base64::engine::general_purpose::STANDARD.decode(encoded).ok()?;
+        return image::load_from_memory(&bytes).ok();
+    }
+
+    let path = source.strip_prefix("file://").unwrap_or(source);
+    image::open(path).ok()
+}
+
+fn draw_border(canvas: &mut RgbImage, x: u32, y: u32, width: u32, height: u32) {
+    let black = Rgb([0, 0, 0]);
+    for dy in 0..height {
+        for dx in 0..width {
+            let on_edge = dx < BORDER_WIDTH
+                || dy < BORDER_WIDTH
+                || dx >= width.saturating_sub(BORDER_WIDTH)
+                || dy >= height.saturating_sub(BORDER_WIDTH);
+            if on_edge {
+                canvas.put_pixel(x + dx, y + dy, black);
+            }
+        }
+    }
+}
+
+fn encode_png(page: &RgbImage) -> Result<Vec<u8>> {
+    let mut buffer = Cursor::new(Vec::new());
+    page.write_to(&mut buffer, ImageFormat::Png)
+        .context("无法编码漫画页图片")?;
+    Ok(buffer.into_inner())
+}
+
+fn credit_lines(comic: &ComicResult, credits: &ComicCredits) -> Vec<String> {
+    let mut lines = vec![format!("作品: {}", comic.title)];
+    if let Some(writer) = &credits.writer {
+        lines.push(format!("原作: {}", writer));
+    }
+    if let Some(artist) = &credits.artist {
+        lines.push(format!("作画: {}", artist));
+    }
+    if !comic.characters.is_empty() {
+        let names: Vec<&str> = comic.characters.iter().map(|c| c.name.as_str()).collect();
+        lines.push(format!("登场角色: {}", names.join("、")));
+    }
+    lines.push(format!("生成时间: {}", comic.metadata.generated_at));
+    if let Some(notes) = &credits.notes {
+        lines.push(notes.clone());
+    }
+    lines
+}
+
+fn comic_info_xml(comic: &ComicResult, credits: &ComicCredits, page_count: usize) -> String {
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<ComicInfo xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\">\n");
+    xml.push_str(&format!("  <Title>{}</Title>\n", escape_xml(&comic.title)));
+    if let Some(writer) = &credits.writer {
+        xml.push_str(&format!("  <Writer>{}</Writer>\n", escape_xml(writer)));
+    }
+    if let Some(artist) = &credits.artist {
+        xml.push_str(&format!("  <Penciller>{}</Penciller>\n", escape_xml(artist)));
+    }
+    xml.push_str(&format!("  <Notes>{}</Notes>\n", escape_xml(&credit_lines(comic, credits).join("\n"))));
+    xml.push_str(&format!("  <PageCount>{}</PageCount>\n", page_count));
+    xml.push_str("  <Pages>\n");
+    for index in 0..page_count {
+        xml.push_str(&format!("    <Page Image=\"{}\"/>\n", index));
+    }
+    xml.push_str("  </Pages>\n");
+    xml.push_str("</ComicInfo>\n");
+    xml
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,278 @@
+use crate::models::CharacterGraph;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphExportFormat {
+    GraphML,
+    Mermaid,
+    Svg,
+    Png,
+}
+
+impl GraphExportFormat {
+    pub fn from_name(format_str: &str) -> Option<Self> {
+        match format_str.to_lowercase().as_str() {
+            "graphml" => Some(GraphExportFormat::GraphML),
+            "mermaid" | "mmd" => Some(GraphExportFormat::Mermaid),
+            "svg" => Some(GraphExportFormat::Svg),
+            "png" => Some(GraphExportFormat::Png),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &str {
+        match self {
+            GraphExportFormat::GraphML => ".graphml",
+            GraphExportFormat::Mermaid => ".mmd",
+            GraphExportFormat::Svg => ".svg",
+            GraphExportFormat::Png => ".png",
+        }
+    }
+}
+
+/// 导出为 GraphML，可直接导入 Gephi / yEd 等工具
+pub fn export_graph_as_graphml(graph: &CharacterGraph, output_path: &Path) -> Result<()> {
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    xml.push_str("  <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n");
+    xml.push_str("  <key id=\"avatar\" for=\"node\" attr.name=\"avatar_url\" attr.type=\"string\"/>\n");
+    xml.push_str("  <key id=\"label\" for=\"edge\" attr.name=\"relation_type\" attr.type=\"string\"/>\n");
+    xml.push_str("  <key id=\"description\" for=\"edge\" attr.name=\"description\" attr.type=\"string\"/>\n");
+    xml.push_str("  <graph id=\"characters\" edgedefault=\"directed\">\n");
+
+    for node in &graph.nodes {
+        xml.push_str(&format!("    <node id=\"{}\">\n", escape_xml(&node.id)));
+        xml.push_str(&format!("      <data key=\"name\">{}</data>\n", escape_xml(&node.name)));
+        if let Some(avatar) = &node.avatar_url {
+            xml.push_str(&format!("      <data key=\"avatar\">{}</data>\n", escape_xml(avatar)));
+        }
+        xml.push_str("    </node>\n");
+    }
+
+    for edge in &graph.edges {
+        xml.push_str(&format!(
+            "    <edge id=\"{}\" source=\"{}\" target=\"{}\">\n",
+            escape_xml(&edge.id),
+            escape_xml(&edge.from),
+            escape_xml(&edge.to)
+        ));
+        xml.push_str(&format!("      <data key=\"label\">{}</data>\n", escape_xml(&edge.label)));
+        if let Some(desc) = &edge.description {
+            xml.push_str(&format!("      <data key=\"description\">{}</data>\n", escape_xml(desc)));
+        }
+        xml.push_str("    </edge>\n");
+    }
+
+    xml.push_str("  </graph>\n");
+    xml.push_str("</graphml>\n");
+
+    write_file(output_path, &xml)
+}
+
+/// 生成 Mermaid 流程图源码，可嵌入支持 Mermaid 的 Markdown / Wiki
+pub fn graph_to_mermaid(graph: &CharacterGraph) -> String {
+    let ids: HashMap<&str, String> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.id.as_str(), format!("c{}", i)))
+        .collect();
+
+    let mut mermaid = String::from("graph LR\n");
+    for node in &graph.nodes {
+        mermaid.push_str(&format!("    {}[\"{}\"]\n", ids[node.id.as_str()], escape_mermaid(&node.name)));
+    }
+    for edge in &graph.edges {
+        let (Some(from), Some(to)) = (ids.get(edge.from.as_str()), ids.get(edge.to.as_str())) else {
+            continue;
+        };
+        if edge.label.trim().is_empty() {
+            mermaid.push_str(&format!("    {} --> {}\n", from, to));
+        } else {
+            mermaid.push_str(&format!("    {} -->|\"{}\"| {}\n", from, escape_mermaid(&edge.label), to));
+        }
+    }
+    mermaid
+}
+
+pub fn export_graph_as_mermaid(graph: &CharacterGraph, output_path: &Path) -> Result<()> {
+    write_file(output_path, &graph_to_mermaid(graph))
+}
+
+/// 以环形布局渲染为 SVG 图片
+pub fn export_graph_as_svg(graph: &CharacterGraph, output_path: &Path) -> Result<()> {
+    write_file(output_path, &graph_to_svg(graph))
+}
+
+/// 把 SVG 版本栅格化为 PNG，人物名和关系用系统字体绘制
+pub fn export_graph_as_png(graph: &CharacterGraph, output_path: &Path) -> Result<()> {
+    let mut options = resvg::usvg::Options::default();
+    options.fontdb_mut().load_system_fonts();
+    let tree = resvg::usvg::Tree::from_str(&graph_to_svg(graph), &options)
+        .context("无法解析关系图")?;
+
+    let size = tree.size().to_int_size();
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(size.width(), size.height())
+        .context("关系图尺寸无效")?;
+    resvg::render(&tree, resvg::tiny_skia::Transform::default(), &mut pixmap.as_mut());
+
+    pixmap.save_png(output_path)
+        .with_context(|| format!("无法保存文件: {:?}", output_path))?;
+
+    Ok(())
+}
+
+fn graph_to_svg(graph: &CharacterGraph) -> String {
+    const NODE_RADIUS: f64 = 36.0;
+    const MARGIN: f64 = 80.0;
+
+    let count = graph.nodes.len().max(1) as f64;
+    let layout_radius = (count * (NODE_RADIUS * 2.0 + 40.0) / (2.0 * PI)).max(160.0);
+    let size = (layout_radius + NODE_RADIUS + MARGIN) * 2.0;
+    let center = size / 2.0;
+
+    let positions: HashMap<&str, (f64, f64)> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| {
+            let angle = 2.0 * PI * i as f64 / count - PI / 2.0;
+            (
+                node.id.as_str(),
+                (center + layout_radius * angle.cos(), center + layout_radius * angle.sin()),
+            )
+        })
+        .collect();
+
+    let mut svg = String::new();
+    svg.push_str(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0:.0}\" height=\"{0:.0}\" viewBox=\"0 0 {0:.0} {0:.0}\" font-family=\"sans-serif\">\n",
+        size
+    ));
+    svg.push_str("  <defs>\n");
+    svg.push_str("    <marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto-start-reverse\">\n");
+    svg.push_str("      <path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"#888888\"/>\n");
+    svg.push_str("    </marker>\n");
+    svg.push_str("  </defs>\n");
+    svg.push_str(&format!("  <rect width=\"{0:.0}\" height=\"{0:.0}\" fill=\"#ffffff\"/>\n", size));
+
+    for edge in &graph.edges {
+        let (Some(&(x1, y1)), Some(&(x2, y2))) =
+            (positions.get(edge.from.as_str()), positions.get(edge.to.as_str()))
+        else {
+            continue;
+        };
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length <= NODE_RADIUS * 2.0 {
+            continue;
+        }
+        let (ux, uy) = (dx / length, dy / length);
+        let (sx, sy) = (x1 + ux * NODE_RADIUS, y1 + uy * NODE_RADIUS);
+        let (ex, ey) = (x2 - ux * NODE_RADIUS, y2 - uy * NODE_RADIUS);
+        svg.push_str(&format!(
+            "  <line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"#888888\" stroke-width=\"1.5\" marker-end=\"url(#arrow)\"/>\n",
+            sx, sy, ex, ey
+        ));
+        if !edge.label.trim().is_empty() {
+            svg.push_str(&format!(
+                "  <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"12\" fill=\"#555555\" text-anchor=\"middle\">{}</text>\n",
+                (sx + ex) / 2.0,
+                (sy + ey) / 2.0 - 4.0,
+                escape_xml(&edge.label)
+            ));
+        }
+    }
+
+    for node in &graph.nodes {
+        let (x, y) = positions[node.id.as_str()];
+        svg.push_str(&format!(
+            "  <circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.0}\" fill=\"#e8f0fe\" stroke=\"#4a6fa5\" stroke-width=\"2\"/>\n",
+            x, y, NODE_RADIUS
+        ));
+        svg.push_str(&format!(
+            "  <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"14\" fill=\"#1f2d3d\" text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text>\n",
+            x,
+            y,
+            escape_xml(&node.name)
+        ));
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn write_file(output_path: &Path, data: &str) -> Result<()> {
+    let mut file = std::fs::File::create(output_path)
+        .with_context(|| format!("无法创建导出文件: {:?}", output_path))?;
+
+    file.write_all(data.as_bytes())
+        .with_context(|| format!("无法保存文件: {:?}", output_path))?;
+
+    Ok(())
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CharacterEdge, CharacterNode};
+
+    fn sample_graph() -> CharacterGraph {
+        CharacterGraph {
+            nodes: vec![
+                CharacterNode { id: "a".to_string(), name: "林动".to_string(), avatar_url: None },
+                CharacterNode { id: "b".to_string(), name: "绫清竹".to_string(), avatar_url: None },
+            ],
+            edges: vec![CharacterEdge {
+                id: "e1".to_string(),
+                from: "a".to_string(),
+                to: "b".to_string(),
+                label: "恋人".to_string(),
+                description: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_graph_to_mermaid() {
+        let mermaid = graph_to_mermaid(&sample_graph());
+        assert!(mermaid.starts_with("graph LR\n"));
+        assert!(mermaid.contains("c0[\"林动\"]"));
+        assert!(mermaid.contains("c0 -->|\"恋人\"| c1"));
+    }
+
+    #[test]
+    fn test_mermaid_skips_dangling_edges() {
+        let mut graph = sample_graph();
+        graph.edges[0].to = "missing".to_string();
+        assert!(!graph_to_mermaid(&graph).contains("-->"));
+    }
+
+    #[test]
+    fn test_export_graph_as_png() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("graph.png");
+        export_graph_as_png(&sample_graph(), &path).unwrap();
+
+        let image = image::open(&path).unwrap();
+        assert!(image.width() > 0);
+        assert_eq!(GraphExportFormat::from_name("PNG"), Some(GraphExportFormat::Png));
+    }
+}
@@ -0,0 +1,116 @@
+use super::ExportContent;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 按章节导出为 Markdown 文件夹，每章一个文件并带 YAML front matter，
+/// 目录结构可直接作为 Obsidian 仓库或静态站点生成器的内容目录使用：
+///
+/// ```text
+/// <output_dir>/
+///   index.md
+///   chapters/
+///     001-第一章.md
+///     002-第二章.md
+/// ```
+pub fn export_as_md_folder(
+    content: &ExportContent,
+    output_dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    let chapters_dir = output_dir.join("chapters");
+    fs::create_dir_all(&chapters_dir)
+        .with_context(|| format!("无法创建导出目录: {:?}", chapters_dir))?;
+
+    let mut written_files = Vec::new();
+    let mut chapter_links = Vec::new();
+
+    for chapter in &content.chapters {
+        let file_stem = format!("{:03}-{}", chapter.number, sanitize_segment(&chapter.title));
+        let file_path = chapters_dir.join(format!("{}.md", file_stem));
+
+        let mut md_content = String::new();
+        md_content.push_str("---\n");
+        md_content.push_str(&format!("title: {}\n", yaml_string(&chapter.title)));
+        md_content.push_str(&format!("number: {}\n", chapter.number));
+        md_content.push_str(&format!(
+            "status: {}\n",
+            yaml_string(chapter.status.as_deref().unwrap_or("draft"))
+        ));
+        md_content.push_str(&format!("word_count: {}\n", chapter.content.chars().count()));
+        match &chapter.summary {
+            Some(summary) if !summary.trim().is_empty() => {
+                md_content.push_str(&format!("summary: {}\n", yaml_string(summary.trim())));
+            }
+            _ => md_content.push_str("summary: \"\"\n"),
+        }
+        md_content.push_str(&format!("book: {}\n", yaml_string(&content.metadata.title)));
+        md_content.push_str("---\n\n");
+        md_content.push_str(&format!("# {}\n\n", chapter.title));
+        md_content.push_str(chapter.content.trim_end());
+        md_content.push('\n');
+
+        fs::write(&file_path, md_content.as_bytes())
+            .with_context(|| format!("无法保存文件: {:?}", file_path))?;
+
+        chapter_links.push(format!("- [[chapters/{}|{}]]", file_stem, chapter.title));
+        written_files.push(file_path);
+    }
+
+    let mut index_content = String::new();
+    index_content.push_str("---\n");
+    index_content.push_str(&format!("title: {}\n", yaml_string(&content.metadata.title)));
+    index_content.push_str(&format!("author: {}\n", yaml_string(&content.metadata.author)));
+    if let Some(desc) = &content.metadata.description {
+        index_content.push_str(&format!("description: {}\n", yaml_string(desc)));
+    }
+    index_content.push_str(&format!("created_at: {}\n", yaml_string(&content.metadata.created_at)));
+    index_content.push_str(&format!("word_count: {}\n", content.metadata.word_count));
+    index_content.push_str(&format!("chapter_count: {}\n", content.metadata.chapter_count));
+    index_content.push_str("---\n\n");
+    index_content.push_str(&format!("# {}\n\n", content.metadata.title));
+    index_content.push_str("## 目录\n\n");
+    index_content.push_str(&chapter_links.join("\n"));
+    index_content.push('\n');
+
+    let index_path = output_dir.join("index.md");
+    fs::write(&index_path, index_content.as_bytes())
+        .with_context(|| format!("无法保存文件: {:?}", index_path))?;
+    written_files.insert(0, index_path);
+
+    Ok(written_files)
+}
+
+/// 生成双引号包裹的 YAML 字符串，转义反斜杠、引号和换行
+fn yaml_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            '\t' => escaped.push_str("\\t"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn sanitize_segment(name: &str) -> String {
+    let cleaned: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | '#' | '^' | '[' | ']' => '_',
+            _ => c,
+        })
+        .collect();
+
+    if cleaned.is_empty() {
+        "untitled".to_string()
+    } else {
+        cleaned
+    }
+}
@@ -3,14 +3,26 @@ pub mod pdf_export;
 pub mod epub_export;
 pub mod txt_export;
 pub mod md_export;
+pub mod md_folder_export;
+pub mod graph_export;
+pub mod comic_export;
+pub mod preflight;
+pub mod wiki_export;
 
 pub use docx_export::export_as_docx;
 pub use pdf_export::export_as_pdf;
 pub use epub_export::export_as_epub;
 pub use txt_export::export_as_txt;
 pub use md_export::export_as_md;
+pub use md_folder_export::export_as_md_folder;
+pub use graph_export::{GraphExportFormat, export_graph_as_graphml, export_graph_as_mermaid, export_graph_as_png, export_graph_as_svg};
+pub use comic_export::{ComicExportFormat, ComicCredits, export_comic_as_cbz, export_comic_as_pdf};
+pub use preflight::{ExportIssueSeverity, ExportValidation, validate_project_export};
+pub use wiki_export::{WikiExportResult, export_wiki};
 
-use anyhow::Result;
+use crate::repository::ProjectRepository;
+use anyhow::{anyhow, Result};
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,6 +47,10 @@ pub struct ChapterContent {
     pub title: String,
     pub number: usize,
     pub content: String,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub summary: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -44,6 +60,7 @@ pub enum ExportFormat {
     Epub,
     Txt,
     Md,
+    MdFolder,
 }
 
 impl ExportFormat {
@@ -54,6 +71,7 @@ impl ExportFormat {
             ExportFormat::Epub => ".epub",
             ExportFormat::Txt => ".txt",
             ExportFormat::Md => ".md",
+            ExportFormat::MdFolder => "",
         }
     }
 
@@ -64,6 +82,7 @@ impl ExportFormat {
             ExportFormat::Epub => "application/epub+zip",
             ExportFormat::Txt => "text/plain",
             ExportFormat::Md => "text/markdown",
+            ExportFormat::MdFolder => "inode/directory",
         }
     }
 
@@ -74,6 +93,76 @@ impl ExportFormat {
             ExportFormat::Epub => "EPUB电子书 (.epub)",
             ExportFormat::Txt => "纯文本 (.txt)",
             ExportFormat::Md => "Markdown文档 (.md)",
+            ExportFormat::MdFolder => "Markdown章节文件夹 (Obsidian)",
         }
     }
 }
+
+/// 从数据库组装整本书的导出内容；author 为空时使用设置中的作者署名
+pub fn load_project_content(conn: &Connection, project_id: &str, author: Option<&str>) -> Result<ExportContent> {
+    let project = ProjectRepository::get_project(conn, project_id)?
+        .ok_or_else(|| anyhow!("项目不存在: {}", project_id))?;
+    let chapters = ProjectRepository::list_chapters(conn, project_id)?;
+    let author = match author {
+        Some(author) if !author.trim().is_empty() => author.to_string(),
+        _ => ProjectRepository::get_author_name(conn)?.unwrap_or_default(),
+    };
+
+    let metadata = ExportMetadata {
+        title: project.name,
+        author,
+        description: project.description,
+        created_at: project.created_at,
+        word_count: chapters.iter().map(|c| c.content.chars().count()).sum(),
+        chapter_count: chapters.len(),
+    };
+
+    Ok(ExportContent {
+        metadata,
+        chapters: chapters
+            .into_iter()
+            .enumerate()
+            .map(|(index, chapter)| ChapterContent {
+                id: chapter.id,
+                title: chapter.title,
+                number: index + 1,
+                content: chapter.content,
+                status: Some(chapter.status),
+                summary: chapter.summary,
+            })
+            .collect(),
+    })
+}
+
+/// 组装单章导出内容，书名和作者取自所属项目
+pub fn load_chapter_content(conn: &Connection, chapter_id: &str, author: Option<&str>) -> Result<ExportContent> {
+    let chapter = ProjectRepository::get_chapter(conn, chapter_id)?
+        .ok_or_else(|| anyhow!("章节不存在: {}", chapter_id))?;
+    let number = ProjectRepository::chapter_number(conn, &chapter)?;
+    let project = ProjectRepository::get_project(conn, &chapter.project_id)?;
+    let author = match author {
+        Some(author) if !author.trim().is_empty() => author.to_string(),
+        _ => ProjectRepository::get_author_name(conn)?.unwrap_or_default(),
+    };
+
+    let metadata = ExportMetadata {
+        title: chapter.title.clone(),
+        author,
+        description: project.map(|p| format!("《{}》第{}章", p.name, number)),
+        created_at: chapter.created_at.clone(),
+        word_count: chapter.content.chars().count(),
+        chapter_count: 1,
+    };
+
+    Ok(ExportContent {
+        metadata,
+        chapters: vec![ChapterContent {
+            id: chapter.id,
+            title: chapter.title,
+            number,
+            content: chapter.content,
+            status: Some(chapter.status),
+            summary: chapter.summary,
+        }],
+    })
+}
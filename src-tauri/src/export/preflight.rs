@@ -0,0 +1,143 @@
+use super::{ExportContent, ExportFormat};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportIssueSeverity {
+    /// 阻止导出
+    Error,
+    /// 可以导出，但结果可能不完整
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportIssue {
+    pub severity: ExportIssueSeverity,
+    pub field: String,
+    pub message: String,
+    pub chapter_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportValidation {
+    pub ready: bool,
+    pub chapter_count: usize,
+    pub word_count: usize,
+    pub issues: Vec<ExportIssue>,
+}
+
+/// 写文件前检查导出内容，报告缺失的元数据和空章节
+pub fn validate_project_export(content: &ExportContent, format: Option<ExportFormat>) -> ExportValidation {
+    let mut issues = Vec::new();
+    let mut push = |severity, field: &str, message: String, chapter_id: Option<&str>| {
+        issues.push(ExportIssue {
+            severity,
+            field: field.to_string(),
+            message,
+            chapter_id: chapter_id.map(|id| id.to_string()),
+        });
+    };
+
+    let metadata = &content.metadata;
+    if metadata.title.trim().is_empty() {
+        push(ExportIssueSeverity::Error, "title", "作品缺少书名".to_string(), None);
+    }
+    if metadata.author.trim().is_empty() {
+        // EPUB 阅读器和书店要求填写作者
+        let severity = if format == Some(ExportFormat::Epub) {
+            ExportIssueSeverity::Error
+        } else {
+            ExportIssueSeverity::Warning
+        };
+        push(severity, "author", "未设置作者署名".to_string(), None);
+    }
+    if metadata.description.as_deref().is_none_or(|d| d.trim().is_empty()) {
+        push(ExportIssueSeverity::Warning, "description", "作品缺少简介".to_string(), None);
+    }
+    if content.chapters.is_empty() {
+        push(ExportIssueSeverity::Error, "chapters", "作品没有任何章节".to_string(), None);
+    }
+
+    let mut seen_titles = HashSet::new();
+    for chapter in &content.chapters {
+        let title = chapter.title.trim();
+        if title.is_empty() {
+            push(
+                ExportIssueSeverity::Warning,
+                "chapter.title",
+                format!("第{}章缺少标题", chapter.number),
+                Some(&chapter.id),
+            );
+        } else if !seen_titles.insert(title.to_string()) {
+            push(
+                ExportIssueSeverity::Warning,
+                "chapter.title",
+                format!("第{}章标题「{}」与前文重复", chapter.number, title),
+                Some(&chapter.id),
+            );
+        }
+        if chapter.content.trim().is_empty() {
+            push(
+                ExportIssueSeverity::Warning,
+                "chapter.content",
+                format!("第{}章「{}」没有正文", chapter.number, title),
+                Some(&chapter.id),
+            );
+        }
+    }
+
+    ExportValidation {
+        ready: !issues.iter().any(|i| i.severity == ExportIssueSeverity::Error),
+        chapter_count: content.chapters.len(),
+        word_count: metadata.word_count,
+        issues,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{ChapterContent, ExportMetadata};
+
+    fn content(author: &str, chapters: Vec<ChapterContent>) -> ExportContent {
+        ExportContent {
+            metadata: ExportMetadata {
+                title: "测试小说".to_string(),
+                author: author.to_string(),
+                description: Some("简介".to_string()),
+                created_at: String::new(),
+                word_count: 0,
+                chapter_count: chapters.len(),
+            },
+            chapters,
+        }
+    }
+
+    fn chapter(id: &str, title: &str, body: &str) -> ChapterContent {
+        ChapterContent {
+            id: id.to_string(),
+            title: title.to_string(),
+            number: 1,
+            content: body.to_string(),
+            status: None,
+            summary: None,
+        }
+    }
+
+    #[test]
+    fn test_missing_author_blocks_epub_only() {
+        let book = content("", vec![chapter("c1", "第一章", "正文")]);
+        assert!(validate_project_export(&book, Some(ExportFormat::Txt)).ready);
+        assert!(!validate_project_export(&book, Some(ExportFormat::Epub)).ready);
+    }
+
+    #[test]
+    fn test_reports_empty_chapters() {
+        let book = content("作者", vec![chapter("c1", "第一章", "  ")]);
+        let validation = validate_project_export(&book, None);
+        assert!(validation.ready);
+        assert!(validation.issues.iter().any(|i| i.field == "chapter.content" && i.chapter_id.as_deref() == Some("c1")));
+        assert!(!validate_project_export(&content("作者", vec![]), None).ready);
+    }
+}
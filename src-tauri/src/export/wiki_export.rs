@@ -0,0 +1,553 @@
+use crate::calendar::{CalendarConfig, CalendarDate};
+use crate::character_presence::aliases_by_character;
+use crate::entity_index::locate_terms;
+use anyhow::{anyhow, Context, Result};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// 记录上次生成的每个文件的哈希，用于增量生成
+const MANIFEST_FILE: &str = ".wiki-manifest.json";
+
+const STYLE: &str = "body{margin:0;font-family:-apple-system,'PingFang SC','Microsoft YaHei',sans-serif;line-height:1.7;color:#222;background:#fafaf7}
+nav{padding:.8em 2em;background:#2f3e46;display:flex;gap:1.2em;flex-wrap:wrap}
+nav a{color:#e6ede8;text-decoration:none}
+main{max-width:860px;margin:0 auto;padding:1.5em 2em 4em}
+h1{border-bottom:2px solid #84a98c;padding-bottom:.3em}
+h2{color:#354f52;margin-top:1.6em}
+a{color:#2a6f97}
+.meta{color:#666}
+table{border-collapse:collapse;width:100%}
+td,th{border-bottom:1px solid #ddd;padding:.4em .6em;text-align:left;vertical-align:top}
+ul.entries{columns:2}
+";
+
+/// 生成结果，路径相对于输出目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WikiExportResult {
+    pub output_dir: String,
+    pub written: Vec<String>,
+    pub unchanged: usize,
+    pub removed: Vec<String>,
+}
+
+/// 条目分类：目录名和显示名
+const CATEGORIES: &[(&str, &str)] = &[
+    ("characters", "人物"),
+    ("locations", "地点"),
+    ("factions", "势力"),
+    ("items", "物品"),
+    ("worldview", "设定"),
+];
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// 把正文中出现的条目名换成链接；所有条目页都在一级子目录下，链接统一以 `../` 开头
+struct Linker {
+    terms: Vec<String>,
+    targets: Vec<String>,
+}
+
+impl Linker {
+    fn add(&mut self, term: &str, target: &str) {
+        if !term.trim().is_empty() && !self.terms.iter().any(|t| t == term) {
+            self.terms.push(term.to_string());
+            self.targets.push(target.to_string());
+        }
+    }
+
+    /// 转义文本并加上链接，不链接到 `current` 页面自身
+    fn inline(&self, text: &str, current: &str) -> String {
+        let terms: Vec<&str> = self.terms.iter().map(String::as_str).collect();
+        let mut last = 0;
+        let mut html = String::new();
+        for (start, end, i) in locate_terms(text, &terms) {
+            if self.targets[i] == current {
+                continue;
+            }
+            html.push_str(&escape_html(&text[last..start]));
+            html.push_str(&link(&self.targets[i], &text[start..end]));
+            last = end;
+        }
+        html.push_str(&escape_html(&text[last..]));
+        html.replace('\n', "<br>")
+    }
+
+    /// 空行分段
+    fn render(&self, text: &str, current: &str) -> String {
+        text.split("\n\n")
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(|p| format!("<p>{}</p>\n", self.inline(p, current)))
+            .collect()
+    }
+}
+
+fn link(target: &str, name: &str) -> String {
+    format!("<a href=\"../{}\">{}</a>", target, escape_html(name))
+}
+
+/// 页面外壳，`depth` 为页面所在目录的层数
+fn page(project_name: &str, title: &str, depth: usize, body: &str) -> String {
+    let prefix = "../".repeat(depth);
+    let nav: Vec<String> = std::iter::once(format!("<a href=\"{}index.html\">{}</a>", prefix, escape_html(project_name)))
+        .chain(CATEGORIES.iter().map(|(dir, label)| format!("<a href=\"{}{}/index.html\">{}</a>", prefix, dir, label)))
+        .chain(std::iter::once(format!("<a href=\"{}timeline.html\">时间线</a>", prefix)))
+        .collect();
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{} - {}</title>\n<link rel=\"stylesheet\" href=\"{}style.css\">\n</head>\n<body>\n<nav>{}</nav>\n<main>\n<h1>{}</h1>\n{}</main>\n</body>\n</html>\n",
+        escape_html(title),
+        escape_html(project_name),
+        prefix,
+        nav.join(""),
+        escape_html(title),
+        body
+    )
+}
+
+/// 条目页，`fields` 为标签和纯文本值，`sections` 为标题和已生成的 HTML
+struct Entry {
+    category: &'static str,
+    id: String,
+    title: String,
+    fields: Vec<(&'static str, String)>,
+    text: Vec<(&'static str, String)>,
+    sections: Vec<(&'static str, String)>,
+}
+
+impl Entry {
+    fn new(category: &'static str, id: &str, title: &str) -> Self {
+        Entry { category, id: id.to_string(), title: title.to_string(), fields: Vec::new(), text: Vec::new(), sections: Vec::new() }
+    }
+
+    fn path(&self) -> String {
+        format!("{}/{}.html", self.category, self.id)
+    }
+
+    fn field(&mut self, label: &'static str, value: Option<&str>) {
+        if let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) {
+            self.fields.push((label, value.to_string()));
+        }
+    }
+
+    fn text(&mut self, label: &'static str, value: Option<&str>) {
+        if let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) {
+            self.text.push((label, value.to_string()));
+        }
+    }
+
+    fn list(&mut self, label: &'static str, items: Vec<String>) {
+        if !items.is_empty() {
+            let items: Vec<String> = items.into_iter().map(|i| format!("<li>{}</li>", i)).collect();
+            self.sections.push((label, format!("<ul>\n{}\n</ul>\n", items.join("\n"))));
+        }
+    }
+
+    fn render(&self, linker: &Linker) -> String {
+        let path = self.path();
+        let mut body = String::new();
+        if !self.fields.is_empty() {
+            body.push_str("<table>\n");
+            for (label, value) in &self.fields {
+                body.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>\n", label, linker.inline(value, &path)));
+            }
+            body.push_str("</table>\n");
+        }
+        for (label, value) in &self.text {
+            body.push_str(&format!("<h2>{}</h2>\n{}", label, linker.render(value, &path)));
+        }
+        for (label, html) in &self.sections {
+            body.push_str(&format!("<h2>{}</h2>\n{}", label, html));
+        }
+        body
+    }
+}
+
+fn query<T, F>(conn: &Connection, sql: &str, project_id: &str, map: F) -> Result<Vec<T>>
+where
+    F: FnMut(&rusqlite::Row<'_>) -> rusqlite::Result<T>,
+{
+    Ok(conn.prepare(sql)?.query_map(params![project_id], map)?.collect::<rusqlite::Result<_>>()?)
+}
+
+/// 从数据库生成全部页面，返回相对路径和内容
+fn build_pages(conn: &Connection, project_id: &str) -> Result<Vec<(String, String)>> {
+    let project_name: String = conn
+        .query_row("SELECT name FROM projects WHERE id = ?", params![project_id], |row| row.get(0))
+        .map_err(|_| anyhow!("项目不存在: {}", project_id))?;
+    let err = |e: String| anyhow!(e);
+
+    type CharacterRow = (String, String, [Option<String>; 8]);
+    let characters: Vec<CharacterRow> = query(
+        conn,
+        "SELECT id, name, role_type, gender, race, status, appearance, personality, background, skills
+         FROM characters WHERE project_id = ? AND merged_into IS NULL ORDER BY created_at",
+        project_id,
+        |row| Ok((row.get(0)?, row.get(1)?, [row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?, row.get(7)?, row.get(8)?, row.get(9)?])),
+    )?;
+    let mut aliases = aliases_by_character(conn, project_id).map_err(err)?;
+    let relations: Vec<(String, String, String, Option<String>)> = query(
+        conn,
+        "SELECT from_character_id, to_character_id, relation_type, description FROM character_relations WHERE project_id = ?",
+        project_id,
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    )?;
+    let kinship = crate::family_tree::list_links(conn, project_id).map_err(err)?;
+    let organizations = crate::organizations::list_organizations(conn, project_id).map_err(err)?;
+    let locations = crate::locations::list_locations(conn, project_id).map_err(err)?;
+    let routes = crate::locations::list_routes(conn, project_id).map_err(err)?;
+    let items = crate::items::list_items(conn, project_id).map_err(err)?;
+    let world_views: Vec<(String, String, String, String)> = query(
+        conn,
+        "SELECT id, category, title, content FROM world_views WHERE project_id = ? ORDER BY category, created_at",
+        project_id,
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    )?;
+
+    let mut linker = Linker { terms: Vec::new(), targets: Vec::new() };
+    let names: HashMap<&str, &str> = characters.iter().map(|(id, name, _)| (id.as_str(), name.as_str())).collect();
+    let character_aliases: HashMap<String, Vec<String>> =
+        characters.iter().map(|(id, _, _)| (id.clone(), aliases.remove(id).unwrap_or_default())).collect();
+    for (id, name, _) in &characters {
+        let target = format!("characters/{}.html", id);
+        linker.add(name, &target);
+        for alias in &character_aliases[id] {
+            linker.add(alias, &target);
+        }
+    }
+    for location in &locations {
+        linker.add(&location.name, &format!("locations/{}.html", location.id));
+    }
+    for organization in &organizations {
+        linker.add(&organization.name, &format!("factions/{}.html", organization.id));
+    }
+    for item in &items {
+        linker.add(&item.name, &format!("items/{}.html", item.id));
+    }
+    for (id, _, title, _) in &world_views {
+        linker.add(title, &format!("worldview/{}.html", id));
+    }
+    let character_link = |id: &str| names.get(id).map(|name| link(&format!("characters/{}.html", id), name));
+
+    let mut entries: Vec<Entry> = Vec::new();
+    for (id, name, [role, gender, race, status, appearance, personality, background, skills]) in &characters {
+        let mut entry = Entry::new("characters", id, name);
+        entry.field("又称", Some(&character_aliases[id].join("、")));
+        entry.field("身份", role.as_deref());
+        entry.field("性别", gender.as_deref());
+        entry.field("种族", race.as_deref());
+        entry.field("状态", status.as_deref());
+        entry.text("外貌", appearance.as_deref());
+        entry.text("性格", personality.as_deref());
+        entry.text("背景", background.as_deref());
+        entry.text("能力", skills.as_deref());
+        entry.list(
+            "人物关系",
+            relations
+                .iter()
+                .filter_map(|(from, to, relation_type, description)| {
+                    let other = if from == id { to } else if to == id { from } else { return None };
+                    let mut line = format!("{}：{}", character_link(other)?, escape_html(relation_type));
+                    if let Some(description) = description.as_deref().filter(|d| !d.trim().is_empty()) {
+                        line.push_str(&format!("（{}）", escape_html(description.trim())));
+                    }
+                    Some(line)
+                })
+                .collect(),
+        );
+        entry.list(
+            "亲属",
+            kinship
+                .iter()
+                .filter_map(|k| {
+                    let (other, label) = match (k.kind.as_str(), k.from_id == *id, k.to_id == *id) {
+                        ("parent", true, _) => (&k.to_id, "子女"),
+                        ("parent", _, true) => (&k.from_id, "父母"),
+                        ("sibling", true, _) | ("sibling", _, true) => (if k.from_id == *id { &k.to_id } else { &k.from_id }, "兄弟姐妹"),
+                        ("spouse", true, _) | ("spouse", _, true) => (if k.from_id == *id { &k.to_id } else { &k.from_id }, "配偶"),
+                        _ => return None,
+                    };
+                    Some(format!("{}：{}", label, character_link(other)?))
+                })
+                .collect(),
+        );
+        let memberships = crate::organizations::character_memberships(conn, id).map_err(err)?;
+        entry.list(
+            "所属势力",
+            memberships
+                .iter()
+                .filter_map(|m| {
+                    let organization = organizations.iter().find(|o| o.id == m.organization_id)?;
+                    let role = m.role.as_deref().map(|r| format!("（{}）", escape_html(r))).unwrap_or_default();
+                    Some(format!("{}{}", link(&format!("factions/{}.html", organization.id), &organization.name), role))
+                })
+                .collect(),
+        );
+        entry.list(
+            "持有物品",
+            items
+                .iter()
+                .filter(|i| i.owner_id.as_deref() == Some(id.as_str()) && !i.destroyed)
+                .map(|i| link(&format!("items/{}.html", i.id), &i.name))
+                .collect(),
+        );
+        entries.push(entry);
+    }
+
+    for location in &locations {
+        let mut entry = Entry::new("locations", &location.id, &location.name);
+        entry.field("层级", Some(&location.level));
+        if let Some(parent) = location.parent_id.as_ref().and_then(|p| locations.iter().find(|l| l.id == *p)) {
+            entry.field("位于", Some(&parent.name));
+        }
+        entry.text("介绍", location.description.as_deref());
+        entry.list(
+            "下辖",
+            locations
+                .iter()
+                .filter(|l| l.parent_id.as_deref() == Some(location.id.as_str()))
+                .map(|l| link(&format!("locations/{}.html", l.id), &l.name))
+                .collect(),
+        );
+        entry.list(
+            "路程",
+            routes
+                .iter()
+                .filter_map(|r| {
+                    let other = if r.from_location_id == location.id { &r.to_location_id } else if r.to_location_id == location.id { &r.from_location_id } else { return None };
+                    let other = locations.iter().find(|l| l.id == *other)?;
+                    Some(format!("{}：{}", link(&format!("locations/{}.html", other.id), &other.name), escape_html(&r.travel_time)))
+                })
+                .collect(),
+        );
+        entries.push(entry);
+    }
+
+    for organization in &organizations {
+        let mut entry = Entry::new("factions", &organization.id, &organization.name);
+        entry.field("类型", organization.org_type.as_deref());
+        if let Some(parent) = organization.parent_id.as_ref().and_then(|p| organizations.iter().find(|o| o.id == *p)) {
+            entry.field("上级", Some(&parent.name));
+        }
+        entry.text("介绍", organization.description.as_deref());
+        entry.text("目标", organization.goals.as_deref());
+        let members = crate::organizations::list_members(conn, &organization.id).map_err(err)?;
+        entry.list(
+            "成员",
+            members
+                .iter()
+                .filter_map(|m| {
+                    let role = m.role.as_deref().map(|r| format!("（{}）", escape_html(r))).unwrap_or_default();
+                    Some(format!("{}{}", character_link(&m.character_id)?, role))
+                })
+                .collect(),
+        );
+        entries.push(entry);
+    }
+
+    for item in &items {
+        let mut entry = Entry::new("items", &item.id, &item.name);
+        entry.field("持有者", item.owner_name.as_deref());
+        if let Some(location) = item.location_id.as_ref().and_then(|l| locations.iter().find(|loc| loc.id == *l)) {
+            entry.field("所在", Some(&location.name));
+        }
+        if item.destroyed {
+            entry.field("状态", Some("已毁"));
+        }
+        entry.text("介绍", item.description.as_deref());
+        entry.list("能力", item.abilities.iter().map(|a| escape_html(a)).collect());
+        let history = crate::items::item_history(conn, &item.id).map_err(err)?;
+        entry.list(
+            "流转",
+            history
+                .iter()
+                .map(|e| {
+                    let action = match e.kind.as_str() {
+                        "transfer" => format!("交给{}", e.character_id.as_deref().and_then(character_link).unwrap_or_default()),
+                        "lose" => "遗失".to_string(),
+                        "destroy" => "被毁".to_string(),
+                        _ => "修复".to_string(),
+                    };
+                    format!("{}：{}", escape_html(&e.chapter_title), action)
+                })
+                .collect(),
+        );
+        entries.push(entry);
+    }
+
+    for (id, category, title, content) in &world_views {
+        let mut entry = Entry::new("worldview", id, title);
+        entry.field("分类", Some(category));
+        entry.text("内容", Some(content));
+        entries.push(entry);
+    }
+
+    let mut pages: Vec<(String, String)> = vec![("style.css".to_string(), STYLE.to_string())];
+    let mut home = String::new();
+    for (dir, label) in CATEGORIES {
+        let list: Vec<String> = entries
+            .iter()
+            .filter(|e| e.category == *dir)
+            .map(|e| format!("<li><a href=\"{}.html\">{}</a></li>", e.id, escape_html(&e.title)))
+            .collect();
+        home.push_str(&format!("<h2><a href=\"{}/index.html\">{}</a></h2>\n<p class=\"meta\">共 {} 条</p>\n", dir, label, list.len()));
+        let body = format!("<ul class=\"entries\">\n{}\n</ul>\n", list.join("\n"));
+        pages.push((format!("{}/index.html", dir), page(&project_name, label, 1, &body)));
+    }
+    home.push_str("<h2><a href=\"timeline.html\">时间线</a></h2>\n");
+    pages.push(("index.html".to_string(), page(&project_name, &project_name, 0, &home)));
+    for entry in &entries {
+        pages.push((entry.path(), page(&project_name, &entry.title, 1, &entry.render(&linker))));
+    }
+    pages.push(("timeline.html".to_string(), page(&project_name, "时间线", 0, &timeline(conn, project_id, &linker)?)));
+    Ok(pages)
+}
+
+/// 角色和世界观的时间线事件，能换算成日期的按日期排，其余排在后面
+fn timeline(conn: &Connection, project_id: &str, linker: &Linker) -> Result<String> {
+    let calendar: CalendarConfig = crate::calendar::load_calendar(conn, project_id).map_err(|e| anyhow!(e))?;
+    let events: Vec<(String, Option<String>, Option<String>, String)> = query(
+        conn,
+        "SELECT e.event_title, e.story_time, e.story_date, c.name FROM character_timeline_events e
+         JOIN characters c ON c.id = e.character_id WHERE c.project_id = ?1 AND c.merged_into IS NULL
+         UNION ALL
+         SELECT e.event_title, e.story_time, e.story_date, w.title FROM worldview_timeline_events e
+         JOIN world_views w ON w.id = e.worldview_id WHERE w.project_id = ?1",
+        project_id,
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    )?;
+    // 同一时间点的事件放在一起
+    let mut dated: BTreeMap<(i64, i64), (String, Vec<String>)> = BTreeMap::new();
+    let mut undated: Vec<String> = Vec::new();
+    for (title, story_time, story_date, owner) in events {
+        let line = format!("{}（{}）", title, owner);
+        let date = story_date
+            .and_then(|json| serde_json::from_str::<CalendarDate>(&json).ok())
+            .or_else(|| calendar.parse(story_time.as_deref()?));
+        match date {
+            Some(date) => {
+                let key = (date.year, date.month.map(|_| calendar.day_number(&date)).unwrap_or(i64::MIN));
+                dated.entry(key).or_insert_with(|| (calendar.format(&date), Vec::new())).1.push(line);
+            }
+            None => undated.push(match story_time.filter(|t| !t.trim().is_empty()) {
+                Some(time) => format!("{}：{}", time.trim(), line),
+                None => line,
+            }),
+        }
+    }
+    let mut rows: Vec<String> = dated
+        .into_values()
+        .map(|(label, lines)| format!("<tr><th>{}</th><td>{}</td></tr>", escape_html(&label), linker.inline(&lines.join("\n"), "")))
+        .collect();
+    if !undated.is_empty() {
+        rows.push(format!("<tr><th>时间不详</th><td>{}</td></tr>", linker.inline(&undated.join("\n"), "")));
+    }
+    // 时间线页在根目录，正文链接需要去掉一层 `../`
+    Ok(format!("<table>\n{}\n</table>\n", rows.join("\n")).replace("href=\"../", "href=\""))
+}
+
+fn hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 生成静态 Wiki；只重写内容变化的页面，删除已不存在的条目页
+pub fn export_wiki(conn: &Connection, project_id: &str, output_dir: &Path) -> Result<WikiExportResult> {
+    let pages = build_pages(conn, project_id)?;
+    let manifest_path = output_dir.join(MANIFEST_FILE);
+    let previous: HashMap<String, String> = fs::read_to_string(&manifest_path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+
+    let mut result = WikiExportResult {
+        output_dir: output_dir.to_string_lossy().to_string(),
+        written: Vec::new(),
+        unchanged: 0,
+        removed: Vec::new(),
+    };
+    let mut manifest: BTreeMap<String, String> = BTreeMap::new();
+    for (path, content) in &pages {
+        let digest = hash(content);
+        let file = output_dir.join(path);
+        if previous.get(path) == Some(&digest) && file.exists() {
+            result.unchanged += 1;
+        } else {
+            if let Some(parent) = file.parent() {
+                fs::create_dir_all(parent).with_context(|| format!("无法创建导出目录: {:?}", parent))?;
+            }
+            fs::write(&file, content.as_bytes()).with_context(|| format!("无法保存文件: {:?}", file))?;
+            result.written.push(path.clone());
+        }
+        manifest.insert(path.clone(), digest);
+    }
+    let mut stale: Vec<&String> = previous.keys().filter(|path| !manifest.contains_key(*path)).collect();
+    stale.sort();
+    for path in stale {
+        let file = output_dir.join(path);
+        if file.exists() {
+            fs::remove_file(&file).with_context(|| format!("无法删除文件: {:?}", file))?;
+        }
+        result.removed.push(path.clone());
+    }
+    fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?).with_context(|| format!("无法保存文件: {:?}", manifest_path))?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wiki_cross_links_and_incremental_regeneration() {
+        let (dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, background, created_at, updated_at) VALUES
+                 ('r1', 'p1', '林舟', '生于青石镇，拜入<天剑宗>。', 't0', 't0'),
+                 ('r2', 'p1', '沈青', NULL, 't0', 't0');
+             INSERT INTO character_relations (id, project_id, from_character_id, to_character_id, relation_type, created_at, updated_at) VALUES
+                 ('x1', 'p1', 'r1', 'r2', '师兄妹', 't0', 't0');
+             INSERT INTO locations (id, project_id, name, level, created_at, updated_at) VALUES ('l1', 'p1', '青石镇', 'city', 't0', 't0');
+             INSERT INTO character_timeline_events (id, character_id, event_type, event_title, story_time, sort_order, created_at, updated_at) VALUES
+                 ('e1', 'r1', 'birth', '降生', '1000年', 1, 't0', 't0'),
+                 ('e2', 'r1', 'milestone', '遇见沈青', NULL, 2, 't0', 't0');",
+        )
+        .unwrap();
+        let out = dir.path().join("wiki");
+
+        let first = export_wiki(&conn, "p1", &out).unwrap();
+        assert!(first.written.contains(&"characters/r1.html".to_string()));
+        let page = fs::read_to_string(out.join("characters/r1.html")).unwrap();
+        assert!(page.contains("<a href=\"../locations/l1.html\">青石镇</a>"));
+        assert!(page.contains("&lt;天剑宗&gt;"));
+        assert!(page.contains("<a href=\"../characters/r2.html\">沈青</a>：师兄妹"));
+        let timeline = fs::read_to_string(out.join("timeline.html")).unwrap();
+        assert!(timeline.contains("<th>1000年</th><td>降生（<a href=\"characters/r1.html\">林舟</a>）</td>"));
+
+        let second = export_wiki(&conn, "p1", &out).unwrap();
+        assert!(second.written.is_empty());
+        assert_eq!(second.unchanged, first.written.len());
+
+        conn.execute("DELETE FROM locations WHERE id = 'l1'", []).unwrap();
+        let third = export_wiki(&conn, "p1", &out).unwrap();
+        assert_eq!(third.removed, vec!["locations/l1.html"]);
+        assert!(third.written.contains(&"characters/r1.html".to_string()));
+        assert!(!out.join("locations/l1.html").exists());
+    }
+}
@@ -0,0 +1,578 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use uuid::Uuid;
+
+/// `from_id` 是 `to_id` 的父亲或母亲
+pub const KIND_PARENT: &str = "parent";
+/// 兄弟姐妹，双向，`from_id` 为较小的 id
+pub const KIND_SIBLING: &str = "sibling";
+/// 配偶，双向，`from_id` 为较小的 id
+pub const KIND_SPOUSE: &str = "spouse";
+/// 父母与子女的年龄差小于这个值时提示
+const MIN_PARENT_AGE_GAP: i32 = 12;
+
+/// 一条亲属关系，与自由填写的 `character_relations` 分开保存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KinshipLink {
+    pub id: String,
+    pub project_id: String,
+    pub from_id: String,
+    pub to_id: String,
+    pub kind: String,
+    pub note: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FamilyNode {
+    pub character_id: String,
+    pub name: String,
+    pub gender: Option<String>,
+    /// 辈分，最年长的一辈为 0
+    pub generation: i32,
+    /// 同一辈中从左到右的位置，配偶相邻，子女大致排在父母下方
+    pub order: usize,
+}
+
+/// `kind` 为 cycle、too_many_parents、generation_conflict 或 age_gap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KinshipIssue {
+    pub kind: String,
+    pub severity: String,
+    pub description: String,
+    pub character_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FamilyTree {
+    pub nodes: Vec<FamilyNode>,
+    pub links: Vec<KinshipLink>,
+    pub issues: Vec<KinshipIssue>,
+}
+
+/// AI 为角色设计的一位亲属，`relation` 是此人相对角色的身份：parent、child、sibling 或 spouse
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FamilyRelative {
+    pub name: String,
+    pub relation: String,
+    pub gender: Option<String>,
+    pub age: Option<i32>,
+    pub description: Option<String>,
+}
+
+/// AI 生成、尚未写入的家族背景
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FamilyDraft {
+    pub character_id: String,
+    pub backstory: String,
+    pub relatives: Vec<FamilyRelative>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FamilyApplyResult {
+    pub created_character_ids: Vec<String>,
+    pub links_created: usize,
+    /// 因校验失败没有写入的亲属，附原因
+    pub skipped: Vec<String>,
+}
+
+/// 生成家族背景所需的角色资料，先读出再调用 AI
+#[derive(Debug, Clone)]
+pub struct FamilyContext {
+    pub character_id: String,
+    pub name: String,
+    pub gender: Option<String>,
+    pub age: Option<i32>,
+    pub background: Option<String>,
+    pub genre: String,
+    /// 已登记的亲属，格式为“身份：名字”
+    pub relatives: Vec<String>,
+}
+
+fn link_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<KinshipLink> {
+    Ok(KinshipLink {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        from_id: row.get(2)?,
+        to_id: row.get(3)?,
+        kind: row.get(4)?,
+        note: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+pub fn list_links(conn: &Connection, project_id: &str) -> Result<Vec<KinshipLink>, String> {
+    conn.prepare(
+        "SELECT k.id, k.project_id, k.from_id, k.to_id, k.kind, k.note, k.created_at FROM character_kinship k
+         JOIN characters a ON a.id = k.from_id JOIN characters b ON b.id = k.to_id
+         WHERE k.project_id = ? AND a.merged_into IS NULL AND b.merged_into IS NULL ORDER BY k.created_at, k.rowid",
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![project_id], link_from_row)
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+fn parents_of<'a>(links: &'a [KinshipLink], id: &str) -> impl Iterator<Item = &'a str> + 'a {
+    let id = id.to_string();
+    links.iter().filter(move |l| l.kind == KIND_PARENT && l.to_id == id).map(|l| l.from_id.as_str())
+}
+
+/// 全部祖先，不含自身
+fn ancestors(links: &[KinshipLink], id: &str) -> HashSet<String> {
+    let mut found = HashSet::new();
+    let mut queue: VecDeque<String> = parents_of(links, id).map(str::to_string).collect();
+    while let Some(next) = queue.pop_front() {
+        if found.insert(next.clone()) {
+            queue.extend(parents_of(links, &next).map(str::to_string));
+        }
+    }
+    found
+}
+
+/// 添加亲属关系：`relative_id` 是 `character_id` 的 parent、child、sibling 或 spouse。
+/// 拒绝自我关联、跨项目、形成祖先环、超过两位父母，以及把直系血亲登记为配偶或兄弟姐妹
+pub fn add_link(conn: &Connection, character_id: &str, relative_id: &str, kind: &str, note: Option<&str>) -> Result<KinshipLink, String> {
+    if character_id == relative_id {
+        return Err("不能与自己建立亲属关系".to_string());
+    }
+    let project = |id: &str| -> Result<String, String> {
+        conn.query_row("SELECT project_id FROM characters WHERE id = ? AND merged_into IS NULL", params![id], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("角色不存在: {}", id))
+    };
+    let project_id = project(character_id)?;
+    if project(relative_id)? != project_id {
+        return Err("两个角色不属于同一项目".to_string());
+    }
+    let (from_id, to_id, kind) = match kind {
+        "parent" => (relative_id, character_id, KIND_PARENT),
+        "child" => (character_id, relative_id, KIND_PARENT),
+        "sibling" | "spouse" => {
+            let kind = if kind == "sibling" { KIND_SIBLING } else { KIND_SPOUSE };
+            if character_id < relative_id { (character_id, relative_id, kind) } else { (relative_id, character_id, kind) }
+        }
+        other => return Err(format!("未知的亲属关系: {}", other)),
+    };
+
+    let links = list_links(conn, &project_id)?;
+    let between = |a: &str, b: &str| links.iter().find(|l| (l.from_id == a && l.to_id == b) || (l.from_id == b && l.to_id == a));
+    if let Some(existing) = between(from_id, to_id) {
+        if existing.kind == kind && existing.from_id == from_id {
+            return Ok(existing.clone());
+        }
+        return Err(format!("两人已登记为 {}", existing.kind));
+    }
+    if kind == KIND_PARENT {
+        if ancestors(&links, from_id).contains(to_id) {
+            return Err("不能把自己的后代登记为父母".to_string());
+        }
+        if parents_of(&links, to_id).count() >= 2 {
+            return Err("一个角色最多登记两位父母".to_string());
+        }
+    } else if ancestors(&links, from_id).contains(to_id) || ancestors(&links, to_id).contains(from_id) {
+        return Err("直系血亲不能登记为配偶或兄弟姐妹".to_string());
+    }
+
+    let link = KinshipLink {
+        id: Uuid::new_v4().to_string(),
+        project_id,
+        from_id: from_id.to_string(),
+        to_id: to_id.to_string(),
+        kind: kind.to_string(),
+        note: note.map(str::to_string),
+        created_at: Utc::now().to_rfc3339(),
+    };
+    conn.execute(
+        "INSERT INTO character_kinship (id, project_id, from_id, to_id, kind, note, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        params![link.id, link.project_id, link.from_id, link.to_id, link.kind, link.note, link.created_at],
+    )
+    .map_err(|e| format!("Failed to save kinship: {}", e))?;
+    Ok(link)
+}
+
+pub fn remove_link(conn: &Connection, link_id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM character_kinship WHERE id = ?", params![link_id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 由亲属关系推出辈分：子女比父母低一辈，配偶和兄弟姐妹同辈；每个家族最年长的一辈为 0。
+/// 约束互相矛盾时记为 generation_conflict
+fn generations(links: &[KinshipLink]) -> (HashMap<String, i32>, Vec<KinshipIssue>) {
+    let mut neighbours: HashMap<&str, Vec<(&str, i32)>> = HashMap::new();
+    for link in links {
+        let step = if link.kind == KIND_PARENT { 1 } else { 0 };
+        neighbours.entry(&link.from_id).or_default().push((&link.to_id, step));
+        neighbours.entry(&link.to_id).or_default().push((&link.from_id, -step));
+    }
+    let mut starts: Vec<&str> = links.iter().flat_map(|l| [l.from_id.as_str(), l.to_id.as_str()]).collect();
+    starts.dedup();
+
+    let mut generation: HashMap<String, i32> = HashMap::new();
+    let mut issues = Vec::new();
+    let mut conflicts: HashSet<(String, String)> = HashSet::new();
+    for start in starts {
+        if generation.contains_key(start) {
+            continue;
+        }
+        let mut family = vec![start];
+        generation.insert(start.to_string(), 0);
+        let mut queue = VecDeque::from([start]);
+        while let Some(id) = queue.pop_front() {
+            let current = generation[id];
+            for &(next, step) in neighbours.get(id).into_iter().flatten() {
+                match generation.get(next) {
+                    None => {
+                        generation.insert(next.to_string(), current + step);
+                        family.push(next);
+                        queue.push_back(next);
+                    }
+                    Some(&g) if g != current + step => {
+                        let pair = if id < next { (id.to_string(), next.to_string()) } else { (next.to_string(), id.to_string()) };
+                        if conflicts.insert(pair.clone()) {
+                            issues.push(KinshipIssue {
+                                kind: "generation_conflict".to_string(),
+                                severity: "medium".to_string(),
+                                description: "亲属关系推出的辈分互相矛盾".to_string(),
+                                character_ids: vec![pair.0, pair.1],
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let top = family.iter().map(|id| generation[*id]).min().unwrap_or(0);
+        for id in family {
+            *generation.get_mut(id).expect("generation") -= top;
+        }
+    }
+    (generation, issues)
+}
+
+/// 检查亲属关系的一致性：祖先环、多于两位父母、辈分矛盾，以及父母与子女年龄差过小
+pub fn check_consistency(conn: &Connection, project_id: &str) -> Result<Vec<KinshipIssue>, String> {
+    let links = list_links(conn, project_id)?;
+    let ages: HashMap<String, (String, Option<i32>)> = conn
+        .prepare("SELECT id, name, age FROM characters WHERE project_id = ? AND merged_into IS NULL")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get::<_, String>(0)?, (row.get(1)?, row.get(2)?))))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let name = |id: &str| ages.get(id).map(|(name, _)| name.clone()).unwrap_or_else(|| id.to_string());
+
+    let mut issues = Vec::new();
+    let mut children: Vec<&str> = links.iter().filter(|l| l.kind == KIND_PARENT).map(|l| l.to_id.as_str()).collect();
+    children.sort();
+    children.dedup();
+    for child in children {
+        let parents: Vec<String> = parents_of(&links, child).map(str::to_string).collect();
+        if ancestors(&links, child).contains(child) {
+            issues.push(KinshipIssue {
+                kind: "cycle".to_string(),
+                severity: "high".to_string(),
+                description: format!("{}出现在自己的祖先中", name(child)),
+                character_ids: vec![child.to_string()],
+            });
+        }
+        if parents.len() > 2 {
+            issues.push(KinshipIssue {
+                kind: "too_many_parents".to_string(),
+                severity: "high".to_string(),
+                description: format!("{}登记了 {} 位父母", name(child), parents.len()),
+                character_ids: std::iter::once(child.to_string()).chain(parents.iter().cloned()).collect(),
+            });
+        }
+        for parent in parents {
+            if let (Some((_, Some(parent_age))), Some((_, Some(child_age)))) = (ages.get(&parent), ages.get(child)) {
+                if parent_age - child_age < MIN_PARENT_AGE_GAP {
+                    issues.push(KinshipIssue {
+                        kind: "age_gap".to_string(),
+                        severity: "medium".to_string(),
+                        description: format!("{}（{}岁）只比子女{}（{}岁）大 {} 岁", name(&parent), parent_age, name(child), child_age, parent_age - child_age),
+                        character_ids: vec![parent.clone(), child.to_string()],
+                    });
+                }
+            }
+        }
+    }
+    let (_, mut conflicts) = generations(&links);
+    for issue in &mut conflicts {
+        issue.description = format!("{}与{}的辈分互相矛盾", name(&issue.character_ids[0]), name(&issue.character_ids[1]));
+    }
+    issues.extend(conflicts);
+    Ok(issues)
+}
+
+/// 供前端绘制的家谱：按辈分分层、同辈排序；`root_id` 指定时只返回该角色所在的家族
+pub fn family_tree(conn: &Connection, project_id: &str, root_id: Option<&str>) -> Result<FamilyTree, String> {
+    let mut links = list_links(conn, project_id)?;
+    if let Some(root) = root_id {
+        let mut family: HashSet<String> = HashSet::from([root.to_string()]);
+        loop {
+            let before = family.len();
+            for link in &links {
+                if family.contains(&link.from_id) || family.contains(&link.to_id) {
+                    family.insert(link.from_id.clone());
+                    family.insert(link.to_id.clone());
+                }
+            }
+            if family.len() == before {
+                break;
+            }
+        }
+        links.retain(|l| family.contains(&l.from_id));
+    }
+    let (generation, _) = generations(&links);
+    let characters: Vec<(String, String, Option<String>)> = conn
+        .prepare("SELECT id, name, gender FROM characters WHERE project_id = ? AND merged_into IS NULL ORDER BY created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let spouses_of = |id: &str| -> Vec<String> {
+        links
+            .iter()
+            .filter(|l| l.kind == KIND_SPOUSE && (l.from_id == id || l.to_id == id))
+            .map(|l| if l.from_id == id { l.to_id.clone() } else { l.from_id.clone() })
+            .collect()
+    };
+
+    let mut order: HashMap<String, usize> = HashMap::new();
+    let deepest = generation.values().copied().max().unwrap_or(-1);
+    for g in 0..=deepest {
+        let row: Vec<&str> = characters.iter().map(|(id, _, _)| id.as_str()).filter(|id| generation.get(*id) == Some(&g)).collect();
+        // 按父母的平均位置排，没有登记父母的排在后面
+        let key = |id: &str| {
+            let positions: Vec<usize> = parents_of(&links, id).filter_map(|p| order.get(p).copied()).collect();
+            if positions.is_empty() { f64::MAX } else { positions.iter().sum::<usize>() as f64 / positions.len() as f64 }
+        };
+        let mut sorted = row.clone();
+        sorted.sort_by(|a, b| key(a).total_cmp(&key(b)));
+        let mut placed: Vec<String> = Vec::new();
+        for id in sorted {
+            if placed.iter().any(|p| p == id) {
+                continue;
+            }
+            placed.push(id.to_string());
+            for spouse in spouses_of(id) {
+                if row.contains(&spouse.as_str()) && !placed.contains(&spouse) {
+                    placed.push(spouse);
+                }
+            }
+        }
+        for (i, id) in placed.into_iter().enumerate() {
+            order.insert(id, i);
+        }
+    }
+
+    let mut nodes: Vec<FamilyNode> = characters
+        .into_iter()
+        .filter_map(|(id, name, gender)| {
+            Some(FamilyNode { generation: *generation.get(&id)?, order: *order.get(&id)?, character_id: id, name, gender })
+        })
+        .collect();
+    nodes.sort_by_key(|n| (n.generation, n.order));
+    let issues = check_consistency(conn, project_id)?
+        .into_iter()
+        .filter(|issue| issue.character_ids.iter().any(|id| generation.contains_key(id)))
+        .collect();
+    Ok(FamilyTree { nodes, links, issues })
+}
+
+pub fn family_context(conn: &Connection, character_id: &str) -> Result<FamilyContext, String> {
+    let (project_id, name, gender, age, background, genre): (String, String, Option<String>, Option<i32>, Option<String>, String) = conn
+        .query_row(
+            "SELECT c.project_id, c.name, c.gender, c.age, c.background, COALESCE(p.genre, '小说')
+             FROM characters c JOIN projects p ON p.id = c.project_id WHERE c.id = ?",
+            params![character_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
+        )
+        .map_err(|_| "角色不存在".to_string())?;
+    let names: HashMap<String, String> = conn
+        .prepare("SELECT id, name FROM characters WHERE project_id = ?")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let relatives = list_links(conn, &project_id)?
+        .into_iter()
+        .filter_map(|l| {
+            let (relation, other) = match l.kind.as_str() {
+                KIND_PARENT if l.to_id == character_id => ("父母", l.from_id),
+                KIND_PARENT if l.from_id == character_id => ("子女", l.to_id),
+                _ if l.from_id == character_id => (if l.kind == KIND_SPOUSE { "配偶" } else { "兄弟姐妹" }, l.to_id),
+                _ if l.to_id == character_id => (if l.kind == KIND_SPOUSE { "配偶" } else { "兄弟姐妹" }, l.from_id),
+                _ => return None,
+            };
+            Some(format!("{}：{}", relation, names.get(&other)?))
+        })
+        .collect();
+    Ok(FamilyContext { character_id: character_id.to_string(), name, gender, age, background, genre, relatives })
+}
+
+pub fn build_prompt(context: &FamilyContext, instructions: Option<&str>) -> String {
+    format!(
+        "为{}小说中的角色“{}”设计可信的家族背景。\n性别：{}\n年龄：{}\n已有背景：{}\n已登记的亲属：{}\n补充要求：{}\n\n\
+         家族背景要能解释角色的性格和动机，与已有背景和亲属不矛盾，不要重复已登记的亲属。\
+         返回 JSON 对象：{{\"backstory\": \"200字以内的家族背景\", \"relatives\": [{{\"name\": \"姓名\", \
+         \"relation\": \"parent、child、sibling 或 spouse，指此人是该角色的什么人\", \"gender\": \"性别\", \"age\": 整数, \"description\": \"一句话介绍\"}}]}}",
+        context.genre,
+        context.name,
+        context.gender.as_deref().unwrap_or("未知"),
+        context.age.map(|a| a.to_string()).unwrap_or_else(|| "未知".to_string()),
+        context.background.as_deref().filter(|b| !b.trim().is_empty()).unwrap_or("暂无"),
+        if context.relatives.is_empty() { "无".to_string() } else { context.relatives.join("、") },
+        instructions.filter(|i| !i.trim().is_empty()).unwrap_or("无"),
+    )
+}
+
+/// 解析 AI 返回的家族背景，去掉无名、身份不合法或与角色同名的亲属
+pub fn parse_family(response: &str, context: &FamilyContext) -> Result<FamilyDraft, String> {
+    #[derive(Deserialize)]
+    struct Parsed {
+        #[serde(default)]
+        backstory: String,
+        #[serde(default)]
+        relatives: Vec<FamilyRelative>,
+    }
+    let start = response.find('{').unwrap_or(0);
+    let end = response.rfind('}').map(|i| i + 1).unwrap_or(response.len());
+    let parsed: Parsed = serde_json::from_str(&response[start..end]).map_err(|e| format!("无法解析生成的家族背景: {}", e))?;
+    let relatives = parsed
+        .relatives
+        .into_iter()
+        .filter_map(|mut r| {
+            r.name = r.name.trim().to_string();
+            r.relation = r.relation.trim().to_lowercase();
+            let valid = ["parent", "child", "sibling", "spouse"].contains(&r.relation.as_str());
+            (valid && !r.name.is_empty() && r.name != context.name).then_some(r)
+        })
+        .collect();
+    Ok(FamilyDraft { character_id: context.character_id.clone(), backstory: parsed.backstory.trim().to_string(), relatives })
+}
+
+pub async fn generate_with_ai(
+    service: &crate::ai::AIService,
+    model_id: &str,
+    context: &FamilyContext,
+    instructions: Option<&str>,
+) -> Result<FamilyDraft, String> {
+    let system_prompt = "你是一位经验丰富的小说人物设计师。只返回 JSON 对象，不要包含markdown代码块标记。";
+    let response = service.complete(model_id, system_prompt, &build_prompt(context, instructions)).await?;
+    parse_family(&response, context)
+}
+
+/// 写入家族背景：项目中已有同名角色时直接关联，否则新建角色；背景追加到角色的 background
+pub fn apply_family(conn: &Connection, draft: &FamilyDraft) -> Result<FamilyApplyResult, String> {
+    let project_id: String = conn
+        .query_row("SELECT project_id FROM characters WHERE id = ?", params![draft.character_id], |row| row.get(0))
+        .map_err(|_| "角色不存在".to_string())?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let mut result = FamilyApplyResult { created_character_ids: Vec::new(), links_created: 0, skipped: Vec::new() };
+    for relative in &draft.relatives {
+        let existing: Option<String> = tx
+            .query_row(
+                "SELECT id FROM characters WHERE project_id = ? AND name = ? AND merged_into IS NULL",
+                params![project_id, relative.name],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let relative_id = match existing {
+            Some(id) => id,
+            None => {
+                let id = Uuid::new_v4().to_string();
+                tx.execute(
+                    "INSERT INTO characters (id, project_id, name, role_type, gender, age, background, created_at, updated_at)
+                     VALUES (?1, ?2, ?3, 'supporting', ?4, ?5, ?6, ?7, ?7)",
+                    params![id, project_id, relative.name, relative.gender, relative.age, relative.description, now],
+                )
+                .map_err(|e| format!("Failed to create character: {}", e))?;
+                result.created_character_ids.push(id.clone());
+                id
+            }
+        };
+        match add_link(&tx, &draft.character_id, &relative_id, &relative.relation, relative.description.as_deref()) {
+            Ok(_) => result.links_created += 1,
+            Err(e) => result.skipped.push(format!("{}：{}", relative.name, e)),
+        }
+    }
+    if !draft.backstory.is_empty() {
+        tx.execute(
+            "UPDATE characters SET background = CASE WHEN background IS NULL OR background = '' THEN ?1 ELSE background || char(10) || char(10) || ?1 END,
+                                   updated_at = ?2 WHERE id = ?3",
+            params![draft.backstory, now, draft.character_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    let names: Vec<&str> = draft.relatives.iter().map(|r| r.name.as_str()).collect();
+    crate::tokenizer::add_words(&names);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kinship_validation_and_tree_layout() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, age, created_at, updated_at) VALUES
+                 ('a', 'p1', '林父', 50, 't0', 't0'), ('b', 'p1', '林母', 28, 't1', 't1'),
+                 ('c', 'p1', '林舟', 20, 't2', 't2'), ('d', 'p1', '林溪', 45, 't3', 't3'), ('e', 'p1', '沈青', 19, 't4', 't4');",
+        )
+        .unwrap();
+        add_link(&conn, "a", "b", "spouse", None).unwrap();
+        add_link(&conn, "c", "a", "parent", None).unwrap();
+        add_link(&conn, "b", "c", "child", None).unwrap();
+        add_link(&conn, "c", "d", "sibling", None).unwrap();
+        add_link(&conn, "c", "e", "spouse", None).unwrap();
+
+        assert!(add_link(&conn, "a", "c", "parent", None).is_err());
+        assert!(add_link(&conn, "c", "e", "parent", None).is_err());
+        assert!(add_link(&conn, "a", "c", "spouse", None).is_err());
+        assert!(add_link(&conn, "c", "c", "sibling", None).is_err());
+
+        let tree = family_tree(&conn, "p1", Some("e")).unwrap();
+        let layout: Vec<(&str, i32, usize)> = tree.nodes.iter().map(|n| (n.name.as_str(), n.generation, n.order)).collect();
+        assert_eq!(layout, vec![("林父", 0, 0), ("林母", 0, 1), ("林舟", 1, 0), ("沈青", 1, 1), ("林溪", 1, 2)]);
+        let kinds: Vec<&str> = tree.issues.iter().map(|i| i.kind.as_str()).collect();
+        assert_eq!(kinds, vec!["age_gap"]);
+    }
+
+    #[test]
+    fn test_parse_and_apply_family() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, background, created_at, updated_at) VALUES
+                 ('c', 'p1', '林舟', '渔村少年', 't0', 't0'), ('x', 'p1', '林海', NULL, 't0', 't0');",
+        )
+        .unwrap();
+        let context = family_context(&conn, "c").unwrap();
+        let response = r#"{"backstory": "林家世代打渔。", "relatives": [
+            {"name": "林海", "relation": "Parent", "age": 45},
+            {"name": "林小雨", "relation": "sibling", "gender": "女", "description": "妹妹"},
+            {"name": "林舟", "relation": "sibling"},
+            {"name": "王伯", "relation": "neighbour"}
+        ]}"#;
+        let draft = parse_family(response, &context).unwrap();
+        assert_eq!(draft.relatives.len(), 2);
+
+        let result = apply_family(&conn, &draft).unwrap();
+        assert_eq!((result.created_character_ids.len(), result.links_created), (1, 2));
+        let background: String = conn.query_row("SELECT background FROM characters WHERE id = 'c'", [], |row| row.get(0)).unwrap();
+        assert_eq!(background, "渔村少年\n\n林家世代打渔。");
+        assert_eq!(family_context(&conn, "c").unwrap().relatives, vec!["父母：林海", "兄弟姐妹：林小雨"]);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::family_tree::{self, FamilyDraft};
+use crate::logger::Logger;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// 添加亲属关系，`relative_id` 是 `character_id` 的 parent、child、sibling 或 spouse
+#[tauri::command]
+pub async fn add_kinship(
+    app: AppHandle,
+    character_id: String,
+    relative_id: String,
+    kind: String,
+    note: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let link = family_tree::add_link(&conn, &character_id, &relative_id, &kind, note.as_deref())?;
+
+    serde_json::to_string(&link).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_kinship(app: AppHandle, link_id: String) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    family_tree::remove_link(&conn, &link_id)
+}
+
+/// 按辈分分层的家谱，`root_id` 指定时只返回该角色所在的家族
+#[tauri::command]
+pub async fn get_family_tree(
+    app: AppHandle,
+    project_id: String,
+    root_id: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let tree = family_tree::family_tree(&conn, &project_id, root_id.as_deref())?;
+
+    serde_json::to_string(&tree).map_err(|e| e.to_string())
+}
+
+/// 检查亲属关系中的祖先环、多于两位父母、辈分矛盾和父母子女年龄差
+#[tauri::command]
+pub async fn check_kinship_consistency(app: AppHandle, project_id: String) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let issues = family_tree::check_consistency(&conn, &project_id)?;
+
+    serde_json::to_string(&issues).map_err(|e| e.to_string())
+}
+
+/// 让 AI 为角色设计家族背景和亲属，返回草稿供修改后再写入
+#[tauri::command]
+pub async fn ai_generate_family_backstory(
+    app: AppHandle,
+    character_id: String,
+    instructions: Option<String>,
+    model_id: Option<String>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("family_tree");
+    logger.info(&format!("Generating family backstory for character {}", character_id));
+
+    let context = {
+        let db_path = get_db_path(&app)?;
+        let conn = crate::database::get_connection(&db_path)
+            .map_err(|e| format!("Failed to get database connection: {}", e))?;
+        family_tree::family_context(&conn, &character_id)?
+    };
+
+    let ai_service = app.state::<std::sync::Arc<tokio::sync::RwLock<crate::ai::AIService>>>();
+    let service = ai_service.read().await;
+    let model_id = model_id.unwrap_or_else(|| "glm-4-flash".to_string());
+    let draft = family_tree::generate_with_ai(&service, &model_id, &context, instructions.as_deref())
+        .await
+        .map_err(|e| {
+            logger.error(&format!("Failed to generate family backstory: {}", e));
+            e
+        })?;
+
+    serde_json::to_string(&draft).map_err(|e| e.to_string())
+}
+
+/// 写入审阅过的家族背景：新建或关联亲属角色并登记亲属关系
+#[tauri::command]
+pub async fn apply_family_backstory(app: AppHandle, draft: FamilyDraft) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let result = family_tree::apply_family(&conn, &draft)?;
+
+    serde_json::to_string(&result).map_err(|e| e.to_string())
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
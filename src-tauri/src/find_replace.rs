@@ -0,0 +1,222 @@
+use crate::version_control_commands::save_restore_point;
+use chrono::Utc;
+use regex::{Regex, RegexBuilder};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// 预览中匹配前后各显示的字数
+const CONTEXT_CHARS: usize = 20;
+
+/// 全书查找替换的条件。`regex` 为 false 时按原文查找；为 true 时 `replacement` 可用 `$1`、`${name}` 引用捕获组
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindReplaceRequest {
+    pub project_id: String,
+    pub pattern: String,
+    pub replacement: String,
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default = "default_true")]
+    pub case_sensitive: bool,
+    /// 只在这些章节中查找，不指定时查找全部章节
+    pub chapter_ids: Option<Vec<String>>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 一处匹配及替换后的文字，`position` 是章节正文中的字符偏移
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceMatch {
+    /// 应用替换时可用来排除这一处
+    pub id: String,
+    pub chapter_id: String,
+    pub chapter_title: String,
+    pub position: usize,
+    pub matched: String,
+    pub replacement: String,
+    pub context_before: String,
+    pub context_after: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplacePreview {
+    pub matches: Vec<ReplaceMatch>,
+    pub total_matches: usize,
+    pub chapters_affected: usize,
+}
+
+/// 替换结果，撤销时恢复到 `restore_point_id` 对应的还原点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceResult {
+    pub restore_point_id: Option<String>,
+    pub replaced: usize,
+    pub chapters_changed: Vec<String>,
+}
+
+fn build_regex(request: &FindReplaceRequest) -> Result<Regex, String> {
+    if request.pattern.is_empty() {
+        return Err("Search pattern cannot be empty".to_string());
+    }
+    let pattern = if request.regex { request.pattern.clone() } else { regex::escape(&request.pattern) };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!request.case_sensitive)
+        .build()
+        .map_err(|e| format!("Invalid pattern: {}", e))
+}
+
+fn load_chapters(conn: &Connection, request: &FindReplaceRequest) -> Result<Vec<(String, String, String)>, String> {
+    let chapters: Vec<(String, String, String)> = conn
+        .prepare("SELECT id, title, COALESCE(content, '') FROM chapters WHERE project_id = ? ORDER BY sort_order, created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![request.project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(match &request.chapter_ids {
+        Some(ids) => chapters.into_iter().filter(|(id, _, _)| ids.contains(id)).collect(),
+        None => chapters,
+    })
+}
+
+/// 逐处计算替换后的文字，返回（匹配起止字节, 替换文字）
+fn replacements(re: &Regex, request: &FindReplaceRequest, content: &str) -> Vec<(usize, usize, String)> {
+    re.captures_iter(content)
+        .map(|caps| {
+            let whole = caps.get(0).expect("capture 0 always exists");
+            let mut replaced = String::new();
+            if request.regex {
+                caps.expand(&request.replacement, &mut replaced);
+            } else {
+                replaced.push_str(&request.replacement);
+            }
+            (whole.start(), whole.end(), replaced)
+        })
+        .filter(|(start, end, _)| start != end)
+        .collect()
+}
+
+fn match_id(chapter_id: &str, start: usize) -> String {
+    format!("{}:{}", chapter_id, start)
+}
+
+/// 列出所有匹配及替换后的样子，不修改正文
+pub fn preview(conn: &Connection, request: &FindReplaceRequest) -> Result<ReplacePreview, String> {
+    let re = build_regex(request)?;
+    let mut matches = Vec::new();
+    let mut chapters_affected = 0;
+    for (chapter_id, title, content) in load_chapters(conn, request)? {
+        let found = replacements(&re, request, &content);
+        if !found.is_empty() {
+            chapters_affected += 1;
+        }
+        for (start, end, replacement) in found {
+            let before: Vec<char> = content[..start].chars().collect();
+            matches.push(ReplaceMatch {
+                id: match_id(&chapter_id, start),
+                chapter_id: chapter_id.clone(),
+                chapter_title: title.clone(),
+                position: before.len(),
+                matched: content[start..end].to_string(),
+                replacement,
+                context_before: before[before.len().saturating_sub(CONTEXT_CHARS)..].iter().collect(),
+                context_after: content[end..].chars().take(CONTEXT_CHARS).collect(),
+            });
+        }
+    }
+    Ok(ReplacePreview { total_matches: matches.len(), matches, chapters_affected })
+}
+
+/// 在一个事务中替换所有匹配（`excluded` 中的匹配 id 除外）。替换前为项目建立还原点，
+/// 整次替换在版本历史中是一项操作，恢复到该还原点即可撤销
+pub fn apply(conn: &Connection, request: &FindReplaceRequest, excluded: &[String]) -> Result<ReplaceResult, String> {
+    let re = build_regex(request)?;
+    let mut changes = Vec::new();
+    let mut replaced = 0;
+    for (chapter_id, _, content) in load_chapters(conn, request)? {
+        let mut updated = String::with_capacity(content.len());
+        let mut last = 0;
+        let mut count = 0;
+        for (start, end, replacement) in replacements(&re, request, &content) {
+            if excluded.contains(&match_id(&chapter_id, start)) {
+                continue;
+            }
+            updated.push_str(&content[last..start]);
+            updated.push_str(&replacement);
+            last = end;
+            count += 1;
+        }
+        updated.push_str(&content[last..]);
+        if count > 0 && updated != content {
+            replaced += count;
+            changes.push((chapter_id, content, updated));
+        }
+    }
+    if changes.is_empty() {
+        return Ok(ReplaceResult { restore_point_id: None, replaced: 0, chapters_changed: Vec::new() });
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let description = format!("将“{}”替换为“{}”之前", request.pattern, request.replacement);
+    let restore_point = save_restore_point(&tx, &request.project_id, &description)?;
+    let now = Utc::now().to_rfc3339();
+    for (chapter_id, old_content, new_content) in &changes {
+        tx.execute(
+            "UPDATE chapters SET content = ?, word_count = ?, updated_at = ? WHERE id = ?",
+            params![new_content, new_content.chars().count() as i32, now, chapter_id],
+        )
+        .map_err(|e| format!("Failed to update chapter: {}", e))?;
+        crate::provenance::record_chapter_change(&tx, chapter_id, old_content, new_content, None)?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(ReplaceResult {
+        restore_point_id: Some(restore_point.id),
+        replaced,
+        chapters_changed: changes.into_iter().map(|(id, _, _)| id).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version_control_commands::restore_project_snapshot;
+
+    #[test]
+    fn test_regex_replace_preview_apply_and_undo() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '林舟说：走吧。林舟说：等等。', 1, 't0', 't0'),
+                 ('c2', 'p1', '第二章', '苏晚说：好。', 2, 't0', 't0');",
+        )
+        .unwrap();
+
+        let request = FindReplaceRequest {
+            project_id: "p1".to_string(),
+            pattern: r"(\p{Han}{2})说：".to_string(),
+            replacement: "${1}道：".to_string(),
+            regex: true,
+            case_sensitive: true,
+            chapter_ids: None,
+        };
+        let preview = super::preview(&conn, &request).unwrap();
+        assert_eq!((preview.total_matches, preview.chapters_affected), (3, 2));
+        assert_eq!(preview.matches[1].position, 7);
+        assert_eq!((preview.matches[1].matched.as_str(), preview.matches[1].replacement.as_str()), ("林舟说：", "林舟道："));
+        assert_eq!(preview.matches[1].context_before, "林舟说：走吧。");
+
+        let result = apply(&conn, &request, &[preview.matches[1].id.clone()]).unwrap();
+        assert_eq!((result.replaced, result.chapters_changed.len()), (2, 2));
+        let content: String = conn.query_row("SELECT content FROM chapters WHERE id = 'c1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(content, "林舟道：走吧。林舟说：等等。");
+
+        restore_project_snapshot(&conn, &result.restore_point_id.unwrap()).unwrap();
+        let content: String = conn.query_row("SELECT content FROM chapters WHERE id = 'c2'", [], |row| row.get(0)).unwrap();
+        assert_eq!(content, "苏晚说：好。");
+
+        // 非正则模式按原文匹配，“$1”不展开
+        let literal = FindReplaceRequest { pattern: "说：".to_string(), replacement: "$1".to_string(), regex: false, ..request };
+        assert_eq!(super::preview(&conn, &literal).unwrap().matches[0].replacement, "$1");
+    }
+}
@@ -0,0 +1,56 @@
+use crate::find_replace::{self, FindReplaceRequest};
+use crate::logger::Logger;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// 预览全书查找替换的每一处匹配，不修改正文
+#[tauri::command]
+pub async fn preview_find_replace(
+    app: AppHandle,
+    request: FindReplaceRequest,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let preview = find_replace::preview(&conn, &request)?;
+    serde_json::to_string(&preview).map_err(|e| e.to_string())
+}
+
+/// 应用全书查找替换，`excluded_ids` 为预览中不替换的匹配。返回的还原点可用 `restore_snapshot` 撤销
+#[tauri::command]
+pub async fn apply_find_replace(
+    app: AppHandle,
+    request: FindReplaceRequest,
+    excluded_ids: Option<Vec<String>>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("find_replace");
+    logger.info(&format!("Replacing \"{}\" in project {}", request.pattern, request.project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let result = find_replace::apply(&conn, &request, &excluded_ids.unwrap_or_default())?;
+    for chapter_id in &result.chapters_changed {
+        if let Err(e) = crate::entity_index::index_chapter(&conn, chapter_id, None) {
+            logger.warn(&format!("Failed to index entities: {}", e));
+        }
+    }
+
+    logger.info(&format!("Replaced {} matches in {} chapters", result.replaced, result.chapters_changed.len()));
+    serde_json::to_string(&result).map_err(|e| e.to_string())
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
@@ -0,0 +1,283 @@
+use crate::entity_index::locate_terms;
+use crate::version_control_commands::save_restore_point;
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// 检查结果中违规前后各显示的字数
+const CONTEXT_CHARS: usize = 20;
+
+/// 术语表条目：正文中应统一写作 `term`，`variants` 是不应出现的写法
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlossaryTerm {
+    pub id: String,
+    pub project_id: String,
+    pub term: String,
+    pub variants: Vec<String>,
+    pub note: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// 正文中出现的一处非规范写法，`position` 是章节正文中的字符偏移
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlossaryViolation {
+    /// 统一写法时可用来排除这一处
+    pub id: String,
+    pub chapter_id: String,
+    pub chapter_title: String,
+    pub term_id: String,
+    pub term: String,
+    pub variant: String,
+    pub position: usize,
+    pub context_before: String,
+    pub context_after: String,
+}
+
+/// 统一写法的结果，撤销时恢复到 `restore_point_id` 对应的还原点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizeResult {
+    pub restore_point_id: Option<String>,
+    pub replaced: usize,
+    pub chapters_changed: Vec<String>,
+}
+
+pub fn list_terms(conn: &Connection, project_id: &str) -> Result<Vec<GlossaryTerm>, String> {
+    conn.prepare(
+        "SELECT id, project_id, term, variants, note, created_at, updated_at FROM glossary_terms
+         WHERE project_id = ? ORDER BY term",
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![project_id], |row| {
+        let variants: String = row.get(3)?;
+        Ok(GlossaryTerm {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            term: row.get(2)?,
+            variants: serde_json::from_str(&variants).unwrap_or_default(),
+            note: row.get(4)?,
+            created_at: row.get(5)?,
+            updated_at: row.get(6)?,
+        })
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+/// 新建或修改术语（`id` 为空时新建）。变体去空、去重，不能与本条或其他条目的规范写法相同
+pub fn save_term(conn: &Connection, mut term: GlossaryTerm) -> Result<GlossaryTerm, String> {
+    term.term = term.term.trim().to_string();
+    if term.term.is_empty() {
+        return Err("Term cannot be empty".to_string());
+    }
+    let others: Vec<GlossaryTerm> = list_terms(conn, &term.project_id)?.into_iter().filter(|t| t.id != term.id).collect();
+    let mut variants: Vec<String> = Vec::new();
+    for variant in term.variants.iter().map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        if variant == term.term || others.iter().any(|t| t.term == variant) {
+            return Err(format!("“{}”已是规范写法，不能作为变体", variant));
+        }
+        if !variants.iter().any(|v| v == variant) {
+            variants.push(variant.to_string());
+        }
+    }
+    term.variants = variants;
+
+    let now = Utc::now().to_rfc3339();
+    if term.id.is_empty() {
+        term.id = uuid::Uuid::new_v4().to_string();
+        term.created_at = now.clone();
+    }
+    term.updated_at = now;
+    conn.execute(
+        "INSERT INTO glossary_terms (id, project_id, term, variants, note, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(id) DO UPDATE SET term = excluded.term, variants = excluded.variants, note = excluded.note,
+             updated_at = excluded.updated_at",
+        params![
+            term.id,
+            term.project_id,
+            term.term,
+            serde_json::to_string(&term.variants).map_err(|e| e.to_string())?,
+            term.note,
+            term.created_at,
+            term.updated_at,
+        ],
+    )
+    .map_err(|e| format!("Failed to save glossary term: {}", e))?;
+    Ok(term)
+}
+
+pub fn delete_term(conn: &Connection, term_id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM glossary_terms WHERE id = ?", params![term_id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn violation_id(chapter_id: &str, start: usize) -> String {
+    format!("{}:{}", chapter_id, start)
+}
+
+/// 找出正文中的变体，返回（起止字节, 术语下标, 变体）。规范写法一起参与匹配，
+/// 避免把“灵力值”中的“灵力”这类规范写法的一部分当作变体
+fn find_variants<'a>(content: &str, terms: &'a [GlossaryTerm]) -> Vec<(usize, usize, usize, &'a str)> {
+    let mut patterns: Vec<(&str, usize, bool)> = Vec::new();
+    for (i, term) in terms.iter().enumerate() {
+        patterns.push((&term.term, i, false));
+        patterns.extend(term.variants.iter().map(|v| (v.as_str(), i, true)));
+    }
+    let texts: Vec<&str> = patterns.iter().map(|(text, _, _)| *text).collect();
+    locate_terms(content, &texts)
+        .into_iter()
+        .filter(|&(_, _, p)| patterns[p].2)
+        .map(|(start, end, p)| (start, end, patterns[p].1, patterns[p].0))
+        .collect()
+}
+
+fn load_chapters(conn: &Connection, project_id: &str, chapter_id: Option<&str>) -> Result<Vec<(String, String, String)>, String> {
+    conn.prepare(
+        "SELECT id, title, COALESCE(content, '') FROM chapters WHERE project_id = ?1 AND (?2 IS NULL OR id = ?2)
+         ORDER BY sort_order, created_at",
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![project_id, chapter_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+/// 检查项目（或指定章节）中出现的非规范写法
+pub fn check(conn: &Connection, project_id: &str, chapter_id: Option<&str>) -> Result<Vec<GlossaryViolation>, String> {
+    let terms = list_terms(conn, project_id)?;
+    let mut violations = Vec::new();
+    for (chapter_id, title, content) in load_chapters(conn, project_id, chapter_id)? {
+        for (start, end, index, variant) in find_variants(&content, &terms) {
+            let before: Vec<char> = content[..start].chars().collect();
+            violations.push(GlossaryViolation {
+                id: violation_id(&chapter_id, start),
+                chapter_id: chapter_id.clone(),
+                chapter_title: title.clone(),
+                term_id: terms[index].id.clone(),
+                term: terms[index].term.clone(),
+                variant: variant.to_string(),
+                position: before.len(),
+                context_before: before[before.len().saturating_sub(CONTEXT_CHARS)..].iter().collect(),
+                context_after: content[end..].chars().take(CONTEXT_CHARS).collect(),
+            });
+        }
+    }
+    Ok(violations)
+}
+
+/// 把变体统一为规范写法。`term_ids` 为空时处理所有术语，`excluded` 中的违规 id 跳过。
+/// 修改前为项目建立还原点，恢复到该还原点即可撤销
+pub fn normalize(conn: &Connection, project_id: &str, term_ids: Option<&[String]>, excluded: &[String]) -> Result<NormalizeResult, String> {
+    let terms = list_terms(conn, project_id)?;
+    let mut changes = Vec::new();
+    let mut replaced = 0;
+    for (chapter_id, _, content) in load_chapters(conn, project_id, None)? {
+        let mut updated = String::with_capacity(content.len());
+        let mut last = 0;
+        for (start, end, index, _) in find_variants(&content, &terms) {
+            let selected = term_ids.is_none_or(|ids| ids.contains(&terms[index].id));
+            if !selected || excluded.contains(&violation_id(&chapter_id, start)) {
+                continue;
+            }
+            updated.push_str(&content[last..start]);
+            updated.push_str(&terms[index].term);
+            last = end;
+            replaced += 1;
+        }
+        updated.push_str(&content[last..]);
+        if updated != content {
+            changes.push((chapter_id, content, updated));
+        }
+    }
+    if changes.is_empty() {
+        return Ok(NormalizeResult { restore_point_id: None, replaced: 0, chapters_changed: Vec::new() });
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let restore_point = save_restore_point(&tx, project_id, "统一术语写法之前")?;
+    let now = Utc::now().to_rfc3339();
+    for (chapter_id, old_content, new_content) in &changes {
+        tx.execute(
+            "UPDATE chapters SET content = ?, word_count = ?, updated_at = ? WHERE id = ?",
+            params![new_content, new_content.chars().count() as i32, now, chapter_id],
+        )
+        .map_err(|e| format!("Failed to update chapter: {}", e))?;
+        crate::provenance::record_chapter_change(&tx, chapter_id, old_content, new_content, None)?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(NormalizeResult {
+        restore_point_id: Some(restore_point.id),
+        replaced,
+        chapters_changed: changes.into_iter().map(|(id, _, _)| id).collect(),
+    })
+}
+
+/// 注入 AI 写作提示词的术语表，没有术语时返回 None
+pub fn prompt_section(conn: &Connection, project_id: &str) -> Result<Option<String>, String> {
+    let terms = list_terms(conn, project_id)?;
+    if terms.is_empty() {
+        return Ok(None);
+    }
+    let mut lines = vec!["【术语表】请严格使用以下规范写法：".to_string()];
+    for term in &terms {
+        let mut line = format!("- {}", term.term);
+        if !term.variants.is_empty() {
+            line.push_str(&format!("（不要写作：{}）", term.variants.join("、")));
+        }
+        if let Some(note) = term.note.as_deref().filter(|n| !n.trim().is_empty()) {
+            line.push_str(&format!("：{}", note.trim()));
+        }
+        lines.push(line);
+    }
+    Ok(Some(lines.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(term: &str, variants: &[&str]) -> GlossaryTerm {
+        GlossaryTerm {
+            id: String::new(),
+            project_id: "p1".to_string(),
+            term: term.to_string(),
+            variants: variants.iter().map(|v| v.to_string()).collect(),
+            note: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_check_normalize_and_prompt() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '他运转灵气，灵气值暴涨。', 1, 't0', 't0'),
+                 ('c2', 'p1', '第二章', '真气耗尽，灵力全无。', 2, 't0', 't0');",
+        )
+        .unwrap();
+
+        let lingli = save_term(&conn, term("灵力", &["灵气", " 真气", "灵气", ""])).unwrap();
+        assert_eq!(lingli.variants, vec!["灵气", "真气"]);
+        save_term(&conn, term("灵气值", &[])).unwrap();
+        assert!(save_term(&conn, term("法力", &["灵力"])).is_err());
+
+        let violations = check(&conn, "p1", None).unwrap();
+        let found: Vec<(&str, usize)> = violations.iter().map(|v| (v.variant.as_str(), v.position)).collect();
+        assert_eq!(found, vec![("灵气", 3), ("真气", 0)]);
+        assert_eq!(violations[0].context_after, "，灵气值暴涨。");
+
+        let result = normalize(&conn, "p1", None, &[violations[1].id.clone()]).unwrap();
+        assert_eq!((result.replaced, result.chapters_changed.clone()), (1, vec!["c1".to_string()]));
+        assert!(result.restore_point_id.is_some());
+        let content: String = conn.query_row("SELECT content FROM chapters WHERE id = 'c1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(content, "他运转灵力，灵气值暴涨。");
+
+        let prompt = prompt_section(&conn, "p1").unwrap().unwrap();
+        assert!(prompt.contains("- 灵力（不要写作：灵气、真气）"));
+    }
+}
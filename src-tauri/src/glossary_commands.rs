@@ -0,0 +1,97 @@
+use crate::glossary::{self, GlossaryTerm};
+use crate::logger::Logger;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+#[tauri::command]
+pub async fn list_glossary_terms(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let terms = glossary::list_terms(&conn, &project_id)?;
+    serde_json::to_string(&terms).map_err(|e| e.to_string())
+}
+
+/// 新建或修改术语，`id` 为空时新建
+#[tauri::command]
+pub async fn save_glossary_term(
+    app: AppHandle,
+    term: GlossaryTerm,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let term = glossary::save_term(&conn, term)?;
+    serde_json::to_string(&term).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_glossary_term(
+    app: AppHandle,
+    term_id: String,
+) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    glossary::delete_term(&conn, &term_id)
+}
+
+/// 检查项目（或指定章节）中术语的非规范写法
+#[tauri::command]
+pub async fn check_glossary(
+    app: AppHandle,
+    project_id: String,
+    chapter_id: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let violations = glossary::check(&conn, &project_id, chapter_id.as_deref())?;
+    serde_json::to_string(&violations).map_err(|e| e.to_string())
+}
+
+/// 一键统一术语写法，`excluded_ids` 为检查结果中保留原样的几处。返回的还原点可用 `restore_snapshot` 撤销
+#[tauri::command]
+pub async fn normalize_glossary_terms(
+    app: AppHandle,
+    project_id: String,
+    term_ids: Option<Vec<String>>,
+    excluded_ids: Option<Vec<String>>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("glossary");
+    logger.info(&format!("Normalizing glossary terms in project {}", project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let result = glossary::normalize(&conn, &project_id, term_ids.as_deref(), &excluded_ids.unwrap_or_default())?;
+    for chapter_id in &result.chapters_changed {
+        if let Err(e) = crate::entity_index::index_chapter(&conn, chapter_id, None) {
+            logger.warn(&format!("Failed to index entities: {}", e));
+        }
+    }
+
+    logger.info(&format!("Normalized {} terms in {} chapters", result.replaced, result.chapters_changed.len()));
+    serde_json::to_string(&result).map_err(|e| e.to_string())
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
@@ -0,0 +1,236 @@
+use crate::chapter_branches::{self, BranchSnapshot};
+use crate::version_control_commands::load_snapshot;
+use chrono::{TimeZone, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const BUNDLE_FORMAT: &str = "ai-novel-studio/chapter-history";
+const BUNDLE_VERSION: u32 = 1;
+
+/// 项目快照中这一章的内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterRevision {
+    pub snapshot_id: String,
+    pub version: String,
+    pub description: String,
+    pub timestamp: i64,
+    pub auto_generated: bool,
+    pub tags: Vec<String>,
+    pub title: String,
+    pub content: String,
+}
+
+/// 章节的一个分支及其快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchHistory {
+    pub name: String,
+    pub base_content: String,
+    pub content: String,
+    pub snapshots: Vec<BranchSnapshot>,
+}
+
+/// 一个章节的完整版本历史，可以导入到其他安装或数据库
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryBundle {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: String,
+    pub chapter_id: String,
+    pub title: String,
+    pub revisions: Vec<ChapterRevision>,
+    pub branches: Vec<BranchHistory>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryImportResult {
+    pub revisions_imported: usize,
+    pub branches_created: usize,
+    pub branch_snapshots_imported: usize,
+    /// 之前已经导入过、被跳过的快照数
+    pub skipped: usize,
+}
+
+/// 收集章节在所有项目快照和分支中的历史
+pub fn collect_history(conn: &Connection, chapter_id: &str) -> Result<HistoryBundle, String> {
+    let (project_id, title): (String, String) = conn
+        .query_row("SELECT project_id, title FROM chapters WHERE id = ?", params![chapter_id], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .map_err(|_| "章节不存在".to_string())?;
+
+    let snapshot_ids: Vec<String> = conn
+        .prepare("SELECT id FROM project_snapshots WHERE project_id = ? ORDER BY timestamp")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let tags = crate::snapshot_tags::tags_by_snapshot(conn, &project_id)?;
+    let mut revisions = Vec::new();
+    for snapshot_id in snapshot_ids {
+        let snapshot = load_snapshot(conn, &snapshot_id)?;
+        let Some(chapter) = snapshot.chapters.into_iter().find(|c| c.id == chapter_id) else { continue };
+        revisions.push(ChapterRevision {
+            tags: tags.get(&snapshot.id).map(|t| t.iter().map(|t| t.tag.clone()).collect()).unwrap_or_default(),
+            snapshot_id: snapshot.id,
+            version: snapshot.version,
+            description: snapshot.description,
+            timestamp: snapshot.timestamp,
+            auto_generated: snapshot.metadata.auto_generated,
+            title: chapter.title,
+            content: chapter.content,
+        });
+    }
+
+    let mut branches = Vec::new();
+    for branch in chapter_branches::list_branches(conn, chapter_id)? {
+        branches.push(BranchHistory {
+            snapshots: chapter_branches::list_branch_snapshots(conn, &branch.id)?,
+            name: branch.name,
+            base_content: branch.base_content,
+            content: branch.content,
+        });
+    }
+
+    Ok(HistoryBundle {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        exported_at: Utc::now().to_rfc3339(),
+        chapter_id: chapter_id.to_string(),
+        title,
+        revisions,
+        branches,
+    })
+}
+
+/// 把历史写成 zstd 压缩的 JSON 文件
+pub fn write_bundle(bundle: &HistoryBundle, path: &Path) -> Result<u64, String> {
+    let json = serde_json::to_vec(bundle).map_err(|e| e.to_string())?;
+    let data = zstd::encode_all(json.as_slice(), 3).map_err(|e| e.to_string())?;
+    std::fs::write(path, &data).map_err(|e| format!("写入历史文件失败: {}", e))?;
+    Ok(data.len() as u64)
+}
+
+pub fn read_bundle(path: &Path) -> Result<HistoryBundle, String> {
+    let data = std::fs::read(path).map_err(|e| format!("读取历史文件失败: {}", e))?;
+    let json = zstd::decode_all(data.as_slice()).map_err(|_| "不是有效的版本历史文件".to_string())?;
+    let bundle: HistoryBundle = serde_json::from_slice(&json).map_err(|_| "不是有效的版本历史文件".to_string())?;
+    if bundle.format != BUNDLE_FORMAT {
+        return Err("不是有效的版本历史文件".to_string());
+    }
+    if bundle.version > BUNDLE_VERSION {
+        return Err(format!("版本历史文件的格式版本 {} 过新，请升级应用", bundle.version));
+    }
+    Ok(bundle)
+}
+
+fn insert_branch_snapshot(conn: &Connection, snapshot: &BranchSnapshot) -> Result<bool, String> {
+    let inserted = conn
+        .execute(
+            "INSERT OR IGNORE INTO chapter_branch_snapshots (id, branch_id, description, content, word_count, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+            params![snapshot.id, snapshot.branch_id, snapshot.description, snapshot.content, snapshot.word_count, snapshot.created_at],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(inserted > 0)
+}
+
+/// 把历史导入到 `chapter_id`，不改动章节当前正文。
+/// 项目快照中的各个版本成为主线分支上的快照，其他分支按名称合并或新建；重复导入不会产生重复的快照
+pub fn import_history(conn: &Connection, chapter_id: &str, bundle: &HistoryBundle) -> Result<HistoryImportResult, String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut result = HistoryImportResult::default();
+    let main = chapter_branches::main_branch(&tx, chapter_id)?;
+
+    for revision in &bundle.revisions {
+        let mut description = format!("{} · {}", revision.version, revision.description);
+        if !revision.tags.is_empty() {
+            description.push_str(&format!("（{}）", revision.tags.join("、")));
+        }
+        let created_at = Utc.timestamp_opt(revision.timestamp, 0).single().unwrap_or_else(Utc::now).to_rfc3339();
+        let snapshot = BranchSnapshot {
+            id: format!("{}:{}", revision.snapshot_id, bundle.chapter_id),
+            branch_id: main.id.clone(),
+            description,
+            word_count: revision.content.chars().count() as i64,
+            content: revision.content.clone(),
+            created_at,
+        };
+        if insert_branch_snapshot(&tx, &snapshot)? {
+            result.revisions_imported += 1;
+        } else {
+            result.skipped += 1;
+        }
+    }
+
+    let mut existing = chapter_branches::list_branches(&tx, chapter_id)?;
+    for history in &bundle.branches {
+        let branch = match existing.iter().find(|b| b.name == history.name) {
+            Some(branch) => branch.clone(),
+            None => {
+                let branch = chapter_branches::insert_inactive_branch(&tx, chapter_id, &history.name, &main.id, &history.base_content, &history.content)?;
+                result.branches_created += 1;
+                existing.push(branch.clone());
+                branch
+            }
+        };
+        for snapshot in &history.snapshots {
+            let snapshot = BranchSnapshot { branch_id: branch.id.clone(), ..snapshot.clone() };
+            if insert_branch_snapshot(&tx, &snapshot)? {
+                result.branch_snapshots_imported += 1;
+            } else {
+                result.skipped += 1;
+            }
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version_control_commands::save_project_snapshot;
+
+    fn open_db(dir: &Path, name: &str) -> Connection {
+        crate::test_support::init_project_db(&dir.join(name))
+    }
+
+    #[test]
+    fn test_history_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = open_db(dir.path(), "source.db");
+        source
+            .execute(
+                "INSERT INTO chapters (id, project_id, title, content, created_at, updated_at) VALUES ('c1', 'p1', '第一章', '初稿', 't0', 't0')",
+                [],
+            )
+            .unwrap();
+        save_project_snapshot(&source, "p1", "v1", "初稿", false).unwrap();
+        source.execute("UPDATE chapters SET content = '二稿' WHERE id = 'c1'", []).unwrap();
+        save_project_snapshot(&source, "p1", "v2", "二稿", false).unwrap();
+        let ending = chapter_branches::create_branch(&source, "c1", "另一种结局").unwrap();
+        chapter_branches::create_branch_snapshot(&source, &ending.id, "分支快照").unwrap();
+
+        let path = dir.path().join("c1.history");
+        write_bundle(&collect_history(&source, "c1").unwrap(), &path).unwrap();
+        let bundle = read_bundle(&path).unwrap();
+        assert_eq!(bundle.revisions.iter().map(|r| r.content.as_str()).collect::<Vec<_>>(), vec!["初稿", "二稿"]);
+
+        let target = open_db(dir.path(), "target.db");
+        target
+            .execute(
+                "INSERT INTO chapters (id, project_id, title, content, created_at, updated_at) VALUES ('x1', 'p1', '第一章', '二稿', 't0', 't0')",
+                [],
+            )
+            .unwrap();
+        let result = import_history(&target, "x1", &bundle).unwrap();
+        assert_eq!((result.revisions_imported, result.branches_created, result.branch_snapshots_imported), (2, 1, 1));
+        let branches = chapter_branches::list_branches(&target, "x1").unwrap();
+        assert_eq!(branches.iter().map(|b| b.name.as_str()).collect::<Vec<_>>(), vec![chapter_branches::MAIN_BRANCH, "另一种结局"]);
+        assert_eq!(chapter_branches::list_branch_snapshots(&target, &branches[0].id).unwrap().len(), 2);
+
+        let again = import_history(&target, "x1", &bundle).unwrap();
+        assert_eq!((again.revisions_imported, again.branch_snapshots_imported, again.skipped), (0, 0, 3));
+    }
+}
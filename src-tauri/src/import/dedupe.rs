@@ -0,0 +1,188 @@
+use super::ImportedChapter;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+/// 标题相似度达到该值即视为同一章
+const TITLE_SIMILARITY_THRESHOLD: f64 = 0.85;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateStrategy {
+    /// 保留项目中已有的章节
+    Skip,
+    /// 用导入的内容覆盖已有章节
+    Replace,
+    /// 仍作为新章节追加
+    Append,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateKind {
+    /// 正文完全相同
+    IdenticalContent,
+    SameTitle,
+    SimilarTitle,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExistingChapter {
+    pub id: String,
+    pub title: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateChapter {
+    /// 导入结果中的章节序号
+    pub index: usize,
+    pub title: String,
+    pub existing_id: String,
+    pub existing_title: String,
+    pub kind: DuplicateKind,
+    pub title_similarity: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChapterImportAction {
+    Insert,
+    Skip { existing_id: String },
+    Replace { existing_id: String },
+}
+
+fn normalize_title(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, ':' | '：' | '·' | '-' | '—' | '_' | '.' | '、'))
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// 忽略空白差异后的正文哈希
+pub fn content_hash(content: &str) -> String {
+    let normalized: String = content.chars().filter(|c| !c.is_whitespace()).collect();
+    let digest = Sha256::digest(normalized.as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 基于字符二元组的 Dice 系数，适合中文短标题
+fn title_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalize_title(a), normalize_title(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+    let bigrams = |s: &str| -> HashSet<(char, char)> {
+        let chars: Vec<char> = s.chars().collect();
+        chars.windows(2).map(|w| (w[0], w[1])).collect()
+    };
+    let (x, y) = (bigrams(&a), bigrams(&b));
+    if x.is_empty() || y.is_empty() {
+        return 0.0;
+    }
+    2.0 * x.intersection(&y).count() as f64 / (x.len() + y.len()) as f64
+}
+
+/// 找出与项目中已有章节重复的导入章节；每个已有章节最多匹配一次
+pub fn find_duplicates(chapters: &[ImportedChapter], existing: &[ExistingChapter]) -> Vec<DuplicateChapter> {
+    let existing_hashes: Vec<String> = existing.iter().map(|e| content_hash(&e.content)).collect();
+    let mut matched: HashSet<usize> = HashSet::new();
+    let mut duplicates = Vec::new();
+
+    for (index, chapter) in chapters.iter().enumerate() {
+        let hash = content_hash(&chapter.content);
+        let identical = existing_hashes
+            .iter()
+            .enumerate()
+            .find(|(i, h)| !matched.contains(i) && **h == hash && !chapter.content.trim().is_empty())
+            .map(|(i, _)| (i, DuplicateKind::IdenticalContent, title_similarity(&chapter.title, &existing[i].title)));
+
+        let by_title = || {
+            existing
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !matched.contains(i))
+                .map(|(i, e)| (i, title_similarity(&chapter.title, &e.title)))
+                .filter(|(_, s)| *s >= TITLE_SIMILARITY_THRESHOLD)
+                .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+                .map(|(i, s)| (i, if s >= 1.0 { DuplicateKind::SameTitle } else { DuplicateKind::SimilarTitle }, s))
+        };
+
+        if let Some((i, kind, similarity)) = identical.or_else(by_title) {
+            matched.insert(i);
+            duplicates.push(DuplicateChapter {
+                index,
+                title: chapter.title.clone(),
+                existing_id: existing[i].id.clone(),
+                existing_title: existing[i].title.clone(),
+                kind,
+                title_similarity: similarity,
+            });
+        }
+    }
+    duplicates
+}
+
+/// 根据重复检测结果和策略决定每个导入章节的处理方式。
+/// 正文完全相同的章节无论何种策略都跳过
+pub fn plan_chapter_actions(
+    chapter_count: usize,
+    duplicates: &[DuplicateChapter],
+    strategy: DuplicateStrategy,
+) -> Vec<ChapterImportAction> {
+    (0..chapter_count)
+        .map(|index| match duplicates.iter().find(|d| d.index == index) {
+            None => ChapterImportAction::Insert,
+            Some(d) => match (strategy, d.kind) {
+                (_, DuplicateKind::IdenticalContent) | (DuplicateStrategy::Skip, _) => {
+                    ChapterImportAction::Skip { existing_id: d.existing_id.clone() }
+                }
+                (DuplicateStrategy::Replace, _) => ChapterImportAction::Replace { existing_id: d.existing_id.clone() },
+                (DuplicateStrategy::Append, _) => ChapterImportAction::Insert,
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn imported(title: &str, content: &str) -> ImportedChapter {
+        ImportedChapter { title: title.to_string(), content: content.to_string(), word_count: content.chars().count() }
+    }
+
+    fn existing(id: &str, title: &str, content: &str) -> ExistingChapter {
+        ExistingChapter { id: id.to_string(), title: title.to_string(), content: content.to_string() }
+    }
+
+    #[test]
+    fn test_detects_by_hash_and_title() {
+        let chapters = vec![
+            imported("第一章 雨夜", "窗外下着雨。\n他推门而入。"),
+            imported("第二章：黎明之前", "修订后的第二章。"),
+            imported("第三章 新的开始", "全新内容。"),
+        ];
+        let project = vec![
+            existing("a", "雨夜", "窗外下着雨。 他推门而入。"),
+            existing("b", "第二章 黎明之前", "旧的第二章。"),
+        ];
+        let duplicates = find_duplicates(&chapters, &project);
+        assert_eq!(duplicates.len(), 2);
+        assert_eq!(duplicates[0].kind, DuplicateKind::IdenticalContent);
+        assert_eq!(duplicates[1].kind, DuplicateKind::SameTitle);
+        assert_eq!(duplicates[1].existing_id, "b");
+
+        let actions = plan_chapter_actions(chapters.len(), &duplicates, DuplicateStrategy::Replace);
+        assert_eq!(actions[0], ChapterImportAction::Skip { existing_id: "a".into() });
+        assert_eq!(actions[1], ChapterImportAction::Replace { existing_id: "b".into() });
+        assert_eq!(actions[2], ChapterImportAction::Insert);
+
+        let actions = plan_chapter_actions(chapters.len(), &duplicates, DuplicateStrategy::Append);
+        assert_eq!(actions[0], ChapterImportAction::Skip { existing_id: "a".into() });
+        assert_eq!(actions[1], ChapterImportAction::Insert);
+    }
+}
@@ -0,0 +1,214 @@
+use super::{ChapterSplitRules, ImportFormat, ImportResult};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+/// 未指定 pattern 时导入的文件类型
+const DEFAULT_EXTENSIONS: &[&str] = &["txt", "md", "markdown", "docx"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DirectoryImportMode {
+    /// 所有文件按文件名顺序作为章节导入同一个项目
+    #[default]
+    Chapters,
+    /// 每个文件创建一个项目
+    ProjectPerFile,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportDirectoryRequest {
+    pub path: String,
+    /// 文件名通配符，如 `*.txt`、`第*章.md`，为空时导入全部 txt/md/docx
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub mode: DirectoryImportMode,
+    /// Chapters 模式下的目标项目
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub encoding: Option<String>,
+    #[serde(default)]
+    pub split_rules: Option<ChapterSplitRules>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryFileResult {
+    pub file_name: String,
+    pub chapter_count: usize,
+    pub word_count: usize,
+    pub project_id: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DirectoryImportResult {
+    /// 所有文件合并后的导入结果
+    pub result: ImportResult,
+    pub files: Vec<DirectoryFileResult>,
+}
+
+/// 列出目录下（不递归）符合条件的文件，按文件名自然排序
+pub fn list_import_files(dir: &Path, pattern: Option<&str>) -> Result<Vec<PathBuf>> {
+    let pattern = pattern.map(str::trim).filter(|p| !p.is_empty());
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("无法读取目录: {:?}", dir))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else { return false };
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+            match pattern {
+                Some(pattern) => wildcard_match(&pattern.to_lowercase(), &name.to_lowercase())
+                    && ImportFormat::from_extension(&ext).is_some(),
+                None => DEFAULT_EXTENSIONS.contains(&ext.as_str()),
+            }
+        })
+        .collect();
+
+    files.sort_by(|a, b| {
+        let name = |p: &PathBuf| p.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string();
+        natural_cmp(&name(a), &name(b))
+    });
+    Ok(files)
+}
+
+/// 支持 `*` 和 `?` 的文件名通配
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[derive(Debug, PartialEq)]
+enum NameToken {
+    Number(u64),
+    Text(String),
+}
+
+fn chinese_digit(c: char) -> Option<u64> {
+    "零一二三四五六七八九".chars().position(|d| d == c).map(|v| v as u64).or(match c {
+        '〇' => Some(0),
+        '两' => Some(2),
+        _ => None,
+    })
+}
+
+pub(crate) fn is_chinese_numeral(c: char) -> bool {
+    chinese_digit(c).is_some() || matches!(c, '十' | '百' | '千' | '万')
+}
+
+/// 解析「十二」「一百零五」「二〇二四」一类的中文数字
+pub(crate) fn parse_chinese_number(text: &str) -> u64 {
+    let has_unit = text.chars().any(|c| matches!(c, '十' | '百' | '千' | '万'));
+    if !has_unit {
+        return text.chars().fold(0, |acc, c| acc * 10 + chinese_digit(c).unwrap_or(0));
+    }
+
+    let (mut total, mut section, mut digit) = (0u64, 0u64, 0u64);
+    for c in text.chars() {
+        match c {
+            '十' | '百' | '千' => {
+                let unit = match c {
+                    '十' => 10,
+                    '百' => 100,
+                    _ => 1000,
+                };
+                section += digit.max(1) * unit;
+                digit = 0;
+            }
+            '万' => {
+                total += (section + digit) * 10_000;
+                section = 0;
+                digit = 0;
+            }
+            _ => digit = chinese_digit(c).unwrap_or(0),
+        }
+    }
+    total + section + digit
+}
+
+fn tokenize_name(name: &str) -> Vec<NameToken> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = name.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        if chars[i].is_ascii_digit() {
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let digits: String = chars[start..i].iter().collect();
+            tokens.push(NameToken::Number(digits.parse().unwrap_or(u64::MAX)));
+        } else if is_chinese_numeral(chars[i]) {
+            while i < chars.len() && is_chinese_numeral(chars[i]) {
+                i += 1;
+            }
+            let numeral: String = chars[start..i].iter().collect();
+            tokens.push(NameToken::Number(parse_chinese_number(&numeral)));
+        } else {
+            while i < chars.len() && !chars[i].is_ascii_digit() && !is_chinese_numeral(chars[i]) {
+                i += 1;
+            }
+            tokens.push(NameToken::Text(chars[start..i].iter().collect::<String>().to_lowercase()));
+        }
+    }
+    tokens
+}
+
+/// 自然排序：数字（包括中文数字）按数值比较，「第2章」排在「第10章」之前
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (ta, tb) = (tokenize_name(a), tokenize_name(b));
+    for (x, y) in ta.iter().zip(tb.iter()) {
+        let ordering = match (x, y) {
+            (NameToken::Number(x), NameToken::Number(y)) => x.cmp(y),
+            (NameToken::Text(x), NameToken::Text(y)) => x.cmp(y),
+            (NameToken::Number(_), NameToken::Text(_)) => Ordering::Less,
+            (NameToken::Text(_), NameToken::Number(_)) => Ordering::Greater,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    ta.len().cmp(&tb.len()).then_with(|| a.cmp(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_order() {
+        let mut names = vec!["第十章.txt", "第2章.txt", "第一章.txt", "chapter10.md", "chapter9.md", "第一百零五章.txt"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(names, vec!["chapter9.md", "chapter10.md", "第一章.txt", "第2章.txt", "第十章.txt", "第一百零五章.txt"]);
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.txt", "第一章.txt"));
+        assert!(wildcard_match("第*章.md", "第12章.md"));
+        assert!(wildcard_match("ch??.docx", "ch01.docx"));
+        assert!(!wildcard_match("*.txt", "notes.md"));
+    }
+}
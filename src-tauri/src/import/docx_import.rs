@@ -1,4 +1,4 @@
-use super::{ImportFormat, ImportResult, ImportedChapter};
+use super::{ImportFormat, ImportMonitor, ImportResult, ImportedChapter};
 use anyhow::{Context, Result};
 use std::fs::File;
 use std::io::{BufReader, Read};
@@ -8,7 +8,7 @@ use quick_xml::Reader;
 use quick_xml::events::Event;
 use regex::Regex;
 
-pub fn import_from_docx(file_path: &Path) -> Result<ImportResult> {
+pub fn import_from_docx(file_path: &Path, monitor: &ImportMonitor) -> Result<ImportResult> {
     let file = File::open(file_path)
         .with_context(|| format!("无法打开 DOCX 文件: {:?}", file_path))?;
     
@@ -18,8 +18,11 @@ pub fn import_from_docx(file_path: &Path) -> Result<ImportResult> {
     let document_xml = archive.by_name("word/document.xml")
         .with_context(|| "DOCX 文件中未找到 document.xml")?;
     
+    let document_size = document_xml.size();
+    monitor.set_total(document_size);
     let mut content = String::new();
-    parse_docx_content(document_xml, &mut content)?;
+    parse_docx_content(monitor.reader(document_xml), &mut content)?;
+    monitor.check_cancelled()?;
     
     let filename = file_path
         .file_stem()
@@ -28,6 +31,7 @@ pub fn import_from_docx(file_path: &Path) -> Result<ImportResult> {
         .to_string();
     
     let chapters = parse_txt_style_chapters(&content);
+    monitor.report("splitting", document_size, chapters.len());
     let chapter_count = chapters.len();
     let word_count: usize = chapters.iter().map(|c| c.word_count).sum();
     
@@ -43,6 +47,8 @@ pub fn import_from_docx(file_path: &Path) -> Result<ImportResult> {
         } else {
             Some("文件内容将作为单章节导入".to_string())
         },
+        warnings: Vec::new(),
+        encoding: None,
     })
 }
 
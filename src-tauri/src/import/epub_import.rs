@@ -0,0 +1,488 @@
+use super::{ImportMonitor, ImportResult, ImportedChapter};
+use anyhow::{anyhow, Context, Result};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+use zip::ZipArchive;
+
+struct ManifestItem {
+    href: String,
+    media_type: String,
+    properties: String,
+}
+
+struct OpfPackage {
+    title: Option<String>,
+    manifest: HashMap<String, ManifestItem>,
+    spine: Vec<String>,
+    toc_id: Option<String>,
+}
+
+pub fn import_from_epub(file_path: &Path, monitor: &ImportMonitor) -> Result<ImportResult> {
+    let file = File::open(file_path)
+        .with_context(|| format!("无法打开 EPUB 文件: {:?}", file_path))?;
+
+    let mut archive = ZipArchive::new(file)
+        .with_context(|| "无法解压 EPUB 文件，请确保文件格式正确")?;
+
+    let container = read_entry(&mut archive, "META-INF/container.xml")
+        .with_context(|| "EPUB 文件中未找到 META-INF/container.xml")?;
+    let opf_path = find_rootfile(&container)
+        .ok_or_else(|| anyhow!("EPUB container.xml 中未找到 OPF 路径"))?;
+    let opf_dir = match opf_path.rfind('/') {
+        Some(i) => opf_path[..=i].to_string(),
+        None => String::new(),
+    };
+
+    let opf = read_entry(&mut archive, &opf_path)
+        .with_context(|| format!("EPUB 文件中未找到 {}", opf_path))?;
+    let package = parse_opf(&opf)?;
+
+    let toc_titles = load_toc_titles(&mut archive, &package, &opf_dir);
+
+    // 按正文各 XHTML 解压后的大小汇报进度
+    let spine_hrefs: Vec<String> = package
+        .spine
+        .iter()
+        .filter_map(|idref| package.manifest.get(idref))
+        .filter(|item| item.media_type.contains("html"))
+        .map(|item| resolve_href(&opf_dir, &item.href))
+        .collect();
+    monitor.set_total(
+        spine_hrefs
+            .iter()
+            .filter_map(|href| archive.by_name(href).ok().map(|e| e.size()))
+            .sum(),
+    );
+    let mut bytes_read = 0u64;
+
+    let mut chapters = Vec::new();
+    for href in spine_hrefs {
+        monitor.check_cancelled()?;
+        let Ok(xhtml) = read_entry(&mut archive, &href) else {
+            continue;
+        };
+        bytes_read += xhtml.len() as u64;
+        monitor.report("parsing", bytes_read, chapters.len());
+        let (heading, paragraphs) = extract_xhtml_text(&xhtml);
+        let content = paragraphs.join("\n");
+        if content.trim().is_empty() {
+            continue;
+        }
+
+        let title = toc_titles
+            .get(&href)
+            .cloned()
+            .or(heading)
+            .unwrap_or_else(|| format!("第{}章", chapters.len() + 1));
+
+        // 正文第一段与标题相同时去掉，避免重复
+        let content = match paragraphs.first() {
+            Some(first) if first.trim() == title.trim() => paragraphs[1..].join("\n"),
+            _ => content,
+        };
+
+        chapters.push(ImportedChapter {
+            title,
+            word_count: content.chars().count(),
+            content,
+        });
+    }
+
+    let title = package.title.unwrap_or_else(|| {
+        file_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("未命名")
+            .to_string()
+    });
+
+    let content = chapters
+        .iter()
+        .map(|c| format!("{}\n{}", c.title, c.content))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let chapter_count = chapters.len();
+    let word_count: usize = chapters.iter().map(|c| c.word_count).sum();
+
+    Ok(ImportResult {
+        success: chapter_count > 0,
+        title,
+        content,
+        chapter_count,
+        word_count,
+        chapters,
+        message: if chapter_count > 0 {
+            Some(format!("成功解析 {} 个章节", chapter_count))
+        } else {
+            Some("EPUB 中没有可导入的正文".to_string())
+        },
+        warnings: Vec::new(),
+        encoding: None,
+    })
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<String> {
+    let mut entry = archive.by_name(name)?;
+    let mut data = String::new();
+    entry.read_to_string(&mut data)?;
+    Ok(data)
+}
+
+/// 把 OPF 中相对路径的 href 解析为压缩包内路径，去掉锚点并解码 URL 转义
+fn resolve_href(base_dir: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or("");
+    let href = urlencoding::decode(href).map(|s| s.into_owned()).unwrap_or_else(|_| href.to_string());
+
+    let mut parts: Vec<&str> = base_dir.split('/').filter(|p| !p.is_empty()).collect();
+    for segment in href.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(segment),
+        }
+    }
+    parts.join("/")
+}
+
+fn attr_value(e: &quick_xml::events::BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+}
+
+fn find_rootfile(container: &str) -> Option<String> {
+    let mut reader = Reader::from_str(container);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) if e.local_name().as_ref() == b"rootfile" => {
+                return attr_value(e, b"full-path");
+            }
+            Ok(Event::Eof) | Err(_) => return None,
+            _ => {}
+        }
+    }
+}
+
+fn parse_opf(opf: &str) -> Result<OpfPackage> {
+    let mut reader = Reader::from_str(opf);
+    reader.config_mut().trim_text(true);
+
+    let mut package = OpfPackage {
+        title: None,
+        manifest: HashMap::new(),
+        spine: Vec::new(),
+        toc_id: None,
+    };
+    let mut in_title = false;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => match e.local_name().as_ref() {
+                b"title" if package.title.is_none() => in_title = true,
+                b"item" => {
+                    if let (Some(id), Some(href)) = (attr_value(e, b"id"), attr_value(e, b"href")) {
+                        package.manifest.insert(id, ManifestItem {
+                            href,
+                            media_type: attr_value(e, b"media-type").unwrap_or_default(),
+                            properties: attr_value(e, b"properties").unwrap_or_default(),
+                        });
+                    }
+                }
+                b"spine" => package.toc_id = attr_value(e, b"toc"),
+                b"itemref" => {
+                    let linear = attr_value(e, b"linear").unwrap_or_default();
+                    if let (Some(idref), false) = (attr_value(e, b"idref"), linear == "no") {
+                        package.spine.push(idref);
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::Text(ref t)) if in_title => {
+                let title = t.unescape().map(|s| s.trim().to_string()).unwrap_or_default();
+                if !title.is_empty() {
+                    package.title = Some(title);
+                }
+            }
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"title" => in_title = false,
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow!("解析 EPUB OPF 时出错: {:?}", e)),
+            _ => {}
+        }
+    }
+
+    if package.spine.is_empty() {
+        return Err(anyhow!("EPUB 文件缺少阅读顺序（spine）"));
+    }
+    Ok(package)
+}
+
+/// 从 EPUB3 nav 文档或 EPUB2 NCX 读取目录标题，键为章节文件路径
+fn load_toc_titles<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    package: &OpfPackage,
+    opf_dir: &str,
+) -> HashMap<String, String> {
+    let nav_item = package.manifest.values().find(|item| item.properties.split_whitespace().any(|p| p == "nav"));
+    let ncx_item = package
+        .toc_id
+        .as_ref()
+        .and_then(|id| package.manifest.get(id))
+        .or_else(|| package.manifest.values().find(|item| item.media_type == "application/x-dtbncx+xml"));
+
+    let (item, is_ncx) = match (nav_item, ncx_item) {
+        (Some(nav), _) => (nav, false),
+        (None, Some(ncx)) => (ncx, true),
+        (None, None) => return HashMap::new(),
+    };
+
+    let toc_path = resolve_href(opf_dir, &item.href);
+    let toc_dir = match toc_path.rfind('/') {
+        Some(i) => toc_path[..=i].to_string(),
+        None => String::new(),
+    };
+    let Ok(toc) = read_entry(archive, &toc_path) else {
+        return HashMap::new();
+    };
+
+    let mut titles = HashMap::new();
+    let mut reader = Reader::from_str(&toc);
+    reader.config_mut().check_end_names = false;
+
+    // NCX: <navPoint><navLabel><text>标题</text></navLabel><content src="..."/></navPoint>
+    // nav: <a href="...">标题</a>
+    let mut pending_label: Option<String> = None;
+    let mut current_href: Option<String> = None;
+    let mut capture = false;
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(ref e)) => match e.local_name().as_ref() {
+                b"text" if is_ncx => {
+                    capture = true;
+                    text.clear();
+                }
+                b"a" if !is_ncx => {
+                    current_href = attr_value(e, b"href");
+                    capture = true;
+                    text.clear();
+                }
+                _ => {}
+            },
+            Ok(Event::Empty(ref e)) if is_ncx && e.local_name().as_ref() == b"content" => {
+                if let (Some(src), Some(label)) = (attr_value(e, b"src"), pending_label.take()) {
+                    titles.entry(resolve_href(&toc_dir, &src)).or_insert(label);
+                }
+            }
+            Ok(Event::Text(ref t)) if capture => {
+                text.push_str(&t.unescape().map(|s| s.into_owned()).unwrap_or_default());
+            }
+            Ok(Event::End(ref e)) => match e.local_name().as_ref() {
+                b"text" if is_ncx && capture => {
+                    capture = false;
+                    pending_label = Some(text.trim().to_string());
+                }
+                b"a" if !is_ncx && capture => {
+                    capture = false;
+                    if let Some(href) = current_href.take() {
+                        let label = text.trim().to_string();
+                        if !label.is_empty() {
+                            titles.entry(resolve_href(&toc_dir, &href)).or_insert(label);
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+
+    titles
+}
+
+/// 提取 XHTML 正文：块级元素各成一段，同时返回第一个 h1-h3 标题
+fn extract_xhtml_text(xhtml: &str) -> (Option<String>, Vec<String>) {
+    let mut reader = Reader::from_str(xhtml);
+    reader.config_mut().check_end_names = false;
+
+    let mut paragraphs = Vec::new();
+    let mut current = String::new();
+    let mut heading: Option<String> = None;
+    let mut skip_depth = 0usize;
+    let mut in_body = !xhtml.contains("<body");
+
+    let flush = |current: &mut String, paragraphs: &mut Vec<String>| {
+        let text = current.trim();
+        if !text.is_empty() {
+            paragraphs.push(text.to_string());
+        }
+        current.clear();
+    };
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(ref e)) => match e.local_name().as_ref() {
+                b"body" => in_body = true,
+                b"script" | b"style" | b"head" => skip_depth += 1,
+                b"h1" | b"h2" | b"h3" => {
+                    flush(&mut current, &mut paragraphs);
+                }
+                b"p" | b"div" | b"li" | b"h4" | b"h5" | b"h6" | b"blockquote" | b"section" => {
+                    flush(&mut current, &mut paragraphs);
+                }
+                _ => {}
+            },
+            Ok(Event::Empty(ref e)) if e.local_name().as_ref() == b"br" => {
+                flush(&mut current, &mut paragraphs);
+            }
+            Ok(Event::End(ref e)) => match e.local_name().as_ref() {
+                b"script" | b"style" | b"head" => skip_depth = skip_depth.saturating_sub(1),
+                b"h1" | b"h2" | b"h3" => {
+                    let text = current.trim().to_string();
+                    if heading.is_none() && !text.is_empty() {
+                        heading = Some(text);
+                    }
+                    flush(&mut current, &mut paragraphs);
+                }
+                b"p" | b"div" | b"li" | b"h4" | b"h5" | b"h6" | b"blockquote" | b"section" => {
+                    flush(&mut current, &mut paragraphs);
+                }
+                _ => {}
+            },
+            Ok(Event::Text(ref t)) if in_body && skip_depth == 0 => {
+                let raw = String::from_utf8_lossy(t.as_ref()).into_owned();
+                current.push_str(&decode_entities(&raw));
+            }
+            Ok(Event::CData(ref t)) if in_body && skip_depth == 0 => {
+                current.push_str(&String::from_utf8_lossy(t.as_ref()));
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    flush(&mut current, &mut paragraphs);
+
+    let paragraphs = paragraphs
+        .into_iter()
+        .map(|p| p.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|p| !p.is_empty())
+        .collect();
+    (heading, paragraphs)
+}
+
+/// XHTML 中常见的实体（quick-xml 只识别 XML 预定义实体）
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start..];
+        let Some(end) = after.find(';').filter(|&i| i <= 10) else {
+            result.push('&');
+            rest = &after[1..];
+            continue;
+        };
+        let entity = &after[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" | "ensp" | "emsp" | "thinsp" => Some(' '),
+            "hellip" => Some('…'),
+            "mdash" => Some('—'),
+            "ndash" => Some('–'),
+            "ldquo" => Some('“'),
+            "rdquo" => Some('”'),
+            "lsquo" => Some('‘'),
+            "rsquo" => Some('’'),
+            "middot" => Some('·'),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                result.push(c);
+                rest = &after[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &after[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn build_epub(path: &Path) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        let options = SimpleFileOptions::default();
+        let files = [
+            ("mimetype", "application/epub+zip".to_string()),
+            (
+                "META-INF/container.xml",
+                r#"<?xml version="1.0"?><container xmlns="urn:oasis:names:tc:opendocument:xmlns:container" version="1.0"><rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles></container>"#.to_string(),
+            ),
+            (
+                "OEBPS/content.opf",
+                r#"<?xml version="1.0"?><package xmlns="http://www.idpf.org/2007/opf" version="2.0"><metadata xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:title>星辰之海</dc:title></metadata><manifest><item id="ncx" href="toc.ncx" media-type="application/x-dtbncx+xml"/><item id="c2" href="text/ch2.xhtml" media-type="application/xhtml+xml"/><item id="c1" href="text/ch1.xhtml" media-type="application/xhtml+xml"/></manifest><spine toc="ncx"><itemref idref="c1"/><itemref idref="c2"/></spine></package>"#.to_string(),
+            ),
+            (
+                "OEBPS/toc.ncx",
+                r#"<?xml version="1.0"?><ncx><navMap><navPoint id="n1"><navLabel><text>第一章 启航</text></navLabel><content src="text/ch1.xhtml"/></navPoint></navMap></ncx>"#.to_string(),
+            ),
+            (
+                "OEBPS/text/ch1.xhtml",
+                "<html><head><title>x</title></head><body><h1>第一章 启航</h1><p>海风&nbsp;吹过。</p><p>船出发了。</p></body></html>".to_string(),
+            ),
+            (
+                "OEBPS/text/ch2.xhtml",
+                "<html><body><h2>第二章 风暴</h2><p>雷声&#x4E00;响。</p></body></html>".to_string(),
+            ),
+        ];
+        for (name, data) in files {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(data.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_import_epub_follows_spine_and_toc() {
+        let path = std::env::temp_dir().join(format!("epub_import_{}.epub", uuid::Uuid::new_v4()));
+        build_epub(&path);
+        let result = import_from_epub(&path, &ImportMonitor::default()).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(result.title, "星辰之海");
+        assert_eq!(result.chapter_count, 2);
+        assert_eq!(result.chapters[0].title, "第一章 启航");
+        assert_eq!(result.chapters[0].content, "海风 吹过。\n船出发了。");
+        assert_eq!(result.chapters[1].title, "第二章 风暴");
+        assert_eq!(result.chapters[1].content, "雷声一响。");
+    }
+}
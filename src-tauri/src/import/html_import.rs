@@ -0,0 +1,362 @@
+use super::{ImportMonitor, ImportResult, ImportedChapter};
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use scraper::{ElementRef, Html, Node, Selector};
+use std::path::Path;
+
+/// 导航、评论、广告等与正文无关的标签
+const SKIP_TAGS: &[&str] = &[
+    "script", "style", "noscript", "nav", "header", "footer", "aside", "form", "iframe", "button",
+    "select", "template", "svg",
+];
+
+/// class / id 中包含这些关键词的元素视为页面模板
+const BOILERPLATE_HINTS: &[&str] = &[
+    "comment", "sidebar", "menu", "navbar", "breadcrumb", "footer", "header", "share", "advert",
+    "banner", "related", "recommend", "copyright", "pagination", "toolbar",
+];
+
+const BLOCK_TAGS: &[&str] = &[
+    "p", "div", "li", "blockquote", "section", "article", "main", "pre", "tr", "dd", "dt", "ul", "ol",
+    "table", "figure",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum HtmlBlock {
+    Heading(u8, String),
+    Paragraph(String),
+}
+
+pub fn import_from_html(file_path: &Path, monitor: &ImportMonitor) -> Result<ImportResult> {
+    let bytes = monitor.read_file(file_path)
+        .with_context(|| format!("无法读取 HTML 文件: {:?}", file_path))?;
+    let raw = String::from_utf8_lossy(&bytes).into_owned();
+
+    let is_archive = file_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| matches!(e.to_lowercase().as_str(), "mht" | "mhtml"))
+        .unwrap_or(false);
+    let html = if is_archive { extract_mhtml_document(&raw)? } else { raw };
+
+    let filename = file_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("未命名")
+        .to_string();
+
+    let document = Html::parse_document(&html);
+    let title = page_title(&document).unwrap_or(filename);
+    let blocks = extract_blocks(&document);
+    let content = blocks_to_markdown(&blocks);
+    let chapters = split_html_chapters(&blocks, &title);
+    monitor.report("splitting", bytes.len() as u64, chapters.len());
+
+    let chapter_count = chapters.len();
+    let word_count: usize = chapters.iter().map(|c| c.word_count).sum();
+
+    Ok(ImportResult {
+        success: chapter_count > 0,
+        title,
+        content,
+        chapter_count,
+        word_count,
+        chapters,
+        message: if chapter_count > 0 {
+            Some(format!("成功解析 {} 个章节", chapter_count))
+        } else {
+            Some("网页中没有识别到正文".to_string())
+        },
+        warnings: Vec::new(),
+        encoding: None,
+    })
+}
+
+/// 单个章节页面：返回页面中的第一个标题（没有时用 <title>）和 Markdown 正文
+pub(super) fn html_to_chapter(html: &str) -> (Option<String>, String) {
+    let document = Html::parse_document(html);
+    let mut blocks = extract_blocks(&document);
+    let heading = blocks.iter().position(|b| matches!(b, HtmlBlock::Heading(..)));
+    let title = match heading {
+        Some(index) => match blocks.remove(index) {
+            HtmlBlock::Heading(_, text) => Some(text),
+            HtmlBlock::Paragraph(_) => None,
+        },
+        None => page_title(&document),
+    };
+    (title, blocks_to_markdown(&blocks))
+}
+
+fn page_title(document: &Html) -> Option<String> {
+    let selector = Selector::parse("title").unwrap();
+    document
+        .select(&selector)
+        .next()
+        .map(|t| collapse_whitespace(&t.text().collect::<String>()))
+        .filter(|t| !t.is_empty())
+}
+
+/// 优先使用 article / main 等正文容器，但容器文字过少时退回整个 body
+fn content_root(document: &Html) -> ElementRef<'_> {
+    let body_selector = Selector::parse("body").unwrap();
+    let body = document.select(&body_selector).next().unwrap_or_else(|| document.root_element());
+    let body_len = text_len(body);
+
+    for candidate in ["article", "main", "[role=main]", "#content", "#chapter", ".chapter", ".content"] {
+        let selector = Selector::parse(candidate).unwrap();
+        let best = document.select(&selector).max_by_key(|e| text_len(*e));
+        if let Some(element) = best {
+            if text_len(element) * 10 >= body_len * 3 {
+                return element;
+            }
+        }
+    }
+    body
+}
+
+fn text_len(element: ElementRef) -> usize {
+    element.text().map(|t| t.trim().chars().count()).sum()
+}
+
+fn is_boilerplate(element: ElementRef) -> bool {
+    let value = element.value();
+    if SKIP_TAGS.contains(&value.name()) {
+        return true;
+    }
+    let markers = value
+        .id()
+        .into_iter()
+        .chain(value.classes())
+        .map(|m| m.to_lowercase())
+        .collect::<Vec<_>>();
+    markers.iter().any(|m| BOILERPLATE_HINTS.iter().any(|hint| m.contains(hint)))
+}
+
+fn extract_blocks(document: &Html) -> Vec<HtmlBlock> {
+    let mut blocks = Vec::new();
+    let mut current = String::new();
+    collect_blocks(content_root(document), &mut blocks, &mut current);
+    flush_paragraph(&mut current, &mut blocks);
+    blocks
+}
+
+fn flush_paragraph(current: &mut String, blocks: &mut Vec<HtmlBlock>) {
+    let text = collapse_whitespace(current);
+    if !text.is_empty() {
+        blocks.push(HtmlBlock::Paragraph(text));
+    }
+    current.clear();
+}
+
+fn collect_blocks(element: ElementRef, blocks: &mut Vec<HtmlBlock>, current: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => current.push_str(text),
+            Node::Element(_) => {
+                let Some(child_element) = ElementRef::wrap(child) else { continue };
+                if is_boilerplate(child_element) {
+                    continue;
+                }
+                let name = child_element.value().name();
+                if let Some(level) = heading_level(name) {
+                    flush_paragraph(current, blocks);
+                    let text = collapse_whitespace(&child_element.text().collect::<String>());
+                    if !text.is_empty() {
+                        blocks.push(HtmlBlock::Heading(level, text));
+                    }
+                } else if name == "br" || name == "hr" {
+                    flush_paragraph(current, blocks);
+                } else if BLOCK_TAGS.contains(&name) {
+                    flush_paragraph(current, blocks);
+                    collect_blocks(child_element, blocks, current);
+                    flush_paragraph(current, blocks);
+                } else {
+                    collect_blocks(child_element, blocks, current);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn heading_level(name: &str) -> Option<u8> {
+    match name {
+        "h1" => Some(1),
+        "h2" => Some(2),
+        "h3" => Some(3),
+        "h4" => Some(4),
+        "h5" => Some(5),
+        "h6" => Some(6),
+        _ => None,
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn blocks_to_markdown(blocks: &[HtmlBlock]) -> String {
+    blocks
+        .iter()
+        .map(|block| match block {
+            HtmlBlock::Heading(level, text) => format!("{} {}", "#".repeat(*level as usize), text),
+            HtmlBlock::Paragraph(text) => text.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// 以出现两次以上的最高级标题作为章节边界；
+/// 只有一个标题时整页为一章，没有标题时按正文中的「第X章」切分
+fn split_html_chapters(blocks: &[HtmlBlock], page_title: &str) -> Vec<ImportedChapter> {
+    let levels: Vec<u8> = blocks
+        .iter()
+        .filter_map(|b| match b {
+            HtmlBlock::Heading(level, _) => Some(*level),
+            _ => None,
+        })
+        .collect();
+    let chapter_level = (1..=6u8)
+        .find(|l| levels.iter().filter(|x| *x == l).count() >= 2)
+        .or_else(|| levels.iter().min().copied());
+
+    let Some(chapter_level) = chapter_level else {
+        let text = blocks_to_markdown(blocks);
+        let chapters = super::txt_import::parse_txt_chapters(&text);
+        if chapters.len() > 1 || text.trim().is_empty() {
+            return chapters;
+        }
+        return vec![ImportedChapter {
+            title: page_title.to_string(),
+            word_count: text.chars().count(),
+            content: text,
+        }];
+    };
+
+    let mut chapters = Vec::new();
+    let mut title: Option<String> = None;
+    let mut body: Vec<HtmlBlock> = Vec::new();
+    let push_chapter = |title: Option<String>, body: &mut Vec<HtmlBlock>, chapters: &mut Vec<ImportedChapter>| {
+        let content = blocks_to_markdown(body);
+        body.clear();
+        if content.trim().is_empty() {
+            return;
+        }
+        chapters.push(ImportedChapter {
+            title: title.unwrap_or_else(|| "序章".to_string()),
+            word_count: content.chars().count(),
+            content,
+        });
+    };
+
+    for block in blocks {
+        match block {
+            HtmlBlock::Heading(level, text) if *level == chapter_level => {
+                push_chapter(title.take(), &mut body, &mut chapters);
+                title = Some(text.clone());
+            }
+            // 高于章节级别的标题（如整本书的书名）不计入正文
+            HtmlBlock::Heading(level, _) if *level < chapter_level => {}
+            _ => body.push(block.clone()),
+        }
+    }
+    push_chapter(title, &mut body, &mut chapters);
+
+    chapters
+}
+
+/// 从 MHTML 网页存档中取出第一个 text/html 部分
+fn extract_mhtml_document(raw: &str) -> Result<String> {
+    let boundary = raw
+        .lines()
+        .take_while(|l| !l.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .split("boundary=")
+        .nth(1)
+        .map(|b| b.trim_start_matches('"').split(['"', ';', ' ']).next().unwrap_or("").to_string())
+        .filter(|b| !b.is_empty())
+        .ok_or_else(|| anyhow!("无法识别的 MHTML 文件：缺少 boundary"))?;
+
+    for part in raw.split(&format!("--{}", boundary)).skip(1) {
+        let normalized = part.replace("\r\n", "\n");
+        let Some((headers, body)) = normalized.split_once("\n\n") else { continue };
+        let headers_lower = headers.to_lowercase();
+        if !headers_lower.contains("content-type: text/html") {
+            continue;
+        }
+        if headers_lower.contains("content-transfer-encoding: base64") {
+            let cleaned: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(cleaned)
+                .context("MHTML 正文 base64 解码失败")?;
+            return Ok(String::from_utf8_lossy(&bytes).into_owned());
+        }
+        if headers_lower.contains("content-transfer-encoding: quoted-printable") {
+            return Ok(decode_quoted_printable(body));
+        }
+        return Ok(body.to_string());
+    }
+
+    Err(anyhow!("MHTML 文件中没有找到网页正文"))
+}
+
+fn decode_quoted_printable(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'=' {
+            // 软换行
+            if bytes.get(i + 1) == Some(&b'\n') {
+                i += 2;
+                continue;
+            }
+            if let Some(hex) = text.get(i + 1..i + 3) {
+                if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                    out.push(byte);
+                    i += 3;
+                    continue;
+                }
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strips_boilerplate_and_splits_on_headings() {
+        let html = r#"<html><head><title>雨夜故事</title><style>p{}</style></head><body>
+            <nav>首页 | 目录</nav>
+            <div class="sidebar">推荐阅读</div>
+            <article>
+              <h1>雨夜故事</h1>
+              <h2>第一章 雨夜</h2><p>窗外下着雨。</p><p>他推门而入。<br>灯灭了。</p>
+              <h2>第二章 黎明</h2><p>天亮了。</p>
+            </article>
+            <div id="comments">沙发！</div>
+        </body></html>"#;
+        let document = Html::parse_document(html);
+        assert_eq!(page_title(&document).as_deref(), Some("雨夜故事"));
+
+        let blocks = extract_blocks(&document);
+        let chapters = split_html_chapters(&blocks, "雨夜故事");
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0].title, "第一章 雨夜");
+        assert_eq!(chapters[0].content, "窗外下着雨。\n\n他推门而入。\n\n灯灭了。");
+        assert!(!blocks_to_markdown(&blocks).contains("沙发"));
+    }
+
+    #[test]
+    fn test_mhtml_quoted_printable() {
+        let archive = "MIME-Version: 1.0\r\nContent-Type: multipart/related; boundary=\"----b\"\r\n\r\n------b\r\nContent-Type: text/html; charset=utf-8\r\nContent-Transfer-Encoding: quoted-printable\r\n\r\n<p>Hello=\r\n World =E4=BD=A0</p>\r\n------b--";
+        let html = extract_mhtml_document(archive).unwrap();
+        assert!(html.contains("<p>Hello World 你</p>"));
+    }
+}
@@ -0,0 +1,128 @@
+use super::{ImportResult, ImportedChapter};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+const EXCERPT_CHARS: usize = 80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportChapterAction {
+    Keep,
+    Skip,
+    /// 并入上一个保留的章节
+    MergeWithPrevious,
+}
+
+/// 用户在预览中对某一章的调整，`index` 对应预览中的章节序号
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportChapterMapping {
+    pub index: usize,
+    pub action: ImportChapterAction,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewChapter {
+    pub index: usize,
+    pub title: String,
+    pub word_count: usize,
+    pub excerpt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportPreview {
+    pub title: String,
+    pub chapter_count: usize,
+    pub word_count: usize,
+    pub chapters: Vec<PreviewChapter>,
+    pub warnings: Vec<String>,
+    pub encoding: Option<String>,
+    pub message: Option<String>,
+}
+
+impl From<&ImportResult> for ImportPreview {
+    fn from(result: &ImportResult) -> Self {
+        Self {
+            title: result.title.clone(),
+            chapter_count: result.chapter_count,
+            word_count: result.word_count,
+            chapters: result
+                .chapters
+                .iter()
+                .enumerate()
+                .map(|(index, c)| PreviewChapter {
+                    index,
+                    title: c.title.clone(),
+                    word_count: c.word_count,
+                    excerpt: c.content.chars().take(EXCERPT_CHARS).collect(),
+                })
+                .collect(),
+            warnings: result.warnings.clone(),
+            encoding: result.encoding.clone(),
+            message: result.message.clone(),
+        }
+    }
+}
+
+/// 按预览中的调整重命名、合并或跳过章节；未出现在映射中的章节原样保留
+pub fn apply_import_mapping(
+    chapters: Vec<ImportedChapter>,
+    mapping: &[ImportChapterMapping],
+) -> Result<Vec<ImportedChapter>> {
+    if let Some(entry) = mapping.iter().find(|m| m.index >= chapters.len()) {
+        return Err(anyhow!("章节映射引用了不存在的第 {} 章", entry.index + 1));
+    }
+
+    let mut result: Vec<ImportedChapter> = Vec::new();
+    for (index, mut chapter) in chapters.into_iter().enumerate() {
+        let entry = mapping.iter().find(|m| m.index == index);
+        let action = entry.map_or(ImportChapterAction::Keep, |m| m.action);
+        if let Some(title) = entry.and_then(|m| m.title.as_deref()).map(str::trim).filter(|t| !t.is_empty()) {
+            chapter.title = title.to_string();
+        }
+
+        match action {
+            ImportChapterAction::Skip => {}
+            ImportChapterAction::MergeWithPrevious if !result.is_empty() => {
+                let previous = result.last_mut().unwrap();
+                if !previous.content.is_empty() {
+                    previous.content.push_str("\n\n");
+                }
+                previous.content.push_str(&chapter.content);
+                previous.word_count += chapter.word_count;
+            }
+            // 第一个保留的章节没有可合并的目标，按保留处理
+            _ => result.push(chapter),
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(title: &str, content: &str) -> ImportedChapter {
+        ImportedChapter { title: title.to_string(), content: content.to_string(), word_count: content.chars().count() }
+    }
+
+    #[test]
+    fn test_rename_merge_and_skip() {
+        let chapters = vec![chapter("序章", "广告"), chapter("第一章", "上"), chapter("第一章（续）", "下"), chapter("第二章", "终")];
+        let mapping = vec![
+            ImportChapterMapping { index: 0, action: ImportChapterAction::Skip, title: None },
+            ImportChapterMapping { index: 1, action: ImportChapterAction::Keep, title: Some("第一章 相遇".into()) },
+            ImportChapterMapping { index: 2, action: ImportChapterAction::MergeWithPrevious, title: None },
+        ];
+        let result = apply_import_mapping(chapters, &mapping).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].title, "第一章 相遇");
+        assert_eq!(result[0].content, "上\n\n下");
+        assert_eq!(result[0].word_count, 2);
+        assert_eq!(result[1].title, "第二章");
+
+        let bad = vec![ImportChapterMapping { index: 9, action: ImportChapterAction::Skip, title: None }];
+        assert!(apply_import_mapping(result, &bad).is_err());
+    }
+}
@@ -1,11 +1,12 @@
-use super::{ImportFormat, ImportResult, ImportedChapter};
+use super::{ImportFormat, ImportMonitor, ImportResult, ImportedChapter};
 use anyhow::{Context, Result};
-use std::fs;
 use std::path::Path;
 use regex::Regex;
 
-pub fn import_from_markdown(file_path: &Path) -> Result<ImportResult> {
-    let content = fs::read_to_string(file_path)
+pub fn import_from_markdown(file_path: &Path, monitor: &ImportMonitor) -> Result<ImportResult> {
+    let bytes = monitor.read_file(file_path)
+        .with_context(|| format!("无法读取 Markdown 文件: {:?}", file_path))?;
+    let content = String::from_utf8(bytes)
         .with_context(|| format!("无法读取 Markdown 文件: {:?}", file_path))?;
     
     let filename = file_path
@@ -16,6 +17,7 @@ pub fn import_from_markdown(file_path: &Path) -> Result<ImportResult> {
     
     let (title, clean_content) = extract_frontmatter(&content, &filename);
     let chapters = parse_md_chapters(&clean_content);
+    monitor.report("splitting", content.len() as u64, chapters.len());
     let chapter_count = chapters.len();
     let word_count: usize = chapters.iter().map(|c| c.word_count).sum();
     
@@ -31,6 +33,8 @@ pub fn import_from_markdown(file_path: &Path) -> Result<ImportResult> {
         } else {
             Some("文件内容将作为单章节导入".to_string())
         },
+        warnings: Vec::new(),
+        encoding: None,
     })
 }
 
@@ -1,10 +1,27 @@
 pub mod txt_import;
 pub mod md_import;
 pub mod docx_import;
+pub mod epub_import;
+pub mod pdf_import;
+pub mod html_import;
+pub mod split_rules;
+pub mod mapping;
+pub mod url_import;
+pub mod directory_import;
+pub mod progress;
+pub mod dedupe;
+pub mod table_import;
 
 pub use txt_import::import_from_txt;
 pub use md_import::import_from_markdown;
 pub use docx_import::import_from_docx;
+pub use epub_import::import_from_epub;
+pub use pdf_import::import_from_pdf;
+pub use html_import::import_from_html;
+pub use split_rules::{ChapterSplitDryRun, ChapterSplitRules};
+pub use mapping::{apply_import_mapping, ImportChapterMapping, ImportPreview};
+pub use progress::{ImportMonitor, ImportState};
+pub use directory_import::{list_import_files, DirectoryFileResult, DirectoryImportMode, DirectoryImportResult, ImportDirectoryRequest};
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
@@ -15,6 +32,9 @@ pub enum ImportFormat {
     Txt,
     Md,
     Docx,
+    Epub,
+    Pdf,
+    Html,
 }
 
 impl ImportFormat {
@@ -23,6 +43,9 @@ impl ImportFormat {
             "txt" => Some(ImportFormat::Txt),
             "md" | "markdown" => Some(ImportFormat::Md),
             "docx" => Some(ImportFormat::Docx),
+            "epub" => Some(ImportFormat::Epub),
+            "pdf" => Some(ImportFormat::Pdf),
+            "html" | "htm" | "mht" | "mhtml" => Some(ImportFormat::Html),
             _ => None,
         }
     }
@@ -32,6 +55,9 @@ impl ImportFormat {
             ImportFormat::Txt => "txt",
             ImportFormat::Md => "md",
             ImportFormat::Docx => "docx",
+            ImportFormat::Epub => "epub",
+            ImportFormat::Pdf => "pdf",
+            ImportFormat::Html => "html",
         }
     }
 }
@@ -45,6 +71,12 @@ pub struct ImportResult {
     pub word_count: usize,
     pub chapters: Vec<ImportedChapter>,
     pub message: Option<String>,
+    /// 导入过程中需要用户手动处理的问题，如提取失败的页面
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// 文本类文件解码时使用的编码
+    #[serde(default)]
+    pub encoding: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -0,0 +1,277 @@
+use super::{ImportMonitor, ImportResult, ImportedChapter};
+use anyhow::{anyhow, Context, Result};
+use pdf_extract::{MediaBox, OutputDev, OutputError, Transform};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// 字号超过正文字号的该倍数、且行长较短时视为标题
+const HEADING_SIZE_RATIO: f64 = 1.3;
+const HEADING_MAX_CHARS: usize = 40;
+
+#[derive(Debug, Clone)]
+struct PdfLine {
+    text: String,
+    font_size: f64,
+}
+
+/// 逐字符收集文本，按坐标换行，并记录每行的最大字号
+struct LineCollector {
+    lines: Vec<PdfLine>,
+    current: String,
+    current_size: f64,
+    page_height: f64,
+    last_x_end: f64,
+    last_y: f64,
+    has_char: bool,
+}
+
+impl LineCollector {
+    fn new() -> Self {
+        Self {
+            lines: Vec::new(),
+            current: String::new(),
+            current_size: 0.0,
+            page_height: 0.0,
+            last_x_end: 0.0,
+            last_y: 0.0,
+            has_char: false,
+        }
+    }
+
+    fn flush_line(&mut self) {
+        let text = self.current.trim().to_string();
+        if !text.is_empty() {
+            self.lines.push(PdfLine { text, font_size: self.current_size });
+        }
+        self.current.clear();
+        self.current_size = 0.0;
+    }
+}
+
+impl OutputDev for LineCollector {
+    fn begin_page(&mut self, _page_num: u32, media_box: &MediaBox, _art_box: Option<(f64, f64, f64, f64)>) -> Result<(), OutputError> {
+        self.page_height = media_box.ury - media_box.lly;
+        self.has_char = false;
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), OutputError> {
+        self.flush_line();
+        Ok(())
+    }
+
+    fn output_character(&mut self, trm: &Transform, width: f64, _spacing: f64, font_size: f64, ch: &str) -> Result<(), OutputError> {
+        let scale = (trm.m11 * trm.m22 - trm.m12 * trm.m21).abs().sqrt();
+        let size = font_size * scale;
+        let x = trm.m31;
+        let y = self.page_height - trm.m32;
+
+        if self.has_char {
+            if (y - self.last_y).abs() > size * 0.5 {
+                self.flush_line();
+            } else if x > self.last_x_end + size * 0.15 && !self.current.ends_with(' ') {
+                self.current.push(' ');
+            }
+        }
+
+        self.current.push_str(ch);
+        self.current_size = self.current_size.max(size);
+        self.last_x_end = x + width * size;
+        self.last_y = y;
+        self.has_char = true;
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+}
+
+pub fn import_from_pdf(file_path: &Path, monitor: &ImportMonitor) -> Result<ImportResult> {
+    let bytes = monitor.read_file(file_path)
+        .with_context(|| format!("无法打开 PDF 文件: {:?}", file_path))?;
+    let mut doc = pdf_extract::Document::load_mem(&bytes)
+        .with_context(|| format!("无法打开 PDF 文件: {:?}", file_path))?;
+    if doc.is_encrypted() {
+        doc.decrypt("").map_err(|_| anyhow!("PDF 文件已加密，无法提取文本"))?;
+    }
+
+    let page_numbers: Vec<u32> = doc.get_pages().keys().copied().collect();
+    let mut lines = Vec::new();
+    let mut failed_pages = Vec::new();
+
+    for page_num in &page_numbers {
+        monitor.check_cancelled()?;
+        // pdf-extract 遇到不规范的页面可能直接 panic，这里逐页隔离
+        let extracted = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut collector = LineCollector::new();
+            pdf_extract::output_doc_page(&doc, &mut collector, *page_num).map(|_| collector.lines)
+        }));
+        match extracted {
+            Ok(Ok(page_lines)) if !page_lines.is_empty() => lines.extend(page_lines),
+            // 没有文字的页面通常是扫描图片
+            _ => failed_pages.push(*page_num),
+        }
+    }
+
+    let title = file_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("未命名")
+        .to_string();
+
+    let strip_page_numbers: Vec<PdfLine> = lines
+        .into_iter()
+        .filter(|l| !is_page_number(&l.text))
+        .collect();
+    let chapters = split_by_headings(&strip_page_numbers);
+    monitor.report("splitting", bytes.len() as u64, chapters.len());
+    let content = strip_page_numbers
+        .iter()
+        .map(|l| l.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let chapter_count = chapters.len();
+    let word_count: usize = chapters.iter().map(|c| c.word_count).sum();
+
+    let warnings: Vec<String> = failed_pages
+        .iter()
+        .map(|p| format!("第 {} 页未能提取文字（可能是扫描图片或字体缺失），请手动补录", p))
+        .collect();
+    let mut message = if chapter_count > 0 {
+        format!("成功解析 {} 页中的 {} 个章节", page_numbers.len(), chapter_count)
+    } else {
+        "PDF 中没有可提取的文字".to_string()
+    };
+    if !failed_pages.is_empty() {
+        message.push_str(&format!(
+            "；{} 页提取失败: {}",
+            failed_pages.len(),
+            failed_pages.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ")
+        ));
+    }
+
+    Ok(ImportResult {
+        success: chapter_count > 0,
+        title,
+        content,
+        chapter_count,
+        word_count,
+        chapters,
+        message: Some(message),
+        warnings,
+        encoding: None,
+    })
+}
+
+fn is_page_number(text: &str) -> bool {
+    let trimmed = text.trim_matches(|c: char| c.is_whitespace() || c == '-' || c == '—' || c == '第' || c == '页');
+    !trimmed.is_empty() && trimmed.len() <= 4 && trimmed.chars().all(|c| c.is_ascii_digit())
+}
+
+/// 正文字号：按字数加权出现最多的字号（四舍五入到 0.5pt）
+fn body_font_size(lines: &[PdfLine]) -> f64 {
+    let mut weights: HashMap<i64, usize> = HashMap::new();
+    for line in lines.iter().filter(|l| l.font_size > 0.0) {
+        *weights.entry((line.font_size * 2.0).round() as i64).or_default() += line.text.chars().count();
+    }
+    weights
+        .into_iter()
+        .max_by_key(|(size, count)| (*count, -*size))
+        .map(|(size, _)| size as f64 / 2.0)
+        .unwrap_or(0.0)
+}
+
+/// 以字号明显偏大的短行作为章节标题切分；找不到时退回按章节标题文字切分
+fn split_by_headings(lines: &[PdfLine]) -> Vec<ImportedChapter> {
+    let body_size = body_font_size(lines);
+
+    let is_heading = |line: &PdfLine| {
+        body_size > 0.0
+            && line.font_size >= body_size * HEADING_SIZE_RATIO
+            && line.text.chars().count() <= HEADING_MAX_CHARS
+    };
+
+    if !lines.iter().any(is_heading) {
+        let text = lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>().join("\n");
+        return super::txt_import::parse_txt_chapters(&text);
+    }
+
+    let mut chapters = Vec::new();
+    let mut title: Option<String> = None;
+    let mut body: Vec<&str> = Vec::new();
+    let push_chapter = |title: Option<String>, body: &mut Vec<&str>, chapters: &mut Vec<ImportedChapter>| {
+        let content = body.join("\n").trim().to_string();
+        body.clear();
+        if content.is_empty() {
+            return;
+        }
+        chapters.push(ImportedChapter {
+            title: title.unwrap_or_else(|| if chapters.is_empty() { "序章".to_string() } else { format!("第{}章", chapters.len() + 1) }),
+            word_count: content.chars().count(),
+            content,
+        });
+    };
+
+    for line in lines {
+        if is_heading(line) {
+            // 连续的大字号行（如分两行排版的标题）合并为一个标题
+            if body.is_empty() {
+                if let Some(existing) = title.as_mut() {
+                    existing.push(' ');
+                    existing.push_str(&line.text);
+                    continue;
+                }
+            }
+            push_chapter(title.take(), &mut body, &mut chapters);
+            title = Some(line.text.clone());
+        } else {
+            body.push(&line.text);
+        }
+    }
+    push_chapter(title, &mut body, &mut chapters);
+
+    chapters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str, font_size: f64) -> PdfLine {
+        PdfLine { text: text.to_string(), font_size }
+    }
+
+    #[test]
+    fn test_split_by_font_size_headings() {
+        let lines = vec![
+            line("第一章", 20.0),
+            line("雨夜", 20.0),
+            line("窗外下着雨。", 10.0),
+            line("他推门而入。", 10.0),
+            line("第二章 黎明", 20.0),
+            line("天亮了。", 10.0),
+        ];
+        let chapters = split_by_headings(&lines);
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0].title, "第一章 雨夜");
+        assert_eq!(chapters[0].content, "窗外下着雨。\n他推门而入。");
+        assert_eq!(chapters[1].title, "第二章 黎明");
+    }
+
+    #[test]
+    fn test_page_number_lines() {
+        assert!(is_page_number("12"));
+        assert!(is_page_number("- 3 -"));
+        assert!(!is_page_number("2024年"));
+    }
+}
@@ -0,0 +1,179 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// 通过 `import://progress` 事件推送的导入进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportProgress {
+    pub import_id: String,
+    /// reading / parsing / splitting / done
+    pub stage: String,
+    pub bytes_read: u64,
+    pub total_bytes: u64,
+    pub chapters_found: usize,
+}
+
+type ProgressSink = Box<dyn Fn(ImportProgress) + Send + Sync>;
+
+/// 解析过程中汇报进度并检查是否已被取消
+pub struct ImportMonitor {
+    import_id: String,
+    total_bytes: AtomicU64,
+    cancelled: Arc<AtomicBool>,
+    sink: Option<ProgressSink>,
+    /// 上次推送时的千分比，避免每读一块就发一次事件
+    last_permille: AtomicU64,
+}
+
+impl Default for ImportMonitor {
+    /// 不推送进度、不可取消，供同步调用的导入函数使用
+    fn default() -> Self {
+        Self {
+            import_id: String::new(),
+            total_bytes: AtomicU64::new(0),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            sink: None,
+            last_permille: AtomicU64::new(u64::MAX),
+        }
+    }
+}
+
+impl ImportMonitor {
+    pub fn new(import_id: &str, cancelled: Arc<AtomicBool>, sink: ProgressSink) -> Self {
+        Self {
+            import_id: import_id.to_string(),
+            total_bytes: AtomicU64::new(0),
+            cancelled,
+            sink: Some(sink),
+            last_permille: AtomicU64::new(u64::MAX),
+        }
+    }
+
+    /// 设置本阶段需要处理的总字节数（压缩包内按解压后的大小计）
+    pub fn set_total(&self, total_bytes: u64) {
+        self.total_bytes.store(total_bytes, Ordering::Relaxed);
+        self.last_permille.store(u64::MAX, Ordering::Relaxed);
+    }
+
+    pub fn check_cancelled(&self) -> Result<()> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            Err(anyhow!("导入已取消"))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn report(&self, stage: &str, bytes_read: u64, chapters_found: usize) {
+        let Some(sink) = &self.sink else { return };
+        let total_bytes = self.total_bytes.load(Ordering::Relaxed);
+        let permille = (bytes_read.min(total_bytes) * 1000).checked_div(total_bytes).unwrap_or(0);
+        // 读取阶段按进度节流，其他阶段每次都推送
+        if stage == "reading" && self.last_permille.swap(permille, Ordering::Relaxed) == permille {
+            return;
+        }
+        sink(ImportProgress {
+            import_id: self.import_id.clone(),
+            stage: stage.to_string(),
+            bytes_read,
+            total_bytes,
+            chapters_found,
+        });
+    }
+
+    /// 包装读取器，按读取的字节数汇报进度；取消后读取返回错误以中断解析
+    pub fn reader<'a, R: Read>(&'a self, inner: R) -> ProgressReader<'a, R> {
+        ProgressReader { inner, monitor: self, bytes_read: 0 }
+    }
+
+    /// 分块读取整个文件并汇报进度
+    pub fn read_file(&self, path: &Path) -> Result<Vec<u8>> {
+        let file = std::fs::File::open(path)?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        self.set_total(size);
+        let mut bytes = Vec::with_capacity(size as usize);
+        self.reader(file).read_to_end(&mut bytes)?;
+        self.check_cancelled()?;
+        Ok(bytes)
+    }
+}
+
+pub struct ProgressReader<'a, R> {
+    inner: R,
+    monitor: &'a ImportMonitor,
+    bytes_read: u64,
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.monitor.cancelled.load(Ordering::Relaxed) {
+            return Err(std::io::Error::other("导入已取消"));
+        }
+        let n = self.inner.read(buf)?;
+        self.bytes_read += n as u64;
+        self.monitor.report("reading", self.bytes_read, 0);
+        Ok(n)
+    }
+}
+
+/// 正在进行的导入的取消标记，由 Tauri 托管
+#[derive(Default)]
+pub struct ImportState {
+    running: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl ImportState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&self, import_id: &str) -> Arc<AtomicBool> {
+        let flag = Arc::new(AtomicBool::new(false));
+        self.running.lock().unwrap().insert(import_id.to_string(), flag.clone());
+        flag
+    }
+
+    pub fn finish(&self, import_id: &str) {
+        self.running.lock().unwrap().remove(import_id);
+    }
+
+    /// 返回 false 表示没有找到正在进行的导入
+    pub fn cancel(&self, import_id: &str) -> bool {
+        match self.running.lock().unwrap().get(import_id) {
+            Some(flag) => {
+                flag.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reader_reports_and_cancels() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink_events = events.clone();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let data = vec![b'a'; 4096];
+        let monitor = ImportMonitor::new("imp-1", cancelled.clone(), Box::new(move |p| {
+            sink_events.lock().unwrap().push(p.bytes_read);
+        }));
+        monitor.set_total(data.len() as u64);
+
+        let mut out = Vec::new();
+        monitor.reader(&data[..]).read_to_end(&mut out).unwrap();
+        assert_eq!(out.len(), 4096);
+        assert_eq!(events.lock().unwrap().last(), Some(&4096));
+
+        cancelled.store(true, Ordering::Relaxed);
+        assert!(monitor.reader(&data[..]).read_to_end(&mut Vec::new()).is_err());
+        assert!(monitor.check_cancelled().is_err());
+    }
+}
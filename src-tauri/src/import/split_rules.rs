@@ -0,0 +1,305 @@
+use super::ImportedChapter;
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterSplitRule {
+    pub id: String,
+    pub name: String,
+    /// 匹配章节标题行的正则，按去除首尾空白后的整行匹配
+    pub pattern: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 用户自定义的分章规则，保存在 app_settings 的 chapter_split_rules 中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterSplitRules {
+    pub rules: Vec<ChapterSplitRule>,
+    /// 没有任何标题匹配时，连续空行达到该数量即视为分章
+    #[serde(default)]
+    pub blank_line_threshold: Option<usize>,
+    /// 单章超过该字数时按段落拆分
+    #[serde(default)]
+    pub max_chapter_chars: Option<usize>,
+}
+
+impl Default for ChapterSplitRules {
+    fn default() -> Self {
+        let rule = |id: &str, name: &str, pattern: &str| ChapterSplitRule {
+            id: id.to_string(),
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+            enabled: true,
+        };
+        Self {
+            rules: vec![
+                rule("cn-chapter", "第X章/节/卷", r"^第[零一二三四五六七八九十百千万两〇\d]+[章节卷回集部篇][\s:：]*.*$"),
+                rule("en-chapter", "Chapter N", r"^(?i)chapter\s*\d+[\s:：.]*.*$"),
+                rule("numbered", "数字编号", r"^\d+[\.、]\s*\S.{0,30}$"),
+            ],
+            blank_line_threshold: None,
+            max_chapter_chars: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleMatchReport {
+    pub rule_id: String,
+    pub name: String,
+    /// 单独使用该规则时得到的章节数
+    pub chapter_count: usize,
+    pub sample_titles: Vec<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterPreview {
+    pub title: String,
+    pub word_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterSplitDryRun {
+    pub chapter_count: usize,
+    pub rules: Vec<RuleMatchReport>,
+    pub chapters: Vec<ChapterPreview>,
+}
+
+const SAMPLE_TITLES: usize = 5;
+
+impl ChapterSplitRules {
+    /// 编译全部启用的规则，任何一条正则无效都会报错
+    pub fn compile(&self) -> Result<Vec<Regex>> {
+        self.rules
+            .iter()
+            .filter(|r| r.enabled)
+            .map(|r| Regex::new(&r.pattern).map_err(|e| anyhow!("分章规则「{}」的正则无效: {}", r.name, e)))
+            .collect()
+    }
+
+    pub fn split(&self, content: &str) -> Result<Vec<ImportedChapter>> {
+        let patterns = self.compile()?;
+        Ok(self.split_with(content, &patterns))
+    }
+
+    fn split_with(&self, content: &str, patterns: &[Regex]) -> Vec<ImportedChapter> {
+        let mut chapters = split_on_headings(content, patterns);
+        if chapters.is_none() {
+            if let Some(threshold) = self.blank_line_threshold.filter(|t| *t > 0) {
+                chapters = split_on_blank_lines(content, threshold);
+            }
+        }
+        let chapters = chapters.unwrap_or_else(|| {
+            let text = content.trim();
+            if text.is_empty() {
+                Vec::new()
+            } else {
+                vec![ImportedChapter {
+                    title: "正文".to_string(),
+                    content: text.to_string(),
+                    word_count: text.chars().count(),
+                }]
+            }
+        });
+
+        match self.max_chapter_chars.filter(|m| *m > 0) {
+            Some(max) => chapters.into_iter().flat_map(|c| split_oversized(c, max)).collect(),
+            None => chapters,
+        }
+    }
+
+    /// 不写入任何数据，报告每条规则单独使用和组合使用时的分章结果
+    pub fn dry_run(&self, content: &str) -> ChapterSplitDryRun {
+        let mut reports = Vec::new();
+        let mut patterns = Vec::new();
+        for rule in self.rules.iter().filter(|r| r.enabled) {
+            match Regex::new(&rule.pattern) {
+                Ok(regex) => {
+                    let chapters = split_on_headings(content, std::slice::from_ref(&regex)).unwrap_or_default();
+                    reports.push(RuleMatchReport {
+                        rule_id: rule.id.clone(),
+                        name: rule.name.clone(),
+                        chapter_count: chapters.len(),
+                        sample_titles: chapters.iter().take(SAMPLE_TITLES).map(|c| c.title.clone()).collect(),
+                        error: None,
+                    });
+                    patterns.push(regex);
+                }
+                Err(e) => reports.push(RuleMatchReport {
+                    rule_id: rule.id.clone(),
+                    name: rule.name.clone(),
+                    chapter_count: 0,
+                    sample_titles: Vec::new(),
+                    error: Some(e.to_string()),
+                }),
+            }
+        }
+
+        let chapters = self.split_with(content, &patterns);
+        ChapterSplitDryRun {
+            chapter_count: chapters.len(),
+            rules: reports,
+            chapters: chapters
+                .into_iter()
+                .map(|c| ChapterPreview { title: c.title, word_count: c.word_count })
+                .collect(),
+        }
+    }
+}
+
+fn push_chapter(chapters: &mut Vec<ImportedChapter>, title: String, body: &[&str]) {
+    let content = body.join("\n").trim().to_string();
+    if content.is_empty() && title == "序章" {
+        return;
+    }
+    chapters.push(ImportedChapter {
+        title,
+        word_count: content.chars().count(),
+        content,
+    });
+}
+
+/// 没有任何一行匹配时返回 None
+fn split_on_headings(content: &str, patterns: &[Regex]) -> Option<Vec<ImportedChapter>> {
+    let mut chapters = Vec::new();
+    let mut title: Option<String> = None;
+    let mut body: Vec<&str> = Vec::new();
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if !trimmed.is_empty() && patterns.iter().any(|p| p.is_match(trimmed)) {
+            push_chapter(&mut chapters, title.take().unwrap_or_else(|| "序章".to_string()), &body);
+            body.clear();
+            title = Some(trimmed.to_string());
+        } else {
+            body.push(line);
+        }
+    }
+    title.as_ref()?;
+    push_chapter(&mut chapters, title.unwrap_or_default(), &body);
+    Some(chapters)
+}
+
+fn split_on_blank_lines(content: &str, threshold: usize) -> Option<Vec<ImportedChapter>> {
+    let mut segments: Vec<Vec<&str>> = vec![Vec::new()];
+    let mut blank_run = 0;
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            blank_run += 1;
+            continue;
+        }
+        if blank_run >= threshold && !segments.last().is_none_or(|s| s.is_empty()) {
+            segments.push(Vec::new());
+        } else if blank_run > 0 {
+            segments.last_mut().unwrap().push("");
+        }
+        blank_run = 0;
+        segments.last_mut().unwrap().push(line);
+    }
+    if segments.len() < 2 {
+        return None;
+    }
+
+    let chapters = segments
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .enumerate()
+        .map(|(index, lines)| {
+            // 首行较短时当作标题
+            let first = lines[0].trim();
+            let (title, body) = if first.chars().count() <= 30 && lines.len() > 1 {
+                (first.to_string(), &lines[1..])
+            } else {
+                (format!("第{}章", index + 1), &lines[..])
+            };
+            let content = body.join("\n").trim().to_string();
+            ImportedChapter { title, word_count: content.chars().count(), content }
+        })
+        .collect();
+    Some(chapters)
+}
+
+/// 超长章节在段落边界拆分，标题追加序号
+fn split_oversized(chapter: ImportedChapter, max_chars: usize) -> Vec<ImportedChapter> {
+    if chapter.word_count <= max_chars {
+        return vec![chapter];
+    }
+
+    let mut parts: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for paragraph in chapter.content.split('\n') {
+        let len = current.chars().count();
+        if len > 0 && len + paragraph.chars().count() > max_chars {
+            parts.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(paragraph);
+    }
+    if !current.trim().is_empty() {
+        parts.push(current);
+    }
+
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(index, content)| {
+            let content = content.trim().to_string();
+            ImportedChapter {
+                title: if index == 0 { chapter.title.clone() } else { format!("{}（{}）", chapter.title, index + 1) },
+                word_count: content.chars().count(),
+                content,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_rule_and_dry_run() {
+        let content = "前言\n\n第一卷 风起\n卷首语\n第1节 相遇\n他们相遇了。\n第2节 离别\n他们分开了。";
+        let rules = ChapterSplitRules {
+            rules: vec![
+                ChapterSplitRule { id: "section".into(), name: "节".into(), pattern: r"^第\d+节".into(), enabled: true },
+                ChapterSplitRule { id: "bad".into(), name: "无效".into(), pattern: r"^第(".into(), enabled: true },
+            ],
+            blank_line_threshold: None,
+            max_chapter_chars: None,
+        };
+        assert!(rules.split(content).is_err());
+
+        let report = rules.dry_run(content);
+        assert_eq!(report.rules[0].chapter_count, 3);
+        assert_eq!(report.rules[0].sample_titles[1], "第1节 相遇");
+        assert!(report.rules[1].error.is_some());
+        assert_eq!(report.chapter_count, 3);
+
+        let chapters = ChapterSplitRules::default().split(content).unwrap();
+        assert_eq!(chapters.iter().map(|c| c.title.as_str()).collect::<Vec<_>>(), vec!["序章", "第一卷 风起", "第1节 相遇", "第2节 离别"]);
+    }
+
+    #[test]
+    fn test_blank_lines_and_max_size() {
+        let content = "开端\n第一段。\n\n\n\n转折\n第二段。\n第三段。";
+        let rules = ChapterSplitRules {
+            rules: Vec::new(),
+            blank_line_threshold: Some(3),
+            max_chapter_chars: Some(5),
+        };
+        let chapters = rules.split(content).unwrap();
+        let titles: Vec<_> = chapters.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, vec!["开端", "转折", "转折（2）"]);
+        assert_eq!(chapters[2].content, "第三段。");
+    }
+}
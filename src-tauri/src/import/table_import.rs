@@ -0,0 +1,295 @@
+use super::txt_import::decode_text;
+use crate::models::{CreateCharacterRequest, CreateWorldViewRequest};
+use anyhow::{anyhow, bail, Context, Result};
+use calamine::{open_workbook_auto, Reader};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 未映射分类列时世界观条目使用的分类
+const DEFAULT_WORLDVIEW_CATEGORY: &str = "其他";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TableImportTarget {
+    Character,
+    WorldView,
+}
+
+/// 表格列到字段的映射
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableColumnMapping {
+    /// 表头名称（忽略大小写和首尾空白）、从 1 开始的列号或 Excel 列字母（如 `C`）
+    pub column: String,
+    /// 目标字段名，如 `name`、`age`、`personality`；多列映射到同一字段时按顺序换行拼接
+    pub field: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableImportRequest {
+    pub file_path: String,
+    pub target: TableImportTarget,
+    pub columns: Vec<TableColumnMapping>,
+    /// XLSX/ODS 的工作表名，为空时使用第一个工作表
+    #[serde(default)]
+    pub sheet: Option<String>,
+    #[serde(default = "default_has_header")]
+    pub has_header: bool,
+    /// CSV 文件的文本编码，为空时自动检测
+    #[serde(default)]
+    pub encoding: Option<String>,
+    /// 仅用于 CSV，默认逗号；`.tsv` 文件默认制表符
+    #[serde(default)]
+    pub delimiter: Option<char>,
+}
+
+fn default_has_header() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableRowError {
+    /// 表格中的行号（从 1 开始，含表头）
+    pub row: usize,
+    pub message: String,
+}
+
+/// 按映射转换后的导入内容，尚未写入数据库
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TableImportPreview {
+    pub headers: Vec<String>,
+    pub characters: Vec<CreateCharacterRequest>,
+    pub world_views: Vec<CreateWorldViewRequest>,
+    /// 每个角色/世界观条目对应的表格行号
+    pub rows: Vec<usize>,
+    pub errors: Vec<TableRowError>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableImportResult {
+    pub created: usize,
+    /// 项目中已存在同名角色/同标题设定而跳过的行
+    pub skipped: Vec<TableRowError>,
+    pub errors: Vec<TableRowError>,
+}
+
+pub const CHARACTER_FIELDS: &[&str] = &[
+    "name", "role_type", "race", "age", "gender", "birth_date", "appearance", "personality", "background",
+    "skills", "status", "bazi", "ziwei", "mbti", "enneagram", "items",
+];
+
+pub const WORLDVIEW_FIELDS: &[&str] = &["category", "title", "content", "tags"];
+
+/// 读取 CSV/TSV/XLSX/XLS/ODS 表格，返回所有行的单元格文本
+pub fn read_table(path: &Path, sheet: Option<&str>, encoding: Option<&str>, delimiter: Option<char>) -> Result<Vec<Vec<String>>> {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    let rows = match ext.as_str() {
+        "csv" | "tsv" | "txt" => {
+            let bytes = std::fs::read(path).with_context(|| format!("无法读取文件: {:?}", path))?;
+            let (text, _, _) = decode_text(&bytes, encoding)?;
+            let delimiter = delimiter.unwrap_or(if ext == "tsv" { '\t' } else { ',' });
+            if !delimiter.is_ascii() {
+                bail!("分隔符必须是 ASCII 字符: {:?}", delimiter);
+            }
+            parse_csv(&text, delimiter as u8)?
+        }
+        "xlsx" | "xlsm" | "xls" | "ods" => {
+            let mut workbook = open_workbook_auto(path).with_context(|| format!("无法打开表格: {:?}", path))?;
+            let name = match sheet.map(str::trim).filter(|s| !s.is_empty()) {
+                Some(name) => name.to_string(),
+                None => workbook.sheet_names().first().cloned().ok_or_else(|| anyhow!("表格中没有工作表"))?,
+            };
+            let range = workbook
+                .worksheet_range(&name)
+                .map_err(|e| anyhow!("无法读取工作表 {}: {}", name, e))?;
+            range.rows().map(|row| row.iter().map(|cell| cell.to_string()).collect()).collect()
+        }
+        _ => bail!("不支持的表格格式: {}", ext),
+    };
+    Ok(rows)
+}
+
+fn parse_csv(text: &str, delimiter: u8) -> Result<Vec<Vec<String>>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(text.as_bytes());
+    reader
+        .records()
+        .map(|record| Ok(record.context("CSV 解析失败")?.iter().map(str::to_string).collect()))
+        .collect()
+}
+
+/// 将映射中的列描述解析为列下标
+fn resolve_column(column: &str, headers: &[String]) -> Option<usize> {
+    let column = column.trim();
+    if let Some(index) = headers.iter().position(|h| h.trim().eq_ignore_ascii_case(column)) {
+        return Some(index);
+    }
+    if let Ok(number) = column.parse::<usize>() {
+        return number.checked_sub(1);
+    }
+    if !column.is_empty() && column.chars().all(|c| c.is_ascii_alphabetic()) {
+        let index = column
+            .to_ascii_uppercase()
+            .bytes()
+            .fold(0usize, |acc, b| acc * 26 + (b - b'A' + 1) as usize);
+        return Some(index - 1);
+    }
+    None
+}
+
+/// 按列映射把表格行转换为角色或世界观创建请求
+pub fn map_table_rows(project_id: &str, rows: &[Vec<String>], request: &TableImportRequest) -> Result<TableImportPreview> {
+    let known_fields = match request.target {
+        TableImportTarget::Character => CHARACTER_FIELDS,
+        TableImportTarget::WorldView => WORLDVIEW_FIELDS,
+    };
+    let headers = if request.has_header { rows.first().cloned().unwrap_or_default() } else { Vec::new() };
+
+    let mut columns = Vec::new();
+    for mapping in &request.columns {
+        let field = mapping.field.trim();
+        if !known_fields.contains(&field) {
+            bail!("未知字段: {}", field);
+        }
+        let index = resolve_column(&mapping.column, &headers).ok_or_else(|| anyhow!("找不到列: {}", mapping.column))?;
+        columns.push((index, field));
+    }
+    let required = match request.target {
+        TableImportTarget::Character => "name",
+        TableImportTarget::WorldView => "title",
+    };
+    if !columns.iter().any(|(_, f)| *f == required) {
+        bail!("必须映射 {} 字段", required);
+    }
+
+    let mut preview = TableImportPreview { headers: headers.clone(), ..Default::default() };
+    let skip = if request.has_header { 1 } else { 0 };
+    for (offset, row) in rows.iter().enumerate().skip(skip) {
+        let row_number = offset + 1;
+        if row.iter().all(|cell| cell.trim().is_empty()) {
+            continue;
+        }
+        let field = |name: &str| -> Option<String> {
+            let values: Vec<&str> = columns
+                .iter()
+                .filter(|(_, f)| *f == name)
+                .filter_map(|(i, _)| row.get(*i).map(|v| v.trim()))
+                .filter(|v| !v.is_empty())
+                .collect();
+            if values.is_empty() { None } else { Some(values.join("\n")) }
+        };
+
+        let Some(key) = field(required) else {
+            preview.errors.push(TableRowError { row: row_number, message: format!("{} 为空", required) });
+            continue;
+        };
+
+        match request.target {
+            TableImportTarget::Character => {
+                let age = match field("age") {
+                    Some(age) => match parse_age(&age) {
+                        Some(age) => Some(age),
+                        None => {
+                            preview.errors.push(TableRowError { row: row_number, message: format!("年龄无法识别: {}", age) });
+                            continue;
+                        }
+                    },
+                    None => None,
+                };
+                preview.characters.push(CreateCharacterRequest {
+                    project_id: project_id.to_string(),
+                    name: key,
+                    role_type: field("role_type"),
+                    race: field("race"),
+                    age,
+                    gender: field("gender"),
+                    birth_date: field("birth_date"),
+                    appearance: field("appearance"),
+                    personality: field("personality"),
+                    background: field("background"),
+                    skills: field("skills"),
+                    status: field("status"),
+                    bazi: field("bazi"),
+                    ziwei: field("ziwei"),
+                    mbti: field("mbti"),
+                    enneagram: field("enneagram"),
+                    items: field("items"),
+                });
+                preview.rows.push(row_number);
+            }
+            TableImportTarget::WorldView => {
+                preview.world_views.push(CreateWorldViewRequest {
+                    project_id: project_id.to_string(),
+                    category: field("category").unwrap_or_else(|| DEFAULT_WORLDVIEW_CATEGORY.to_string()),
+                    title: key,
+                    content: field("content").unwrap_or_default(),
+                    tags: field("tags"),
+                });
+                preview.rows.push(row_number);
+            }
+        }
+    }
+    Ok(preview)
+}
+
+/// 接受「18」「18岁」以及表格里的「18.0」
+fn parse_age(text: &str) -> Option<i32> {
+    let text = text.trim().trim_end_matches(['岁', '歲']).trim();
+    text.parse::<i32>().ok().or_else(|| {
+        text.parse::<f64>()
+            .ok()
+            .filter(|v| v.fract() == 0.0 && *v >= 0.0 && *v <= i32::MAX as f64)
+            .map(|v| v as i32)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(column: &str, field: &str) -> TableColumnMapping {
+        TableColumnMapping { column: column.to_string(), field: field.to_string() }
+    }
+
+    #[test]
+    fn test_maps_character_rows() {
+        let rows = parse_csv("姓名,年龄,性格,特长1,特长2\n林夕,18岁,沉稳,剑术,\n,20,,,\n苏白,abc,,,\n\"陈,默\",30.0,\"冷静\n寡言\",医术,毒术\n", b',').unwrap();
+        let request = TableImportRequest {
+            file_path: "characters.csv".into(),
+            target: TableImportTarget::Character,
+            columns: vec![mapping("姓名", "name"), mapping("B", "age"), mapping("性格", "personality"), mapping("4", "skills"), mapping("特长2", "skills")],
+            sheet: None,
+            has_header: true,
+            encoding: None,
+            delimiter: None,
+        };
+        let preview = map_table_rows("p1", &rows, &request).unwrap();
+        assert_eq!(preview.characters.len(), 2);
+        assert_eq!(preview.characters[0].age, Some(18));
+        assert_eq!(preview.characters[0].skills.as_deref(), Some("剑术"));
+        assert_eq!(preview.characters[1].name, "陈,默");
+        assert_eq!(preview.characters[1].age, Some(30));
+        assert_eq!(preview.characters[1].skills.as_deref(), Some("医术\n毒术"));
+        assert_eq!(preview.rows, vec![2, 5]);
+        assert_eq!(preview.errors.iter().map(|e| e.row).collect::<Vec<_>>(), vec![3, 4]);
+    }
+
+    #[test]
+    fn test_rejects_unknown_field_and_missing_title() {
+        let rows = vec![vec!["标题".to_string(), "内容".to_string()]];
+        let mut request = TableImportRequest {
+            file_path: "world.xlsx".into(),
+            target: TableImportTarget::WorldView,
+            columns: vec![mapping("内容", "content")],
+            sheet: None,
+            has_header: true,
+            encoding: None,
+            delimiter: None,
+        };
+        assert!(map_table_rows("p1", &rows, &request).is_err());
+        request.columns.push(mapping("标题", "name"));
+        assert!(map_table_rows("p1", &rows, &request).is_err());
+    }
+}
@@ -1,12 +1,28 @@
-use super::{ImportFormat, ImportResult, ImportedChapter};
-use anyhow::{Context, Result};
-use std::fs;
+use super::{ChapterSplitRules, ImportFormat, ImportMonitor, ImportResult, ImportedChapter};
+use anyhow::{anyhow, Context, Result};
+use encoding_rs::{Encoding, UTF_8};
 use std::path::Path;
 use regex::Regex;
 
-pub fn import_from_txt(file_path: &Path) -> Result<ImportResult> {
-    let content = fs::read_to_string(file_path)
+/// `encoding` 为空时自动检测编码，否则按指定编码（如 "gbk"、"big5"）解码；
+/// `rules` 为空时使用内置的分章规则
+pub fn import_from_txt(
+    file_path: &Path,
+    encoding: Option<&str>,
+    rules: Option<&ChapterSplitRules>,
+    monitor: &ImportMonitor,
+) -> Result<ImportResult> {
+    let bytes = monitor.read_file(file_path)
         .with_context(|| format!("无法读取 TXT 文件: {:?}", file_path))?;
+    let (content, used_encoding, had_errors) = decode_text(&bytes, encoding)?;
+
+    let mut warnings = Vec::new();
+    if had_errors {
+        warnings.push(format!(
+            "按 {} 解码时遇到无法识别的字符，如出现乱码请手动指定编码",
+            used_encoding.name()
+        ));
+    }
     
     let filename = file_path
         .file_stem()
@@ -14,7 +30,11 @@ pub fn import_from_txt(file_path: &Path) -> Result<ImportResult> {
         .unwrap_or("未命名")
         .to_string();
     
-    let chapters = parse_txt_chapters(&content);
+    let chapters = match rules {
+        Some(rules) => rules.split(&content)?,
+        None => parse_txt_chapters(&content),
+    };
+    monitor.report("splitting", bytes.len() as u64, chapters.len());
     let chapter_count = chapters.len();
     let word_count: usize = chapters.iter().map(|c| c.word_count).sum();
     
@@ -30,10 +50,36 @@ pub fn import_from_txt(file_path: &Path) -> Result<ImportResult> {
         } else {
             Some("文件内容将作为单章节导入".to_string())
         },
+        warnings,
+        encoding: Some(used_encoding.name().to_string()),
     })
 }
 
-fn parse_txt_chapters(content: &str) -> Vec<ImportedChapter> {
+/// 解码文本文件：优先使用指定编码，其次 BOM，再次合法 UTF-8，最后交给 chardetng 猜测
+/// （GBK / GB18030 / Big5 等中文小说常见编码）
+pub fn decode_text(bytes: &[u8], encoding: Option<&str>) -> Result<(String, &'static Encoding, bool)> {
+    let encoding = match encoding.map(str::trim).filter(|e| !e.is_empty()) {
+        Some(label) => Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| anyhow!("不支持的文本编码: {}", label))?,
+        None => detect_encoding(bytes),
+    };
+    let (text, used_encoding, had_errors) = encoding.decode(bytes);
+    Ok((text.into_owned(), used_encoding, had_errors))
+}
+
+fn detect_encoding(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return UTF_8;
+    }
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(None, true)
+}
+
+pub(super) fn parse_txt_chapters(content: &str) -> Vec<ImportedChapter> {
     let mut chapters = Vec::new();
     
     let chapter_patterns = vec![
@@ -145,4 +191,20 @@ mod tests {
         assert_eq!(chapters.len(), 1);
         assert_eq!(chapters[0].title, "正文");
     }
+
+    #[test]
+    fn test_decode_gbk_and_big5() {
+        let text = "第一章 开始\n这是一个关于远方和故乡的故事，他在雨夜里走了很久很久。";
+        let (gbk, _, _) = encoding_rs::GBK.encode(text);
+        let (decoded, encoding, had_errors) = decode_text(&gbk, None).unwrap();
+        assert_eq!(decoded, text);
+        assert_eq!(encoding, encoding_rs::GBK);
+        assert!(!had_errors);
+
+        let traditional = "第一章 開始\n這是一個關於遠方和故鄉的故事，他在雨夜裡走了很久很久。";
+        let (big5, _, _) = encoding_rs::BIG5.encode(traditional);
+        let (decoded, encoding, _) = decode_text(&big5, Some("big5")).unwrap();
+        assert_eq!(decoded, traditional);
+        assert_eq!(encoding, encoding_rs::BIG5);
+    }
 }
@@ -0,0 +1,349 @@
+use super::html_import::html_to_chapter;
+use regex::Regex;
+use reqwest::Url;
+use rusqlite::{params, Connection};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+pub const USER_AGENT: &str = "AINovelStudio-Importer/1.0";
+/// 两次请求之间的最小间隔
+pub const DEFAULT_DELAY_MS: u64 = 1500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UrlImportRequest {
+    pub project_id: String,
+    pub index_url: String,
+    #[serde(default)]
+    pub delay_ms: Option<u64>,
+    #[serde(default)]
+    pub max_chapters: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChapterLink {
+    pub url: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlImportItemStatus {
+    Pending,
+    Done,
+    Failed,
+    /// robots.txt 不允许抓取
+    Disallowed,
+}
+
+impl UrlImportItemStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            UrlImportItemStatus::Pending => "pending",
+            UrlImportItemStatus::Done => "done",
+            UrlImportItemStatus::Failed => "failed",
+            UrlImportItemStatus::Disallowed => "disallowed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "done" => UrlImportItemStatus::Done,
+            "failed" => UrlImportItemStatus::Failed,
+            "disallowed" => UrlImportItemStatus::Disallowed,
+            _ => UrlImportItemStatus::Pending,
+        }
+    }
+}
+
+/// url_import_items 中的一行，记录每个章节链接的抓取状态，用于断点续传
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UrlImportItem {
+    pub id: String,
+    pub task_id: String,
+    pub project_id: String,
+    pub url: String,
+    pub title: String,
+    pub sort_order: i32,
+    pub status: UrlImportItemStatus,
+    pub chapter_id: Option<String>,
+    pub error: Option<String>,
+}
+
+pub fn save_url_import_items(conn: &Connection, items: &[UrlImportItem]) -> rusqlite::Result<()> {
+    let now = chrono::Utc::now().to_rfc3339();
+    for item in items {
+        conn.execute(
+            "INSERT OR REPLACE INTO url_import_items (id, task_id, project_id, url, title, sort_order, status, chapter_id, error, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                item.id,
+                item.task_id,
+                item.project_id,
+                item.url,
+                item.title,
+                item.sort_order,
+                item.status.as_str(),
+                item.chapter_id,
+                item.error,
+                now,
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn load_url_import_items(conn: &Connection, task_id: &str) -> rusqlite::Result<Vec<UrlImportItem>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, task_id, project_id, url, title, sort_order, status, chapter_id, error
+         FROM url_import_items WHERE task_id = ?1 ORDER BY sort_order",
+    )?;
+    let items = stmt
+        .query_map(params![task_id], |row| {
+            Ok(UrlImportItem {
+                id: row.get(0)?,
+                task_id: row.get(1)?,
+                project_id: row.get(2)?,
+                url: row.get(3)?,
+                title: row.get(4)?,
+                sort_order: row.get(5)?,
+                status: UrlImportItemStatus::parse(&row.get::<_, String>(6)?),
+                chapter_id: row.get(7)?,
+                error: row.get(8)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(items)
+}
+
+pub fn update_url_import_item(
+    conn: &Connection,
+    id: &str,
+    status: UrlImportItemStatus,
+    chapter_id: Option<&str>,
+    error: Option<&str>,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE url_import_items SET status = ?1, chapter_id = COALESCE(?2, chapter_id), error = ?3, updated_at = ?4 WHERE id = ?5",
+        params![status.as_str(), chapter_id, error, chrono::Utc::now().to_rfc3339(), id],
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UrlImportProgress {
+    pub task_id: String,
+    pub completed: usize,
+    pub total: usize,
+    pub current_title: String,
+    pub failed: usize,
+}
+
+/// robots.txt 中适用于本程序的规则
+#[derive(Debug, Clone, Default)]
+pub struct RobotsRules {
+    allow: Vec<String>,
+    disallow: Vec<String>,
+    pub crawl_delay: Option<Duration>,
+}
+
+impl RobotsRules {
+    /// 只识别 `*` 和本程序名称的分组，优先使用针对本程序的分组
+    pub fn parse(robots_txt: &str) -> Self {
+        let agent_name = USER_AGENT.split('/').next().unwrap_or(USER_AGENT).to_lowercase();
+        let mut specific = RobotsRules::default();
+        let mut generic = RobotsRules::default();
+        let mut has_specific = false;
+
+        let mut group_agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+        for line in robots_txt.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = line.split_once(':') else { continue };
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
+
+            if key == "user-agent" {
+                if in_rules {
+                    group_agents.clear();
+                    in_rules = false;
+                }
+                group_agents.push(value.to_lowercase());
+                continue;
+            }
+            in_rules = true;
+
+            let targets_specific = group_agents.iter().any(|a| a != "*" && agent_name.contains(a.as_str()));
+            let targets_generic = group_agents.iter().any(|a| a == "*");
+            if targets_specific {
+                has_specific = true;
+            }
+            for (matches, rules) in [(targets_specific, &mut specific), (targets_generic, &mut generic)] {
+                if !matches {
+                    continue;
+                }
+                match key.as_str() {
+                    "allow" if !value.is_empty() => rules.allow.push(value.to_string()),
+                    "disallow" if !value.is_empty() => rules.disallow.push(value.to_string()),
+                    "crawl-delay" => rules.crawl_delay = value.parse::<f64>().ok().map(Duration::from_secs_f64),
+                    _ => {}
+                }
+            }
+        }
+
+        if has_specific { specific } else { generic }
+    }
+
+    /// 最长匹配优先，长度相同时 Allow 优先
+    pub fn is_allowed(&self, path: &str) -> bool {
+        let longest = |rules: &[String]| rules.iter().filter(|r| robots_match(r, path)).map(|r| r.len()).max();
+        match (longest(&self.allow), longest(&self.disallow)) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(allow), Some(disallow)) => allow >= disallow,
+        }
+    }
+}
+
+/// 支持 `*` 通配符和 `$` 结尾锚点
+fn robots_match(rule: &str, path: &str) -> bool {
+    let anchored = rule.ends_with('$');
+    let rule = rule.trim_end_matches('$');
+    let pattern = rule.split('*').map(regex::escape).collect::<Vec<_>>().join(".*");
+    let pattern = format!("^{}{}", pattern, if anchored { "$" } else { "" });
+    Regex::new(&pattern).map(|re| re.is_match(path)).unwrap_or(false)
+}
+
+/// 从目录页中找出章节链接：优先使用文字形如「第X章」的链接，
+/// 找不到时退回与目录页同站点、同路径前缀的链接
+pub fn extract_chapter_links(html: &str, index_url: &str) -> Vec<ChapterLink> {
+    let Ok(base) = Url::parse(index_url) else { return Vec::new() };
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("a[href]").unwrap();
+    let chapter_title = Regex::new(r"^(第[零一二三四五六七八九十百千万两〇\d]+[章节回卷]|(?i:chapter)\s*\d+|\d+[\.、\s])").unwrap();
+
+    let mut seen = HashSet::new();
+    let links: Vec<ChapterLink> = document
+        .select(&selector)
+        .filter_map(|a| {
+            let href = a.value().attr("href")?;
+            let mut url = base.join(href).ok()?;
+            url.set_fragment(None);
+            if url.host_str() != base.host_str() || url == base || !matches!(url.scheme(), "http" | "https") {
+                return None;
+            }
+            let title = a.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ");
+            Some(ChapterLink { url: url.to_string(), title })
+        })
+        .filter(|link| !link.title.is_empty() && seen.insert(link.url.clone()))
+        .collect();
+
+    let titled: Vec<ChapterLink> = links.iter().filter(|l| chapter_title.is_match(&l.title)).cloned().collect();
+    if titled.len() >= 2 {
+        return titled;
+    }
+
+    let prefix = base.path().rsplit_once('/').map(|(dir, _)| format!("{}/", dir)).unwrap_or_default();
+    links
+        .into_iter()
+        .filter(|l| Url::parse(&l.url).map(|u| u.path().starts_with(&prefix) && u.path() != prefix).unwrap_or(false))
+        .collect()
+}
+
+/// 顺序抓取网页，遵守 robots.txt 和请求间隔
+pub struct PoliteFetcher {
+    client: reqwest::Client,
+    robots: RobotsRules,
+    delay: Duration,
+    last_request: Option<Instant>,
+}
+
+impl PoliteFetcher {
+    pub async fn for_site(url: &str, delay_ms: Option<u64>) -> Result<Self, String> {
+        let parsed = Url::parse(url).map_err(|e| format!("无效的网址 {}: {}", url, e))?;
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let robots_url = parsed.join("/robots.txt").map_err(|e| e.to_string())?;
+        let robots = match client.get(robots_url).send().await {
+            Ok(resp) if resp.status().is_success() => RobotsRules::parse(&resp.text().await.unwrap_or_default()),
+            // 站点没有 robots.txt 时视为允许
+            _ => RobotsRules::default(),
+        };
+
+        let requested = Duration::from_millis(delay_ms.unwrap_or(DEFAULT_DELAY_MS));
+        let delay = robots.crawl_delay.map_or(requested, |d| d.max(requested));
+        Ok(Self { client, robots, delay, last_request: None })
+    }
+
+    pub fn is_allowed(&self, url: &str) -> bool {
+        Url::parse(url).map(|u| self.robots.is_allowed(u.path())).unwrap_or(false)
+    }
+
+    pub async fn fetch(&mut self, url: &str) -> Result<String, String> {
+        if !self.is_allowed(url) {
+            return Err(format!("robots.txt 不允许抓取: {}", url));
+        }
+        if let Some(last) = self.last_request {
+            let elapsed = last.elapsed();
+            if elapsed < self.delay {
+                tokio::time::sleep(self.delay - elapsed).await;
+            }
+        }
+        self.last_request = Some(Instant::now());
+
+        let resp = self.client.get(url).send().await.map_err(|e| format!("请求失败 {}: {}", url, e))?;
+        if !resp.status().is_success() {
+            return Err(format!("请求失败 {}: HTTP {}", url, resp.status()));
+        }
+        let bytes = resp.bytes().await.map_err(|e| e.to_string())?;
+        let (text, _, _) = super::txt_import::decode_text(&bytes, None).map_err(|e| e.to_string())?;
+        Ok(text)
+    }
+
+    /// 抓取章节页，返回标题和正文
+    pub async fn fetch_chapter(&mut self, link_title: &str, url: &str) -> Result<(String, String), String> {
+        let html = self.fetch(url).await?;
+        let (heading, content) = html_to_chapter(&html);
+        if content.trim().is_empty() {
+            return Err(format!("页面中没有识别到正文: {}", url));
+        }
+        let title = heading.filter(|h| h.chars().count() <= 50).unwrap_or_else(|| link_title.to_string());
+        Ok((title, content))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_robots_rules() {
+        let robots = RobotsRules::parse(
+            "User-agent: *\nDisallow: /vip/\nAllow: /vip/free*\nCrawl-delay: 3\n\nUser-agent: BadBot\nDisallow: /",
+        );
+        assert!(robots.is_allowed("/book/1/2.html"));
+        assert!(!robots.is_allowed("/vip/3.html"));
+        assert!(robots.is_allowed("/vip/free-1.html"));
+        assert_eq!(robots.crawl_delay, Some(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn test_extract_chapter_links() {
+        let html = r#"<ul>
+            <li><a href="/">首页</a></li>
+            <li><a href="1.html">第一章 开端</a></li>
+            <li><a href="2.html#top">第二章 转折</a></li>
+            <li><a href="1.html">第一章 开端</a></li>
+            <li><a href="https://other.site/3.html">第三章 外链</a></li>
+        </ul>"#;
+        let links = extract_chapter_links(html, "https://example.com/book/12/index.html");
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[1].url, "https://example.com/book/12/2.html");
+        assert_eq!(links[1].title, "第二章 转折");
+    }
+}
@@ -0,0 +1,117 @@
+use crate::items::{self, CreateItemRequest, UpdateItemRequest};
+use crate::logger::Logger;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+#[tauri::command]
+pub async fn create_item(app: AppHandle, request: CreateItemRequest) -> Result<String, String> {
+    let logger = Logger::new().with_feature("items");
+    logger.info(&format!("Creating item {} in project {}", request.name, request.project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let item = items::create_item(&conn, &request)?;
+
+    serde_json::to_string(&item).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_items(app: AppHandle, project_id: String) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let list = items::list_items(&conn, &project_id)?;
+
+    serde_json::to_string(&list).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_item(app: AppHandle, request: UpdateItemRequest) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let item = items::update_item(&conn, &request)?;
+
+    serde_json::to_string(&item).map_err(|e| e.to_string())
+}
+
+/// 删除物品，流转记录随之删除
+#[tauri::command]
+pub async fn delete_item(app: AppHandle, id: String) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    items::delete_item(&conn, &id)
+}
+
+/// 记录物品在某章的转交、遗失、被毁或修复
+#[tauri::command]
+pub async fn record_item_event(
+    app: AppHandle,
+    item_id: String,
+    chapter_id: String,
+    kind: String,
+    character_id: Option<String>,
+    note: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let event = items::record_event(&conn, &item_id, &chapter_id, &kind, character_id.as_deref(), note.as_deref())?;
+
+    serde_json::to_string(&event).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_item_event(app: AppHandle, id: String) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    items::delete_event(&conn, &id)
+}
+
+#[tauri::command]
+pub async fn get_item_history(app: AppHandle, item_id: String) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let history = items::item_history(&conn, &item_id)?;
+
+    serde_json::to_string(&history).map_err(|e| e.to_string())
+}
+
+/// 检查物品被毁后仍在使用、被非持有者使用等问题
+#[tauri::command]
+pub async fn check_item_consistency(app: AppHandle, project_id: String) -> Result<String, String> {
+    let logger = Logger::new().with_feature("items");
+    logger.info(&format!("Checking item consistency for project {}", project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let report = items::check_project(&conn, &project_id)?;
+
+    serde_json::to_string(&report).map_err(|e| e.to_string())
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
@@ -0,0 +1,495 @@
+use crate::character_presence::aliases_by_character;
+use crate::status_check::acts_in;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// transfer：交给 `character_id`；lose：遗失，没有持有者；destroy：被毁；restore：修复或重铸
+pub const EVENT_KINDS: &[&str] = &["transfer", "lose", "destroy", "restore"];
+
+/// 物品或法宝，`owner_id`/`destroyed` 是按流转记录推出的最终状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryItem {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub abilities: Vec<String>,
+    pub location_id: Option<String>,
+    pub first_chapter_id: Option<String>,
+    /// 故事开始时的持有者
+    pub initial_owner_id: Option<String>,
+    pub owner_id: Option<String>,
+    pub owner_name: Option<String>,
+    pub destroyed: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateItemRequest {
+    pub project_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub abilities: Option<Vec<String>>,
+    pub location_id: Option<String>,
+    pub first_chapter_id: Option<String>,
+    pub initial_owner_id: Option<String>,
+}
+
+/// 为 None 的字段保持不变；`location_id`、`first_chapter_id`、`initial_owner_id` 传空字符串时清除
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateItemRequest {
+    pub id: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub abilities: Option<Vec<String>>,
+    pub location_id: Option<String>,
+    pub first_chapter_id: Option<String>,
+    pub initial_owner_id: Option<String>,
+}
+
+/// 物品的一次流转，按章节顺序和 `position` 排列
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemEvent {
+    pub id: String,
+    pub item_id: String,
+    pub chapter_id: String,
+    pub chapter_title: String,
+    pub kind: String,
+    pub character_id: Option<String>,
+    pub character_name: Option<String>,
+    pub note: Option<String>,
+    pub position: i64,
+    pub created_at: String,
+}
+
+/// `kind` 为 used_after_destroyed、wrong_owner、used_before_first_appearance 或 transferred_after_destroyed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemIssue {
+    pub item_id: String,
+    pub item_name: String,
+    pub kind: String,
+    pub severity: String,
+    pub chapter_id: String,
+    pub chapter_title: String,
+    pub character_id: Option<String>,
+    pub character_name: Option<String>,
+    pub count: usize,
+    /// 这一章第一处问题的原文
+    pub excerpt: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemReport {
+    pub issues: Vec<ItemIssue>,
+    pub items_checked: usize,
+}
+
+const ITEM_COLUMNS: &str =
+    "id, project_id, name, description, abilities_json, location_id, first_chapter_id, initial_owner_id, created_at, updated_at";
+const EVENT_COLUMNS: &str =
+    "e.id, e.item_id, e.chapter_id, ch.title, e.kind, e.character_id, c.name, e.note, e.position, e.created_at";
+
+fn item_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<StoryItem> {
+    let abilities: String = row.get(4)?;
+    Ok(StoryItem {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        name: row.get(2)?,
+        description: row.get(3)?,
+        abilities: serde_json::from_str(&abilities).unwrap_or_default(),
+        location_id: row.get(5)?,
+        first_chapter_id: row.get(6)?,
+        initial_owner_id: row.get(7)?,
+        owner_id: None,
+        owner_name: None,
+        destroyed: false,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+fn event_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ItemEvent> {
+    Ok(ItemEvent {
+        id: row.get(0)?,
+        item_id: row.get(1)?,
+        chapter_id: row.get(2)?,
+        chapter_title: row.get(3)?,
+        kind: row.get(4)?,
+        character_id: row.get(5)?,
+        character_name: row.get(6)?,
+        note: row.get(7)?,
+        position: row.get(8)?,
+        created_at: row.get(9)?,
+    })
+}
+
+/// 项目内全部流转记录，按章节顺序
+fn project_events(conn: &Connection, project_id: &str) -> Result<Vec<ItemEvent>, String> {
+    conn.prepare(&format!(
+        "SELECT {} FROM item_events e
+         JOIN story_items i ON i.id = e.item_id
+         JOIN chapters ch ON ch.id = e.chapter_id
+         LEFT JOIN characters c ON c.id = e.character_id
+         WHERE i.project_id = ? ORDER BY ch.sort_order, ch.created_at, e.position, e.created_at",
+        EVENT_COLUMNS
+    ))
+    .map_err(|e| e.to_string())?
+    .query_map(params![project_id], event_from_row)
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+/// 流转前后的持有者和是否已毁
+#[derive(Debug, Clone, Default)]
+struct ItemState {
+    owner: Option<String>,
+    destroyed: bool,
+}
+
+impl ItemState {
+    fn apply(&mut self, event: &ItemEvent) {
+        match event.kind.as_str() {
+            "transfer" => self.owner = event.character_id.clone(),
+            "lose" => self.owner = None,
+            "destroy" => self.destroyed = true,
+            "restore" => self.destroyed = false,
+            _ => {}
+        }
+    }
+}
+
+pub fn list_items(conn: &Connection, project_id: &str) -> Result<Vec<StoryItem>, String> {
+    let mut items: Vec<StoryItem> = conn
+        .prepare(&format!("SELECT {} FROM story_items WHERE project_id = ? ORDER BY created_at, rowid", ITEM_COLUMNS))
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], item_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let events = project_events(conn, project_id)?;
+    let names: HashMap<String, String> = conn
+        .prepare("SELECT id, name FROM characters WHERE project_id = ?")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    for item in &mut items {
+        let mut state = ItemState { owner: item.initial_owner_id.clone(), destroyed: false };
+        for event in events.iter().filter(|e| e.item_id == item.id) {
+            state.apply(event);
+        }
+        item.owner_name = state.owner.as_ref().and_then(|id| names.get(id).cloned());
+        item.owner_id = state.owner;
+        item.destroyed = state.destroyed;
+    }
+    Ok(items)
+}
+
+pub fn get_item(conn: &Connection, id: &str) -> Result<StoryItem, String> {
+    let project_id: String = conn
+        .query_row("SELECT project_id FROM story_items WHERE id = ?", params![id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "物品不存在".to_string())?;
+    list_items(conn, &project_id)?.into_iter().find(|i| i.id == id).ok_or_else(|| "物品不存在".to_string())
+}
+
+/// 校验引用的记录属于同一项目，`table` 为 characters、chapters 或 locations
+fn check_reference(conn: &Connection, table: &str, id: Option<&str>, project_id: &str, label: &str) -> Result<(), String> {
+    let Some(id) = id else { return Ok(()) };
+    let owner: Option<String> = conn
+        .query_row(&format!("SELECT project_id FROM {} WHERE id = ?", table), params![id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match owner {
+        Some(owner) if owner == project_id => Ok(()),
+        Some(_) => Err(format!("{}不属于同一项目", label)),
+        None => Err(format!("{}不存在", label)),
+    }
+}
+
+fn check_references(
+    conn: &Connection,
+    project_id: &str,
+    location_id: Option<&str>,
+    first_chapter_id: Option<&str>,
+    initial_owner_id: Option<&str>,
+) -> Result<(), String> {
+    check_reference(conn, "locations", location_id, project_id, "地点")?;
+    check_reference(conn, "chapters", first_chapter_id, project_id, "首次出现的章节")?;
+    check_reference(conn, "characters", initial_owner_id, project_id, "持有者")
+}
+
+fn check_name(conn: &Connection, project_id: &str, name: &str, id: Option<&str>) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("物品名称不能为空".to_string());
+    }
+    let exists: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM story_items WHERE project_id = ? AND name = ? AND id != COALESCE(?, ''))",
+            params![project_id, name, id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if exists {
+        return Err(format!("物品“{}”已存在", name));
+    }
+    Ok(())
+}
+
+pub fn create_item(conn: &Connection, request: &CreateItemRequest) -> Result<StoryItem, String> {
+    let name = request.name.trim();
+    check_name(conn, &request.project_id, name, None)?;
+    check_references(
+        conn,
+        &request.project_id,
+        request.location_id.as_deref(),
+        request.first_chapter_id.as_deref(),
+        request.initial_owner_id.as_deref(),
+    )?;
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+    let abilities = serde_json::to_string(&request.abilities.clone().unwrap_or_default()).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO story_items (id, project_id, name, description, abilities_json, location_id, first_chapter_id, initial_owner_id, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)",
+        params![id, request.project_id, name, request.description, abilities, request.location_id, request.first_chapter_id, request.initial_owner_id, now],
+    )
+    .map_err(|e| format!("Failed to create item: {}", e))?;
+    crate::tokenizer::add_words(&[name]);
+    get_item(conn, &id)
+}
+
+/// 空字符串表示清除，None 表示不变
+fn patch(value: &Option<String>, current: &Option<String>) -> Option<String> {
+    match value {
+        Some(v) if v.is_empty() => None,
+        Some(v) => Some(v.clone()),
+        None => current.clone(),
+    }
+}
+
+pub fn update_item(conn: &Connection, request: &UpdateItemRequest) -> Result<StoryItem, String> {
+    let current = get_item(conn, &request.id)?;
+    let name = request.name.as_deref().map(str::trim).unwrap_or(&current.name).to_string();
+    check_name(conn, &current.project_id, &name, Some(&current.id))?;
+    let location_id = patch(&request.location_id, &current.location_id);
+    let first_chapter_id = patch(&request.first_chapter_id, &current.first_chapter_id);
+    let initial_owner_id = patch(&request.initial_owner_id, &current.initial_owner_id);
+    check_references(conn, &current.project_id, location_id.as_deref(), first_chapter_id.as_deref(), initial_owner_id.as_deref())?;
+    let abilities = serde_json::to_string(request.abilities.as_ref().unwrap_or(&current.abilities)).map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE story_items SET name = ?, description = ?, abilities_json = ?, location_id = ?, first_chapter_id = ?, initial_owner_id = ?, updated_at = ?
+         WHERE id = ?",
+        params![
+            name,
+            request.description.clone().or(current.description),
+            abilities,
+            location_id,
+            first_chapter_id,
+            initial_owner_id,
+            Utc::now().to_rfc3339(),
+            request.id
+        ],
+    )
+    .map_err(|e| format!("Failed to update item: {}", e))?;
+    if name != current.name {
+        crate::tokenizer::add_words(&[name.as_str()]);
+    }
+    get_item(conn, &request.id)
+}
+
+/// 删除物品，流转记录随之删除
+pub fn delete_item(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM story_items WHERE id = ?", params![id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 记录一次流转，排在同一章已有记录之后；transfer 必须指定接手的角色
+pub fn record_event(
+    conn: &Connection,
+    item_id: &str,
+    chapter_id: &str,
+    kind: &str,
+    character_id: Option<&str>,
+    note: Option<&str>,
+) -> Result<ItemEvent, String> {
+    if !EVENT_KINDS.contains(&kind) {
+        return Err(format!("未知的流转类型: {}", kind));
+    }
+    if kind == "transfer" && character_id.is_none() {
+        return Err("转交需要指定接手的角色".to_string());
+    }
+    let item = get_item(conn, item_id)?;
+    check_reference(conn, "chapters", Some(chapter_id), &item.project_id, "章节")?;
+    check_reference(conn, "characters", character_id, &item.project_id, "角色")?;
+    let position: i64 = conn
+        .query_row(
+            "SELECT COALESCE(MAX(position), -1) + 1 FROM item_events WHERE item_id = ? AND chapter_id = ?",
+            params![item_id, chapter_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO item_events (id, item_id, chapter_id, kind, character_id, note, position, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        params![id, item_id, chapter_id, kind, character_id, note, position, Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| format!("Failed to record item event: {}", e))?;
+    item_history(conn, item_id)?.into_iter().find(|e| e.id == id).ok_or_else(|| "流转记录不存在".to_string())
+}
+
+pub fn delete_event(conn: &Connection, event_id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM item_events WHERE id = ?", params![event_id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 物品的流转记录，按章节顺序
+pub fn item_history(conn: &Connection, item_id: &str) -> Result<Vec<ItemEvent>, String> {
+    let item = get_item(conn, item_id)?;
+    Ok(project_events(conn, &item.project_id)?.into_iter().filter(|e| e.item_id == item_id).collect())
+}
+
+/// 检查物品被毁后是否还在被使用、是否被非持有者使用、是否在首次出现前就被使用；
+/// “使用”指以角色名或别名开头、提到物品名且不在回忆的句子
+pub fn check_project(conn: &Connection, project_id: &str) -> Result<ItemReport, String> {
+    let items = list_items(conn, project_id)?;
+    let events = project_events(conn, project_id)?;
+    let chapters: Vec<(String, String, String)> = conn
+        .prepare("SELECT id, title, COALESCE(content, '') FROM chapters WHERE project_id = ? ORDER BY sort_order, created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut aliases = aliases_by_character(conn, project_id)?;
+    let characters: Vec<(String, String, Vec<String>)> = conn
+        .prepare("SELECT id, name FROM characters WHERE project_id = ? AND merged_into IS NULL ORDER BY created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|(id, name)| {
+            let mut terms = vec![name.clone()];
+            terms.extend(aliases.remove(&id).unwrap_or_default());
+            (id, name, terms)
+        })
+        .collect();
+    let name_of = |id: &str| characters.iter().find(|(c, _, _)| c == id).map(|(_, name, _)| name.clone());
+
+    let mut report = ItemReport { issues: Vec::new(), items_checked: items.len() };
+    for item in &items {
+        let item_events: Vec<&ItemEvent> = events.iter().filter(|e| e.item_id == item.id).collect();
+        let first = item.first_chapter_id.as_ref().and_then(|id| chapters.iter().position(|(c, _, _)| c == id));
+        let mut state = ItemState { owner: item.initial_owner_id.clone(), destroyed: false };
+        for (index, (chapter_id, chapter_title, content)) in chapters.iter().enumerate() {
+            let here: Vec<&ItemEvent> = item_events.iter().copied().filter(|e| e.chapter_id == *chapter_id).collect();
+            let issue = |kind: &str, severity: &str, character_id: Option<&str>, excerpt: &str, count: usize, description: String| ItemIssue {
+                item_id: item.id.clone(),
+                item_name: item.name.clone(),
+                kind: kind.to_string(),
+                severity: severity.to_string(),
+                chapter_id: chapter_id.clone(),
+                chapter_title: chapter_title.clone(),
+                character_id: character_id.map(str::to_string),
+                character_name: character_id.and_then(name_of),
+                count,
+                excerpt: excerpt.to_string(),
+                description,
+            };
+
+            // 每个角色在本章第一次使用的句子和次数
+            let mut uses: Vec<(&str, &str, usize)> = Vec::new();
+            for sentence in content.split(['。', '！', '？', '；', '\n']).filter(|s| s.contains(item.name.as_str())) {
+                let Some((user, _, _)) = characters.iter().find(|(_, _, terms)| acts_in(sentence, terms)) else { continue };
+                match uses.iter_mut().find(|(id, _, _)| id == user) {
+                    Some(found) => found.2 += 1,
+                    None => uses.push((user.as_str(), sentence.trim(), 1)),
+                }
+            }
+            for (user, excerpt, count) in uses {
+                if first.is_some_and(|first| index < first) {
+                    let description = format!("“{}”在首次出现之前就被使用", item.name);
+                    report.issues.push(issue("used_before_first_appearance", "low", Some(user), excerpt, count, description));
+                }
+                // 本章有流转记录时，使用可能发生在流转之前，不好判断
+                if !here.is_empty() {
+                    continue;
+                }
+                if state.destroyed {
+                    let description = format!("“{}”已经被毁，却仍在使用", item.name);
+                    report.issues.push(issue("used_after_destroyed", "high", Some(user), excerpt, count, description));
+                } else if let Some(owner) = state.owner.as_deref().filter(|owner| *owner != user) {
+                    let description = format!("“{}”此时在{}手中，没有转交记录", item.name, name_of(owner).unwrap_or_default());
+                    report.issues.push(issue("wrong_owner", "medium", Some(user), excerpt, count, description));
+                }
+            }
+
+            for event in here {
+                if event.kind == "transfer" && state.destroyed {
+                    let description =
+                        format!("“{}”已经被毁，却又转交给了{}", item.name, event.character_name.clone().unwrap_or_default());
+                    let excerpt = event.note.as_deref().unwrap_or_default();
+                    report.issues.push(issue("transferred_after_destroyed", "medium", event.character_id.as_deref(), excerpt, 1, description));
+                }
+                state.apply(event);
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item_state_and_misuse() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES
+                 ('r1', 'p1', '林舟', 't0', 't0'), ('r2', 'p1', '沈青', 't0', 't0');
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '林舟拔出青霜剑。沈青握住青霜剑。', 1, 't0', 't0'),
+                 ('c2', 'p1', '第二章', '林舟把青霜剑交给沈青。沈青挥动青霜剑，剑身寸寸断裂。', 2, 't0', 't0'),
+                 ('c3', 'p1', '第三章', '沈青想起青霜剑。沈青拔出青霜剑迎敌。', 3, 't0', 't0');",
+        )
+        .unwrap();
+        let sword = create_item(
+            &conn,
+            &CreateItemRequest {
+                project_id: "p1".to_string(),
+                name: "青霜剑".to_string(),
+                description: None,
+                abilities: Some(vec!["斩断寒气".to_string()]),
+                location_id: None,
+                first_chapter_id: Some("c1".to_string()),
+                initial_owner_id: Some("r1".to_string()),
+            },
+        )
+        .unwrap();
+        assert!(record_event(&conn, &sword.id, "c2", "transfer", None, None).is_err());
+        record_event(&conn, &sword.id, "c2", "transfer", Some("r2"), None).unwrap();
+        let destroyed = record_event(&conn, &sword.id, "c2", "destroy", None, Some("剑身断裂")).unwrap();
+        assert_eq!(destroyed.position, 1);
+
+        let sword = get_item(&conn, &sword.id).unwrap();
+        assert_eq!((sword.owner_name.as_deref(), sword.destroyed), (Some("沈青"), true));
+        assert_eq!(sword.abilities, vec!["斩断寒气"]);
+
+        let report = check_project(&conn, "p1").unwrap();
+        let found: Vec<(&str, &str, Option<&str>)> =
+            report.issues.iter().map(|i| (i.kind.as_str(), i.chapter_id.as_str(), i.character_name.as_deref())).collect();
+        assert_eq!(found, vec![("wrong_owner", "c1", Some("沈青")), ("used_after_destroyed", "c3", Some("沈青"))]);
+        assert_eq!(report.issues[1].excerpt, "沈青拔出青霜剑迎敌");
+    }
+}
@@ -0,0 +1,355 @@
+use crate::models::KnowledgeEntry;
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// 每次送给 AI 的正文字数上限，长章节按段落切成几段
+const SEGMENT_CHARS: usize = 4000;
+/// 提示词里最多列出的已有条目标题
+const KNOWN_TITLES: usize = 80;
+/// 同名条目的内容相似度达到这个值就视为同一条事实
+const DUPLICATE_SIMILARITY: f64 = 0.5;
+const ENTRY_TYPES: [&str; 8] = ["character", "worldview", "plot", "item", "location", "event", "concept", "other"];
+
+/// 需要提取知识的章节
+#[derive(Debug, Clone)]
+pub struct ChapterToScan {
+    pub chapter_id: String,
+    pub title: String,
+    pub content: String,
+    pub content_hash: String,
+}
+
+/// AI 从正文中提取的一条事实
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractedFact {
+    pub entry_type: String,
+    pub title: String,
+    pub content: String,
+    pub keywords: Option<String>,
+    pub importance: i32,
+    /// 支撑这条事实的原文
+    pub excerpt: Option<String>,
+}
+
+/// 一章的提取结果：新建的待核实条目，以及补充了出处的已有条目
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChapterExtraction {
+    pub chapter_id: String,
+    pub created: Vec<KnowledgeEntry>,
+    pub merged_entry_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeSource {
+    pub chapter_id: String,
+    pub chapter_title: String,
+    pub excerpt: Option<String>,
+}
+
+fn content_hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 项目中正文有变化、尚未提取过的章节；`chapter_ids` 限定范围，`force` 时忽略提取记录
+pub fn chapters_to_scan(conn: &Connection, project_id: &str, chapter_ids: Option<&[String]>, force: bool) -> Result<Vec<ChapterToScan>, String> {
+    let rows: Vec<(String, String, String, Option<String>)> = conn
+        .prepare(
+            "SELECT c.id, c.title, c.content, s.content_hash FROM chapters c
+             LEFT JOIN knowledge_extraction_scans s ON s.chapter_id = c.id
+             WHERE c.project_id = ? ORDER BY c.sort_order",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rows
+        .into_iter()
+        .filter(|(id, ..)| chapter_ids.is_none_or(|ids| ids.contains(id)))
+        .filter(|(_, _, content, _)| !content.trim().is_empty())
+        .filter_map(|(chapter_id, title, content, scanned)| {
+            let content_hash = content_hash(&content);
+            (force || scanned.as_deref() != Some(content_hash.as_str())).then_some(ChapterToScan { chapter_id, title, content, content_hash })
+        })
+        .collect())
+}
+
+/// 按段落把正文切成不超过 SEGMENT_CHARS 的片段
+pub fn split_segments(content: &str) -> Vec<String> {
+    let mut segments = vec![String::new()];
+    for paragraph in content.lines().map(str::trim).filter(|p| !p.is_empty()) {
+        let current = segments.last_mut().unwrap();
+        if !current.is_empty() && current.chars().count() + paragraph.chars().count() > SEGMENT_CHARS {
+            segments.push(String::new());
+        }
+        let current = segments.last_mut().unwrap();
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(paragraph);
+    }
+    segments.retain(|s| !s.is_empty());
+    segments
+}
+
+/// 已有条目的标题，提示 AI 沿用同样的叫法
+pub fn known_titles(conn: &Connection, project_id: &str) -> Result<Vec<String>, String> {
+    conn.prepare("SELECT title FROM knowledge_entries WHERE project_id = ? ORDER BY importance DESC, updated_at DESC LIMIT ?")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id, KNOWN_TITLES as i64], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+pub fn build_prompt(chapter_title: &str, segment: &str, known_titles: &[String]) -> String {
+    let known = if known_titles.is_empty() {
+        String::new()
+    } else {
+        format!("知识库中已有这些条目，提到同一对象时沿用相同的标题：{}\n\n", known_titles.join("、"))
+    };
+    format!(
+        "从小说章节《{}》的正文中提取之后写作需要保持一致的设定事实，例如人物的外貌、身份、关系和经历，地点、物品、组织、事件和规则。\n\n\
+         {}正文：\n{}\n\n\
+         返回 JSON 数组：[{{\"entry_type\": \"{}\" 之一, \"title\": \"事实所属对象的名称\", \"content\": \"一两句话陈述事实\", \
+         \"keywords\": \"逗号分隔的关键词\", \"importance\": 1-5, \"excerpt\": \"原文中的依据，不超过50字\"}}]。\
+         只提取正文明确写出的内容，不要推测；没有值得记录的事实时返回 []。",
+        chapter_title,
+        known,
+        segment,
+        ENTRY_TYPES.join("、")
+    )
+}
+
+/// 解析 AI 的回答，丢弃缺少标题或内容的事实，未知分类归为 other
+pub fn parse_facts(response: &str) -> Vec<ExtractedFact> {
+    let start = response.find('[').unwrap_or(0);
+    let end = response.rfind(']').map(|i| i + 1).unwrap_or(response.len());
+    let items: Vec<serde_json::Value> = serde_json::from_str(&response[start..end]).unwrap_or_default();
+    let field = |item: &serde_json::Value, key: &str| item.get(key).and_then(|v| v.as_str()).map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+
+    items
+        .iter()
+        .filter_map(|item| {
+            let title = field(item, "title")?;
+            let content = field(item, "content")?;
+            let entry_type = field(item, "entry_type").filter(|t| ENTRY_TYPES.contains(&t.as_str())).unwrap_or_else(|| "other".to_string());
+            Some(ExtractedFact {
+                entry_type,
+                title,
+                content,
+                keywords: field(item, "keywords"),
+                importance: item.get("importance").and_then(|v| v.as_i64()).unwrap_or(3).clamp(1, 5) as i32,
+                excerpt: field(item, "excerpt"),
+            })
+        })
+        .collect()
+}
+
+fn normalize(text: &str) -> String {
+    text.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// 去掉标点后按相邻两字计算 Jaccard 相似度
+fn similarity(a: &str, b: &str) -> f64 {
+    let bigrams = |text: &str| -> HashSet<(char, char)> {
+        let chars: Vec<char> = normalize(text).chars().collect();
+        chars.windows(2).map(|w| (w[0], w[1])).collect()
+    };
+    let (a, b) = (bigrams(a), bigrams(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let intersection = a.intersection(&b).count();
+    intersection as f64 / (a.len() + b.len() - intersection) as f64
+}
+
+/// 同名且内容相同或相近的条目视为重复
+fn find_duplicate<'a>(existing: &'a [(String, String, String)], fact: &ExtractedFact) -> Option<&'a str> {
+    let title = normalize(&fact.title);
+    let content = normalize(&fact.content);
+    existing
+        .iter()
+        .filter(|(_, t, _)| normalize(t) == title)
+        .find(|(_, _, c)| {
+            let existing = normalize(c);
+            !existing.is_empty() && (existing.contains(&content) || content.contains(&existing) || similarity(c, &fact.content) >= DUPLICATE_SIMILARITY)
+        })
+        .map(|(id, _, _)| id.as_str())
+}
+
+/// 保存一章提取的事实：重复的只给已有条目补充出处，其余写成待核实的条目，并记录本章已提取
+pub fn save_facts(conn: &Connection, project_id: &str, chapter: &ChapterToScan, facts: &[ExtractedFact]) -> Result<ChapterExtraction, String> {
+    let mut existing: Vec<(String, String, String)> = conn
+        .prepare("SELECT id, title, content FROM knowledge_entries WHERE project_id = ?")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let now = Utc::now().to_rfc3339();
+    let mut result = ChapterExtraction { chapter_id: chapter.chapter_id.clone(), ..Default::default() };
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for fact in facts {
+        let entry_id = match find_duplicate(&existing, fact) {
+            Some(id) => {
+                if !result.merged_entry_ids.iter().any(|m| m == id) && !result.created.iter().any(|e| e.id == id) {
+                    result.merged_entry_ids.push(id.to_string());
+                }
+                id.to_string()
+            }
+            None => {
+                let entry = KnowledgeEntry {
+                    id: Uuid::new_v4().to_string(),
+                    project_id: project_id.to_string(),
+                    entry_type: fact.entry_type.clone(),
+                    title: fact.title.clone(),
+                    content: fact.content.clone(),
+                    source_type: "chapter".to_string(),
+                    source_id: Some(chapter.chapter_id.clone()),
+                    keywords: fact.keywords.clone(),
+                    importance: fact.importance,
+                    is_verified: false,
+                    created_at: now.clone(),
+                    updated_at: now.clone(),
+                };
+                tx.execute(
+                    "INSERT INTO knowledge_entries
+                     (id, project_id, entry_type, title, content, source_type, source_id, keywords, importance, is_verified, created_at, updated_at)
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, 0, ?, ?)",
+                    params![
+                        entry.id,
+                        entry.project_id,
+                        entry.entry_type,
+                        entry.title,
+                        entry.content,
+                        entry.source_type,
+                        entry.source_id,
+                        entry.keywords,
+                        entry.importance,
+                        entry.created_at,
+                        entry.updated_at
+                    ],
+                )
+                .map_err(|e| e.to_string())?;
+                existing.push((entry.id.clone(), entry.title.clone(), entry.content.clone()));
+                let id = entry.id.clone();
+                result.created.push(entry);
+                id
+            }
+        };
+        tx.execute(
+            "INSERT INTO knowledge_sources (entry_id, chapter_id, excerpt, created_at) VALUES (?, ?, ?, ?)
+             ON CONFLICT(entry_id, chapter_id) DO UPDATE SET excerpt = COALESCE(excluded.excerpt, excerpt)",
+            params![entry_id, chapter.chapter_id, fact.excerpt, now],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.execute(
+        "INSERT INTO knowledge_extraction_scans (chapter_id, content_hash, scanned_at) VALUES (?, ?, ?)
+         ON CONFLICT(chapter_id) DO UPDATE SET content_hash = excluded.content_hash, scanned_at = excluded.scanned_at",
+        params![chapter.chapter_id, chapter.content_hash, now],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+/// 条目的出处章节
+pub fn entry_sources(conn: &Connection, entry_id: &str) -> Result<Vec<KnowledgeSource>, String> {
+    conn.prepare(
+        "SELECT s.chapter_id, c.title, s.excerpt FROM knowledge_sources s JOIN chapters c ON c.id = s.chapter_id
+         WHERE s.entry_id = ? ORDER BY c.sort_order",
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![entry_id], |row| Ok(KnowledgeSource { chapter_id: row.get(0)?, chapter_title: row.get(1)?, excerpt: row.get(2)? }))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+/// 批量标记条目为已核实，返回实际更新的条数
+pub fn verify_entries(conn: &Connection, entry_ids: &[String]) -> Result<usize, String> {
+    let now = Utc::now().to_rfc3339();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut updated = 0;
+    for id in entry_ids {
+        updated += tx
+            .execute("UPDATE knowledge_entries SET is_verified = 1, updated_at = ? WHERE id = ? AND is_verified = 0", params![now, id])
+            .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(updated)
+}
+
+/// 逐段让 AI 提取一章的事实
+pub async fn extract_with_ai(
+    service: &crate::ai::AIService,
+    model_id: &str,
+    chapter: &ChapterToScan,
+    known_titles: &[String],
+) -> Result<Vec<ExtractedFact>, String> {
+    let system_prompt = "你是一位细致的小说设定整理员，负责把正文里的设定事实整理进知识库。只返回 JSON 数组，不要包含markdown代码块标记。";
+    let mut facts = Vec::new();
+    for segment in split_segments(&chapter.content) {
+        let response = service.complete(model_id, system_prompt, &build_prompt(&chapter.title, &segment, known_titles)).await?;
+        facts.extend(parse_facts(&response));
+    }
+    Ok(facts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracted_facts_are_deduplicated_and_sourced() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '沈青生着一双灰色的眼睛。\n他出身江南沈家。', 1, 't0', 't0'),
+                 ('c2', 'p1', '第二章', '   ', 2, 't0', 't0');
+             INSERT INTO knowledge_entries (id, project_id, entry_type, title, content, source_type, created_at, updated_at) VALUES
+                 ('k1', 'p1', 'character', '沈青', '沈青出身江南沈家。', 'manual', 't0', 't0');",
+        )
+        .unwrap();
+
+        let chapters = chapters_to_scan(&conn, "p1", None, false).unwrap();
+        assert_eq!(chapters.iter().map(|c| c.chapter_id.as_str()).collect::<Vec<_>>(), vec!["c1"]);
+        assert_eq!(split_segments(&chapters[0].content).len(), 1);
+
+        let response = r#"```json
+[{"entry_type": "character", "title": "沈青", "content": "沈青有一双灰色的眼睛", "keywords": "沈青,眼睛", "importance": 4, "excerpt": "沈青生着一双灰色的眼睛"},
+ {"entry_type": "character", "title": "沈青", "content": "沈青出身江南沈家", "excerpt": "他出身江南沈家"},
+ {"entry_type": "unknown", "title": "沈家", "content": "江南的世家", "importance": 9},
+ {"entry_type": "character", "title": "", "content": "没有标题"}]
+```"#;
+        let facts = parse_facts(response);
+        assert_eq!(facts.len(), 3);
+        assert_eq!((facts[2].entry_type.as_str(), facts[2].importance), ("other", 5));
+
+        let result = save_facts(&conn, "p1", &chapters[0], &facts).unwrap();
+        assert_eq!(result.created.iter().map(|e| e.title.as_str()).collect::<Vec<_>>(), vec!["沈青", "沈家"]);
+        assert!(result.created.iter().all(|e| !e.is_verified && e.source_id.as_deref() == Some("c1")));
+        assert_eq!(result.merged_entry_ids, vec!["k1"]);
+        let sources = entry_sources(&conn, "k1").unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].excerpt.as_deref(), Some("他出身江南沈家"));
+        assert!(chapters_to_scan(&conn, "p1", None, false).unwrap().is_empty());
+        assert_eq!(chapters_to_scan(&conn, "p1", Some(&["c1".to_string()]), true).unwrap().len(), 1);
+
+        // 再次提取同样的事实不会产生新条目
+        let again = save_facts(&conn, "p1", &chapters[0], &facts).unwrap();
+        assert!(again.created.is_empty());
+        assert_eq!(again.merged_entry_ids.len(), 3);
+
+        let ids: Vec<String> = result.created.iter().map(|e| e.id.clone()).collect();
+        assert_eq!(verify_entries(&conn, &ids).unwrap(), 2);
+        assert_eq!(verify_entries(&conn, &ids).unwrap(), 0);
+    }
+}
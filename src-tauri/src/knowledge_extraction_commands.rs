@@ -0,0 +1,73 @@
+use crate::knowledge_extraction;
+use crate::logger::Logger;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// 从新增或改动过的章节中提取事实，写成待核实的知识条目；`chapter_ids` 为空时扫描整个项目，`force` 时重新提取正文未变的章节
+#[tauri::command]
+pub async fn extract_chapter_knowledge(
+    app: AppHandle,
+    project_id: String,
+    chapter_ids: Option<Vec<String>>,
+    force: Option<bool>,
+    model_id: Option<String>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("knowledge_extraction");
+    logger.info(&format!("Extracting chapter knowledge for project {}", project_id));
+
+    let (chapters, known_titles) = {
+        let conn = open_connection(&app)?;
+        (
+            knowledge_extraction::chapters_to_scan(&conn, &project_id, chapter_ids.as_deref(), force.unwrap_or(false))?,
+            knowledge_extraction::known_titles(&conn, &project_id)?,
+        )
+    };
+
+    let ai_service = app.state::<std::sync::Arc<tokio::sync::RwLock<crate::ai::AIService>>>();
+    let service = ai_service.read().await;
+    let model_id = model_id.unwrap_or_else(|| "glm-4-flash".to_string());
+    let mut results = Vec::new();
+    for chapter in &chapters {
+        let facts = knowledge_extraction::extract_with_ai(&service, &model_id, chapter, &known_titles).await.map_err(|e| {
+            logger.error(&format!("Failed to extract knowledge from chapter {}: {}", chapter.chapter_id, e));
+            e
+        })?;
+        let conn = open_connection(&app)?;
+        results.push(knowledge_extraction::save_facts(&conn, &project_id, chapter, &facts)?);
+    }
+
+    serde_json::to_string(&results).map_err(|e| e.to_string())
+}
+
+/// 知识条目出自哪些章节及原文依据
+#[tauri::command]
+pub async fn get_knowledge_sources(app: AppHandle, entry_id: String) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let sources = knowledge_extraction::entry_sources(&conn, &entry_id)?;
+    serde_json::to_string(&sources).map_err(|e| e.to_string())
+}
+
+/// 把自动提取的条目标记为已核实，返回更新的条数
+#[tauri::command]
+pub async fn verify_knowledge_entries(app: AppHandle, entry_ids: Vec<String>) -> Result<usize, String> {
+    let conn = open_connection(&app)?;
+    knowledge_extraction::verify_entries(&conn, &entry_ids)
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
+
+fn open_connection(app: &AppHandle) -> Result<rusqlite::Connection, String> {
+    crate::database::get_connection(&get_db_path(app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))
+}
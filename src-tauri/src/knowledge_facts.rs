@@ -0,0 +1,503 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use uuid::Uuid;
+
+/// 属性名最多这么多字，更长的冒号前文字不当作属性
+const MAX_ATTRIBUTE_CHARS: usize = 8;
+/// 单个条目送给 AI 时最多取的字数
+const FACT_CHARS: usize = 400;
+/// 写进 AI 上下文的事实条数上限
+const MAX_CONTEXT_FACTS: usize = 60;
+
+/// 账本中的一条事实，即一个知识条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerFact {
+    pub entry_id: String,
+    pub entry_type: String,
+    pub title: String,
+    pub content: String,
+    pub source_type: String,
+    pub importance: i32,
+    pub is_verified: bool,
+    pub is_canonical: bool,
+}
+
+/// 同一对象名下的所有事实
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityFacts {
+    pub entity: String,
+    pub facts: Vec<LedgerFact>,
+    pub open_conflicts: usize,
+}
+
+/// 检查得到、尚未保存的冲突
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectedConflict {
+    pub entity: String,
+    /// 冲突的事项，如“眼睛颜色”“籍贯”
+    pub attribute: String,
+    pub entry_ids: Vec<String>,
+    pub description: String,
+    pub severity: String,
+    /// attribute 为按“属性: 值”比对得出，ai 为 AI 检查得出
+    pub detected_by: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeConflict {
+    pub id: String,
+    pub project_id: String,
+    pub entity: String,
+    pub attribute: String,
+    pub entry_ids: Vec<String>,
+    pub description: String,
+    pub severity: String,
+    pub detected_by: String,
+    /// open、resolved 或 dismissed
+    pub status: String,
+    pub canonical_entry_id: Option<String>,
+    pub created_at: String,
+    pub resolved_at: Option<String>,
+}
+
+fn normalize(text: &str) -> String {
+    text.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+pub fn load_facts(conn: &Connection, project_id: &str) -> Result<Vec<LedgerFact>, String> {
+    conn.prepare(
+        "SELECT id, entry_type, title, content, source_type, importance, is_verified, is_canonical
+         FROM knowledge_entries WHERE project_id = ? ORDER BY title, created_at",
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![project_id], |row| {
+        Ok(LedgerFact {
+            entry_id: row.get(0)?,
+            entry_type: row.get(1)?,
+            title: row.get(2)?,
+            content: row.get(3)?,
+            source_type: row.get(4)?,
+            importance: row.get::<_, Option<i32>>(5)?.unwrap_or(0),
+            is_verified: row.get::<_, Option<i32>>(6)?.unwrap_or(0) != 0,
+            is_canonical: row.get::<_, Option<i32>>(7)?.unwrap_or(0) != 0,
+        })
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+/// 按条目标题把事实归到对象名下，返回对象名和事实下标
+pub fn group_by_entity(facts: &[LedgerFact]) -> Vec<(String, Vec<usize>)> {
+    let mut groups: BTreeMap<String, (String, Vec<usize>)> = BTreeMap::new();
+    for (i, fact) in facts.iter().enumerate() {
+        let key = normalize(&fact.title);
+        if key.is_empty() {
+            continue;
+        }
+        groups.entry(key).or_insert_with(|| (fact.title.trim().to_string(), Vec::new())).1.push(i);
+    }
+    groups.into_values().collect()
+}
+
+/// 正文中“属性: 值”形式的行，如角色同步生成的“性别: 女”
+fn attribute_values(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(['：', ':'])?;
+            let (key, value) = (key.trim(), value.trim().trim_end_matches(['。', '.']));
+            (!key.is_empty() && !value.is_empty() && key.chars().count() <= MAX_ATTRIBUTE_CHARS).then(|| (key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// 同一对象的同一属性在不同条目中取值不同
+pub fn detect_attribute_conflicts(facts: &[LedgerFact], groups: &[(String, Vec<usize>)]) -> Vec<DetectedConflict> {
+    let mut conflicts = Vec::new();
+    for (entity, members) in groups {
+        if members.len() < 2 {
+            continue;
+        }
+        let mut attributes: BTreeMap<String, Vec<(String, usize)>> = BTreeMap::new();
+        for &i in members {
+            for (key, value) in attribute_values(&facts[i].content) {
+                attributes.entry(key).or_default().push((value, i));
+            }
+        }
+        for (attribute, values) in attributes {
+            let distinct: HashSet<String> = values.iter().map(|(v, _)| normalize(v)).collect();
+            let mut entries: Vec<usize> = values.iter().map(|(_, i)| *i).collect();
+            entries.sort_unstable();
+            entries.dedup();
+            if distinct.len() < 2 || entries.len() < 2 {
+                continue;
+            }
+            let mut said: Vec<&str> = Vec::new();
+            for (value, _) in &values {
+                if !said.contains(&value.as_str()) {
+                    said.push(value);
+                }
+            }
+            conflicts.push(DetectedConflict {
+                entity: entity.clone(),
+                description: format!("{}的「{}」有不同说法：{}", entity, attribute, said.join("、")),
+                attribute,
+                entry_ids: entries.iter().map(|&i| facts[i].entry_id.clone()).collect(),
+                severity: "high".to_string(),
+                detected_by: "attribute".to_string(),
+            });
+        }
+    }
+    conflicts
+}
+
+fn excerpt(content: &str) -> String {
+    let content = content.trim();
+    match content.char_indices().nth(FACT_CHARS) {
+        Some((i, _)) => format!("{}……", &content[..i]),
+        None => content.to_string(),
+    }
+}
+
+pub fn build_prompt(entity: &str, facts: &[LedgerFact], members: &[usize]) -> String {
+    let listed: Vec<String> = members.iter().enumerate().map(|(n, &i)| format!("[F{}] {}", n + 1, excerpt(&facts[i].content))).collect();
+    format!(
+        "下面是小说知识库里关于「{}」的几条记录。找出相互矛盾的事实，例如眼睛颜色不同、籍贯不同、年龄或辈分对不上。\n\n{}\n\n\
+         返回 JSON 数组：[{{\"facts\": [\"F1\", \"F2\"], \"attribute\": \"冲突的事项\", \"description\": \"各条记录分别怎么说\", \
+         \"severity\": \"high、medium 或 low\"}}]。\
+         补充或细化不算矛盾，随剧情发生的合理变化也不算矛盾；没有矛盾时返回 []。",
+        entity,
+        listed.join("\n")
+    )
+}
+
+/// 解析 AI 的回答，每条冲突要引用至少两条不同的记录
+pub fn parse_conflicts(entity: &str, facts: &[LedgerFact], members: &[usize], response: &str) -> Vec<DetectedConflict> {
+    let start = response.find('[').unwrap_or(0);
+    let end = response.rfind(']').map(|i| i + 1).unwrap_or(response.len());
+    let items: Vec<serde_json::Value> = serde_json::from_str(&response[start..end]).unwrap_or_default();
+    let field = |item: &serde_json::Value, key: &str| item.get(key).and_then(|v| v.as_str()).unwrap_or_default().trim().to_string();
+
+    let mut conflicts = Vec::new();
+    for item in &items {
+        let mut refs: Vec<usize> = item
+            .get("facts")
+            .and_then(|v| v.as_array())
+            .map(|codes| {
+                codes
+                    .iter()
+                    .filter_map(|c| c.as_str()?.trim().trim_start_matches(['F', 'f']).parse::<usize>().ok())
+                    .filter_map(|n| members.get(n.checked_sub(1)?).copied())
+                    .collect()
+            })
+            .unwrap_or_default();
+        refs.sort_unstable();
+        refs.dedup();
+        let description = field(item, "description");
+        if refs.len() < 2 || description.is_empty() {
+            continue;
+        }
+        let severity = field(item, "severity");
+        let attribute = field(item, "attribute");
+        conflicts.push(DetectedConflict {
+            entity: entity.to_string(),
+            attribute: if attribute.is_empty() { "其他".to_string() } else { attribute },
+            entry_ids: refs.iter().map(|&i| facts[i].entry_id.clone()).collect(),
+            description,
+            severity: if ["high", "medium", "low"].contains(&severity.as_str()) { severity } else { "medium".to_string() },
+            detected_by: "ai".to_string(),
+        });
+    }
+    conflicts
+}
+
+/// 逐个对象让 AI 检查多条记录之间的矛盾
+pub async fn check_with_ai(
+    service: &crate::ai::AIService,
+    model_id: &str,
+    facts: &[LedgerFact],
+    groups: &[(String, Vec<usize>)],
+) -> Result<Vec<DetectedConflict>, String> {
+    let system_prompt = "你是一位严谨的小说设定编辑，负责核对同一人物或事物的设定是否前后一致。只返回 JSON 数组，不要包含markdown代码块标记。";
+    let mut conflicts = Vec::new();
+    for (entity, members) in groups.iter().filter(|(_, m)| m.len() >= 2) {
+        let response = service.complete(model_id, system_prompt, &build_prompt(entity, facts, members)).await?;
+        conflicts.extend(parse_conflicts(entity, facts, members, &response));
+    }
+    Ok(conflicts)
+}
+
+fn conflict_key(entity: &str, attribute: &str, entry_ids: &[String]) -> String {
+    let mut ids = entry_ids.to_vec();
+    ids.sort();
+    format!("{}\n{}\n{}", normalize(entity), normalize(attribute), ids.join(","))
+}
+
+fn read_conflict(row: &rusqlite::Row) -> rusqlite::Result<KnowledgeConflict> {
+    let entry_ids: String = row.get(4)?;
+    Ok(KnowledgeConflict {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        entity: row.get(2)?,
+        attribute: row.get(3)?,
+        entry_ids: serde_json::from_str(&entry_ids).unwrap_or_default(),
+        description: row.get(5)?,
+        severity: row.get(6)?,
+        detected_by: row.get(7)?,
+        status: row.get(8)?,
+        canonical_entry_id: row.get(9)?,
+        created_at: row.get(10)?,
+        resolved_at: row.get(11)?,
+    })
+}
+
+const CONFLICT_COLUMNS: &str =
+    "id, project_id, entity, attribute, entry_ids, description, severity, detected_by, status, canonical_entry_id, created_at, resolved_at";
+
+pub fn list_conflicts(conn: &Connection, project_id: &str, include_closed: bool) -> Result<Vec<KnowledgeConflict>, String> {
+    let sql = format!(
+        "SELECT {} FROM knowledge_conflicts WHERE project_id = ? AND (? OR status = 'open') ORDER BY entity, created_at",
+        CONFLICT_COLUMNS
+    );
+    conn.prepare(&sql)
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id, include_closed], read_conflict)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 用本次检查结果替换未处理的冲突；已解决或忽略过的同一冲突不再提出，涉及的条目中恰有一条定论时直接记为已解决
+pub fn save_conflicts(conn: &Connection, project_id: &str, detected: &[DetectedConflict]) -> Result<Vec<KnowledgeConflict>, String> {
+    let closed: HashSet<String> = list_conflicts(conn, project_id, true)?
+        .into_iter()
+        .filter(|c| c.status != "open")
+        .map(|c| conflict_key(&c.entity, &c.attribute, &c.entry_ids))
+        .collect();
+    let canonical: HashSet<String> = load_facts(conn, project_id)?.into_iter().filter(|f| f.is_canonical).map(|f| f.entry_id).collect();
+
+    let now = Utc::now().to_rfc3339();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM knowledge_conflicts WHERE project_id = ? AND status = 'open'", params![project_id])
+        .map_err(|e| e.to_string())?;
+    let mut seen = HashSet::new();
+    for conflict in detected {
+        let key = conflict_key(&conflict.entity, &conflict.attribute, &conflict.entry_ids);
+        if closed.contains(&key) || !seen.insert(key) {
+            continue;
+        }
+        let canonical_ids: Vec<&String> = conflict.entry_ids.iter().filter(|id| canonical.contains(*id)).collect();
+        let (status, canonical_entry_id, resolved_at) = match canonical_ids.as_slice() {
+            [only] => ("resolved", Some(only.to_string()), Some(now.clone())),
+            _ => ("open", None, None),
+        };
+        tx.execute(
+            "INSERT INTO knowledge_conflicts
+             (id, project_id, entity, attribute, entry_ids, description, severity, detected_by, status, canonical_entry_id, created_at, resolved_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                Uuid::new_v4().to_string(),
+                project_id,
+                conflict.entity,
+                conflict.attribute,
+                serde_json::to_string(&conflict.entry_ids).map_err(|e| e.to_string())?,
+                conflict.description,
+                conflict.severity,
+                conflict.detected_by,
+                status,
+                canonical_entry_id,
+                now,
+                resolved_at
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    list_conflicts(conn, project_id, false)
+}
+
+/// 设置或取消定论。设为定论时，它所在的未处理冲突标记为已解决，冲突中的其他条目取消定论；
+/// 取消定论时，以它为准解决的冲突重新打开
+pub fn set_canonical(conn: &Connection, entry_id: &str, canonical: bool) -> Result<(), String> {
+    let project_id: String = conn
+        .query_row("SELECT project_id FROM knowledge_entries WHERE id = ?", params![entry_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "知识条目不存在".to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute("UPDATE knowledge_entries SET is_canonical = ?, updated_at = ? WHERE id = ?", params![canonical, now, entry_id])
+        .map_err(|e| e.to_string())?;
+    for conflict in list_conflicts(&tx, &project_id, true)? {
+        if !conflict.entry_ids.iter().any(|id| id == entry_id) {
+            continue;
+        }
+        if canonical && conflict.status == "open" {
+            for other in conflict.entry_ids.iter().filter(|id| *id != entry_id) {
+                tx.execute("UPDATE knowledge_entries SET is_canonical = 0 WHERE id = ?", params![other]).map_err(|e| e.to_string())?;
+            }
+            tx.execute(
+                "UPDATE knowledge_conflicts SET status = 'resolved', canonical_entry_id = ?, resolved_at = ? WHERE id = ?",
+                params![entry_id, now, conflict.id],
+            )
+            .map_err(|e| e.to_string())?;
+        } else if !canonical && conflict.canonical_entry_id.as_deref() == Some(entry_id) {
+            tx.execute(
+                "UPDATE knowledge_conflicts SET status = 'open', canonical_entry_id = NULL, resolved_at = NULL WHERE id = ?",
+                params![conflict.id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// 忽略一条冲突，之后的检查不再提出
+pub fn dismiss_conflict(conn: &Connection, conflict_id: &str) -> Result<(), String> {
+    let updated = conn
+        .execute(
+            "UPDATE knowledge_conflicts SET status = 'dismissed', resolved_at = ? WHERE id = ?",
+            params![Utc::now().to_rfc3339(), conflict_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("冲突记录不存在".to_string());
+    }
+    Ok(())
+}
+
+/// 事实账本：按对象列出所有事实，定论和已核实的排在前面
+pub fn fact_ledger(conn: &Connection, project_id: &str) -> Result<Vec<EntityFacts>, String> {
+    let facts = load_facts(conn, project_id)?;
+    let mut open: HashMap<String, usize> = HashMap::new();
+    for conflict in list_conflicts(conn, project_id, false)? {
+        *open.entry(normalize(&conflict.entity)).or_default() += 1;
+    }
+    Ok(group_by_entity(&facts)
+        .into_iter()
+        .map(|(entity, members)| {
+            let mut entity_facts: Vec<LedgerFact> = members.iter().map(|&i| facts[i].clone()).collect();
+            entity_facts.sort_by_key(|f| (!f.is_canonical, !f.is_verified, -f.importance));
+            EntityFacts { open_conflicts: open.get(&normalize(&entity)).copied().unwrap_or(0), entity, facts: entity_facts }
+        })
+        .collect())
+}
+
+/// 写进 AI 上下文的事实。冲突中被定论否定的条目不写入；未处理的冲突里已有定论或已核实的条目时，略过其余未核实的说法。
+/// 由角色和世界观同步来的条目已在对应摘要里，不重复写入。`focus` 为光标附近提到的条目（按远近排列），
+/// 给出时只写入这些条目和定论，不再列出全部事实
+pub fn context_facts(conn: &Connection, project_id: &str, focus: Option<&[String]>) -> Result<Vec<LedgerFact>, String> {
+    let facts = load_facts(conn, project_id)?;
+    let by_id: HashMap<&str, &LedgerFact> = facts.iter().map(|f| (f.entry_id.as_str(), f)).collect();
+    let mut superseded: HashSet<String> = HashSet::new();
+    for conflict in list_conflicts(conn, project_id, true)? {
+        match conflict.status.as_str() {
+            "resolved" => {
+                superseded.extend(conflict.entry_ids.into_iter().filter(|id| conflict.canonical_entry_id.as_deref() != Some(id.as_str())));
+            }
+            "open" => {
+                let involved: Vec<&LedgerFact> = conflict.entry_ids.iter().filter_map(|id| by_id.get(id.as_str()).copied()).collect();
+                if involved.iter().any(|f| f.is_canonical || f.is_verified) {
+                    superseded.extend(involved.iter().filter(|f| !f.is_canonical && !f.is_verified).map(|f| f.entry_id.clone()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut kept: Vec<&LedgerFact> = facts
+        .iter()
+        .filter(|f| !superseded.contains(&f.entry_id))
+        .filter(|f| f.is_canonical || !["character", "worldview"].contains(&f.source_type.as_str()))
+        .collect();
+    match focus.filter(|ids| !ids.is_empty()) {
+        Some(ids) => {
+            let rank: HashMap<&str, usize> = ids.iter().enumerate().map(|(i, id)| (id.as_str(), i)).collect();
+            kept.retain(|f| f.is_canonical || rank.contains_key(f.entry_id.as_str()));
+            kept.sort_by_key(|f| (rank.get(f.entry_id.as_str()).copied().unwrap_or(usize::MAX), !f.is_verified, -f.importance));
+            kept.truncate(MAX_CONTEXT_FACTS);
+        }
+        None => {
+            kept.sort_by_key(|f| (!f.is_canonical, !f.is_verified, -f.importance));
+            kept.truncate(MAX_CONTEXT_FACTS);
+            kept.sort_by(|a, b| a.title.cmp(&b.title));
+        }
+    }
+    Ok(kept.into_iter().cloned().collect())
+}
+
+/// 把 `context_facts` 选出的事实写成摘要，每条一行
+pub fn format_context(facts: &[LedgerFact]) -> String {
+    facts
+        .iter()
+        .map(|f| {
+            let content = f.content.split_whitespace().collect::<Vec<_>>().join(" ");
+            if f.is_canonical {
+                format!("{}：{}（定论）", f.title, content)
+            } else {
+                format!("{}：{}", f.title, content)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conflicts_canonical_and_context() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO knowledge_entries (id, project_id, entry_type, title, content, source_type, importance, created_at, updated_at) VALUES
+                 ('k1', 'p1', 'character', '沈青', '眼睛: 灰色\n籍贯: 江南', 'manual', 3, 't0', 't0'),
+                 ('k2', 'p1', 'character', '沈青', '眼睛：黑色。', 'chapter', 2, 't1', 't1'),
+                 ('k3', 'p1', 'character', ' 沈青', '籍贯: 江南', 'chapter', 1, 't2', 't2'),
+                 ('k4', 'p1', 'location', '青云山', '终年积雪', 'chapter', 1, 't0', 't0');",
+        )
+        .unwrap();
+
+        let facts = load_facts(&conn, "p1").unwrap();
+        let groups = group_by_entity(&facts);
+        assert_eq!(groups.iter().map(|(e, m)| (e.as_str(), m.len())).collect::<Vec<_>>(), vec![("沈青", 3), ("青云山", 1)]);
+
+        let detected = detect_attribute_conflicts(&facts, &groups);
+        assert_eq!(detected.len(), 1);
+        assert_eq!(detected[0].attribute, "眼睛");
+        assert_eq!(detected[0].entry_ids, vec!["k1", "k2"]);
+
+        let members = &groups[0].1;
+        let response = r#"[{"facts": ["F1", "F2"], "attribute": "眼睛颜色", "description": "一处灰色一处黑色", "severity": "high"},
+                          {"facts": ["F2"], "attribute": "无效", "description": "只引用一条", "severity": "low"}]"#;
+        assert_eq!(parse_conflicts("沈青", &facts, members, response).len(), 1);
+
+        let saved = save_conflicts(&conn, "p1", &detected).unwrap();
+        assert_eq!(saved.len(), 1);
+        let summary = format_context(&context_facts(&conn, "p1", None).unwrap());
+        assert!(summary.contains("黑色") && summary.contains("灰色"));
+
+        set_canonical(&conn, "k1", true).unwrap();
+        assert!(list_conflicts(&conn, "p1", false).unwrap().is_empty());
+        let summary = format_context(&context_facts(&conn, "p1", None).unwrap());
+        assert!(summary.contains("灰色") && summary.contains("（定论）"));
+        assert!(!summary.contains("黑色"));
+        assert!(summary.contains("终年积雪"));
+        let focused = format_context(&context_facts(&conn, "p1", Some(&["k4".to_string()])).unwrap());
+        assert_eq!(focused.lines().collect::<Vec<_>>(), vec!["青云山：终年积雪", "沈青：眼睛: 灰色 籍贯: 江南（定论）"]);
+
+        // 已解决的冲突不会在下次检查时重新提出
+        assert!(save_conflicts(&conn, "p1", &detected).unwrap().is_empty());
+        let ledger = fact_ledger(&conn, "p1").unwrap();
+        assert_eq!(ledger[0].facts[0].entry_id, "k1");
+        assert_eq!(ledger[0].open_conflicts, 0);
+
+        set_canonical(&conn, "k1", false).unwrap();
+        assert_eq!(list_conflicts(&conn, "p1", false).unwrap().len(), 1);
+        dismiss_conflict(&conn, &saved[0].id).unwrap();
+        assert!(list_conflicts(&conn, "p1", false).unwrap().is_empty());
+    }
+}
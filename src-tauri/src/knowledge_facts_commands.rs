@@ -0,0 +1,84 @@
+use crate::knowledge_facts;
+use crate::logger::Logger;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// 按对象检查知识条目之间的事实冲突；先比对“属性: 值”，`use_ai` 时再让 AI 检查自由描述
+#[tauri::command]
+pub async fn check_knowledge_contradictions(
+    app: AppHandle,
+    project_id: String,
+    use_ai: Option<bool>,
+    model_id: Option<String>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("knowledge_facts");
+    logger.info(&format!("Checking knowledge contradictions for project {}", project_id));
+
+    let facts = {
+        let conn = open_connection(&app)?;
+        knowledge_facts::load_facts(&conn, &project_id)?
+    };
+    let groups = knowledge_facts::group_by_entity(&facts);
+    let mut detected = knowledge_facts::detect_attribute_conflicts(&facts, &groups);
+
+    if use_ai.unwrap_or(true) {
+        let ai_service = app.state::<std::sync::Arc<tokio::sync::RwLock<crate::ai::AIService>>>();
+        let service = ai_service.read().await;
+        let model_id = model_id.unwrap_or_else(|| "glm-4-flash".to_string());
+        let found = knowledge_facts::check_with_ai(&service, &model_id, &facts, &groups).await.map_err(|e| {
+            logger.error(&format!("Failed to check knowledge contradictions: {}", e));
+            e
+        })?;
+        detected.extend(found);
+    }
+
+    let conn = open_connection(&app)?;
+    let conflicts = knowledge_facts::save_conflicts(&conn, &project_id, &detected)?;
+    serde_json::to_string(&conflicts).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_knowledge_conflicts(app: AppHandle, project_id: String, include_closed: Option<bool>) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let conflicts = knowledge_facts::list_conflicts(&conn, &project_id, include_closed.unwrap_or(false))?;
+    serde_json::to_string(&conflicts).map_err(|e| e.to_string())
+}
+
+/// 按对象列出的事实账本
+#[tauri::command]
+pub async fn get_fact_ledger(app: AppHandle, project_id: String) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let ledger = knowledge_facts::fact_ledger(&conn, &project_id)?;
+    serde_json::to_string(&ledger).map_err(|e| e.to_string())
+}
+
+/// 把条目设为定论或取消定论
+#[tauri::command]
+pub async fn set_canonical_fact(app: AppHandle, entry_id: String, canonical: bool) -> Result<(), String> {
+    let conn = open_connection(&app)?;
+    knowledge_facts::set_canonical(&conn, &entry_id, canonical)
+}
+
+#[tauri::command]
+pub async fn dismiss_knowledge_conflict(app: AppHandle, conflict_id: String) -> Result<(), String> {
+    let conn = open_connection(&app)?;
+    knowledge_facts::dismiss_conflict(&conn, &conflict_id)
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
+
+fn open_connection(app: &AppHandle) -> Result<rusqlite::Connection, String> {
+    crate::database::get_connection(&get_db_path(app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))
+}
@@ -0,0 +1,335 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::f64::consts::PI;
+
+/// 未指定深度时，从焦点条目向外展开的层数
+pub const DEFAULT_DEPTH: usize = 2;
+/// 标签传播最多迭代的轮数
+const MAX_ROUNDS: usize = 20;
+/// 相邻节点之间的建议间距
+const NODE_SPACING: f64 = 80.0;
+/// 焦点布局中每一圈的半径增量
+const RING_SPACING: f64 = 160.0;
+/// 黄金角，簇内节点按螺旋排布
+const GOLDEN_ANGLE: f64 = 2.399_963_229_728_653;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeGraphQuery {
+    pub project_id: String,
+    /// 以该条目为中心取子图，为空时返回整个知识图谱
+    pub focus_entry_id: Option<String>,
+    pub depth: Option<usize>,
+    pub entry_types: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub entry_id: String,
+    pub title: String,
+    pub entry_type: String,
+    pub importance: i32,
+    pub is_verified: bool,
+    /// 与焦点条目的距离，没有焦点时为空
+    pub depth: Option<usize>,
+    pub degree: usize,
+    pub cluster: usize,
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub relation_id: String,
+    pub from_entry_id: String,
+    pub to_entry_id: String,
+    pub relation_type: String,
+    pub strength: i32,
+}
+
+/// 关系紧密的一组条目，`label` 取簇内连接最多的条目标题
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphCluster {
+    pub id: usize,
+    pub label: String,
+    pub size: usize,
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KnowledgeGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    pub clusters: Vec<GraphCluster>,
+}
+
+fn load_nodes(conn: &Connection, project_id: &str) -> Result<Vec<GraphNode>, String> {
+    conn.prepare("SELECT id, title, entry_type, importance, is_verified FROM knowledge_entries WHERE project_id = ? ORDER BY id")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| {
+            Ok(GraphNode {
+                entry_id: row.get(0)?,
+                title: row.get(1)?,
+                entry_type: row.get(2)?,
+                importance: row.get::<_, Option<i32>>(3)?.unwrap_or(0),
+                is_verified: row.get::<_, Option<i32>>(4)?.unwrap_or(0) != 0,
+                depth: None,
+                degree: 0,
+                cluster: 0,
+                x: 0.0,
+                y: 0.0,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+fn load_edges(conn: &Connection, project_id: &str) -> Result<Vec<GraphEdge>, String> {
+    conn.prepare(
+        "SELECT id, from_entry_id, to_entry_id, relation_type, strength FROM knowledge_relations
+         WHERE project_id = ? AND from_entry_id != to_entry_id ORDER BY id",
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![project_id], |row| {
+        Ok(GraphEdge {
+            relation_id: row.get(0)?,
+            from_entry_id: row.get(1)?,
+            to_entry_id: row.get(2)?,
+            relation_type: row.get(3)?,
+            strength: row.get::<_, Option<i32>>(4)?.unwrap_or(1),
+        })
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+/// 无向加权邻接表，同一对条目的多条关系强度相加
+fn adjacency(count: usize, index: &HashMap<&str, usize>, edges: &[GraphEdge]) -> Vec<BTreeMap<usize, f64>> {
+    let mut neighbors = vec![BTreeMap::new(); count];
+    for edge in edges {
+        if let (Some(&a), Some(&b)) = (index.get(edge.from_entry_id.as_str()), index.get(edge.to_entry_id.as_str())) {
+            let weight = edge.strength.max(1) as f64;
+            *neighbors[a].entry(b).or_insert(0.0) += weight;
+            *neighbors[b].entry(a).or_insert(0.0) += weight;
+        }
+    }
+    neighbors
+}
+
+/// 加权标签传播划分社区，节点按条目 id 顺序处理，同样的数据每次结果相同；簇按大小从大到小编号
+fn communities(neighbors: &[BTreeMap<usize, f64>]) -> Vec<usize> {
+    let mut labels: Vec<usize> = (0..neighbors.len()).collect();
+    for _ in 0..MAX_ROUNDS {
+        let mut changed = false;
+        for node in 0..neighbors.len() {
+            let mut weights: BTreeMap<usize, f64> = BTreeMap::new();
+            for (&other, &weight) in &neighbors[node] {
+                *weights.entry(labels[other]).or_insert(0.0) += weight;
+            }
+            let Some(best) = weights.values().copied().reduce(f64::max) else {
+                continue;
+            };
+            // 当前标签已是最优之一时保持不变，否则取编号最小的最优标签
+            if weights.get(&labels[node]) == Some(&best) {
+                continue;
+            }
+            labels[node] = weights.iter().find(|(_, w)| **w == best).map(|(&l, _)| l).unwrap_or(labels[node]);
+            changed = true;
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut sizes: BTreeMap<usize, usize> = BTreeMap::new();
+    for &label in &labels {
+        *sizes.entry(label).or_default() += 1;
+    }
+    let mut order: Vec<(usize, usize)> = sizes.into_iter().collect();
+    order.sort_by_key(|&(label, size)| (std::cmp::Reverse(size), label));
+    let renumber: HashMap<usize, usize> = order.iter().enumerate().map(|(id, &(label, _))| (label, id)).collect();
+    labels.iter().map(|l| renumber[l]).collect()
+}
+
+/// 从焦点出发的无向广度优先距离
+fn distances(neighbors: &[BTreeMap<usize, f64>], focus: usize, depth: usize) -> HashMap<usize, usize> {
+    let mut seen = HashMap::from([(focus, 0)]);
+    let mut queue = VecDeque::from([focus]);
+    while let Some(node) = queue.pop_front() {
+        let d = seen[&node];
+        if d == depth {
+            continue;
+        }
+        for &next in neighbors[node].keys() {
+            if let std::collections::hash_map::Entry::Vacant(slot) = seen.entry(next) {
+                slot.insert(d + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    seen
+}
+
+/// 没有焦点时：各簇中心排在一个圆上，簇内按连接数从多到少由中心向外螺旋排布
+fn cluster_layout(nodes: &mut [GraphNode], clusters: &mut [GraphCluster]) {
+    let radius_of = |size: usize| NODE_SPACING * (size as f64).sqrt();
+    let total: f64 = clusters.iter().map(|c| radius_of(c.size) * 2.0 + NODE_SPACING).sum();
+    let ring = if clusters.len() > 1 { total / (2.0 * PI) } else { 0.0 };
+    let mut angle = 0.0;
+    for cluster in clusters.iter_mut() {
+        let span = (radius_of(cluster.size) * 2.0 + NODE_SPACING) / total * 2.0 * PI;
+        angle += span / 2.0;
+        cluster.x = ring * angle.cos();
+        cluster.y = ring * angle.sin();
+        angle += span / 2.0;
+
+        let mut members: Vec<&mut GraphNode> = nodes.iter_mut().filter(|n| n.cluster == cluster.id).collect();
+        members.sort_by(|a, b| b.degree.cmp(&a.degree).then(b.importance.cmp(&a.importance)).then(a.title.cmp(&b.title)));
+        for (i, node) in members.into_iter().enumerate() {
+            let r = NODE_SPACING * (i as f64).sqrt();
+            node.x = cluster.x + r * (i as f64 * GOLDEN_ANGLE).cos();
+            node.y = cluster.y + r * (i as f64 * GOLDEN_ANGLE).sin();
+        }
+    }
+}
+
+/// 有焦点时：焦点在原点，其余条目按距离排成同心圆，同一圈内同簇的相邻
+fn radial_layout(nodes: &mut [GraphNode], clusters: &mut [GraphCluster]) {
+    let deepest = nodes.iter().filter_map(|n| n.depth).max().unwrap_or(0);
+    for depth in 1..=deepest {
+        let mut ring: Vec<&mut GraphNode> = nodes.iter_mut().filter(|n| n.depth == Some(depth)).collect();
+        ring.sort_by(|a, b| a.cluster.cmp(&b.cluster).then(a.title.cmp(&b.title)));
+        let radius = (depth as f64 * RING_SPACING).max(ring.len() as f64 * NODE_SPACING / (2.0 * PI));
+        let count = ring.len() as f64;
+        for (i, node) in ring.into_iter().enumerate() {
+            let angle = i as f64 / count * 2.0 * PI;
+            node.x = radius * angle.cos();
+            node.y = radius * angle.sin();
+        }
+    }
+    for cluster in clusters.iter_mut() {
+        let members: Vec<&GraphNode> = nodes.iter().filter(|n| n.cluster == cluster.id).collect();
+        cluster.x = members.iter().map(|n| n.x).sum::<f64>() / members.len() as f64;
+        cluster.y = members.iter().map(|n| n.y).sum::<f64>() / members.len() as f64;
+    }
+}
+
+/// 按条件取知识图谱，附带社区划分和建议的布局坐标
+pub fn query_graph(conn: &Connection, query: &KnowledgeGraphQuery) -> Result<KnowledgeGraph, String> {
+    let mut nodes = load_nodes(conn, &query.project_id)?;
+    if let Some(focus) = &query.focus_entry_id {
+        if !nodes.iter().any(|n| &n.entry_id == focus) {
+            return Err("焦点条目不存在".to_string());
+        }
+    }
+    // 按类型过滤时焦点条目始终保留
+    if let Some(types) = &query.entry_types {
+        nodes.retain(|n| types.contains(&n.entry_type) || query.focus_entry_id.as_ref() == Some(&n.entry_id));
+    }
+    let mut edges = load_edges(conn, &query.project_id)?;
+
+    if let Some(focus) = &query.focus_entry_id {
+        let index: HashMap<&str, usize> = nodes.iter().enumerate().map(|(i, n)| (n.entry_id.as_str(), i)).collect();
+        let neighbors = adjacency(nodes.len(), &index, &edges);
+        let reached = distances(&neighbors, index[focus.as_str()], query.depth.unwrap_or(DEFAULT_DEPTH));
+        for (i, node) in nodes.iter_mut().enumerate() {
+            node.depth = reached.get(&i).copied();
+        }
+        nodes.retain(|n| n.depth.is_some());
+    }
+
+    let index: HashMap<&str, usize> = nodes.iter().enumerate().map(|(i, n)| (n.entry_id.as_str(), i)).collect();
+    edges.retain(|e| index.contains_key(e.from_entry_id.as_str()) && index.contains_key(e.to_entry_id.as_str()));
+    let neighbors = adjacency(nodes.len(), &index, &edges);
+    let labels = communities(&neighbors);
+    for (i, node) in nodes.iter_mut().enumerate() {
+        node.degree = neighbors[i].len();
+        node.cluster = labels[i];
+    }
+
+    let cluster_count = labels.iter().max().map_or(0, |m| m + 1);
+    let mut clusters: Vec<GraphCluster> = (0..cluster_count)
+        .map(|id| {
+            let members: Vec<&GraphNode> = nodes.iter().filter(|n| n.cluster == id).collect();
+            let hub = members.iter().max_by(|a, b| a.degree.cmp(&b.degree).then(a.importance.cmp(&b.importance)).then(b.title.cmp(&a.title)));
+            GraphCluster { id, label: hub.map(|n| n.title.clone()).unwrap_or_default(), size: members.len(), x: 0.0, y: 0.0 }
+        })
+        .collect();
+    if query.focus_entry_id.is_some() {
+        radial_layout(&mut nodes, &mut clusters);
+    } else {
+        cluster_layout(&mut nodes, &mut clusters);
+    }
+
+    Ok(KnowledgeGraph { nodes, edges, clusters })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_graph_clusters_and_focus() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO knowledge_entries (id, project_id, entry_type, title, content, source_type, created_at, updated_at) VALUES
+                 ('a', 'p1', 'character', '沈青', '', 'manual', 't0', 't0'),
+                 ('b', 'p1', 'character', '柳三娘', '', 'manual', 't0', 't0'),
+                 ('c', 'p1', 'location', '江南', '', 'manual', 't0', 't0'),
+                 ('d', 'p1', 'location', '京城', '', 'manual', 't0', 't0'),
+                 ('e', 'p1', 'character', '太子', '', 'manual', 't0', 't0'),
+                 ('f', 'p1', 'item', '玉玺', '', 'manual', 't0', 't0');
+             INSERT INTO knowledge_relations (id, project_id, from_entry_id, to_entry_id, relation_type, strength, created_at) VALUES
+                 ('r1', 'p1', 'a', 'b', 'friend', 3, 't0'),
+                 ('r2', 'p1', 'b', 'c', 'lives_in', 3, 't0'),
+                 ('r3', 'p1', 'a', 'c', 'born_in', 3, 't0'),
+                 ('r4', 'p1', 'c', 'd', 'road', 1, 't0'),
+                 ('r5', 'p1', 'd', 'e', 'lives_in', 3, 't0'),
+                 ('r6', 'p1', 'e', 'f', 'owns', 3, 't0'),
+                 ('r7', 'p1', 'd', 'f', 'kept_in', 3, 't0');",
+        )
+        .unwrap();
+
+        let full = query_graph(&conn, &KnowledgeGraphQuery { project_id: "p1".to_string(), focus_entry_id: None, depth: None, entry_types: None }).unwrap();
+        assert_eq!((full.nodes.len(), full.edges.len(), full.clusters.len()), (6, 7, 2));
+        let cluster_of = |id: &str| full.nodes.iter().find(|n| n.entry_id == id).unwrap().cluster;
+        assert_eq!(cluster_of("a"), cluster_of("b"));
+        assert_eq!(cluster_of("d"), cluster_of("f"));
+        assert_ne!(cluster_of("a"), cluster_of("d"));
+        assert!(full.clusters.iter().all(|c| c.size == 3));
+        let (a, d) = (&full.clusters[cluster_of("a")], &full.clusters[cluster_of("d")]);
+        assert!((a.x - d.x).abs() + (a.y - d.y).abs() > NODE_SPACING);
+
+        let focused = query_graph(
+            &conn,
+            &KnowledgeGraphQuery { project_id: "p1".to_string(), focus_entry_id: Some("a".to_string()), depth: Some(2), entry_types: None },
+        )
+        .unwrap();
+        let mut reached: Vec<(&str, Option<usize>)> = focused.nodes.iter().map(|n| (n.entry_id.as_str(), n.depth)).collect();
+        reached.sort();
+        assert_eq!(reached, vec![("a", Some(0)), ("b", Some(1)), ("c", Some(1)), ("d", Some(2))]);
+        assert_eq!(focused.edges.len(), 4);
+        let origin = focused.nodes.iter().find(|n| n.entry_id == "a").unwrap();
+        assert_eq!((origin.x, origin.y), (0.0, 0.0));
+
+        let filtered = query_graph(
+            &conn,
+            &KnowledgeGraphQuery {
+                project_id: "p1".to_string(),
+                focus_entry_id: Some("a".to_string()),
+                depth: Some(3),
+                entry_types: Some(vec!["character".to_string()]),
+            },
+        )
+        .unwrap();
+        assert_eq!(filtered.nodes.len(), 2);
+        assert!(query_graph(
+            &conn,
+            &KnowledgeGraphQuery { project_id: "p1".to_string(), focus_entry_id: Some("x".to_string()), depth: None, entry_types: None }
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,25 @@
+use crate::knowledge_graph::{self, KnowledgeGraphQuery};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// 取知识图谱：可按条目类型过滤、以某个条目为中心限定深度，返回社区划分和建议坐标
+#[tauri::command]
+pub async fn get_knowledge_graph(app: AppHandle, query: KnowledgeGraphQuery) -> Result<String, String> {
+    let conn = crate::database::get_connection(&get_db_path(&app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    let graph = knowledge_graph::query_graph(&conn, &query)?;
+    serde_json::to_string(&graph).map_err(|e| e.to_string())
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
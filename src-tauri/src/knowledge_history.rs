@@ -0,0 +1,343 @@
+use crate::snapshot_store;
+use crate::text_diff::{self, ParagraphDiff};
+use crate::version_control::FieldChange;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// 知识条目某一时刻的内容；核实和定论标记由各自的流程维护，不随版本恢复
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnowledgeEntrySnapshot {
+    pub id: String,
+    pub entry_type: String,
+    pub title: String,
+    pub content: String,
+    pub keywords: Option<String>,
+    pub importance: i32,
+    pub source_type: String,
+    pub source_id: Option<String>,
+}
+
+/// 知识条目的一个历史版本，内容存在快照数据块中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeRevision {
+    pub id: String,
+    pub entry_id: String,
+    pub project_id: String,
+    pub title: String,
+    /// 如“修改前”“删除前”“恢复前”“生成时引用”
+    pub note: Option<String>,
+    pub created_at: String,
+}
+
+/// 两个版本之间的差异，正文逐段对比
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeRevisionDiff {
+    pub entry_id: String,
+    pub field_changes: Vec<FieldChange>,
+    pub paragraphs: Vec<ParagraphDiff>,
+    pub inserted_chars: usize,
+    pub deleted_chars: usize,
+}
+
+/// 一次 AI 生成，`kind` 为发起生成的功能，如 knowledge_context
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationRun {
+    pub id: String,
+    pub project_id: String,
+    pub kind: String,
+    pub chapter_id: Option<String>,
+    pub created_at: String,
+    pub revisions: Vec<KnowledgeRevision>,
+}
+
+fn current_state(conn: &Connection, entry_id: &str) -> Result<Option<(String, KnowledgeEntrySnapshot)>, String> {
+    conn.query_row(
+        "SELECT project_id, id, entry_type, title, content, keywords, importance, source_type, source_id FROM knowledge_entries WHERE id = ?",
+        params![entry_id],
+        |row| {
+            Ok((
+                row.get(0)?,
+                KnowledgeEntrySnapshot {
+                    id: row.get(1)?,
+                    entry_type: row.get(2)?,
+                    title: row.get(3)?,
+                    content: row.get(4)?,
+                    keywords: row.get(5)?,
+                    importance: row.get::<_, Option<i32>>(6)?.unwrap_or(0),
+                    source_type: row.get(7)?,
+                    source_id: row.get(8)?,
+                },
+            ))
+        },
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+fn latest_revision(conn: &Connection, entry_id: &str) -> Result<Option<(String, String)>, String> {
+    conn.query_row(
+        "SELECT id, blob_hash FROM knowledge_revisions WHERE entry_id = ? ORDER BY created_at DESC, rowid DESC LIMIT 1",
+        params![entry_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// 保存条目当前的内容，与最近一个版本相同时不重复保存；条目不存在时返回 None
+pub fn record_revision(conn: &Connection, entry_id: &str, note: Option<&str>) -> Result<Option<KnowledgeRevision>, String> {
+    let Some((project_id, state)) = current_state(conn, entry_id)? else {
+        return Ok(None);
+    };
+    let hash = snapshot_store::store_item(conn, &state)?;
+    if latest_revision(conn, entry_id)?.is_some_and(|(_, latest)| latest == hash) {
+        return Ok(None);
+    }
+    let revision = KnowledgeRevision {
+        id: uuid::Uuid::new_v4().to_string(),
+        entry_id: entry_id.to_string(),
+        project_id,
+        title: state.title,
+        note: note.map(str::to_string),
+        created_at: Utc::now().to_rfc3339(),
+    };
+    conn.execute(
+        "INSERT INTO knowledge_revisions (id, entry_id, project_id, blob_hash, title, note, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        params![revision.id, revision.entry_id, revision.project_id, hash, revision.title, revision.note, revision.created_at],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(Some(revision))
+}
+
+/// 与条目当前内容一致的版本 id，没有时先保存一个
+fn current_revision_id(conn: &Connection, entry_id: &str) -> Result<Option<String>, String> {
+    if let Some(revision) = record_revision(conn, entry_id, Some("生成时引用"))? {
+        return Ok(Some(revision.id));
+    }
+    Ok(latest_revision(conn, entry_id)?.map(|(id, _)| id))
+}
+
+const COLUMNS: &str = "id, entry_id, project_id, title, note, created_at";
+
+fn read_revision(row: &rusqlite::Row) -> rusqlite::Result<KnowledgeRevision> {
+    Ok(KnowledgeRevision {
+        id: row.get(0)?,
+        entry_id: row.get(1)?,
+        project_id: row.get(2)?,
+        title: row.get(3)?,
+        note: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+/// 条目的历史版本，最新的在前
+pub fn list_revisions(conn: &Connection, entry_id: &str) -> Result<Vec<KnowledgeRevision>, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM knowledge_revisions WHERE entry_id = ? ORDER BY created_at DESC, rowid DESC", COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let revisions = stmt.query_map(params![entry_id], read_revision).map_err(|e| e.to_string())?;
+    revisions.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// 已删除条目各自的最后一个版本，用于找回
+pub fn deleted_entries(conn: &Connection, project_id: &str) -> Result<Vec<KnowledgeRevision>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM knowledge_revisions r
+             WHERE r.project_id = ?1 AND NOT EXISTS (SELECT 1 FROM knowledge_entries k WHERE k.id = r.entry_id)
+               AND r.rowid = (SELECT rowid FROM knowledge_revisions WHERE entry_id = r.entry_id
+                              ORDER BY created_at DESC, rowid DESC LIMIT 1)
+             ORDER BY r.created_at DESC",
+            COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let revisions = stmt.query_map(params![project_id], read_revision).map_err(|e| e.to_string())?;
+    revisions.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn load_revision(conn: &Connection, revision_id: &str) -> Result<(String, KnowledgeEntrySnapshot), String> {
+    let (project_id, hash): (String, String) = conn
+        .query_row(
+            "SELECT project_id, blob_hash FROM knowledge_revisions WHERE id = ?",
+            params![revision_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("版本不存在: {}", revision_id))?;
+    Ok((project_id, snapshot_store::load_item(conn, &hash)?))
+}
+
+/// 某个版本的完整内容
+pub fn revision_content(conn: &Connection, revision_id: &str) -> Result<KnowledgeEntrySnapshot, String> {
+    Ok(load_revision(conn, revision_id)?.1)
+}
+
+fn field_change(changes: &mut Vec<FieldChange>, field: &str, old: Option<&str>, new: Option<&str>) {
+    if old != new {
+        changes.push(FieldChange {
+            field: field.to_string(),
+            old_value: old.map(str::to_string),
+            new_value: new.map(str::to_string),
+        });
+    }
+}
+
+/// 对比某个版本与另一个版本，`against` 为空时与条目当前内容对比（条目已删除时视为空）
+pub fn diff_revision(conn: &Connection, revision_id: &str, against: Option<&str>) -> Result<KnowledgeRevisionDiff, String> {
+    let (_, from) = load_revision(conn, revision_id)?;
+    let to = match against {
+        Some(other) => Some(load_revision(conn, other)?.1),
+        None => current_state(conn, &from.id)?.map(|(_, state)| state),
+    };
+    let mut field_changes = Vec::new();
+    field_change(&mut field_changes, "title", Some(&from.title), to.as_ref().map(|t| t.title.as_str()));
+    field_change(&mut field_changes, "entry_type", Some(&from.entry_type), to.as_ref().map(|t| t.entry_type.as_str()));
+    field_change(&mut field_changes, "keywords", from.keywords.as_deref(), to.as_ref().and_then(|t| t.keywords.as_deref()));
+    let importance = to.as_ref().map(|t| t.importance.to_string());
+    field_change(&mut field_changes, "importance", Some(&from.importance.to_string()), importance.as_deref());
+    let paragraphs = text_diff::diff_paragraphs(&from.content, to.as_ref().map(|t| t.content.as_str()).unwrap_or(""));
+    let (inserted_chars, deleted_chars) = text_diff::change_counts(&paragraphs);
+    Ok(KnowledgeRevisionDiff { entry_id: from.id, field_changes, paragraphs, inserted_chars, deleted_chars })
+}
+
+/// 把条目恢复为某个版本，已删除的条目会重新创建（未核实）；恢复前的内容另存为一个版本，可以撤销
+pub fn restore_revision(conn: &Connection, revision_id: &str) -> Result<KnowledgeEntrySnapshot, String> {
+    let (project_id, state) = load_revision(conn, revision_id)?;
+    record_revision(conn, &state.id, Some("恢复前"))?;
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO knowledge_entries
+         (id, project_id, entry_type, title, content, source_type, source_id, keywords, importance, is_verified, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 0, ?10, ?10)
+         ON CONFLICT(id) DO UPDATE SET entry_type = excluded.entry_type, title = excluded.title, content = excluded.content,
+             keywords = excluded.keywords, importance = excluded.importance, updated_at = excluded.updated_at",
+        params![
+            state.id,
+            project_id,
+            state.entry_type,
+            state.title,
+            state.content,
+            state.source_type,
+            state.source_id,
+            state.keywords,
+            state.importance,
+            now
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    crate::knowledge_links::link_entry(conn, &state.id)?;
+    Ok(state)
+}
+
+/// 记录一次生成用到的条目，保存各条目当前的版本，返回生成记录的 id
+pub fn record_generation_run(conn: &Connection, project_id: &str, kind: &str, chapter_id: Option<&str>, entry_ids: &[String]) -> Result<String, String> {
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO knowledge_generation_runs (id, project_id, kind, chapter_id, created_at) VALUES (?, ?, ?, ?, ?)",
+        params![run_id, project_id, kind, chapter_id, Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| e.to_string())?;
+    for entry_id in entry_ids {
+        if let Some(revision_id) = current_revision_id(&tx, entry_id)? {
+            tx.execute(
+                "INSERT OR IGNORE INTO knowledge_run_revisions (run_id, revision_id, entry_id) VALUES (?, ?, ?)",
+                params![run_id, revision_id, entry_id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(run_id)
+}
+
+fn run_revisions(conn: &Connection, run_id: &str) -> Result<Vec<KnowledgeRevision>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT r.id, r.entry_id, r.project_id, r.title, r.note, r.created_at FROM knowledge_run_revisions u
+             JOIN knowledge_revisions r ON r.id = u.revision_id WHERE u.run_id = ? ORDER BY r.title",
+        )
+        .map_err(|e| e.to_string())?;
+    let revisions = stmt.query_map(params![run_id], read_revision).map_err(|e| e.to_string())?;
+    revisions.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn read_runs(conn: &Connection, sql: &str, key: &str) -> Result<Vec<GenerationRun>, String> {
+    let runs: Vec<(String, String, String, Option<String>, String)> = conn
+        .prepare(sql)
+        .map_err(|e| e.to_string())?
+        .query_map(params![key], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    runs.into_iter()
+        .map(|(id, project_id, kind, chapter_id, created_at)| {
+            let revisions = run_revisions(conn, &id)?;
+            Ok(GenerationRun { id, project_id, kind, chapter_id, created_at, revisions })
+        })
+        .collect()
+}
+
+/// 一次生成及其用到的条目版本
+pub fn generation_run(conn: &Connection, run_id: &str) -> Result<GenerationRun, String> {
+    read_runs(conn, "SELECT id, project_id, kind, chapter_id, created_at FROM knowledge_generation_runs WHERE id = ?", run_id)?
+        .pop()
+        .ok_or_else(|| format!("生成记录不存在: {}", run_id))
+}
+
+/// 用到过该条目的生成，最新的在前
+pub fn entry_generation_runs(conn: &Connection, entry_id: &str) -> Result<Vec<GenerationRun>, String> {
+    read_runs(
+        conn,
+        "SELECT g.id, g.project_id, g.kind, g.chapter_id, g.created_at FROM knowledge_generation_runs g
+         WHERE g.id IN (SELECT run_id FROM knowledge_run_revisions WHERE entry_id = ?) ORDER BY g.created_at DESC, g.rowid DESC",
+        entry_id,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_revisions_and_generation_runs() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO knowledge_entries (id, project_id, entry_type, title, content, source_type, keywords, importance, created_at, updated_at)
+                 VALUES ('k1', 'p1', 'character', '沈青', '灰色的眼睛。', 'manual', '沈青', 3, 't0', 't0');",
+        )
+        .unwrap();
+
+        let run = record_generation_run(&conn, "p1", "knowledge_context", None, &["k1".to_string(), "missing".to_string()]).unwrap();
+        let used = generation_run(&conn, &run).unwrap().revisions;
+        assert_eq!(used.len(), 1);
+        assert_eq!(used[0].note.as_deref(), Some("生成时引用"));
+
+        // 修改前的内容与生成时引用的版本相同，不重复保存
+        assert!(record_revision(&conn, "k1", Some("修改前")).unwrap().is_none());
+        conn.execute("UPDATE knowledge_entries SET title = '沈清', content = '黑色的眼睛。' WHERE id = 'k1'", []).unwrap();
+        let diff = diff_revision(&conn, &used[0].id, None).unwrap();
+        assert_eq!(diff.field_changes.len(), 1);
+        assert_eq!(diff.field_changes[0].new_value.as_deref(), Some("沈清"));
+        assert!(diff.inserted_chars > 0 && diff.deleted_chars > 0);
+
+        let second = record_generation_run(&conn, "p1", "knowledge_context", None, &["k1".to_string()]).unwrap();
+        assert_ne!(generation_run(&conn, &second).unwrap().revisions[0].id, used[0].id);
+        assert_eq!(entry_generation_runs(&conn, "k1").unwrap().len(), 2);
+
+        record_revision(&conn, "k1", Some("删除前")).unwrap();
+        conn.execute("DELETE FROM knowledge_entries WHERE id = 'k1'", []).unwrap();
+        assert_eq!(deleted_entries(&conn, "p1").unwrap()[0].title, "沈清");
+        restore_revision(&conn, &used[0].id).unwrap();
+        let (title, content): (String, String) =
+            conn.query_row("SELECT title, content FROM knowledge_entries WHERE id = 'k1'", [], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        assert_eq!((title.as_str(), content.as_str()), ("沈青", "灰色的眼睛。"));
+        assert_eq!(list_revisions(&conn, "k1").unwrap().len(), 2);
+        assert_eq!(revision_content(&conn, &used[0].id).unwrap().title, "沈青");
+
+        snapshot_store::remove_orphan_chunks(&conn).unwrap();
+        assert!(diff_revision(&conn, &used[0].id, None).unwrap().field_changes.is_empty());
+    }
+}
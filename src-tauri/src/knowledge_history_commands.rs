@@ -0,0 +1,84 @@
+use crate::knowledge_history;
+use crate::logger::Logger;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// 知识条目的历史版本，条目每次修改或删除前自动保存
+#[tauri::command]
+pub async fn get_knowledge_revisions(app: AppHandle, entry_id: String) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let revisions = knowledge_history::list_revisions(&conn, &entry_id)?;
+    serde_json::to_string(&revisions).map_err(|e| e.to_string())
+}
+
+/// 已删除、可以找回的知识条目
+#[tauri::command]
+pub async fn get_deleted_knowledge_entries(app: AppHandle, project_id: String) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let revisions = knowledge_history::deleted_entries(&conn, &project_id)?;
+    serde_json::to_string(&revisions).map_err(|e| e.to_string())
+}
+
+/// 某个版本的完整内容
+#[tauri::command]
+pub async fn get_knowledge_revision_content(app: AppHandle, revision_id: String) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let content = knowledge_history::revision_content(&conn, &revision_id)?;
+    serde_json::to_string(&content).map_err(|e| e.to_string())
+}
+
+/// 对比知识条目的两个版本，不指定 `against_revision_id` 时与当前内容对比
+#[tauri::command]
+pub async fn diff_knowledge_revision(
+    app: AppHandle,
+    revision_id: String,
+    against_revision_id: Option<String>,
+) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let diff = knowledge_history::diff_revision(&conn, &revision_id, against_revision_id.as_deref())?;
+    serde_json::to_string(&diff).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn restore_knowledge_revision(app: AppHandle, revision_id: String) -> Result<String, String> {
+    let logger = Logger::new().with_feature("knowledge_history");
+    logger.info(&format!("Restoring knowledge revision {}", revision_id));
+
+    let conn = open_connection(&app)?;
+    let entry = knowledge_history::restore_revision(&conn, &revision_id)?;
+    serde_json::to_string(&entry).map_err(|e| e.to_string())
+}
+
+/// 一次生成用到的各条目版本，`run_id` 见知识上下文的 `generation_run_id`
+#[tauri::command]
+pub async fn get_knowledge_generation_run(app: AppHandle, run_id: String) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let run = knowledge_history::generation_run(&conn, &run_id)?;
+    serde_json::to_string(&run).map_err(|e| e.to_string())
+}
+
+/// 用到过该知识条目的生成，最新的在前
+#[tauri::command]
+pub async fn get_knowledge_entry_generation_runs(app: AppHandle, entry_id: String) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let runs = knowledge_history::entry_generation_runs(&conn, &entry_id)?;
+    serde_json::to_string(&runs).map_err(|e| e.to_string())
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
+
+fn open_connection(app: &AppHandle) -> Result<rusqlite::Connection, String> {
+    crate::database::get_connection(&get_db_path(app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))
+}
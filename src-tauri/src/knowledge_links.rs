@@ -0,0 +1,240 @@
+use crate::entity_index::locate_terms;
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// 光标前后各取这么多字，范围内被提到的条目优先进入 AI 上下文
+pub const DEFAULT_WINDOW: usize = 1500;
+/// 单字的标题或关键词误匹配太多，不参与链接
+const MIN_TERM_CHARS: usize = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeMention {
+    pub entry_id: String,
+    pub chapter_id: String,
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// 光标附近被提到的条目，`distance` 为最近一次提及到光标的字数，光标落在提及内时为 0
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NearbyEntry {
+    pub entry_id: String,
+    pub title: String,
+    pub distance: usize,
+    pub mentions: usize,
+}
+
+/// 条目的标题和关键词，关键词按常见分隔符拆开
+fn entry_terms(title: &str, keywords: Option<&str>) -> Vec<String> {
+    let mut terms = vec![title.trim().to_string()];
+    for keyword in keywords.unwrap_or("").split([',', '，', '、', ';', '；', '\n']) {
+        let keyword = keyword.trim().to_string();
+        if !terms.contains(&keyword) {
+            terms.push(keyword);
+        }
+    }
+    terms.retain(|t| t.chars().count() >= MIN_TERM_CHARS);
+    terms
+}
+
+/// 字节偏移到字符偏移
+fn char_offsets(content: &str) -> Vec<usize> {
+    let mut offsets = vec![0; content.len() + 1];
+    for (chars, (byte, c)) in content.char_indices().enumerate() {
+        offsets[byte..byte + c.len_utf8()].iter_mut().for_each(|o| *o = chars);
+        offsets[byte + c.len_utf8()] = chars + 1;
+    }
+    offsets
+}
+
+/// `terms` 为（条目 id，词）；返回在正文中找到的提及，较长的词优先
+fn locate(chapter_id: &str, content: &str, terms: &[(String, String)]) -> Vec<KnowledgeMention> {
+    let texts: Vec<&str> = terms.iter().map(|(_, t)| t.as_str()).collect();
+    let offsets = char_offsets(content);
+    locate_terms(content, &texts)
+        .into_iter()
+        .map(|(start, end, i)| KnowledgeMention {
+            entry_id: terms[i].0.clone(),
+            chapter_id: chapter_id.to_string(),
+            text: terms[i].1.clone(),
+            start: offsets[start],
+            end: offsets[end],
+        })
+        .collect()
+}
+
+fn project_terms(conn: &Connection, project_id: &str, entry_id: Option<&str>) -> Result<Vec<(String, String)>, String> {
+    let rows: Vec<(String, String, Option<String>)> = conn
+        .prepare("SELECT id, title, keywords FROM knowledge_entries WHERE project_id = ?1 AND (?2 IS NULL OR id = ?2) ORDER BY id")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id, entry_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rows.into_iter().flat_map(|(id, title, keywords)| entry_terms(&title, keywords.as_deref()).into_iter().map(move |t| (id.clone(), t))).collect())
+}
+
+fn insert_mentions(conn: &Connection, project_id: &str, mentions: &[KnowledgeMention]) -> Result<(), String> {
+    let now = Utc::now().to_rfc3339();
+    for mention in mentions {
+        conn.execute(
+            "INSERT INTO knowledge_mentions (id, project_id, entry_id, chapter_id, text, start_offset, end_offset, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                Uuid::new_v4().to_string(),
+                project_id,
+                mention.entry_id,
+                mention.chapter_id,
+                mention.text,
+                mention.start as i64,
+                mention.end as i64,
+                now
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// 重新链接一章正文中提到的知识条目，返回提及次数
+pub fn link_chapter(conn: &Connection, chapter_id: &str) -> Result<usize, String> {
+    let (project_id, content): (String, String) = conn
+        .query_row("SELECT project_id, COALESCE(content, '') FROM chapters WHERE id = ?", params![chapter_id], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .map_err(|_| "章节不存在".to_string())?;
+    let mentions = locate(chapter_id, &content, &project_terms(conn, &project_id, None)?);
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM knowledge_mentions WHERE chapter_id = ?", params![chapter_id]).map_err(|e| e.to_string())?;
+    insert_mentions(&tx, &project_id, &mentions)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(mentions.len())
+}
+
+/// 条目新建或改名后，只重新链接这一个条目；与其他条目重叠的部分等章节下次保存时再整章校正
+pub fn link_entry(conn: &Connection, entry_id: &str) -> Result<usize, String> {
+    let project_id: String = conn
+        .query_row("SELECT project_id FROM knowledge_entries WHERE id = ?", params![entry_id], |row| row.get(0))
+        .map_err(|_| "知识条目不存在".to_string())?;
+    let terms = project_terms(conn, &project_id, Some(entry_id))?;
+    let chapters: Vec<(String, String)> = conn
+        .prepare("SELECT id, COALESCE(content, '') FROM chapters WHERE project_id = ?")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mentions: Vec<KnowledgeMention> = if terms.is_empty() {
+        Vec::new()
+    } else {
+        chapters.iter().flat_map(|(chapter_id, content)| locate(chapter_id, content, &terms)).collect()
+    };
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM knowledge_mentions WHERE entry_id = ?", params![entry_id]).map_err(|e| e.to_string())?;
+    insert_mentions(&tx, &project_id, &mentions)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(mentions.len())
+}
+
+/// 重新链接项目的所有章节
+pub fn link_project(conn: &Connection, project_id: &str) -> Result<usize, String> {
+    let chapter_ids: Vec<String> = conn
+        .prepare("SELECT id FROM chapters WHERE project_id = ?")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    chapter_ids.iter().try_fold(0, |total, id| Ok(total + link_chapter(conn, id)?))
+}
+
+pub fn chapter_mentions(conn: &Connection, chapter_id: &str) -> Result<Vec<KnowledgeMention>, String> {
+    conn.prepare("SELECT entry_id, chapter_id, text, start_offset, end_offset FROM knowledge_mentions WHERE chapter_id = ? ORDER BY start_offset")
+        .map_err(|e| e.to_string())?
+        .query_map(params![chapter_id], |row| {
+            Ok(KnowledgeMention {
+                entry_id: row.get(0)?,
+                chapter_id: row.get(1)?,
+                text: row.get(2)?,
+                start: row.get::<_, i64>(3)? as usize,
+                end: row.get::<_, i64>(4)? as usize,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 光标前后 `window` 字内被提到的条目，离光标越近越靠前，同样近的按提及次数排序
+pub fn entries_near(conn: &Connection, chapter_id: &str, cursor: usize, window: usize) -> Result<Vec<NearbyEntry>, String> {
+    let mut nearby: HashMap<String, (usize, usize)> = HashMap::new();
+    for mention in chapter_mentions(conn, chapter_id)? {
+        let distance = if cursor < mention.start {
+            mention.start - cursor
+        } else {
+            cursor.saturating_sub(mention.end)
+        };
+        if distance > window {
+            continue;
+        }
+        let slot = nearby.entry(mention.entry_id).or_insert((distance, 0));
+        slot.0 = slot.0.min(distance);
+        slot.1 += 1;
+    }
+
+    let mut entries = Vec::new();
+    for (entry_id, (distance, mentions)) in nearby {
+        let title: String = conn
+            .query_row("SELECT title FROM knowledge_entries WHERE id = ?", params![entry_id], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        entries.push(NearbyEntry { entry_id, title, distance, mentions });
+    }
+    entries.sort_by(|a, b| a.distance.cmp(&b.distance).then(b.mentions.cmp(&a.mentions)).then(a.title.cmp(&b.title)));
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_chapter_and_entries_near_cursor() {
+        let (_dir, conn) = crate::test_support::project_db();
+        let content = format!("沈青来到青云山下。{}寒铁剑在鞘中轻鸣，沈青握紧了剑。", "雪".repeat(100));
+        conn.execute_batch(
+            "INSERT INTO knowledge_entries (id, project_id, entry_type, title, content, source_type, keywords, created_at, updated_at) VALUES
+                 ('k1', 'p1', 'character', '沈青', '', 'manual', '青,沈公子', 't0', 't0'),
+                 ('k2', 'p1', 'location', '青云山', '', 'manual', NULL, 't0', 't0'),
+                 ('k3', 'p1', 'item', '寒铁剑', '', 'manual', '剑', 't0', 't0');",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES ('c1', 'p1', '第一章', ?, 1, 't0', 't0')",
+            params![content],
+        )
+        .unwrap();
+
+        assert_eq!(link_chapter(&conn, "c1").unwrap(), 4);
+        let mentions = chapter_mentions(&conn, "c1").unwrap();
+        assert_eq!((mentions[1].entry_id.as_str(), mentions[1].start, mentions[1].end), ("k2", 4, 7));
+
+        let near_start: Vec<String> = entries_near(&conn, "c1", 0, 50).unwrap().into_iter().map(|e| e.entry_id).collect();
+        assert_eq!(near_start, vec!["k1", "k2"]);
+        let cursor = content.chars().count();
+        let near_end = entries_near(&conn, "c1", cursor, 50).unwrap();
+        assert_eq!(near_end.iter().map(|e| e.entry_id.as_str()).collect::<Vec<_>>(), vec!["k1", "k3"]);
+        assert_eq!(near_end[0].mentions, 1);
+        assert_eq!(entries_near(&conn, "c1", cursor, 1000).unwrap()[0].mentions, 2);
+
+        conn.execute("UPDATE knowledge_entries SET keywords = '寒铁' WHERE id = 'k3'", []).unwrap();
+        conn.execute("UPDATE knowledge_entries SET title = '玄铁剑' WHERE id = 'k3'", []).unwrap();
+        assert_eq!(link_entry(&conn, "k3").unwrap(), 1);
+        assert_eq!(link_project(&conn, "p1").unwrap(), 4);
+    }
+}
@@ -0,0 +1,43 @@
+use crate::knowledge_links;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// 光标附近提到的知识条目，`window` 为光标前后的字数
+#[tauri::command]
+pub async fn get_knowledge_near_cursor(app: AppHandle, chapter_id: String, cursor: usize, window: Option<usize>) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let entries = knowledge_links::entries_near(&conn, &chapter_id, cursor, window.unwrap_or(knowledge_links::DEFAULT_WINDOW))?;
+    serde_json::to_string(&entries).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_chapter_knowledge_mentions(app: AppHandle, chapter_id: String) -> Result<String, String> {
+    let conn = open_connection(&app)?;
+    let mentions = knowledge_links::chapter_mentions(&conn, &chapter_id)?;
+    serde_json::to_string(&mentions).map_err(|e| e.to_string())
+}
+
+/// 重新链接整个项目，返回提及次数
+#[tauri::command]
+pub async fn relink_knowledge_mentions(app: AppHandle, project_id: String) -> Result<usize, String> {
+    let conn = open_connection(&app)?;
+    knowledge_links::link_project(&conn, &project_id)
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
+
+fn open_connection(app: &AppHandle) -> Result<rusqlite::Connection, String> {
+    crate::database::get_connection(&get_db_path(app)?)
+        .map_err(|e| format!("Failed to get database connection: {}", e))
+}
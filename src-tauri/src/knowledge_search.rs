@@ -0,0 +1,242 @@
+use crate::models::{KnowledgeEntry, KnowledgeSearchResult};
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+
+/// 知识库默认使用的向量模型
+pub const EMBEDDING_MODEL: &str = "embedding-3";
+/// 每次请求向量接口时最多提交的条目数
+pub const EMBEDDING_BATCH_SIZE: usize = 16;
+/// 参与向量化的正文最多取这么多字
+const MAX_EMBED_CHARS: usize = 2000;
+/// 只有语义命中时，相似度低于这个值的条目不返回
+const MIN_SEMANTIC_SCORE: f32 = 0.35;
+const SEMANTIC_WEIGHT: f32 = 0.8;
+const KEYWORD_WEIGHT: f32 = 0.2;
+
+/// 需要（重新）生成向量的条目
+#[derive(Debug, Clone)]
+pub struct PendingEmbedding {
+    pub entry_id: String,
+    pub content_hash: String,
+    pub text: String,
+}
+
+/// 条目参与向量化的文本：标题、关键词和截断后的正文
+fn embedding_text(entry: &KnowledgeEntry) -> String {
+    let content: String = entry.content.chars().take(MAX_EMBED_CHARS).collect();
+    format!("{}\n{}\n{}", entry.title, entry.keywords.as_deref().unwrap_or(""), content)
+}
+
+fn content_hash(text: &str) -> String {
+    Sha256::digest(text.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn encode(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decode(bytes: &[u8]) -> Vec<f32> {
+    bytes.chunks_exact(4).map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect()
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        0.0
+    } else {
+        dot / norm
+    }
+}
+
+/// 条目、当前内容的哈希和已存的向量
+type StoredEntry = (KnowledgeEntry, String, Option<Vec<f32>>);
+
+/// 条目和已存的向量；向量缺失、模型不同或内容已改动时为空
+fn load_entries(conn: &Connection, project_id: &str, model: &str) -> Result<Vec<StoredEntry>, String> {
+    let rows: Vec<(KnowledgeEntry, Option<String>, Option<Vec<u8>>)> = conn
+        .prepare(
+            "SELECT e.id, e.project_id, e.entry_type, e.title, e.content, e.source_type, e.source_id,
+                    e.keywords, e.importance, e.is_verified, e.created_at, e.updated_at, v.content_hash, v.embedding
+             FROM knowledge_entries e
+             LEFT JOIN knowledge_embeddings v ON v.entry_id = e.id AND v.model = ?2
+             WHERE e.project_id = ?1",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id, model], |row| {
+            Ok((
+                KnowledgeEntry {
+                    id: row.get(0)?,
+                    project_id: row.get(1)?,
+                    entry_type: row.get(2)?,
+                    title: row.get(3)?,
+                    content: row.get(4)?,
+                    source_type: row.get(5)?,
+                    source_id: row.get(6)?,
+                    keywords: row.get(7)?,
+                    importance: row.get(8)?,
+                    is_verified: row.get::<_, i32>(9)? != 0,
+                    created_at: row.get(10)?,
+                    updated_at: row.get(11)?,
+                },
+                row.get(12)?,
+                row.get(13)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(entry, stored_hash, bytes)| {
+            let hash = content_hash(&embedding_text(&entry));
+            let vector = bytes.filter(|_| stored_hash.as_deref() == Some(hash.as_str())).map(|b| decode(&b));
+            (entry, hash, vector)
+        })
+        .collect())
+}
+
+/// 项目中还没有最新向量的条目
+pub fn stale_entries(conn: &Connection, project_id: &str, model: &str) -> Result<Vec<PendingEmbedding>, String> {
+    Ok(load_entries(conn, project_id, model)?
+        .into_iter()
+        .filter(|(_, _, vector)| vector.is_none())
+        .map(|(entry, content_hash, _)| PendingEmbedding { text: embedding_text(&entry), entry_id: entry.id, content_hash })
+        .collect())
+}
+
+/// 保存一批向量，`vectors` 与 `pending` 一一对应
+pub fn store_embeddings(conn: &Connection, model: &str, pending: &[PendingEmbedding], vectors: &[Vec<f32>]) -> Result<(), String> {
+    if pending.len() != vectors.len() {
+        return Err("向量数量与条目数量不一致".to_string());
+    }
+    let now = Utc::now().to_rfc3339();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for (item, vector) in pending.iter().zip(vectors) {
+        tx.execute(
+            "INSERT INTO knowledge_embeddings (entry_id, model, content_hash, embedding, updated_at) VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT(entry_id) DO UPDATE SET model = excluded.model, content_hash = excluded.content_hash,
+                 embedding = excluded.embedding, updated_at = excluded.updated_at",
+            params![item.entry_id, model, item.content_hash, encode(vector), now],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// 关键词得分：每个查询词按标题、关键词、正文的命中位置加权，归一到 0~1
+fn keyword_score(terms: &BTreeSet<String>, entry: &KnowledgeEntry) -> f32 {
+    if terms.is_empty() {
+        return 0.0;
+    }
+    let title = entry.title.to_lowercase();
+    let keywords = entry.keywords.as_deref().unwrap_or("").to_lowercase();
+    let content = entry.content.to_lowercase();
+    let total: f32 = terms
+        .iter()
+        .map(|term| {
+            if title.contains(term.as_str()) {
+                1.0
+            } else if keywords.contains(term.as_str()) {
+                0.7
+            } else if content.contains(term.as_str()) {
+                0.4
+            } else {
+                0.0
+            }
+        })
+        .sum();
+    total / terms.len() as f32
+}
+
+/// 搜索知识条目。有查询向量时按语义相似度和关键词得分加权排序，否则只按关键词排序
+pub fn search(
+    conn: &Connection,
+    project_id: &str,
+    query: &str,
+    query_embedding: Option<&[f32]>,
+    entry_types: Option<&[String]>,
+    limit: usize,
+) -> Result<Vec<KnowledgeSearchResult>, String> {
+    let terms: BTreeSet<String> = crate::tokenizer::words(query).into_iter().map(|w| w.to_lowercase()).collect();
+    let mut results: Vec<KnowledgeSearchResult> = load_entries(conn, project_id, EMBEDDING_MODEL)?
+        .into_iter()
+        .filter(|(entry, _, _)| entry_types.is_none_or(|types| types.contains(&entry.entry_type)))
+        .filter_map(|(entry, _, vector)| {
+            let keyword = keyword_score(&terms, &entry);
+            let semantic = match (query_embedding, vector.as_deref()) {
+                (Some(query), Some(vector)) => Some(cosine(query, vector).max(0.0)),
+                _ => None,
+            };
+            let (relevance_score, match_type) = match semantic {
+                Some(semantic) if keyword > 0.0 => (SEMANTIC_WEIGHT * semantic + KEYWORD_WEIGHT * keyword, "hybrid"),
+                Some(semantic) if semantic >= MIN_SEMANTIC_SCORE => (SEMANTIC_WEIGHT * semantic, "semantic"),
+                _ if keyword > 0.0 => (keyword, "keyword"),
+                _ => return None,
+            };
+            Some(KnowledgeSearchResult { entry, relevance_score, match_type: match_type.to_string() })
+        })
+        .collect();
+    results.sort_by(|a, b| {
+        b.relevance_score
+            .partial_cmp(&a.relevance_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(b.entry.importance.cmp(&a.entry.importance))
+    });
+    results.truncate(limit);
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_blends_semantic_and_keyword_scores() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO knowledge_entries (id, project_id, entry_type, title, content, source_type, keywords, importance, is_verified, created_at, updated_at) VALUES
+                 ('k1', 'p1', 'location', '青云山', '终年积雪的山门，掌门闭关之地', 'manual', '宗门', 5, 0, 't0', 't0'),
+                 ('k2', 'p1', 'item', '寒铁剑', '主角在寒潭底拾得的古剑', 'manual', NULL, 3, 0, 't0', 't0'),
+                 ('k3', 'p1', 'character', '柳三娘', '江南茶馆的老板娘', 'manual', NULL, 1, 0, 't0', 't0');",
+        )
+        .unwrap();
+
+        let pending = stale_entries(&conn, "p1", EMBEDDING_MODEL).unwrap();
+        assert_eq!(pending.len(), 3);
+        let vectors: Vec<Vec<f32>> = pending
+            .iter()
+            .map(|p| match p.entry_id.as_str() {
+                "k1" => vec![1.0, 0.0, 0.0],
+                "k2" => vec![0.8, 0.6, 0.0],
+                _ => vec![0.0, 0.0, 1.0],
+            })
+            .collect();
+        store_embeddings(&conn, EMBEDDING_MODEL, &pending, &vectors).unwrap();
+        assert!(stale_entries(&conn, "p1", EMBEDDING_MODEL).unwrap().is_empty());
+
+        let results = search(&conn, "p1", "青云山", Some(&[1.0, 0.0, 0.0]), None, 10).unwrap();
+        let found: Vec<(&str, &str)> = results.iter().map(|r| (r.entry.id.as_str(), r.match_type.as_str())).collect();
+        assert_eq!(found, vec![("k1", "hybrid"), ("k2", "semantic")]);
+        assert!(results[0].relevance_score > results[1].relevance_score);
+
+        let keyword_only = search(&conn, "p1", "茶馆", None, None, 10).unwrap();
+        assert_eq!(keyword_only.len(), 1);
+        assert_eq!((keyword_only[0].entry.id.as_str(), keyword_only[0].match_type.as_str()), ("k3", "keyword"));
+
+        let types = vec!["item".to_string()];
+        let filtered = search(&conn, "p1", "青云山", Some(&[1.0, 0.0, 0.0]), Some(&types), 10).unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].entry.id, "k2");
+
+        conn.execute("UPDATE knowledge_entries SET content = '山门已毁' WHERE id = 'k1'", []).unwrap();
+        let stale = stale_entries(&conn, "p1", EMBEDDING_MODEL).unwrap();
+        assert_eq!(stale.iter().map(|p| p.entry_id.as_str()).collect::<Vec<_>>(), vec!["k1"]);
+    }
+}
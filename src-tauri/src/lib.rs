@@ -5,11 +5,13 @@ pub mod export;
 pub mod import;
 pub mod logger;
 pub mod models;
+pub mod repository;
 pub mod plugin_system;
 pub mod plugin_commands;
 pub mod plugin_marketplace_commands;
 pub mod cloud_sync;
 pub mod cloud_sync_commands;
+pub mod text_merge;
 pub mod multimedia_generation;
 pub mod multimedia_generation_commands;
 pub mod writing_tools;
@@ -21,11 +23,51 @@ pub mod character_tags;
 pub mod character_growth_commands;
 pub mod character_dialogue;
 pub mod character_dialogue_commands;
+pub mod text_diff;
+pub mod chapter_branches;
+pub mod auto_snapshot;
+pub mod chapter_post_save;
+pub mod snapshot_store;
+pub mod snapshot_tags;
+pub mod provenance;
+pub mod history_bundle;
+pub mod version_storage;
+pub mod tokenizer;
+pub mod pacing;
+pub mod character_presence;
+pub mod entity_index;
+pub mod text_analysis;
+pub mod timeline_check;
+pub mod sensitive_dictionaries;
+pub mod autocorrect;
+pub mod writing_stats;
+pub mod glossary;
+pub mod numeral_style;
+pub mod character_merge;
+pub mod voice_profile;
+pub mod character_sheet;
+pub mod character_portraits;
+pub mod relation_states;
+pub mod character_aliases;
+pub mod status_check;
+pub mod family_tree;
+pub mod organizations;
+pub mod locations;
+pub mod calendar;
+pub mod items;
+pub mod worldview_history;
+pub mod economy;
+pub mod knowledge_search;
+pub mod knowledge_facts;
+pub mod knowledge_links;
+pub mod knowledge_history;
+#[cfg(test)]
+mod test_support;
 
 pub use ai::*;
 pub use models::*;
-pub use export::{ExportFormat, export_as_docx, export_as_pdf, export_as_epub, export_as_txt, export_as_md};
-pub use import::{ImportFormat, ImportResult, ImportedChapter, import_from_txt, import_from_markdown, import_from_docx};
+pub use export::{ExportFormat, export_as_docx, export_as_pdf, export_as_epub, export_as_txt, export_as_md, export_as_md_folder};
+pub use import::{ImportFormat, ImportResult, ImportedChapter, import_from_txt, import_from_markdown, import_from_docx, import_from_epub, import_from_pdf, import_from_html};
 pub use plugin_system::*;
 pub use plugin_commands::*;
 pub use cloud_sync::{SyncConfig, SyncStatus, SyncResult, ConflictResolutionStrategy, ProviderType};
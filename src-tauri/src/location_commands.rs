@@ -0,0 +1,136 @@
+use crate::locations::{self, CreateLocationRequest, UpdateLocationRequest};
+use crate::logger::Logger;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+#[tauri::command]
+pub async fn create_location(app: AppHandle, request: CreateLocationRequest) -> Result<String, String> {
+    let logger = Logger::new().with_feature("locations");
+    logger.info(&format!("Creating location {} in project {}", request.name, request.project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let location = locations::create_location(&conn, &request)?;
+
+    serde_json::to_string(&location).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_locations(app: AppHandle, project_id: String) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let list = locations::list_locations(&conn, &project_id)?;
+
+    serde_json::to_string(&list).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_location(app: AppHandle, request: UpdateLocationRequest) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let location = locations::update_location(&conn, &request)?;
+
+    serde_json::to_string(&location).map_err(|e| e.to_string())
+}
+
+/// 删除地点，下级地点改为没有上级，路程和章节标注随之删除
+#[tauri::command]
+pub async fn delete_location(app: AppHandle, id: String) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    locations::delete_location(&conn, &id)
+}
+
+/// 记录两地之间的路程，已有记录时覆盖
+#[tauri::command]
+pub async fn set_location_route(
+    app: AppHandle,
+    from_location_id: String,
+    to_location_id: String,
+    travel_time: String,
+    note: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let route = locations::set_route(&conn, &from_location_id, &to_location_id, &travel_time, note.as_deref())?;
+
+    serde_json::to_string(&route).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_location_route(app: AppHandle, id: String) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    locations::remove_route(&conn, &id)
+}
+
+#[tauri::command]
+pub async fn get_location_routes(app: AppHandle, project_id: String) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let routes = locations::list_routes(&conn, &project_id)?;
+
+    serde_json::to_string(&routes).map_err(|e| e.to_string())
+}
+
+/// 标注章节发生的地点，第一个为主要地点
+#[tauri::command]
+pub async fn set_chapter_locations(app: AppHandle, chapter_id: String, location_ids: Vec<String>) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let list = locations::set_chapter_locations(&conn, &chapter_id, &location_ids)?;
+
+    serde_json::to_string(&list).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_chapter_locations(app: AppHandle, chapter_id: String) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let list = locations::chapter_locations(&conn, &chapter_id)?;
+
+    serde_json::to_string(&list).map_err(|e| e.to_string())
+}
+
+/// 在该地点发生的章节
+#[tauri::command]
+pub async fn get_location_chapters(app: AppHandle, location_id: String) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let chapters = locations::location_chapters(&conn, &location_id)?;
+
+    serde_json::to_string(&chapters).map_err(|e| e.to_string())
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
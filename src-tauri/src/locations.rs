@@ -0,0 +1,427 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// 地点层级，从大到小；other 不参与层级校验
+pub const LEVELS: &[&str] = &["world", "region", "city", "building"];
+const LEVEL_LABELS: &[(&str, &str)] = &[("world", "世界"), ("region", "地区"), ("city", "城市"), ("building", "建筑"), ("other", "地点")];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Location {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    /// world、region、city、building 或 other
+    pub level: String,
+    pub parent_id: Option<String>,
+    pub description: Option<String>,
+    /// 从最上级到自身的名字
+    pub path: Vec<String>,
+    pub chapter_count: usize,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateLocationRequest {
+    pub project_id: String,
+    pub name: String,
+    pub level: Option<String>,
+    pub parent_id: Option<String>,
+    pub description: Option<String>,
+}
+
+/// 为 None 的字段保持不变；`parent_id` 传空字符串时清除
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateLocationRequest {
+    pub id: String,
+    pub name: Option<String>,
+    pub level: Option<String>,
+    pub parent_id: Option<String>,
+    pub description: Option<String>,
+}
+
+/// 两地之间的路程，双向
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocationRoute {
+    pub id: String,
+    pub project_id: String,
+    pub from_location_id: String,
+    pub to_location_id: String,
+    /// 如“骑马三日”“一炷香”
+    pub travel_time: String,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocationChapter {
+    pub chapter_id: String,
+    pub title: String,
+}
+
+struct Row {
+    id: String,
+    project_id: String,
+    name: String,
+    level: String,
+    parent_id: Option<String>,
+    description: Option<String>,
+    chapter_count: usize,
+    created_at: String,
+    updated_at: String,
+}
+
+fn level_rank(level: &str) -> Option<usize> {
+    LEVELS.iter().position(|l| *l == level)
+}
+
+fn level_label(level: &str) -> &'static str {
+    LEVEL_LABELS.iter().find(|(l, _)| *l == level).map(|(_, label)| *label).unwrap_or("地点")
+}
+
+fn load_rows(conn: &Connection, project_id: &str) -> Result<Vec<Row>, String> {
+    conn.prepare(
+        "SELECT l.id, l.project_id, l.name, l.level, l.parent_id, l.description,
+                (SELECT COUNT(*) FROM chapter_locations cl WHERE cl.location_id = l.id), l.created_at, l.updated_at
+         FROM locations l WHERE l.project_id = ? ORDER BY l.created_at, l.rowid",
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![project_id], |row| {
+        Ok(Row {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            name: row.get(2)?,
+            level: row.get(3)?,
+            parent_id: row.get(4)?,
+            description: row.get(5)?,
+            chapter_count: row.get::<_, i64>(6)? as usize,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+        })
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+/// 项目的全部地点，按层级从大到小
+pub fn list_locations(conn: &Connection, project_id: &str) -> Result<Vec<Location>, String> {
+    let rows = load_rows(conn, project_id)?;
+    let by_id: HashMap<&str, &Row> = rows.iter().map(|r| (r.id.as_str(), r)).collect();
+    let mut locations: Vec<Location> = rows
+        .iter()
+        .map(|row| {
+            let mut path = vec![row.name.clone()];
+            let mut current = row.parent_id.as_deref();
+            while let Some(parent) = current.and_then(|id| by_id.get(id)) {
+                if path.len() > rows.len() {
+                    break;
+                }
+                path.insert(0, parent.name.clone());
+                current = parent.parent_id.as_deref();
+            }
+            Location {
+                id: row.id.clone(),
+                project_id: row.project_id.clone(),
+                name: row.name.clone(),
+                level: row.level.clone(),
+                parent_id: row.parent_id.clone(),
+                description: row.description.clone(),
+                path,
+                chapter_count: row.chapter_count,
+                created_at: row.created_at.clone(),
+                updated_at: row.updated_at.clone(),
+            }
+        })
+        .collect();
+    locations.sort_by_key(|l| l.path.len());
+    Ok(locations)
+}
+
+pub fn get_location(conn: &Connection, id: &str) -> Result<Location, String> {
+    let project_id: String = conn
+        .query_row("SELECT project_id FROM locations WHERE id = ?", params![id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "地点不存在".to_string())?;
+    list_locations(conn, &project_id)?.into_iter().find(|l| l.id == id).ok_or_else(|| "地点不存在".to_string())
+}
+
+/// 校验上级地点属于同一项目、不是自己或自己的下级，且层级比自己大（如城市不能隶属于建筑）
+fn check_parent(conn: &Connection, project_id: &str, id: Option<&str>, level: &str, parent_id: Option<&str>) -> Result<(), String> {
+    let Some(parent_id) = parent_id else { return Ok(()) };
+    let parent = get_location(conn, parent_id).map_err(|_| "上级地点不存在".to_string())?;
+    if parent.project_id != project_id {
+        return Err("上级地点不属于同一项目".to_string());
+    }
+    let rows = load_rows(conn, project_id)?;
+    let mut current = Some(parent_id.to_string());
+    while let Some(ancestor) = current {
+        if Some(ancestor.as_str()) == id {
+            return Err("上级地点不能是自己或自己的下级".to_string());
+        }
+        current = rows.iter().find(|r| r.id == ancestor).and_then(|r| r.parent_id.clone());
+    }
+    if let (Some(rank), Some(parent_rank)) = (level_rank(level), level_rank(&parent.level)) {
+        if rank <= parent_rank {
+            return Err(format!("{}不能隶属于{}", level_label(level), level_label(&parent.level)));
+        }
+    }
+    Ok(())
+}
+
+fn normalize_level(level: Option<&str>) -> String {
+    level.filter(|l| LEVELS.contains(l)).unwrap_or("other").to_string()
+}
+
+pub fn create_location(conn: &Connection, request: &CreateLocationRequest) -> Result<Location, String> {
+    let name = request.name.trim();
+    if name.is_empty() {
+        return Err("地点名称不能为空".to_string());
+    }
+    let level = normalize_level(request.level.as_deref());
+    check_parent(conn, &request.project_id, None, &level, request.parent_id.as_deref())?;
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO locations (id, project_id, name, level, parent_id, description, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+        params![id, request.project_id, name, level, request.parent_id, request.description, now],
+    )
+    .map_err(|e| format!("Failed to create location: {}", e))?;
+    crate::tokenizer::add_words(&[name]);
+    get_location(conn, &id)
+}
+
+pub fn update_location(conn: &Connection, request: &UpdateLocationRequest) -> Result<Location, String> {
+    let current = get_location(conn, &request.id)?;
+    let level = match &request.level {
+        Some(level) => normalize_level(Some(level)),
+        None => current.level.clone(),
+    };
+    let parent_id = match &request.parent_id {
+        Some(p) if p.is_empty() => None,
+        Some(p) => Some(p.clone()),
+        None => current.parent_id.clone(),
+    };
+    check_parent(conn, &current.project_id, Some(&current.id), &level, parent_id.as_deref())?;
+    let name = request.name.as_deref().map(str::trim).filter(|n| !n.is_empty()).unwrap_or(&current.name).to_string();
+    conn.execute(
+        "UPDATE locations SET name = ?, level = ?, parent_id = ?, description = ?, updated_at = ? WHERE id = ?",
+        params![name, level, parent_id, request.description.clone().or(current.description), Utc::now().to_rfc3339(), request.id],
+    )
+    .map_err(|e| format!("Failed to update location: {}", e))?;
+    crate::tokenizer::add_words(&[name]);
+    get_location(conn, &request.id)
+}
+
+/// 删除地点，下级地点改为没有上级，路程和章节标注随之删除
+pub fn delete_location(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM locations WHERE id = ?", params![id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 记录两地之间的路程，已有记录时覆盖
+pub fn set_route(conn: &Connection, from_id: &str, to_id: &str, travel_time: &str, note: Option<&str>) -> Result<LocationRoute, String> {
+    if from_id == to_id {
+        return Err("起点和终点不能相同".to_string());
+    }
+    let (from, to) = (get_location(conn, from_id)?, get_location(conn, to_id)?);
+    if from.project_id != to.project_id {
+        return Err("两个地点不属于同一项目".to_string());
+    }
+    let (a, b) = if from_id < to_id { (from_id, to_id) } else { (to_id, from_id) };
+    conn.execute(
+        "INSERT INTO location_routes (id, project_id, from_location_id, to_location_id, travel_time, note, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(from_location_id, to_location_id) DO UPDATE SET travel_time = excluded.travel_time, note = excluded.note",
+        params![Uuid::new_v4().to_string(), from.project_id, a, b, travel_time.trim(), note, Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| format!("Failed to save route: {}", e))?;
+    conn.query_row(
+        "SELECT id, project_id, from_location_id, to_location_id, travel_time, note FROM location_routes
+         WHERE from_location_id = ? AND to_location_id = ?",
+        params![a, b],
+        route_from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn route_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<LocationRoute> {
+    Ok(LocationRoute {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        from_location_id: row.get(2)?,
+        to_location_id: row.get(3)?,
+        travel_time: row.get(4)?,
+        note: row.get(5)?,
+    })
+}
+
+pub fn remove_route(conn: &Connection, route_id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM location_routes WHERE id = ?", params![route_id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn list_routes(conn: &Connection, project_id: &str) -> Result<Vec<LocationRoute>, String> {
+    conn.prepare(
+        "SELECT id, project_id, from_location_id, to_location_id, travel_time, note FROM location_routes
+         WHERE project_id = ? ORDER BY created_at, rowid",
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![project_id], route_from_row)
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+/// 标注章节发生的地点，整体替换原有标注，按传入顺序保存（第一个为主要地点）
+pub fn set_chapter_locations(conn: &Connection, chapter_id: &str, location_ids: &[String]) -> Result<Vec<Location>, String> {
+    let project_id: String = conn
+        .query_row("SELECT project_id FROM chapters WHERE id = ?", params![chapter_id], |row| row.get(0))
+        .map_err(|_| "章节不存在".to_string())?;
+    let locations = list_locations(conn, &project_id)?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM chapter_locations WHERE chapter_id = ?", params![chapter_id]).map_err(|e| e.to_string())?;
+    for (position, location_id) in location_ids.iter().enumerate() {
+        if !locations.iter().any(|l| &l.id == location_id) {
+            return Err(format!("地点不存在或不属于同一项目: {}", location_id));
+        }
+        tx.execute(
+            "INSERT OR IGNORE INTO chapter_locations (chapter_id, location_id, position) VALUES (?, ?, ?)",
+            params![chapter_id, location_id, position as i64],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    chapter_locations(conn, chapter_id)
+}
+
+/// 章节标注的地点，主要地点在前
+pub fn chapter_locations(conn: &Connection, chapter_id: &str) -> Result<Vec<Location>, String> {
+    let project_id: Option<String> = conn
+        .query_row("SELECT project_id FROM chapters WHERE id = ?", params![chapter_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(project_id) = project_id else { return Ok(Vec::new()) };
+    let ids: Vec<String> = conn
+        .prepare("SELECT location_id FROM chapter_locations WHERE chapter_id = ? ORDER BY position")
+        .map_err(|e| e.to_string())?
+        .query_map(params![chapter_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut locations = list_locations(conn, &project_id)?;
+    locations.retain(|l| ids.contains(&l.id));
+    locations.sort_by_key(|l| ids.iter().position(|id| *id == l.id));
+    Ok(locations)
+}
+
+/// 发生在该地点的章节，按章节顺序
+pub fn location_chapters(conn: &Connection, location_id: &str) -> Result<Vec<LocationChapter>, String> {
+    conn.prepare(
+        "SELECT c.id, c.title FROM chapter_locations cl JOIN chapters c ON c.id = cl.chapter_id
+         WHERE cl.location_id = ? ORDER BY c.sort_order, c.created_at",
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![location_id], |row| Ok(LocationChapter { chapter_id: row.get(0)?, title: row.get(1)? }))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+/// 续写提示词中的本章地点：所属层级、描述，以及从这些地点出发的路程
+pub fn prompt_section(conn: &Connection, chapter_id: &str) -> Result<Option<String>, String> {
+    let locations = chapter_locations(conn, chapter_id)?;
+    let Some(first) = locations.first() else { return Ok(None) };
+    let all = list_locations(conn, &first.project_id)?;
+    let name = |id: &str| all.iter().find(|l| l.id == id).map(|l| l.name.clone()).unwrap_or_default();
+
+    let mut lines = vec!["【本章地点】".to_string()];
+    for location in &locations {
+        let mut line = format!("- {}（{}", location.name, level_label(&location.level));
+        if location.path.len() > 1 {
+            line.push_str(&format!("，位于{}", location.path[..location.path.len() - 1].join(" › ")));
+        }
+        line.push('）');
+        if let Some(description) = location.description.as_deref().filter(|d| !d.trim().is_empty()) {
+            line.push_str(&format!("：{}", description.trim()));
+        }
+        lines.push(line);
+    }
+    let routes: Vec<String> = list_routes(conn, &first.project_id)?
+        .into_iter()
+        .filter(|r| locations.iter().any(|l| l.id == r.from_location_id || l.id == r.to_location_id))
+        .map(|r| {
+            // 本章地点写在前面
+            let (here, there) = if locations.iter().any(|l| l.id == r.from_location_id) {
+                (&r.from_location_id, &r.to_location_id)
+            } else {
+                (&r.to_location_id, &r.from_location_id)
+            };
+            let mut line = format!("- {} ↔ {}：{}", name(here), name(there), r.travel_time);
+            if let Some(note) = r.note.filter(|n| !n.trim().is_empty()) {
+                line.push_str(&format!("（{}）", note.trim()));
+            }
+            line
+        })
+        .collect();
+    if !routes.is_empty() {
+        lines.push("路程：".to_string());
+        lines.extend(routes);
+    }
+    Ok(Some(lines.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_location_hierarchy_routes_and_prompt() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '', 1, 't0', 't0'), ('c2', 'p1', '第二章', '', 2, 't0', 't0');",
+        )
+        .unwrap();
+        let create = |name: &str, level: &str, parent_id: Option<&str>| {
+            create_location(
+                &conn,
+                &CreateLocationRequest {
+                    project_id: "p1".to_string(),
+                    name: name.to_string(),
+                    level: Some(level.to_string()),
+                    parent_id: parent_id.map(str::to_string),
+                    description: None,
+                },
+            )
+        };
+        let world = create("大夏", "world", None).unwrap();
+        let north = create("北境", "region", Some(&world.id)).unwrap();
+        let town = create("青石镇", "city", Some(&north.id)).unwrap();
+        let inn = create("悦来客栈", "building", Some(&town.id)).unwrap();
+        let mountain = create("落霞山", "region", Some(&world.id)).unwrap();
+        assert!(create("小镇", "city", Some(&inn.id)).is_err());
+        let cycle = UpdateLocationRequest { id: north.id.clone(), name: None, level: None, parent_id: Some(town.id.clone()), description: None };
+        assert!(update_location(&conn, &cycle).is_err());
+        assert_eq!(get_location(&conn, &inn.id).unwrap().path, vec!["大夏", "北境", "青石镇", "悦来客栈"]);
+
+        set_route(&conn, &mountain.id, &town.id, "步行两日", None).unwrap();
+        set_route(&conn, &town.id, &mountain.id, "骑马一日", Some("山路难行")).unwrap();
+        assert_eq!(list_routes(&conn, "p1").unwrap().len(), 1);
+
+        set_chapter_locations(&conn, "c1", &[inn.id.clone(), town.id.clone()]).unwrap();
+        assert!(set_chapter_locations(&conn, "c2", &["missing".to_string()]).is_err());
+        assert_eq!(location_chapters(&conn, &town.id).unwrap()[0].chapter_id, "c1");
+        assert_eq!(
+            prompt_section(&conn, "c1").unwrap().unwrap(),
+            "【本章地点】\n- 悦来客栈（建筑，位于大夏 › 北境 › 青石镇）\n- 青石镇（城市，位于大夏 › 北境）\n路程：\n- 青石镇 ↔ 落霞山：骑马一日（山路难行）"
+        );
+        assert_eq!(prompt_section(&conn, "c2").unwrap(), None);
+
+        delete_location(&conn, &north.id).unwrap();
+        assert_eq!(get_location(&conn, &town.id).unwrap().parent_id, None);
+    }
+}
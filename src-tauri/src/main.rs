@@ -3,6 +3,7 @@
 
 mod database;
 mod models;
+mod repository;
 mod commands;
 mod logger;
 mod ai;
@@ -15,14 +16,92 @@ mod multimedia_generation;
 mod multimedia_generation_commands;
 mod collaboration;
 mod collaboration_commands;
+mod collaboration_transport;
+mod collaboration_activity;
+mod collaboration_auth;
+mod collaboration_replay;
+mod crdt;
+mod comments;
+mod comments_commands;
 mod text_analysis;
+mod text_merge;
+mod text_diff;
 mod text_analysis_commands;
 mod writing_tools;
 mod writing_tools_commands;
+mod sensitive_dictionaries;
+mod autocorrect;
+mod tokenizer;
+mod pacing;
+mod entity_index;
+mod vocabulary;
+mod similarity;
+mod project_analysis;
+mod pov_check;
+mod writing_stats;
+mod writing_stats_commands;
+mod writing_sprints;
+mod find_replace;
+mod find_replace_commands;
+mod name_generator;
+mod name_generator_commands;
+mod naming_cultures;
+mod cast_generator;
+mod cast_generator_commands;
+mod relation_inference;
+mod relation_inference_commands;
+mod relation_states;
+mod character_aliases;
+mod status_check;
+mod family_tree;
+mod family_tree_commands;
+mod organizations;
+mod organization_commands;
+mod locations;
+mod location_commands;
+mod calendar;
+mod calendar_commands;
+mod items;
+mod item_commands;
+mod power_system;
+mod power_system_commands;
+mod economy;
+mod economy_commands;
+mod knowledge_search;
+mod knowledge_extraction;
+mod knowledge_extraction_commands;
+mod knowledge_facts;
+mod knowledge_facts_commands;
+mod knowledge_graph;
+mod knowledge_graph_commands;
+mod knowledge_links;
+mod knowledge_links_commands;
+mod knowledge_history;
+mod knowledge_history_commands;
+mod worldview_history;
+mod worldview_conflicts;
+mod worldview_conflicts_commands;
+mod glossary;
+mod glossary_commands;
+mod numeral_style;
 mod version_control;
 mod version_control_commands;
+mod snapshot_store;
+mod snapshot_tags;
+mod provenance;
+mod history_bundle;
+mod chapter_branches;
+mod auto_snapshot;
+mod chapter_post_save;
+mod version_storage;
 mod character_growth;
 mod character_tags;
+mod character_merge;
+mod character_presence;
+mod voice_profile;
+mod character_sheet;
+mod character_portraits;
+mod timeline_check;
 mod character_growth_commands;
 mod character_dialogue;
 mod character_dialogue_commands;
@@ -30,6 +109,9 @@ mod import;
 mod prompt_template_commands;
 mod outline;
 mod reverse_analysis;
+mod tts;
+#[cfg(test)]
+mod test_support;
 
 use tauri::Manager;
 use logger::Logger;
@@ -39,6 +121,7 @@ use plugin_marketplace_commands::MarketplaceState;
 use cloud_sync_commands::CloudSyncState;
 use multimedia_generation_commands::MultimediaState;
 use collaboration_commands::CollaborationState;
+use import::ImportState;
 use rusqlite::params;
 use uuid::Uuid;
 
@@ -74,6 +157,15 @@ fn main() {
             database::init_database(&db_path).expect("Failed to initialize database");
             app_logger.info("Database initialized successfully");
 
+            // 上次退出时仍在运行的后台任务已经中断，标记为失败后可以继续（如网页导入）
+            if let Ok(conn) = database::get_connection(&db_path) {
+                match ai::task_queue::interrupt_running_tasks(&conn) {
+                    Ok(0) => {}
+                    Ok(count) => app_logger.info(&format!("Marked {} interrupted background tasks as failed", count)),
+                    Err(e) => app_logger.warn(&format!("Failed to reset interrupted background tasks: {}", e)),
+                }
+            }
+
             // 从数据库加载已保存的 API 密钥
             if let Some(saved_key) = load_api_key_from_db(&db_path, "bigmodel") {
                 app_logger.info("Found saved BigModel API key, setting environment variable");
@@ -86,6 +178,7 @@ fn main() {
             tauri::async_runtime::spawn(async move {
                 let service = ai_service_clone.read().await;
                 service.get_registry().initialize_default_bigmodel_models().await;
+                service.initialize_default_embedding_models().await;
             });
 
             app.manage(ai_service);
@@ -111,10 +204,28 @@ fn main() {
             app.manage(multimedia_state);
             app_logger.info("Multimedia generation initialized");
 
-            let collab_state = CollaborationState::new();
+            app.manage(ImportState::new());
+
+            let collab_state = CollaborationState::with_db_path(db_path.clone());
             app.manage(collab_state);
             app_logger.info("Collaboration initialized");
 
+            // 分词器加载默认词典较慢，放到后台
+            let tokenizer_db_path = db_path.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                let result = database::get_connection(&tokenizer_db_path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|conn| tokenizer::load_user_dictionary(&conn));
+                if let Err(e) = result {
+                    Logger::new().with_feature("main").warn(&format!("Failed to load user dictionary: {}", e));
+                }
+            });
+
+            app.manage(chapter_post_save::ChapterPostSaveState::new(db_path.clone()));
+            app.manage(auto_snapshot::AutoSnapshotState::new(db_path.clone()));
+            auto_snapshot::spawn_retention_task(db_path.clone());
+            app_logger.info("Auto snapshots initialized");
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -183,9 +294,29 @@ fn main() {
             commands::update_knowledge_entry,
             commands::delete_knowledge_entry,
             commands::search_knowledge,
+            commands::find_related_knowledge,
+            knowledge_extraction_commands::extract_chapter_knowledge,
+            knowledge_extraction_commands::get_knowledge_sources,
+            knowledge_extraction_commands::verify_knowledge_entries,
+            knowledge_facts_commands::check_knowledge_contradictions,
+            knowledge_facts_commands::get_knowledge_conflicts,
+            knowledge_facts_commands::get_fact_ledger,
+            knowledge_facts_commands::set_canonical_fact,
+            knowledge_facts_commands::dismiss_knowledge_conflict,
             commands::create_knowledge_relation,
             commands::get_knowledge_relations,
             commands::delete_knowledge_relation,
+            knowledge_graph_commands::get_knowledge_graph,
+            knowledge_links_commands::get_knowledge_near_cursor,
+            knowledge_links_commands::get_chapter_knowledge_mentions,
+            knowledge_links_commands::relink_knowledge_mentions,
+            knowledge_history_commands::get_knowledge_revisions,
+            knowledge_history_commands::get_deleted_knowledge_entries,
+            knowledge_history_commands::get_knowledge_revision_content,
+            knowledge_history_commands::diff_knowledge_revision,
+            knowledge_history_commands::restore_knowledge_revision,
+            knowledge_history_commands::get_knowledge_generation_run,
+            knowledge_history_commands::get_knowledge_entry_generation_runs,
             commands::build_knowledge_context,
             commands::sync_character_to_knowledge,
             commands::sync_worldview_to_knowledge,
@@ -206,9 +337,25 @@ fn main() {
             commands::export_project,
             commands::export_chapter,
             commands::get_export_formats,
+            commands::validate_export,
+            commands::export_character_graph,
+            commands::export_worldview_wiki,
+            commands::export_comic,
             // 导入命令
             commands::import_file,
+            commands::cancel_import,
+            commands::preview_import,
+            commands::detect_duplicate_chapters,
+            commands::preview_table_import,
+            commands::import_table,
             commands::import_to_project,
+            commands::import_directory,
+            commands::get_chapter_split_rules,
+            commands::save_chapter_split_rules,
+            commands::preview_chapter_split_rules,
+            commands::start_url_import,
+            commands::resume_url_import,
+            commands::get_url_import_items,
             // 提示词模板命令
             prompt_template_commands::get_custom_prompt_templates,
             prompt_template_commands::get_prompt_template_by_id,
@@ -222,8 +369,19 @@ fn main() {
             outline::commands::create_outline_node,
             outline::commands::update_outline_node,
             outline::commands::delete_outline_node,
+            outline::commands::move_outline_node,
+            outline::commands::get_outline_progress,
+            outline::commands::promote_plot_points_to_outline,
+            outline::commands::push_outline_to_plot_points,
+            outline::commands::link_outline_node_to_plot_point,
+            outline::commands::unlink_outline_node_from_plot_point,
             outline::commands::get_outline_templates,
             outline::commands::apply_outline_template,
+            outline::commands::scaffold_chapters_from_outline,
+            outline::commands::check_outline_drift,
+            outline::commands::expand_outline_node,
+            outline::commands::export_outline,
+            outline::commands::import_outline,
             outline::commands::generate_outline_with_ai,
             outline::commands::save_generated_outline,
             // 插件系统命令
@@ -259,16 +417,42 @@ fn main() {
             cloud_sync_commands::cloud_sync_start_auto,
             cloud_sync_commands::cloud_sync_stop_auto,
             cloud_sync_commands::cloud_sync_resolve_conflict,
+            cloud_sync_commands::cloud_sync_get_conflicts,
+            cloud_sync_commands::cloud_sync_get_history,
+            cloud_sync_commands::lan_sync_start_host,
+            cloud_sync_commands::lan_sync_stop_host,
+            cloud_sync_commands::lan_sync_discover,
+            cloud_sync_commands::lan_sync_pair,
+            cloud_sync_commands::lan_sync_list_paired,
+            cloud_sync_commands::lan_sync_unpair,
+            cloud_sync_commands::lan_sync_start,
             // 协作编辑命令
             collaboration_commands::collab_create_session,
             collaboration_commands::collab_join_session,
+            collaboration_commands::collab_create_invite,
+            collaboration_commands::collab_revoke_invite,
+            collaboration_commands::collab_list_invites,
             collaboration_commands::collab_leave_session,
             collaboration_commands::collab_broadcast_operation,
+            collaboration_commands::collab_get_chapter_text,
             collaboration_commands::collab_update_cursor,
             collaboration_commands::collab_get_session,
             collaboration_commands::collab_get_user_cursors,
             collaboration_commands::collab_generate_user_id,
             collaboration_commands::collab_generate_color,
+            collaboration_commands::collab_start_server,
+            collaboration_commands::collab_stop_server,
+            collaboration_commands::collab_connect,
+            collaboration_commands::collab_disconnect,
+            collaboration_commands::collab_get_activity,
+            collaboration_commands::collab_replay_chapter,
+            collaboration_commands::collab_get_chapter_timeline,
+            comments_commands::comment_create,
+            comments_commands::comment_reply,
+            comments_commands::comment_resolve,
+            comments_commands::comment_delete,
+            comments_commands::comment_list,
+            comments_commands::comment_export_open,
             // 文本分析命令
             text_analysis_commands::analyze_writing_style,
             text_analysis_commands::analyze_rhythm,
@@ -277,12 +461,134 @@ fn main() {
             text_analysis_commands::detect_repetitions,
             text_analysis_commands::check_logic,
             text_analysis_commands::run_full_analysis,
+            text_analysis_commands::analyze_book_pacing,
+            text_analysis_commands::build_entity_index,
+            text_analysis_commands::get_entity_index,
+            text_analysis_commands::get_entity_mentions,
             // 写作工具命令
             writing_tools_commands::detect_sensitive_words,
             writing_tools_commands::detect_typos,
             writing_tools_commands::check_grammar,
+            writing_tools_commands::apply_line_edits,
             writing_tools_commands::normalize_format,
+            writing_tools_commands::fix_typography,
+            writing_tools_commands::check_numerals,
+            writing_tools_commands::get_typography_settings,
+            writing_tools_commands::set_typography_settings,
             writing_tools_commands::run_full_writing_tools,
+            writing_tools_commands::detect_cliches,
+            writing_tools_commands::get_cliche_settings,
+            writing_tools_commands::set_custom_cliches,
+            writing_tools_commands::set_cliche_suppressed,
+            writing_tools_commands::list_sensitive_dictionaries,
+            writing_tools_commands::create_sensitive_dictionary,
+            writing_tools_commands::delete_sensitive_dictionary,
+            writing_tools_commands::get_sensitive_dictionary_words,
+            writing_tools_commands::set_sensitive_word,
+            writing_tools_commands::import_sensitive_dictionary,
+            writing_tools_commands::set_sensitive_dictionary_enabled,
+            writing_tools_commands::get_sensitive_whitelist,
+            writing_tools_commands::set_sensitive_whitelist,
+            writing_tools_commands::list_autocorrect_rules,
+            writing_tools_commands::save_autocorrect_rule,
+            writing_tools_commands::delete_autocorrect_rule,
+            writing_tools_commands::apply_autocorrect,
+            writing_tools_commands::run_autocorrect,
+            text_analysis_commands::add_dictionary_words,
+            text_analysis_commands::remove_dictionary_word,
+            text_analysis_commands::list_dictionary_words,
+            text_analysis_commands::analyze_vocabulary,
+            text_analysis_commands::get_word_stats,
+            text_analysis_commands::set_vocabulary_list,
+            text_analysis_commands::get_vocabulary_lists,
+            text_analysis_commands::detect_similar_passages,
+            text_analysis_commands::run_project_analysis,
+            text_analysis_commands::get_project_analysis_results,
+            text_analysis_commands::analyze_pov,
+            find_replace_commands::preview_find_replace,
+            find_replace_commands::apply_find_replace,
+            writing_stats_commands::get_writing_dashboard,
+            writing_stats_commands::get_writing_goals,
+            writing_stats_commands::set_writing_goals,
+            writing_stats_commands::get_writing_history,
+            writing_stats_commands::start_writing_sprint,
+            writing_stats_commands::get_writing_sprint_progress,
+            writing_stats_commands::stop_writing_sprint,
+            writing_stats_commands::get_writing_sprint_history,
+            writing_stats_commands::get_writing_sprint_bests,
+            name_generator_commands::generate_names,
+            name_generator_commands::save_naming_culture,
+            name_generator_commands::get_naming_cultures,
+            name_generator_commands::delete_naming_culture,
+            name_generator_commands::assign_naming_culture,
+            name_generator_commands::generate_culture_names,
+            name_generator_commands::check_naming_conventions,
+            cast_generator_commands::ai_generate_cast,
+            cast_generator_commands::apply_cast,
+            cast_generator_commands::undo_cast,
+            relation_inference_commands::infer_relations,
+            relation_inference_commands::accept_relation_proposals,
+            family_tree_commands::add_kinship,
+            family_tree_commands::remove_kinship,
+            family_tree_commands::get_family_tree,
+            family_tree_commands::check_kinship_consistency,
+            family_tree_commands::ai_generate_family_backstory,
+            family_tree_commands::apply_family_backstory,
+            organization_commands::create_organization,
+            organization_commands::get_organizations,
+            organization_commands::update_organization,
+            organization_commands::delete_organization,
+            organization_commands::add_organization_member,
+            organization_commands::update_organization_member,
+            organization_commands::remove_organization_member,
+            organization_commands::get_organization_members,
+            organization_commands::get_character_organizations,
+            location_commands::create_location,
+            location_commands::get_locations,
+            location_commands::update_location,
+            location_commands::delete_location,
+            location_commands::set_location_route,
+            location_commands::remove_location_route,
+            location_commands::get_location_routes,
+            location_commands::set_chapter_locations,
+            location_commands::get_chapter_locations,
+            location_commands::get_location_chapters,
+            calendar_commands::get_story_calendar,
+            calendar_commands::save_story_calendar,
+            calendar_commands::parse_story_date,
+            calendar_commands::format_story_date,
+            calendar_commands::story_date_interval,
+            item_commands::create_item,
+            item_commands::get_items,
+            item_commands::update_item,
+            item_commands::delete_item,
+            item_commands::record_item_event,
+            item_commands::delete_item_event,
+            item_commands::get_item_history,
+            item_commands::check_item_consistency,
+            power_system_commands::save_power_system,
+            power_system_commands::get_power_systems,
+            power_system_commands::delete_power_system,
+            power_system_commands::record_power_progress,
+            power_system_commands::delete_power_progress,
+            power_system_commands::get_character_power_progress,
+            power_system_commands::check_power_consistency,
+            economy_commands::save_currency,
+            economy_commands::get_currencies,
+            economy_commands::delete_currency,
+            economy_commands::save_price_reference,
+            economy_commands::get_price_references,
+            economy_commands::delete_price_reference,
+            economy_commands::record_character_wealth,
+            economy_commands::delete_character_wealth,
+            economy_commands::get_character_wealth,
+            economy_commands::check_spending_consistency,
+            worldview_conflicts_commands::check_worldview_contradictions,
+            glossary_commands::list_glossary_terms,
+            glossary_commands::save_glossary_term,
+            glossary_commands::delete_glossary_term,
+            glossary_commands::check_glossary,
+            glossary_commands::normalize_glossary_terms,
             // 版本控制命令
             version_control_commands::create_snapshot,
             version_control_commands::get_snapshots,
@@ -290,8 +596,29 @@ fn main() {
             version_control_commands::restore_snapshot,
             version_control_commands::delete_snapshot,
             version_control_commands::compare_snapshots,
+            version_control_commands::restore_snapshot_paragraphs,
+            version_control_commands::create_restore_point,
+            version_control_commands::get_chapter_blame,
+            version_control_commands::export_chapter_history,
+            version_control_commands::import_chapter_history,
+            version_control_commands::get_worldview_revisions,
+            version_control_commands::get_deleted_world_views,
+            version_control_commands::diff_worldview_revision,
+            version_control_commands::restore_worldview_revision,
+            version_control_commands::get_snapshot_storage_stats,
+            version_control_commands::get_version_storage_stats,
+            version_control_commands::tag_snapshot,
+            version_control_commands::untag_snapshot,
+            version_control_commands::list_snapshot_tags,
             version_control_commands::get_version_config,
             version_control_commands::set_version_config,
+            version_control_commands::create_chapter_branch,
+            version_control_commands::list_chapter_branches,
+            version_control_commands::switch_chapter_branch,
+            version_control_commands::create_branch_snapshot,
+            version_control_commands::list_branch_snapshots,
+            version_control_commands::merge_chapter_branch,
+            version_control_commands::delete_chapter_branch,
             // 角色成长和标签命令
             character_growth_commands::create_growth_record,
             character_growth_commands::get_growth_timeline,
@@ -302,6 +629,27 @@ fn main() {
             character_growth_commands::search_tags,
             character_growth_commands::get_tag_library,
             character_growth_commands::get_tag_statistics,
+            character_growth_commands::set_character_aliases,
+            character_growth_commands::get_character_aliases,
+            character_growth_commands::save_character_aliases,
+            character_growth_commands::find_unregistered_aliases,
+            character_growth_commands::get_character_presence,
+            character_growth_commands::merge_characters,
+            character_growth_commands::record_relation_state,
+            character_growth_commands::delete_relation_state,
+            character_growth_commands::get_character_graph_as_of,
+            character_growth_commands::get_relation_evolution,
+            character_growth_commands::get_voice_profile,
+            character_growth_commands::save_voice_profile,
+            character_growth_commands::check_character_voices,
+            character_growth_commands::export_character_sheet,
+            character_growth_commands::import_character_sheet,
+            character_growth_commands::get_portrait_gallery,
+            character_growth_commands::save_character_portraits,
+            character_growth_commands::set_portrait_as_avatar,
+            character_growth_commands::delete_character_portrait,
+            character_growth_commands::check_timeline_consistency,
+            character_growth_commands::check_character_status_consistency,
             // 角色对话命令
             character_dialogue_commands::create_dialogue_session,
             character_dialogue_commands::get_dialogue_sessions,
@@ -323,6 +671,10 @@ fn main() {
             // 逆向分析命令
             reverse_analysis::commands::reverse_analyze_novel,
             reverse_analysis::commands::reverse_analyze_and_import,
+            // 有声书导出命令
+            tts::commands::get_tts_voices,
+            tts::commands::preview_tts_voice,
+            tts::commands::start_audiobook_export,
             // AI 影视生成命令 (moyin-creator 集成)
             ai::prompt_compiler::compile_image_prompt,
             ai::prompt_compiler::compile_video_prompt,
@@ -410,6 +762,7 @@ fn main() {
             commands::get_foreshadowing_stats,
             // 情感曲线命令
             commands::calculate_emotion_curve,
+            commands::compare_emotion_curve,
             // 优化器命令
             commands::optimize_chapter,
             // 蓝图命令（L1规划层）
@@ -158,6 +158,9 @@ pub struct CharacterTimelineEvent {
     pub event_title: String,
     pub event_description: String,
     pub story_time: Option<String>,
+    /// 按项目历法的结构化日期
+    #[serde(default)]
+    pub story_date: Option<crate::calendar::CalendarDate>,
     pub real_chapter_id: Option<String>,
     pub emotional_state: Option<String>,
     pub state_changes: Option<String>,
@@ -173,6 +176,9 @@ pub struct CreateCharacterTimelineEventRequest {
     pub event_title: String,
     pub event_description: String,
     pub story_time: Option<String>,
+    /// 按项目历法的结构化日期
+    #[serde(default)]
+    pub story_date: Option<crate::calendar::CalendarDate>,
     pub real_chapter_id: Option<String>,
     pub emotional_state: Option<String>,
     pub state_changes: Option<String>,
@@ -185,6 +191,9 @@ pub struct UpdateCharacterTimelineEventRequest {
     pub event_title: Option<String>,
     pub event_description: Option<String>,
     pub story_time: Option<String>,
+    /// 按项目历法的结构化日期
+    #[serde(default)]
+    pub story_date: Option<crate::calendar::CalendarDate>,
     pub real_chapter_id: Option<String>,
     pub emotional_state: Option<String>,
     pub state_changes: Option<String>,
@@ -270,6 +279,9 @@ pub struct WorldViewTimelineEvent {
     pub event_title: String,
     pub event_description: String,
     pub story_time: Option<String>,
+    /// 按项目历法的结构化日期
+    #[serde(default)]
+    pub story_date: Option<crate::calendar::CalendarDate>,
     pub impact_scope: Option<String>,
     pub related_characters: Option<String>,
     pub sort_order: i32,
@@ -284,6 +296,9 @@ pub struct CreateWorldViewTimelineEventRequest {
     pub event_title: String,
     pub event_description: String,
     pub story_time: Option<String>,
+    /// 按项目历法的结构化日期
+    #[serde(default)]
+    pub story_date: Option<crate::calendar::CalendarDate>,
     pub impact_scope: Option<String>,
     pub related_characters: Option<String>,
     pub sort_order: Option<i32>,
@@ -295,6 +310,9 @@ pub struct UpdateWorldViewTimelineEventRequest {
     pub event_title: Option<String>,
     pub event_description: Option<String>,
     pub story_time: Option<String>,
+    /// 按项目历法的结构化日期
+    #[serde(default)]
+    pub story_date: Option<crate::calendar::CalendarDate>,
     pub impact_scope: Option<String>,
     pub related_characters: Option<String>,
     pub sort_order: Option<i32>,
@@ -578,6 +596,14 @@ pub struct KnowledgeContext {
     pub active_characters: Vec<String>,
     pub current_location: Option<String>,
     pub timeline_context: String,
+    #[serde(default)]
+    pub organizations_summary: String,
+    /// 知识库中的事实，冲突时只保留定论
+    #[serde(default)]
+    pub facts_summary: String,
+    /// 本次上下文的生成记录，可据此查到用到的各条目版本
+    #[serde(default)]
+    pub generation_run_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -588,6 +614,10 @@ pub struct BuildKnowledgeContextRequest {
     pub include_worldview: Option<bool>,
     pub include_plot: Option<bool>,
     pub include_timeline: Option<bool>,
+    pub include_organizations: Option<bool>,
+    pub include_facts: Option<bool>,
+    /// 光标在当前章节中的字符位置，给出时优先写入光标附近提到的知识条目
+    pub cursor_offset: Option<usize>,
     pub max_tokens: Option<i32>,
 }
 
@@ -706,6 +736,31 @@ pub struct EmotionCurveStats {
     pub pacing_balance: f32,
 }
 
+/// 一章的目标情绪与实际情绪强度，`deviation` 为实际减目标
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChapterEmotionDeviation {
+    pub chapter_id: String,
+    pub chapter_number: i32,
+    pub chapter_title: String,
+    pub phase_name: String,
+    pub emotion_target: f32,
+    pub emotion_range: (i32, i32),
+    pub actual_intensity: f32,
+    pub deviation: f32,
+    pub within_range: bool,
+    pub suggestion: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmotionCurveComparison {
+    pub arc_type: String,
+    pub chapters: Vec<ChapterEmotionDeviation>,
+    pub deviation_series: Vec<f32>,
+    pub mean_absolute_deviation: f32,
+    /// 偏离目标区间最多的章节编号，按偏离程度排序
+    pub needs_adjustment: Vec<i32>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OptimizeChapterRequest {
     pub project_id: String,
@@ -7,7 +7,8 @@ use crate::multimedia_generation::illustration::IllustrationGenerator;
 use crate::multimedia_generation::image_client::{ImageClient, ImageProviderConfig};
 use crate::ai::OpenAIAdapter;
 use std::sync::Arc;
-use tauri::State;
+use crate::logger::Logger;
+use tauri::{AppHandle, Manager, State};
 use tokio::sync::RwLock;
 
 #[derive(Clone)]
@@ -199,6 +200,7 @@ pub async fn mmg_generate_scene_illustration(
 
 #[tauri::command]
 pub async fn mmg_generate_character_portrait(
+    app: AppHandle,
     character_id: String,
     character_name: String,
     appearance: String,
@@ -225,6 +227,14 @@ pub async fn mmg_generate_character_portrait(
         .generate_character_portrait(character_id, character_name, appearance, art_style)
         .await?;
 
+    // 生成的立绘存入角色画廊，保存失败不影响返回结果
+    let saved = get_db_path(&app)
+        .and_then(|db_path| crate::database::get_connection(&db_path).map_err(|e| e.to_string()))
+        .and_then(|conn| crate::character_portraits::save_generated(&conn, &portrait, &style));
+    if let Err(e) = saved {
+        Logger::new().with_feature("multimedia").warn(&format!("Failed to save portraits to gallery: {}", e));
+    }
+
     serde_json::to_string(&portrait).map_err(|e| e.to_string())
 }
 
@@ -258,3 +268,16 @@ pub async fn mmg_generate_cover(
 
     Ok(cover)
 }
+
+fn get_db_path(app: &AppHandle) -> Result<std::path::PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
@@ -0,0 +1,301 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+const SURNAMES: &[&str] = &[
+    "李", "王", "张", "刘", "陈", "杨", "赵", "黄", "周", "吴", "徐", "孙", "朱", "胡", "郭", "何", "林", "高", "罗", "郑", "梁",
+    "谢", "宋", "唐", "许", "韩", "冯", "邓", "曹", "彭", "萧", "田", "董", "袁", "蒋", "叶", "程", "苏", "魏", "沈", "姚", "陆",
+    "顾", "秦", "江", "薛", "段", "温", "云", "柳", "沐", "楚", "燕", "谢", "裴", "晏", "欧阳", "上官", "司马", "诸葛", "慕容",
+    "南宫", "东方", "令狐", "独孤", "公孙",
+];
+const GIVEN_MALE: &[&str] = &[
+    "轩", "浩", "宇", "辰", "泽", "然", "峰", "远", "承", "知", "墨", "渊", "川", "清", "舟", "昊", "霖", "毅", "恒", "哲", "骁",
+    "琛", "澜", "岳", "楷", "晟", "衍", "景", "睿", "翊", "珩", "砚", "钧", "策", "修", "行",
+];
+const GIVEN_FEMALE: &[&str] = &[
+    "婉", "清", "瑶", "雪", "兰", "芷", "若", "晴", "语", "诗", "梦", "璇", "萱", "怡", "薇", "宁", "月", "嫣", "绮", "素", "蓉",
+    "岚", "琬", "妍", "霜", "黛", "筠", "韵", "茜", "菱", "棠", "鸢", "昭", "音", "容", "汐",
+];
+/// 表字首字（排行或美称）和次字
+const COURTESY_FIRST: &[&str] = &["子", "伯", "仲", "叔", "季", "元", "文", "德", "公", "孟", "少", "长"];
+const COURTESY_SECOND: &[&str] = &[
+    "明", "远", "卿", "和", "谦", "安", "敬", "仁", "义", "达", "成", "章", "衡", "晦", "正", "之", "瑜", "扬", "渊", "舆",
+];
+const WESTERN_SYLLABLES: &[&str] = &[
+    "艾", "伦", "亚", "瑟", "索", "维", "克", "多", "安", "卡", "洛", "德", "斯", "特", "尔", "文", "巴", "布", "雷", "诺", "格",
+    "兰", "米", "拉", "奥", "利", "弗", "汉", "杰", "西", "凯", "埃", "希", "罗", "蒙", "塞",
+];
+const WESTERN_MALE_ENDINGS: &[&str] = &["斯", "尔", "德", "克", "恩", "特", "森", "文", "姆", "顿"];
+const WESTERN_FEMALE_ENDINGS: &[&str] = &["娅", "娜", "丝", "莉", "琳", "莎", "妮", "蒂", "薇", "拉"];
+const WESTERN_SURNAMES: &[&str] = &[
+    "格兰特", "布莱克", "卡特", "道森", "埃弗雷特", "黑尔", "兰利", "默瑟", "诺伍德", "普雷斯科特", "里德", "辛克莱", "桑顿", "万斯",
+    "惠特克", "费尔柴尔德", "阿什福德", "温特", "斯通", "莫里斯",
+];
+const SECT_PREFIXES: &[&str] = &[
+    "青云", "天剑", "玄天", "太虚", "紫霄", "万剑", "碧落", "凌霄", "昆仑", "蜀山", "灵霄", "落霞", "玄冥", "九幽", "天机", "归元",
+    "清虚", "焚天", "寒冰", "星河", "白鹿", "问道", "栖霞", "浮玉",
+];
+const SECT_SUFFIXES: &[&str] = &["宗", "门", "派", "阁", "谷", "宫", "殿", "山庄", "剑派", "书院", "教", "盟", "剑宗", "道宫"];
+const TECHNIQUE_PREFIXES: &[&str] = &[
+    "太上", "九天", "玄阴", "紫炎", "青木", "金刚", "幽冥", "天雷", "寒霜", "星辰", "混元", "太乙", "赤霄", "北冥", "无极", "大日",
+    "归墟", "琉璃",
+];
+const TECHNIQUE_CORES: &[&str] = &["剑", "雷", "火", "冰", "风", "龙", "凤", "星", "月", "魂", "灵", "神", "掌", "拳", "指", "印"];
+const TECHNIQUE_SUFFIXES: &[&str] = &["诀", "经", "功", "法", "录", "真经", "神通", "秘典", "心法", "宝典"];
+
+pub const STYLES: &[&str] = &["chinese", "chinese_courtesy", "western", "xianxia_sect", "xianxia_technique"];
+
+/// 起名条件。`min_syllables`/`max_syllables` 对人名限制名（不含姓）的字数，对门派、功法限制全名字数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NameRequest {
+    pub style: String,
+    /// male 或 female，不指定时混合
+    pub gender: Option<String>,
+    /// 指定姓氏
+    pub surname: Option<String>,
+    pub count: Option<usize>,
+    pub min_syllables: Option<usize>,
+    pub max_syllables: Option<usize>,
+    /// 名字中必须包含的字
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// 名字中不能出现的字
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// 固定种子时结果可复现
+    pub seed: Option<u64>,
+    /// 指定项目时跳过与已有角色重名或互相包含的名字
+    pub project_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedName {
+    pub name: String,
+    pub style: String,
+    pub surname: Option<String>,
+    pub given_name: Option<String>,
+    /// 表字，只在 chinese_courtesy 风格中生成
+    pub courtesy_name: Option<String>,
+    /// AI 给出的寓意解释
+    pub meaning: Option<String>,
+}
+
+fn pick<'a>(rng: &mut StdRng, list: &[&'a str]) -> &'a str {
+    list.choose(rng).copied().unwrap_or_default()
+}
+
+fn pick_in(rng: &mut StdRng, lists: &[&[&'static str]]) -> &'static str {
+    let list = lists[rng.gen_range(0..lists.len())];
+    pick(rng, list)
+}
+
+/// 各风格默认的字数范围
+fn default_range(style: &str) -> (usize, usize) {
+    match style {
+        "western" => (2, 3),
+        "xianxia_sect" => (3, 5),
+        "xianxia_technique" => (3, 6),
+        _ => (1, 2),
+    }
+}
+
+fn given_lists(gender: Option<&str>) -> Vec<&'static [&'static str]> {
+    match gender {
+        Some("male") => vec![GIVEN_MALE],
+        Some("female") => vec![GIVEN_FEMALE],
+        _ => vec![GIVEN_MALE, GIVEN_FEMALE],
+    }
+}
+
+fn candidate(rng: &mut StdRng, request: &NameRequest, syllables: usize) -> GeneratedName {
+    let gender = request.gender.as_deref();
+    let mut name = GeneratedName {
+        name: String::new(),
+        style: request.style.clone(),
+        surname: None,
+        given_name: None,
+        courtesy_name: None,
+        meaning: None,
+    };
+    match request.style.as_str() {
+        "western" => {
+            let endings = match gender {
+                Some("female") => WESTERN_FEMALE_ENDINGS,
+                Some("male") => WESTERN_MALE_ENDINGS,
+                _ if rng.gen_bool(0.5) => WESTERN_FEMALE_ENDINGS,
+                _ => WESTERN_MALE_ENDINGS,
+            };
+            let mut given: String = (1..syllables).map(|_| pick(rng, WESTERN_SYLLABLES)).collect();
+            given.push_str(pick(rng, endings));
+            let surname = request.surname.clone().unwrap_or_else(|| pick(rng, WESTERN_SURNAMES).to_string());
+            name.name = format!("{}·{}", given, surname);
+            name.surname = Some(surname);
+            name.given_name = Some(given);
+        }
+        "xianxia_sect" => {
+            name.name = format!("{}{}", pick(rng, SECT_PREFIXES), pick(rng, SECT_SUFFIXES));
+        }
+        "xianxia_technique" => {
+            let core = if rng.gen_bool(0.5) { pick(rng, TECHNIQUE_CORES) } else { "" };
+            name.name = format!("{}{}{}", pick(rng, TECHNIQUE_PREFIXES), core, pick(rng, TECHNIQUE_SUFFIXES));
+        }
+        _ => {
+            let lists = given_lists(gender);
+            let given: String = (0..syllables).map(|_| pick_in(rng, &lists)).collect();
+            let surname = request.surname.clone().unwrap_or_else(|| pick(rng, SURNAMES).to_string());
+            if request.style == "chinese_courtesy" {
+                name.courtesy_name = Some(format!("{}{}", pick(rng, COURTESY_FIRST), pick(rng, COURTESY_SECOND)));
+            }
+            name.name = format!("{}{}", surname, given);
+            name.surname = Some(surname);
+            name.given_name = Some(given);
+        }
+    }
+    name
+}
+
+/// 与已有名字相同，或一方包含另一方（如“林舟”和“林舟远”）
+pub(crate) fn collides(name: &str, existing: &[String]) -> bool {
+    existing.iter().any(|e| e.chars().count() >= 2 && (name.contains(e.as_str()) || e.contains(name)))
+}
+
+/// 按条件随机生成名字，跳过重复和与 `existing` 冲突的名字。条件过严时返回的名字可能少于 `count`
+pub fn generate(request: &NameRequest, existing: &[String]) -> Result<Vec<GeneratedName>, String> {
+    if !STYLES.contains(&request.style.as_str()) {
+        return Err(format!("未知的起名风格: {}", request.style));
+    }
+    let (default_min, default_max) = default_range(&request.style);
+    let min = request.min_syllables.unwrap_or(default_min).max(1);
+    let max = request.max_syllables.unwrap_or(default_max).max(min);
+    let count = request.count.unwrap_or(10).min(100);
+    let mut rng = match request.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let mut seen = HashSet::new();
+    let mut names = Vec::new();
+    for _ in 0..count * 500 {
+        if names.len() >= count {
+            break;
+        }
+        let syllables = rng.gen_range(min..=max);
+        let name = candidate(&mut rng, request, syllables);
+        let measured = name.given_name.as_deref().unwrap_or(&name.name).chars().count();
+        let text = format!("{}{}", name.name, name.courtesy_name.as_deref().unwrap_or_default());
+        if measured < min
+            || measured > max
+            || !request.include.iter().all(|c| text.contains(c.as_str()))
+            || request.exclude.iter().any(|c| !c.is_empty() && text.contains(c.as_str()))
+            || collides(&name.name, existing)
+            || !seen.insert(name.name.clone())
+        {
+            continue;
+        }
+        names.push(name);
+    }
+    Ok(names)
+}
+
+/// 项目已有角色的名字和别名
+pub fn existing_names(conn: &Connection, project_id: &str) -> Result<Vec<String>, String> {
+    conn.prepare(
+        "SELECT name FROM characters WHERE project_id = ?1
+         UNION SELECT a.alias FROM character_aliases a JOIN characters c ON c.id = a.character_id WHERE c.project_id = ?1",
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![project_id], |row| row.get(0))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+/// 解析 AI 返回的 {"名字": "寓意"}
+pub fn parse_meanings(response: &str) -> HashMap<String, String> {
+    let start = response.find('{').unwrap_or(0);
+    let end = response.rfind('}').map(|i| i + 1).unwrap_or(response.len());
+    let parsed: serde_json::Value = serde_json::from_str(&response[start..end]).unwrap_or_default();
+    parsed
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(name, meaning)| Some((name.clone(), meaning.as_str()?.trim().to_string())))
+        .collect()
+}
+
+/// 让 AI 解释每个名字的寓意和出处，写入 `meaning`
+pub async fn explain_with_ai(service: &crate::ai::AIService, model_id: &str, names: &mut [GeneratedName]) -> Result<(), String> {
+    let system_prompt = "你是一位精通姓名学和古典文学的小说顾问。只返回 JSON 对象，不要包含markdown代码块标记。";
+    let list: Vec<String> = names
+        .iter()
+        .map(|n| match &n.courtesy_name {
+            Some(courtesy) => format!("{}（字{}）", n.name, courtesy),
+            None => n.name.clone(),
+        })
+        .collect();
+    let user_prompt = format!(
+        "请用一两句话解释下面每个名字的寓意，有典故的注明出处，返回格式：{{\"名字\": \"寓意\"}}，键与给出的名字（不含括号部分）完全一致。\n\n{}",
+        list.join("\n")
+    );
+    let response = service.complete(model_id, system_prompt, &user_prompt).await?;
+    let meanings = parse_meanings(&response);
+    for name in names.iter_mut() {
+        name.meaning = meanings.get(&name.name).cloned();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(style: &str) -> NameRequest {
+        NameRequest {
+            style: style.to_string(),
+            gender: None,
+            surname: None,
+            count: Some(20),
+            min_syllables: None,
+            max_syllables: None,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            seed: Some(7),
+            project_id: None,
+        }
+    }
+
+    #[test]
+    fn test_constraints_and_uniqueness() {
+        let chinese = NameRequest {
+            gender: Some("female".to_string()),
+            surname: Some("林".to_string()),
+            min_syllables: Some(2),
+            include: vec!["雪".to_string()],
+            exclude: vec!["霜".to_string()],
+            ..request("chinese")
+        };
+        let existing = vec!["林雪瑶".to_string()];
+        let names = generate(&chinese, &existing).unwrap();
+        assert!(!names.is_empty());
+        for name in &names {
+            assert!(name.name.starts_with('林') && name.name.contains('雪') && !name.name.contains('霜'));
+            assert_eq!(name.given_name.as_ref().unwrap().chars().count(), 2);
+            assert_ne!(name.name, "林雪瑶");
+        }
+        assert_eq!(names.iter().map(|n| &n.name).collect::<HashSet<_>>().len(), names.len());
+        assert_eq!(generate(&chinese, &existing).unwrap()[0].name, names[0].name);
+
+        let courtesy = generate(&request("chinese_courtesy"), &[]).unwrap();
+        assert!(courtesy.iter().all(|n| n.courtesy_name.as_ref().is_some_and(|c| c.chars().count() == 2)));
+        let sects = generate(&NameRequest { max_syllables: Some(3), ..request("xianxia_sect") }, &[]).unwrap();
+        assert!(sects.iter().all(|n| n.name.chars().count() == 3));
+        assert!(generate(&request("western"), &[]).unwrap().iter().all(|n| n.name.contains('·')));
+        assert!(generate(&request("elvish"), &[]).is_err());
+
+        let meanings = parse_meanings("```json\n{\"林雪晴\": \"雪后初晴\"}\n```");
+        assert_eq!(meanings["林雪晴"], "雪后初晴");
+    }
+}
@@ -0,0 +1,132 @@
+use crate::logger::Logger;
+use crate::name_generator::{self, NameRequest};
+use crate::naming_cultures::{self, SaveNamingCultureRequest};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// 按风格和条件批量起名；指定项目时避开已有角色名，`explain` 为 true 时让 AI 解释寓意
+#[tauri::command]
+pub async fn generate_names(
+    app: AppHandle,
+    request: NameRequest,
+    explain: Option<bool>,
+    model_id: Option<String>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("name_generator");
+
+    let existing = match &request.project_id {
+        Some(project_id) => {
+            let db_path = get_db_path(&app)?;
+            let conn = crate::database::get_connection(&db_path)
+                .map_err(|e| format!("Failed to get database connection: {}", e))?;
+            name_generator::existing_names(&conn, project_id)?
+        }
+        None => Vec::new(),
+    };
+    let mut names = name_generator::generate(&request, &existing)?;
+
+    if explain.unwrap_or(false) && !names.is_empty() {
+        let ai_service = app.state::<std::sync::Arc<tokio::sync::RwLock<crate::ai::AIService>>>();
+        let service = ai_service.read().await;
+        let model_id = model_id.unwrap_or_else(|| "glm-4-flash".to_string());
+        if let Err(e) = name_generator::explain_with_ai(&service, &model_id, &mut names).await {
+            logger.warn(&format!("AI name explanation failed: {}", e));
+        }
+    }
+
+    serde_json::to_string(&names).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn save_naming_culture(app: AppHandle, request: SaveNamingCultureRequest) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let culture = naming_cultures::save_culture(&conn, &request)?;
+
+    serde_json::to_string(&culture).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_naming_cultures(app: AppHandle, project_id: String) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let cultures = naming_cultures::list_cultures(&conn, &project_id)?;
+
+    serde_json::to_string(&cultures).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_naming_culture(app: AppHandle, id: String) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    naming_cultures::delete_culture(&conn, &id)
+}
+
+/// 指定角色（character）或地点（location）所属的文化，`culture_id` 为空时取消
+#[tauri::command]
+pub async fn assign_naming_culture(
+    app: AppHandle,
+    entity_type: String,
+    entity_id: String,
+    culture_id: Option<String>,
+) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    naming_cultures::assign_culture(&conn, &entity_type, &entity_id, culture_id.as_deref())
+}
+
+/// 按文化的起名规则生成人名或地名，避开项目中已有的名字
+#[tauri::command]
+pub async fn generate_culture_names(
+    app: AppHandle,
+    culture_id: String,
+    entity_type: String,
+    count: Option<usize>,
+    seed: Option<u64>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("name_generator");
+    logger.info(&format!("Generating {} names for culture {}", entity_type, culture_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let culture = naming_cultures::get_culture(&conn, &culture_id)?;
+    let existing = naming_cultures::existing_names(&conn, &culture.project_id)?;
+    let names = culture.rules.generate(&entity_type, count.unwrap_or(10), seed, &existing)?;
+
+    serde_json::to_string(&names).map_err(|e| e.to_string())
+}
+
+/// 找出不符合所属文化起名规则的角色名和地名
+#[tauri::command]
+pub async fn check_naming_conventions(app: AppHandle, project_id: String) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let violations = naming_cultures::check_project(&conn, &project_id)?;
+
+    serde_json::to_string(&violations).map_err(|e| e.to_string())
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
@@ -0,0 +1,472 @@
+use chrono::Utc;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// 一种文化的起名规则。音节表为空时由声母、韵母、韵尾组合出音节；拉丁字母不区分大小写
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NamingRules {
+    /// 音节表，如“阿”“尔”“萨”或 "ka"、"rel"
+    pub syllables: Vec<String>,
+    pub onsets: Vec<String>,
+    pub vowels: Vec<String>,
+    pub codas: Vec<String>,
+    /// 名（不含姓和前后缀）的音节数范围
+    pub min_syllables: usize,
+    pub max_syllables: usize,
+    /// 人名可带的前缀、后缀，如“阿”“-dor”
+    pub person_prefixes: Vec<String>,
+    pub person_suffixes: Vec<String>,
+    /// 地名后缀，如“堡”“城”
+    pub place_suffixes: Vec<String>,
+    /// 姓氏表，为空时人名不带姓
+    pub family_names: Vec<String>,
+    /// 名与姓之间的分隔符，如“·”
+    pub separator: Option<String>,
+    /// 不能出现的字或字母组合
+    pub forbidden: Vec<String>,
+}
+
+impl Default for NamingRules {
+    fn default() -> Self {
+        NamingRules {
+            syllables: Vec::new(),
+            onsets: Vec::new(),
+            vowels: Vec::new(),
+            codas: Vec::new(),
+            min_syllables: 2,
+            max_syllables: 3,
+            person_prefixes: Vec::new(),
+            person_suffixes: Vec::new(),
+            place_suffixes: Vec::new(),
+            family_names: Vec::new(),
+            separator: None,
+            forbidden: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamingCulture {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub rules: NamingRules,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveNamingCultureRequest {
+    /// 为空时新建
+    pub id: Option<String>,
+    pub project_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub rules: NamingRules,
+}
+
+/// 违反所属文化起名规则的名字，`entity_type` 为 character 或 location
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamingViolation {
+    pub entity_type: String,
+    pub entity_id: String,
+    pub name: String,
+    pub culture_id: String,
+    pub culture_name: String,
+    pub reason: String,
+}
+
+pub const ENTITY_TYPES: &[&str] = &["character", "location"];
+
+fn table(entity_type: &str) -> Result<&'static str, String> {
+    match entity_type {
+        "character" => Ok("characters"),
+        "location" => Ok("locations"),
+        _ => Err(format!("未知的条目类型: {}", entity_type)),
+    }
+}
+
+impl NamingRules {
+    /// 音节表；为空时由声母、韵母、韵尾组合，声母和韵尾可以为空
+    pub fn inventory(&self) -> Vec<String> {
+        let mut syllables: Vec<String> = self.syllables.iter().map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty()).collect();
+        if syllables.is_empty() {
+            let optional = |list: &[String]| -> Vec<String> {
+                std::iter::once(String::new()).chain(list.iter().map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty())).collect()
+            };
+            for onset in optional(&self.onsets) {
+                for vowel in self.vowels.iter().map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty()) {
+                    for coda in optional(&self.codas) {
+                        syllables.push(format!("{}{}{}", onset, vowel, coda));
+                    }
+                }
+            }
+        }
+        let mut seen = HashSet::new();
+        syllables.retain(|s| seen.insert(s.clone()));
+        syllables
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.inventory().is_empty() {
+            return Err("至少要填写音节表或韵母".to_string());
+        }
+        if self.min_syllables == 0 || self.min_syllables > self.max_syllables {
+            return Err("音节数范围无效".to_string());
+        }
+        Ok(())
+    }
+
+    /// 把一段名字拆成音节，返回所有可能的音节数
+    fn syllable_counts(&self, text: &str, inventory: &[String]) -> HashSet<usize> {
+        let text = text.to_lowercase();
+        let mut reachable: Vec<HashSet<usize>> = vec![HashSet::new(); text.len() + 1];
+        reachable[0].insert(0);
+        for start in (0..text.len()).filter(|i| text.is_char_boundary(*i)) {
+            if reachable[start].is_empty() {
+                continue;
+            }
+            let counts: Vec<usize> = reachable[start].iter().map(|c| c + 1).collect();
+            for syllable in inventory.iter().filter(|s| text[start..].starts_with(s.as_str())) {
+                reachable[start + syllable.len()].extend(counts.iter().copied());
+            }
+        }
+        reachable.pop().unwrap_or_default()
+    }
+
+    /// 去掉前后缀后检查名字的主体，不合规则时返回原因
+    fn check_part(&self, part: &str, prefixes: &[String], suffixes: &[String], inventory: &[String]) -> Option<String> {
+        let lower = part.to_lowercase();
+        let strip = |text: &str, affixes: &[String], front: bool| -> Vec<String> {
+            let mut out = vec![text.to_string()];
+            for affix in affixes.iter().map(|a| a.trim().trim_matches('-').to_lowercase()).filter(|a| !a.is_empty()) {
+                let rest = if front { text.strip_prefix(affix.as_str()) } else { text.strip_suffix(affix.as_str()) };
+                if let Some(rest) = rest.filter(|r| !r.is_empty()) {
+                    out.push(rest.to_string());
+                }
+            }
+            out
+        };
+        let cores: Vec<String> = strip(&lower, prefixes, true).iter().flat_map(|t| strip(t, suffixes, false)).collect();
+        let counts: HashSet<usize> = cores.iter().flat_map(|core| self.syllable_counts(core, inventory)).collect();
+        if counts.is_empty() {
+            return Some(format!("“{}”无法拆成该文化的音节", part));
+        }
+        if !counts.iter().any(|c| (self.min_syllables..=self.max_syllables).contains(c)) {
+            let mut counts: Vec<usize> = counts.into_iter().collect();
+            counts.sort_unstable();
+            return Some(format!(
+                "“{}”有 {} 个音节，规则要求 {}–{} 个",
+                part, counts[0], self.min_syllables, self.max_syllables
+            ));
+        }
+        None
+    }
+
+    /// 检查名字是否符合规则，符合时返回 None；`entity_type` 为 character 或 location
+    pub fn check_name(&self, name: &str, entity_type: &str) -> Option<String> {
+        let name = name.trim();
+        let lower = name.to_lowercase();
+        if let Some(word) = self.forbidden.iter().find(|w| !w.trim().is_empty() && lower.contains(&w.trim().to_lowercase())) {
+            return Some(format!("含有禁用的“{}”", word.trim()));
+        }
+        let inventory = self.inventory();
+        if entity_type == "location" {
+            return self.check_part(name, &[], &self.place_suffixes, &inventory);
+        }
+        let mut parts: Vec<&str> = match self.separator.as_deref().filter(|s| !s.is_empty()) {
+            Some(separator) => name.split(separator).map(str::trim).filter(|p| !p.is_empty()).collect(),
+            None => vec![name],
+        };
+        if !self.family_names.is_empty() {
+            let is_family = |p: &str| self.family_names.iter().any(|f| f.trim().eq_ignore_ascii_case(p));
+            match parts.iter().position(|p| is_family(p)) {
+                Some(i) => {
+                    parts.remove(i);
+                }
+                None if parts.len() == 1 => {
+                    // 没有分隔符时姓在前
+                    if let Some(family) = self.family_names.iter().map(|f| f.trim()).find(|f| !f.is_empty() && lower.starts_with(&f.to_lowercase()) && lower.len() > f.len()) {
+                        return self.check_part(&name[family.len()..], &self.person_prefixes, &self.person_suffixes, &inventory);
+                    }
+                }
+                None => {}
+            }
+        }
+        parts.iter().find_map(|p| self.check_part(p, &self.person_prefixes, &self.person_suffixes, &inventory))
+    }
+
+    fn capitalize(text: &str) -> String {
+        let mut chars = text.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        }
+    }
+
+    fn candidate(&self, rng: &mut StdRng, inventory: &[String], entity_type: &str) -> String {
+        let syllables = rng.gen_range(self.min_syllables..=self.max_syllables);
+        let mut core: String = (0..syllables).map(|_| inventory.choose(rng).map(String::as_str).unwrap_or_default()).collect();
+        let pick = |rng: &mut StdRng, list: &[String]| list.choose(rng).map(|a| a.trim().trim_matches('-').to_string()).unwrap_or_default();
+        if entity_type == "location" {
+            core.push_str(&pick(rng, &self.place_suffixes));
+            return Self::capitalize(&core);
+        }
+        if !self.person_prefixes.is_empty() && rng.gen_bool(0.3) {
+            core = format!("{}{}", pick(rng, &self.person_prefixes), core);
+        }
+        if !self.person_suffixes.is_empty() && rng.gen_bool(0.5) {
+            core.push_str(&pick(rng, &self.person_suffixes));
+        }
+        let given = Self::capitalize(&core);
+        if self.family_names.is_empty() {
+            return given;
+        }
+        let family = pick(rng, &self.family_names);
+        match self.separator.as_deref() {
+            Some(separator) => format!("{}{}{}", given, separator, family),
+            None => format!("{}{}", family, given),
+        }
+    }
+
+    /// 按规则随机生成人名或地名，跳过不合规则、重复和与 `existing` 冲突的名字
+    pub fn generate(&self, entity_type: &str, count: usize, seed: Option<u64>, existing: &[String]) -> Result<Vec<String>, String> {
+        self.validate()?;
+        table(entity_type)?;
+        let inventory = self.inventory();
+        let count = count.min(100);
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let mut seen = HashSet::new();
+        let mut names = Vec::new();
+        for _ in 0..count * 200 {
+            if names.len() >= count {
+                break;
+            }
+            let name = self.candidate(&mut rng, &inventory, entity_type);
+            if self.check_name(&name, entity_type).is_some()
+                || crate::name_generator::collides(&name, existing)
+                || !seen.insert(name.clone())
+            {
+                continue;
+            }
+            names.push(name);
+        }
+        Ok(names)
+    }
+}
+
+const SELECT_CULTURE: &str = "SELECT id, project_id, name, description, rules_json, created_at, updated_at FROM naming_cultures";
+
+fn culture_from_row(row: &rusqlite::Row) -> rusqlite::Result<(NamingCulture, String)> {
+    Ok((
+        NamingCulture {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            name: row.get(2)?,
+            description: row.get(3)?,
+            rules: NamingRules::default(),
+            created_at: row.get(5)?,
+            updated_at: row.get(6)?,
+        },
+        row.get(4)?,
+    ))
+}
+
+fn with_rules((mut culture, json): (NamingCulture, String)) -> Result<NamingCulture, String> {
+    culture.rules = serde_json::from_str(&json).map_err(|e| format!("起名规则损坏: {}", e))?;
+    Ok(culture)
+}
+
+pub fn get_culture(conn: &Connection, id: &str) -> Result<NamingCulture, String> {
+    let row = conn
+        .query_row(&format!("{} WHERE id = ?", SELECT_CULTURE), params![id], culture_from_row)
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("文化不存在: {}", id))?;
+    with_rules(row)
+}
+
+pub fn list_cultures(conn: &Connection, project_id: &str) -> Result<Vec<NamingCulture>, String> {
+    let rows = conn
+        .prepare(&format!("{} WHERE project_id = ? ORDER BY created_at, name", SELECT_CULTURE))
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], culture_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    rows.into_iter().map(with_rules).collect()
+}
+
+pub fn save_culture(conn: &Connection, request: &SaveNamingCultureRequest) -> Result<NamingCulture, String> {
+    let name = request.name.trim();
+    if name.is_empty() {
+        return Err("文化名称不能为空".to_string());
+    }
+    request.rules.validate()?;
+    let rules = serde_json::to_string(&request.rules).map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let id = match &request.id {
+        Some(id) => {
+            let updated = conn
+                .execute(
+                    "UPDATE naming_cultures SET name = ?, description = ?, rules_json = ?, updated_at = ? WHERE id = ?",
+                    params![name, request.description, rules, now, id],
+                )
+                .map_err(|e| e.to_string())?;
+            if updated == 0 {
+                return Err(format!("文化不存在: {}", id));
+            }
+            id.clone()
+        }
+        None => {
+            let id = uuid::Uuid::new_v4().to_string();
+            conn.execute(
+                "INSERT INTO naming_cultures (id, project_id, name, description, rules_json, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                params![id, request.project_id, name, request.description, rules, now, now],
+            )
+            .map_err(|e| e.to_string())?;
+            id
+        }
+    };
+    get_culture(conn, &id)
+}
+
+/// 删除文化，归属该文化的角色和地点改为未指定
+pub fn delete_culture(conn: &Connection, id: &str) -> Result<(), String> {
+    for entity_type in ENTITY_TYPES {
+        conn.execute(&format!("UPDATE {} SET naming_culture_id = NULL WHERE naming_culture_id = ?", table(entity_type)?), params![id])
+            .map_err(|e| e.to_string())?;
+    }
+    conn.execute("DELETE FROM naming_cultures WHERE id = ?", params![id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 指定角色或地点所属的文化，`culture_id` 为空时取消
+pub fn assign_culture(conn: &Connection, entity_type: &str, entity_id: &str, culture_id: Option<&str>) -> Result<(), String> {
+    if let Some(culture_id) = culture_id {
+        get_culture(conn, culture_id)?;
+    }
+    let updated = conn
+        .execute(&format!("UPDATE {} SET naming_culture_id = ? WHERE id = ?", table(entity_type)?), params![culture_id, entity_id])
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("条目不存在: {}", entity_id));
+    }
+    Ok(())
+}
+
+/// 项目中已有的角色名、别名和地名，生成时避开
+pub fn existing_names(conn: &Connection, project_id: &str) -> Result<Vec<String>, String> {
+    let mut names = crate::name_generator::existing_names(conn, project_id)?;
+    let places: Vec<String> = conn
+        .prepare("SELECT name FROM locations WHERE project_id = ?")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    names.extend(places);
+    Ok(names)
+}
+
+/// 检查已指定文化的角色和地点的名字
+pub fn check_project(conn: &Connection, project_id: &str) -> Result<Vec<NamingViolation>, String> {
+    let cultures = list_cultures(conn, project_id)?;
+    let mut violations = Vec::new();
+    for entity_type in ENTITY_TYPES {
+        let extra = if *entity_type == "character" { " AND merged_into IS NULL" } else { "" };
+        let rows: Vec<(String, String, String)> = conn
+            .prepare(&format!(
+                "SELECT id, name, naming_culture_id FROM {} WHERE project_id = ? AND naming_culture_id IS NOT NULL{} ORDER BY created_at",
+                table(entity_type)?,
+                extra
+            ))
+            .map_err(|e| e.to_string())?
+            .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+        for (entity_id, name, culture_id) in rows {
+            let Some(culture) = cultures.iter().find(|c| c.id == culture_id) else { continue };
+            if let Some(reason) = culture.rules.check_name(&name, entity_type) {
+                violations.push(NamingViolation {
+                    entity_type: entity_type.to_string(),
+                    entity_id,
+                    name,
+                    culture_id,
+                    culture_name: culture.name.clone(),
+                    reason,
+                });
+            }
+        }
+    }
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_and_check_names() {
+        let elvish = NamingRules {
+            onsets: vec!["l".to_string(), "th".to_string(), "s".to_string()],
+            vowels: vec!["a".to_string(), "e".to_string(), "i".to_string()],
+            codas: vec!["n".to_string(), "r".to_string()],
+            person_suffixes: vec!["-iel".to_string()],
+            place_suffixes: vec!["dor".to_string()],
+            forbidden: vec!["k".to_string()],
+            ..NamingRules::default()
+        };
+        let names = elvish.generate("character", 15, Some(3), &["Lathen".to_string()]).unwrap();
+        assert_eq!(names.len(), 15);
+        assert!(names.iter().all(|n| elvish.check_name(n, "character").is_none() && n != "Lathen"));
+        assert_eq!(elvish.generate("character", 15, Some(3), &[]).unwrap()[0], names[0]);
+        assert!(elvish.generate("location", 5, Some(3), &[]).unwrap().iter().all(|n| n.ends_with("dor")));
+
+        assert!(elvish.check_name("Thaleniel", "character").is_none());
+        assert!(elvish.check_name("Lirendor", "location").is_none());
+        assert!(elvish.check_name("Kalen", "character").unwrap().contains("禁用"));
+        assert!(elvish.check_name("Lo", "character").unwrap().contains("无法拆成"));
+        assert!(elvish.check_name("La", "character").unwrap().contains("1 个音节"));
+
+        let northern = NamingRules {
+            syllables: ["阿", "尔", "萨", "德", "兰"].iter().map(|s| s.to_string()).collect(),
+            family_names: vec!["铁炉".to_string()],
+            separator: Some("·".to_string()),
+            ..NamingRules::default()
+        };
+        assert!(northern.check_name("阿尔萨·铁炉", "character").is_none());
+        assert!(northern.check_name("林舟", "character").is_some());
+
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES
+                 ('r1', 'p1', '阿尔萨·铁炉', 't0', 't0'), ('r2', 'p1', '林舟', 't0', 't0');
+             INSERT INTO locations (id, project_id, name, created_at, updated_at) VALUES ('l1', 'p1', '青石镇', 't0', 't0');",
+        )
+        .unwrap();
+        let culture = save_culture(
+            &conn,
+            &SaveNamingCultureRequest { id: None, project_id: "p1".to_string(), name: "北地".to_string(), description: None, rules: northern },
+        )
+        .unwrap();
+        assign_culture(&conn, "character", "r1", Some(&culture.id)).unwrap();
+        assign_culture(&conn, "character", "r2", Some(&culture.id)).unwrap();
+        let violations = check_project(&conn, "p1").unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].entity_id, "r2");
+
+        delete_culture(&conn, &culture.id).unwrap();
+        assert!(check_project(&conn, "p1").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,318 @@
+use crate::tokenizer;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// chinese：正文用汉字数字，百分比、小数、带字母的型号等保留阿拉伯数字；
+/// arabic：计量、计数的两位及以上数字用阿拉伯数字，成语和约数保留汉字
+pub const NUMERAL_STYLES: &[&str] = &["chinese", "arabic"];
+
+/// 问题前后各显示的字数
+const CONTEXT_CHARS: usize = 10;
+
+/// 常用量词和计量单位，多字的在前
+const CLASSIFIERS: &[&str] = &[
+    "公里", "千米", "厘米", "毫米", "公斤", "千克", "分钟", "小时", "个", "只", "匹", "头", "条", "尾", "本", "部", "册", "卷", "套",
+    "封", "把", "柄", "口", "座", "棵", "株", "朵", "枝", "束", "盆", "张", "页", "扇", "道", "栋", "间", "所", "辆", "台", "列",
+    "艘", "架", "件", "身", "双", "支", "管", "杆", "盏", "面", "首", "句", "场", "阵", "滴", "峰", "位", "名", "根", "颗", "粒",
+    "块", "片", "层", "次", "遍", "回", "篇", "声", "步", "天", "年", "月", "日", "岁", "元", "里", "丈", "尺", "寸", "斤", "钱",
+    "米", "克", "吨", "秒", "周", "人", "户", "家", "群", "队", "份", "碗", "杯", "壶", "瓶", "袋", "枚", "尊", "具", "副", "对",
+    "幅", "章", "节", "集", "届", "级", "号", "点",
+];
+
+/// 名词及其可搭配的量词，第一个是修改建议
+const MEASURE_WORDS: &[(&str, &[&str])] = &[
+    ("马", &["匹"]),
+    ("牛", &["头"]),
+    ("猪", &["头", "口"]),
+    ("鱼", &["条", "尾"]),
+    ("狗", &["条", "只"]),
+    ("骆驼", &["峰", "头", "匹"]),
+    ("书", &["本", "部", "册", "卷", "套"]),
+    ("信", &["封"]),
+    ("刀", &["把", "柄", "口"]),
+    ("剑", &["把", "柄", "口"]),
+    ("枪", &["把", "支", "杆"]),
+    ("山", &["座"]),
+    ("桥", &["座"]),
+    ("河", &["条", "道"]),
+    ("树", &["棵", "株"]),
+    ("花", &["朵", "枝", "束", "盆"]),
+    ("纸", &["张", "页"]),
+    ("床", &["张"]),
+    ("门", &["扇", "道", "个"]),
+    ("车", &["辆", "台"]),
+    ("船", &["艘", "条", "只"]),
+    ("飞机", &["架"]),
+    ("衣服", &["件", "套", "身"]),
+    ("鞋", &["双", "只"]),
+    ("笔", &["支", "枝", "管"]),
+    ("灯", &["盏"]),
+    ("镜子", &["面"]),
+    ("旗", &["面", "杆"]),
+    ("眼睛", &["双", "只"]),
+    ("房子", &["栋", "座", "间", "套", "所"]),
+    ("诗", &["首", "句"]),
+    ("歌", &["首", "支"]),
+    ("电影", &["部", "场"]),
+    ("雨", &["场", "阵", "滴"]),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NumeralIssue {
+    /// numeral_style 或 measure_word
+    pub issue_type: String,
+    /// 字符偏移
+    pub position: usize,
+    pub original: String,
+    pub suggestion: String,
+    pub description: String,
+    pub context: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NumeralCheck {
+    pub style: String,
+    pub issues: Vec<NumeralIssue>,
+    pub total_count: usize,
+    /// 应用全部建议后的文本
+    pub fixed: String,
+}
+
+const DIGITS: &[char] = &['零', '一', '二', '三', '四', '五', '六', '七', '八', '九'];
+
+fn digit_value(c: char) -> Option<u64> {
+    match c {
+        '零' | '〇' => Some(0),
+        '两' => Some(2),
+        _ => DIGITS.iter().position(|&d| d == c).map(|i| i as u64),
+    }
+}
+
+/// 解析“一百零五”“十五”“三万二千”这类规范写法，约数（“三四”）和成语（“千千万万”）返回 None
+fn parse_chinese(text: &str) -> Option<u64> {
+    let (mut total, mut section, mut digit): (u64, u64, Option<u64>) = (0, 0, None);
+    let mut last_unit = u64::MAX;
+    let mut last_big = u64::MAX;
+    for c in text.chars() {
+        if let Some(value) = digit_value(c) {
+            if digit.is_some() && value != 0 {
+                return None;
+            }
+            digit = if value == 0 { None } else { Some(value) };
+            continue;
+        }
+        match c {
+            '十' | '百' | '千' => {
+                let unit = match c {
+                    '十' => 10,
+                    '百' => 100,
+                    _ => 1000,
+                };
+                if unit >= last_unit {
+                    return None;
+                }
+                section += digit.take().unwrap_or(1) * unit;
+                last_unit = unit;
+            }
+            '万' | '亿' => {
+                let big = if c == '万' { 10_000 } else { 100_000_000 };
+                if big >= last_big || (section == 0 && digit.is_none()) {
+                    return None;
+                }
+                total = (total + section + digit.take().unwrap_or(0)) * big;
+                section = 0;
+                last_unit = u64::MAX;
+                last_big = big;
+            }
+            _ => return None,
+        }
+    }
+    Some(total + section + digit.unwrap_or(0))
+}
+
+/// 整数转汉字数字，如 105 → 一百零五、20050 → 二万零五十
+pub(crate) fn to_chinese(n: u64) -> String {
+    if n == 0 {
+        return "零".to_string();
+    }
+    fn section(n: u64) -> String {
+        let mut out = String::new();
+        let mut zero = false;
+        for (unit, name) in [(1000, "千"), (100, "百"), (10, "十"), (1, "")] {
+            let d = n / unit % 10;
+            if d == 0 {
+                zero = !out.is_empty();
+                continue;
+            }
+            if zero {
+                out.push('零');
+                zero = false;
+            }
+            out.push(DIGITS[d as usize]);
+            out.push_str(name);
+        }
+        out
+    }
+    let mut out = String::new();
+    for (unit, name) in [(100_000_000, "亿"), (10_000, "万"), (1, "")] {
+        let part = n / unit % 10_000;
+        if part == 0 {
+            continue;
+        }
+        if !out.is_empty() && part < 1000 {
+            out.push('零');
+        }
+        out.push_str(&section(part));
+        out.push_str(name);
+    }
+    if out.starts_with("一十") {
+        out.remove(0);
+    }
+    out
+}
+
+fn context(text: &str, start: usize, end: usize) -> String {
+    let before: Vec<char> = text[..start].chars().collect();
+    let mut context: String = before[before.len().saturating_sub(CONTEXT_CHARS)..].iter().collect();
+    context.push_str(&text[start..end]);
+    context.extend(text[end..].chars().take(CONTEXT_CHARS));
+    context
+}
+
+fn classifier_at(text: &str) -> Option<&'static str> {
+    CLASSIFIERS.iter().find(|c| text.starts_with(**c)).copied()
+}
+
+/// 阿拉伯数字改汉字：跳过小数、百分比、时间、紧挨字母的型号，四位数年份逐位转写
+fn chinese_style_issues(text: &str, spans: &mut Vec<(usize, usize, String, &'static str, String)>) {
+    let re = Regex::new(r"\d+").expect("valid numeral pattern");
+    for m in re.find_iter(text) {
+        let before = text[..m.start()].chars().next_back();
+        let after = text[m.end()..].trim_start_matches(' ').chars().next();
+        let technical = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric() || ".:：%‰℃°/-_".contains(c));
+        if technical(before) || technical(after) || m.as_str().starts_with('0') || m.as_str().len() > 8 {
+            continue;
+        }
+        let rest = &text[m.end()..];
+        let suggestion = if m.as_str().len() == 4 && rest.starts_with('年') {
+            m.as_str().chars().map(|c| if c == '0' { '〇' } else { DIGITS[c.to_digit(10).unwrap_or(0) as usize] }).collect()
+        } else {
+            to_chinese(m.as_str().parse().unwrap_or(0))
+        };
+        spans.push((m.start(), m.end(), suggestion, "numeral_style", "正文数字建议用汉字书写".to_string()));
+    }
+}
+
+/// 汉字改阿拉伯数字：只处理后接量词或单位的两位及以上数，约数和成语保留，四位年份逐位转写
+fn arabic_style_issues(text: &str, spans: &mut Vec<(usize, usize, String, &'static str, String)>) {
+    let re = Regex::new("[零〇一二两三四五六七八九十百千万亿]+").expect("valid numeral pattern");
+    for m in re.find_iter(text) {
+        let rest = &text[m.end()..];
+        if classifier_at(rest).is_none() || text[..m.start()].ends_with(['几', '数']) {
+            continue;
+        }
+        let is_year = rest.starts_with('年') && m.as_str().chars().count() == 4 && m.as_str().chars().all(|c| digit_value(c).is_some());
+        let suggestion = if is_year {
+            m.as_str().chars().filter_map(digit_value).map(|d| d.to_string()).collect()
+        } else {
+            match parse_chinese(m.as_str()) {
+                Some(value) if value >= 10 => value.to_string(),
+                _ => continue,
+            }
+        };
+        spans.push((m.start(), m.end(), suggestion, "numeral_style", "计量、计数建议用阿拉伯数字".to_string()));
+    }
+}
+
+/// 数词（或这、那、每、几）后的量词与名词搭配不当，如“一个马”
+fn measure_word_issues(text: &str, spans: &mut Vec<(usize, usize, String, &'static str, String)>) {
+    let re = Regex::new("[0-9零〇一二两三四五六七八九十百千万几这那每哪半]").expect("valid quantifier pattern");
+    for m in re.find_iter(text) {
+        let rest = &text[m.end()..];
+        let Some(classifier) = classifier_at(rest) else { continue };
+        let after = &rest[classifier.len()..];
+        // 分词结果以名词开头且不是“书生”“马车”这类词典里的合成词；
+        // 未登录词（词性 x，如“马走”）是分词器猜的，也按名词处理
+        let window: String = after.chars().take(6).collect();
+        let Some((token, tag)) = tokenizer::tag(&window).into_iter().next() else { continue };
+        let Some((noun, allowed)) = MEASURE_WORDS
+            .iter()
+            .filter(|(word, _)| token == *word || (tag == "x" && token.starts_with(*word)))
+            .max_by_key(|(word, _)| word.len())
+        else {
+            continue;
+        };
+        if allowed.contains(&classifier) {
+            continue;
+        }
+        let start = m.end();
+        spans.push((
+            start,
+            start + classifier.len(),
+            allowed[0].to_string(),
+            "measure_word",
+            format!("“{}”的量词一般用“{}”", noun, allowed.join("”“")),
+        ));
+    }
+}
+
+/// 按数字风格检查正文并给出修改建议，`fixed` 是应用全部建议后的文本
+pub fn check(text: &str, style: &str) -> Result<NumeralCheck, String> {
+    if !NUMERAL_STYLES.contains(&style) {
+        return Err(format!("未知的数字风格: {}", style));
+    }
+    let mut spans = Vec::new();
+    if style == "chinese" {
+        chinese_style_issues(text, &mut spans);
+    } else {
+        arabic_style_issues(text, &mut spans);
+    }
+    measure_word_issues(text, &mut spans);
+    spans.sort_by_key(|span| span.0);
+
+    let mut issues = Vec::new();
+    let mut fixed = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, end, suggestion, issue_type, description) in spans {
+        if start < last {
+            continue;
+        }
+        fixed.push_str(&text[last..start]);
+        fixed.push_str(&suggestion);
+        last = end;
+        issues.push(NumeralIssue {
+            issue_type: issue_type.to_string(),
+            position: text[..start].chars().count(),
+            original: text[start..end].to_string(),
+            suggestion,
+            description,
+            context: context(text, start, end),
+        });
+    }
+    fixed.push_str(&text[last..]);
+    Ok(NumeralCheck { style: style.to_string(), total_count: issues.len(), issues, fixed })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numeral_styles_and_measure_words() {
+        assert_eq!(parse_chinese("一百零五"), Some(105));
+        assert_eq!(parse_chinese("三万二千"), Some(32000));
+        assert_eq!(parse_chinese("三四"), None);
+        assert_eq!(parse_chinese("千千万万"), None);
+        assert_eq!((to_chinese(15), to_chinese(105), to_chinese(20050)), ("十五".to_string(), "一百零五".to_string(), "二万零五十".to_string()));
+
+        let chinese = check("2024年，他骑着一个马走了15里，手机电量剩20%，型号A7。", "chinese").unwrap();
+        assert_eq!(chinese.fixed, "二〇二四年，他骑着一匹马走了十五里，手机电量剩20%，型号A7。");
+        let types: Vec<&str> = chinese.issues.iter().map(|i| i.issue_type.as_str()).collect();
+        assert_eq!(types, vec!["numeral_style", "measure_word", "numeral_style"]);
+        assert_eq!(chinese.issues[1].position, 10);
+
+        let arabic = check("二〇二四年，三十六名弟子十万火急赶来，三四个人留下，一个书生说的。", "arabic").unwrap();
+        assert_eq!(arabic.fixed, "2024年，36名弟子十万火急赶来，三四个人留下，一个书生说的。");
+        assert!(check("", "roman").is_err());
+    }
+}
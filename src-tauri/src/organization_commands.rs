@@ -0,0 +1,143 @@
+use crate::logger::Logger;
+use crate::organizations::{self, CreateOrganizationRequest, UpdateOrganizationRequest};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+#[tauri::command]
+pub async fn create_organization(app: AppHandle, request: CreateOrganizationRequest) -> Result<String, String> {
+    let logger = Logger::new().with_feature("organizations");
+    logger.info(&format!("Creating organization {} in project {}", request.name, request.project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let organization = organizations::create_organization(&conn, &request)?;
+
+    serde_json::to_string(&organization).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_organizations(app: AppHandle, project_id: String) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let list = organizations::list_organizations(&conn, &project_id)?;
+
+    serde_json::to_string(&list).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_organization(app: AppHandle, request: UpdateOrganizationRequest) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let organization = organizations::update_organization(&conn, &request)?;
+
+    serde_json::to_string(&organization).map_err(|e| e.to_string())
+}
+
+/// 删除组织，成员记录随之删除，下级组织改为没有上级
+#[tauri::command]
+pub async fn delete_organization(app: AppHandle, id: String) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    organizations::delete_organization(&conn, &id)
+}
+
+#[tauri::command]
+pub async fn add_organization_member(
+    app: AppHandle,
+    organization_id: String,
+    character_id: String,
+    role: Option<String>,
+    join_chapter_id: Option<String>,
+    leave_chapter_id: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let member = organizations::add_member(
+        &conn,
+        &organization_id,
+        &character_id,
+        role.as_deref(),
+        join_chapter_id.as_deref(),
+        leave_chapter_id.as_deref(),
+    )?;
+
+    serde_json::to_string(&member).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_organization_member(
+    app: AppHandle,
+    member_id: String,
+    role: Option<String>,
+    join_chapter_id: Option<String>,
+    leave_chapter_id: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let member = organizations::update_member(
+        &conn,
+        &member_id,
+        role.as_deref(),
+        join_chapter_id.as_deref(),
+        leave_chapter_id.as_deref(),
+    )?;
+
+    serde_json::to_string(&member).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_organization_member(app: AppHandle, member_id: String) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    organizations::remove_member(&conn, &member_id)
+}
+
+#[tauri::command]
+pub async fn get_organization_members(app: AppHandle, organization_id: String) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let members = organizations::list_members(&conn, &organization_id)?;
+
+    serde_json::to_string(&members).map_err(|e| e.to_string())
+}
+
+/// 角色参加过的全部组织
+#[tauri::command]
+pub async fn get_character_organizations(app: AppHandle, character_id: String) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let memberships = organizations::character_memberships(&conn, &character_id)?;
+
+    serde_json::to_string(&memberships).map_err(|e| e.to_string())
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
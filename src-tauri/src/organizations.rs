@@ -0,0 +1,408 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// 势力或组织（门派、帮会、朝廷、公司……），`parent_id` 指向上级组织
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Organization {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub org_type: Option<String>,
+    pub parent_id: Option<String>,
+    pub description: Option<String>,
+    pub goals: Option<String>,
+    /// 关联的世界观条目
+    pub worldview_id: Option<String>,
+    pub member_count: usize,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateOrganizationRequest {
+    pub project_id: String,
+    pub name: String,
+    pub org_type: Option<String>,
+    pub parent_id: Option<String>,
+    pub description: Option<String>,
+    pub goals: Option<String>,
+    pub worldview_id: Option<String>,
+}
+
+/// 为 None 的字段保持不变；`parent_id`、`worldview_id` 传空字符串时清除
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateOrganizationRequest {
+    pub id: String,
+    pub name: Option<String>,
+    pub org_type: Option<String>,
+    pub parent_id: Option<String>,
+    pub description: Option<String>,
+    pub goals: Option<String>,
+    pub worldview_id: Option<String>,
+}
+
+/// 组织成员，`join_chapter_id`/`leave_chapter_id` 为空表示故事开始前加入、一直没有离开
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrganizationMember {
+    pub id: String,
+    pub organization_id: String,
+    pub character_id: String,
+    pub character_name: String,
+    pub role: Option<String>,
+    pub join_chapter_id: Option<String>,
+    pub leave_chapter_id: Option<String>,
+    pub created_at: String,
+}
+
+const ORGANIZATION_COLUMNS: &str = "o.id, o.project_id, o.name, o.org_type, o.parent_id, o.description, o.goals, o.worldview_id,
+     (SELECT COUNT(*) FROM organization_members m WHERE m.organization_id = o.id AND m.leave_chapter_id IS NULL), o.created_at, o.updated_at";
+const MEMBER_COLUMNS: &str =
+    "m.id, m.organization_id, m.character_id, c.name, m.role, m.join_chapter_id, m.leave_chapter_id, m.created_at";
+
+fn organization_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Organization> {
+    Ok(Organization {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        name: row.get(2)?,
+        org_type: row.get(3)?,
+        parent_id: row.get(4)?,
+        description: row.get(5)?,
+        goals: row.get(6)?,
+        worldview_id: row.get(7)?,
+        member_count: row.get::<_, i64>(8)? as usize,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+fn member_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<OrganizationMember> {
+    Ok(OrganizationMember {
+        id: row.get(0)?,
+        organization_id: row.get(1)?,
+        character_id: row.get(2)?,
+        character_name: row.get(3)?,
+        role: row.get(4)?,
+        join_chapter_id: row.get(5)?,
+        leave_chapter_id: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+pub fn get_organization(conn: &Connection, id: &str) -> Result<Organization, String> {
+    conn.query_row(&format!("SELECT {} FROM organizations o WHERE o.id = ?", ORGANIZATION_COLUMNS), params![id], organization_from_row)
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "组织不存在".to_string())
+}
+
+/// 项目的全部组织，上级组织排在前面
+pub fn list_organizations(conn: &Connection, project_id: &str) -> Result<Vec<Organization>, String> {
+    conn.prepare(&format!(
+        "SELECT {} FROM organizations o WHERE o.project_id = ? ORDER BY o.parent_id IS NOT NULL, o.created_at",
+        ORGANIZATION_COLUMNS
+    ))
+    .map_err(|e| e.to_string())?
+    .query_map(params![project_id], organization_from_row)
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+/// 校验上级组织和世界观条目属于同一项目，且上级不是自己或自己的下级
+fn check_links(conn: &Connection, project_id: &str, id: Option<&str>, parent_id: Option<&str>, worldview_id: Option<&str>) -> Result<(), String> {
+    if let Some(parent_id) = parent_id {
+        let mut current = Some(parent_id.to_string());
+        while let Some(ancestor) = current {
+            if Some(ancestor.as_str()) == id {
+                return Err("上级组织不能是自己或自己的下级".to_string());
+            }
+            let parent = get_organization(conn, &ancestor).map_err(|_| "上级组织不存在".to_string())?;
+            if parent.project_id != project_id {
+                return Err("上级组织不属于同一项目".to_string());
+            }
+            current = parent.parent_id;
+        }
+    }
+    if let Some(worldview_id) = worldview_id {
+        let same: Option<bool> = conn
+            .query_row("SELECT project_id = ? FROM world_views WHERE id = ?", params![project_id, worldview_id], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if same != Some(true) {
+            return Err("世界观条目不存在或不属于同一项目".to_string());
+        }
+    }
+    Ok(())
+}
+
+pub fn create_organization(conn: &Connection, request: &CreateOrganizationRequest) -> Result<Organization, String> {
+    let name = request.name.trim();
+    if name.is_empty() {
+        return Err("组织名称不能为空".to_string());
+    }
+    check_links(conn, &request.project_id, None, request.parent_id.as_deref(), request.worldview_id.as_deref())?;
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO organizations (id, project_id, name, org_type, parent_id, description, goals, worldview_id, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)",
+        params![id, request.project_id, name, request.org_type, request.parent_id, request.description, request.goals, request.worldview_id, now],
+    )
+    .map_err(|e| format!("Failed to create organization: {}", e))?;
+    crate::tokenizer::add_words(&[name]);
+    get_organization(conn, &id)
+}
+
+pub fn update_organization(conn: &Connection, request: &UpdateOrganizationRequest) -> Result<Organization, String> {
+    let current = get_organization(conn, &request.id)?;
+    let clear = |value: &Option<String>, old: Option<String>| match value {
+        Some(v) if v.is_empty() => None,
+        Some(v) => Some(v.clone()),
+        None => old,
+    };
+    let parent_id = clear(&request.parent_id, current.parent_id);
+    let worldview_id = clear(&request.worldview_id, current.worldview_id);
+    check_links(conn, &current.project_id, Some(&current.id), parent_id.as_deref(), worldview_id.as_deref())?;
+    let name = request.name.as_deref().map(str::trim).filter(|n| !n.is_empty()).unwrap_or(&current.name).to_string();
+    conn.execute(
+        "UPDATE organizations SET name = ?, org_type = ?, parent_id = ?, description = ?, goals = ?, worldview_id = ?, updated_at = ? WHERE id = ?",
+        params![
+            name,
+            request.org_type.clone().or(current.org_type),
+            parent_id,
+            request.description.clone().or(current.description),
+            request.goals.clone().or(current.goals),
+            worldview_id,
+            Utc::now().to_rfc3339(),
+            request.id,
+        ],
+    )
+    .map_err(|e| format!("Failed to update organization: {}", e))?;
+    crate::tokenizer::add_words(&[name]);
+    get_organization(conn, &request.id)
+}
+
+/// 删除组织，成员记录随之删除，下级组织改为没有上级
+pub fn delete_organization(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM organizations WHERE id = ?", params![id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 章节在项目中的阅读顺序
+fn chapter_positions(conn: &Connection, project_id: &str) -> Result<HashMap<String, usize>, String> {
+    let ids: Vec<String> = conn
+        .prepare("SELECT id FROM chapters WHERE project_id = ? ORDER BY sort_order, created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(ids.into_iter().enumerate().map(|(i, id)| (id, i)).collect())
+}
+
+/// 校验成员的角色和章节属于组织所在项目，且离开章节不早于加入章节
+fn check_member(
+    conn: &Connection,
+    organization: &Organization,
+    character_id: &str,
+    join_chapter_id: Option<&str>,
+    leave_chapter_id: Option<&str>,
+) -> Result<(), String> {
+    let same: Option<bool> = conn
+        .query_row(
+            "SELECT project_id = ? FROM characters WHERE id = ? AND merged_into IS NULL",
+            params![organization.project_id, character_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if same != Some(true) {
+        return Err("角色不存在或不属于同一项目".to_string());
+    }
+    let positions = chapter_positions(conn, &organization.project_id)?;
+    let position = |id: Option<&str>| -> Result<Option<usize>, String> {
+        id.map(|id| positions.get(id).copied().ok_or_else(|| "章节不存在或不属于同一项目".to_string())).transpose()
+    };
+    if let (Some(join), Some(leave)) = (position(join_chapter_id)?, position(leave_chapter_id)?) {
+        if leave < join {
+            return Err("离开章节不能早于加入章节".to_string());
+        }
+    }
+    Ok(())
+}
+
+pub fn add_member(
+    conn: &Connection,
+    organization_id: &str,
+    character_id: &str,
+    role: Option<&str>,
+    join_chapter_id: Option<&str>,
+    leave_chapter_id: Option<&str>,
+) -> Result<OrganizationMember, String> {
+    let organization = get_organization(conn, organization_id)?;
+    check_member(conn, &organization, character_id, join_chapter_id, leave_chapter_id)?;
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO organization_members (id, organization_id, character_id, role, join_chapter_id, leave_chapter_id, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+        params![id, organization_id, character_id, role, join_chapter_id, leave_chapter_id, Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| format!("Failed to add member: {}", e))?;
+    get_member(conn, &id)
+}
+
+fn get_member(conn: &Connection, member_id: &str) -> Result<OrganizationMember, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM organization_members m JOIN characters c ON c.id = m.character_id WHERE m.id = ?", MEMBER_COLUMNS),
+        params![member_id],
+        member_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "成员不存在".to_string())
+}
+
+/// 修改成员的职位和加入、离开章节，传入的值整体替换原值
+pub fn update_member(
+    conn: &Connection,
+    member_id: &str,
+    role: Option<&str>,
+    join_chapter_id: Option<&str>,
+    leave_chapter_id: Option<&str>,
+) -> Result<OrganizationMember, String> {
+    let member = get_member(conn, member_id)?;
+    let organization = get_organization(conn, &member.organization_id)?;
+    check_member(conn, &organization, &member.character_id, join_chapter_id, leave_chapter_id)?;
+    conn.execute(
+        "UPDATE organization_members SET role = ?, join_chapter_id = ?, leave_chapter_id = ? WHERE id = ?",
+        params![role, join_chapter_id, leave_chapter_id, member_id],
+    )
+    .map_err(|e| e.to_string())?;
+    get_member(conn, member_id)
+}
+
+pub fn remove_member(conn: &Connection, member_id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM organization_members WHERE id = ?", params![member_id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn list_members(conn: &Connection, organization_id: &str) -> Result<Vec<OrganizationMember>, String> {
+    conn.prepare(&format!(
+        "SELECT {} FROM organization_members m JOIN characters c ON c.id = m.character_id
+         WHERE m.organization_id = ? AND c.merged_into IS NULL ORDER BY m.created_at, m.rowid",
+        MEMBER_COLUMNS
+    ))
+    .map_err(|e| e.to_string())?
+    .query_map(params![organization_id], member_from_row)
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+/// 角色参加过的全部组织（成员记录）
+pub fn character_memberships(conn: &Connection, character_id: &str) -> Result<Vec<OrganizationMember>, String> {
+    conn.prepare(&format!(
+        "SELECT {} FROM organization_members m JOIN characters c ON c.id = m.character_id
+         WHERE m.character_id = ? ORDER BY m.created_at, m.rowid",
+        MEMBER_COLUMNS
+    ))
+    .map_err(|e| e.to_string())?
+    .query_map(params![character_id], member_from_row)
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+/// 写作上下文中的组织摘要，每个组织一行；给出 `chapter_id` 时只列该章仍在组织中的成员
+pub fn context_summary(conn: &Connection, project_id: &str, chapter_id: Option<&str>) -> Result<String, String> {
+    let organizations = list_organizations(conn, project_id)?;
+    let positions = chapter_positions(conn, project_id)?;
+    let current = chapter_id.and_then(|id| positions.get(id).copied());
+    let names: HashMap<&str, &str> = organizations.iter().map(|o| (o.id.as_str(), o.name.as_str())).collect();
+
+    let mut lines = Vec::new();
+    for organization in &organizations {
+        let members: Vec<String> = list_members(conn, &organization.id)?
+            .into_iter()
+            .filter(|m| {
+                let at = |id: &Option<String>| id.as_ref().and_then(|id| positions.get(id).copied());
+                match current {
+                    Some(current) => {
+                        at(&m.join_chapter_id).is_none_or(|join| join <= current)
+                            && at(&m.leave_chapter_id).is_none_or(|leave| leave > current)
+                    }
+                    None => m.leave_chapter_id.is_none(),
+                }
+            })
+            .map(|m| match m.role.filter(|r| !r.trim().is_empty()) {
+                Some(role) => format!("{}（{}）", m.character_name, role),
+                None => m.character_name,
+            })
+            .collect();
+        let mut parts = vec![organization.name.clone()];
+        if let Some(t) = &organization.org_type { parts.push(format!("[{}]", t)); }
+        if let Some(parent) = organization.parent_id.as_deref().and_then(|id| names.get(id)) { parts.push(format!("隶属:{}", parent)); }
+        if let Some(g) = &organization.goals { parts.push(format!("目标:{}", g)); }
+        if !members.is_empty() { parts.push(format!("成员:{}", members.join("、"))); }
+        lines.push(parts.join(" | "));
+    }
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_organization_hierarchy_members_and_context() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES
+                 ('r1', 'p1', '林舟', 't0', 't0'), ('r2', 'p1', '沈青', 't0', 't0');
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '', 1, 't0', 't0'), ('c2', 'p1', '第二章', '', 2, 't0', 't0'), ('c3', 'p1', '第三章', '', 3, 't0', 't0');",
+        )
+        .unwrap();
+        let request = |name: &str, parent_id: Option<String>| CreateOrganizationRequest {
+            project_id: "p1".to_string(),
+            name: name.to_string(),
+            org_type: Some("门派".to_string()),
+            parent_id,
+            description: None,
+            goals: Some("守护北境".to_string()),
+            worldview_id: None,
+        };
+        let sect = create_organization(&conn, &request("青云门", None)).unwrap();
+        let branch = create_organization(&conn, &request("青云外门", Some(sect.id.clone()))).unwrap();
+        let cycle = UpdateOrganizationRequest {
+            id: sect.id.clone(),
+            name: None,
+            org_type: None,
+            parent_id: Some(branch.id.clone()),
+            description: None,
+            goals: None,
+            worldview_id: None,
+        };
+        assert!(update_organization(&conn, &cycle).is_err());
+
+        add_member(&conn, &sect.id, "r1", Some("掌门"), None, None).unwrap();
+        let member = add_member(&conn, &sect.id, "r2", None, Some("c2"), None).unwrap();
+        assert!(update_member(&conn, &member.id, None, Some("c3"), Some("c1")).is_err());
+        update_member(&conn, &member.id, Some("弟子"), Some("c1"), Some("c3")).unwrap();
+        assert_eq!(character_memberships(&conn, "r2").unwrap()[0].role.as_deref(), Some("弟子"));
+
+        let at_second = context_summary(&conn, "p1", Some("c2")).unwrap();
+        assert_eq!(
+            at_second,
+            "青云门 | [门派] | 目标:守护北境 | 成员:林舟（掌门）、沈青（弟子）\n青云外门 | [门派] | 隶属:青云门 | 目标:守护北境"
+        );
+        assert!(!context_summary(&conn, "p1", Some("c3")).unwrap().contains("沈青"));
+
+        delete_organization(&conn, &sect.id).unwrap();
+        let remaining = list_organizations(&conn, "p1").unwrap();
+        assert_eq!((remaining.len(), remaining[0].parent_id.clone()), (1, None));
+    }
+}
@@ -25,7 +25,7 @@ fn get_db_path(app: &AppHandle) -> Result<std::path::PathBuf, String> {
     }
 }
 
-fn init_outline_tables(conn: &rusqlite::Connection) -> Result<(), String> {
+pub(crate) fn init_outline_tables(conn: &rusqlite::Connection) -> Result<(), String> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS outline_nodes (
             id TEXT PRIMARY KEY,
@@ -45,7 +45,23 @@ fn init_outline_tables(conn: &rusqlite::Connection) -> Result<(), String> {
         )",
         [],
     ).map_err(|e| e.to_string())?;
-    
+
+    // 由该节点生成的章节，与 chapter_missions.beat_id 互相指向
+    conn.execute("ALTER TABLE outline_nodes ADD COLUMN chapter_id TEXT", []).ok();
+
+    // 大纲节点与情节点的一一对应，用于双向同步
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS outline_plot_links (
+            outline_node_id TEXT PRIMARY KEY,
+            plot_point_id TEXT NOT NULL UNIQUE,
+            project_id TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (outline_node_id) REFERENCES outline_nodes(id) ON DELETE CASCADE,
+            FOREIGN KEY (plot_point_id) REFERENCES plot_points(id) ON DELETE CASCADE
+        )",
+        [],
+    ).map_err(|e| e.to_string())?;
+
     Ok(())
 }
 
@@ -248,6 +264,84 @@ pub async fn delete_outline_node(app: AppHandle, id: String) -> Result<(), Strin
     Ok(())
 }
 
+/// 移动节点并重新编号原位置和新位置的同级节点，返回项目的全部节点
+#[tauri::command]
+pub async fn move_outline_node(app: AppHandle, node_id: String, new_parent: Option<String>, new_index: usize) -> Result<Vec<OutlineNode>, String> {
+    let logger = Logger::new().with_feature("outline");
+    log_command_start(&logger, "move_outline_node", &node_id);
+
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+
+    let project_id = crate::outline::reorder::move_node(&conn, &node_id, new_parent.as_deref(), new_index)
+        .inspect_err(|e| log_command_error(&logger, "move_outline_node", e))?;
+
+    log_command_success(&logger, "move_outline_node", &node_id);
+    get_outline_nodes(app, project_id).await
+}
+
+/// 各大纲节点的目标字数和已写字数，供节奏看板使用
+#[tauri::command]
+pub async fn get_outline_progress(app: AppHandle, project_id: String) -> Result<crate::outline::progress::OutlineProgress, String> {
+    let logger = Logger::new().with_feature("outline");
+    log_command_start(&logger, "get_outline_progress", &project_id);
+
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    let progress = crate::outline::progress::outline_progress(&conn, &project_id)?;
+
+    log_command_success(&logger, "get_outline_progress", &format!("{}/{} words", progress.total_actual, progress.total_target));
+    Ok(progress)
+}
+
+/// 把情节点树提升到大纲，已关联的节点只更新内容；`plot_point_ids` 为空时同步全部情节点
+#[tauri::command]
+pub async fn promote_plot_points_to_outline(
+    app: AppHandle,
+    project_id: String,
+    plot_point_ids: Option<Vec<String>>,
+) -> Result<crate::outline::plot_sync::PlotSyncResult, String> {
+    let logger = Logger::new().with_feature("outline");
+    log_command_start(&logger, "promote_plot_points_to_outline", &project_id);
+
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    let result = crate::outline::plot_sync::promote_plot_points(&conn, &project_id, &plot_point_ids.unwrap_or_default())
+        .inspect_err(|e| log_command_error(&logger, "promote_plot_points_to_outline", e))?;
+
+    log_command_success(&logger, "promote_plot_points_to_outline", &format!("{} created, {} updated", result.created, result.updated));
+    Ok(result)
+}
+
+/// 把大纲的修改推回已关联的情节点
+#[tauri::command]
+pub async fn push_outline_to_plot_points(app: AppHandle, project_id: String) -> Result<crate::outline::plot_sync::PlotSyncResult, String> {
+    let logger = Logger::new().with_feature("outline");
+    log_command_start(&logger, "push_outline_to_plot_points", &project_id);
+
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    let result = crate::outline::plot_sync::push_outline_to_plot_points(&conn, &project_id)
+        .inspect_err(|e| log_command_error(&logger, "push_outline_to_plot_points", e))?;
+
+    log_command_success(&logger, "push_outline_to_plot_points", &format!("{} created, {} updated", result.created, result.updated));
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn link_outline_node_to_plot_point(app: AppHandle, outline_node_id: String, plot_point_id: String) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    crate::outline::plot_sync::link(&conn, &outline_node_id, &plot_point_id)
+}
+
+#[tauri::command]
+pub async fn unlink_outline_node_from_plot_point(app: AppHandle, outline_node_id: String) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    crate::outline::plot_sync::unlink(&conn, &outline_node_id)
+}
+
 #[tauri::command]
 pub async fn get_outline_templates() -> Result<Vec<OutlineTemplate>, String> {
     Ok(get_default_templates())
@@ -285,10 +379,14 @@ pub async fn apply_outline_template(app: AppHandle, project_id: String, template
                 OutlineNodeType::Scene => "scene",
                 OutlineNodeType::Beat => "beat",
             };
+            let word_count_target = node.word_count_range.map(|r| r.midpoint());
+            let metadata = node.word_count_range.map(|r| {
+                serde_json::json!({ "word_count_min": r.min, "word_count_max": r.max }).to_string()
+            });
 
             conn.execute(
-                "INSERT INTO outline_nodes (id, project_id, parent_id, title, content, node_type, sort_order, status, word_count_target, word_count_actual, created_at, updated_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'planned', NULL, 0, ?8, ?9)",
+                "INSERT INTO outline_nodes (id, project_id, parent_id, title, content, node_type, sort_order, status, word_count_target, word_count_actual, metadata, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'planned', ?8, 0, ?9, ?10, ?11)",
                 params![
                     &id,
                     project_id,
@@ -297,6 +395,8 @@ pub async fn apply_outline_template(app: AppHandle, project_id: String, template
                     &node.description,
                     node_type_str,
                     *sort_order,
+                    word_count_target,
+                    &metadata,
                     now.to_rfc3339(),
                     now.to_rfc3339()
                 ],
@@ -311,9 +411,9 @@ pub async fn apply_outline_template(app: AppHandle, project_id: String, template
                 node_type: node.node_type.clone(),
                 sort_order: *sort_order,
                 status: OutlineNodeStatus::Planned,
-                word_count_target: None,
+                word_count_target,
                 word_count_actual: 0,
-                metadata: None,
+                metadata,
                 created_at: now,
                 updated_at: now,
             });
@@ -333,6 +433,114 @@ pub async fn apply_outline_template(app: AppHandle, project_id: String, template
     Ok(created_nodes)
 }
 
+/// 把选中的大纲叶子节点一次性生成章节草稿和导演脚本
+#[tauri::command]
+pub async fn scaffold_chapters_from_outline(app: AppHandle, project_id: String, node_ids: Vec<String>) -> Result<Vec<ScaffoldedChapter>, String> {
+    let logger = Logger::new().with_feature("outline");
+    log_command_start(&logger, "scaffold_chapters_from_outline", &format!("{} nodes", node_ids.len()));
+
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+
+    let chapters = crate::outline::scaffold::scaffold_chapters(&conn, &project_id, &node_ids)
+        .inspect_err(|e| log_command_error(&logger, "scaffold_chapters_from_outline", e))?;
+
+    log_command_success(&logger, "scaffold_chapters_from_outline", &format!("{} chapters created", chapters.len()));
+    Ok(chapters)
+}
+
+/// 对比大纲和正文，找出未写的节点、大纲外的章节和顺序颠倒的地方
+#[tauri::command]
+pub async fn check_outline_drift(
+    app: AppHandle,
+    ai_service: tauri::State<'_, Arc<RwLock<AIService>>>,
+    project_id: String,
+    model_id: Option<String>,
+) -> Result<crate::outline::drift::OutlineDriftReport, String> {
+    let logger = Logger::new().with_feature("outline");
+    log_command_start(&logger, "check_outline_drift", &project_id);
+
+    let (beats, chapters) = {
+        let db_path = get_db_path(&app)?;
+        let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+        (crate::outline::drift::load_beats(&conn, &project_id)?, crate::outline::drift::load_chapters(&conn, &project_id)?)
+    };
+
+    let service = ai_service.read().await;
+    let model_id = model_id.unwrap_or_else(|| "glm-4-flash".to_string());
+    let report = crate::outline::drift::check_with_ai(&service, &model_id, &beats, &chapters)
+        .await
+        .inspect_err(|e| log_command_error(&logger, "check_outline_drift", e))?;
+
+    log_command_success(
+        &logger,
+        "check_outline_drift",
+        &format!("{} unwritten, {} uncovered, {} out of order", report.unwritten_beats.len(), report.uncovered_chapters.len(), report.order_mismatches.len()),
+    );
+    Ok(report)
+}
+
+/// 把大纲导出为 OPML 或 Markdown，`output_path` 为空时写到应用数据目录的 exports 下，返回文件路径
+#[tauri::command]
+pub async fn export_outline(app: AppHandle, project_id: String, format: String, output_path: Option<String>) -> Result<String, String> {
+    let logger = Logger::new().with_feature("outline");
+    log_command_start(&logger, "export_outline", &format!("project: {}, format: {}", project_id, format));
+
+    let file_format = crate::outline::interchange::OutlineFileFormat::from_str(&format)
+        .ok_or_else(|| format!("不支持的大纲导出格式: {}", format))?;
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    let title: String = conn
+        .query_row("SELECT name FROM projects WHERE id = ?1", params![&project_id], |row| row.get(0))
+        .map_err(|e| format!("Project not found: {}", e))?;
+    let tree = crate::outline::interchange::load_tree(&conn, &project_id)?;
+    let content = match file_format {
+        crate::outline::interchange::OutlineFileFormat::Opml => crate::outline::interchange::to_opml(&title, &tree),
+        crate::outline::interchange::OutlineFileFormat::Markdown => crate::outline::interchange::to_markdown(&title, &tree),
+    };
+
+    let output_path = match output_path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => {
+            let export_dir = app.path().app_data_dir().map_err(|e| e.to_string())?.join("exports");
+            std::fs::create_dir_all(&export_dir).map_err(|e| e.to_string())?;
+            export_dir.join(format!("outline_{}{}", Utc::now().format("%Y%m%d_%H%M%S"), file_format.extension()))
+        }
+    };
+    std::fs::write(&output_path, content).map_err(|e| {
+        let message = format!("无法保存文件: {}", e);
+        log_command_error(&logger, "export_outline", &message);
+        message
+    })?;
+
+    log_command_success(&logger, "export_outline", &output_path.to_string_lossy());
+    Ok(output_path.to_string_lossy().to_string())
+}
+
+/// 从 OPML 或 Markdown 文件导入大纲，追加到现有大纲之后；格式按扩展名判断
+#[tauri::command]
+pub async fn import_outline(app: AppHandle, project_id: String, file_path: String) -> Result<Vec<OutlineNode>, String> {
+    let logger = Logger::new().with_feature("outline");
+    log_command_start(&logger, "import_outline", &file_path);
+
+    let extension = std::path::Path::new(&file_path).extension().and_then(|e| e.to_str()).unwrap_or_default().to_string();
+    let file_format = crate::outline::interchange::OutlineFileFormat::from_str(&extension)
+        .ok_or_else(|| format!("不支持的大纲文件格式: {}", extension))?;
+    let text = std::fs::read_to_string(&file_path).map_err(|e| format!("无法读取文件: {}", e))?;
+    let tree = match file_format {
+        crate::outline::interchange::OutlineFileFormat::Opml => crate::outline::interchange::parse_opml(&text)?,
+        crate::outline::interchange::OutlineFileFormat::Markdown => crate::outline::interchange::parse_markdown(&text)?,
+    };
+
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    let count = crate::outline::interchange::insert_tree(&conn, &project_id, &tree)
+        .inspect_err(|e| log_command_error(&logger, "import_outline", e))?;
+
+    log_command_success(&logger, "import_outline", &format!("{} nodes imported", count));
+    get_outline_nodes(app, project_id).await
+}
+
 #[tauri::command]
 pub async fn generate_outline_with_ai(
     app: AppHandle,
@@ -400,6 +608,46 @@ pub async fn generate_outline_with_ai(
     Ok(outline)
 }
 
+/// 让 AI 结合上下文把一个节点展开为 `depth` 层子节点，接在已有子节点之后，返回新建的节点
+#[tauri::command]
+pub async fn expand_outline_node(
+    app: AppHandle,
+    ai_service: tauri::State<'_, Arc<RwLock<AIService>>>,
+    node_id: String,
+    depth: Option<usize>,
+    style: Option<String>,
+) -> Result<Vec<OutlineNode>, String> {
+    let logger = Logger::new().with_feature("outline");
+    log_command_start(&logger, "expand_outline_node", &node_id);
+
+    let depth = depth.unwrap_or(1).clamp(1, crate::outline::expand::MAX_DEPTH);
+    let context = {
+        let db_path = get_db_path(&app)?;
+        let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+        crate::outline::expand::load_context(&conn, &node_id)?
+    };
+
+    let service = ai_service.read().await;
+    let system_prompt = "你是一位专业的小说大纲设计师，擅长把情节拆分为层次清晰的子节点。只返回 JSON 数组，不要包含markdown代码块标记。";
+    let prompt = crate::outline::expand::build_prompt(&context, depth, style.as_deref());
+    let response = service.complete("glm-4-flash", system_prompt, &prompt).await.map_err(|e| {
+        log_command_error(&logger, "expand_outline_node", &e);
+        format!("AI generation failed: {}", e)
+    })?;
+    let children = crate::outline::expand::parse_children(&response, &context.node_type, depth);
+    if children.is_empty() {
+        return Err("AI 没有返回可用的子节点".to_string());
+    }
+
+    let db_path = get_db_path(&app)?;
+    let conn = get_connection(&db_path).map_err(|e| e.to_string())?;
+    let ids = crate::outline::expand::insert_children(&conn, &context, &children)?;
+
+    log_command_success(&logger, "expand_outline_node", &format!("{} nodes created", ids.len()));
+    let nodes = get_outline_nodes(app, context.project_id).await?;
+    Ok(nodes.into_iter().filter(|n| ids.contains(&n.id)).collect())
+}
+
 #[tauri::command]
 pub async fn save_generated_outline(app: AppHandle, project_id: String, outline: OutlineGenerationResult) -> Result<Vec<OutlineNode>, String> {
     let logger = Logger::new().with_feature("outline");
@@ -0,0 +1,307 @@
+use crate::outline::commands::init_outline_tables;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// 每章送给 AI 的摘要字数上限
+const SUMMARY_CHARS: usize = 300;
+/// 每个大纲节点送给 AI 的描述字数上限
+const BEAT_CHARS: usize = 200;
+
+/// 大纲中应当写成正文的一项：已关联章节的节点，或未被关联节点覆盖的最底层节点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedBeat {
+    pub outline_node_id: String,
+    pub title: String,
+    pub description: String,
+    pub node_type: String,
+    /// 由大纲生成的章节，见 `outline_nodes.chapter_id`
+    pub linked_chapter_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManuscriptChapter {
+    pub chapter_id: String,
+    pub title: String,
+    pub sort_order: i32,
+    /// 章节摘要，没有摘要时取正文开头
+    pub summary: String,
+    pub written: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BeatRef {
+    pub outline_node_id: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterRef {
+    pub chapter_id: String,
+    pub title: String,
+}
+
+/// 大纲在前的节点反而写在了靠后的章节里
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderMismatch {
+    pub beat: BeatRef,
+    pub chapter: ChapterRef,
+    /// 大纲中排在 `beat` 之后、却写在更前面章节里的节点
+    pub later_beat: BeatRef,
+    pub later_chapter: ChapterRef,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OutlineDriftReport {
+    /// 已在正文中找到对应章节的大纲节点数
+    pub matched_beats: usize,
+    pub unwritten_beats: Vec<BeatRef>,
+    pub uncovered_chapters: Vec<ChapterRef>,
+    pub order_mismatches: Vec<OrderMismatch>,
+}
+
+struct OutlineRow {
+    id: String,
+    parent_id: Option<String>,
+    title: String,
+    content: String,
+    node_type: String,
+    sort_order: i32,
+    chapter_id: Option<String>,
+}
+
+fn truncate(text: &str, limit: usize) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(limit) {
+        Some((i, _)) => format!("{}……", &text[..i]),
+        None => text.to_string(),
+    }
+}
+
+/// 按大纲顺序取出应当写成正文的节点；节点关联了章节时其子节点不再单列
+pub fn load_beats(conn: &Connection, project_id: &str) -> Result<Vec<PlannedBeat>, String> {
+    init_outline_tables(conn)?;
+    let rows: Vec<OutlineRow> = conn
+        .prepare(
+            "SELECT n.id, n.parent_id, n.title, COALESCE(n.content, ''), n.node_type, n.sort_order,
+                    (SELECT c.id FROM chapters c WHERE c.id = n.chapter_id)
+             FROM outline_nodes n WHERE n.project_id = ? AND n.status != 'skipped'",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| {
+            Ok(OutlineRow {
+                id: row.get(0)?,
+                parent_id: row.get(1)?,
+                title: row.get(2)?,
+                content: row.get(3)?,
+                node_type: row.get(4)?,
+                sort_order: row.get(5)?,
+                chapter_id: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut children: HashMap<Option<&str>, Vec<&OutlineRow>> = HashMap::new();
+    for row in &rows {
+        children.entry(row.parent_id.as_deref()).or_default().push(row);
+    }
+    for list in children.values_mut() {
+        list.sort_by_key(|r| r.sort_order);
+    }
+    let mut beats = Vec::new();
+    let mut stack: Vec<&OutlineRow> = children.get(&None).map(|l| l.iter().rev().copied().collect()).unwrap_or_default();
+    while let Some(row) = stack.pop() {
+        let kids = children.get(&Some(row.id.as_str()));
+        if row.chapter_id.is_some() || (kids.is_none() && row.node_type != "arc") {
+            beats.push(PlannedBeat {
+                outline_node_id: row.id.clone(),
+                title: row.title.clone(),
+                description: row.content.trim().to_string(),
+                node_type: row.node_type.clone(),
+                linked_chapter_id: row.chapter_id.clone(),
+            });
+        } else if let Some(kids) = kids {
+            stack.extend(kids.iter().rev());
+        }
+    }
+    Ok(beats)
+}
+
+pub fn load_chapters(conn: &Connection, project_id: &str) -> Result<Vec<ManuscriptChapter>, String> {
+    conn.prepare("SELECT id, title, sort_order, COALESCE(summary, ''), content FROM chapters WHERE project_id = ? ORDER BY sort_order, created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| {
+            let summary: String = row.get(3)?;
+            let content: String = row.get(4)?;
+            Ok(ManuscriptChapter {
+                chapter_id: row.get(0)?,
+                title: row.get(1)?,
+                sort_order: row.get(2)?,
+                written: !content.trim().is_empty(),
+                summary: truncate(if summary.trim().is_empty() { &content } else { &summary }, SUMMARY_CHARS),
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 通过大纲生成章节时留下的关联直接匹配，返回 (节点下标, 章节下标)
+pub fn linked_matches(beats: &[PlannedBeat], chapters: &[ManuscriptChapter]) -> Vec<(usize, usize)> {
+    beats
+        .iter()
+        .enumerate()
+        .filter_map(|(b, beat)| {
+            let chapter_id = beat.linked_chapter_id.as_deref()?;
+            Some((b, chapters.iter().position(|c| c.chapter_id == chapter_id)?))
+        })
+        .collect()
+}
+
+/// 未关联的节点和已写章节，需要 AI 匹配；两边有一边为空时返回 None
+pub fn build_match_prompt(beats: &[PlannedBeat], chapters: &[ManuscriptChapter], linked: &[(usize, usize)]) -> Option<(String, Vec<usize>, Vec<usize>)> {
+    let beat_ids: Vec<usize> = (0..beats.len()).filter(|b| !linked.iter().any(|(lb, _)| lb == b)).collect();
+    let chapter_ids: Vec<usize> = (0..chapters.len()).filter(|&c| chapters[c].written && !linked.iter().any(|(_, lc)| *lc == c)).collect();
+    if beat_ids.is_empty() || chapter_ids.is_empty() {
+        return None;
+    }
+    let listed_beats: Vec<String> = beat_ids
+        .iter()
+        .enumerate()
+        .map(|(n, &b)| format!("[B{}] {}：{}", n + 1, beats[b].title, truncate(&beats[b].description, BEAT_CHARS)))
+        .collect();
+    let listed_chapters: Vec<String> = chapter_ids
+        .iter()
+        .enumerate()
+        .map(|(n, &c)| format!("[C{}] {}：{}", n + 1, chapters[c].title, chapters[c].summary))
+        .collect();
+    let prompt = format!(
+        "下面是一部小说的大纲节点和已写章节的摘要。判断每个大纲节点的情节写在了哪些章节里。\n\n\
+         大纲节点：\n{}\n\n章节：\n{}\n\n\
+         返回 JSON 数组：[{{\"beat\": \"B1\", \"chapters\": [\"C2\"]}}]。\
+         只列出确实写到了的节点，一个节点可以对应多个章节，没有写到的节点不要列出。",
+        listed_beats.join("\n"),
+        listed_chapters.join("\n")
+    );
+    Some((prompt, beat_ids, chapter_ids))
+}
+
+/// 解析 AI 的匹配结果，编号换回节点和章节下标
+pub fn parse_matches(response: &str, beat_ids: &[usize], chapter_ids: &[usize]) -> Vec<(usize, usize)> {
+    let start = response.find('[').unwrap_or(0);
+    let end = response.rfind(']').map(|i| i + 1).unwrap_or(response.len());
+    let items: Vec<serde_json::Value> = serde_json::from_str(&response[start..end]).unwrap_or_default();
+    let code = |value: &serde_json::Value, prefix: char, ids: &[usize]| {
+        value.as_str()?.trim().trim_start_matches([prefix, prefix.to_ascii_lowercase()]).parse::<usize>().ok().and_then(|n| ids.get(n.checked_sub(1)?).copied())
+    };
+
+    let mut matches = Vec::new();
+    for item in &items {
+        let Some(beat) = item.get("beat").and_then(|v| code(v, 'B', beat_ids)) else { continue };
+        for chapter in item.get("chapters").and_then(|v| v.as_array()).into_iter().flatten() {
+            if let Some(chapter) = code(chapter, 'C', chapter_ids) {
+                if !matches.contains(&(beat, chapter)) {
+                    matches.push((beat, chapter));
+                }
+            }
+        }
+    }
+    matches
+}
+
+/// 根据匹配结果汇总未写的节点、大纲外的章节和顺序颠倒的地方
+pub fn build_report(beats: &[PlannedBeat], chapters: &[ManuscriptChapter], matches: &[(usize, usize)]) -> OutlineDriftReport {
+    let beat_ref = |b: usize| BeatRef { outline_node_id: beats[b].outline_node_id.clone(), title: beats[b].title.clone() };
+    let chapter_ref = |c: usize| ChapterRef { chapter_id: chapters[c].chapter_id.clone(), title: chapters[c].title.clone() };
+    let written: Vec<(usize, usize)> = matches.iter().copied().filter(|&(_, c)| chapters[c].written).collect();
+
+    let mut report = OutlineDriftReport::default();
+    // 每个节点最早写到的章节
+    let mut first_chapter: Vec<Option<usize>> = vec![None; beats.len()];
+    for &(b, c) in &written {
+        if first_chapter[b].is_none_or(|f| chapters[c].sort_order < chapters[f].sort_order) {
+            first_chapter[b] = Some(c);
+        }
+    }
+    for (b, first) in first_chapter.iter().enumerate() {
+        if first.is_none() {
+            report.unwritten_beats.push(beat_ref(b));
+        }
+    }
+    report.matched_beats = beats.len() - report.unwritten_beats.len();
+
+    let covered: HashSet<usize> = matches.iter().map(|&(_, c)| c).collect();
+    report.uncovered_chapters = (0..chapters.len()).filter(|c| chapters[*c].written && !covered.contains(c)).map(chapter_ref).collect();
+
+    // 与大纲中后面最早写到的节点比较，只报告每个节点最明显的一处颠倒
+    for (b, first) in first_chapter.iter().enumerate() {
+        let Some(c) = *first else { continue };
+        let later = (b + 1..beats.len())
+            .filter_map(|l| first_chapter[l].map(|lc| (l, lc)))
+            .filter(|&(_, lc)| chapters[lc].sort_order < chapters[c].sort_order)
+            .min_by_key(|&(_, lc)| chapters[lc].sort_order);
+        if let Some((l, lc)) = later {
+            report.order_mismatches.push(OrderMismatch { beat: beat_ref(b), chapter: chapter_ref(c), later_beat: beat_ref(l), later_chapter: chapter_ref(lc) });
+        }
+    }
+    report
+}
+
+/// 对比大纲和正文；已关联的节点直接匹配，其余交给 AI
+pub async fn check_with_ai(service: &crate::ai::AIService, model_id: &str, beats: &[PlannedBeat], chapters: &[ManuscriptChapter]) -> Result<OutlineDriftReport, String> {
+    let mut matches = linked_matches(beats, chapters);
+    if let Some((prompt, beat_ids, chapter_ids)) = build_match_prompt(beats, chapters, &matches) {
+        let system_prompt = "你是一位细心的小说编辑，负责核对正文是否按大纲展开。只返回 JSON 数组，不要包含markdown代码块标记。";
+        let response = service.complete(model_id, system_prompt, &prompt).await?;
+        matches.extend(parse_matches(&response, &beat_ids, &chapter_ids));
+    }
+    Ok(build_report(beats, chapters, &matches))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drift_report() {
+        let (_dir, conn) = crate::test_support::project_db();
+        init_outline_tables(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO chapters (id, project_id, title, content, summary, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '林舟在雨夜拜别师父。', NULL, 1, 't0', 't0'),
+                 ('c2', 'p1', '第二章', '……', '林舟途中遇袭。', 2, 't0', 't0'),
+                 ('c3', 'p1', '第三章', '……', '沈青的往事。', 3, 't0', 't0'),
+                 ('c4', 'p1', '第四章', '', NULL, 4, 't0', 't0');
+             INSERT INTO outline_nodes (id, project_id, parent_id, title, content, node_type, sort_order, status, chapter_id, created_at, updated_at) VALUES
+                 ('a1', 'p1', NULL, '第一卷', '', 'arc', 0, 'planned', NULL, 't0', 't0'),
+                 ('n1', 'p1', 'a1', '遇袭', '', 'chapter', 0, 'planned', NULL, 't0', 't0'),
+                 ('n2', 'p1', 'a1', '拜别', '', 'chapter', 1, 'planned', 'c1', 't0', 't0'),
+                 ('b1', 'p1', 'n2', '叩首', '', 'beat', 0, 'planned', NULL, 't0', 't0'),
+                 ('n3', 'p1', 'a1', '入京', '', 'chapter', 2, 'planned', 'c4', 't0', 't0'),
+                 ('n4', 'p1', 'a1', '删掉的', '', 'chapter', 3, 'skipped', NULL, 't0', 't0');",
+        )
+        .unwrap();
+
+        let beats = load_beats(&conn, "p1").unwrap();
+        assert_eq!(beats.iter().map(|b| b.outline_node_id.as_str()).collect::<Vec<_>>(), vec!["n1", "n2", "n3"]);
+        let chapters = load_chapters(&conn, "p1").unwrap();
+        assert_eq!(chapters[0].summary, "林舟在雨夜拜别师父。");
+
+        let linked = linked_matches(&beats, &chapters);
+        assert_eq!(linked, vec![(1, 0), (2, 3)]);
+        let (prompt, beat_ids, chapter_ids) = build_match_prompt(&beats, &chapters, &linked).unwrap();
+        assert!(prompt.contains("[B1] 遇袭") && prompt.contains("[C2] 第三章：沈青的往事。"));
+        assert_eq!((beat_ids, chapter_ids.clone()), (vec![0], vec![1, 2]));
+
+        let mut matches = linked;
+        matches.extend(parse_matches(r#"[{"beat": "B1", "chapters": ["C1", "C9"]}, {"beat": "B5", "chapters": ["C2"]}]"#, &[0], &chapter_ids));
+        let report = build_report(&beats, &chapters, &matches);
+        assert_eq!(report.matched_beats, 2);
+        assert_eq!(report.unwritten_beats.iter().map(|b| b.title.as_str()).collect::<Vec<_>>(), vec!["入京"]);
+        assert_eq!(report.uncovered_chapters.iter().map(|c| c.chapter_id.as_str()).collect::<Vec<_>>(), vec!["c3"]);
+        assert_eq!(report.order_mismatches.len(), 1);
+        assert_eq!((report.order_mismatches[0].beat.title.as_str(), report.order_mismatches[0].later_beat.title.as_str()), ("遇袭", "拜别"));
+    }
+}
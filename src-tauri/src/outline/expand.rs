@@ -0,0 +1,267 @@
+use crate::outline::commands::init_outline_tables;
+use crate::outline::interchange::{type_name, OutlineTreeNode};
+use crate::outline::types::OutlineNodeType;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use uuid::Uuid;
+
+/// 最多展开的层数
+pub const MAX_DEPTH: usize = 3;
+/// 每个节点最多接受的子节点数，多出的丢弃
+const MAX_CHILDREN: usize = 12;
+const TITLE_CHARS: usize = 40;
+
+/// 展开节点时送给 AI 的上下文
+#[derive(Debug, Clone)]
+pub struct ExpandContext {
+    pub project_id: String,
+    pub node_id: String,
+    pub title: String,
+    pub content: String,
+    pub node_type: OutlineNodeType,
+    /// 从根到父节点的标题
+    pub ancestors: Vec<String>,
+    pub previous_sibling: Option<(String, String)>,
+    pub next_sibling: Option<(String, String)>,
+    pub existing_children: Vec<String>,
+}
+
+fn parse_type(name: &str) -> OutlineNodeType {
+    match name {
+        "arc" => OutlineNodeType::Arc,
+        "chapter" => OutlineNodeType::Chapter,
+        "beat" => OutlineNodeType::Beat,
+        _ => OutlineNodeType::Scene,
+    }
+}
+
+/// 子节点的类型比父节点低一级，节拍之下仍为节拍
+fn child_type(parent: &OutlineNodeType) -> OutlineNodeType {
+    match parent {
+        OutlineNodeType::Arc => OutlineNodeType::Chapter,
+        OutlineNodeType::Chapter => OutlineNodeType::Scene,
+        OutlineNodeType::Scene | OutlineNodeType::Beat => OutlineNodeType::Beat,
+    }
+}
+
+fn type_label(node_type: &OutlineNodeType) -> &'static str {
+    match node_type {
+        OutlineNodeType::Arc => "故事弧",
+        OutlineNodeType::Chapter => "章节",
+        OutlineNodeType::Scene => "场景",
+        OutlineNodeType::Beat => "节拍",
+    }
+}
+
+pub fn load_context(conn: &Connection, node_id: &str) -> Result<ExpandContext, String> {
+    init_outline_tables(conn)?;
+    let (project_id, parent_id, title, content, node_type, sort_order): (String, Option<String>, String, String, String, i32) = conn
+        .query_row(
+            "SELECT project_id, parent_id, title, COALESCE(content, ''), node_type, sort_order FROM outline_nodes WHERE id = ?",
+            params![node_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "大纲节点不存在".to_string())?;
+
+    let mut ancestors = Vec::new();
+    let mut current = parent_id.clone();
+    while let Some(id) = current {
+        let (title, parent): (String, Option<String>) = conn
+            .query_row("SELECT title, parent_id FROM outline_nodes WHERE id = ?", params![id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        ancestors.insert(0, title);
+        current = parent;
+    }
+
+    let sibling = |order: &str| -> Result<Option<(String, String)>, String> {
+        conn.query_row(
+            &format!(
+                "SELECT title, COALESCE(content, '') FROM outline_nodes WHERE project_id = ?1 AND parent_id IS ?2 AND id != ?3 AND sort_order {} ?4
+                 ORDER BY sort_order {} LIMIT 1",
+                order,
+                if order == "<" { "DESC" } else { "ASC" }
+            ),
+            params![project_id, parent_id, node_id, sort_order],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())
+    };
+    let previous_sibling = sibling("<")?;
+    let next_sibling = sibling(">")?;
+
+    let existing_children = conn
+        .prepare("SELECT title FROM outline_nodes WHERE parent_id = ? ORDER BY sort_order")
+        .map_err(|e| e.to_string())?
+        .query_map(params![node_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(ExpandContext {
+        project_id,
+        node_id: node_id.to_string(),
+        title,
+        content,
+        node_type: parse_type(&node_type),
+        ancestors,
+        previous_sibling,
+        next_sibling,
+        existing_children,
+    })
+}
+
+pub fn build_prompt(context: &ExpandContext, depth: usize, style: Option<&str>) -> String {
+    let mut sections = Vec::new();
+    if !context.ancestors.is_empty() {
+        sections.push(format!("所在位置：{}", context.ancestors.join(" > ")));
+    }
+    if let Some((title, content)) = &context.previous_sibling {
+        sections.push(format!("前一个节点：{}\n{}", title, content.trim()));
+    }
+    sections.push(format!("要展开的{}：{}\n{}", type_label(&context.node_type), context.title, context.content.trim()));
+    if let Some((title, content)) = &context.next_sibling {
+        sections.push(format!("后一个节点：{}\n{}", title, content.trim()));
+    }
+    if !context.existing_children.is_empty() {
+        sections.push(format!("已有的子节点（新内容接在它们之后，不要重复）：{}", context.existing_children.join("、")));
+    }
+
+    let mut levels = Vec::new();
+    let mut node_type = context.node_type.clone();
+    for _ in 0..depth {
+        node_type = child_type(&node_type);
+        levels.push(type_label(&node_type));
+    }
+    let example = if depth > 1 {
+        "[{\"title\": \"标题\", \"description\": \"一两句话的内容\", \"children\": [{\"title\": \"标题\", \"description\": \"内容\", \"children\": []}]}]"
+    } else {
+        "[{\"title\": \"标题\", \"description\": \"一两句话的内容\"}]"
+    };
+    format!(
+        "{}\n\n请把这个节点展开为{}层子节点（依次为{}），承接前后节点，不要写到后一个节点的内容。{}\
+         按发生顺序返回 JSON 数组：{}。标题不超过{}个字，每层 2 到 8 个节点。",
+        sections.join("\n\n"),
+        depth,
+        levels.join("、"),
+        style.filter(|s| !s.trim().is_empty()).map(|s| format!("风格要求：{}。", s.trim())).unwrap_or_default(),
+        example,
+        TITLE_CHARS / 2
+    )
+}
+
+/// 解析 AI 返回的子节点树：丢弃没有标题的节点、重复的标题和超出层数的部分，过长的标题截断
+pub fn parse_children(response: &str, parent_type: &OutlineNodeType, depth: usize) -> Vec<OutlineTreeNode> {
+    let start = response.find('[').unwrap_or(0);
+    let end = response.rfind(']').map(|i| i + 1).unwrap_or(response.len());
+    let items: Vec<serde_json::Value> = serde_json::from_str(&response[start..end]).unwrap_or_default();
+
+    fn convert(items: &[serde_json::Value], parent_type: &OutlineNodeType, depth: usize) -> Vec<OutlineTreeNode> {
+        let node_type = child_type(parent_type);
+        let mut nodes: Vec<OutlineTreeNode> = Vec::new();
+        for item in items {
+            let field = |key: &str| item.get(key).and_then(|v| v.as_str()).unwrap_or_default().trim().to_string();
+            let title: String = field("title").chars().take(TITLE_CHARS).collect();
+            if title.is_empty() || nodes.iter().any(|n| n.title == title) {
+                continue;
+            }
+            let children = match item.get("children").and_then(|v| v.as_array()) {
+                Some(children) if depth > 1 => convert(children, &node_type, depth - 1),
+                _ => Vec::new(),
+            };
+            nodes.push(OutlineTreeNode { title, content: field("description"), node_type: node_type.clone(), word_count_target: None, children });
+            if nodes.len() == MAX_CHILDREN {
+                break;
+            }
+        }
+        nodes
+    }
+    convert(&items, parent_type, depth.clamp(1, MAX_DEPTH))
+}
+
+/// 把展开结果接在节点已有的子节点之后，返回新建的节点 id（先序）
+pub fn insert_children(conn: &Connection, context: &ExpandContext, children: &[OutlineTreeNode]) -> Result<Vec<String>, String> {
+    let first_sort_order: i32 = conn
+        .query_row("SELECT COALESCE(MAX(sort_order) + 1, 0) FROM outline_nodes WHERE parent_id = ?", params![context.node_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    fn insert(
+        tx: &rusqlite::Transaction,
+        project_id: &str,
+        parent_id: &str,
+        nodes: &[OutlineTreeNode],
+        first_sort_order: i32,
+        now: &str,
+        ids: &mut Vec<String>,
+    ) -> Result<(), String> {
+        for (index, node) in nodes.iter().enumerate() {
+            let id = Uuid::new_v4().to_string();
+            tx.execute(
+                "INSERT INTO outline_nodes (id, project_id, parent_id, title, content, node_type, sort_order, status, word_count_actual, created_at, updated_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, 'planned', 0, ?, ?)",
+                params![id, project_id, parent_id, node.title, node.content, type_name(&node.node_type), first_sort_order + index as i32, now, now],
+            )
+            .map_err(|e| format!("创建大纲节点失败: {}", e))?;
+            ids.push(id.clone());
+            insert(tx, project_id, &id, &node.children, 0, now, ids)?;
+        }
+        Ok(())
+    }
+    let mut ids = Vec::new();
+    insert(&tx, &context.project_id, &context.node_id, children, first_sort_order, &now, &mut ids)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_context_parse_and_insert() {
+        let (_dir, conn) = crate::test_support::project_db();
+        init_outline_tables(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO outline_nodes (id, project_id, parent_id, title, content, node_type, sort_order, created_at, updated_at) VALUES
+                 ('a1', 'p1', NULL, '第一卷', '', 'arc', 0, 't0', 't0'),
+                 ('n1', 'p1', 'a1', '雨夜', '师门被灭', 'chapter', 0, 't0', 't0'),
+                 ('n2', 'p1', 'a1', '出城', '林舟逃离长安', 'chapter', 1, 't0', 't0'),
+                 ('n3', 'p1', 'a1', '入京', '', 'chapter', 2, 't0', 't0'),
+                 ('s1', 'p1', 'n2', '城门', '', 'scene', 0, 't0', 't0');",
+        )
+        .unwrap();
+
+        let context = load_context(&conn, "n2").unwrap();
+        assert_eq!(context.ancestors, vec!["第一卷"]);
+        assert_eq!(context.previous_sibling.as_ref().map(|s| s.0.as_str()), Some("雨夜"));
+        assert_eq!(context.next_sibling.as_ref().map(|s| s.0.as_str()), Some("入京"));
+        let prompt = build_prompt(&context, 2, Some("悬疑"));
+        assert!(prompt.contains("依次为场景、节拍") && prompt.contains("风格要求：悬疑") && prompt.contains("已有的子节点"));
+
+        let response = r#"```json
+[{"title": "乔装", "description": "扮成货郎", "children": [{"title": "换衣", "children": [{"title": "超出层数"}]}]},
+ {"title": "乔装", "description": "重复"},
+ {"title": "  ", "description": "没有标题"},
+ {"title": "追兵", "description": "城外遇到追兵"}]
+```"#;
+        let children = parse_children(response, &context.node_type, 2);
+        assert_eq!(children.iter().map(|c| c.title.as_str()).collect::<Vec<_>>(), vec!["乔装", "追兵"]);
+        assert_eq!(children[0].node_type, OutlineNodeType::Scene);
+        assert_eq!(children[0].children[0].node_type, OutlineNodeType::Beat);
+        assert!(children[0].children[0].children.is_empty());
+
+        let ids = insert_children(&conn, &context, &children).unwrap();
+        assert_eq!(ids.len(), 3);
+        let order: Vec<(String, i32)> = conn
+            .prepare("SELECT title, sort_order FROM outline_nodes WHERE parent_id = 'n2' ORDER BY sort_order")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(order, vec![("城门".to_string(), 0), ("乔装".to_string(), 1), ("追兵".to_string(), 2)]);
+    }
+}
@@ -0,0 +1,374 @@
+use crate::outline::commands::init_outline_tables;
+use crate::outline::types::OutlineNodeType;
+use chrono::Utc;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutlineFileFormat {
+    Opml,
+    Markdown,
+}
+
+impl OutlineFileFormat {
+    pub fn from_str(format: &str) -> Option<Self> {
+        match format.to_lowercase().as_str() {
+            "opml" | "xml" => Some(Self::Opml),
+            "md" | "markdown" | "txt" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Opml => ".opml",
+            Self::Markdown => ".md",
+        }
+    }
+}
+
+/// 导入导出用的大纲树
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineTreeNode {
+    pub title: String,
+    pub content: String,
+    pub node_type: OutlineNodeType,
+    pub word_count_target: Option<i32>,
+    pub children: Vec<OutlineTreeNode>,
+}
+
+pub(crate) fn type_name(node_type: &OutlineNodeType) -> &'static str {
+    match node_type {
+        OutlineNodeType::Arc => "arc",
+        OutlineNodeType::Chapter => "chapter",
+        OutlineNodeType::Scene => "scene",
+        OutlineNodeType::Beat => "beat",
+    }
+}
+
+fn parse_type(name: &str) -> Option<OutlineNodeType> {
+    match name {
+        "arc" => Some(OutlineNodeType::Arc),
+        "chapter" => Some(OutlineNodeType::Chapter),
+        "scene" => Some(OutlineNodeType::Scene),
+        "beat" => Some(OutlineNodeType::Beat),
+        _ => None,
+    }
+}
+
+/// 文件没有写明节点类型时按层级推断：一级为故事弧，二级为章节，三级为场景，更深为节拍
+pub(crate) fn type_for_depth(depth: usize) -> OutlineNodeType {
+    match depth {
+        0 => OutlineNodeType::Arc,
+        1 => OutlineNodeType::Chapter,
+        2 => OutlineNodeType::Scene,
+        _ => OutlineNodeType::Beat,
+    }
+}
+
+pub fn load_tree(conn: &Connection, project_id: &str) -> Result<Vec<OutlineTreeNode>, String> {
+    init_outline_tables(conn)?;
+    let rows: Vec<(String, Option<String>, OutlineTreeNode)> = conn
+        .prepare(
+            "SELECT id, parent_id, title, COALESCE(content, ''), node_type, word_count_target FROM outline_nodes
+             WHERE project_id = ? ORDER BY sort_order, created_at",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| {
+            let node_type: String = row.get(4)?;
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                OutlineTreeNode {
+                    title: row.get(2)?,
+                    content: row.get(3)?,
+                    node_type: parse_type(&node_type).unwrap_or(OutlineNodeType::Scene),
+                    word_count_target: row.get(5)?,
+                    children: Vec::new(),
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut children: HashMap<Option<String>, Vec<(String, OutlineTreeNode)>> = HashMap::new();
+    for (id, parent_id, node) in rows {
+        children.entry(parent_id).or_default().push((id, node));
+    }
+    fn build(children: &mut HashMap<Option<String>, Vec<(String, OutlineTreeNode)>>, parent_id: Option<String>) -> Vec<OutlineTreeNode> {
+        let list = children.remove(&parent_id).unwrap_or_default();
+        list.into_iter()
+            .map(|(id, mut node)| {
+                node.children = build(children, Some(id));
+                node
+            })
+            .collect()
+    }
+    Ok(build(&mut children, None))
+}
+
+fn escape_attr(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "&#10;")
+}
+
+/// 导出为 OPML 2.0；正文放在常用的 `_note` 属性，节点类型和目标字数用自定义属性保存
+pub fn to_opml(title: &str, nodes: &[OutlineTreeNode]) -> String {
+    fn write(out: &mut String, nodes: &[OutlineTreeNode], depth: usize) {
+        for node in nodes {
+            let indent = "  ".repeat(depth + 2);
+            out.push_str(&format!("{}<outline text=\"{}\" nodeType=\"{}\"", indent, escape_attr(&node.title), type_name(&node.node_type)));
+            if !node.content.trim().is_empty() {
+                out.push_str(&format!(" _note=\"{}\"", escape_attr(node.content.trim())));
+            }
+            if let Some(target) = node.word_count_target {
+                out.push_str(&format!(" wordCountTarget=\"{}\"", target));
+            }
+            if node.children.is_empty() {
+                out.push_str("/>\n");
+            } else {
+                out.push_str(">\n");
+                write(out, &node.children, depth + 1);
+                out.push_str(&format!("{}</outline>\n", indent));
+            }
+        }
+    }
+    let mut out = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<opml version=\"2.0\">\n  <head>\n    <title>{}</title>\n    <dateCreated>{}</dateCreated>\n  </head>\n  <body>\n",
+        escape_attr(title),
+        Utc::now().to_rfc2822()
+    );
+    write(&mut out, nodes, 0);
+    out.push_str("  </body>\n</opml>\n");
+    out
+}
+
+/// 导出为 Markdown：前六级用标题，更深的层级用缩进列表，正文写在标题下方。
+/// 级数不低于节点类型对应的级数，跳级的节点导入后仍是原来的类型
+pub fn to_markdown(title: &str, nodes: &[OutlineTreeNode]) -> String {
+    fn type_depth(node_type: &OutlineNodeType) -> usize {
+        match node_type {
+            OutlineNodeType::Arc => 0,
+            OutlineNodeType::Chapter => 1,
+            OutlineNodeType::Scene => 2,
+            OutlineNodeType::Beat => 3,
+        }
+    }
+    fn write(out: &mut String, nodes: &[OutlineTreeNode], min_depth: usize) {
+        for node in nodes {
+            let depth = min_depth.max(type_depth(&node.node_type));
+            if depth < 6 {
+                out.push_str(&format!("{} {}\n\n", "#".repeat(depth + 1), node.title.trim()));
+                if !node.content.trim().is_empty() {
+                    out.push_str(&format!("{}\n\n", node.content.trim()));
+                }
+            } else {
+                let indent = "  ".repeat(depth - 6);
+                out.push_str(&format!("{}- {}\n", indent, node.title.trim()));
+                for line in node.content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+                    out.push_str(&format!("{}  {}\n", indent, line));
+                }
+                if node.children.is_empty() && depth == 6 {
+                    out.push('\n');
+                }
+            }
+            write(out, &node.children, depth + 1);
+        }
+    }
+    let mut out = format!("<!-- {} -->\n\n", title.trim());
+    write(&mut out, nodes, 0);
+    out.trim_end().to_string() + "\n"
+}
+
+fn attr(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+}
+
+fn opml_node(e: &BytesStart, depth: usize) -> OutlineTreeNode {
+    OutlineTreeNode {
+        title: attr(e, b"text").or_else(|| attr(e, b"title")).unwrap_or_default().trim().to_string(),
+        content: attr(e, b"_note").unwrap_or_default().trim().to_string(),
+        node_type: attr(e, b"nodeType").and_then(|t| parse_type(&t)).unwrap_or_else(|| type_for_depth(depth)),
+        word_count_target: attr(e, b"wordCountTarget").and_then(|t| t.parse().ok()),
+        children: Vec::new(),
+    }
+}
+
+pub fn parse_opml(xml: &str) -> Result<Vec<OutlineTreeNode>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut roots = Vec::new();
+    let mut stack: Vec<OutlineTreeNode> = Vec::new();
+    let mut in_body = false;
+    fn attach(roots: &mut Vec<OutlineTreeNode>, stack: &mut [OutlineTreeNode], node: OutlineTreeNode) {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => roots.push(node),
+        }
+    }
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(ref e)) if e.local_name().as_ref() == b"body" => in_body = true,
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"body" => in_body = false,
+            Ok(Event::Start(ref e)) if in_body && e.local_name().as_ref() == b"outline" => stack.push(opml_node(e, stack.len())),
+            Ok(Event::Empty(ref e)) if in_body && e.local_name().as_ref() == b"outline" => {
+                let node = opml_node(e, stack.len());
+                attach(&mut roots, &mut stack, node);
+            }
+            Ok(Event::End(ref e)) if in_body && e.local_name().as_ref() == b"outline" => {
+                if let Some(node) = stack.pop() {
+                    attach(&mut roots, &mut stack, node);
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(format!("OPML 解析失败: {}", e)),
+            _ => {}
+        }
+    }
+    if roots.is_empty() {
+        return Err("OPML 文件中没有大纲节点".to_string());
+    }
+    Ok(roots)
+}
+
+/// 解析 Markdown：`#` 标题的级数决定层级，标题下的列表项作为更深一层的子节点（每缩进两格再深一层），
+/// 其余文字作为最近节点的正文
+pub fn parse_markdown(markdown: &str) -> Result<Vec<OutlineTreeNode>, String> {
+    // (层级, 节点)，按出现顺序，最后再组装成树
+    let mut flat: Vec<(usize, OutlineTreeNode)> = Vec::new();
+    let mut heading_depth: Option<usize> = None;
+    let mut in_comment = false;
+    let new_node = |title: &str, depth: usize| OutlineTreeNode {
+        title: title.trim().to_string(),
+        content: String::new(),
+        node_type: type_for_depth(depth),
+        word_count_target: None,
+        children: Vec::new(),
+    };
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if in_comment || trimmed.starts_with("<!--") {
+            in_comment = !trimmed.contains("-->");
+            continue;
+        }
+        let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+            let depth = hashes - 1;
+            heading_depth = Some(depth);
+            flat.push((depth, new_node(&trimmed[hashes..], depth)));
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        let item = ["- ", "* ", "+ "].iter().find_map(|m| trimmed.strip_prefix(m));
+        if let Some(item) = item {
+            let depth = heading_depth.map(|d| d + 1).unwrap_or(0) + indent / 2;
+            flat.push((depth, new_node(item, depth)));
+        } else if !trimmed.is_empty() {
+            if let Some((_, node)) = flat.last_mut() {
+                if !node.content.is_empty() {
+                    node.content.push('\n');
+                }
+                node.content.push_str(trimmed);
+            }
+        }
+    }
+    if flat.is_empty() {
+        return Err("Markdown 文件中没有标题或列表项".to_string());
+    }
+
+    // 跳级的标题挂到最近的上一级节点下
+    let mut roots = Vec::new();
+    let mut stack: Vec<(usize, OutlineTreeNode)> = Vec::new();
+    fn close(roots: &mut Vec<OutlineTreeNode>, stack: &mut Vec<(usize, OutlineTreeNode)>) {
+        let (_, node) = stack.pop().unwrap();
+        match stack.last_mut() {
+            Some((_, parent)) => parent.children.push(node),
+            None => roots.push(node),
+        }
+    }
+    for (depth, node) in flat {
+        while stack.last().is_some_and(|(d, _)| *d >= depth) {
+            close(&mut roots, &mut stack);
+        }
+        stack.push((depth, node));
+    }
+    while !stack.is_empty() {
+        close(&mut roots, &mut stack);
+    }
+    Ok(roots)
+}
+
+/// 把导入的树追加到项目大纲的末尾，返回新建的节点数
+pub fn insert_tree(conn: &Connection, project_id: &str, nodes: &[OutlineTreeNode]) -> Result<usize, String> {
+    init_outline_tables(conn)?;
+    let first_sort_order: i32 = conn
+        .query_row("SELECT COALESCE(MAX(sort_order) + 1, 0) FROM outline_nodes WHERE project_id = ? AND parent_id IS NULL", params![project_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    fn insert(
+        tx: &rusqlite::Transaction,
+        project_id: &str,
+        parent_id: Option<&str>,
+        nodes: &[OutlineTreeNode],
+        first_sort_order: i32,
+        now: &str,
+    ) -> Result<usize, String> {
+        let mut count = 0;
+        for (index, node) in nodes.iter().enumerate() {
+            let id = Uuid::new_v4().to_string();
+            tx.execute(
+                "INSERT INTO outline_nodes (id, project_id, parent_id, title, content, node_type, sort_order, status, word_count_target, word_count_actual, created_at, updated_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, 'planned', ?, 0, ?, ?)",
+                params![id, project_id, parent_id, node.title, node.content, type_name(&node.node_type), first_sort_order + index as i32, node.word_count_target, now, now],
+            )
+            .map_err(|e| format!("创建大纲节点失败: {}", e))?;
+            count += 1 + insert(tx, project_id, Some(&id), &node.children, 0, now)?;
+        }
+        Ok(count)
+    }
+    let count = insert(&tx, project_id, None, nodes, first_sort_order, &now)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opml_and_markdown_round_trip() {
+        let (_dir, conn) = crate::test_support::project_db();
+
+        let markdown = "# 第一卷\n\n林舟的旅程\n第二行\n\n## 雨夜\n\n- 拜别师父\n  - 叩首\n- 出城\n\n# 第二卷\n\n### 跳级的标题\n";
+        let tree = parse_markdown(markdown).unwrap();
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].content, "林舟的旅程\n第二行");
+        let rainy = &tree[0].children[0];
+        assert_eq!((rainy.title.as_str(), &rainy.node_type), ("雨夜", &OutlineNodeType::Chapter));
+        assert_eq!(rainy.children.iter().map(|c| c.title.as_str()).collect::<Vec<_>>(), vec!["拜别师父", "出城"]);
+        assert_eq!(rainy.children[0].children[0].node_type, OutlineNodeType::Beat);
+        assert_eq!((tree[1].children[0].title.as_str(), &tree[1].children[0].node_type), ("跳级的标题", &OutlineNodeType::Scene));
+
+        assert_eq!(insert_tree(&conn, "p1", &tree).unwrap(), 7);
+        let loaded = load_tree(&conn, "p1").unwrap();
+        assert_eq!(loaded, tree);
+
+        let mut with_target = loaded.clone();
+        with_target[1].word_count_target = Some(3000);
+        with_target[1].content = "含有 \"引号\" & <尖括号>\n换行".to_string();
+        let opml = to_opml("长夜", &with_target);
+        assert_eq!(parse_opml(&opml).unwrap(), with_target);
+        assert_eq!(parse_markdown(&to_markdown("长夜", &loaded)).unwrap(), loaded);
+        assert!(parse_opml("<opml><body></body></opml>").is_err());
+    }
+}
@@ -1,5 +1,12 @@
 pub mod types;
 pub mod commands;
+pub mod scaffold;
+pub mod drift;
+pub mod reorder;
+pub mod interchange;
+pub mod progress;
+pub mod plot_sync;
+pub mod expand;
 
 pub use types::*;
 pub use commands::*;
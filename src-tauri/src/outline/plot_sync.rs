@@ -0,0 +1,368 @@
+use crate::outline::commands::init_outline_tables;
+use crate::outline::interchange::{type_for_depth, type_name};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlotSyncResult {
+    pub created: usize,
+    pub updated: usize,
+}
+
+struct TreeRow {
+    id: String,
+    parent_id: Option<String>,
+    title: String,
+    text: String,
+    status: String,
+    sort_order: i32,
+    chapter_id: Option<String>,
+}
+
+fn plot_status_to_outline(status: &str) -> &'static str {
+    match status {
+        "in_progress" => "inprogress",
+        "completed" => "completed",
+        _ => "planned",
+    }
+}
+
+fn outline_status_to_plot(status: &str) -> &'static str {
+    match status {
+        "inprogress" => "in_progress",
+        "completed" => "completed",
+        _ => "draft",
+    }
+}
+
+/// 两边都还存在的关联，返回 (大纲节点, 情节点)
+fn load_links(conn: &Connection, project_id: &str) -> Result<Vec<(String, String)>, String> {
+    init_outline_tables(conn)?;
+    conn.prepare(
+        "SELECT l.outline_node_id, l.plot_point_id FROM outline_plot_links l
+         JOIN outline_nodes n ON n.id = l.outline_node_id JOIN plot_points p ON p.id = l.plot_point_id
+         WHERE l.project_id = ?",
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+fn insert_link(conn: &Connection, project_id: &str, outline_node_id: &str, plot_point_id: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO outline_plot_links (outline_node_id, plot_point_id, project_id, created_at) VALUES (?, ?, ?, ?)",
+        params![outline_node_id, plot_point_id, project_id, Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 手动关联已有的大纲节点和情节点，两者之前的关联会被替换
+pub fn link(conn: &Connection, outline_node_id: &str, plot_point_id: &str) -> Result<(), String> {
+    init_outline_tables(conn)?;
+    let node_project: Option<String> = conn
+        .query_row("SELECT project_id FROM outline_nodes WHERE id = ?", params![outline_node_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let plot_project: Option<String> = conn
+        .query_row("SELECT project_id FROM plot_points WHERE id = ?", params![plot_point_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let project_id = match (node_project, plot_project) {
+        (Some(a), Some(b)) if a == b => a,
+        (None, _) => return Err("大纲节点不存在".to_string()),
+        (_, None) => return Err("情节点不存在".to_string()),
+        _ => return Err("大纲节点和情节点不属于同一项目".to_string()),
+    };
+    conn.execute("DELETE FROM outline_plot_links WHERE plot_point_id = ?", params![plot_point_id]).map_err(|e| e.to_string())?;
+    insert_link(conn, &project_id, outline_node_id, plot_point_id)
+}
+
+pub fn unlink(conn: &Connection, outline_node_id: &str) -> Result<(), String> {
+    init_outline_tables(conn)?;
+    conn.execute("DELETE FROM outline_plot_links WHERE outline_node_id = ?", params![outline_node_id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn children_of(rows: &[TreeRow]) -> HashMap<Option<&str>, Vec<&TreeRow>> {
+    let ids: Vec<&str> = rows.iter().map(|r| r.id.as_str()).collect();
+    let mut children: HashMap<Option<&str>, Vec<&TreeRow>> = HashMap::new();
+    for row in rows {
+        // 父节点不在项目里时当作根节点
+        let parent = row.parent_id.as_deref().filter(|p| ids.contains(p));
+        children.entry(parent).or_default().push(row);
+    }
+    for list in children.values_mut() {
+        list.sort_by_key(|r| r.sort_order);
+    }
+    children
+}
+
+/// 把情节点树提升到大纲：已关联的节点更新标题、描述和状态，未关联的新建节点并关联。
+/// 新节点挂在父情节点对应的大纲节点下，`plot_point_ids` 为空时同步全部情节点。
+pub fn promote_plot_points(conn: &Connection, project_id: &str, plot_point_ids: &[String]) -> Result<PlotSyncResult, String> {
+    let links = load_links(conn, project_id)?;
+    let rows: Vec<TreeRow> = conn
+        .prepare(
+            "SELECT p.id, p.parent_id, p.title, COALESCE(p.description, ''), COALESCE(p.status, 'draft'), p.sort_order,
+                    (SELECT c.id FROM chapters c WHERE c.id = p.chapter_id)
+             FROM plot_points p WHERE p.project_id = ?",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| {
+            Ok(TreeRow {
+                id: row.get(0)?,
+                parent_id: row.get(1)?,
+                title: row.get(2)?,
+                text: row.get(3)?,
+                status: row.get(4)?,
+                sort_order: row.get(5)?,
+                chapter_id: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let children = children_of(&rows);
+    let mut node_for: HashMap<String, String> = links.into_iter().map(|(node, plot)| (plot, node)).collect();
+
+    let starts: Vec<&TreeRow> = if plot_point_ids.is_empty() {
+        children.get(&None).cloned().unwrap_or_default()
+    } else {
+        rows.iter().filter(|r| plot_point_ids.contains(&r.id)).collect()
+    };
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let mut result = PlotSyncResult::default();
+    // (情节点, 大纲父节点, 大纲层级)
+    let mut stack: Vec<(&TreeRow, Option<String>, usize)> = Vec::new();
+    for start in starts.iter().rev() {
+        let parent = start.parent_id.as_ref().and_then(|p| node_for.get(p)).cloned();
+        let depth = match &parent {
+            Some(parent) => outline_depth(&tx, parent)? + 1,
+            None => 0,
+        };
+        stack.push((*start, parent, depth));
+    }
+    while let Some((row, parent, depth)) = stack.pop() {
+        let node_id = match node_for.get(&row.id) {
+            Some(node_id) => {
+                tx.execute(
+                    "UPDATE outline_nodes SET title = ?, content = ?, status = ?, chapter_id = COALESCE(?, chapter_id), updated_at = ? WHERE id = ?",
+                    params![row.title, row.text, plot_status_to_outline(&row.status), row.chapter_id, now, node_id],
+                )
+                .map_err(|e| e.to_string())?;
+                result.updated += 1;
+                node_id.clone()
+            }
+            None => {
+                let node_id = Uuid::new_v4().to_string();
+                let sort_order: i32 = tx
+                    .query_row(
+                        "SELECT COALESCE(MAX(sort_order) + 1, 0) FROM outline_nodes WHERE project_id = ? AND parent_id IS ?",
+                        params![project_id, parent],
+                        |r| r.get(0),
+                    )
+                    .map_err(|e| e.to_string())?;
+                tx.execute(
+                    "INSERT INTO outline_nodes (id, project_id, parent_id, title, content, node_type, sort_order, status, word_count_actual, chapter_id, created_at, updated_at)
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?)",
+                    params![
+                        node_id,
+                        project_id,
+                        parent,
+                        row.title,
+                        row.text,
+                        type_name(&type_for_depth(depth)),
+                        sort_order,
+                        plot_status_to_outline(&row.status),
+                        row.chapter_id,
+                        now,
+                        now
+                    ],
+                )
+                .map_err(|e| format!("创建大纲节点失败: {}", e))?;
+                insert_link(&tx, project_id, &node_id, &row.id)?;
+                node_for.insert(row.id.clone(), node_id.clone());
+                result.created += 1;
+                node_id
+            }
+        };
+        for child in children.get(&Some(row.id.as_str())).into_iter().flatten().rev() {
+            stack.push((*child, Some(node_id.clone()), depth + 1));
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+fn outline_depth(conn: &Connection, node_id: &str) -> Result<usize, String> {
+    let mut depth = 0;
+    let mut current: Option<String> = conn
+        .query_row("SELECT parent_id FROM outline_nodes WHERE id = ?", params![node_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    while let Some(id) = current {
+        depth += 1;
+        current = conn
+            .query_row("SELECT parent_id FROM outline_nodes WHERE id = ?", params![id], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?
+            .flatten();
+    }
+    Ok(depth)
+}
+
+/// 把大纲的修改推回情节点：已关联的情节点更新标题、描述、状态、章节和顺序，
+/// 已关联节点下新增的子节点创建为对应情节点的子情节点
+pub fn push_outline_to_plot_points(conn: &Connection, project_id: &str) -> Result<PlotSyncResult, String> {
+    let links = load_links(conn, project_id)?;
+    let rows: Vec<TreeRow> = conn
+        .prepare(
+            "SELECT n.id, n.parent_id, n.title, COALESCE(n.content, ''), COALESCE(n.status, 'planned'), n.sort_order,
+                    (SELECT c.id FROM chapters c WHERE c.id = n.chapter_id)
+             FROM outline_nodes n WHERE n.project_id = ?",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| {
+            Ok(TreeRow {
+                id: row.get(0)?,
+                parent_id: row.get(1)?,
+                title: row.get(2)?,
+                text: row.get(3)?,
+                status: row.get(4)?,
+                sort_order: row.get(5)?,
+                chapter_id: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let children = children_of(&rows);
+    let plot_for: HashMap<String, String> = links.into_iter().collect();
+    let levels: HashMap<String, i32> = conn
+        .prepare("SELECT id, COALESCE(level, 0) FROM plot_points WHERE project_id = ?")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let mut result = PlotSyncResult::default();
+    // (大纲节点, 父节点对应的情节点及其层级)
+    let mut stack: Vec<(&TreeRow, Option<(String, i32)>)> = children.get(&None).into_iter().flatten().rev().map(|r| (*r, None)).collect();
+    while let Some((row, parent_plot)) = stack.pop() {
+        let plot = match (plot_for.get(&row.id), &parent_plot) {
+            (Some(plot_id), _) => {
+                let level = parent_plot.as_ref().map(|(_, level)| level + 1).unwrap_or_else(|| levels.get(plot_id).copied().unwrap_or(0));
+                tx.execute(
+                    "UPDATE plot_points SET title = ?, description = ?, status = ?, chapter_id = COALESCE(?, chapter_id), sort_order = ?, level = ?,
+                         parent_id = COALESCE(?, parent_id), updated_at = ? WHERE id = ?",
+                    params![
+                        row.title,
+                        row.text,
+                        outline_status_to_plot(&row.status),
+                        row.chapter_id,
+                        row.sort_order,
+                        level,
+                        parent_plot.as_ref().map(|(id, _)| id),
+                        now,
+                        plot_id
+                    ],
+                )
+                .map_err(|e| e.to_string())?;
+                result.updated += 1;
+                Some((plot_id.clone(), level))
+            }
+            (None, Some((parent_id, parent_level))) => {
+                let plot_id = Uuid::new_v4().to_string();
+                tx.execute(
+                    "INSERT INTO plot_points (id, project_id, parent_id, title, description, chapter_id, status, sort_order, level, created_at, updated_at)
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                    params![
+                        plot_id,
+                        project_id,
+                        parent_id,
+                        row.title,
+                        row.text,
+                        row.chapter_id,
+                        outline_status_to_plot(&row.status),
+                        row.sort_order,
+                        parent_level + 1,
+                        now,
+                        now
+                    ],
+                )
+                .map_err(|e| format!("创建情节点失败: {}", e))?;
+                insert_link(&tx, project_id, &row.id, &plot_id)?;
+                result.created += 1;
+                Some((plot_id, parent_level + 1))
+            }
+            (None, None) => None,
+        };
+        for child in children.get(&Some(row.id.as_str())).into_iter().flatten().rev() {
+            stack.push((*child, plot.clone()));
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_promote_and_push_back() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO plot_points (id, project_id, parent_id, title, description, status, sort_order, level, created_at, updated_at) VALUES
+                 ('pp1', 'p1', NULL, '复仇', '林舟为师门复仇', 'in_progress', 0, 0, 't0', 't0'),
+                 ('pp2', 'p1', 'pp1', '查明真凶', NULL, 'draft', 0, 1, 't0', 't0'),
+                 ('pp3', 'p1', 'pp1', '手刃仇人', NULL, 'draft', 1, 1, 't0', 't0');",
+        )
+        .unwrap();
+
+        let promoted = promote_plot_points(&conn, "p1", &[]).unwrap();
+        assert_eq!((promoted.created, promoted.updated), (3, 0));
+        let node = |plot_id: &str| -> (String, Option<String>, String, String) {
+            conn.query_row(
+                "SELECT n.id, n.parent_id, n.node_type, n.status FROM outline_nodes n JOIN outline_plot_links l ON l.outline_node_id = n.id WHERE l.plot_point_id = ?",
+                params![plot_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap()
+        };
+        let (root_id, root_parent, root_type, root_status) = node("pp1");
+        assert_eq!((root_parent, root_type.as_str(), root_status.as_str()), (None, "arc", "inprogress"));
+        let (child_id, child_parent, child_type, _) = node("pp3");
+        assert_eq!((child_parent.as_deref(), child_type.as_str()), (Some(root_id.as_str()), "chapter"));
+
+        // 在大纲里改名并新增子节点，推回情节点
+        conn.execute("UPDATE outline_nodes SET title = '血债血偿', status = 'completed' WHERE id = ?", params![child_id]).unwrap();
+        conn.execute(
+            "INSERT INTO outline_nodes (id, project_id, parent_id, title, node_type, sort_order, created_at, updated_at) VALUES ('nx', 'p1', ?, '夜袭', 'scene', 0, 't0', 't0')",
+            params![child_id],
+        )
+        .unwrap();
+        let pushed = push_outline_to_plot_points(&conn, "p1").unwrap();
+        assert_eq!((pushed.created, pushed.updated), (1, 3));
+        let (title, status): (String, String) = conn.query_row("SELECT title, status FROM plot_points WHERE id = 'pp3'", [], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        assert_eq!((title.as_str(), status.as_str()), ("血债血偿", "completed"));
+        let (parent_id, level): (String, i32) =
+            conn.query_row("SELECT parent_id, level FROM plot_points WHERE title = '夜袭'", [], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        assert_eq!((parent_id.as_str(), level), ("pp3", 2));
+
+        // 再次提升时只更新不重复创建
+        let again = promote_plot_points(&conn, "p1", &[]).unwrap();
+        assert_eq!((again.created, again.updated), (0, 4));
+        assert!(link(&conn, "nx", "missing").is_err());
+    }
+}
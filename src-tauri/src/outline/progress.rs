@@ -0,0 +1,220 @@
+use crate::outline::commands::init_outline_tables;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// 未设置范围时，超出目标字数这个比例才算超支
+const OVER_BUDGET_RATIO: f64 = 1.2;
+
+/// 大纲节点的字数进度；父节点汇总子树里关联的章节，未设目标时取子节点目标之和
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BeatProgress {
+    pub outline_node_id: String,
+    pub parent_id: Option<String>,
+    pub title: String,
+    pub node_type: String,
+    pub depth: usize,
+    pub target_words: Option<i32>,
+    pub actual_words: i32,
+    /// 已写字数占目标的百分比，没有目标时为空
+    pub percent: Option<f64>,
+    pub chapter_ids: Vec<String>,
+    /// not_started、in_progress、on_budget 或 over_budget
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OutlineProgress {
+    pub total_target: i32,
+    pub total_actual: i32,
+    /// 按大纲顺序排列
+    pub beats: Vec<BeatProgress>,
+}
+
+struct Row {
+    id: String,
+    parent_id: Option<String>,
+    title: String,
+    node_type: String,
+    sort_order: i32,
+    target: Option<i32>,
+    metadata: Option<String>,
+}
+
+/// 节点自己的字数下限和上限：模板写入的范围优先，否则取目标字数及其 1.2 倍
+fn budget(row: &Row, target: i32) -> (i32, i32) {
+    let range = row.metadata.as_deref().and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok()).and_then(|m| {
+        Some((m.get("word_count_min")?.as_i64()? as i32, m.get("word_count_max")?.as_i64()? as i32))
+    });
+    range.filter(|_| row.target == Some(target)).unwrap_or((target, (target as f64 * OVER_BUDGET_RATIO) as i32))
+}
+
+/// 计算各节点的字数进度，并把已写字数写回 `outline_nodes.word_count_actual`
+pub fn outline_progress(conn: &Connection, project_id: &str) -> Result<OutlineProgress, String> {
+    init_outline_tables(conn)?;
+    let rows: Vec<Row> = conn
+        .prepare("SELECT id, parent_id, title, node_type, sort_order, word_count_target, metadata FROM outline_nodes WHERE project_id = ?")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| {
+            Ok(Row {
+                id: row.get(0)?,
+                parent_id: row.get(1)?,
+                title: row.get(2)?,
+                node_type: row.get(3)?,
+                sort_order: row.get(4)?,
+                target: row.get(5)?,
+                metadata: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    // 节点关联的章节：由大纲生成的章节，以及导演脚本指向该节点的章节
+    let mut linked: HashMap<String, BTreeSet<String>> = HashMap::new();
+    let links: Vec<(String, String)> = conn
+        .prepare(
+            "SELECT n.id, c.id FROM outline_nodes n JOIN chapters c ON c.id = n.chapter_id WHERE n.project_id = ?1
+             UNION
+             SELECT m.beat_id, c.id FROM chapter_missions m JOIN chapters c ON c.id = m.chapter_id WHERE c.project_id = ?1 AND m.beat_id IS NOT NULL",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    for (node_id, chapter_id) in links {
+        linked.entry(node_id).or_default().insert(chapter_id);
+    }
+    let words: HashMap<String, i32> = conn
+        .prepare("SELECT id, word_count FROM chapters WHERE project_id = ?")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get::<_, Option<i32>>(1)?.unwrap_or(0))))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut children: HashMap<Option<&str>, Vec<&Row>> = HashMap::new();
+    for row in &rows {
+        children.entry(row.parent_id.as_deref()).or_default().push(row);
+    }
+    for list in children.values_mut() {
+        list.sort_by_key(|r| r.sort_order);
+    }
+
+    /// 返回子树关联的章节和目标字数，并按先序把节点进度写入 `beats`
+    fn visit(
+        row: &Row,
+        depth: usize,
+        children: &HashMap<Option<&str>, Vec<&Row>>,
+        linked: &HashMap<String, BTreeSet<String>>,
+        words: &HashMap<String, i32>,
+        beats: &mut Vec<BeatProgress>,
+    ) -> (BTreeSet<String>, Option<i32>) {
+        let index = beats.len();
+        beats.push(BeatProgress {
+            outline_node_id: row.id.clone(),
+            parent_id: row.parent_id.clone(),
+            title: row.title.clone(),
+            node_type: row.node_type.clone(),
+            depth,
+            target_words: None,
+            actual_words: 0,
+            percent: None,
+            chapter_ids: Vec::new(),
+            status: String::new(),
+        });
+        let mut chapters = linked.get(&row.id).cloned().unwrap_or_default();
+        let mut child_target: Option<i32> = None;
+        for child in children.get(&Some(row.id.as_str())).into_iter().flatten() {
+            let (child_chapters, target) = visit(child, depth + 1, children, linked, words, beats);
+            chapters.extend(child_chapters);
+            if let Some(target) = target {
+                child_target = Some(child_target.unwrap_or(0) + target);
+            }
+        }
+        let target = row.target.or(child_target);
+        let actual: i32 = chapters.iter().map(|c| words.get(c).copied().unwrap_or(0)).sum();
+        let status = match target {
+            _ if actual == 0 => "not_started",
+            None => "in_progress",
+            Some(target) => {
+                let (min, max) = budget(row, target);
+                if actual > max {
+                    "over_budget"
+                } else if actual < min {
+                    "in_progress"
+                } else {
+                    "on_budget"
+                }
+            }
+        };
+        let beat = &mut beats[index];
+        beat.target_words = target;
+        beat.actual_words = actual;
+        beat.percent = target.filter(|t| *t > 0).map(|t| (actual as f64 * 1000.0 / t as f64).round() / 10.0);
+        beat.chapter_ids = chapters.iter().cloned().collect();
+        beat.status = status.to_string();
+        (chapters, target)
+    }
+
+    let mut progress = OutlineProgress::default();
+    let mut all_chapters = BTreeSet::new();
+    for root in children.get(&None).into_iter().flatten() {
+        let (chapters, target) = visit(root, 0, &children, &linked, &words, &mut progress.beats);
+        all_chapters.extend(chapters);
+        progress.total_target += target.unwrap_or(0);
+    }
+    progress.total_actual = all_chapters.iter().map(|c| words.get(c).copied().unwrap_or(0)).sum();
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for beat in &progress.beats {
+        tx.execute(
+            "UPDATE outline_nodes SET word_count_actual = ? WHERE id = ?",
+            params![beat.actual_words, beat.outline_node_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outline_progress_rolls_up_linked_chapters() {
+        let (_dir, conn) = crate::test_support::project_db();
+        init_outline_tables(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO chapters (id, project_id, title, content, word_count, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '……', 2500, 1, 't0', 't0'),
+                 ('c2', 'p1', '第二章', '……', 5000, 2, 't0', 't0');
+             INSERT INTO chapter_missions (id, chapter_id, chapter_number, beat_id, created_at) VALUES ('m2', 'c2', 2, 'n2', 't0');
+             INSERT INTO outline_nodes (id, project_id, parent_id, title, node_type, sort_order, word_count_target, metadata, chapter_id, created_at, updated_at) VALUES
+                 ('a1', 'p1', NULL, '第一卷', 'arc', 0, NULL, NULL, NULL, 't0', 't0'),
+                 ('n1', 'p1', 'a1', '雨夜', 'chapter', 0, 3000, '{\"word_count_min\": 2000, \"word_count_max\": 4000}', 'c1', 't0', 't0'),
+                 ('n2', 'p1', 'a1', '出城', 'chapter', 1, 3000, NULL, NULL, 't0', 't0'),
+                 ('n3', 'p1', 'a1', '入京', 'chapter', 2, 2000, NULL, NULL, 't0', 't0');",
+        )
+        .unwrap();
+
+        let progress = outline_progress(&conn, "p1").unwrap();
+        let summary: Vec<(&str, Option<i32>, i32, &str)> =
+            progress.beats.iter().map(|b| (b.outline_node_id.as_str(), b.target_words, b.actual_words, b.status.as_str())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("a1", Some(8000), 7500, "in_progress"),
+                ("n1", Some(3000), 2500, "on_budget"),
+                ("n2", Some(3000), 5000, "over_budget"),
+                ("n3", Some(2000), 0, "not_started"),
+            ]
+        );
+        assert_eq!(progress.beats[1].percent, Some(83.3));
+        assert_eq!((progress.total_target, progress.total_actual), (8000, 7500));
+        let stored: i32 = conn.query_row("SELECT word_count_actual FROM outline_nodes WHERE id = 'a1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(stored, 7500);
+    }
+}
@@ -0,0 +1,98 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+
+fn sibling_ids(conn: &Connection, project_id: &str, parent_id: Option<&str>, exclude: &str) -> Result<Vec<String>, String> {
+    conn.prepare("SELECT id FROM outline_nodes WHERE project_id = ? AND parent_id IS ? AND id != ? ORDER BY sort_order, created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id, parent_id, exclude], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 把节点移到 `new_parent_id` 下的第 `new_index` 位（超出时放到最后），原位置和新位置的同级节点重新编号。
+/// 层级由 parent_id 决定，子节点随节点一起移动；不能移到自己的子孙节点下。返回节点所属项目。
+pub fn move_node(conn: &Connection, node_id: &str, new_parent_id: Option<&str>, new_index: usize) -> Result<String, String> {
+    let (project_id, old_parent_id): (String, Option<String>) = conn
+        .query_row("SELECT project_id, parent_id FROM outline_nodes WHERE id = ?", params![node_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "大纲节点不存在".to_string())?;
+
+    let mut ancestor = new_parent_id.map(String::from);
+    while let Some(id) = ancestor {
+        if id == node_id {
+            return Err("不能把节点移到它自己或子节点下".to_string());
+        }
+        let parent: (String, Option<String>) = conn
+            .query_row("SELECT project_id, parent_id FROM outline_nodes WHERE id = ?", params![id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .optional()
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "目标父节点不存在".to_string())?;
+        if parent.0 != project_id {
+            return Err("目标父节点不属于同一项目".to_string());
+        }
+        ancestor = parent.1;
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let renumber = |ids: &[String]| -> Result<(), String> {
+        for (order, id) in ids.iter().enumerate() {
+            tx.execute("UPDATE outline_nodes SET sort_order = ? WHERE id = ?", params![order as i32, id]).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    };
+    if old_parent_id.as_deref() != new_parent_id {
+        renumber(&sibling_ids(&tx, &project_id, old_parent_id.as_deref(), node_id)?)?;
+    }
+    let mut siblings = sibling_ids(&tx, &project_id, new_parent_id, node_id)?;
+    siblings.insert(new_index.min(siblings.len()), node_id.to_string());
+    renumber(&siblings)?;
+    tx.execute(
+        "UPDATE outline_nodes SET parent_id = ?, updated_at = ? WHERE id = ?",
+        params![new_parent_id, Utc::now().to_rfc3339(), node_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(project_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_node_renumbers_siblings() {
+        let (_dir, conn) = crate::test_support::project_db();
+        crate::outline::commands::init_outline_tables(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO outline_nodes (id, project_id, parent_id, title, node_type, sort_order, created_at, updated_at) VALUES
+                 ('a1', 'p1', NULL, '第一卷', 'arc', 0, 't0', 't0'),
+                 ('a2', 'p1', NULL, '第二卷', 'arc', 1, 't0', 't0'),
+                 ('n1', 'p1', 'a1', '甲', 'chapter', 0, 't0', 't0'),
+                 ('n2', 'p1', 'a1', '乙', 'chapter', 1, 't0', 't0'),
+                 ('n3', 'p1', 'a1', '丙', 'chapter', 2, 't0', 't0'),
+                 ('n4', 'p1', 'a2', '丁', 'chapter', 0, 't0', 't0'),
+                 ('b1', 'p1', 'n1', '节拍', 'beat', 0, 't0', 't0');",
+        )
+        .unwrap();
+        let order = |parent: &str| sibling_ids(&conn, "p1", Some(parent), "");
+
+        move_node(&conn, "n3", Some("a1"), 0).unwrap();
+        assert_eq!(order("a1").unwrap(), vec!["n3", "n1", "n2"]);
+
+        assert_eq!(move_node(&conn, "n1", Some("a2"), 1).unwrap(), "p1");
+        assert_eq!(order("a1").unwrap(), vec!["n3", "n2"]);
+        assert_eq!(order("a2").unwrap(), vec!["n4", "n1"]);
+        let sort_orders: Vec<i32> = ["n3", "n2", "n4", "n1"]
+            .iter()
+            .map(|id| conn.query_row("SELECT sort_order FROM outline_nodes WHERE id = ?", params![id], |row| row.get(0)).unwrap())
+            .collect();
+        assert_eq!(sort_orders, vec![0, 1, 0, 1]);
+        assert_eq!(order("n1").unwrap(), vec!["b1"]);
+
+        assert!(move_node(&conn, "a2", Some("b1"), 0).is_err());
+        move_node(&conn, "n2", None, 9).unwrap();
+        assert_eq!(sibling_ids(&conn, "p1", None, "").unwrap(), vec!["a1", "a2", "n2"]);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::outline::commands::init_outline_tables;
+use crate::outline::types::ScaffoldedChapter;
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+struct Node {
+    id: String,
+    parent_id: Option<String>,
+    title: String,
+    content: String,
+    node_type: String,
+    sort_order: i32,
+    word_count_target: Option<i32>,
+    chapter_id: Option<String>,
+}
+
+/// 按大纲树的先序遍历排列节点，同级按 sort_order
+fn tree_order(nodes: &[Node]) -> Vec<&Node> {
+    let mut children: HashMap<Option<&str>, Vec<&Node>> = HashMap::new();
+    for node in nodes {
+        children.entry(node.parent_id.as_deref()).or_default().push(node);
+    }
+    for list in children.values_mut() {
+        list.sort_by_key(|n| n.sort_order);
+    }
+    let mut ordered = Vec::new();
+    let mut stack: Vec<&Node> = children.get(&None).map(|l| l.iter().rev().copied().collect()).unwrap_or_default();
+    while let Some(node) = stack.pop() {
+        ordered.push(node);
+        if let Some(list) = children.get(&Some(node.id.as_str())) {
+            stack.extend(list.iter().rev());
+        }
+    }
+    ordered
+}
+
+/// 把选中的大纲叶子节点生成章节草稿和导演脚本，按大纲顺序追加到已有章节之后。
+/// 节点的场景、节拍子节点作为导演脚本的微节拍；章节与节点通过
+/// `outline_nodes.chapter_id` 和 `chapter_missions.beat_id` 互相关联。
+pub fn scaffold_chapters(conn: &Connection, project_id: &str, node_ids: &[String]) -> Result<Vec<ScaffoldedChapter>, String> {
+    if node_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    init_outline_tables(conn)?;
+    let nodes: Vec<Node> = conn
+        .prepare(
+            "SELECT n.id, n.parent_id, n.title, COALESCE(n.content, ''), n.node_type, n.sort_order, n.word_count_target,
+                    (SELECT c.id FROM chapters c WHERE c.id = n.chapter_id)
+             FROM outline_nodes n WHERE n.project_id = ?",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| {
+            Ok(Node {
+                id: row.get(0)?,
+                parent_id: row.get(1)?,
+                title: row.get(2)?,
+                content: row.get(3)?,
+                node_type: row.get(4)?,
+                sort_order: row.get(5)?,
+                word_count_target: row.get(6)?,
+                chapter_id: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    if let Some(missing) = node_ids.iter().find(|id| !nodes.iter().any(|n| &n.id == *id)) {
+        return Err(format!("大纲节点不存在: {}", missing));
+    }
+    let ordered = tree_order(&nodes);
+    let selected: Vec<&Node> = ordered.iter().copied().filter(|n| node_ids.contains(&n.id)).collect();
+    for node in &selected {
+        if nodes.iter().any(|n| n.parent_id.as_deref() == Some(node.id.as_str()) && matches!(n.node_type.as_str(), "arc" | "chapter")) {
+            return Err(format!("「{}」下还有章节或故事弧，请选择最底层的节点", node.title));
+        }
+        if node.chapter_id.is_some() {
+            return Err(format!("「{}」已经生成过章节", node.title));
+        }
+    }
+
+    let (max_sort_order, chapter_count): (Option<i32>, i32) = conn
+        .query_row("SELECT MAX(sort_order), COUNT(*) FROM chapters WHERE project_id = ?", params![project_id], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    let first_sort_order = max_sort_order.map(|s| s + 1).unwrap_or(0);
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let mut scaffolded = Vec::new();
+    for (index, node) in selected.iter().enumerate() {
+        let chapter_id = Uuid::new_v4().to_string();
+        let mission_id = Uuid::new_v4().to_string();
+        let sort_order = first_sort_order + index as i32;
+        tx.execute(
+            "INSERT INTO chapters (id, project_id, title, content, word_count, sort_order, status, target_word_count, created_at, updated_at)
+             VALUES (?, ?, ?, '', 0, ?, 'draft', ?, ?, ?)",
+            params![chapter_id, project_id, node.title, sort_order, node.word_count_target, now, now],
+        )
+        .map_err(|e| format!("创建章节失败: {}", e))?;
+
+        let macro_beat = if node.content.trim().is_empty() { node.title.clone() } else { node.content.trim().to_string() };
+        let micro_beats: Vec<String> = ordered
+            .iter()
+            .filter(|n| n.parent_id.as_deref() == Some(node.id.as_str()))
+            .map(|n| if n.content.trim().is_empty() { n.title.clone() } else { format!("{}：{}", n.title, n.content.trim()) })
+            .collect();
+        tx.execute(
+            "INSERT INTO chapter_missions (id, chapter_id, chapter_number, macro_beat, micro_beats, allowed_new_characters, forbidden_characters, beat_id, created_at)
+             VALUES (?, ?, ?, ?, ?, '[]', '[]', ?, ?)",
+            params![
+                mission_id,
+                chapter_id,
+                chapter_count + index as i32 + 1,
+                macro_beat,
+                serde_json::to_string(&micro_beats).unwrap_or_default(),
+                node.id,
+                now
+            ],
+        )
+        .map_err(|e| format!("创建章节导演脚本失败: {}", e))?;
+        tx.execute("UPDATE outline_nodes SET chapter_id = ?, updated_at = ? WHERE id = ?", params![chapter_id, now, node.id])
+            .map_err(|e| e.to_string())?;
+
+        scaffolded.push(ScaffoldedChapter {
+            outline_node_id: node.id.clone(),
+            chapter_id,
+            chapter_mission_id: mission_id,
+            title: node.title.clone(),
+            sort_order,
+            target_word_count: node.word_count_target,
+        });
+    }
+    tx.execute("UPDATE projects SET updated_at = ? WHERE id = ?", params![now, project_id]).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(scaffolded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scaffold_chapters_in_outline_order() {
+        let (_dir, conn) = crate::test_support::project_db();
+        init_outline_tables(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES ('c0', 'p1', '序章', '', 4, 't0', 't0');
+             INSERT INTO outline_nodes (id, project_id, parent_id, title, content, node_type, sort_order, word_count_target, created_at, updated_at) VALUES
+                 ('a1', 'p1', NULL, '第一卷', '', 'arc', 0, NULL, 't0', 't0'),
+                 ('n2', 'p1', 'a1', '出城', '林舟离开长安', 'chapter', 1, 3000, 't0', 't0'),
+                 ('n1', 'p1', 'a1', '雨夜', '', 'chapter', 0, NULL, 't0', 't0'),
+                 ('b1', 'p1', 'n2', '告别', '与师父道别', 'beat', 0, NULL, 't0', 't0'),
+                 ('b2', 'p1', 'n2', '遇袭', '', 'beat', 1, NULL, 't0', 't0');",
+        )
+        .unwrap();
+
+        assert!(scaffold_chapters(&conn, "p1", &["a1".to_string()]).is_err());
+        let chapters = scaffold_chapters(&conn, "p1", &["n2".to_string(), "n1".to_string()]).unwrap();
+        assert_eq!(chapters.iter().map(|c| (c.title.as_str(), c.sort_order)).collect::<Vec<_>>(), vec![("雨夜", 5), ("出城", 6)]);
+        assert_eq!(chapters[1].target_word_count, Some(3000));
+
+        let (macro_beat, micro_beats, beat_id): (String, String, String) = conn
+            .query_row("SELECT macro_beat, micro_beats, beat_id FROM chapter_missions WHERE chapter_id = ?", params![chapters[1].chapter_id], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap();
+        assert_eq!((macro_beat.as_str(), micro_beats.as_str(), beat_id.as_str()), ("林舟离开长安", r#"["告别：与师父道别","遇袭"]"#, "n2"));
+        let linked: String = conn.query_row("SELECT chapter_id FROM outline_nodes WHERE id = 'n2'", [], |row| row.get(0)).unwrap();
+        assert_eq!(linked, chapters[1].chapter_id);
+        assert!(scaffold_chapters(&conn, "p1", &["n1".to_string()]).is_err());
+    }
+}
@@ -103,9 +103,38 @@ pub struct TemplateNode {
     pub title: String,
     pub node_type: OutlineNodeType,
     pub description: String,
+    /// 该节拍推荐的字数范围，应用模板时取中间值作为目标字数
+    #[serde(default)]
+    pub word_count_range: Option<WordCountRange>,
     pub children: Vec<TemplateNode>,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct WordCountRange {
+    pub min: i32,
+    pub max: i32,
+}
+
+impl WordCountRange {
+    pub fn midpoint(&self) -> i32 {
+        (self.min + self.max) / 2
+    }
+}
+
+fn act(title: &str, description: &str, children: Vec<TemplateNode>) -> TemplateNode {
+    TemplateNode { title: title.to_string(), node_type: OutlineNodeType::Arc, description: description.to_string(), word_count_range: None, children }
+}
+
+fn beat(title: &str, description: &str, min: i32, max: i32) -> TemplateNode {
+    TemplateNode {
+        title: title.to_string(),
+        node_type: OutlineNodeType::Beat,
+        description: description.to_string(),
+        word_count_range: Some(WordCountRange { min, max }),
+        children: vec![],
+    }
+}
+
 pub fn get_default_templates() -> Vec<OutlineTemplate> {
     vec![
         OutlineTemplate {
@@ -117,23 +146,27 @@ pub fn get_default_templates() -> Vec<OutlineTemplate> {
                     title: "第一幕：铺垫".to_string(),
                     node_type: OutlineNodeType::Arc,
                     description: "介绍背景、人物、建立冲突".to_string(),
+                    word_count_range: None,
                     children: vec![
                         TemplateNode {
                             title: "开篇".to_string(),
                             node_type: OutlineNodeType::Scene,
                             description: "故事开场，吸引读者".to_string(),
+                            word_count_range: None,
                             children: vec![],
                         },
                         TemplateNode {
                             title: "人物介绍".to_string(),
                             node_type: OutlineNodeType::Scene,
                             description: "展示主要角色".to_string(),
+                            word_count_range: None,
                             children: vec![],
                         },
                         TemplateNode {
                             title: "激励事件".to_string(),
                             node_type: OutlineNodeType::Scene,
                             description: "打破平衡的事件".to_string(),
+                            word_count_range: None,
                             children: vec![],
                         },
                     ],
@@ -142,17 +175,20 @@ pub fn get_default_templates() -> Vec<OutlineTemplate> {
                     title: "第二幕：对抗".to_string(),
                     node_type: OutlineNodeType::Arc,
                     description: "冲突升级，角色成长".to_string(),
+                    word_count_range: None,
                     children: vec![
                         TemplateNode {
                             title: "中点".to_string(),
                             node_type: OutlineNodeType::Scene,
                             description: "故事的转折点".to_string(),
+                            word_count_range: None,
                             children: vec![],
                         },
                         TemplateNode {
                             title: "低谷".to_string(),
                             node_type: OutlineNodeType::Scene,
                             description: "主角遭遇最大挫折".to_string(),
+                            word_count_range: None,
                             children: vec![],
                         },
                     ],
@@ -161,17 +197,20 @@ pub fn get_default_templates() -> Vec<OutlineTemplate> {
                     title: "第三幕：解决".to_string(),
                     node_type: OutlineNodeType::Arc,
                     description: "高潮与结局".to_string(),
+                    word_count_range: None,
                     children: vec![
                         TemplateNode {
                             title: "高潮".to_string(),
                             node_type: OutlineNodeType::Scene,
                             description: "最终对决".to_string(),
+                            word_count_range: None,
                             children: vec![],
                         },
                         TemplateNode {
                             title: "结局".to_string(),
                             node_type: OutlineNodeType::Scene,
                             description: "故事的收尾".to_string(),
+                            word_count_range: None,
                             children: vec![],
                         },
                     ],
@@ -187,32 +226,35 @@ pub fn get_default_templates() -> Vec<OutlineTemplate> {
                     title: "出发".to_string(),
                     node_type: OutlineNodeType::Arc,
                     description: "英雄接受召唤".to_string(),
+                    word_count_range: None,
                     children: vec![
-                        TemplateNode { title: "平凡世界".to_string(), node_type: OutlineNodeType::Scene, description: "英雄的日常".to_string(), children: vec![] },
-                        TemplateNode { title: "冒险召唤".to_string(), node_type: OutlineNodeType::Scene, description: "英雄面临挑战".to_string(), children: vec![] },
-                        TemplateNode { title: "拒绝召唤".to_string(), node_type: OutlineNodeType::Scene, description: "英雄的犹豫".to_string(), children: vec![] },
-                        TemplateNode { title: "遇见导师".to_string(), node_type: OutlineNodeType::Scene, description: "获得指引".to_string(), children: vec![] },
+                        TemplateNode { title: "平凡世界".to_string(), node_type: OutlineNodeType::Scene, description: "英雄的日常".to_string(), word_count_range: None, children: vec![] },
+                        TemplateNode { title: "冒险召唤".to_string(), node_type: OutlineNodeType::Scene, description: "英雄面临挑战".to_string(), word_count_range: None, children: vec![] },
+                        TemplateNode { title: "拒绝召唤".to_string(), node_type: OutlineNodeType::Scene, description: "英雄的犹豫".to_string(), word_count_range: None, children: vec![] },
+                        TemplateNode { title: "遇见导师".to_string(), node_type: OutlineNodeType::Scene, description: "获得指引".to_string(), word_count_range: None, children: vec![] },
                     ],
                 },
                 TemplateNode {
                     title: "启蒙".to_string(),
                     node_type: OutlineNodeType::Arc,
                     description: "英雄的试炼与成长".to_string(),
+                    word_count_range: None,
                     children: vec![
-                        TemplateNode { title: "跨越门槛".to_string(), node_type: OutlineNodeType::Scene, description: "进入特殊世界".to_string(), children: vec![] },
-                        TemplateNode { title: "试炼之路".to_string(), node_type: OutlineNodeType::Scene, description: "面对挑战".to_string(), children: vec![] },
-                        TemplateNode { title: "最深的洞穴".to_string(), node_type: OutlineNodeType::Scene, description: "面对最大的恐惧".to_string(), children: vec![] },
-                        TemplateNode { title: "磨难".to_string(), node_type: OutlineNodeType::Scene, description: "生死考验".to_string(), children: vec![] },
+                        TemplateNode { title: "跨越门槛".to_string(), node_type: OutlineNodeType::Scene, description: "进入特殊世界".to_string(), word_count_range: None, children: vec![] },
+                        TemplateNode { title: "试炼之路".to_string(), node_type: OutlineNodeType::Scene, description: "面对挑战".to_string(), word_count_range: None, children: vec![] },
+                        TemplateNode { title: "最深的洞穴".to_string(), node_type: OutlineNodeType::Scene, description: "面对最大的恐惧".to_string(), word_count_range: None, children: vec![] },
+                        TemplateNode { title: "磨难".to_string(), node_type: OutlineNodeType::Scene, description: "生死考验".to_string(), word_count_range: None, children: vec![] },
                     ],
                 },
                 TemplateNode {
                     title: "归来".to_string(),
                     node_type: OutlineNodeType::Arc,
                     description: "英雄回归".to_string(),
+                    word_count_range: None,
                     children: vec![
-                        TemplateNode { title: "归途".to_string(), node_type: OutlineNodeType::Scene, description: "返回平凡世界".to_string(), children: vec![] },
-                        TemplateNode { title: "复活".to_string(), node_type: OutlineNodeType::Scene, description: "最后的考验".to_string(), children: vec![] },
-                        TemplateNode { title: "带着灵药归来".to_string(), node_type: OutlineNodeType::Scene, description: "英雄改变世界".to_string(), children: vec![] },
+                        TemplateNode { title: "归途".to_string(), node_type: OutlineNodeType::Scene, description: "返回平凡世界".to_string(), word_count_range: None, children: vec![] },
+                        TemplateNode { title: "复活".to_string(), node_type: OutlineNodeType::Scene, description: "最后的考验".to_string(), word_count_range: None, children: vec![] },
+                        TemplateNode { title: "带着灵药归来".to_string(), node_type: OutlineNodeType::Scene, description: "英雄改变世界".to_string(), word_count_range: None, children: vec![] },
                     ],
                 },
             ],
@@ -222,10 +264,108 @@ pub fn get_default_templates() -> Vec<OutlineTemplate> {
             name: "多视角叙事".to_string(),
             description: "适合多主角、多线叙事的小说".to_string(),
             structure: vec![
-                TemplateNode { title: "A线：主线剧情".to_string(), node_type: OutlineNodeType::Arc, description: "主要故事线".to_string(), children: vec![] },
-                TemplateNode { title: "B线：副线剧情".to_string(), node_type: OutlineNodeType::Arc, description: "次要故事线".to_string(), children: vec![] },
-                TemplateNode { title: "C线：背景线索".to_string(), node_type: OutlineNodeType::Arc, description: "隐藏的故事线".to_string(), children: vec![] },
+                TemplateNode { title: "A线：主线剧情".to_string(), node_type: OutlineNodeType::Arc, description: "主要故事线".to_string(), word_count_range: None, children: vec![] },
+                TemplateNode { title: "B线：副线剧情".to_string(), node_type: OutlineNodeType::Arc, description: "次要故事线".to_string(), word_count_range: None, children: vec![] },
+                TemplateNode { title: "C线：背景线索".to_string(), node_type: OutlineNodeType::Arc, description: "隐藏的故事线".to_string(), word_count_range: None, children: vec![] },
+            ],
+        },
+        OutlineTemplate {
+            id: "heros-journey-12".to_string(),
+            name: "英雄之旅（十二阶段）".to_string(),
+            description: "沃格勒整理的十二阶段英雄之旅，每个阶段附推荐字数，适合约十万字的长篇".to_string(),
+            structure: vec![
+                act("第一幕：启程", "英雄离开平凡世界", vec![
+                    beat("平凡世界", "展示英雄的日常、缺陷和渴望，让读者在变化来临前认识他", 3000, 6000),
+                    beat("冒险召唤", "一个事件或消息打破日常，向英雄提出挑战", 2000, 4000),
+                    beat("拒绝召唤", "英雄因恐惧或牵挂而犹豫，点明这次冒险的代价", 1500, 3000),
+                    beat("遇见导师", "导师给予建议、训练或关键道具，让英雄下定决心", 2000, 5000),
+                    beat("跨越第一道门槛", "英雄正式踏入特殊世界，再也无法回头", 2000, 4000),
+                ]),
+                act("第二幕：试炼", "英雄在特殊世界中经受考验", vec![
+                    beat("考验、盟友与敌人", "英雄学习新世界的规则，结识伙伴，确认对手", 10000, 20000),
+                    beat("接近最深的洞穴", "为最大的挑战做准备，团队内部出现张力", 4000, 8000),
+                    beat("磨难", "英雄直面死亡或最深的恐惧，经历象征性的死亡与重生", 5000, 10000),
+                    beat("获得奖赏", "战胜磨难后得到宝物、知识或和解", 3000, 6000),
+                ]),
+                act("第三幕：归来", "英雄带着改变回到平凡世界", vec![
+                    beat("归途", "英雄踏上归程，但敌人的追击或后果随之而来", 3000, 6000),
+                    beat("复活", "最终的高潮考验，英雄用所学彻底蜕变", 5000, 10000),
+                    beat("携宝归来", "英雄回到平凡世界，用收获改变自己和他人", 2000, 4000),
+                ]),
+            ],
+        },
+        OutlineTemplate {
+            id: "save-the-cat".to_string(),
+            name: "救猫咪节拍表".to_string(),
+            description: "布莱克·斯奈德的十五个节拍，按全书约八万字给出每个节拍的推荐字数".to_string(),
+            structure: vec![
+                act("第一幕", "建立主角的世界和问题", vec![
+                    beat("开场画面", "用一个画面展示主角故事开始前的状态，与终场画面形成对照", 500, 1500),
+                    beat("主题陈述", "某个角色点出主角需要领悟的道理，主角此时还不明白", 500, 1000),
+                    beat("铺垫", "展示主角的生活、身边人物以及他需要改变的地方", 4000, 8000),
+                    beat("催化剂", "打破现状的事件，主角的生活再也回不去", 1000, 2000),
+                    beat("争论", "主角犹豫、权衡，该不该踏上新的道路", 3000, 6000),
+                    beat("进入第二幕", "主角做出选择，进入一个全新的世界", 1000, 2000),
+                ]),
+                act("第二幕", "主角在新世界中追求目标", vec![
+                    beat("B故事", "引入副线人物，通常承载主题，常是感情线", 2000, 4000),
+                    beat("游戏时间", "兑现故事前提的承诺，读者最期待看到的部分", 10000, 16000),
+                    beat("中点", "假胜利或假失败，赌注提高，时钟开始倒计时", 1000, 3000),
+                    beat("坏人逼近", "外部敌人反扑，内部矛盾加剧", 6000, 12000),
+                    beat("一无所有", "主角失去一切，常伴随某种“死亡的气息”", 1000, 2000),
+                    beat("灵魂黑夜", "主角在绝望中反思，最终领悟主题", 2000, 4000),
+                ]),
+                act("第三幕", "主角用领悟解决问题", vec![
+                    beat("进入第三幕", "A故事与B故事交汇，主角找到解决办法", 1000, 2000),
+                    beat("终局", "主角执行计划，击败反派，完成蜕变", 6000, 12000),
+                    beat("终场画面", "与开场画面对照，证明主角已经改变", 500, 1500),
+                ]),
+            ],
+        },
+        OutlineTemplate {
+            id: "qi-cheng-zhuan-he".to_string(),
+            name: "起承转合".to_string(),
+            description: "中国传统的四段式结构，可用于全书，也可套用在单卷或中短篇".to_string(),
+            structure: vec![
+                beat("起", "交代人物、环境和起因，埋下贯穿全篇的矛盾", 5000, 15000),
+                beat("承", "承接开端展开情节，矛盾逐步积累和深化", 15000, 40000),
+                beat("转", "出现意料之外的转折，矛盾爆发，推向高潮", 10000, 25000),
+                beat("合", "收束各条线索，解决矛盾，点明主旨", 5000, 15000),
+            ],
+        },
+        OutlineTemplate {
+            id: "golden-three-chapters".to_string(),
+            name: "网文黄金三章".to_string(),
+            description: "网络小说开篇三章的节拍，决定读者是否追读，每章两三千字".to_string(),
+            structure: vec![
+                act("第一章：抓住读者", "开篇即冲突，让读者迅速代入主角", vec![
+                    beat("开篇钩子", "第一段就抛出冲突、危机或反常，不铺陈背景", 300, 800),
+                    beat("主角亮相", "通过行动展示主角的处境和性格，让读者产生代入感", 800, 1500),
+                    beat("困境与金手指", "主角陷入困境，金手指或转机出现", 800, 1500),
+                    beat("章末悬念", "留下让读者忍不住点下一章的悬念", 200, 500),
+                ]),
+                act("第二章：展开世界", "围绕主角的目标逐步交代设定", vec![
+                    beat("金手指初试", "展示金手指或能力的规则和潜力", 800, 1500),
+                    beat("世界观渗透", "借事件带出世界设定，避免大段说明", 800, 1500),
+                    beat("明确目标", "给主角一个清晰的短期目标和阻碍", 500, 1000),
+                ]),
+                act("第三章：第一个爽点", "兑现前两章的期待，确立追读理由", vec![
+                    beat("矛盾升级", "对手或困境施压，情绪压到最低", 800, 1500),
+                    beat("打脸或反转", "主角借助金手指完成第一次逆袭，释放情绪", 1000, 2000),
+                    beat("更大的目标", "揭示更广阔的舞台和长线目标，为后续埋线", 300, 800),
+                ]),
             ],
         },
     ]
 }
+
+/// 由大纲节点生成的章节及其导演脚本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScaffoldedChapter {
+    pub outline_node_id: String,
+    pub chapter_id: String,
+    pub chapter_mission_id: String,
+    pub title: String,
+    pub sort_order: i32,
+    pub target_word_count: Option<i32>,
+}
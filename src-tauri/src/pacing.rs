@@ -0,0 +1,220 @@
+use crate::tokenizer;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// 计算方法变化时加一，让旧的缓存失效
+const METRICS_VERSION: u32 = 1;
+
+const ACTION_WORDS: &[&str] = &[
+    "跑", "跳", "打", "击", "冲", "扑", "砍", "刺", "踢", "抓", "推", "拉", "追", "逃", "躲", "闪", "射", "撞", "摔", "挥",
+    "攻击", "厮杀", "搏斗", "出手", "拔剑", "奔", "杀", "fight", "run", "hit", "jump",
+];
+const EVENT_MARKERS: &[&str] = &[
+    "突然", "忽然", "猛地", "就在这时", "就在此时", "这时", "没想到", "竟然", "终于", "决定", "发现", "原来", "与此同时",
+];
+/// 只由这些字符组成的行是场景分隔，如 `***`、`——`、`◇◇◇`
+const SCENE_BREAK_CHARS: &[char] = &['*', '＊', '-', '—', '=', '#', '~', '～', '·', '◇', '◆', '○', '●', '☆', '★', '§'];
+
+/// 一段正文（整章或一个场景）的节奏指标
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PacingMetrics {
+    pub char_count: usize,
+    /// 每百词中的动作词数
+    pub action_density: f32,
+    /// 引号内对话占正文的比例，0-1
+    pub dialogue_ratio: f32,
+    pub avg_sentence_length: f32,
+    pub sentence_length_variance: f32,
+    /// 转折、发现、决定等事件标记出现的次数
+    pub event_count: usize,
+    /// 综合以上指标的热度，0-100
+    pub intensity: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterPacing {
+    pub chapter_id: String,
+    pub title: String,
+    pub metrics: PacingMetrics,
+    pub scenes: Vec<PacingMetrics>,
+}
+
+/// 全书的节奏热力图：`chapters` 按章节顺序，`series` 是各章的热度，便于直接绘图
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PacingHeatmap {
+    pub project_id: String,
+    pub chapters: Vec<ChapterPacing>,
+    pub series: Vec<f32>,
+    pub average_intensity: f32,
+    /// 热度明显低于全书平均的章节，可能是拖沓的部分
+    pub low_chapters: Vec<String>,
+    /// 使用缓存、未重新计算的章节数
+    pub cached: usize,
+}
+
+/// 只由分隔符组成的非空行
+pub(crate) fn is_scene_break(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && trimmed.chars().all(|c| SCENE_BREAK_CHARS.contains(&c) || c.is_whitespace())
+}
+
+/// 按场景分隔行把正文切成场景，没有分隔行时整章是一个场景
+pub fn split_scenes(content: &str) -> Vec<String> {
+    let mut scenes = vec![String::new()];
+    for line in content.lines() {
+        if is_scene_break(line) {
+            scenes.push(String::new());
+            continue;
+        }
+        let scene = scenes.last_mut().unwrap();
+        scene.push_str(line);
+        scene.push('\n');
+    }
+    scenes.retain(|s| !s.trim().is_empty());
+    scenes
+}
+
+/// 引号（含中文引号、直角引号）内的非空白字数
+pub(crate) fn dialogue_chars(text: &str) -> usize {
+    let mut depth = 0usize;
+    let mut straight_open = false;
+    let mut count = 0;
+    for c in text.chars() {
+        match c {
+            '“' | '「' | '『' => depth += 1,
+            '”' | '」' | '』' => depth = depth.saturating_sub(1),
+            '"' => straight_open = !straight_open,
+            c if !c.is_whitespace() && (depth > 0 || straight_open) => count += 1,
+            _ => {}
+        }
+    }
+    count
+}
+
+pub fn measure(text: &str) -> PacingMetrics {
+    let char_count = text.chars().filter(|c| !c.is_whitespace()).count();
+    if char_count == 0 {
+        return PacingMetrics::default();
+    }
+    let words = tokenizer::words(text);
+    let actions = words.iter().filter(|w| ACTION_WORDS.contains(w)).count();
+    let action_density = actions as f32 / words.len().max(1) as f32 * 100.0;
+    let dialogue_ratio = dialogue_chars(text) as f32 / char_count as f32;
+
+    let lengths: Vec<f32> = text
+        .split_inclusive(['.', '!', '?', '。', '！', '？', '\n'])
+        .map(|s| s.chars().filter(|c| !c.is_whitespace()).count() as f32)
+        .filter(|&n| n > 0.0)
+        .collect();
+    let avg_sentence_length = lengths.iter().sum::<f32>() / lengths.len().max(1) as f32;
+    let sentence_length_variance =
+        lengths.iter().map(|n| (n - avg_sentence_length).powi(2)).sum::<f32>() / lengths.len().max(1) as f32;
+    let event_count = EVENT_MARKERS.iter().map(|m| text.matches(m).count()).sum();
+
+    // 各项按经验上限归一：每百词 5 个动作词、每千字 5 个事件、句长变异系数 1
+    let action = (action_density / 5.0).min(1.0);
+    let events = (event_count as f32 / char_count as f32 * 1000.0 / 5.0).min(1.0);
+    let variation = if avg_sentence_length > 0.0 { (sentence_length_variance.sqrt() / avg_sentence_length).min(1.0) } else { 0.0 };
+    let intensity = (0.35 * action + 0.25 * dialogue_ratio.min(1.0) + 0.25 * events + 0.15 * variation) * 100.0;
+
+    PacingMetrics {
+        char_count,
+        action_density,
+        dialogue_ratio,
+        avg_sentence_length,
+        sentence_length_variance,
+        event_count,
+        intensity,
+    }
+}
+
+fn content_hash(content: &str) -> String {
+    let digest = Sha256::digest(format!("{}\n{}", METRICS_VERSION, content).as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 章节的整章和各场景指标；正文哈希与缓存一致时直接使用缓存
+fn chapter_metrics(conn: &Connection, chapter_id: &str, content: &str) -> Result<(PacingMetrics, Vec<PacingMetrics>, bool), String> {
+    let hash = content_hash(content);
+    let cached: Option<String> = conn
+        .query_row(
+            "SELECT metrics_json FROM pacing_cache WHERE chapter_id = ? AND content_hash = ?",
+            params![chapter_id, hash],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some((metrics, scenes)) = cached.and_then(|json| serde_json::from_str(&json).ok()) {
+        return Ok((metrics, scenes, true));
+    }
+
+    let metrics = measure(content);
+    let scenes: Vec<PacingMetrics> = split_scenes(content).iter().map(|s| measure(s)).collect();
+    conn.execute(
+        "INSERT OR REPLACE INTO pacing_cache (chapter_id, content_hash, metrics_json, computed_at) VALUES (?, ?, ?, ?)",
+        params![chapter_id, hash, serde_json::to_string(&(&metrics, &scenes)).map_err(|e| e.to_string())?, Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok((metrics, scenes, false))
+}
+
+/// 计算全书各章和各场景的节奏热力图
+pub fn book_pacing(conn: &Connection, project_id: &str) -> Result<PacingHeatmap, String> {
+    let chapters: Vec<(String, String, String)> = conn
+        .prepare("SELECT id, title, content FROM chapters WHERE project_id = ? ORDER BY sort_order, created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, Option<String>>(2)?.unwrap_or_default())))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut result = Vec::new();
+    let mut cached = 0;
+    for (chapter_id, title, content) in chapters {
+        let (metrics, scenes, hit) = chapter_metrics(conn, &chapter_id, &content)?;
+        cached += hit as usize;
+        result.push(ChapterPacing { chapter_id, title, metrics, scenes });
+    }
+
+    let series: Vec<f32> = result.iter().map(|c| c.metrics.intensity).collect();
+    let n = series.len().max(1) as f32;
+    let average_intensity = series.iter().sum::<f32>() / n;
+    let std_dev = (series.iter().map(|i| (i - average_intensity).powi(2)).sum::<f32>() / n).sqrt();
+    let low_chapters = result
+        .iter()
+        .filter(|c| c.metrics.char_count > 0 && c.metrics.intensity < average_intensity - std_dev)
+        .map(|c| c.chapter_id.clone())
+        .collect();
+
+    Ok(PacingHeatmap { project_id: project_id.to_string(), chapters: result, series, average_intensity, low_chapters, cached })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_book_pacing_uses_cache() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '他突然拔剑冲了上去。“站住！”\n***\n刀光一闪，两人厮杀在一起。', 1, 't0', 't0'),
+                 ('c2', 'p1', '第二章', '午后的阳光照在院子里，茶已经凉了。她坐在窗边看书，一页一页地翻着。', 2, 't0', 't0');",
+        )
+        .unwrap();
+
+        let heatmap = book_pacing(&conn, "p1").unwrap();
+        assert_eq!(heatmap.cached, 0);
+        let first = &heatmap.chapters[0];
+        assert_eq!(first.scenes.len(), 2);
+        assert!(first.metrics.dialogue_ratio > 0.0 && first.metrics.event_count >= 1);
+        assert!(heatmap.series[0] > heatmap.series[1]);
+        assert_eq!(heatmap.chapters[1].metrics.dialogue_ratio, 0.0);
+
+        assert_eq!(book_pacing(&conn, "p1").unwrap().cached, 2);
+        conn.execute("UPDATE chapters SET content = '他猛地转身。' WHERE id = 'c2'", []).unwrap();
+        assert_eq!(book_pacing(&conn, "p1").unwrap().cached, 1);
+    }
+}
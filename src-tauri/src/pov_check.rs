@@ -0,0 +1,272 @@
+use crate::entity_index::locate_terms;
+use crate::pacing::is_scene_break;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 表示人物内心活动的词，出现时说明叙述进入了这个人物的视角
+const INTERIOR_MARKERS: &[&str] = &[
+    "心想", "心里", "心中", "心头", "心底", "暗想", "暗道", "暗自", "觉得", "感到", "感觉", "想起", "想到", "意识到", "以为",
+];
+/// 第一人称叙述者在统计中的名字
+const NARRATOR: &str = "我";
+
+/// 一个场景的叙事人称和视角：`person` 为 first/third，`mode` 为 limited（限知）、omniscient（全知）或 unknown（没有内心描写）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenePov {
+    pub scene_index: usize,
+    pub start_paragraph: usize,
+    pub person: String,
+    pub mode: String,
+    pub pov_character: Option<String>,
+    /// 各角色内心描写的次数，从多到少
+    pub interior_counts: Vec<(String, usize)>,
+}
+
+/// 限知场景中途切换到另一个角色的内心
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeadHop {
+    pub scene_index: usize,
+    pub paragraph_index: usize,
+    pub from: String,
+    pub to: String,
+    pub excerpt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PovViolation {
+    pub scene_index: usize,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterPov {
+    pub chapter_id: String,
+    pub title: String,
+    /// 导演脚本中声明的视角
+    pub declared_pov: Option<String>,
+    pub scenes: Vec<ScenePov>,
+    pub head_hops: Vec<HeadHop>,
+    pub violations: Vec<PovViolation>,
+}
+
+#[derive(Default)]
+struct SceneAccumulator {
+    start_paragraph: usize,
+    first_person: usize,
+    third_person: usize,
+    interior: HashMap<usize, usize>,
+    last_interior: Option<usize>,
+    hops: Vec<(usize, usize, usize, String)>,
+}
+
+/// 去掉引号里的对话，只留叙述
+fn narration(line: &str) -> String {
+    let mut depth = 0usize;
+    let mut straight_open = false;
+    let mut text = String::new();
+    for c in line.chars() {
+        match c {
+            '“' | '「' | '『' => depth += 1,
+            '”' | '」' | '』' => depth = depth.saturating_sub(1),
+            '"' => straight_open = !straight_open,
+            c if depth == 0 && !straight_open => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+fn finish_scene(index: usize, scene: SceneAccumulator, names: &[String], head_hops: &mut Vec<HeadHop>) -> ScenePov {
+    let mut counts: Vec<(usize, usize)> = scene.interior.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let person = if scene.first_person >= 2 && scene.first_person * 2 >= scene.third_person { "first" } else { "third" };
+    let mode = match counts.len() {
+        0 => "unknown",
+        1 | 2 => "limited",
+        _ => "omniscient",
+    };
+    if mode == "limited" {
+        head_hops.extend(scene.hops.into_iter().map(|(paragraph_index, from, to, excerpt)| HeadHop {
+            scene_index: index,
+            paragraph_index,
+            from: names[from].clone(),
+            to: names[to].clone(),
+            excerpt,
+        }));
+    }
+    ScenePov {
+        scene_index: index,
+        start_paragraph: scene.start_paragraph,
+        person: person.to_string(),
+        mode: mode.to_string(),
+        pov_character: counts.first().map(|(owner, _)| names[*owner].clone()),
+        interior_counts: counts.into_iter().map(|(owner, count)| (names[owner].clone(), count)).collect(),
+    }
+}
+
+/// 按场景推断叙事人称和视角角色。`characters` 为（名字, 名字和别名）；
+/// 内心描写归给同一段里它之前最近提到的角色，段内没有时沿用上一个提到的角色（对应“他”“她”）
+pub fn analyze_text(content: &str, characters: &[(String, Vec<String>)]) -> (Vec<ScenePov>, Vec<HeadHop>) {
+    let mut names: Vec<String> = characters.iter().map(|(name, _)| name.clone()).collect();
+    names.push(NARRATOR.to_string());
+    let mut terms: Vec<(&str, usize)> = characters
+        .iter()
+        .enumerate()
+        .flat_map(|(i, (_, character_terms))| character_terms.iter().map(move |t| (t.as_str(), i)))
+        .collect();
+    terms.push((NARRATOR, characters.len()));
+    let term_strs: Vec<&str> = terms.iter().map(|(t, _)| *t).collect();
+
+    let mut scenes = Vec::new();
+    let mut head_hops = Vec::new();
+    let mut scene = SceneAccumulator::default();
+    let mut last_named: Option<usize> = None;
+
+    for (paragraph_index, line) in content.split('\n').enumerate() {
+        if is_scene_break(line) {
+            let finished = std::mem::replace(&mut scene, SceneAccumulator { start_paragraph: paragraph_index + 1, ..Default::default() });
+            scenes.push(finish_scene(scenes.len(), finished, &names, &mut head_hops));
+            last_named = None;
+            continue;
+        }
+        let text = narration(line);
+        scene.first_person += text.matches(NARRATOR).count();
+        scene.third_person += text.matches(['他', '她']).count();
+
+        let mentions = locate_terms(&text, &term_strs);
+        for (marker, _, _) in locate_terms(&text, INTERIOR_MARKERS) {
+            let owner = mentions.iter().rev().find(|(_, end, _)| *end <= marker).map(|(_, _, i)| terms[*i].1).or(last_named);
+            let Some(owner) = owner else { continue };
+            *scene.interior.entry(owner).or_default() += 1;
+            if let Some(previous) = scene.last_interior.filter(|&p| p != owner) {
+                scene.hops.push((paragraph_index, previous, owner, line.trim().chars().take(30).collect()));
+            }
+            scene.last_interior = Some(owner);
+        }
+        if let Some((_, _, i)) = mentions.last() {
+            last_named = Some(terms[*i].1);
+        }
+    }
+    scenes.push(finish_scene(scenes.len(), scene, &names, &mut head_hops));
+    (scenes, head_hops)
+}
+
+/// 对照导演脚本声明的视角（如“林舟”“第一人称”“全知”）检查各场景
+fn check_declared(declared: &str, characters: &[(String, Vec<String>)], scenes: &[ScenePov]) -> Vec<PovViolation> {
+    let first = declared.contains("第一人称");
+    let third = declared.contains("第三人称");
+    let omniscient = declared.contains("全知") || declared.contains("上帝");
+    let character = characters.iter().find(|(_, terms)| terms.iter().any(|t| declared.contains(t.as_str()))).map(|(name, _)| name);
+
+    let mut violations = Vec::new();
+    for scene in scenes.iter().filter(|s| s.mode != "unknown") {
+        let mut push = |description: String| violations.push(PovViolation { scene_index: scene.scene_index, description });
+        if first && scene.person == "third" {
+            push("导演脚本要求第一人称，本场景是第三人称叙述".to_string());
+        } else if third && scene.person == "first" {
+            push("导演脚本要求第三人称，本场景是第一人称叙述".to_string());
+        }
+        if omniscient || scene.person == "first" {
+            continue;
+        }
+        if let Some(character) = character {
+            if scene.mode == "omniscient" {
+                push(format!("导演脚本指定{}的视角，本场景进入了多个角色的内心", character));
+            } else if scene.pov_character.as_ref().is_some_and(|pov| pov != character) {
+                push(format!("导演脚本指定{}的视角，本场景主要是{}的视角", character, scene.pov_character.as_deref().unwrap_or_default()));
+            }
+        }
+    }
+    violations
+}
+
+fn load_characters(conn: &Connection, project_id: &str) -> Result<Vec<(String, Vec<String>)>, String> {
+    let rows: Vec<(String, String)> = conn
+        .prepare("SELECT id, name FROM characters WHERE project_id = ? AND merged_into IS NULL ORDER BY created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut characters = Vec::new();
+    for (id, name) in rows {
+        let mut terms = vec![name.clone()];
+        let aliases: Vec<String> = conn
+            .prepare("SELECT alias FROM character_aliases WHERE character_id = ?")
+            .map_err(|e| e.to_string())?
+            .query_map(params![id], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+        terms.extend(aliases);
+        characters.push((name, terms));
+    }
+    Ok(characters)
+}
+
+/// 检查项目中各章（或指定的一章）的视角
+pub fn check_project(conn: &Connection, project_id: &str, chapter_id: Option<&str>) -> Result<Vec<ChapterPov>, String> {
+    let characters = load_characters(conn, project_id)?;
+    let chapters: Vec<(String, String, String)> = conn
+        .prepare(
+            "SELECT id, title, COALESCE(content, '') FROM chapters WHERE project_id = ?1 AND (?2 IS NULL OR id = ?2)
+             ORDER BY sort_order, created_at",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id, chapter_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut result = Vec::new();
+    for (chapter_id, title, content) in chapters {
+        let declared_pov: Option<String> = conn
+            .query_row(
+                "SELECT pov FROM chapter_missions WHERE chapter_id = ? AND pov IS NOT NULL AND pov != '' ORDER BY created_at DESC LIMIT 1",
+                params![chapter_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let (scenes, head_hops) = analyze_text(&content, &characters);
+        let violations = declared_pov.as_deref().map(|d| check_declared(d, &characters, &scenes)).unwrap_or_default();
+        result.push(ChapterPov { chapter_id, title, declared_pov, scenes, head_hops, violations });
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_head_hop_and_declared_pov() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES
+                 ('r1', 'p1', '林舟', 't0', 't0'), ('r2', 'p1', '苏晚', 't1', 't1');
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '林舟推开门，心想她不会来了。\n他坐下，觉得屋里很冷。\n苏晚站在窗外，暗想他还是老样子。\n***\n苏晚转身离开，心里空落落的。', 1, 't0', 't0');
+             INSERT INTO chapter_missions (id, chapter_id, chapter_number, pov, created_at) VALUES
+                 ('m1', 'c1', 1, '林舟', 't0');",
+        )
+        .unwrap();
+
+        let chapter = check_project(&conn, "p1", None).unwrap().remove(0);
+        assert_eq!(chapter.scenes.len(), 2);
+        assert_eq!(chapter.scenes[0].person, "third");
+        assert_eq!(chapter.scenes[0].mode, "limited");
+        assert_eq!(chapter.scenes[0].pov_character.as_deref(), Some("林舟"));
+        assert_eq!(chapter.head_hops.len(), 1);
+        assert_eq!((chapter.head_hops[0].from.as_str(), chapter.head_hops[0].to.as_str()), ("林舟", "苏晚"));
+        assert_eq!(chapter.head_hops[0].paragraph_index, 2);
+        // 第二个场景完全是苏晚的视角，与导演脚本声明的林舟不符
+        assert_eq!(chapter.violations.len(), 1);
+        assert_eq!(chapter.violations[0].scene_index, 1);
+
+        let (scenes, _) = analyze_text("我走进院子，觉得一切都变了。\n我想起了小时候。", &[]);
+        assert_eq!(scenes[0].person, "first");
+        assert_eq!(scenes[0].pov_character.as_deref(), Some(NARRATOR));
+    }
+}
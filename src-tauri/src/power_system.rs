@@ -0,0 +1,548 @@
+use crate::character_presence::aliases_by_character;
+use crate::entity_index::locate_terms;
+use crate::relation_inference::chunks;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 修炼或魔法体系，`levels` 按 rank 从低到高
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerSystem {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// 使用力量的代价，如消耗寿元、灵石
+    pub costs: Option<String>,
+    /// 硬性限制，如“低一个大境界无法越级伤敌”
+    pub constraints: Option<String>,
+    pub levels: Vec<PowerLevel>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// 境界或等级
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerLevel {
+    pub id: String,
+    pub system_id: String,
+    pub name: String,
+    pub rank: i64,
+    pub description: Option<String>,
+    /// 突破到这一境界的条件
+    pub breakthrough: Option<String>,
+    pub abilities: Option<String>,
+}
+
+/// 保存体系时的境界，带 `id` 的保留原有记录，角色的进阶记录随之保留
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LevelInput {
+    pub id: Option<String>,
+    pub name: String,
+    pub description: Option<String>,
+    pub breakthrough: Option<String>,
+    pub abilities: Option<String>,
+}
+
+/// `id` 为空时新建；`levels` 的顺序即从低到高，未列出的境界连同进阶记录一起删除
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavePowerSystemRequest {
+    pub id: Option<String>,
+    pub project_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub costs: Option<String>,
+    pub constraints: Option<String>,
+    pub levels: Vec<LevelInput>,
+}
+
+/// 角色在某章达到某个境界，`chapter_id` 为空表示故事开始前已达到
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerProgress {
+    pub id: String,
+    pub character_id: String,
+    pub character_name: String,
+    pub system_id: String,
+    pub system_name: String,
+    pub level_id: String,
+    pub level_name: String,
+    pub rank: i64,
+    pub chapter_id: Option<String>,
+    pub chapter_title: Option<String>,
+    pub note: Option<String>,
+    pub created_at: String,
+}
+
+/// 正文中角色表现出超出当前境界的能力
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerViolation {
+    pub character_id: String,
+    pub name: String,
+    pub system_name: String,
+    pub current_level: String,
+    /// 正文中表现出的境界或能力
+    pub shown_level: String,
+    /// 与原文完全一致
+    pub excerpt: String,
+    pub reason: String,
+    pub severity: String,
+    pub chapter_id: String,
+    pub chapter_title: String,
+}
+
+fn load_levels(conn: &Connection, system_id: &str) -> Result<Vec<PowerLevel>, String> {
+    conn.prepare("SELECT id, system_id, name, rank, description, breakthrough, abilities FROM power_levels WHERE system_id = ? ORDER BY rank")
+        .map_err(|e| e.to_string())?
+        .query_map(params![system_id], |row| {
+            Ok(PowerLevel {
+                id: row.get(0)?,
+                system_id: row.get(1)?,
+                name: row.get(2)?,
+                rank: row.get(3)?,
+                description: row.get(4)?,
+                breakthrough: row.get(5)?,
+                abilities: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+pub fn list_systems(conn: &Connection, project_id: &str) -> Result<Vec<PowerSystem>, String> {
+    let mut systems: Vec<PowerSystem> = conn
+        .prepare(
+            "SELECT id, project_id, name, description, costs, constraints, created_at, updated_at
+             FROM power_systems WHERE project_id = ? ORDER BY created_at, rowid",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| {
+            Ok(PowerSystem {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                name: row.get(2)?,
+                description: row.get(3)?,
+                costs: row.get(4)?,
+                constraints: row.get(5)?,
+                levels: Vec::new(),
+                created_at: row.get(6)?,
+                updated_at: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    for system in &mut systems {
+        system.levels = load_levels(conn, &system.id)?;
+    }
+    Ok(systems)
+}
+
+pub fn get_system(conn: &Connection, id: &str) -> Result<PowerSystem, String> {
+    let project_id: String = conn
+        .query_row("SELECT project_id FROM power_systems WHERE id = ?", params![id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "力量体系不存在".to_string())?;
+    list_systems(conn, &project_id)?.into_iter().find(|s| s.id == id).ok_or_else(|| "力量体系不存在".to_string())
+}
+
+/// 新建或更新体系并整体替换境界列表
+pub fn save_system(conn: &mut Connection, request: &SavePowerSystemRequest) -> Result<PowerSystem, String> {
+    let name = request.name.trim();
+    if name.is_empty() {
+        return Err("体系名称不能为空".to_string());
+    }
+    for (i, level) in request.levels.iter().enumerate() {
+        if level.name.trim().is_empty() {
+            return Err("境界名称不能为空".to_string());
+        }
+        if request.levels[..i].iter().any(|l| l.name.trim() == level.name.trim()) {
+            return Err(format!("境界“{}”重复", level.name.trim()));
+        }
+    }
+    let now = Utc::now().to_rfc3339();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let id = match &request.id {
+        Some(id) => {
+            let project_id: String = tx
+                .query_row("SELECT project_id FROM power_systems WHERE id = ?", params![id], |row| row.get(0))
+                .optional()
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "力量体系不存在".to_string())?;
+            if project_id != request.project_id {
+                return Err("力量体系不属于该项目".to_string());
+            }
+            tx.execute(
+                "UPDATE power_systems SET name = ?, description = ?, costs = ?, constraints = ?, updated_at = ? WHERE id = ?",
+                params![name, request.description, request.costs, request.constraints, now, id],
+            )
+            .map_err(|e| format!("Failed to update power system: {}", e))?;
+            id.clone()
+        }
+        None => {
+            let id = Uuid::new_v4().to_string();
+            tx.execute(
+                "INSERT INTO power_systems (id, project_id, name, description, costs, constraints, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+                params![id, request.project_id, name, request.description, request.costs, request.constraints, now],
+            )
+            .map_err(|e| format!("Failed to create power system: {}", e))?;
+            id
+        }
+    };
+
+    let existing: Vec<String> = tx
+        .prepare("SELECT id FROM power_levels WHERE system_id = ?")
+        .map_err(|e| e.to_string())?
+        .query_map(params![id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    for old in existing.iter().filter(|old| !request.levels.iter().any(|l| l.id.as_ref() == Some(*old))) {
+        tx.execute("DELETE FROM power_levels WHERE id = ?", params![old]).map_err(|e| e.to_string())?;
+    }
+    for (rank, level) in request.levels.iter().enumerate() {
+        let values = (level.name.trim(), &level.description, &level.breakthrough, &level.abilities, rank as i64);
+        match level.id.as_ref().filter(|l| existing.contains(l)) {
+            Some(level_id) => tx.execute(
+                "UPDATE power_levels SET name = ?, description = ?, breakthrough = ?, abilities = ?, rank = ? WHERE id = ?",
+                params![values.0, values.1, values.2, values.3, values.4, level_id],
+            ),
+            None => tx.execute(
+                "INSERT INTO power_levels (id, system_id, name, description, breakthrough, abilities, rank) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                params![Uuid::new_v4().to_string(), id, values.0, values.1, values.2, values.3, values.4],
+            ),
+        }
+        .map_err(|e| format!("Failed to save power level: {}", e))?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    crate::tokenizer::add_words(&request.levels.iter().map(|l| l.name.trim()).collect::<Vec<_>>());
+    get_system(conn, &id)
+}
+
+/// 删除体系，境界和进阶记录随之删除
+pub fn delete_system(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM power_systems WHERE id = ?", params![id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+const PROGRESS_QUERY: &str = "SELECT p.id, p.character_id, c.name, s.id, s.name, l.id, l.name, l.rank, p.chapter_id, ch.title, p.note, p.created_at
+     FROM character_power_progress p
+     JOIN characters c ON c.id = p.character_id
+     JOIN power_levels l ON l.id = p.level_id
+     JOIN power_systems s ON s.id = l.system_id
+     LEFT JOIN chapters ch ON ch.id = p.chapter_id";
+
+fn progress_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<PowerProgress> {
+    Ok(PowerProgress {
+        id: row.get(0)?,
+        character_id: row.get(1)?,
+        character_name: row.get(2)?,
+        system_id: row.get(3)?,
+        system_name: row.get(4)?,
+        level_id: row.get(5)?,
+        level_name: row.get(6)?,
+        rank: row.get(7)?,
+        chapter_id: row.get(8)?,
+        chapter_title: row.get(9)?,
+        note: row.get(10)?,
+        created_at: row.get(11)?,
+    })
+}
+
+/// 角色的进阶记录，故事开始前的在前，其余按章节顺序
+pub fn character_progress(conn: &Connection, character_id: &str) -> Result<Vec<PowerProgress>, String> {
+    conn.prepare(&format!(
+        "{} WHERE p.character_id = ? ORDER BY ch.sort_order IS NOT NULL, ch.sort_order, ch.created_at, s.created_at, l.rank",
+        PROGRESS_QUERY
+    ))
+    .map_err(|e| e.to_string())?
+    .query_map(params![character_id], progress_from_row)
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+/// 记录角色在某章达到某个境界，同一境界已有记录时改为新的章节和备注
+pub fn record_progress(
+    conn: &Connection,
+    character_id: &str,
+    level_id: &str,
+    chapter_id: Option<&str>,
+    note: Option<&str>,
+) -> Result<PowerProgress, String> {
+    let character_project: String = conn
+        .query_row("SELECT project_id FROM characters WHERE id = ?", params![character_id], |row| row.get(0))
+        .map_err(|_| "角色不存在".to_string())?;
+    let level_project: String = conn
+        .query_row(
+            "SELECT s.project_id FROM power_levels l JOIN power_systems s ON s.id = l.system_id WHERE l.id = ?",
+            params![level_id],
+            |row| row.get(0),
+        )
+        .map_err(|_| "境界不存在".to_string())?;
+    if character_project != level_project {
+        return Err("境界不属于角色所在的项目".to_string());
+    }
+    if let Some(chapter_id) = chapter_id {
+        let chapter_project: String = conn
+            .query_row("SELECT project_id FROM chapters WHERE id = ?", params![chapter_id], |row| row.get(0))
+            .map_err(|_| "章节不存在".to_string())?;
+        if chapter_project != character_project {
+            return Err("章节不属于角色所在的项目".to_string());
+        }
+    }
+    conn.execute(
+        "INSERT INTO character_power_progress (id, character_id, level_id, chapter_id, note, created_at) VALUES (?, ?, ?, ?, ?, ?)
+         ON CONFLICT(character_id, level_id) DO UPDATE SET chapter_id = excluded.chapter_id, note = excluded.note",
+        params![Uuid::new_v4().to_string(), character_id, level_id, chapter_id, note, Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| format!("Failed to record power progress: {}", e))?;
+    character_progress(conn, character_id)?.into_iter().find(|p| p.level_id == level_id).ok_or_else(|| "进阶记录不存在".to_string())
+}
+
+pub fn delete_progress(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM character_power_progress WHERE id = ?", params![id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+struct RankedCharacter {
+    id: String,
+    name: String,
+    terms: Vec<String>,
+    /// 到本章为止在每个体系中的最高境界
+    levels: Vec<PowerProgress>,
+}
+
+/// 检查一章所需的数据，先从数据库读出，再在不持有连接的情况下调用 AI
+#[derive(Debug, Clone)]
+pub struct PowerCheckContext {
+    systems: Vec<PowerSystem>,
+    characters: Vec<RankedCharacter>,
+    chapter: (String, String, String),
+}
+
+/// 读取章节、体系和有进阶记录的角色在本章时的境界；本章的突破也算在内
+pub fn load_check_context(conn: &Connection, chapter_id: &str) -> Result<PowerCheckContext, String> {
+    let (project_id, title, content, sort_order): (String, String, String, i64) = conn
+        .query_row(
+            "SELECT project_id, title, COALESCE(content, ''), sort_order FROM chapters WHERE id = ?",
+            params![chapter_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|_| "章节不存在".to_string())?;
+    let systems = list_systems(conn, &project_id)?;
+    let progress: Vec<PowerProgress> = conn
+        .prepare(&format!(
+            "{} WHERE c.project_id = ? AND c.merged_into IS NULL AND (p.chapter_id IS NULL OR ch.sort_order <= ?) ORDER BY l.rank",
+            PROGRESS_QUERY
+        ))
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id, sort_order], progress_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut aliases = aliases_by_character(conn, &project_id)?;
+    let mut characters: Vec<RankedCharacter> = Vec::new();
+    for record in progress {
+        let index = match characters.iter().position(|c| c.id == record.character_id) {
+            Some(i) => i,
+            None => {
+                let mut terms = vec![record.character_name.clone()];
+                terms.extend(aliases.remove(&record.character_id).unwrap_or_default());
+                characters.push(RankedCharacter { id: record.character_id.clone(), name: record.character_name.clone(), terms, levels: Vec::new() });
+                characters.len() - 1
+            }
+        };
+        let levels = &mut characters[index].levels;
+        // 按 rank 升序读出，同一体系后来的更高
+        levels.retain(|l| l.system_id != record.system_id);
+        levels.push(record);
+    }
+    Ok(PowerCheckContext { systems, characters, chapter: (chapter_id.to_string(), title, content) })
+}
+
+impl PowerCheckContext {
+    fn present(&self, text: &str) -> Vec<&RankedCharacter> {
+        self.characters
+            .iter()
+            .filter(|c| {
+                let terms: Vec<&str> = c.terms.iter().map(String::as_str).collect();
+                !locate_terms(text, &terms).is_empty()
+            })
+            .collect()
+    }
+
+    fn prompt(&self, chunk: &str, present: &[&RankedCharacter]) -> String {
+        let systems: Vec<String> = self
+            .systems
+            .iter()
+            .filter(|s| present.iter().any(|c| c.levels.iter().any(|l| l.system_id == s.id)))
+            .map(|s| {
+                let levels: Vec<String> = s
+                    .levels
+                    .iter()
+                    .map(|l| {
+                        let mut text = l.name.clone();
+                        let details: Vec<String> = [("能力", &l.abilities), ("突破条件", &l.breakthrough)]
+                            .iter()
+                            .filter_map(|(label, value)| value.as_ref().filter(|v| !v.trim().is_empty()).map(|v| format!("{}：{}", label, v.trim())))
+                            .collect();
+                        if !details.is_empty() {
+                            text.push_str(&format!("（{}）", details.join("；")));
+                        }
+                        text
+                    })
+                    .collect();
+                let mut text = format!("【{}】境界从低到高：{}", s.name, levels.join(" → "));
+                for (label, value) in [("代价", &s.costs), ("限制", &s.constraints)] {
+                    if let Some(value) = value.as_ref().filter(|v| !v.trim().is_empty()) {
+                        text.push_str(&format!("\n{}：{}", label, value.trim()));
+                    }
+                }
+                text
+            })
+            .collect();
+        let characters: Vec<String> = present
+            .iter()
+            .map(|c| {
+                let levels: Vec<String> = c.levels.iter().map(|l| format!("{}·{}", l.system_name, l.level_name)).collect();
+                format!("- {}：{}", c.name, levels.join("、"))
+            })
+            .collect();
+        format!(
+            "下面是小说的力量体系设定和人物此时的境界。阅读片段，找出人物使用了超出当前境界的能力、无视体系限制或没有付出代价的地方。\n\n\
+             {}\n\n人物当前境界：\n{}\n\n\
+             返回 JSON 数组：[{{\"character\": \"人物\", \"shown_level\": \"片段中表现出的境界或能力\", \
+             \"excerpt\": \"片段中的原句，必须与原文完全一致\", \"reason\": \"为什么超出\", \"severity\": \"high、medium 或 low\"}}]。\
+             越级战斗等设定允许的情况不要返回，没有问题时返回 []。\n\n{}",
+            systems.join("\n\n"),
+            characters.join("\n"),
+            chunk
+        )
+    }
+
+    fn by_name(&self, name: &str) -> Option<&RankedCharacter> {
+        let name = name.trim();
+        self.characters
+            .iter()
+            .find(|c| c.name == name)
+            .or_else(|| self.characters.iter().find(|c| c.terms.iter().any(|t| t == name)))
+    }
+
+    /// 解析 AI 的回答：人物要有进阶记录，原句要能在片段中找到
+    fn parse(&self, chunk: &str, response: &str) -> Vec<PowerViolation> {
+        let start = response.find('[').unwrap_or(0);
+        let end = response.rfind(']').map(|i| i + 1).unwrap_or(response.len());
+        let items: Vec<serde_json::Value> = serde_json::from_str(&response[start..end]).unwrap_or_default();
+        let field = |item: &serde_json::Value, key: &str| item.get(key).and_then(|v| v.as_str()).unwrap_or_default().trim().to_string();
+
+        let mut violations = Vec::new();
+        for item in &items {
+            let Some(character) = self.by_name(&field(item, "character")) else { continue };
+            let excerpt = field(item, "excerpt");
+            if excerpt.is_empty() || !chunk.contains(&excerpt) {
+                continue;
+            }
+            let shown_level = field(item, "shown_level");
+            // 能对应到某个体系的境界时，用那个体系的当前境界
+            let current = character
+                .levels
+                .iter()
+                .find(|l| self.systems.iter().any(|s| s.id == l.system_id && s.levels.iter().any(|level| shown_level.contains(&level.name))))
+                .or(character.levels.first());
+            let Some(current) = current else { continue };
+            let severity = field(item, "severity");
+            violations.push(PowerViolation {
+                character_id: character.id.clone(),
+                name: character.name.clone(),
+                system_name: current.system_name.clone(),
+                current_level: current.level_name.clone(),
+                shown_level,
+                excerpt,
+                reason: field(item, "reason"),
+                severity: if ["high", "medium", "low"].contains(&severity.as_str()) { severity } else { "medium".to_string() },
+                chapter_id: self.chapter.0.clone(),
+                chapter_title: self.chapter.1.clone(),
+            });
+        }
+        violations
+    }
+}
+
+/// 按片段让 AI 检查有进阶记录的角色是否越级
+pub async fn check_with_ai(service: &crate::ai::AIService, model_id: &str, context: &PowerCheckContext) -> Result<Vec<PowerViolation>, String> {
+    let system_prompt = "你是一位熟悉网络小说设定的编辑，负责检查战力是否崩坏。只返回 JSON 数组，不要包含markdown代码块标记。";
+    let mut violations = Vec::new();
+    for chunk in chunks(&context.chapter.2) {
+        let present = context.present(&chunk);
+        if present.is_empty() {
+            continue;
+        }
+        let response = service.complete(model_id, system_prompt, &context.prompt(&chunk, &present)).await?;
+        violations.extend(context.parse(&chunk, &response));
+    }
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_progress_and_parse() {
+        let (_dir, mut conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES
+                 ('r1', 'p1', '林舟', 't0', 't0'), ('r2', 'p1', '沈青', 't0', 't0');
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '林舟盘膝打坐。', 1, 't0', 't0'),
+                 ('c2', 'p1', '第二章', '林舟一剑斩落金丹老怪。沈青在旁观战。', 2, 't0', 't0'),
+                 ('c3', 'p1', '第三章', '林舟突破筑基。', 3, 't0', 't0');",
+        )
+        .unwrap();
+        let level = |name: &str| LevelInput { id: None, name: name.to_string(), description: None, breakthrough: None, abilities: None };
+        let mut request = SavePowerSystemRequest {
+            id: None,
+            project_id: "p1".to_string(),
+            name: "修真".to_string(),
+            description: None,
+            costs: Some("消耗灵力".to_string()),
+            constraints: Some("不能越过大境界伤敌".to_string()),
+            levels: vec![level("炼气"), level("筑基"), level("金丹")],
+        };
+        let system = save_system(&mut conn, &request).unwrap();
+        let qi = system.levels[0].id.clone();
+        record_progress(&conn, "r1", &qi, None, None).unwrap();
+        record_progress(&conn, "r1", &system.levels[1].id, Some("c3"), Some("雷劫")).unwrap();
+
+        // 重排时保留带 id 的境界和进阶记录，去掉的境界一并删除
+        request.id = Some(system.id.clone());
+        request.levels = vec![LevelInput { id: Some(qi.clone()), ..level("练气") }, LevelInput { id: Some(system.levels[1].id.clone()), ..level("筑基") }];
+        let system = save_system(&mut conn, &request).unwrap();
+        assert_eq!(system.levels.iter().map(|l| l.name.as_str()).collect::<Vec<_>>(), vec!["练气", "筑基"]);
+        let progress = character_progress(&conn, "r1").unwrap();
+        assert_eq!(progress.iter().map(|p| p.level_name.as_str()).collect::<Vec<_>>(), vec!["练气", "筑基"]);
+
+        let context = load_check_context(&conn, "c2").unwrap();
+        assert_eq!(context.characters.len(), 1);
+        assert_eq!(context.characters[0].levels[0].level_name, "练气");
+        let chunk = &context.chapter.2;
+        assert!(context.prompt(chunk, &context.present(chunk)).contains("- 林舟：修真·练气"));
+        let response = r#"[
+            {"character": "林舟", "shown_level": "金丹战力", "excerpt": "林舟一剑斩落金丹老怪", "reason": "炼气无法斩杀金丹", "severity": "high"},
+            {"character": "沈青", "shown_level": "金丹", "excerpt": "沈青在旁观战", "reason": "", "severity": "low"},
+            {"character": "林舟", "shown_level": "筑基", "excerpt": "林舟御剑飞行", "reason": "", "severity": "bad"}
+        ]"#;
+        let violations = context.parse(chunk, response);
+        assert_eq!(violations.len(), 1);
+        assert_eq!((violations[0].current_level.as_str(), violations[0].severity.as_str()), ("练气", "high"));
+
+        let context = load_check_context(&conn, "c3").unwrap();
+        assert_eq!(context.characters[0].levels[0].level_name, "筑基");
+    }
+}
@@ -0,0 +1,114 @@
+use crate::logger::Logger;
+use crate::power_system::{self, SavePowerSystemRequest};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// 新建或更新力量体系，境界列表整体替换
+#[tauri::command]
+pub async fn save_power_system(app: AppHandle, request: SavePowerSystemRequest) -> Result<String, String> {
+    let logger = Logger::new().with_feature("power_system");
+    logger.info(&format!("Saving power system {} in project {}", request.name, request.project_id));
+
+    let db_path = get_db_path(&app)?;
+    let mut conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let system = power_system::save_system(&mut conn, &request)?;
+
+    serde_json::to_string(&system).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_power_systems(app: AppHandle, project_id: String) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let list = power_system::list_systems(&conn, &project_id)?;
+
+    serde_json::to_string(&list).map_err(|e| e.to_string())
+}
+
+/// 删除力量体系，境界和角色的进阶记录随之删除
+#[tauri::command]
+pub async fn delete_power_system(app: AppHandle, id: String) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    power_system::delete_system(&conn, &id)
+}
+
+#[tauri::command]
+pub async fn record_power_progress(
+    app: AppHandle,
+    character_id: String,
+    level_id: String,
+    chapter_id: Option<String>,
+    note: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let progress = power_system::record_progress(&conn, &character_id, &level_id, chapter_id.as_deref(), note.as_deref())?;
+
+    serde_json::to_string(&progress).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_power_progress(app: AppHandle, id: String) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    power_system::delete_progress(&conn, &id)
+}
+
+#[tauri::command]
+pub async fn get_character_power_progress(app: AppHandle, character_id: String) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let progress = power_system::character_progress(&conn, &character_id)?;
+
+    serde_json::to_string(&progress).map_err(|e| e.to_string())
+}
+
+/// 让 AI 检查本章中角色是否使用了超出当前境界的能力
+#[tauri::command]
+pub async fn check_power_consistency(app: AppHandle, chapter_id: String, model_id: Option<String>) -> Result<String, String> {
+    let logger = Logger::new().with_feature("power_system");
+    logger.info(&format!("Checking power consistency for chapter {}", chapter_id));
+
+    let context = {
+        let db_path = get_db_path(&app)?;
+        let conn = crate::database::get_connection(&db_path)
+            .map_err(|e| format!("Failed to get database connection: {}", e))?;
+        power_system::load_check_context(&conn, &chapter_id)?
+    };
+
+    let ai_service = app.state::<std::sync::Arc<tokio::sync::RwLock<crate::ai::AIService>>>();
+    let service = ai_service.read().await;
+    let model_id = model_id.unwrap_or_else(|| "glm-4-flash".to_string());
+    let violations = power_system::check_with_ai(&service, &model_id, &context).await.map_err(|e| {
+        logger.error(&format!("Failed to check power consistency: {}", e));
+        e
+    })?;
+
+    serde_json::to_string(&violations).map_err(|e| e.to_string())
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
@@ -0,0 +1,191 @@
+use crate::models::Character;
+use crate::text_analysis::{ReadabilityModel, TextAnalyzer};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// 分析器或输出格式变化时加一，让旧结果失效
+const ANALYSIS_VERSION: u32 = 1;
+
+/// 一章保存的分析结果，`stale` 表示正文在分析之后又改过
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterAnalysisResult {
+    pub chapter_id: String,
+    pub title: String,
+    pub content_hash: String,
+    pub computed_at: String,
+    pub stale: bool,
+    pub results: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectAnalysisSummary {
+    pub total_chapters: usize,
+    /// 重新分析的章节数，其余章节正文没变，沿用已有结果
+    pub analyzed: usize,
+    pub cached: usize,
+}
+
+/// 正文和角色表一起决定分析结果（逻辑检查会用到角色名）
+fn content_hash(content: &str, characters: &[Character]) -> String {
+    let names: Vec<&str> = characters.iter().map(|c| c.name.as_str()).collect();
+    let digest = Sha256::digest(format!("{}\n{}\n{}", ANALYSIS_VERSION, names.join("\u{1}"), content).as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn load_characters(conn: &Connection, project_id: &str) -> Result<Vec<Character>, String> {
+    conn.prepare(
+        "SELECT id, project_id, name, role_type, race, age, gender, birth_date, appearance, personality, background, skills, status,
+         bazi, ziwei, mbti, enneagram, items, avatar_url, created_at, updated_at FROM characters WHERE project_id = ? AND merged_into IS NULL ORDER BY created_at",
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![project_id], |row| {
+        Ok(Character {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            name: row.get(2)?,
+            role_type: row.get(3)?,
+            race: row.get(4)?,
+            age: row.get(5)?,
+            gender: row.get(6)?,
+            birth_date: row.get(7)?,
+            appearance: row.get(8)?,
+            personality: row.get(9)?,
+            background: row.get(10)?,
+            skills: row.get(11)?,
+            status: row.get(12)?,
+            bazi: row.get(13)?,
+            ziwei: row.get(14)?,
+            mbti: row.get(15)?,
+            enneagram: row.get(16)?,
+            items: row.get(17)?,
+            avatar_url: row.get(18)?,
+            created_at: row.get(19)?,
+            updated_at: row.get(20)?,
+        })
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+fn load_chapters(conn: &Connection, project_id: &str) -> Result<Vec<(String, String, String)>, String> {
+    conn.prepare("SELECT id, title, content FROM chapters WHERE project_id = ? ORDER BY sort_order, created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, Option<String>>(2)?.unwrap_or_default())))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 对项目所有章节运行全部分析，正文和角色都没变的章节跳过。
+/// 每处理完一章调用 `on_progress(已处理, 总数)`，返回 false 时中止并返回 `Ok(None)`
+pub fn run_project_analysis(
+    conn: &Connection,
+    project_id: &str,
+    mut on_progress: impl FnMut(usize, usize) -> bool,
+) -> Result<Option<ProjectAnalysisSummary>, String> {
+    let characters = load_characters(conn, project_id)?;
+    let chapters = load_chapters(conn, project_id)?;
+    let mut summary = ProjectAnalysisSummary { total_chapters: chapters.len(), ..Default::default() };
+
+    for (i, (chapter_id, _, content)) in chapters.iter().enumerate() {
+        let hash = content_hash(content, &characters);
+        let cached: Option<String> = conn
+            .query_row("SELECT content_hash FROM analysis_results WHERE chapter_id = ?", params![chapter_id], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if cached.as_deref() == Some(hash.as_str()) {
+            summary.cached += 1;
+        } else {
+            // 与 `run_full_analysis` 相同的一组分析
+            let results = serde_json::json!({
+                "writing_style": TextAnalyzer::analyze_writing_style(content),
+                "rhythm": TextAnalyzer::analyze_rhythm(content),
+                "emotion": TextAnalyzer::analyze_emotion(content),
+                "readability": TextAnalyzer::analyze_readability(content, &ReadabilityModel::default()),
+                "repetitions": TextAnalyzer::detect_repetitions(content, 3),
+                "logic": TextAnalyzer::check_logic(content, &characters),
+            });
+            conn.execute(
+                "INSERT OR REPLACE INTO analysis_results (chapter_id, project_id, content_hash, results_json, computed_at) VALUES (?, ?, ?, ?, ?)",
+                params![chapter_id, project_id, hash, results.to_string(), Utc::now().to_rfc3339()],
+            )
+            .map_err(|e| e.to_string())?;
+            summary.analyzed += 1;
+        }
+        if !on_progress(i + 1, chapters.len()) {
+            return Ok(None);
+        }
+    }
+    Ok(Some(summary))
+}
+
+/// 项目各章已保存的分析结果，按章节顺序；还没分析过的章节不在其中
+pub fn load_results(conn: &Connection, project_id: &str) -> Result<Vec<ChapterAnalysisResult>, String> {
+    let characters = load_characters(conn, project_id)?;
+    let mut results = Vec::new();
+    for (chapter_id, title, content) in load_chapters(conn, project_id)? {
+        let row: Option<(String, String, String)> = conn
+            .query_row(
+                "SELECT content_hash, results_json, computed_at FROM analysis_results WHERE chapter_id = ?",
+                params![chapter_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if let Some((content_hash, json, computed_at)) = row {
+            results.push(ChapterAnalysisResult {
+                stale: content_hash != self::content_hash(&content, &characters),
+                results: serde_json::from_str(&json).map_err(|e| e.to_string())?,
+                chapter_id,
+                title,
+                content_hash,
+                computed_at,
+            });
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_changed_chapters_are_reanalyzed() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '雨下了一整夜。', 1, 't0', 't0'),
+                 ('c2', 'p1', '第二章', '她推开门，愤怒地看着他。', 2, 't0', 't0');",
+        )
+        .unwrap();
+
+        let mut progress = Vec::new();
+        let first = run_project_analysis(&conn, "p1", |done, total| {
+            progress.push((done, total));
+            true
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!((first.analyzed, first.cached), (2, 0));
+        assert_eq!(progress, vec![(1, 2), (2, 2)]);
+
+        conn.execute("UPDATE chapters SET content = '天亮了。' WHERE id = 'c2'", []).unwrap();
+        let results = load_results(&conn, "p1").unwrap();
+        assert_eq!(results.iter().map(|r| r.stale).collect::<Vec<_>>(), vec![false, true]);
+        assert!(results[0].results["readability"]["overall"]["score"].is_number());
+
+        let second = run_project_analysis(&conn, "p1", |_, _| true).unwrap().unwrap();
+        assert_eq!((second.analyzed, second.cached), (1, 1));
+        assert!(load_results(&conn, "p1").unwrap().iter().all(|r| !r.stale));
+
+        // 新增角色会影响逻辑检查，所有章节都要重新分析；中途取消时返回 None
+        conn.execute("INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES ('r1', 'p1', '林舟', 't0', 't0')", [])
+            .unwrap();
+        assert!(run_project_analysis(&conn, "p1", |done, _| done < 1).unwrap().is_none());
+        assert_eq!(run_project_analysis(&conn, "p1", |_, _| true).unwrap().unwrap().analyzed, 1);
+    }
+}
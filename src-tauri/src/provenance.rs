@@ -0,0 +1,308 @@
+use crate::text_diff::{self, DiffOp, ParagraphChange};
+use crate::text_merge::{lcs_matches, split_paragraphs};
+use chrono::{Duration, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// 只和这段时间内的 AI 生成结果比对
+const GENERATION_WINDOW_DAYS: i64 = 7;
+/// 每次保存最多和最近这么多条 AI 生成结果比对
+const GENERATION_CANDIDATES: usize = 20;
+/// 新写入的文字至少有这么多字（不计空白）才和 AI 生成结果比对，避免零星改字被误判
+const MIN_MATCH_CHARS: usize = 4;
+
+/// 文字的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentSource {
+    Typing,
+    AiContinue,
+    AiRewrite,
+    Import,
+}
+
+impl ContentSource {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ContentSource::Typing => "typing",
+            ContentSource::AiContinue => "ai_continue",
+            ContentSource::AiRewrite => "ai_rewrite",
+            ContentSource::Import => "import",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "ai_continue" => ContentSource::AiContinue,
+            "ai_rewrite" => ContentSource::AiRewrite,
+            "import" => ContentSource::Import,
+            _ => ContentSource::Typing,
+        }
+    }
+}
+
+/// 一次改动的来源，AI 生成的文字同时记录模型
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Origin {
+    pub source: ContentSource,
+    pub model_id: Option<String>,
+}
+
+impl Origin {
+    pub fn import() -> Self {
+        Origin { source: ContentSource::Import, model_id: None }
+    }
+
+    fn typing() -> Self {
+        Origin { source: ContentSource::Typing, model_id: None }
+    }
+}
+
+/// 章节中一个段落最后一次改动的来源，按段落内容的哈希与正文对齐
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ParagraphOrigin {
+    hash: String,
+    #[serde(flatten)]
+    origin: Origin,
+    changed_at: Option<String>,
+}
+
+/// 逐段落的来源，`changed_at` 为空表示开始记录来源之前就已存在
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlameLine {
+    pub index: usize,
+    pub text: String,
+    pub source: ContentSource,
+    pub model_id: Option<String>,
+    pub changed_at: Option<String>,
+}
+
+fn paragraph_hash(text: &str) -> String {
+    format!("{:x}", Sha256::digest(text.as_bytes()))[..16].to_string()
+}
+
+fn non_space(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// 记录一次 AI 生成的结果，之后保存章节时据此识别来自 AI 的段落
+pub fn record_generation(
+    conn: &Connection,
+    project_id: Option<&str>,
+    source: ContentSource,
+    model_id: &str,
+    text: &str,
+) -> Result<(), String> {
+    let now = Utc::now();
+    conn.execute(
+        "INSERT INTO ai_generations (id, project_id, source, model_id, text, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+        params![uuid::Uuid::new_v4().to_string(), project_id, source.as_str(), model_id, text, now.to_rfc3339()],
+    )
+    .map_err(|e| e.to_string())?;
+    let expired = (now - Duration::days(GENERATION_WINDOW_DAYS)).to_rfc3339();
+    conn.execute("DELETE FROM ai_generations WHERE created_at < ?", params![expired]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn recent_generations(conn: &Connection, project_id: &str) -> Result<Vec<(Origin, String)>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT source, model_id, text FROM ai_generations
+             WHERE project_id IS NULL OR project_id = ? ORDER BY created_at DESC LIMIT ?",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![project_id, GENERATION_CANDIDATES as i64], |row| {
+            let source: String = row.get(0)?;
+            let text: String = row.get(2)?;
+            Ok((Origin { source: ContentSource::parse(&source), model_id: row.get(1)? }, non_space(&text)))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rows)
+}
+
+fn load_origins(conn: &Connection, chapter_id: &str) -> Result<Vec<ParagraphOrigin>, String> {
+    let saved: Option<String> = conn
+        .query_row("SELECT paragraphs_json FROM chapter_provenance WHERE chapter_id = ?", params![chapter_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match saved {
+        Some(json) => serde_json::from_str(&json).map_err(|e| e.to_string()),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// 把保存的来源按内容对齐到正文的每个段落；对不上的段落（如同步或协作写入的）来源未知
+fn align_origins(paragraphs: &[String], saved: &[ParagraphOrigin]) -> Vec<ParagraphOrigin> {
+    let hashes: Vec<String> = paragraphs.iter().map(|p| paragraph_hash(p)).collect();
+    let saved_hashes: Vec<&str> = saved.iter().map(|o| o.hash.as_str()).collect();
+    let current: Vec<&str> = hashes.iter().map(String::as_str).collect();
+    lcs_matches(&current, &saved_hashes)
+        .into_iter()
+        .zip(hashes)
+        .map(|(matched, hash)| match matched {
+            Some(j) => saved[j].clone(),
+            None => ParagraphOrigin { hash, origin: Origin::typing(), changed_at: None },
+        })
+        .collect()
+}
+
+/// 章节正文写入后调用：按段落比较新旧正文，记录改动的来源。
+/// 不指定 `origin` 时，和最近的 AI 生成结果相符的文字记为 AI，其余记为手写
+pub fn record_chapter_change(
+    conn: &Connection,
+    chapter_id: &str,
+    old_content: &str,
+    new_content: &str,
+    origin: Option<&Origin>,
+) -> Result<(), String> {
+    if old_content == new_content {
+        return Ok(());
+    }
+    let old_origins = align_origins(&split_paragraphs(old_content), &load_origins(conn, chapter_id)?);
+    let generations = match origin {
+        Some(_) => Vec::new(),
+        None => {
+            let project_id: String = conn
+                .query_row("SELECT project_id FROM chapters WHERE id = ?", params![chapter_id], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            recent_generations(conn, &project_id)?
+        }
+    };
+    let detect = |written: &str| -> Origin {
+        if let Some(origin) = origin {
+            return origin.clone();
+        }
+        let written = non_space(written);
+        if written.chars().count() < MIN_MATCH_CHARS {
+            return Origin::typing();
+        }
+        generations
+            .iter()
+            .find(|(_, text)| text.contains(&written))
+            .map(|(origin, _)| origin.clone())
+            .unwrap_or_else(Origin::typing)
+    };
+
+    let now = Utc::now().to_rfc3339();
+    let mut origins = Vec::new();
+    // 每种来源改动的段落数、插入字数、删除字数
+    let mut deltas: HashMap<(ContentSource, Option<String>), (i64, i64, i64)> = HashMap::new();
+    for paragraph in text_diff::diff_paragraphs(old_content, new_content) {
+        let count = |op: DiffOp| -> i64 {
+            paragraph.runs.iter().filter(|r| r.op == op).map(|r| non_space(&r.text).chars().count() as i64).sum()
+        };
+        let (inserted, deleted) = (count(DiffOp::Insert), count(DiffOp::Delete));
+        let new_text: String =
+            paragraph.runs.iter().filter(|r| r.op != DiffOp::Delete).map(|r| r.text.as_str()).collect();
+        let changed = match paragraph.change {
+            ParagraphChange::Unchanged => {
+                if let Some(i) = paragraph.old_index {
+                    origins.push(old_origins[i].clone());
+                }
+                continue;
+            }
+            ParagraphChange::Removed => Origin::typing(),
+            ParagraphChange::Added | ParagraphChange::Modified => {
+                let inserted_text: String =
+                    paragraph.runs.iter().filter(|r| r.op == DiffOp::Insert).map(|r| r.text.as_str()).collect();
+                let changed = detect(&inserted_text);
+                origins.push(ParagraphOrigin {
+                    hash: paragraph_hash(&new_text),
+                    origin: changed.clone(),
+                    changed_at: Some(now.clone()),
+                });
+                changed
+            }
+        };
+        let delta = deltas.entry((changed.source, changed.model_id)).or_default();
+        *delta = (delta.0 + 1, delta.1 + inserted, delta.2 + deleted);
+    }
+
+    conn.execute(
+        "INSERT INTO chapter_provenance (chapter_id, paragraphs_json, updated_at) VALUES (?, ?, ?)
+         ON CONFLICT(chapter_id) DO UPDATE SET paragraphs_json = excluded.paragraphs_json, updated_at = excluded.updated_at",
+        params![chapter_id, serde_json::to_string(&origins).map_err(|e| e.to_string())?, now],
+    )
+    .map_err(|e| e.to_string())?;
+    for ((source, model_id), (paragraphs, inserted, deleted)) in deltas {
+        conn.execute(
+            "INSERT INTO chapter_provenance_log (id, chapter_id, source, model_id, paragraphs, inserted_chars, deleted_chars, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![uuid::Uuid::new_v4().to_string(), chapter_id, source.as_str(), model_id, paragraphs, inserted, deleted, now],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// 章节当前正文每个段落最后一次改动的来源和模型
+pub fn blame_chapter(conn: &Connection, chapter_id: &str) -> Result<Vec<BlameLine>, String> {
+    let content: Option<String> = conn
+        .query_row("SELECT content FROM chapters WHERE id = ?", params![chapter_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("章节不存在: {}", chapter_id))?;
+    let paragraphs = split_paragraphs(&content.unwrap_or_default());
+    let origins = align_origins(&paragraphs, &load_origins(conn, chapter_id)?);
+    Ok(paragraphs
+        .into_iter()
+        .zip(origins)
+        .enumerate()
+        .map(|(index, (text, origin))| BlameLine {
+            index,
+            text,
+            source: origin.origin.source,
+            model_id: origin.origin.model_id,
+            changed_at: origin.changed_at,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blame_attributes_ai_and_typed_paragraphs() {
+        let (_dir, conn) = crate::test_support::project_db();
+
+        let imported = "雨下了一整夜。";
+        conn.execute(
+            "INSERT INTO chapters (id, project_id, title, content, created_at, updated_at) VALUES ('c1', 'p1', '第一章', ?, 't0', 't0')",
+            params![imported],
+        )
+        .unwrap();
+        record_chapter_change(&conn, "c1", "", imported, Some(&Origin::import())).unwrap();
+
+        record_generation(&conn, Some("p1"), ContentSource::AiContinue, "gpt-4o", "天亮时，她推开了那扇锈迹斑斑的铁门。").unwrap();
+        let save = |content: &str| {
+            let old: String = conn.query_row("SELECT content FROM chapters WHERE id = 'c1'", [], |row| row.get(0)).unwrap();
+            conn.execute("UPDATE chapters SET content = ? WHERE id = 'c1'", params![content]).unwrap();
+            record_chapter_change(&conn, "c1", &old, content, None).unwrap();
+        };
+        save("雨下了一整夜。\n天亮时，她推开了那扇锈迹斑斑的铁门。");
+        save("雨下了一整夜，没有停。\n天亮时，她推开了那扇锈迹斑斑的铁门。\n门外空无一人。");
+
+        let blame = blame_chapter(&conn, "c1").unwrap();
+        let sources: Vec<(ContentSource, Option<&str>)> =
+            blame.iter().map(|line| (line.source, line.model_id.as_deref())).collect();
+        assert_eq!(
+            sources,
+            vec![
+                (ContentSource::Typing, None),
+                (ContentSource::AiContinue, Some("gpt-4o")),
+                (ContentSource::Typing, None),
+            ]
+        );
+
+        let log_rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM chapter_provenance_log WHERE chapter_id = 'c1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(log_rows, 3);
+    }
+}
@@ -0,0 +1,321 @@
+use crate::character_presence::aliases_by_character;
+use crate::entity_index::locate_terms;
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 每次交给 AI 的正文最多这么多字
+const CHUNK_CHARS: usize = 4000;
+/// 默认只保留置信度不低于这个值的建议
+pub const DEFAULT_MIN_CONFIDENCE: f64 = 0.5;
+
+/// 从正文推断出的关系建议，kind 为 new（新关系）或 changed（已有关系的类型变了）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelationProposal {
+    pub id: String,
+    pub kind: String,
+    pub from_character_id: String,
+    pub from_name: String,
+    pub to_character_id: String,
+    pub to_name: String,
+    pub relation_type: String,
+    pub description: Option<String>,
+    /// 正文中的依据，与原文完全一致
+    pub evidence: String,
+    pub chapter_id: String,
+    pub chapter_title: String,
+    pub confidence: f64,
+    pub existing_relation_id: Option<String>,
+    pub existing_relation_type: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct KnownCharacter {
+    id: String,
+    name: String,
+    terms: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct KnownRelation {
+    id: String,
+    from: String,
+    to: String,
+    relation_type: String,
+}
+
+/// 推断所需的项目数据，先从数据库读出，再在不持有连接的情况下调用 AI
+#[derive(Debug, Clone)]
+pub struct InferenceContext {
+    characters: Vec<KnownCharacter>,
+    relations: Vec<KnownRelation>,
+    chapters: Vec<(String, String, String)>,
+}
+
+/// `chapter_ids` 为空时读取项目全部章节
+pub fn load_context(conn: &Connection, project_id: &str, chapter_ids: Option<&[String]>) -> Result<InferenceContext, String> {
+    let mut aliases = aliases_by_character(conn, project_id)?;
+    let characters: Vec<KnownCharacter> = conn
+        .prepare("SELECT id, name FROM characters WHERE project_id = ? AND merged_into IS NULL ORDER BY created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|(id, name)| {
+            let mut terms = vec![name.clone()];
+            terms.extend(aliases.remove(&id).unwrap_or_default());
+            KnownCharacter { id, name, terms }
+        })
+        .collect();
+    let relations = conn
+        .prepare("SELECT id, from_character_id, to_character_id, relation_type FROM character_relations WHERE project_id = ?")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| {
+            Ok(KnownRelation { id: row.get(0)?, from: row.get(1)?, to: row.get(2)?, relation_type: row.get(3)? })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let chapters = conn
+        .prepare("SELECT id, title, content FROM chapters WHERE project_id = ? ORDER BY sort_order, created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?.unwrap_or_default()))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|(id, _, _)| chapter_ids.is_none_or(|ids| ids.contains(id)))
+        .collect();
+    Ok(InferenceContext { characters, relations, chapters })
+}
+
+/// 按段落把正文切成不超过 `CHUNK_CHARS` 字的片段
+pub(crate) fn chunks(text: &str) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for line in text.lines() {
+        if !current.is_empty() && current.chars().count() + line.chars().count() > CHUNK_CHARS {
+            chunks.push(std::mem::take(&mut current));
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    if !current.trim().is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+impl InferenceContext {
+    /// 片段中出现的角色
+    fn present(&self, text: &str) -> Vec<&KnownCharacter> {
+        self.characters
+            .iter()
+            .filter(|c| {
+                let terms: Vec<&str> = c.terms.iter().map(String::as_str).collect();
+                !locate_terms(text, &terms).is_empty()
+            })
+            .collect()
+    }
+
+    fn by_name(&self, name: &str) -> Option<&KnownCharacter> {
+        let name = name.trim();
+        self.characters
+            .iter()
+            .find(|c| c.name == name)
+            .or_else(|| self.characters.iter().find(|c| c.terms.iter().any(|t| t == name)))
+    }
+
+    fn prompt(&self, chunk: &str, present: &[&KnownCharacter]) -> String {
+        let names: Vec<String> = present
+            .iter()
+            .map(|c| if c.terms.len() > 1 { format!("{}（又称{}）", c.name, c.terms[1..].join("、")) } else { c.name.clone() })
+            .collect();
+        let known: Vec<String> = self
+            .relations
+            .iter()
+            .filter_map(|r| {
+                let from = present.iter().find(|c| c.id == r.from)?;
+                let to = present.iter().find(|c| c.id == r.to)?;
+                Some(format!("{} → {}：{}", from.name, to.name, r.relation_type))
+            })
+            .collect();
+        format!(
+            "阅读下面的小说片段，找出其中人物之间新出现或发生变化的关系（如结盟、反目、师徒、恋人、亲属）。\
+             只考虑这些人物：{}。\n已记录的关系：{}\n\n\
+             返回 JSON 数组：[{{\"from\": \"人物\", \"to\": \"人物\", \"relation_type\": \"关系\", \"description\": \"一句话说明\", \
+             \"evidence\": \"片段中能说明这段关系的原句，必须与原文完全一致\", \"confidence\": 0到1之间的小数}}]。\
+             与已记录关系相同的不要返回，没有时返回 []。\n\n{}",
+            names.join("、"),
+            if known.is_empty() { "无".to_string() } else { known.join("；") },
+            chunk
+        )
+    }
+
+    /// 解析 AI 的回答：人物要能对应到角色，依据要能在片段中找到，与已有关系完全相同的丢弃
+    fn parse(&self, chapter: &(String, String, String), chunk: &str, response: &str, min_confidence: f64) -> Vec<RelationProposal> {
+        let start = response.find('[').unwrap_or(0);
+        let end = response.rfind(']').map(|i| i + 1).unwrap_or(response.len());
+        let items: Vec<serde_json::Value> = serde_json::from_str(&response[start..end]).unwrap_or_default();
+        let field = |item: &serde_json::Value, key: &str| item.get(key).and_then(|v| v.as_str()).unwrap_or_default().trim().to_string();
+
+        let mut proposals = Vec::new();
+        for item in &items {
+            let (Some(from), Some(to)) = (self.by_name(&field(item, "from")), self.by_name(&field(item, "to"))) else { continue };
+            let relation_type = field(item, "relation_type");
+            let evidence = field(item, "evidence");
+            let confidence = item.get("confidence").and_then(|v| v.as_f64()).unwrap_or(0.0).clamp(0.0, 1.0);
+            if from.id == to.id || relation_type.is_empty() || evidence.is_empty() || !chunk.contains(&evidence) || confidence < min_confidence {
+                continue;
+            }
+            let pair: Vec<&KnownRelation> = self
+                .relations
+                .iter()
+                .filter(|r| (r.from == from.id && r.to == to.id) || (r.from == to.id && r.to == from.id))
+                .collect();
+            if pair.iter().any(|r| r.relation_type == relation_type) {
+                continue;
+            }
+            let existing = pair.iter().find(|r| r.from == from.id).or(pair.first());
+            let description = Some(field(item, "description")).filter(|d| !d.is_empty());
+            proposals.push(RelationProposal {
+                id: Uuid::new_v4().to_string(),
+                kind: if existing.is_some() { "changed" } else { "new" }.to_string(),
+                from_character_id: from.id.clone(),
+                from_name: from.name.clone(),
+                to_character_id: to.id.clone(),
+                to_name: to.name.clone(),
+                relation_type,
+                description,
+                evidence,
+                chapter_id: chapter.0.clone(),
+                chapter_title: chapter.1.clone(),
+                confidence,
+                existing_relation_id: existing.map(|r| r.id.clone()),
+                existing_relation_type: existing.map(|r| r.relation_type.clone()),
+            });
+        }
+        proposals
+    }
+}
+
+/// 逐章让 AI 推断关系，只处理出现两个以上角色的片段；同一对人物的同一种关系只保留置信度最高的一条
+pub async fn infer_with_ai(
+    service: &crate::ai::AIService,
+    model_id: &str,
+    context: &InferenceContext,
+    min_confidence: f64,
+) -> Result<Vec<RelationProposal>, String> {
+    let system_prompt = "你是一位细致的小说编辑，负责整理人物关系。只返回 JSON 数组，不要包含markdown代码块标记。";
+    let mut proposals: Vec<RelationProposal> = Vec::new();
+    for chapter in &context.chapters {
+        for chunk in chunks(&chapter.2) {
+            let present = context.present(&chunk);
+            if present.len() < 2 {
+                continue;
+            }
+            let response = service.complete(model_id, system_prompt, &context.prompt(&chunk, &present)).await?;
+            for proposal in context.parse(chapter, &chunk, &response, min_confidence) {
+                let same = proposals.iter().position(|p| {
+                    p.from_character_id == proposal.from_character_id
+                        && p.to_character_id == proposal.to_character_id
+                        && p.relation_type == proposal.relation_type
+                });
+                match same {
+                    Some(i) if proposals[i].confidence >= proposal.confidence => {}
+                    Some(i) => proposals[i] = proposal,
+                    None => proposals.push(proposal),
+                }
+            }
+        }
+    }
+    Ok(proposals)
+}
+
+/// 采纳建议：new 新建关系，changed 把已有关系改为新的类型和说明；返回写入的条数
+pub fn accept_proposals(conn: &Connection, project_id: &str, proposals: &[RelationProposal]) -> Result<usize, String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let mut written = 0;
+    for proposal in proposals {
+        written += match &proposal.existing_relation_id {
+            Some(relation_id) if proposal.kind == "changed" => tx
+                .execute(
+                    "UPDATE OR IGNORE character_relations SET relation_type = ?, description = COALESCE(?, description), updated_at = ?
+                     WHERE id = ? AND project_id = ?",
+                    params![proposal.relation_type, proposal.description, now, relation_id, project_id],
+                )
+                .map_err(|e| e.to_string())?,
+            _ => tx
+                .execute(
+                    "INSERT OR IGNORE INTO character_relations (id, project_id, from_character_id, to_character_id, relation_type, description, created_at, updated_at)
+                     SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7
+                     WHERE (SELECT COUNT(*) FROM characters WHERE project_id = ?2 AND id IN (?3, ?4)) = 2",
+                    params![
+                        Uuid::new_v4().to_string(),
+                        project_id,
+                        proposal.from_character_id,
+                        proposal.to_character_id,
+                        proposal.relation_type,
+                        proposal.description,
+                        now,
+                    ],
+                )
+                .map_err(|e| e.to_string())?,
+        };
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_accept_proposals() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES
+                 ('r1', 'p1', '林舟', 't0', 't0'), ('r2', 'p1', '沈青', 't0', 't0'), ('r3', 'p1', '老周', 't0', 't0');
+             INSERT INTO character_aliases (character_id, alias) VALUES ('r3', '周伯');
+             INSERT INTO character_relations (id, project_id, from_character_id, to_character_id, relation_type, created_at, updated_at) VALUES
+                 ('x1', 'p1', 'r1', 'r2', 'friend', 't0', 't0');
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '沈青拔剑指向林舟：“从今往后，你我恩断义绝。”\n周伯把林舟拉到身后。', 1, 't0', 't0');",
+        )
+        .unwrap();
+        let context = load_context(&conn, "p1", None).unwrap();
+        let chapter = context.chapters[0].clone();
+        let chunk = chunks(&chapter.2).remove(0);
+        assert_eq!(context.present(&chunk).len(), 3);
+
+        let response = r#"[
+            {"from": "沈青", "to": "林舟", "relation_type": "enemy", "evidence": "从今往后，你我恩断义绝。", "confidence": 0.9},
+            {"from": "周伯", "to": "林舟", "relation_type": "guardian", "description": "护着林舟", "evidence": "周伯把林舟拉到身后", "confidence": 0.7},
+            {"from": "林舟", "to": "沈青", "relation_type": "friend", "evidence": "沈青拔剑指向林舟", "confidence": 0.9},
+            {"from": "林舟", "to": "老周", "relation_type": "kin", "evidence": "编造的句子", "confidence": 0.9},
+            {"from": "林舟", "to": "老周", "relation_type": "student", "evidence": "周伯把林舟拉到身后", "confidence": 0.2}
+        ]"#;
+        let proposals = context.parse(&chapter, &chunk, response, DEFAULT_MIN_CONFIDENCE);
+        let summary: Vec<(&str, &str, &str)> =
+            proposals.iter().map(|p| (p.kind.as_str(), p.from_name.as_str(), p.relation_type.as_str())).collect();
+        assert_eq!(summary, vec![("changed", "沈青", "enemy"), ("new", "老周", "guardian")]);
+        assert_eq!(proposals[0].existing_relation_id.as_deref(), Some("x1"));
+
+        assert_eq!(accept_proposals(&conn, "p1", &proposals).unwrap(), 2);
+        let types: Vec<String> = conn
+            .prepare("SELECT relation_type FROM character_relations ORDER BY relation_type")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(types, vec!["enemy", "guardian"]);
+    }
+}
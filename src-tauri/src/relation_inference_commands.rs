@@ -0,0 +1,64 @@
+use crate::logger::Logger;
+use crate::relation_inference::{self, RelationProposal};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// 让 AI 通读章节，提出新的或已变化的人物关系，附带原文依据和置信度；`chapter_ids` 为空时分析全部章节
+#[tauri::command]
+pub async fn infer_relations(
+    app: AppHandle,
+    project_id: String,
+    chapter_ids: Option<Vec<String>>,
+    min_confidence: Option<f64>,
+    model_id: Option<String>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("relation_inference");
+    logger.info(&format!("Inferring relations for project {}", project_id));
+
+    let context = {
+        let db_path = get_db_path(&app)?;
+        let conn = crate::database::get_connection(&db_path)
+            .map_err(|e| format!("Failed to get database connection: {}", e))?;
+        relation_inference::load_context(&conn, &project_id, chapter_ids.as_deref())?
+    };
+
+    let ai_service = app.state::<std::sync::Arc<tokio::sync::RwLock<crate::ai::AIService>>>();
+    let service = ai_service.read().await;
+    let model_id = model_id.unwrap_or_else(|| "glm-4-flash".to_string());
+    let min_confidence = min_confidence.unwrap_or(relation_inference::DEFAULT_MIN_CONFIDENCE);
+    let proposals = relation_inference::infer_with_ai(&service, &model_id, &context, min_confidence)
+        .await
+        .map_err(|e| {
+            logger.error(&format!("Failed to infer relations: {}", e));
+            e
+        })?;
+
+    serde_json::to_string(&proposals).map_err(|e| e.to_string())
+}
+
+/// 采纳用户勾选的关系建议，写入 character_relations，返回写入的条数
+#[tauri::command]
+pub async fn accept_relation_proposals(
+    app: AppHandle,
+    project_id: String,
+    proposals: Vec<RelationProposal>,
+) -> Result<usize, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    relation_inference::accept_proposals(&conn, &project_id, &proposals)
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
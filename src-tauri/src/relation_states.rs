@@ -0,0 +1,284 @@
+use crate::models::{CharacterEdge, CharacterNode};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// 某段关系在某一章的状态：强度 0-100，好感 -1（敌对）到 1（亲密），status 是“疏远”“结盟”之类的描述
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelationState {
+    pub id: String,
+    pub relation_id: String,
+    pub chapter_id: String,
+    pub strength: i32,
+    pub sentiment: f64,
+    pub status: Option<String>,
+    pub note: Option<String>,
+    pub updated_at: String,
+}
+
+/// 截至某章的关系边，`state` 是该章及之前最近的一条状态记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeAsOf {
+    #[serde(flatten)]
+    pub edge: CharacterEdge,
+    pub state: Option<RelationState>,
+}
+
+/// 截至某章的人物关系图
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphAsOf {
+    pub chapter_id: String,
+    pub chapter_index: usize,
+    pub nodes: Vec<CharacterNode>,
+    pub edges: Vec<EdgeAsOf>,
+}
+
+/// 关系变化曲线上的一点，`changed` 表示 status 与上一点不同
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatePoint {
+    pub chapter_id: String,
+    pub chapter_title: String,
+    pub chapter_index: usize,
+    pub strength: i32,
+    pub sentiment: f64,
+    pub status: Option<String>,
+    pub changed: bool,
+}
+
+/// 一段关系在全书中的变化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelationTrack {
+    pub relation_id: String,
+    pub from_name: String,
+    pub to_name: String,
+    pub relation_type: String,
+    pub points: Vec<StatePoint>,
+}
+
+fn state_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<RelationState> {
+    Ok(RelationState {
+        id: row.get(0)?,
+        relation_id: row.get(1)?,
+        chapter_id: row.get(2)?,
+        strength: row.get(3)?,
+        sentiment: row.get(4)?,
+        status: row.get(5)?,
+        note: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+/// 项目章节按阅读顺序的 (id, title)
+fn chapter_order(conn: &Connection, project_id: &str) -> Result<Vec<(String, String)>, String> {
+    conn.prepare("SELECT id, title FROM chapters WHERE project_id = ? ORDER BY sort_order, created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 项目全部状态记录，按关系分组
+fn states_by_relation(conn: &Connection, project_id: &str) -> Result<HashMap<String, Vec<RelationState>>, String> {
+    let states: Vec<RelationState> = conn
+        .prepare(
+            "SELECT s.id, s.relation_id, s.chapter_id, s.strength, s.sentiment, s.status, s.note, s.updated_at
+             FROM character_relation_states s JOIN character_relations r ON r.id = s.relation_id WHERE r.project_id = ?",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], state_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut grouped: HashMap<String, Vec<RelationState>> = HashMap::new();
+    for state in states {
+        grouped.entry(state.relation_id.clone()).or_default().push(state);
+    }
+    Ok(grouped)
+}
+
+/// 记录关系在某章的状态，同一章已有记录时覆盖；强度和好感超出范围时截断
+pub fn record_state(
+    conn: &Connection,
+    relation_id: &str,
+    chapter_id: &str,
+    strength: i32,
+    sentiment: f64,
+    status: Option<&str>,
+    note: Option<&str>,
+) -> Result<RelationState, String> {
+    let same_project: Option<bool> = conn
+        .query_row(
+            "SELECT r.project_id = c.project_id FROM character_relations r, chapters c WHERE r.id = ? AND c.id = ?",
+            params![relation_id, chapter_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match same_project {
+        None => return Err("关系或章节不存在".to_string()),
+        Some(false) => return Err("关系和章节不属于同一项目".to_string()),
+        Some(true) => {}
+    }
+    let status = status.map(str::trim).filter(|s| !s.is_empty());
+    conn.execute(
+        "INSERT INTO character_relation_states (id, relation_id, chapter_id, strength, sentiment, status, note, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)
+         ON CONFLICT(relation_id, chapter_id) DO UPDATE SET
+             strength = excluded.strength, sentiment = excluded.sentiment, status = excluded.status,
+             note = excluded.note, updated_at = excluded.updated_at",
+        params![
+            Uuid::new_v4().to_string(),
+            relation_id,
+            chapter_id,
+            strength.clamp(0, 100),
+            sentiment.clamp(-1.0, 1.0),
+            status,
+            note,
+            Utc::now().to_rfc3339(),
+        ],
+    )
+    .map_err(|e| format!("Failed to save relation state: {}", e))?;
+    conn.query_row(
+        "SELECT id, relation_id, chapter_id, strength, sentiment, status, note, updated_at
+         FROM character_relation_states WHERE relation_id = ? AND chapter_id = ?",
+        params![relation_id, chapter_id],
+        state_from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
+pub fn delete_state(conn: &Connection, state_id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM character_relation_states WHERE id = ?", params![state_id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 截至 `chapter_id`（含）的关系图：有状态记录的关系从第一条记录所在章起才出现，
+/// 没有任何记录的关系视为一直存在
+pub fn graph_as_of(conn: &Connection, project_id: &str, chapter_id: &str) -> Result<GraphAsOf, String> {
+    let chapters = chapter_order(conn, project_id)?;
+    let chapter_index = chapters.iter().position(|(id, _)| id == chapter_id).ok_or_else(|| "章节不存在".to_string())?;
+    let index: HashMap<&str, usize> = chapters.iter().enumerate().map(|(i, (id, _))| (id.as_str(), i)).collect();
+    let mut states = states_by_relation(conn, project_id)?;
+
+    let nodes: Vec<CharacterNode> = conn
+        .prepare("SELECT id, name, avatar_url FROM characters WHERE project_id = ? AND merged_into IS NULL ORDER BY created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok(CharacterNode { id: row.get(0)?, name: row.get(1)?, avatar_url: row.get(2)? }))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let relations: Vec<CharacterEdge> = conn
+        .prepare(
+            "SELECT r.id, r.from_character_id, r.to_character_id, r.relation_type, r.description FROM character_relations r
+             JOIN characters c1 ON c1.id = r.from_character_id JOIN characters c2 ON c2.id = r.to_character_id
+             WHERE r.project_id = ? AND c1.merged_into IS NULL AND c2.merged_into IS NULL ORDER BY r.created_at",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| {
+            Ok(CharacterEdge { id: row.get(0)?, from: row.get(1)?, to: row.get(2)?, label: row.get(3)?, description: row.get(4)? })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut edges = Vec::new();
+    for edge in relations {
+        let recorded: Vec<(usize, RelationState)> = states
+            .remove(&edge.id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|s| index.get(s.chapter_id.as_str()).map(|&i| (i, s)))
+            .collect();
+        if recorded.is_empty() {
+            edges.push(EdgeAsOf { edge, state: None });
+            continue;
+        }
+        if let Some((_, state)) = recorded.into_iter().filter(|(i, _)| *i <= chapter_index).max_by_key(|(i, _)| *i) {
+            edges.push(EdgeAsOf { edge, state: Some(state) });
+        }
+    }
+    Ok(GraphAsOf { chapter_id: chapter_id.to_string(), chapter_index, nodes, edges })
+}
+
+/// 全书中关系的变化曲线，按章节顺序排列；`relation_id` 指定时只返回这一段关系，没有状态记录的关系不返回
+pub fn evolution(conn: &Connection, project_id: &str, relation_id: Option<&str>) -> Result<Vec<RelationTrack>, String> {
+    let chapters = chapter_order(conn, project_id)?;
+    let mut states = states_by_relation(conn, project_id)?;
+    let relations: Vec<(String, String, String, String)> = conn
+        .prepare(
+            "SELECT r.id, c1.name, c2.name, r.relation_type FROM character_relations r
+             JOIN characters c1 ON c1.id = r.from_character_id JOIN characters c2 ON c2.id = r.to_character_id
+             WHERE r.project_id = ?1 AND (?2 IS NULL OR r.id = ?2) ORDER BY r.created_at",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id, relation_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut tracks = Vec::new();
+    for (relation_id, from_name, to_name, relation_type) in relations {
+        let mut recorded = states.remove(&relation_id).unwrap_or_default();
+        let mut points: Vec<StatePoint> = Vec::new();
+        for (chapter_index, (chapter_id, chapter_title)) in chapters.iter().enumerate() {
+            let Some(i) = recorded.iter().position(|s| &s.chapter_id == chapter_id) else { continue };
+            let state = recorded.swap_remove(i);
+            let changed = points.last().is_some_and(|p| p.status != state.status);
+            points.push(StatePoint {
+                chapter_id: chapter_id.clone(),
+                chapter_title: chapter_title.clone(),
+                chapter_index,
+                strength: state.strength,
+                sentiment: state.sentiment,
+                status: state.status,
+                changed,
+            });
+        }
+        if !points.is_empty() {
+            tracks.push(RelationTrack { relation_id, from_name, to_name, relation_type, points });
+        }
+    }
+    Ok(tracks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graph_as_of_and_evolution() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES
+                 ('r1', 'p1', '林舟', 't0', 't0'), ('r2', 'p1', '沈青', 't0', 't0'), ('r3', 'p1', '老周', 't0', 't0');
+             INSERT INTO character_relations (id, project_id, from_character_id, to_character_id, relation_type, created_at, updated_at) VALUES
+                 ('x1', 'p1', 'r1', 'r2', 'friend', 't0', 't0'), ('x2', 'p1', 'r3', 'r1', 'mentor', 't1', 't1');
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '', 1, 't0', 't0'), ('c2', 'p1', '第二章', '', 2, 't0', 't0'), ('c3', 'p1', '第三章', '', 3, 't0', 't0');",
+        )
+        .unwrap();
+        record_state(&conn, "x1", "c1", 80, 0.9, Some("结盟"), None).unwrap();
+        record_state(&conn, "x1", "c3", 30, -0.5, Some("疏远"), None).unwrap();
+        let overwritten = record_state(&conn, "x1", "c3", 150, -2.0, Some("决裂"), Some("背叛")).unwrap();
+        assert_eq!((overwritten.strength, overwritten.sentiment), (100, -1.0));
+        record_state(&conn, "x2", "c2", 60, 0.4, None, None).unwrap();
+
+        let first = graph_as_of(&conn, "p1", "c1").unwrap();
+        assert_eq!(first.edges.len(), 1);
+        assert_eq!(first.edges[0].state.as_ref().unwrap().status.as_deref(), Some("结盟"));
+        let second = graph_as_of(&conn, "p1", "c2").unwrap();
+        let statuses: Vec<Option<&str>> =
+            second.edges.iter().map(|e| e.state.as_ref().and_then(|s| s.status.as_deref())).collect();
+        assert_eq!(statuses, vec![Some("结盟"), None]);
+        let third = graph_as_of(&conn, "p1", "c3").unwrap();
+        assert_eq!(third.edges[0].state.as_ref().unwrap().status.as_deref(), Some("决裂"));
+
+        let tracks = evolution(&conn, "p1", Some("x1")).unwrap();
+        let points: Vec<(usize, bool)> = tracks[0].points.iter().map(|p| (p.chapter_index, p.changed)).collect();
+        assert_eq!(points, vec![(0, false), (2, true)]);
+        assert_eq!(evolution(&conn, "p1", None).unwrap().len(), 2);
+    }
+}
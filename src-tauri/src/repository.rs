@@ -0,0 +1,99 @@
+use crate::models::{Chapter, Project};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult, Row};
+
+/// 项目、章节的共享查询，供命令层和导出模块共用，保证列名与模型一致
+pub struct ProjectRepository;
+
+const PROJECT_COLUMNS: &str = "id, name, description, genre, template, status, created_at, updated_at";
+const CHAPTER_COLUMNS: &str = "id, project_id, title, content, word_count, sort_order, status, created_at, updated_at, summary";
+
+impl ProjectRepository {
+    pub fn list_projects(conn: &Connection) -> SqlResult<Vec<Project>> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM projects ORDER BY updated_at DESC",
+            PROJECT_COLUMNS
+        ))?;
+        let projects = stmt.query_map([], project_from_row)?.collect();
+        projects
+    }
+
+    pub fn get_project(conn: &Connection, project_id: &str) -> SqlResult<Option<Project>> {
+        conn.query_row(
+            &format!("SELECT {} FROM projects WHERE id = ?1", PROJECT_COLUMNS),
+            params![project_id],
+            project_from_row,
+        )
+        .optional()
+    }
+
+    /// 按 sort_order 排序返回项目下的全部章节
+    pub fn list_chapters(conn: &Connection, project_id: &str) -> SqlResult<Vec<Chapter>> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM chapters WHERE project_id = ?1 ORDER BY sort_order ASC",
+            CHAPTER_COLUMNS
+        ))?;
+        let chapters = stmt.query_map(params![project_id], chapter_from_row)?.collect();
+        chapters
+    }
+
+    pub fn get_chapter(conn: &Connection, chapter_id: &str) -> SqlResult<Option<Chapter>> {
+        conn.query_row(
+            &format!("SELECT {} FROM chapters WHERE id = ?1", CHAPTER_COLUMNS),
+            params![chapter_id],
+            chapter_from_row,
+        )
+        .optional()
+    }
+
+    /// 章节在项目中的序号（从 1 开始），按 sort_order 计算
+    pub fn chapter_number(conn: &Connection, chapter: &Chapter) -> SqlResult<usize> {
+        let before: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM chapters WHERE project_id = ?1 AND (sort_order < ?2 OR (sort_order = ?2 AND created_at < ?3))",
+            params![chapter.project_id, chapter.sort_order, chapter.created_at],
+            |row| row.get(0),
+        )?;
+        Ok(before as usize + 1)
+    }
+
+    /// 作者署名保存在 app_settings 的 author_name 中
+    pub fn get_author_name(conn: &Connection) -> SqlResult<Option<String>> {
+        conn.query_row(
+            "SELECT value FROM app_settings WHERE key = 'author_name'",
+            [],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map(|name| name.filter(|n| !n.trim().is_empty()))
+    }
+}
+
+fn project_from_row(row: &Row) -> SqlResult<Project> {
+    Ok(Project {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        genre: row.get(3)?,
+        template: row.get(4)?,
+        status: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+fn chapter_from_row(row: &Row) -> SqlResult<Chapter> {
+    Ok(Chapter {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        title: row.get(2)?,
+        content: row.get(3)?,
+        word_count: row.get(4)?,
+        sort_order: row.get(5)?,
+        status: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+        versions: None,
+        evaluation: None,
+        generation_status: None,
+        summary: row.get(9).ok(),
+    })
+}
@@ -0,0 +1,320 @@
+use crate::writing_tools::{SensitiveWord, WritingTools};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 内置词库的 id，词在代码中维护，不能修改
+pub const BUILTIN_DICTIONARY: &str = "builtin";
+const SEVERITIES: &[&str] = &["low", "medium", "high"];
+
+/// 敏感词库，`enabled` 为在指定项目中是否启用（项目没有单独设置时等于 `enabled_by_default`）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensitiveDictionary {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub builtin: bool,
+    pub enabled_by_default: bool,
+    pub enabled: bool,
+    pub word_count: usize,
+}
+
+/// 从文本导入的结果，`invalid_lines` 为无法识别的行
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DictionaryImport {
+    pub imported: usize,
+    pub invalid_lines: Vec<String>,
+}
+
+fn check_severity(severity: &str) -> Result<(), String> {
+    if SEVERITIES.contains(&severity) {
+        Ok(())
+    } else {
+        Err(format!("Unknown severity: {}", severity))
+    }
+}
+
+fn check_editable(dictionary_id: &str) -> Result<(), String> {
+    if dictionary_id == BUILTIN_DICTIONARY {
+        Err("The built-in dictionary cannot be modified".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// 所有词库，内置词库在最前；指定项目时按项目设置计算 `enabled`
+pub fn list_dictionaries(conn: &Connection, project_id: Option<&str>) -> Result<Vec<SensitiveDictionary>, String> {
+    let overrides: HashMap<String, bool> = match project_id {
+        Some(project_id) => conn
+            .prepare("SELECT dictionary_id, enabled FROM sensitive_dictionary_projects WHERE project_id = ?")
+            .map_err(|e| e.to_string())?
+            .query_map(params![project_id], |row| Ok((row.get(0)?, row.get::<_, i32>(1)? != 0)))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?,
+        None => HashMap::new(),
+    };
+
+    let mut dictionaries = vec![SensitiveDictionary {
+        id: BUILTIN_DICTIONARY.to_string(),
+        name: "内置词库".to_string(),
+        description: None,
+        builtin: true,
+        enabled_by_default: true,
+        enabled: overrides.get(BUILTIN_DICTIONARY).copied().unwrap_or(true),
+        word_count: WritingTools::builtin_sensitive_words().len(),
+    }];
+    let mut stmt = conn
+        .prepare(
+            "SELECT d.id, d.name, d.description, d.enabled_by_default,
+                    (SELECT COUNT(*) FROM sensitive_dictionary_words w WHERE w.dictionary_id = d.id)
+             FROM sensitive_dictionaries d ORDER BY d.created_at, d.name",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            let id: String = row.get(0)?;
+            let enabled_by_default = row.get::<_, i32>(3)? != 0;
+            Ok(SensitiveDictionary {
+                enabled: overrides.get(&id).copied().unwrap_or(enabled_by_default),
+                id,
+                name: row.get(1)?,
+                description: row.get(2)?,
+                builtin: false,
+                enabled_by_default,
+                word_count: row.get::<_, i64>(4)? as usize,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        dictionaries.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(dictionaries)
+}
+
+pub fn create_dictionary(
+    conn: &Connection,
+    name: &str,
+    description: Option<&str>,
+    enabled_by_default: bool,
+) -> Result<SensitiveDictionary, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Dictionary name cannot be empty".to_string());
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO sensitive_dictionaries (id, name, description, enabled_by_default, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)",
+        params![id, name, description, enabled_by_default as i32, now, now],
+    )
+    .map_err(|e| format!("Failed to create dictionary: {}", e))?;
+    Ok(SensitiveDictionary {
+        id,
+        name: name.to_string(),
+        description: description.map(|d| d.to_string()),
+        builtin: false,
+        enabled_by_default,
+        enabled: enabled_by_default,
+        word_count: 0,
+    })
+}
+
+pub fn delete_dictionary(conn: &Connection, dictionary_id: &str) -> Result<(), String> {
+    check_editable(dictionary_id)?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM sensitive_dictionary_projects WHERE dictionary_id = ?", params![dictionary_id])
+        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM sensitive_dictionaries WHERE id = ?", params![dictionary_id])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+pub fn get_words(conn: &Connection, dictionary_id: &str) -> Result<Vec<SensitiveWord>, String> {
+    if dictionary_id == BUILTIN_DICTIONARY {
+        return Ok(WritingTools::builtin_sensitive_words());
+    }
+    conn.prepare("SELECT word, severity FROM sensitive_dictionary_words WHERE dictionary_id = ? ORDER BY word")
+        .map_err(|e| e.to_string())?
+        .query_map(params![dictionary_id], |row| {
+            Ok(SensitiveWord { word: row.get(0)?, severity: row.get(1)?, dictionary: dictionary_id.to_string() })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 添加或修改词库中的词，`severity` 为 None 时删除
+pub fn set_word(conn: &Connection, dictionary_id: &str, word: &str, severity: Option<&str>) -> Result<(), String> {
+    check_editable(dictionary_id)?;
+    let word = word.trim();
+    match severity {
+        Some(severity) => {
+            check_severity(severity)?;
+            if word.is_empty() {
+                return Err("Word cannot be empty".to_string());
+            }
+            conn.execute(
+                "INSERT OR REPLACE INTO sensitive_dictionary_words (dictionary_id, word, severity) VALUES (?, ?, ?)",
+                params![dictionary_id, word, severity],
+            )
+            .map_err(|e| format!("Failed to save word: {}", e))?;
+        }
+        None => {
+            conn.execute(
+                "DELETE FROM sensitive_dictionary_words WHERE dictionary_id = ? AND word = ?",
+                params![dictionary_id, word],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// 解析 txt 词表的一行：`词` 或 `词 级别`（用空格、制表符或逗号分隔），`#` 开头为注释
+fn parse_line<'a>(line: &'a str, default_severity: &'a str) -> Option<Result<(&'a str, &'a str), ()>> {
+    let line = line.trim().trim_start_matches('\u{feff}');
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let Some(split) = line.rfind([' ', '\t', ',', '，']) else {
+        return Some(Ok((line, default_severity)));
+    };
+    let (word, severity) = (line[..split].trim(), line[split..].trim_start_matches([' ', '\t', ',', '，']).trim());
+    if word.is_empty() || !SEVERITIES.contains(&severity) {
+        return Some(Err(()));
+    }
+    Some(Ok((word, severity)))
+}
+
+/// 从 txt 内容导入词，已有的词按导入的级别覆盖
+pub fn import_words(
+    conn: &Connection,
+    dictionary_id: &str,
+    content: &str,
+    default_severity: &str,
+) -> Result<DictionaryImport, String> {
+    check_editable(dictionary_id)?;
+    check_severity(default_severity)?;
+    let exists: Option<String> = conn
+        .query_row("SELECT id FROM sensitive_dictionaries WHERE id = ?", params![dictionary_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if exists.is_none() {
+        return Err(format!("Dictionary not found: {}", dictionary_id));
+    }
+
+    let mut result = DictionaryImport::default();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for line in content.lines() {
+        match parse_line(line, default_severity) {
+            None => {}
+            Some(Err(())) => result.invalid_lines.push(line.trim().to_string()),
+            Some(Ok((word, severity))) => {
+                tx.execute(
+                    "INSERT OR REPLACE INTO sensitive_dictionary_words (dictionary_id, word, severity) VALUES (?, ?, ?)",
+                    params![dictionary_id, word, severity],
+                )
+                .map_err(|e| format!("Failed to import word: {}", e))?;
+                result.imported += 1;
+            }
+        }
+    }
+    tx.execute(
+        "UPDATE sensitive_dictionaries SET updated_at = ? WHERE id = ?",
+        params![Utc::now().to_rfc3339(), dictionary_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+/// 在项目中启用或停用词库
+pub fn set_project_enabled(conn: &Connection, project_id: &str, dictionary_id: &str, enabled: bool) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO sensitive_dictionary_projects (project_id, dictionary_id, enabled) VALUES (?, ?, ?)",
+        params![project_id, dictionary_id, enabled as i32],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn get_whitelist(conn: &Connection, project_id: &str) -> Result<Vec<String>, String> {
+    conn.prepare("SELECT word FROM sensitive_whitelist WHERE project_id = ? ORDER BY rowid")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 整体替换项目的白名单
+pub fn set_whitelist(conn: &Connection, project_id: &str, words: &[String]) -> Result<Vec<String>, String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM sensitive_whitelist WHERE project_id = ?", params![project_id])
+        .map_err(|e| e.to_string())?;
+    for word in words.iter().map(|w| w.trim()).filter(|w| !w.is_empty()) {
+        tx.execute("INSERT OR IGNORE INTO sensitive_whitelist (project_id, word) VALUES (?, ?)", params![project_id, word])
+            .map_err(|e| format!("Failed to save whitelist word: {}", e))?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    get_whitelist(conn, project_id)
+}
+
+/// 项目启用的所有词库中的词（同一个词出现在多个词库时取最高级别）和项目白名单
+pub fn active_words(conn: &Connection, project_id: &str) -> Result<(Vec<SensitiveWord>, Vec<String>), String> {
+    let rank = |severity: &str| SEVERITIES.iter().position(|s| *s == severity).unwrap_or(0);
+    let mut words: Vec<SensitiveWord> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for dictionary in list_dictionaries(conn, Some(project_id))?.into_iter().filter(|d| d.enabled) {
+        for word in get_words(conn, &dictionary.id)? {
+            match index.get(&word.word) {
+                Some(&i) if rank(&word.severity) > rank(&words[i].severity) => words[i] = word,
+                Some(_) => {}
+                None => {
+                    index.insert(word.word.clone(), words.len());
+                    words.push(word);
+                }
+            }
+        }
+    }
+    Ok((words, get_whitelist(conn, project_id)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_dictionaries_and_whitelist() {
+        let (_dir, conn) = crate::test_support::project_db();
+
+        let platform = create_dictionary(&conn, "某平台", None, false).unwrap();
+        let import = import_words(&conn, &platform.id, "# 平台词表\n赌场\n恐怖,high\n毒品\tcritical\n\n", "medium").unwrap();
+        assert_eq!((import.imported, import.invalid_lines), (2, vec!["毒品\tcritical".to_string()]));
+        assert!(set_word(&conn, BUILTIN_DICTIONARY, "赌场", Some("low")).is_err());
+
+        let text = "他走进赌场，看了一部暴力美学的恐怖片。";
+        let (words, whitelist) = active_words(&conn, "p1").unwrap();
+        let found: Vec<String> = WritingTools::detect_sensitive_words(text, &words, &whitelist)
+            .sensitive_words
+            .into_iter()
+            .map(|m| m.word)
+            .collect();
+        assert_eq!(found, vec!["暴力", "恐怖"]);
+
+        // 启用平台词库后“恐怖”取较高的级别；白名单中的“暴力美学”不再提示其中的“暴力”
+        set_project_enabled(&conn, "p1", &platform.id, true).unwrap();
+        set_whitelist(&conn, "p1", &["暴力美学".to_string()]).unwrap();
+        let (words, whitelist) = active_words(&conn, "p1").unwrap();
+        let detection = WritingTools::detect_sensitive_words(text, &words, &whitelist);
+        let found: Vec<(&str, &str, usize)> =
+            detection.sensitive_words.iter().map(|m| (m.word.as_str(), m.severity.as_str(), m.position)).collect();
+        assert_eq!(found, vec![("赌场", "medium", 3), ("恐怖", "high", 15)]);
+        assert_eq!(detection.severity, "high");
+
+        delete_dictionary(&conn, &platform.id).unwrap();
+        assert_eq!(list_dictionaries(&conn, Some("p1")).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::text_merge::split_paragraphs;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// 每个 shingle 的字数
+const SHINGLE_CHARS: usize = 5;
+/// MinHash 签名分成 BANDS 段、每段 ROWS 个值，任一段完全相同的段落才进一步比较
+const BANDS: usize = 32;
+const ROWS: usize = 4;
+pub const DEFAULT_THRESHOLD: f64 = 0.5;
+/// 短于这么多字的段落（对白、过渡句）不参与比较
+pub const DEFAULT_MIN_CHARS: usize = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParagraphRef {
+    pub chapter_id: String,
+    pub chapter_title: String,
+    /// 段落在章节正文中的序号（按换行切分，与版本对比一致）
+    pub paragraph_index: usize,
+    pub excerpt: String,
+}
+
+/// 一对相似段落，`similarity` 是两段 shingle 集合的 Jaccard 相似度，0-1
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarPair {
+    pub first: ParagraphRef,
+    pub second: ParagraphRef,
+    pub similarity: f64,
+    pub same_chapter: bool,
+}
+
+/// 去掉空白和标点后按字切成重叠的 shingle，哈希成整数
+fn shingles(text: &str) -> HashSet<u64> {
+    let chars: Vec<char> = text.chars().filter(|c| c.is_alphanumeric()).collect();
+    if chars.len() < SHINGLE_CHARS {
+        return HashSet::new();
+    }
+    chars
+        .windows(SHINGLE_CHARS)
+        .map(|window| {
+            let mut hasher = DefaultHasher::new();
+            window.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+/// splitmix64，把同一个 shingle 哈希按不同种子打散成一组独立的哈希函数
+fn mix(value: u64, seed: u64) -> u64 {
+    let mut z = value ^ seed.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn signature(shingles: &HashSet<u64>) -> Vec<u64> {
+    (0..(BANDS * ROWS) as u64).map(|seed| shingles.iter().map(|&s| mix(s, seed)).min().unwrap_or(u64::MAX)).collect()
+}
+
+fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let intersection = a.intersection(b).count();
+    intersection as f64 / (a.len() + b.len() - intersection).max(1) as f64
+}
+
+/// 找出项目中相似度不低于 `threshold` 的段落对，包括跨章节和同一章内的重复，按相似度从高到低排列
+pub fn find_similar_paragraphs(conn: &Connection, project_id: &str, threshold: f64, min_chars: usize) -> Result<Vec<SimilarPair>, String> {
+    let chapters: Vec<(String, String, String)> = conn
+        .prepare("SELECT id, title, content FROM chapters WHERE project_id = ? ORDER BY sort_order, created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, Option<String>>(2)?.unwrap_or_default())))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    // (章节序号, 段落序号, 原文, shingle 集合)
+    let mut paragraphs: Vec<(usize, usize, String, HashSet<u64>)> = Vec::new();
+    for (chapter_index, (_, _, content)) in chapters.iter().enumerate() {
+        for (paragraph_index, text) in split_paragraphs(content).into_iter().enumerate() {
+            if text.chars().filter(|c| !c.is_whitespace()).count() < min_chars {
+                continue;
+            }
+            let set = shingles(&text);
+            if !set.is_empty() {
+                paragraphs.push((chapter_index, paragraph_index, text, set));
+            }
+        }
+    }
+
+    let mut candidates: HashSet<(usize, usize)> = HashSet::new();
+    let mut buckets: HashMap<(usize, &[u64]), Vec<usize>> = HashMap::new();
+    let signatures: Vec<Vec<u64>> = paragraphs.iter().map(|p| signature(&p.3)).collect();
+    for (i, sig) in signatures.iter().enumerate() {
+        for band in 0..BANDS {
+            let bucket = buckets.entry((band, &sig[band * ROWS..(band + 1) * ROWS])).or_default();
+            candidates.extend(bucket.iter().map(|&j| (j, i)));
+            bucket.push(i);
+        }
+    }
+
+    let reference = |index: usize| {
+        let (chapter_index, paragraph_index, text, _) = &paragraphs[index];
+        let (chapter_id, chapter_title, _) = &chapters[*chapter_index];
+        ParagraphRef {
+            chapter_id: chapter_id.clone(),
+            chapter_title: chapter_title.clone(),
+            paragraph_index: *paragraph_index,
+            excerpt: text.trim().chars().take(40).collect(),
+        }
+    };
+    let mut pairs: Vec<SimilarPair> = candidates
+        .into_iter()
+        .filter_map(|(i, j)| {
+            let similarity = jaccard(&paragraphs[i].3, &paragraphs[j].3);
+            (similarity >= threshold).then(|| SimilarPair {
+                first: reference(i),
+                second: reference(j),
+                similarity,
+                same_chapter: paragraphs[i].0 == paragraphs[j].0,
+            })
+        })
+        .collect();
+    pairs.sort_by(|a, b| {
+        b.similarity
+            .total_cmp(&a.similarity)
+            .then_with(|| (&a.first.chapter_id, a.first.paragraph_index).cmp(&(&b.first.chapter_id, b.first.paragraph_index)))
+    });
+    Ok(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recycled_description_found_across_chapters() {
+        let (_dir, conn) = crate::test_support::project_db();
+        let description = "夕阳的余晖洒在古老的城墙上，将斑驳的砖石染成一片温暖的金红色，远处传来悠长的钟声";
+        let contents = [
+            format!("{}。\n他走进城门。", description),
+            "城中的集市人声鼎沸，小贩们吆喝着各自的货物，孩童在人群中追逐嬉闹，一派热闹景象。".to_string(),
+            format!("她抬起头。\n{}，风吹过城头。", description.replace("温暖的", "")),
+        ];
+        for (i, content) in contents.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES (?, 'p1', ?, ?, ?, 't0', 't0')",
+                params![format!("c{}", i), format!("第{}章", i + 1), content, i as i64],
+            )
+            .unwrap();
+        }
+
+        let pairs = find_similar_paragraphs(&conn, "p1", DEFAULT_THRESHOLD, 20).unwrap();
+        assert_eq!(pairs.len(), 1);
+        let pair = &pairs[0];
+        assert_eq!((pair.first.chapter_id.as_str(), pair.second.chapter_id.as_str()), ("c0", "c2"));
+        assert_eq!((pair.first.paragraph_index, pair.second.paragraph_index), (0, 1));
+        assert!(pair.similarity > 0.6 && pair.similarity < 1.0 && !pair.same_chapter);
+        assert!(find_similar_paragraphs(&conn, "p1", DEFAULT_THRESHOLD, 100).unwrap().is_empty());
+    }
+}
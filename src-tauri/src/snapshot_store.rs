@@ -0,0 +1,217 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+/// 快照正文直接以 JSON 保存在快照行中
+pub const INLINE: &str = "inline";
+/// 快照正文列只保存块哈希数组，内容在 `snapshot_blobs` 中
+pub const CHUNKED: &str = "chunked";
+
+/// 快照中按块存储的正文列：每个章节、角色、世界观、情节点各为一块
+pub const BODY_COLUMNS: [&str; 4] = ["chapters_json", "characters_json", "world_views_json", "plot_points_json"];
+
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// 快照存储的去重统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageStats {
+    pub snapshot_count: i64,
+    pub chunked_snapshot_count: i64,
+    /// 被引用的不同块的数量
+    pub blob_count: i64,
+    /// 不去重、不压缩时正文占用的字节数
+    pub logical_bytes: i64,
+    /// 实际占用的字节数
+    pub stored_bytes: i64,
+    /// `logical_bytes / stored_bytes`
+    pub dedup_ratio: f64,
+}
+
+fn put_chunk(conn: &Connection, value: &Value) -> Result<String, String> {
+    let data = serde_json::to_vec(value).map_err(|e| e.to_string())?;
+    let hash = format!("{:x}", Sha256::digest(&data));
+    let exists = conn
+        .query_row("SELECT 1 FROM snapshot_blobs WHERE hash = ?", params![hash], |_| Ok(()))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .is_some();
+    if !exists {
+        let compressed = zstd::encode_all(data.as_slice(), COMPRESSION_LEVEL).map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO snapshot_blobs (hash, data, size, compressed_size, created_at) VALUES (?, ?, ?, ?, ?)",
+            params![hash, compressed, data.len() as i64, compressed.len() as i64, chrono::Utc::now().to_rfc3339()],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(hash)
+}
+
+fn get_chunk(conn: &Connection, hash: &str) -> Result<Value, String> {
+    let data: Vec<u8> = conn
+        .query_row("SELECT data FROM snapshot_blobs WHERE hash = ?", params![hash], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("快照数据块 {} 丢失", hash))?;
+    let data = zstd::decode_all(data.as_slice()).map_err(|e| e.to_string())?;
+    serde_json::from_slice(&data).map_err(|e| e.to_string())
+}
+
+/// 把列表的每一项存为一个块，返回块哈希数组的 JSON
+pub fn store_items<T: Serialize>(conn: &Connection, items: &[T]) -> Result<String, String> {
+    let hashes = items
+        .iter()
+        .map(|item| put_chunk(conn, &serde_json::to_value(item).map_err(|e| e.to_string())?))
+        .collect::<Result<Vec<_>, _>>()?;
+    serde_json::to_string(&hashes).map_err(|e| e.to_string())
+}
+
+/// 按块哈希数组重新拼出列表的 JSON
+pub fn load_items(conn: &Connection, hashes: &str) -> Result<String, String> {
+    let hashes: Vec<String> = serde_json::from_str(hashes).map_err(|e| e.to_string())?;
+    let items = hashes.iter().map(|hash| get_chunk(conn, hash)).collect::<Result<Vec<_>, _>>()?;
+    serde_json::to_string(&items).map_err(|e| e.to_string())
+}
+
+/// 单独存一项（如世界观条目的历史版本），返回块哈希
+pub fn store_item<T: Serialize>(conn: &Connection, item: &T) -> Result<String, String> {
+    put_chunk(conn, &serde_json::to_value(item).map_err(|e| e.to_string())?)
+}
+
+pub fn load_item<T: serde::de::DeserializeOwned>(conn: &Connection, hash: &str) -> Result<T, String> {
+    serde_json::from_value(get_chunk(conn, hash)?).map_err(|e| e.to_string())
+}
+
+/// 把快照行的正文列还原为完整 JSON，`storage` 为快照行的存储方式
+pub fn inline_bodies(conn: &Connection, storage: &str, bodies: [String; 4]) -> Result<[String; 4], String> {
+    if storage != CHUNKED {
+        return Ok(bodies);
+    }
+    let [chapters, characters, world_views, plot_points] = bodies;
+    Ok([
+        load_items(conn, &chapters)?,
+        load_items(conn, &characters)?,
+        load_items(conn, &world_views)?,
+        load_items(conn, &plot_points)?,
+    ])
+}
+
+/// 同步导出的快照行一律还原为内联存储，其他设备上没有对应的数据块
+pub fn inline_sync_row(conn: &Connection, row: &mut Map<String, Value>) -> Result<(), String> {
+    if row.get("storage").and_then(Value::as_str) != Some(CHUNKED) {
+        return Ok(());
+    }
+    for column in BODY_COLUMNS {
+        let hashes = row.get(column).and_then(Value::as_str).unwrap_or("[]").to_string();
+        row.insert(column.to_string(), Value::String(load_items(conn, &hashes)?));
+    }
+    row.insert("storage".to_string(), Value::String(INLINE.to_string()));
+    Ok(())
+}
+
+/// 分块存储的快照和世界观历史版本引用的全部块哈希（可重复），`project_id` 为空时统计所有项目
+fn referenced_hashes_sql() -> String {
+    BODY_COLUMNS
+        .iter()
+        .map(|column| {
+            format!(
+                "SELECT j.value AS hash FROM project_snapshots s, json_each(s.{}) j
+                 WHERE s.storage = 'chunked' AND (?1 IS NULL OR s.project_id = ?1)",
+                column
+            )
+        })
+        .chain([
+            "SELECT blob_hash AS hash FROM worldview_revisions WHERE ?1 IS NULL OR project_id = ?1".to_string(),
+            "SELECT blob_hash AS hash FROM knowledge_revisions WHERE ?1 IS NULL OR project_id = ?1".to_string(),
+        ])
+        .collect::<Vec<_>>()
+        .join(" UNION ALL ")
+}
+
+/// 删除不再被任何快照引用的块，返回删除的数量
+pub fn remove_orphan_chunks(conn: &Connection) -> Result<usize, String> {
+    let sql = format!("DELETE FROM snapshot_blobs WHERE hash NOT IN ({})", referenced_hashes_sql());
+    conn.execute(&sql, params![None::<String>]).map_err(|e| e.to_string())
+}
+
+pub fn storage_stats(conn: &Connection, project_id: Option<&str>) -> Result<StorageStats, String> {
+    let (snapshot_count, chunked_snapshot_count, inline_bytes, hash_list_bytes) = conn
+        .query_row(
+            "SELECT COUNT(*),
+                    COALESCE(SUM(storage = 'chunked'), 0),
+                    COALESCE(SUM(CASE WHEN storage = 'chunked' THEN 0 ELSE
+                        LENGTH(CAST(chapters_json AS BLOB)) + LENGTH(CAST(characters_json AS BLOB))
+                        + LENGTH(CAST(world_views_json AS BLOB)) + LENGTH(CAST(plot_points_json AS BLOB)) END), 0),
+                    COALESCE(SUM(CASE WHEN storage = 'chunked' THEN
+                        LENGTH(chapters_json) + LENGTH(characters_json) + LENGTH(world_views_json) + LENGTH(plot_points_json)
+                        ELSE 0 END), 0)
+             FROM project_snapshots WHERE ?1 IS NULL OR project_id = ?1",
+            params![project_id],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?, row.get::<_, i64>(3)?)),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let references = referenced_hashes_sql();
+    let chunk_bytes: i64 = conn
+        .query_row(
+            &format!("SELECT COALESCE(SUM(b.size), 0) FROM ({}) r JOIN snapshot_blobs b ON b.hash = r.hash", references),
+            params![project_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let (blob_count, compressed_bytes) = conn
+        .query_row(
+            &format!("SELECT COUNT(*), COALESCE(SUM(compressed_size), 0) FROM snapshot_blobs WHERE hash IN ({})", references),
+            params![project_id],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let logical_bytes = inline_bytes + chunk_bytes;
+    let stored_bytes = inline_bytes + hash_list_bytes + compressed_bytes;
+    Ok(StorageStats {
+        snapshot_count,
+        chunked_snapshot_count,
+        blob_count,
+        logical_bytes,
+        stored_bytes,
+        dedup_ratio: if stored_bytes > 0 { logical_bytes as f64 / stored_bytes as f64 } else { 1.0 },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version_control_commands::{load_snapshot, save_project_snapshot};
+
+    #[test]
+    fn test_chunks_are_shared_between_snapshots() {
+        let (_dir, conn) = crate::test_support::project_db();
+        let long_text = "雨一直下。".repeat(400);
+        for (id, order) in [("c1", 1), ("c2", 2)] {
+            conn.execute(
+                "INSERT INTO chapters (id, project_id, title, content, sort_order, word_count, created_at, updated_at)
+                 VALUES (?, 'p1', ?, ?, ?, 2000, 't0', 't0')",
+                params![id, id, long_text, order],
+            )
+            .unwrap();
+        }
+
+        let first = save_project_snapshot(&conn, "p1", "v1", "第一版", false).unwrap();
+        conn.execute("UPDATE chapters SET content = '改写后的第二章' WHERE id = 'c2'", []).unwrap();
+        let second = save_project_snapshot(&conn, "p1", "v2", "第二版", true).unwrap();
+
+        // 两个快照共用未改动的第一章
+        let stats = storage_stats(&conn, Some("p1")).unwrap();
+        assert_eq!((stats.snapshot_count, stats.chunked_snapshot_count, stats.blob_count), (2, 2, 3));
+        assert!(stats.stored_bytes * 4 < stats.logical_bytes);
+
+        let loaded = load_snapshot(&conn, &second.id).unwrap();
+        assert_eq!(loaded.chapters[0].content, long_text);
+        assert_eq!(loaded.chapters[1].content, "改写后的第二章");
+
+        conn.execute("DELETE FROM project_snapshots WHERE id = ?", params![first.id]).unwrap();
+        assert_eq!(remove_orphan_chunks(&conn).unwrap(), 1);
+        assert_eq!(load_snapshot(&conn, &second.id).unwrap().chapters[0].content, long_text);
+    }
+}
@@ -0,0 +1,132 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 快照上的标签，如“已投稿”“参赛版本”。带标签的快照不会被保留策略清理
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotTag {
+    pub id: String,
+    pub snapshot_id: String,
+    pub project_id: String,
+    pub tag: String,
+    pub note: Option<String>,
+    /// 标记为里程碑的快照在时间线上突出显示
+    pub milestone: bool,
+    pub created_at: String,
+}
+
+/// 给快照打标签；同一快照上的同名标签会更新备注和里程碑标记
+pub fn tag_snapshot(
+    conn: &Connection,
+    snapshot_id: &str,
+    tag: &str,
+    note: Option<&str>,
+    milestone: bool,
+) -> Result<SnapshotTag, String> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err("标签不能为空".to_string());
+    }
+    let project_id: String = conn
+        .query_row("SELECT project_id FROM project_snapshots WHERE id = ?", params![snapshot_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("快照不存在: {}", snapshot_id))?;
+    conn.execute(
+        "INSERT INTO snapshot_tags (id, snapshot_id, project_id, tag, note, milestone, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(snapshot_id, tag) DO UPDATE SET note = excluded.note, milestone = excluded.milestone",
+        params![uuid::Uuid::new_v4().to_string(), snapshot_id, project_id, tag, note, milestone, Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.query_row(
+        "SELECT id, snapshot_id, project_id, tag, note, milestone, created_at FROM snapshot_tags WHERE snapshot_id = ? AND tag = ?",
+        params![snapshot_id, tag],
+        read_tag,
+    )
+    .map_err(|e| e.to_string())
+}
+
+pub fn untag_snapshot(conn: &Connection, tag_id: &str) -> Result<(), String> {
+    let deleted = conn.execute("DELETE FROM snapshot_tags WHERE id = ?", params![tag_id]).map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err("标签不存在".to_string());
+    }
+    Ok(())
+}
+
+fn read_tag(row: &rusqlite::Row) -> rusqlite::Result<SnapshotTag> {
+    Ok(SnapshotTag {
+        id: row.get(0)?,
+        snapshot_id: row.get(1)?,
+        project_id: row.get(2)?,
+        tag: row.get(3)?,
+        note: row.get(4)?,
+        milestone: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+/// 项目的全部快照标签，按打标签的时间排序
+pub fn list_tags(conn: &Connection, project_id: &str) -> Result<Vec<SnapshotTag>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, snapshot_id, project_id, tag, note, milestone, created_at
+             FROM snapshot_tags WHERE project_id = ? ORDER BY created_at",
+        )
+        .map_err(|e| e.to_string())?;
+    let tags = stmt
+        .query_map(params![project_id], read_tag)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(tags)
+}
+
+/// 按快照分组的标签
+pub fn tags_by_snapshot(conn: &Connection, project_id: &str) -> Result<HashMap<String, Vec<SnapshotTag>>, String> {
+    let mut grouped: HashMap<String, Vec<SnapshotTag>> = HashMap::new();
+    for tag in list_tags(conn, project_id)? {
+        grouped.entry(tag.snapshot_id.clone()).or_default().push(tag);
+    }
+    Ok(grouped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auto_snapshot::prune_auto_snapshots;
+
+    #[test]
+    fn test_tagged_snapshots_survive_pruning() {
+        let (_dir, conn) = crate::test_support::project_db();
+        // 三个同一天、相隔两小时的旧自动快照，按保留策略只留最新的一个
+        let day = (Utc::now().timestamp() / 86400 - 30) * 86400;
+        for (id, offset) in [("s1", 0), ("s2", 7200), ("s3", 14400)] {
+            conn.execute(
+                "INSERT INTO project_snapshots (id, project_id, version, timestamp, description, chapters_json, characters_json,
+                 world_views_json, plot_points_json, metadata_json, auto_generated, created_at)
+                 VALUES (?, 'p1', ?, ?, '', '[]', '[]', '[]', '[]', '{}', 1, 't0')",
+                params![id, id, day + offset],
+            )
+            .unwrap();
+        }
+
+        let tag = tag_snapshot(&conn, "s1", " 已投稿 ", Some("发给编辑的版本"), true).unwrap();
+        assert_eq!(tag.tag, "已投稿");
+        let updated = tag_snapshot(&conn, "s1", "已投稿", None, false).unwrap();
+        assert_eq!((updated.id.as_str(), updated.note, updated.milestone), (tag.id.as_str(), None, false));
+        assert!(tag_snapshot(&conn, "missing", "参赛版本", None, false).is_err());
+
+        assert_eq!(prune_auto_snapshots(&conn, "p1").unwrap(), 1);
+        let remaining: Vec<String> = conn
+            .prepare("SELECT id FROM project_snapshots ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(remaining, vec!["s1", "s3"]);
+        assert_eq!(tags_by_snapshot(&conn, "p1").unwrap()["s1"].len(), 1);
+    }
+}
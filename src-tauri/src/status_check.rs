@@ -0,0 +1,245 @@
+use crate::character_growth::{GrowthChange, GrowthChangeType};
+use crate::character_presence::{aliases_by_character, speaker};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 状态关键词和归类，先匹配的优先；“假死”一类写在前面排除
+const STATUS_WORDS: &[(&str, Option<&str>)] = &[
+    ("假死", None),
+    ("死亡", Some("deceased")), ("身亡", Some("deceased")), ("已死", Some("deceased")), ("阵亡", Some("deceased")),
+    ("去世", Some("deceased")), ("逝世", Some("deceased")), ("病逝", Some("deceased")), ("牺牲", Some("deceased")),
+    ("殒命", Some("deceased")), ("遇害", Some("deceased")), ("被杀", Some("deceased")), ("deceased", Some("deceased")),
+    ("dead", Some("deceased")),
+    ("出狱", None), ("获释", None),
+    ("入狱", Some("imprisoned")), ("囚禁", Some("imprisoned")), ("关押", Some("imprisoned")), ("被捕", Some("imprisoned")),
+    ("被俘", Some("imprisoned")), ("imprisoned", Some("imprisoned")), ("captured", Some("imprisoned")),
+    ("失踪", Some("missing")), ("下落不明", Some("missing")), ("不知所踪", Some("missing")), ("missing", Some("missing")),
+];
+/// 句中出现这些词时，角色多半是在回忆或被提起，不算出场
+const REMINISCENCE_WORDS: &[&str] = &[
+    "想起", "回忆", "记得", "生前", "遗", "墓", "灵位", "梦", "曾经", "当年", "从前", "死", "牺牲", "尸", "下落", "消息",
+];
+
+/// 把状态描述归类为 deceased、imprisoned 或 missing，其他状态（包括“获救”“归来”）返回 None
+pub fn classify(status: &str) -> Option<&'static str> {
+    let status = status.to_lowercase();
+    STATUS_WORDS.iter().find(|(word, _)| status.contains(word)).and_then(|(_, kind)| *kind)
+}
+
+/// 角色在某章起进入的受限状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusPeriod {
+    pub character_id: String,
+    pub name: String,
+    /// deceased、imprisoned 或 missing
+    pub status: String,
+    /// 成长记录中写的状态原文
+    pub status_label: String,
+    pub since_chapter_id: String,
+    pub since_chapter_title: String,
+    /// 状态解除的章节，仍在持续时为 None
+    pub until_chapter_id: Option<String>,
+}
+
+/// `kind` 为 dialogue（仍在说话）或 appearance（作为主语行动）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusViolation {
+    pub character_id: String,
+    pub name: String,
+    pub status: String,
+    pub status_label: String,
+    pub since_chapter_id: String,
+    pub chapter_id: String,
+    pub chapter_title: String,
+    pub kind: String,
+    pub severity: String,
+    pub count: usize,
+    /// 这一章第一处违例的原文
+    pub excerpt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusReport {
+    pub periods: Vec<StatusPeriod>,
+    pub violations: Vec<StatusViolation>,
+}
+
+/// 受限状态区间，`since`/`until` 是章节下标，左闭右开
+struct Period {
+    character_id: String,
+    status: &'static str,
+    label: String,
+    since: usize,
+    until: Option<usize>,
+}
+
+/// 从成长记录中的状态变化推出每个角色的受限状态区间
+fn status_periods(conn: &Connection, project_id: &str, chapter_index: &HashMap<String, usize>) -> Result<Vec<Period>, String> {
+    let records: Vec<(String, String, String)> = conn
+        .prepare(
+            "SELECT g.character_id, g.chapter_id, g.changes_json FROM character_growth_records g
+             JOIN characters c ON c.id = g.character_id
+             WHERE c.project_id = ? AND c.merged_into IS NULL ORDER BY g.position, g.created_at",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut changes: Vec<(String, usize, String)> = Vec::new();
+    for (character_id, chapter_id, changes_json) in records {
+        let Some(&index) = chapter_index.get(&chapter_id) else { continue };
+        let parsed: Vec<GrowthChange> = serde_json::from_str(&changes_json).unwrap_or_default();
+        for change in parsed.into_iter().filter(|c| c.change_type == GrowthChangeType::Status) {
+            let label = change.after.unwrap_or(change.description);
+            changes.push((character_id.clone(), index, label));
+        }
+    }
+    changes.sort_by_key(|(character_id, index, _)| (character_id.clone(), *index));
+
+    let mut periods: Vec<Period> = Vec::new();
+    let mut open: HashMap<String, usize> = HashMap::new();
+    for (character_id, index, label) in changes {
+        if let Some(i) = open.remove(&character_id) {
+            periods[i].until = Some(index);
+        }
+        if let Some(status) = classify(&label) {
+            open.insert(character_id.clone(), periods.len());
+            periods.push(Period { character_id, status, label, since: index, until: None });
+        }
+    }
+    Ok(periods)
+}
+
+/// 角色名或别名开头、且不在回忆的句子，视为角色在行动
+pub(crate) fn acts_in(sentence: &str, terms: &[String]) -> bool {
+    let sentence = sentence.trim_start_matches(|c: char| c.is_whitespace() || matches!(c, '“' | '”' | '「' | '」' | '"' | '，'));
+    terms.iter().any(|t| sentence.starts_with(t.as_str())) && !REMINISCENCE_WORDS.iter().any(|w| sentence.contains(w))
+}
+
+/// 检查被标记为死亡、入狱或失踪的角色在之后的章节中是否还在说话或行动；状态解除的章节起不再检查
+pub fn check_project(conn: &Connection, project_id: &str) -> Result<StatusReport, String> {
+    let chapters: Vec<(String, String, String)> = conn
+        .prepare("SELECT id, title, content FROM chapters WHERE project_id = ? ORDER BY sort_order, created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, Option<String>>(2)?.unwrap_or_default())))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let chapter_index: HashMap<String, usize> = chapters.iter().enumerate().map(|(i, (id, _, _))| (id.clone(), i)).collect();
+    let periods = status_periods(conn, project_id, &chapter_index)?;
+
+    let mut aliases = aliases_by_character(conn, project_id)?;
+    let names: HashMap<String, String> = conn
+        .prepare("SELECT id, name FROM characters WHERE project_id = ? AND merged_into IS NULL")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let terms: Vec<(String, Vec<String>)> = names
+        .iter()
+        .map(|(id, name)| {
+            let mut terms = vec![name.clone()];
+            terms.extend(aliases.remove(id).unwrap_or_default());
+            (id.clone(), terms)
+        })
+        .collect();
+
+    let mut report = StatusReport { periods: Vec::new(), violations: Vec::new() };
+    for Period { character_id, status, label, since, until } in periods {
+        let name = names[&character_id].clone();
+        let own_terms = &terms.iter().find(|(id, _)| *id == character_id).expect("character terms").1;
+        let end = until.unwrap_or(chapters.len());
+        for (chapter_id, chapter_title, content) in &chapters[since + 1..end.max(since + 1)] {
+            let mut dialogue: Vec<&str> = Vec::new();
+            let mut actions: Vec<&str> = Vec::new();
+            for line in content.lines() {
+                if speaker(line, &terms) == Some(character_id.as_str()) {
+                    dialogue.push(line);
+                    continue;
+                }
+                actions.extend(line.split(['。', '！', '？', '；']).filter(|s| acts_in(s, own_terms)));
+            }
+            for (kind, found) in [("dialogue", dialogue), ("appearance", actions)] {
+                let Some(first) = found.first() else { continue };
+                let severity = match (status, kind) {
+                    ("deceased", "dialogue") => "high",
+                    ("deceased", _) | (_, "dialogue") => "medium",
+                    _ => "low",
+                };
+                report.violations.push(StatusViolation {
+                    character_id: character_id.clone(),
+                    name: name.clone(),
+                    status: status.to_string(),
+                    status_label: label.clone(),
+                    since_chapter_id: chapters[since].0.clone(),
+                    chapter_id: chapter_id.clone(),
+                    chapter_title: chapter_title.clone(),
+                    kind: kind.to_string(),
+                    severity: severity.to_string(),
+                    count: found.len(),
+                    excerpt: first.trim().to_string(),
+                });
+            }
+        }
+        report.periods.push(StatusPeriod {
+            character_id,
+            name,
+            status: status.to_string(),
+            status_label: label,
+            since_chapter_id: chapters[since].0.clone(),
+            since_chapter_title: chapters[since].1.clone(),
+            until_chapter_id: until.map(|i| chapters[i].0.clone()),
+        });
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dead_and_imprisoned_characters_flagged_until_released() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES
+                 ('r1', 'p1', '林舟', 't0', 't0'), ('r2', 'p1', '沈青', 't0', 't0');
+             INSERT INTO character_aliases (character_id, alias) VALUES ('r2', '阿青');
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '林舟倒在雪里。沈青被押进大牢。', 1, 't0', 't0'),
+                 ('c2', 'p1', '第二章', '阿青想起林舟生前的样子。\n林舟说：“我还活着。”\n阿青推开牢门。', 2, 't0', 't0'),
+                 ('c3', 'p1', '第三章', '沈青走出城门。', 3, 't0', 't0');",
+        )
+        .unwrap();
+        let status = |after: &str| {
+            format!(
+                r#"[{{"change_type": "status", "category": "状态", "description": "", "before": null, "after": "{}", "significance": "critical"}}]"#,
+                after
+            )
+        };
+        for (id, character_id, chapter_id, position, after) in
+            [("g1", "r1", "c1", 1, "死亡"), ("g2", "r2", "c1", 1, "入狱"), ("g3", "r2", "c3", 3, "出狱")]
+        {
+            conn.execute(
+                "INSERT INTO character_growth_records (id, character_id, chapter_id, position, changes_json, created_at) VALUES (?, ?, ?, ?, ?, 't0')",
+                params![id, character_id, chapter_id, position, status(after)],
+            )
+            .unwrap();
+        }
+
+        let report = check_project(&conn, "p1").unwrap();
+        assert_eq!(report.periods.len(), 2);
+        assert_eq!(report.periods[1].until_chapter_id.as_deref(), Some("c3"));
+        let found: Vec<(&str, &str, &str, &str)> = report
+            .violations
+            .iter()
+            .map(|v| (v.name.as_str(), v.chapter_id.as_str(), v.kind.as_str(), v.severity.as_str()))
+            .collect();
+        assert_eq!(found, vec![("林舟", "c2", "dialogue", "high"), ("沈青", "c2", "appearance", "low")]);
+        assert_eq!(report.violations[1].excerpt, "阿青推开牢门");
+    }
+}
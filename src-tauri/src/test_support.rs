@@ -0,0 +1,20 @@
+//! 单元测试共用的数据库夹具
+
+use rusqlite::Connection;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// 在 `db_path` 建表，并插入测试用的项目 p1「长夜」
+pub fn init_project_db(db_path: &Path) -> Connection {
+    crate::database::init_database(db_path).unwrap();
+    let conn = Connection::open(db_path).unwrap();
+    conn.execute("INSERT INTO projects (id, name, created_at, updated_at) VALUES ('p1', '长夜', 't0', 't0')", []).unwrap();
+    conn
+}
+
+/// 临时目录中的 [`init_project_db`]；返回的目录在测试结束前不能释放
+pub fn project_db() -> (TempDir, Connection) {
+    let dir = tempfile::tempdir().unwrap();
+    let conn = init_project_db(&dir.path().join("test.db"));
+    (dir, conn)
+}
@@ -1,3 +1,4 @@
+use crate::tokenizer;
 use serde::{Serialize, Deserialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +17,22 @@ pub struct RhythmAnalysis {
     pub pacing_segments: Vec<PacingSegment>,
     pub action_vs_description_ratio: f32,
     pub dialogue_ratio: f32,
+    /// 引号内对话字数占全文的百分比
+    pub dialogue_percentage: f32,
+    /// 平均连续多少段对话后才出现叙述
+    pub avg_dialogue_run: f32,
+    pub unattributed_dialogues: Vec<UnattributedDialogue>,
+}
+
+/// 连续多段没有说话人提示的对话，读者容易分不清是谁在说
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnattributedDialogue {
+    pub start_paragraph: usize,
+    pub end_paragraph: usize,
+    /// 第一段在全文中的字符偏移
+    pub position: usize,
+    pub paragraph_count: usize,
+    pub excerpt: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,13 +64,78 @@ pub struct EmotionScore {
     pub score: f32,
 }
 
+/// 可读性模型：各项指标的理想值和上限，超过理想值开始扣分，达到上限扣满
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadabilityModel {
+    pub audience: String,
+    pub ideal_sentence_length: f32,
+    pub max_sentence_length: f32,
+    /// 每句的分句数，嵌套的括号、引号每层再加一
+    pub ideal_clause_depth: f32,
+    pub max_clause_depth: f32,
+    /// 生僻字占汉字的比例达到这个值时扣满
+    pub max_rare_char_rate: f32,
+    pub sentence_weight: f32,
+    pub clause_weight: f32,
+    pub rare_char_weight: f32,
+}
+
+impl ReadabilityModel {
+    /// 目标读者预设：children 儿童、young_adult 青少年、web_novel 网文、literary 文学
+    pub fn preset(audience: &str) -> Result<Self, String> {
+        let (sentence, clause, rare) = match audience {
+            "children" => ((12.0, 25.0), (1.5, 3.0), 0.002),
+            "young_adult" => ((18.0, 35.0), (2.0, 4.0), 0.005),
+            "web_novel" => ((20.0, 40.0), (2.5, 4.5), 0.008),
+            "literary" => ((30.0, 60.0), (3.0, 6.0), 0.02),
+            _ => return Err(format!("未知的读者预设: {}", audience)),
+        };
+        Ok(Self {
+            audience: audience.to_string(),
+            ideal_sentence_length: sentence.0,
+            max_sentence_length: sentence.1,
+            ideal_clause_depth: clause.0,
+            max_clause_depth: clause.1,
+            max_rare_char_rate: rare,
+            sentence_weight: 0.4,
+            clause_weight: 0.35,
+            rare_char_weight: 0.25,
+        })
+    }
+}
+
+impl Default for ReadabilityModel {
+    fn default() -> Self {
+        Self::preset("web_novel").unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReadabilityMetrics {
+    pub sentence_count: usize,
+    pub char_count: usize,
+    pub avg_sentence_length: f32,
+    pub avg_clause_depth: f32,
+    pub rare_char_rate: f32,
+    /// 0-100，越高越适合目标读者
+    pub score: f32,
+    pub issues: Vec<String>,
+}
+
+/// 一段的可读性，`index` 是按换行切分的段落序号
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParagraphReadability {
+    pub index: usize,
+    pub excerpt: String,
+    pub metrics: ReadabilityMetrics,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReadabilityAnalysis {
-    pub flesch_score: f32,
+    pub model: ReadabilityModel,
+    pub overall: ReadabilityMetrics,
     pub reading_level: String,
-    pub avg_sentence_complexity: f32,
-    pub syllable_count: usize,
-    pub word_count: usize,
+    pub paragraphs: Vec<ParagraphReadability>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -101,6 +183,9 @@ pub struct TimelineIssue {
     pub description: String,
 }
 
+/// 连续这么多段没有说话人提示的对话才提示
+const UNATTRIBUTED_RUN: usize = 4;
+
 pub struct TextAnalyzer;
 
 impl TextAnalyzer {
@@ -116,7 +201,7 @@ impl TextAnalyzer {
             total_chars as f32 / sentences.len() as f32
         };
 
-        let words: Vec<&str> = text.split_whitespace().collect();
+        let words = tokenizer::words(text);
         let avg_word_length = if words.is_empty() {
             0.0
         } else {
@@ -145,20 +230,32 @@ impl TextAnalyzer {
     }
 
     pub fn analyze_rhythm(text: &str) -> RhythmAnalysis {
-        let paragraphs: Vec<&str> = text.split('\n').filter(|p| !p.trim().is_empty()).collect();
+        let mut offset = 0;
+        let mut paragraphs: Vec<(usize, &str)> = Vec::new();
+        for line in text.split('\n') {
+            if !line.trim().is_empty() {
+                paragraphs.push((offset, line));
+            }
+            offset += line.chars().count() + 1;
+        }
         let segment_size = std::cmp::max(1, paragraphs.len() / 10);
         
         let mut pacing_segments = Vec::new();
         let mut total_intensity = 0.0;
-        let mut action_count = 0;
+        let action_count = 0;
         let mut dialogue_count = 0;
         let mut description_count = 0;
+        let mut dialogue_runs = Vec::new();
+        let mut run = 0;
+        let mut unattributed_dialogues = Vec::new();
+        let mut unattributed_start = None;
 
-        for (i, paragraph) in paragraphs.iter().enumerate() {
+        for (i, (_, paragraph)) in paragraphs.iter().enumerate() {
             let intensity = Self::calculate_paragraph_intensity(paragraph);
             total_intensity += intensity;
 
-            let segment_type = if paragraph.contains('"') || paragraph.contains('"') {
+            let is_dialogue = crate::pacing::dialogue_chars(paragraph) > 0;
+            let segment_type = if is_dialogue {
                 dialogue_count += 1;
                 "dialogue".to_string()
             } else {
@@ -166,6 +263,18 @@ impl TextAnalyzer {
                 "description".to_string()
             };
 
+            if is_dialogue {
+                run += 1;
+            } else if run > 0 {
+                dialogue_runs.push(run);
+                run = 0;
+            }
+            if is_dialogue && !Self::has_speaker_tag(paragraph) {
+                unattributed_start.get_or_insert(i);
+            } else if let Some(start) = unattributed_start.take() {
+                Self::push_unattributed(&mut unattributed_dialogues, &paragraphs, start, i);
+            }
+
             if i % segment_size == 0 || i == paragraphs.len() - 1 {
                 pacing_segments.push(PacingSegment {
                     start_position: i.saturating_sub(segment_size),
@@ -175,6 +284,12 @@ impl TextAnalyzer {
                 });
             }
         }
+        if run > 0 {
+            dialogue_runs.push(run);
+        }
+        if let Some(start) = unattributed_start {
+            Self::push_unattributed(&mut unattributed_dialogues, &paragraphs, start, paragraphs.len());
+        }
 
         let pacing_score = if pacing_segments.is_empty() {
             50.0
@@ -195,12 +310,58 @@ impl TextAnalyzer {
             (dialogue_count as f32 / total_content as f32) * 100.0
         };
 
+        let total_chars = text.chars().filter(|c| !c.is_whitespace()).count();
+        let dialogue_percentage = if total_chars == 0 {
+            0.0
+        } else {
+            crate::pacing::dialogue_chars(text) as f32 / total_chars as f32 * 100.0
+        };
+        let avg_dialogue_run = if dialogue_runs.is_empty() {
+            0.0
+        } else {
+            dialogue_runs.iter().sum::<usize>() as f32 / dialogue_runs.len() as f32
+        };
+
         RhythmAnalysis {
             pacing_score,
             pacing_segments,
             action_vs_description_ratio,
             dialogue_ratio,
+            dialogue_percentage,
+            avg_dialogue_run,
+            unattributed_dialogues,
+        }
+    }
+
+    /// 引号外有“说”“问”“道”之类的提示语时，认为这段对话有说话人
+    fn has_speaker_tag(paragraph: &str) -> bool {
+        const SPEECH_MARKERS: [&str; 14] = ["说", "道", "问", "答", "喊", "叫", "嚷", "吼", "低语", "嘀咕", "said", "asked", "replied", "shouted"];
+        let mut depth = 0usize;
+        let mut straight_open = false;
+        let mut narration = String::new();
+        for c in paragraph.chars() {
+            match c {
+                '“' | '「' | '『' => depth += 1,
+                '”' | '」' | '』' => depth = depth.saturating_sub(1),
+                '"' => straight_open = !straight_open,
+                c if depth == 0 && !straight_open => narration.push(c),
+                _ => {}
+            }
+        }
+        SPEECH_MARKERS.iter().any(|m| narration.contains(m))
+    }
+
+    fn push_unattributed(found: &mut Vec<UnattributedDialogue>, paragraphs: &[(usize, &str)], start: usize, end: usize) {
+        if end - start < UNATTRIBUTED_RUN {
+            return;
         }
+        found.push(UnattributedDialogue {
+            start_paragraph: start,
+            end_paragraph: end - 1,
+            position: paragraphs[start].0,
+            paragraph_count: end - start,
+            excerpt: paragraphs[start].1.trim().chars().take(30).collect(),
+        });
     }
 
     pub fn analyze_emotion(text: &str) -> EmotionAnalysis {
@@ -281,56 +442,132 @@ impl TextAnalyzer {
         }
     }
 
-    pub fn analyze_readability(text: &str) -> ReadabilityAnalysis {
-        let sentences: Vec<&str> = text.split_inclusive(&['.', '!', '?', '。', '！', '？'])
-            .filter(|s| !s.trim().is_empty())
-            .collect();
+    /// 整段文字的情绪强度（0-100）：带情绪的段落占比和这些段落的平均强度各占一部分
+    pub fn emotion_intensity(text: &str) -> f32 {
+        let paragraphs = text.split('\n').filter(|p| !p.trim().is_empty()).count();
+        if paragraphs == 0 {
+            return 0.0;
+        }
+        let curve = Self::analyze_emotion(text).emotion_curve;
+        let coverage = curve.len() as f32 / paragraphs as f32;
+        // 段落强度按每字节情绪词数计，约 2 已是情绪很浓的段落
+        let strength = curve.iter().map(|p| p.intensity).sum::<f32>() / curve.len().max(1) as f32 / 2.0;
+        (coverage * 60.0 + strength.min(1.0) * 40.0).min(100.0)
+    }
 
-        let words: Vec<&str> = text.split_whitespace().collect();
-        let word_count = words.len();
+    pub fn analyze_readability(text: &str, model: &ReadabilityModel) -> ReadabilityAnalysis {
+        let overall = Self::readability_metrics(text, model);
+        let reading_level = match overall.score {
+            s if s >= 80.0 => "轻松",
+            s if s >= 60.0 => "适中",
+            s if s >= 40.0 => "偏难",
+            _ => "晦涩",
+        }
+        .to_string();
+        let paragraphs = text
+            .split('\n')
+            .enumerate()
+            .filter(|(_, p)| !p.trim().is_empty())
+            .map(|(index, p)| ParagraphReadability {
+                index,
+                excerpt: p.trim().chars().take(30).collect(),
+                metrics: Self::readability_metrics(p, model),
+            })
+            .collect();
 
-        let syllable_count: usize = words.iter()
-            .map(|w| Self::count_syllables(w))
-            .sum();
+        ReadabilityAnalysis {
+            model: model.clone(),
+            overall,
+            reading_level,
+            paragraphs,
+        }
+    }
 
-        let avg_sentence_complexity = if sentences.is_empty() {
-            0.0
-        } else {
-            let avg_words_per_sentence = word_count as f32 / sentences.len() as f32;
-            let avg_syllables_per_word = syllable_count as f32 / word_count.max(1) as f32;
-            (avg_words_per_sentence * 0.4 + avg_syllables_per_word * 0.6).min(100.0)
-        };
+    fn readability_metrics(text: &str, model: &ReadabilityModel) -> ReadabilityMetrics {
+        let sentences: Vec<&str> = text
+            .split_inclusive(['.', '!', '?', '。', '！', '？', '…', '\n'])
+            .filter(|s| s.chars().any(char::is_alphanumeric))
+            .collect();
+        if sentences.is_empty() {
+            return ReadabilityMetrics { score: 100.0, ..Default::default() };
+        }
+        let char_count = text.chars().filter(|c| !c.is_whitespace()).count();
+        let avg_sentence_length = char_count as f32 / sentences.len() as f32;
+        let avg_clause_depth = sentences.iter().map(|s| Self::clause_depth(s)).sum::<usize>() as f32 / sentences.len() as f32;
+        let rare_char_rate = Self::rare_char_rate(text);
+
+        // 超过理想值后按到上限的距离线性扣分
+        let over = |value: f32, ideal: f32, max: f32| ((value - ideal) / (max - ideal).max(f32::EPSILON)).clamp(0.0, 1.0);
+        let sentence_penalty = over(avg_sentence_length, model.ideal_sentence_length, model.max_sentence_length);
+        let clause_penalty = over(avg_clause_depth, model.ideal_clause_depth, model.max_clause_depth);
+        let rare_penalty = over(rare_char_rate, 0.0, model.max_rare_char_rate);
+        let total_weight = (model.sentence_weight + model.clause_weight + model.rare_char_weight).max(f32::EPSILON);
+        let penalty = (sentence_penalty * model.sentence_weight + clause_penalty * model.clause_weight + rare_penalty * model.rare_char_weight)
+            / total_weight;
+
+        let mut issues = Vec::new();
+        if avg_sentence_length > model.max_sentence_length {
+            issues.push(format!("平均句长 {:.0} 字，超过目标读者的上限 {:.0} 字", avg_sentence_length, model.max_sentence_length));
+        }
+        if avg_clause_depth > model.max_clause_depth {
+            issues.push(format!("每句平均 {:.1} 层分句，结构偏复杂", avg_clause_depth));
+        }
+        if rare_char_rate > model.max_rare_char_rate {
+            issues.push(format!("生僻字占 {:.1}%", rare_char_rate * 100.0));
+        }
 
-        let flesch_score = if sentences.is_empty() || word_count == 0 {
-            0.0
-        } else {
-            206.835 - 1.015 * (word_count as f32 / sentences.len() as f32)
-                - 84.6 * (syllable_count as f32 / word_count as f32)
-        };
+        ReadabilityMetrics {
+            sentence_count: sentences.len(),
+            char_count,
+            avg_sentence_length,
+            avg_clause_depth,
+            rare_char_rate,
+            score: (1.0 - penalty) * 100.0,
+            issues,
+        }
+    }
 
-        let reading_level = match flesch_score {
-            s if s >= 90.0 => "小学低年级".to_string(),
-            s if s >= 80.0 => "小学高年级".to_string(),
-            s if s >= 70.0 => "初中".to_string(),
-            s if s >= 60.0 => "高中".to_string(),
-            s if s >= 50.0 => "大学".to_string(),
-            s if s >= 30.0 => "专业".to_string(),
-            _ => "学术".to_string(),
-        };
+    /// 一句的分句数加上括号、引号的最大嵌套层数
+    fn clause_depth(sentence: &str) -> usize {
+        let clauses = 1 + sentence.chars().filter(|c| matches!(c, '，' | ',' | '、' | '；' | ';' | '：' | ':')).count();
+        let mut depth = 0usize;
+        let mut max_depth = 0;
+        for c in sentence.chars() {
+            match c {
+                '（' | '(' | '“' | '「' | '『' | '《' | '‘' => {
+                    depth += 1;
+                    max_depth = max_depth.max(depth);
+                }
+                '）' | ')' | '”' | '」' | '』' | '》' | '’' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        clauses + max_depth
+    }
 
-        ReadabilityAnalysis {
-            flesch_score,
-            reading_level,
-            avg_sentence_complexity,
-            syllable_count,
-            word_count,
+    /// 生僻字占汉字的比例：字本身和它所在的词在通用语料中都少见
+    fn rare_char_rate(text: &str) -> f32 {
+        const RARE_WORD_FREQ: usize = 100;
+        const RARE_CHAR_FREQ: usize = 200;
+        let mut han = 0;
+        let mut rare = 0;
+        for word in tokenizer::words(text) {
+            let chars: Vec<char> = word.chars().filter(|c| ('\u{4e00}'..='\u{9fff}').contains(c)).collect();
+            if chars.is_empty() {
+                continue;
+            }
+            han += chars.len();
+            if tokenizer::reference_frequency(word) < RARE_WORD_FREQ {
+                rare += chars.iter().filter(|c| tokenizer::reference_frequency(&c.to_string()) < RARE_CHAR_FREQ).count();
+            }
         }
+        rare as f32 / han.max(1) as f32
     }
 
     pub fn detect_repetitions(text: &str, min_repetitions: usize) -> RepetitionDetection {
-        let words: Vec<&str> = text.split_whitespace()
-            .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
-            .filter(|w| w.len() > 1)
+        let words: Vec<&str> = tokenizer::words(text)
+            .into_iter()
+            .filter(|w| w.chars().count() > 1)
             .collect();
 
         let mut word_counts: std::collections::HashMap<&str, (usize, Vec<usize>)> = std::collections::HashMap::new();
@@ -495,7 +732,7 @@ impl TextAnalyzer {
                 emotion_count += 1;
             }
         }
-        let word_count = paragraph.split_whitespace().count();
+        let word_count = tokenizer::words(paragraph).len();
 
         if word_count == 0 {
             return 0.0;
@@ -505,13 +742,6 @@ impl TextAnalyzer {
         intensity.min(100.0)
     }
 
-    fn count_syllables(word: &str) -> usize {
-        word.chars()
-            .filter(|c| c.is_alphanumeric() || matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'ā' | 'á' | 'ǎ' | 'à' | 'ē' | 'é' | 'ě' | 'è' | 'ī' | 'í' | 'ǐ' | 'ì' | 'ō' | 'ó' | 'ǒ' | 'ò' | 'ū' | 'ú' | 'ǔ' | 'ù' | 'ǖ' | 'ǘ' | 'ǚ'))
-            .count()
-            .max(1)
-    }
-
     fn detect_repeated_phrases(text: &str, min_repetitions: usize) -> Vec<RepeatedItem> {
         let phrases: Vec<&str> = text.matches(&['.', '。'][..])
             .map(|s| s.trim())
@@ -536,3 +766,43 @@ impl TextAnalyzer {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rhythm_flags_unattributed_dialogue() {
+        let text = "雨停了。\n林舟问：“你去哪？”\n“北边。”\n“多久回来？”\n“不知道。”\n“那我等你。”\n“别等。”\n他转身走了。";
+        let rhythm = TextAnalyzer::analyze_rhythm(text);
+        assert_eq!(rhythm.avg_dialogue_run, 6.0);
+        assert!(rhythm.dialogue_percentage > 40.0 && rhythm.dialogue_percentage < 80.0);
+        assert_eq!(rhythm.unattributed_dialogues.len(), 1);
+        let run = &rhythm.unattributed_dialogues[0];
+        assert_eq!((run.start_paragraph, run.end_paragraph, run.paragraph_count), (2, 6, 5));
+        assert_eq!(run.position, text.find("“北边").map(|b| text[..b].chars().count()).unwrap());
+    }
+
+    #[test]
+    fn test_emotion_intensity_tracks_emotional_paragraphs() {
+        let calm = "午后的院子很安静。\n她翻了一页书。";
+        let charged = "他愤怒地吼叫，浑身颤抖。\n她震惊地后退，泪水夺眶而出，悲伤得说不出话。";
+        assert_eq!(TextAnalyzer::emotion_intensity(calm), 0.0);
+        assert_eq!(TextAnalyzer::emotion_intensity(""), 0.0);
+        let intensity = TextAnalyzer::emotion_intensity(charged);
+        assert!(intensity > 60.0 && intensity <= 100.0);
+    }
+
+    #[test]
+    fn test_readability_scores_paragraphs_per_audience() {
+        let text = "他笑了。她也笑了。\n在那座据说始建于前朝、历经数次兵燹而屹立不倒的古老城池（其城墙之上，至今仍可见到饕餮纹样的残迹）深处，藏着一段踟蹰百年、无人知晓的往事，而这段往事，又与他那位早已故去、生前却被人称作“怪人”的祖父有关。";
+        let children = TextAnalyzer::analyze_readability(text, &ReadabilityModel::preset("children").unwrap());
+        let literary = TextAnalyzer::analyze_readability(text, &ReadabilityModel::preset("literary").unwrap());
+        assert_eq!(children.paragraphs.len(), 2);
+        let (easy, hard) = (&children.paragraphs[0].metrics, &children.paragraphs[1].metrics);
+        assert!(easy.score > 95.0 && easy.issues.is_empty());
+        assert!(hard.score < 30.0 && hard.issues.len() == 3 && hard.rare_char_rate > 0.0);
+        assert!(literary.overall.score > children.overall.score);
+        assert!(ReadabilityModel::preset("unknown").is_err());
+    }
+}
@@ -1,7 +1,19 @@
-use crate::text_analysis::TextAnalyzer;
+use crate::text_analysis::{ReadabilityModel, TextAnalyzer};
 use crate::models::Character;
 use crate::logger::Logger;
+use crate::ai::task_queue::{
+    is_task_cancelled, save_task, update_task_progress, update_task_state, CreateTaskRequest, TaskQueue, TaskState, TaskType,
+};
+use crate::entity_index;
+use crate::pacing;
+use crate::pov_check;
+use crate::project_analysis;
+use crate::similarity;
+use crate::tokenizer;
+use crate::vocabulary;
 use serde_json;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
 
 #[tauri::command]
 pub async fn analyze_writing_style(
@@ -36,14 +48,27 @@ pub async fn analyze_emotion(
     serde_json::to_string(&analysis).map_err(|e| e.to_string())
 }
 
+/// 自定义模型优先，其次是读者预设，都没有时按网文读者评估
+fn readability_model(audience: Option<&str>, model: Option<ReadabilityModel>) -> Result<ReadabilityModel, String> {
+    match (model, audience) {
+        (Some(model), _) => Ok(model),
+        (None, Some(audience)) => ReadabilityModel::preset(audience),
+        (None, None) => Ok(ReadabilityModel::default()),
+    }
+}
+
+/// 按目标读者评估可读性，返回整体和逐段的评分
 #[tauri::command]
 pub async fn analyze_readability(
     text: String,
+    audience: Option<String>,
+    model: Option<ReadabilityModel>,
 ) -> Result<String, String> {
     let logger = Logger::new().with_feature("text_analysis");
     logger.info("Analyzing readability");
 
-    let analysis = TextAnalyzer::analyze_readability(&text);
+    let model = readability_model(audience.as_deref(), model)?;
+    let analysis = TextAnalyzer::analyze_readability(&text, &model);
     serde_json::to_string(&analysis).map_err(|e| e.to_string())
 }
 
@@ -78,6 +103,7 @@ pub async fn check_logic(
 pub async fn run_full_analysis(
     text: String,
     characters_json: Option<String>,
+    audience: Option<String>,
 ) -> Result<String, String> {
     let logger = Logger::new().with_feature("text_analysis");
     logger.info("Running full text analysis");
@@ -92,7 +118,7 @@ pub async fn run_full_analysis(
     let writing_style = TextAnalyzer::analyze_writing_style(&text);
     let rhythm = TextAnalyzer::analyze_rhythm(&text);
     let emotion = TextAnalyzer::analyze_emotion(&text);
-    let readability = TextAnalyzer::analyze_readability(&text);
+    let readability = TextAnalyzer::analyze_readability(&text, &readability_model(audience.as_deref(), None)?);
     let repetitions = TextAnalyzer::detect_repetitions(&text, 3);
     let logic = TextAnalyzer::check_logic(&text, &characters);
 
@@ -107,3 +133,333 @@ pub async fn run_full_analysis(
 
     serde_json::to_string(&full_analysis).map_err(|e| e.to_string())
 }
+
+/// 全书各章、各场景的节奏热力图，正文未变的章节使用缓存
+#[tauri::command]
+pub async fn analyze_book_pacing(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("text_analysis");
+    logger.info(&format!("Analyzing pacing of project {}", project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let heatmap = pacing::book_pacing(&conn, &project_id)?;
+    serde_json::to_string(&heatmap).map_err(|e| e.to_string())
+}
+
+/// 为项目（或其中一章）建立人物、地点、物品的索引；`use_ai` 时再让 AI 补充识别专有名词
+#[tauri::command]
+pub async fn build_entity_index(
+    app: AppHandle,
+    project_id: String,
+    chapter_id: Option<String>,
+    use_ai: Option<bool>,
+    model_id: Option<String>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("text_analysis");
+    logger.info(&format!("Building entity index for project {}", project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let chapters: Vec<(String, String)> = conn
+        .prepare("SELECT id, COALESCE(content, '') FROM chapters WHERE project_id = ?1 AND (?2 IS NULL OR id = ?2)")
+        .map_err(|e| e.to_string())?
+        .query_map(rusqlite::params![project_id, chapter_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let ai_service = app.state::<std::sync::Arc<tokio::sync::RwLock<crate::ai::AIService>>>();
+    let model_id = model_id.unwrap_or_else(|| "glm-4-flash".to_string());
+    let mut mentions = 0;
+    for (chapter_id, content) in &chapters {
+        let ai_entities = if use_ai.unwrap_or(false) {
+            let service = ai_service.read().await;
+            match entity_index::extract_with_ai(&service, &model_id, content).await {
+                Ok(entities) => Some(entities),
+                Err(e) => {
+                    logger.warn(&format!("AI entity extraction failed for chapter {}: {}", chapter_id, e));
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        mentions += entity_index::index_chapter(&conn, chapter_id, ai_entities.as_deref())?;
+    }
+
+    let index = entity_index::entity_index(&conn, &project_id)?;
+    serde_json::to_string(&serde_json::json!({
+        "chapters": chapters.len(),
+        "mentions": mentions,
+        "entities": index,
+    })).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_entity_index(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let index = entity_index::entity_index(&conn, &project_id)?;
+    serde_json::to_string(&index).map_err(|e| e.to_string())
+}
+
+/// 实体在各章中的出现位置，用于跳转
+#[tauri::command]
+pub async fn get_entity_mentions(
+    app: AppHandle,
+    project_id: String,
+    name: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let mentions = entity_index::find_mentions(&conn, &project_id, &name)?;
+    serde_json::to_string(&mentions).map_err(|e| e.to_string())
+}
+
+/// 把自创的人名、地名、术语加入分词用户词典，立即生效
+#[tauri::command]
+pub async fn add_dictionary_words(
+    app: AppHandle,
+    project_id: Option<String>,
+    words: Vec<String>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("text_analysis");
+    logger.info(&format!("Adding {} words to user dictionary", words.len()));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let added = tokenizer::add_dictionary_words(&conn, project_id.as_deref(), &words)?;
+    serde_json::to_string(&added).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_dictionary_word(
+    app: AppHandle,
+    word: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    tokenizer::remove_dictionary_word(&conn, &word)?;
+    Ok("{\"status\":\"success\"}".to_string())
+}
+
+#[tauri::command]
+pub async fn list_dictionary_words(
+    app: AppHandle,
+    project_id: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let words = tokenizer::list_dictionary_words(&conn, project_id.as_deref())?;
+    serde_json::to_string(&words).map_err(|e| e.to_string())
+}
+
+/// 项目的词汇丰富度、过度使用的词和短语，以及赘词在各作者、各章的分布
+#[tauri::command]
+pub async fn analyze_vocabulary(
+    app: AppHandle,
+    project_id: String,
+    top_n: Option<usize>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("text_analysis");
+    logger.info(&format!("Analyzing vocabulary of project {}", project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let report = vocabulary::analyze(&conn, &project_id, top_n.unwrap_or(20))?;
+    serde_json::to_string(&report).map_err(|e| e.to_string())
+}
+
+/// 项目（或指定章节）的词频、二元组和各章变化；`format` 为 "csv" 时返回 CSV 文本
+#[tauri::command]
+pub async fn get_word_stats(
+    app: AppHandle,
+    project_id: Option<String>,
+    chapter_id: Option<String>,
+    top_n: Option<usize>,
+    format: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let project_id = match (project_id, &chapter_id) {
+        (Some(project_id), _) => project_id,
+        (None, Some(chapter_id)) => conn
+            .query_row("SELECT project_id FROM chapters WHERE id = ?", [chapter_id], |row| row.get(0))
+            .map_err(|_| "章节不存在".to_string())?,
+        (None, None) => return Err("project_id or chapter_id is required".to_string()),
+    };
+    let stats = vocabulary::word_stats(&conn, &project_id, chapter_id.as_deref(), top_n.unwrap_or(50))?;
+    if format.as_deref() == Some("csv") {
+        return vocabulary::word_stats_csv(&stats);
+    }
+    serde_json::to_string(&stats).map_err(|e| e.to_string())
+}
+
+/// 替换项目的忽略词（list 为 "ignore"）或自定义赘词（"crutch"）
+#[tauri::command]
+pub async fn set_vocabulary_list(
+    app: AppHandle,
+    project_id: String,
+    list: String,
+    words: Vec<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let lists = vocabulary::set_list(&conn, &project_id, &list, &words)?;
+    serde_json::to_string(&lists).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_vocabulary_lists(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let lists = vocabulary::get_lists(&conn, &project_id)?;
+    serde_json::to_string(&lists).map_err(|e| e.to_string())
+}
+
+/// 找出各章之间（以及同一章内）近似重复的段落，按相似度排序
+#[tauri::command]
+pub async fn detect_similar_passages(
+    app: AppHandle,
+    project_id: String,
+    threshold: Option<f64>,
+    min_chars: Option<usize>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("text_analysis");
+    logger.info(&format!("Detecting similar passages in project {}", project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let pairs = similarity::find_similar_paragraphs(
+        &conn,
+        &project_id,
+        threshold.unwrap_or(similarity::DEFAULT_THRESHOLD),
+        min_chars.unwrap_or(similarity::DEFAULT_MIN_CHARS),
+    )?;
+    serde_json::to_string(&pairs).map_err(|e| e.to_string())
+}
+
+/// 在任务队列中对全书所有章节运行全部分析，只重新分析改动过的章节；返回任务
+#[tauri::command]
+pub async fn run_project_analysis(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("text_analysis");
+    logger.info(&format!("Queueing project analysis for {}", project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let task = TaskQueue::new().add_task(CreateTaskRequest {
+        project_id: project_id.clone(),
+        task_type: TaskType::Custom,
+        priority: None,
+        provider: Some("project_analysis".to_string()),
+        input_data: serde_json::json!({ "project_id": project_id }),
+        max_retries: Some(0),
+    });
+    save_task(&conn, &task).map_err(|e| e.to_string())?;
+
+    let task_id = task.id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let Ok(conn) = crate::database::get_connection(&db_path) else {
+            return;
+        };
+        let _ = update_task_state(&conn, &task_id, TaskState::Running, None, None);
+        let outcome = project_analysis::run_project_analysis(&conn, &project_id, |done, total| {
+            let _ = update_task_progress(&conn, &task_id, (done * 100 / total.max(1)) as u32);
+            !is_task_cancelled(&conn, &task_id)
+        });
+        match outcome {
+            Ok(Some(summary)) => {
+                let summary = serde_json::to_value(&summary).unwrap_or_default();
+                let _ = update_task_state(&conn, &task_id, TaskState::Completed, Some(&summary), None);
+                logger.info(&format!("Project analysis {} completed", task_id));
+            }
+            Ok(None) => logger.info(&format!("Project analysis {} cancelled", task_id)),
+            Err(e) => {
+                let _ = update_task_state(&conn, &task_id, TaskState::Failed, None, Some(&e));
+                logger.error(&format!("Project analysis {} failed: {}", task_id, e));
+            }
+        }
+    });
+
+    serde_json::to_string(&task).map_err(|e| e.to_string())
+}
+
+/// 批量分析保存的各章结果，正文改过的章节标记为过期
+#[tauri::command]
+pub async fn get_project_analysis_results(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let results = project_analysis::load_results(&conn, &project_id)?;
+    serde_json::to_string(&results).map_err(|e| e.to_string())
+}
+
+/// 推断各场景的叙事人称和视角角色，标出场景中途换视角和与导演脚本声明不符的地方
+#[tauri::command]
+pub async fn analyze_pov(
+    app: AppHandle,
+    project_id: String,
+    chapter_id: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let chapters = pov_check::check_project(&conn, &project_id, chapter_id.as_deref())?;
+    serde_json::to_string(&chapters).map_err(|e| e.to_string())
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
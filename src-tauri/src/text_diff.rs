@@ -0,0 +1,295 @@
+use crate::text_merge::{lcs_matches, split_paragraphs};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// 超过这个规模（两侧词数之积）不再求最长公共子序列，整段按删除加插入处理
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// 一段连续的相同、插入或删除的文字
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiffRun {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParagraphChange {
+    Unchanged,
+    Added,
+    Removed,
+    Modified,
+}
+
+/// 一个段落的变化，`old_index`/`new_index` 为段落在两个版本中的序号
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParagraphDiff {
+    pub change: ParagraphChange,
+    pub old_index: Option<usize>,
+    pub new_index: Option<usize>,
+    pub runs: Vec<DiffRun>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CharClass {
+    Cjk,
+    Word,
+    Space,
+    Other,
+}
+
+fn char_class(c: char) -> CharClass {
+    match c as u32 {
+        0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF | 0x20000..=0x2FA1F => {
+            CharClass::Cjk
+        }
+        _ if c.is_alphanumeric() || c == '_' => CharClass::Word,
+        _ if c.is_whitespace() => CharClass::Space,
+        _ => CharClass::Other,
+    }
+}
+
+/// 分词：中日韩文字和标点各自成词，西文单词、数字和连续空白各为一个词
+pub fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut current: Option<CharClass> = None;
+    for (i, c) in text.char_indices() {
+        let class = char_class(c);
+        let joins = matches!(class, CharClass::Word | CharClass::Space) && current == Some(class);
+        if !joins {
+            if i > start {
+                tokens.push(&text[start..i]);
+            }
+            start = i;
+        }
+        current = Some(class);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+enum Segment {
+    Same(usize, usize),
+    Changed(Range<usize>, Range<usize>),
+}
+
+/// 把两个序列划分为相同的项和变化的区间，先去掉共同的首尾以减少计算量
+fn segments<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Segment> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+    let (a_end, b_end) = (a.len() - suffix, b.len() - suffix);
+    let (a_mid, b_mid) = (&a[prefix..a_end], &b[prefix..b_end]);
+
+    let mut out: Vec<Segment> = (0..prefix).map(|k| Segment::Same(k, k)).collect();
+    let matches = if a_mid.len().saturating_mul(b_mid.len()) <= MAX_LCS_CELLS {
+        lcs_matches(a_mid, b_mid)
+    } else {
+        vec![None; a_mid.len()]
+    };
+    let (mut i, mut j) = (0, 0);
+    for (x, y) in matches.into_iter().enumerate().filter_map(|(x, m)| m.map(|y| (x, y))) {
+        if x > i || y > j {
+            out.push(Segment::Changed(prefix + i..prefix + x, prefix + j..prefix + y));
+        }
+        out.push(Segment::Same(prefix + x, prefix + y));
+        (i, j) = (x + 1, y + 1);
+    }
+    if i < a_mid.len() || j < b_mid.len() {
+        out.push(Segment::Changed(prefix + i..a_end, prefix + j..b_end));
+    }
+    out.extend((0..suffix).map(|k| Segment::Same(a_end + k, b_end + k)));
+    out
+}
+
+fn push_run(runs: &mut Vec<DiffRun>, op: DiffOp, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match runs.last_mut() {
+        Some(last) if last.op == op => last.text.push_str(text),
+        _ => runs.push(DiffRun { op, text: text.to_string() }),
+    }
+}
+
+/// 逐词比较两段文字
+pub fn diff_words(old: &str, new: &str) -> Vec<DiffRun> {
+    let (old_tokens, new_tokens) = (tokenize(old), tokenize(new));
+    let mut runs = Vec::new();
+    for segment in segments(&old_tokens, &new_tokens) {
+        match segment {
+            Segment::Same(i, _) => push_run(&mut runs, DiffOp::Equal, old_tokens[i]),
+            Segment::Changed(a, b) => {
+                push_run(&mut runs, DiffOp::Delete, &old_tokens[a].concat());
+                push_run(&mut runs, DiffOp::Insert, &new_tokens[b].concat());
+            }
+        }
+    }
+    runs
+}
+
+/// 同一处被改动的两段文字中，相同的字占比达到这个值才视为同一段落的修改
+const MODIFIED_SIMILARITY: f64 = 0.5;
+
+fn non_space_chars(text: &str) -> usize {
+    text.chars().filter(|c| !c.is_whitespace()).count()
+}
+
+/// 逐词比较两段文字，相似时返回差异
+fn similar_runs(old: &str, new: &str) -> Option<Vec<DiffRun>> {
+    let runs = diff_words(old, new);
+    let equal: usize = runs.iter().filter(|r| r.op == DiffOp::Equal).map(|r| non_space_chars(&r.text)).sum();
+    let total = non_space_chars(old) + non_space_chars(new);
+    (total > 0 && (2 * equal) as f64 / total as f64 >= MODIFIED_SIMILARITY).then_some(runs)
+}
+
+/// 先按段落对齐，再对修改过的段落逐词比较。同一处被替换的段落按顺序与足够相似的新段落配对，
+/// 配不上的按删除和新增处理
+pub fn diff_paragraphs(old: &str, new: &str) -> Vec<ParagraphDiff> {
+    let (old, new) = (split_paragraphs(old), split_paragraphs(new));
+    let whole = |op: DiffOp, text: &str| {
+        let mut runs = Vec::new();
+        push_run(&mut runs, op, text);
+        runs
+    };
+    let added = |j: usize| ParagraphDiff {
+        change: ParagraphChange::Added,
+        old_index: None,
+        new_index: Some(j),
+        runs: whole(DiffOp::Insert, &new[j]),
+    };
+    let mut out = Vec::new();
+    for segment in segments(&old, &new) {
+        match segment {
+            Segment::Same(i, j) => out.push(ParagraphDiff {
+                change: ParagraphChange::Unchanged,
+                old_index: Some(i),
+                new_index: Some(j),
+                runs: whole(DiffOp::Equal, &old[i]),
+            }),
+            Segment::Changed(a, b) => {
+                let mut next = b.start;
+                for i in a {
+                    let paired = (next..b.end).find_map(|j| similar_runs(&old[i], &new[j]).map(|runs| (j, runs)));
+                    match paired {
+                        Some((j, runs)) => {
+                            out.extend((next..j).map(added));
+                            out.push(ParagraphDiff {
+                                change: ParagraphChange::Modified,
+                                old_index: Some(i),
+                                new_index: Some(j),
+                                runs,
+                            });
+                            next = j + 1;
+                        }
+                        None => out.push(ParagraphDiff {
+                            change: ParagraphChange::Removed,
+                            old_index: Some(i),
+                            new_index: None,
+                            runs: whole(DiffOp::Delete, &old[i]),
+                        }),
+                    }
+                }
+                out.extend((next..b.end).map(added));
+            }
+        }
+    }
+    out
+}
+
+/// 插入和删除的字数（不计空白）
+pub fn change_counts(paragraphs: &[ParagraphDiff]) -> (usize, usize) {
+    let count = |op: DiffOp| {
+        paragraphs
+            .iter()
+            .flat_map(|p| &p.runs)
+            .filter(|r| r.op == op)
+            .map(|r| non_space_chars(&r.text))
+            .sum()
+    };
+    (count(DiffOp::Insert), count(DiffOp::Delete))
+}
+
+/// 把旧版本中选中的段落（按旧版本的段落序号）合并进当前正文：
+/// 当前缺失的段落插回原来的位置，被改动的段落换回旧版本，其余内容保持不变。
+/// 返回合并后的正文和实际恢复的段落序号
+pub fn restore_paragraphs(current: &str, snapshot: &str, selected: &[Range<usize>]) -> (String, Vec<usize>) {
+    let is_selected = |index: usize| selected.iter().any(|range| range.contains(&index));
+    let mut paragraphs = Vec::new();
+    let mut restored = Vec::new();
+    for paragraph in diff_paragraphs(current, snapshot) {
+        let text = |op: DiffOp| {
+            paragraph.runs.iter().filter(|r| r.op == DiffOp::Equal || r.op == op).map(|r| r.text.as_str()).collect::<String>()
+        };
+        match (paragraph.change, paragraph.new_index) {
+            (ParagraphChange::Modified | ParagraphChange::Added, Some(index)) if is_selected(index) => {
+                paragraphs.push(text(DiffOp::Insert));
+                restored.push(index);
+            }
+            (ParagraphChange::Added, _) => {}
+            _ => paragraphs.push(text(DiffOp::Delete)),
+        }
+    }
+    (paragraphs.join("\n"), restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_mixed_text() {
+        assert_eq!(tokenize("他说：“Hello world 2024”"), vec!["他", "说", "：", "“", "Hello", " ", "world", " ", "2024", "”"]);
+    }
+
+    #[test]
+    fn test_paragraph_and_word_diff() {
+        let old = "第一段。\n他走进了雨夜的街道。\nThe quick fox\n结尾";
+        let new = "第一段。\n她缓缓走进了雨夜的街道。\nThe slow fox\n新增的一段\n结尾";
+        let diff = diff_paragraphs(old, new);
+        let changes: Vec<ParagraphChange> = diff.iter().map(|p| p.change).collect();
+        assert_eq!(
+            changes,
+            vec![
+                ParagraphChange::Unchanged,
+                ParagraphChange::Modified,
+                ParagraphChange::Modified,
+                ParagraphChange::Added,
+                ParagraphChange::Unchanged
+            ]
+        );
+        let run = |op, text: &str| DiffRun { op, text: text.to_string() };
+        assert_eq!(
+            diff[1].runs,
+            vec![run(DiffOp::Delete, "他"), run(DiffOp::Insert, "她缓缓"), run(DiffOp::Equal, "走进了雨夜的街道。")]
+        );
+        assert_eq!(
+            diff[2].runs,
+            vec![run(DiffOp::Equal, "The "), run(DiffOp::Delete, "quick"), run(DiffOp::Insert, "slow"), run(DiffOp::Equal, " fox")]
+        );
+        assert_eq!(change_counts(&diff), (3 + 4 + 5, 1 + 5));
+    }
+
+    #[test]
+    fn test_restore_selected_paragraphs() {
+        let snapshot = "开头\n被删掉的那场戏\n中段旧稿\n结尾";
+        let current = "开头\n中段新稿\n结尾\n新写的尾声";
+        let (draft, restored) = restore_paragraphs(current, snapshot, std::slice::from_ref(&(1..2)));
+        assert_eq!(draft, "开头\n被删掉的那场戏\n中段新稿\n结尾\n新写的尾声");
+        assert_eq!(restored, vec![1]);
+
+        let (draft, restored) = restore_paragraphs(current, snapshot, &[2..3, 3..4]);
+        assert_eq!(draft, "开头\n中段旧稿\n结尾\n新写的尾声");
+        assert_eq!(restored, vec![2]);
+    }
+}
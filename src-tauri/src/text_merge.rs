@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+
+pub const CONFLICT_START: &str = "<<<<<<< 本地";
+pub const CONFLICT_SEPARATOR: &str = "=======";
+pub const CONFLICT_END: &str = ">>>>>>> 远端";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeHunkKind {
+    Unchanged,
+    /// 只有本地修改
+    Local,
+    /// 只有远端修改
+    Remote,
+    /// 两侧做了相同的修改
+    Both,
+    Conflict,
+}
+
+/// 合并结果中的一段，按段落（行）划分
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MergeHunk {
+    pub index: usize,
+    pub kind: MergeHunkKind,
+    pub base: Vec<String>,
+    pub local: Vec<String>,
+    pub remote: Vec<String>,
+}
+
+impl MergeHunk {
+    /// 无冲突时采用的段落
+    fn resolved(&self) -> &[String] {
+        match self.kind {
+            MergeHunkKind::Remote => &self.remote,
+            _ => &self.local,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MergeResult {
+    /// 合并后的文本，冲突处带有 `<<<<<<<`/`=======`/`>>>>>>>` 标记
+    pub merged: String,
+    pub hunks: Vec<MergeHunk>,
+    pub conflict_count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HunkChoice {
+    Local,
+    Remote,
+    /// 先本地后远端
+    Both,
+    Base,
+    Custom,
+}
+
+/// 用户对某个冲突段的处理
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HunkResolution {
+    pub hunk: usize,
+    pub choice: HunkChoice,
+    /// choice 为 Custom 时使用的文本
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+pub(crate) fn split_paragraphs(text: &str) -> Vec<String> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+    text.split('\n').map(str::to_string).collect()
+}
+
+/// 基于最长公共子序列，返回 a 中每一项在 b 中的匹配位置
+pub(crate) fn lcs_matches<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Option<usize>> {
+    let (n, m) = (a.len(), b.len());
+    let mut table = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i][j] = if a[i] == b[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let mut matches = vec![None; n];
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            matches[i] = Some(j);
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    matches
+}
+
+/// 按段落做三方合并（diff3）：两侧都未改动或只有一侧改动的段落自动合并，
+/// 两侧对同一处做了不同修改时产生冲突段
+pub fn merge_three_way(base: &str, local: &str, remote: &str) -> MergeResult {
+    let base = split_paragraphs(base);
+    let local = split_paragraphs(local);
+    let remote = split_paragraphs(remote);
+    let to_local = lcs_matches(&base, &local);
+    let to_remote = lcs_matches(&base, &remote);
+
+    let mut hunks: Vec<MergeHunk> = Vec::new();
+    let mut push = |kind: MergeHunkKind, b: &[String], l: &[String], r: &[String]| {
+        if kind == MergeHunkKind::Unchanged {
+            if let Some(last) = hunks.last_mut().filter(|h| h.kind == MergeHunkKind::Unchanged) {
+                last.base.extend_from_slice(b);
+                last.local.extend_from_slice(l);
+                last.remote.extend_from_slice(r);
+                return;
+            }
+        }
+        hunks.push(MergeHunk { index: hunks.len(), kind, base: b.to_vec(), local: l.to_vec(), remote: r.to_vec() });
+    };
+
+    let (mut i, mut j, mut k) = (0, 0, 0);
+    loop {
+        // 下一个在三方都保持不变的基线段落
+        let stable = (i..base.len()).find_map(|x| match (to_local[x], to_remote[x]) {
+            (Some(l), Some(r)) if l >= j && r >= k => Some((x, l, r)),
+            _ => None,
+        });
+        let (ni, nj, nk) = stable.unwrap_or((base.len(), local.len(), remote.len()));
+
+        let (b, l, r) = (&base[i..ni], &local[j..nj], &remote[k..nk]);
+        if !(b.is_empty() && l.is_empty() && r.is_empty()) {
+            let kind = if l == b {
+                MergeHunkKind::Remote
+            } else if r == b {
+                MergeHunkKind::Local
+            } else if l == r {
+                MergeHunkKind::Both
+            } else {
+                MergeHunkKind::Conflict
+            };
+            push(kind, b, l, r);
+        }
+
+        match stable {
+            Some((x, l, r)) => {
+                push(MergeHunkKind::Unchanged, &base[x..=x], &local[l..=l], &remote[r..=r]);
+                (i, j, k) = (x + 1, l + 1, r + 1);
+            }
+            None => break,
+        }
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    for hunk in &hunks {
+        if hunk.kind == MergeHunkKind::Conflict {
+            lines.push(CONFLICT_START.to_string());
+            lines.extend(hunk.local.iter().cloned());
+            lines.push(CONFLICT_SEPARATOR.to_string());
+            lines.extend(hunk.remote.iter().cloned());
+            lines.push(CONFLICT_END.to_string());
+        } else {
+            lines.extend(hunk.resolved().iter().cloned());
+        }
+    }
+    let conflict_count = hunks.iter().filter(|h| h.kind == MergeHunkKind::Conflict).count();
+    MergeResult { merged: lines.join("\n"), hunks, conflict_count }
+}
+
+/// 按用户的选择生成最终文本；每个冲突段都必须有对应的处理
+pub fn resolve_merge(result: &MergeResult, resolutions: &[HunkResolution]) -> Result<String, String> {
+    let mut lines: Vec<String> = Vec::new();
+    for hunk in &result.hunks {
+        let resolution = resolutions.iter().find(|r| r.hunk == hunk.index);
+        match (hunk.kind, resolution) {
+            (MergeHunkKind::Conflict, None) => return Err(format!("第 {} 段冲突尚未处理", hunk.index + 1)),
+            (_, None) => lines.extend(hunk.resolved().iter().cloned()),
+            (_, Some(resolution)) => match resolution.choice {
+                HunkChoice::Local => lines.extend(hunk.local.iter().cloned()),
+                HunkChoice::Remote => lines.extend(hunk.remote.iter().cloned()),
+                HunkChoice::Both => {
+                    lines.extend(hunk.local.iter().cloned());
+                    lines.extend(hunk.remote.iter().cloned());
+                }
+                HunkChoice::Base => lines.extend(hunk.base.iter().cloned()),
+                HunkChoice::Custom => {
+                    let text = resolution.text.as_deref().unwrap_or_default();
+                    lines.extend(split_paragraphs(text));
+                }
+            },
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_merge() {
+        let base = "第一段\n第二段\n第三段\n第四段";
+        let local = "第一段（改）\n第二段\n第三段\n第四段";
+        let remote = "第一段\n第二段\n第三段\n第四段\n第五段";
+        let result = merge_three_way(base, local, remote);
+        assert_eq!(result.conflict_count, 0);
+        assert_eq!(result.merged, "第一段（改）\n第二段\n第三段\n第四段\n第五段");
+    }
+
+    #[test]
+    fn test_conflict_and_resolution() {
+        let base = "开头\n他走进房间。\n结尾";
+        let local = "开头\n他推门走进房间。\n结尾";
+        let remote = "开头\n她走进房间。\n结尾";
+        let result = merge_three_way(base, local, remote);
+        assert_eq!(result.conflict_count, 1);
+        assert!(result.merged.contains(CONFLICT_START));
+        let conflict = result.hunks.iter().find(|h| h.kind == MergeHunkKind::Conflict).unwrap();
+        assert_eq!(conflict.base, vec!["他走进房间。"]);
+
+        assert!(resolve_merge(&result, &[]).is_err());
+        let resolved = resolve_merge(
+            &result,
+            &[HunkResolution { hunk: conflict.index, choice: HunkChoice::Custom, text: Some("她推门走进房间。".into()) }],
+        )
+        .unwrap();
+        assert_eq!(resolved, "开头\n她推门走进房间。\n结尾");
+    }
+
+    #[test]
+    fn test_identical_edits_and_deletions() {
+        let result = merge_three_way("a\nb\nc", "a\nB\nc", "a\nB\nc");
+        assert_eq!(result.conflict_count, 0);
+        assert_eq!(result.hunks[1].kind, MergeHunkKind::Both);
+        assert_eq!(result.merged, "a\nB\nc");
+        let result = merge_three_way("a\nb\nc", "a\nc", "a\nb\nc");
+        assert_eq!(result.merged, "a\nc");
+    }
+}
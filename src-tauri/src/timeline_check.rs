@@ -0,0 +1,511 @@
+use crate::calendar::{CalendarConfig, CalendarDate};
+use crate::entity_index::locate_terms;
+use crate::import::directory_import::{is_chinese_numeral, parse_chinese_number};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+const SEASONS: [char; 4] = ['春', '夏', '秋', '冬'];
+/// 正文中表示季节的词和对应的季节序号
+const SEASON_WORDS: &[(&str, usize)] = &[
+    ("初春", 0), ("春天", 0), ("春日", 0), ("春风", 0), ("开春", 0),
+    ("盛夏", 1), ("夏天", 1), ("夏日", 1), ("酷暑", 1), ("仲夏", 1),
+    ("初秋", 2), ("秋天", 2), ("秋日", 2), ("深秋", 2), ("秋风", 2),
+    ("寒冬", 3), ("冬天", 3), ("冬日", 3), ("隆冬", 3), ("初冬", 3),
+];
+/// 出现这些词时认为进入了新的一年，季节可以从头开始
+const YEAR_ADVANCE: &[&str] = &["第二年", "次年", "翌年", "来年", "年后", "过年", "新年", "除夕"];
+/// “三年后”“两年前”“十年来”一类的相对时间，不是纪年
+const RELATIVE_YEAR_SUFFIX: &[char] = &['后', '前', '来', '间', '里', '内', '多', '的', '了'];
+
+/// 冲突涉及的记录，`source_type` 为 character、character_event、worldview_event 或 chapter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceRef {
+    pub source_type: String,
+    pub id: String,
+    pub label: String,
+    pub chapter_id: Option<String>,
+}
+
+/// `kind` 为 before_birth、after_death、out_of_order、age_regression、age_mismatch 或 season_regression
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineViolation {
+    pub kind: String,
+    pub severity: String,
+    pub description: String,
+    pub sources: Vec<SourceRef>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineReport {
+    pub violations: Vec<TimelineViolation>,
+    pub events_checked: usize,
+    pub chapters_checked: usize,
+}
+
+/// 从时间描述中解析出的纪年和季节，解析不出的部分为 None
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StoryTime {
+    pub year: Option<i64>,
+    pub season: Option<usize>,
+    /// 按项目历法换算的天数，日期精确到月时才有
+    pub day: Option<i64>,
+}
+
+impl StoryTime {
+    /// 两个时间都有纪年时才能比较；都有具体日期时按天比较，同一年且都有季节时再比较季节
+    fn compare(&self, other: &StoryTime) -> Option<Ordering> {
+        if let (Some(a), Some(b)) = (self.day, other.day) {
+            return Some(a.cmp(&b));
+        }
+        let ordering = self.year?.cmp(&other.year?);
+        match (ordering, self.season, other.season) {
+            (Ordering::Equal, Some(a), Some(b)) => Some(a.cmp(&b)),
+            _ => Some(ordering),
+        }
+    }
+
+    fn label(&self) -> String {
+        let year = self.year.map(|y| format!("{}年", y)).unwrap_or_default();
+        let season = self.season.map(|s| SEASONS[s].to_string()).unwrap_or_default();
+        format!("{}{}", year, season)
+    }
+}
+
+/// 文本中第一个纪年，“元年”记为 1；`min_year` 用于过滤正文里“等了三年”这类时长
+pub(crate) fn parse_year(text: &str, min_year: i64) -> Option<i64> {
+    year_at(text, min_year).map(|(year, _)| year)
+}
+
+/// 同 `parse_year`，另外给出“年”字之后的字节位置
+pub(crate) fn year_at(text: &str, min_year: i64) -> Option<(i64, usize)> {
+    let chars: Vec<char> = text.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        if c != '年' || chars.get(i + 1).is_some_and(|next| RELATIVE_YEAR_SUFFIX.contains(next)) {
+            continue;
+        }
+        let mut start = i;
+        while start > 0 && (chars[start - 1].is_ascii_digit() || is_chinese_numeral(chars[start - 1])) {
+            start -= 1;
+        }
+        let year = if start == i {
+            (i > 0 && chars[i - 1] == '元').then_some(1)
+        } else {
+            let digits: String = chars[start..i].iter().collect();
+            Some(if digits.chars().all(|d| d.is_ascii_digit()) { digits.parse().unwrap_or(0) } else { parse_chinese_number(&digits) as i64 })
+        };
+        match year {
+            Some(year) if year >= min_year => {
+                let end = chars[..=i].iter().map(|c| c.len_utf8()).sum();
+                return Some((year, end));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+pub fn parse_story_time(text: &str) -> StoryTime {
+    StoryTime { year: parse_year(text, 1), season: text.chars().find_map(|c| SEASONS.iter().position(|&s| s == c)), day: None }
+}
+
+/// 优先用事件的结构化日期，其次按项目历法解析时间描述（纪元换算成绝对年份），季节仍从描述中读
+fn event_time(calendar: &CalendarConfig, date: Option<&str>, text: Option<&str>) -> StoryTime {
+    let mut time = text.map(parse_story_time).unwrap_or_default();
+    let date = date.and_then(|json| serde_json::from_str::<CalendarDate>(json).ok()).or_else(|| calendar.parse(text?));
+    if let Some(date) = date {
+        time.year = Some(date.year);
+        time.day = date.month.map(|_| calendar.day_number(&date));
+    }
+    time
+}
+
+/// 从出生到某个时间的周岁，有具体日期时按天数算
+fn age_at(birth: &StoryTime, time: &StoryTime, days_per_year: i64) -> Option<i64> {
+    match (birth.day, time.day) {
+        (Some(born), Some(now)) => Some((now - born).div_euclid(days_per_year)),
+        _ => Some(time.year? - birth.year?),
+    }
+}
+
+/// 正文中的季节词，按出现顺序
+fn seasons_in(text: &str) -> Vec<usize> {
+    let terms: Vec<&str> = SEASON_WORDS.iter().map(|(word, _)| *word).collect();
+    locate_terms(text, &terms).into_iter().map(|(_, _, i)| SEASON_WORDS[i].1).collect()
+}
+
+/// 正文中“N岁”的年龄，归给同一句里离数字最近的前一个角色名，没有时取“岁的”后紧跟的角色名
+fn age_mentions(text: &str, characters: &[(String, Vec<String>)]) -> Vec<(usize, i64)> {
+    let mut found = Vec::new();
+    for (byte, _) in text.match_indices('岁') {
+        let before: Vec<char> = text[..byte].chars().collect();
+        let mut start = before.len();
+        while start > 0 && (before[start - 1].is_ascii_digit() || is_chinese_numeral(before[start - 1])) {
+            start -= 1;
+        }
+        if start == before.len() {
+            continue;
+        }
+        let digits: String = before[start..].iter().collect();
+        let age = if digits.chars().all(|d| d.is_ascii_digit()) { digits.parse().unwrap_or(0) } else { parse_chinese_number(&digits) as i64 };
+        let sentence_start = before[..start].iter().rposition(|c| matches!(c, '。' | '！' | '？' | '\n' | '；')).map(|p| p + 1).unwrap_or(0);
+        let sentence: String = before[sentence_start..start].iter().collect();
+        let after = &text[byte + '岁'.len_utf8()..];
+        let owner = characters
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (_, terms))| terms.iter().filter_map(|t| sentence.rfind(t.as_str())).max().map(|p| (p, i)))
+            .max()
+            .map(|(_, i)| i)
+            .or_else(|| {
+                let after = after.strip_prefix('的')?;
+                characters.iter().position(|(_, terms)| terms.iter().any(|t| after.starts_with(t.as_str())))
+            });
+        if let Some(owner) = owner {
+            found.push((owner, age));
+        }
+    }
+    found
+}
+
+struct CharacterInfo {
+    id: String,
+    name: String,
+    terms: Vec<String>,
+    birth: Option<(StoryTime, SourceRef)>,
+    death: Option<(StoryTime, SourceRef)>,
+}
+
+struct TimedEvent {
+    source: SourceRef,
+    event_type: String,
+    time: StoryTime,
+}
+
+fn violation(kind: &str, severity: &str, description: String, sources: Vec<SourceRef>) -> TimelineViolation {
+    TimelineViolation { kind: kind.to_string(), severity: severity.to_string(), description, sources }
+}
+
+/// 按排序先后检查一组事件的时间是否倒退
+fn check_order(events: &[TimedEvent], owner: &str, violations: &mut Vec<TimelineViolation>) {
+    let mut latest: Option<&TimedEvent> = None;
+    for event in events {
+        if let Some(previous) = latest {
+            if event.time.compare(&previous.time) == Some(Ordering::Less) {
+                violations.push(violation(
+                    "out_of_order",
+                    "medium",
+                    format!(
+                        "{}的时间线中“{}”（{}）排在“{}”（{}）之后",
+                        owner,
+                        event.source.label,
+                        event.time.label(),
+                        previous.source.label,
+                        previous.time.label()
+                    ),
+                    vec![previous.source.clone(), event.source.clone()],
+                ));
+                continue;
+            }
+        }
+        if event.time.year.is_some() {
+            latest = Some(event);
+        }
+    }
+}
+
+/// 检查事件是否发生在角色出生之前或死亡之后
+fn check_life_span(character: &CharacterInfo, event: &TimedEvent, violations: &mut Vec<TimelineViolation>) {
+    if event.event_type == "birth" || event.event_type == "death" {
+        return;
+    }
+    if let Some((birth, birth_source)) = &character.birth {
+        if event.time.compare(birth) == Some(Ordering::Less) {
+            violations.push(violation(
+                "before_birth",
+                "high",
+                format!("“{}”（{}）早于{}的出生（{}）", event.source.label, event.time.label(), character.name, birth.label()),
+                vec![birth_source.clone(), event.source.clone()],
+            ));
+        }
+    }
+    if let Some((death, death_source)) = &character.death {
+        if event.time.compare(death) == Some(Ordering::Greater) {
+            violations.push(violation(
+                "after_death",
+                "high",
+                format!("“{}”（{}）晚于{}的死亡（{}）", event.source.label, event.time.label(), character.name, death.label()),
+                vec![death_source.clone(), event.source.clone()],
+            ));
+        }
+    }
+}
+
+fn load_characters(conn: &Connection, project_id: &str, calendar: &CalendarConfig) -> Result<(Vec<CharacterInfo>, Vec<Vec<TimedEvent>>), String> {
+    let rows: Vec<(String, String, Option<String>)> = conn
+        .prepare("SELECT id, name, birth_date FROM characters WHERE project_id = ? AND merged_into IS NULL ORDER BY created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut aliases: HashMap<String, Vec<String>> = HashMap::new();
+    let mut stmt = conn
+        .prepare("SELECT a.character_id, a.alias FROM character_aliases a JOIN characters c ON c.id = a.character_id WHERE c.project_id = ?")
+        .map_err(|e| e.to_string())?;
+    for row in stmt.query_map(params![project_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))).map_err(|e| e.to_string())? {
+        let (character_id, alias) = row.map_err(|e| e.to_string())?;
+        aliases.entry(character_id).or_default().push(alias);
+    }
+
+    let mut characters = Vec::new();
+    let mut timelines = Vec::new();
+    for (id, name, birth_date) in rows {
+        let events: Vec<TimedEvent> = conn
+            .prepare(
+                "SELECT id, event_type, event_title, story_time, real_chapter_id, story_date FROM character_timeline_events
+                 WHERE character_id = ? ORDER BY sort_order, created_at",
+            )
+            .map_err(|e| e.to_string())?
+            .query_map(params![id], |row| {
+                let story_time: Option<String> = row.get(3)?;
+                let story_date: Option<String> = row.get(5)?;
+                Ok(TimedEvent {
+                    source: SourceRef { source_type: "character_event".to_string(), id: row.get(0)?, label: row.get(2)?, chapter_id: row.get(4)? },
+                    event_type: row.get(1)?,
+                    time: event_time(calendar, story_date.as_deref(), story_time.as_deref()),
+                })
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+        let find = |event_type: &str| {
+            events.iter().find(|e| e.event_type == event_type && e.time.year.is_some()).map(|e| (e.time, e.source.clone()))
+        };
+        let character_source = SourceRef { source_type: "character".to_string(), id: id.clone(), label: name.clone(), chapter_id: None };
+        let birth = find("birth").or_else(|| {
+            let time = event_time(calendar, None, birth_date.as_deref());
+            time.year.map(|_| (time, character_source))
+        });
+        let death = find("death");
+        let mut terms = vec![name.clone()];
+        terms.extend(aliases.remove(&id).unwrap_or_default());
+        characters.push(CharacterInfo { id, name, terms, birth, death });
+        timelines.push(events);
+    }
+    Ok((characters, timelines))
+}
+
+/// 交叉检查角色时间线、世界观时间线和正文中的年份、年龄、季节，找出不可能的先后顺序
+pub fn check_project(conn: &Connection, project_id: &str) -> Result<TimelineReport, String> {
+    let calendar = crate::calendar::load_calendar(conn, project_id)?;
+    let (characters, timelines) = load_characters(conn, project_id, &calendar)?;
+    let mut violations = Vec::new();
+    let mut events_checked = 0;
+
+    for (character, events) in characters.iter().zip(&timelines) {
+        events_checked += events.len();
+        check_order(events, &character.name, &mut violations);
+        for event in events {
+            check_life_span(character, event, &mut violations);
+        }
+    }
+
+    let world_views: Vec<(String, String)> = conn
+        .prepare("SELECT id, title FROM world_views WHERE project_id = ? ORDER BY created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    for (world_view_id, title) in world_views {
+        let events: Vec<(TimedEvent, String)> = conn
+            .prepare(
+                "SELECT id, event_type, event_title, story_time, COALESCE(related_characters, ''), story_date FROM worldview_timeline_events
+                 WHERE worldview_id = ? ORDER BY sort_order, created_at",
+            )
+            .map_err(|e| e.to_string())?
+            .query_map(params![world_view_id], |row| {
+                let story_time: Option<String> = row.get(3)?;
+                let story_date: Option<String> = row.get(5)?;
+                Ok((
+                    TimedEvent {
+                        source: SourceRef { source_type: "worldview_event".to_string(), id: row.get(0)?, label: row.get(2)?, chapter_id: None },
+                        event_type: row.get(1)?,
+                        time: event_time(&calendar, story_date.as_deref(), story_time.as_deref()),
+                    },
+                    row.get(4)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+        events_checked += events.len();
+        let (events, related): (Vec<TimedEvent>, Vec<String>) = events.into_iter().unzip();
+        check_order(&events, &title, &mut violations);
+        for (event, related) in events.iter().zip(&related) {
+            for character in characters.iter().filter(|c| c.terms.iter().any(|t| related.contains(t.as_str()))) {
+                check_life_span(character, event, &mut violations);
+            }
+        }
+    }
+
+    let chapters: Vec<(String, String, String)> = conn
+        .prepare("SELECT id, title, COALESCE(content, '') FROM chapters WHERE project_id = ? ORDER BY sort_order, created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let chapter_source = |index: usize| SourceRef {
+        source_type: "chapter".to_string(),
+        id: chapters[index].0.clone(),
+        label: chapters[index].1.clone(),
+        chapter_id: Some(chapters[index].0.clone()),
+    };
+    let terms: Vec<(String, Vec<String>)> = characters.iter().map(|c| (c.id.clone(), c.terms.clone())).collect();
+    // 每个角色目前为止在正文中最大的年龄和所在章节
+    let mut ages: HashMap<usize, (i64, usize)> = HashMap::new();
+    let mut last_season: Option<(usize, usize)> = None;
+    // 挂在章节上、带纪年的角色事件给出该章的故事时间
+    let mut chapter_times: HashMap<&str, StoryTime> = HashMap::new();
+    for event in timelines.iter().flatten().filter(|e| e.time.year.is_some()) {
+        if let Some(chapter_id) = &event.source.chapter_id {
+            chapter_times.entry(chapter_id.as_str()).or_insert(event.time);
+        }
+    }
+
+    for (index, (id, title, content)) in chapters.iter().enumerate() {
+        for (owner, age) in age_mentions(content, &terms) {
+            // 相差一岁以内可能是虚岁或生日未到，不提示
+            if let (Some(time), Some((birth, birth_source))) = (chapter_times.get(id.as_str()), &characters[owner].birth) {
+                if let Some(expected) = age_at(birth, time, calendar.days_per_year()).filter(|expected| (age - expected).abs() > 1) {
+                    violations.push(violation(
+                        "age_mismatch",
+                        "medium",
+                        format!("{}在“{}”中是{}岁，但按时间线此时（{}）应为{}岁", characters[owner].name, title, age, time.label(), expected),
+                        vec![birth_source.clone(), chapter_source(index)],
+                    ));
+                }
+            }
+            match ages.get(&owner) {
+                Some(&(previous, previous_chapter)) if age < previous => violations.push(violation(
+                    "age_regression",
+                    "medium",
+                    format!("{}在“{}”中是{}岁，之前的“{}”中已经{}岁", characters[owner].name, title, age, chapters[previous_chapter].1, previous),
+                    vec![chapter_source(previous_chapter), chapter_source(index)],
+                )),
+                Some(&(previous, _)) if age == previous => {}
+                _ => {
+                    ages.insert(owner, (age, index));
+                }
+            }
+        }
+
+        let seasons = seasons_in(content);
+        let new_year = YEAR_ADVANCE.iter().any(|w| content.contains(w));
+        if let (Some((previous, previous_chapter)), Some(&first), false) = (last_season, seasons.first(), new_year) {
+            // 只往回退一个季节（如秋天之后又是夏天）才提示，往前跨三个季节而没有交代新年的情况较少
+            if (previous + 4 - first) % 4 == 1 {
+                violations.push(violation(
+                    "season_regression",
+                    "low",
+                    format!("“{}”是{}天，但前面的“{}”已经是{}天", title, SEASONS[first], chapters[previous_chapter].1, SEASONS[previous]),
+                    vec![chapter_source(previous_chapter), chapter_source(index)],
+                ));
+            }
+        }
+        last_season = match seasons.last() {
+            Some(&season) => Some((season, index)),
+            None if new_year => None,
+            None => last_season,
+        };
+
+        // 正文里明确写了纪年（三位数以上）时，检查出场的角色是否已经出生
+        if let Some(year) = parse_year(content, 100) {
+            let time = StoryTime { year: Some(year), season: None, day: None };
+            for character in &characters {
+                let Some((birth, birth_source)) = &character.birth else { continue };
+                let mentioned = !locate_terms(content, &character.terms.iter().map(String::as_str).collect::<Vec<_>>()).is_empty();
+                if mentioned && time.compare(birth) == Some(Ordering::Less) {
+                    violations.push(violation(
+                        "before_birth",
+                        "medium",
+                        format!("“{}”发生在{}年，{}此时还没有出生（{}）", title, year, character.name, birth.label()),
+                        vec![birth_source.clone(), chapter_source(index)],
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(TimelineReport { violations, events_checked, chapters_checked: chapters.len() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeline_violations_from_events_and_text() {
+        assert_eq!(parse_story_time("天启三年春").year, Some(3));
+        assert_eq!(parse_story_time("1024年冬"), StoryTime { year: Some(1024), season: Some(3), day: None });
+        assert_eq!(parse_story_time("三年后").year, None);
+
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES ('r1', 'p1', '林舟', 't0', 't0');
+             INSERT INTO character_timeline_events (id, character_id, event_type, event_title, story_time, sort_order, created_at, updated_at) VALUES
+                 ('e1', 'r1', 'birth', '降生', '1000年春', 1, 't0', 't0'),
+                 ('e2', 'r1', 'milestone', '拜师', '998年', 2, 't0', 't0'),
+                 ('e3', 'r1', 'milestone', '下山', '1016年', 3, 't0', 't0');
+             INSERT INTO world_views (id, project_id, category, title, content, created_at, updated_at) VALUES ('w1', 'p1', 'history', '王朝史', '', 't0', 't0');
+             INSERT INTO worldview_timeline_events (id, worldview_id, event_type, event_title, story_time, related_characters, sort_order, created_at, updated_at) VALUES
+                 ('v1', 'w1', 'war', '北境之战', '995年', '林舟', 1, 't0', 't0');
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '深秋，十七岁的林舟走出山门。', 1, 't0', 't0'),
+                 ('c2', 'p1', '第二章', '夏天的蝉鸣里，林舟说自己今年十六岁。', 2, 't0', 't0'),
+                 ('c3', 'p1', '第三章', '第二年春天，他回到了山里。', 3, 't0', 't0');",
+        )
+        .unwrap();
+
+        let report = check_project(&conn, "p1").unwrap();
+        assert_eq!((report.events_checked, report.chapters_checked), (4, 3));
+        let kinds: Vec<&str> = report.violations.iter().map(|v| v.kind.as_str()).collect();
+        assert_eq!(kinds, vec!["out_of_order", "before_birth", "before_birth", "age_regression", "season_regression"]);
+        let war = &report.violations[2];
+        assert_eq!((war.sources[0].id.as_str(), war.sources[1].id.as_str()), ("e1", "v1"));
+        assert_eq!(report.violations[3].sources[1].chapter_id.as_deref(), Some("c2"));
+    }
+
+    #[test]
+    fn test_calendar_dates_and_age_intervals() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES ('r1', 'p1', '林舟', 't0', 't0');
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '十七岁的林舟走出山门。', 1, 't0', 't0'),
+                 ('c2', 'p1', '第二章', '二十五岁的林舟回到京城。', 2, 't0', 't0');
+             INSERT INTO character_timeline_events (id, character_id, event_type, event_title, story_time, story_date, real_chapter_id, sort_order, created_at, updated_at) VALUES
+                 ('e1', 'r1', 'birth', '降生', '天启元年', '{\"year\": 1000, \"month\": 3, \"day\": 1}', NULL, 1, 't0', 't0'),
+                 ('e2', 'r1', 'milestone', '下山', '永安三年', NULL, 'c1', 2, 't0', 't0'),
+                 ('e3', 'r1', 'milestone', '回京', NULL, '{\"year\": 1022, \"month\": 2, \"day\": 1}', 'c2', 3, 't0', 't0');",
+        )
+        .unwrap();
+        let config = crate::calendar::CalendarConfig {
+            eras: vec![
+                crate::calendar::Era { name: "天启".to_string(), start_year: 1000 },
+                crate::calendar::Era { name: "永安".to_string(), start_year: 1015 },
+            ],
+            ..Default::default()
+        };
+        crate::calendar::save_calendar(&conn, "p1", config).unwrap();
+
+        // 永安三年是 1017 年，十七岁合理；1022 年二月还差一个月满二十二岁，写成二十五岁
+        let report = check_project(&conn, "p1").unwrap();
+        let kinds: Vec<&str> = report.violations.iter().map(|v| v.kind.as_str()).collect();
+        assert_eq!(kinds, vec!["age_mismatch"]);
+        assert!(report.violations[0].description.contains("应为21岁"));
+    }
+}
@@ -0,0 +1,154 @@
+use chrono::Utc;
+use jieba_rs::Jieba;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::sync::{OnceLock, RwLock};
+
+/// 文本分析和写作工具共用的中文分词器，默认词典之外加载用户词典
+static TOKENIZER: OnceLock<RwLock<Jieba>> = OnceLock::new();
+
+fn tokenizer() -> &'static RwLock<Jieba> {
+    TOKENIZER.get_or_init(|| RwLock::new(Jieba::new()))
+}
+
+/// 用户词典中的词，如自创的人名、地名、功法
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DictionaryWord {
+    pub word: String,
+    pub project_id: Option<String>,
+    pub created_at: String,
+}
+
+/// 分词，保留标点和空白
+pub fn cut(text: &str) -> Vec<&str> {
+    tokenizer().read().unwrap().cut(text, true)
+}
+
+/// 分词后只保留包含文字或数字的词
+pub fn words(text: &str) -> Vec<&str> {
+    cut(text).into_iter().filter(|w| w.chars().any(char::is_alphanumeric)).collect()
+}
+
+/// 带词性的分词结果，词性沿用 jieba 的标注，如 nr 人名、ns 地名
+pub fn tag(text: &str) -> Vec<(String, String)> {
+    let jieba = tokenizer().read().unwrap();
+    jieba.tag(text, true).into_iter().map(|t| (t.word.to_string(), t.tag.to_string())).collect()
+}
+
+/// 默认词典的总词频，与 `reference_frequency` 一起估算词在通用语料中的频率
+pub const REFERENCE_TOTAL: f64 = 60_101_967.0;
+
+/// 词（或短语）在默认词典语料中的词频；词典里没有的按组成它的词估算
+pub fn reference_frequency(term: &str) -> usize {
+    tokenizer().read().unwrap().suggest_freq(term)
+}
+
+pub fn add_words<S: AsRef<str>>(words: &[S]) {
+    let mut jieba = tokenizer().write().unwrap();
+    for word in words {
+        let word = word.as_ref().trim();
+        if !word.is_empty() && !word.contains(char::is_whitespace) {
+            jieba.add_word(word, None, None);
+        }
+    }
+}
+
+fn query_words(conn: &Connection, sql: &str) -> Result<Vec<String>, String> {
+    conn.prepare(sql)
+        .map_err(|e| e.to_string())?
+        .query_map([], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 把用户词典、所有角色名和别名、世界观条目名加入分词器，返回加入的词数
+pub fn load_user_dictionary(conn: &Connection) -> Result<usize, String> {
+    let mut all = query_words(conn, "SELECT word FROM user_dictionary")?;
+    all.extend(query_words(conn, "SELECT name FROM characters")?);
+    all.extend(query_words(conn, "SELECT alias FROM character_aliases")?);
+    all.extend(query_words(conn, "SELECT title FROM world_views")?);
+    add_words(&all);
+    Ok(all.len())
+}
+
+/// 重建分词器，用于删除用户词典中的词之后
+pub fn reload_user_dictionary(conn: &Connection) -> Result<usize, String> {
+    *tokenizer().write().unwrap() = Jieba::new();
+    load_user_dictionary(conn)
+}
+
+/// 加入用户词典并立即生效，已有的词忽略
+pub fn add_dictionary_words(conn: &Connection, project_id: Option<&str>, words: &[String]) -> Result<Vec<DictionaryWord>, String> {
+    let now = Utc::now().to_rfc3339();
+    let mut added = Vec::new();
+    for word in words {
+        let word = word.trim();
+        if word.is_empty() || word.contains(char::is_whitespace) {
+            continue;
+        }
+        let inserted = conn
+            .execute(
+                "INSERT OR IGNORE INTO user_dictionary (word, project_id, created_at) VALUES (?, ?, ?)",
+                params![word, project_id, now],
+            )
+            .map_err(|e| e.to_string())?;
+        if inserted > 0 {
+            added.push(DictionaryWord { word: word.to_string(), project_id: project_id.map(str::to_string), created_at: now.clone() });
+        }
+    }
+    add_words(&added.iter().map(|w| w.word.as_str()).collect::<Vec<_>>());
+    Ok(added)
+}
+
+pub fn remove_dictionary_word(conn: &Connection, word: &str) -> Result<(), String> {
+    let deleted = conn.execute("DELETE FROM user_dictionary WHERE word = ?", params![word]).map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("词典中没有“{}”", word));
+    }
+    reload_user_dictionary(conn)?;
+    Ok(())
+}
+
+/// 用户词典，指定项目时包括该项目的词和不属于任何项目的词
+pub fn list_dictionary_words(conn: &Connection, project_id: Option<&str>) -> Result<Vec<DictionaryWord>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT word, project_id, created_at FROM user_dictionary
+             WHERE ?1 IS NULL OR project_id IS NULL OR project_id = ?1 ORDER BY created_at, word",
+        )
+        .map_err(|e| e.to_string())?;
+    let words = stmt
+        .query_map(params![project_id], |row| {
+            Ok(DictionaryWord { word: row.get(0)?, project_id: row.get(1)?, created_at: row.get(2)? })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_dictionary_words_stay_whole() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("dict.db");
+        crate::database::init_database(&db_path).unwrap();
+        let conn = Connection::open(&db_path).unwrap();
+
+        let text = "韩立祭出青元剑诀，剑光一闪。";
+        assert_eq!(words(text).last(), Some(&"一闪"));
+        let added = add_dictionary_words(&conn, None, &["青元剑诀".to_string(), " ".to_string()]).unwrap();
+        assert_eq!(added.len(), 1);
+        assert!(words(text).contains(&"青元剑诀"));
+        assert!(add_dictionary_words(&conn, None, &["青元剑诀".to_string()]).unwrap().is_empty());
+        assert_eq!(list_dictionary_words(&conn, Some("p1")).unwrap().len(), 1);
+
+        remove_dictionary_word(&conn, "青元剑诀").unwrap();
+        assert!(list_dictionary_words(&conn, None).unwrap().is_empty());
+        assert!(remove_dictionary_word(&conn, "青元剑诀").is_err());
+    }
+}
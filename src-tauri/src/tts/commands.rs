@@ -0,0 +1,235 @@
+use super::providers::{build_m4b, probe_duration_ms, TtsEngine};
+use super::types::*;
+use crate::ai::task_queue::{
+    is_task_cancelled, save_task, update_task_progress, update_task_state, CreateTaskRequest,
+    QueuedTask, TaskQueue, TaskState, TaskType,
+};
+use crate::logger::{log_command_start, log_command_success, Logger};
+use rusqlite::{params, OptionalExtension};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
+
+struct AudiobookSource {
+    chapter_id: String,
+    title: String,
+    content: String,
+    pov: Option<String>,
+}
+
+#[tauri::command]
+pub async fn get_tts_voices() -> Result<Vec<TtsVoice>, String> {
+    Ok(TtsEngine::default_voices())
+}
+
+/// 试听：合成一小段文本，返回 MP3 数据
+#[tauri::command]
+pub async fn preview_tts_voice(
+    provider: TtsProviderConfig,
+    voice: String,
+    text: String,
+) -> Result<Vec<u8>, String> {
+    let sample: String = text.chars().take(200).collect();
+    TtsEngine::synthesize(&provider, &voice, &sample).await
+}
+
+/// 启动有声书导出任务。任务写入 ai_task_queue 后在后台逐章合成，
+/// 进度通过 `audiobook://progress` 事件推送，可用 cancel_task 取消
+#[tauri::command]
+pub async fn start_audiobook_export(
+    app: AppHandle,
+    request: AudiobookRequest,
+) -> Result<QueuedTask, String> {
+    let logger = Logger::new().with_feature("audiobook");
+    log_command_start(&logger, "start_audiobook_export", &format!("project: {}", request.project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path).map_err(|e| e.to_string())?;
+
+    let project_name: String = conn
+        .query_row("SELECT name FROM projects WHERE id = ?1", params![request.project_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("项目不存在: {}", request.project_id))?;
+
+    let sources = load_sources(&conn, &request)?;
+    if sources.is_empty() {
+        return Err("没有可导出的章节".to_string());
+    }
+
+    let output_dir = match &request.output_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => app.path().app_data_dir().map_err(|e| e.to_string())?
+            .join("exports")
+            .join(format!("audiobook_{}_{}", sanitize_segment(&project_name), chrono::Utc::now().format("%Y%m%d_%H%M%S"))),
+    };
+    std::fs::create_dir_all(&output_dir).map_err(|e| e.to_string())?;
+
+    let provider_name = serde_json::to_value(&request.provider.provider)
+        .ok()
+        .and_then(|v| v.as_str().map(|s| s.to_string()));
+    // 任务记录只保存导出参数，API 密钥不写入数据库
+    let mut persisted = request.clone();
+    persisted.provider.api_key = None;
+    let task = TaskQueue::new().add_task(CreateTaskRequest {
+        project_id: request.project_id.clone(),
+        task_type: TaskType::AudioGeneration,
+        priority: None,
+        provider: provider_name,
+        input_data: serde_json::to_value(&persisted).map_err(|e| e.to_string())?,
+        max_retries: Some(0),
+    });
+    save_task(&conn, &task).map_err(|e| e.to_string())?;
+
+    let task_id = task.id.clone();
+    let job_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let logger = Logger::new().with_feature("audiobook");
+        let outcome = run_audiobook_job(&job_app, &db_path, &task_id, &request, &project_name, sources, &output_dir).await;
+        let Ok(conn) = crate::database::get_connection(&db_path) else {
+            return;
+        };
+        match outcome {
+            Ok(Some(result)) => {
+                let output = serde_json::to_value(&result).unwrap_or_default();
+                let _ = update_task_state(&conn, &task_id, TaskState::Completed, Some(&output), None);
+                logger.info(&format!("Audiobook task {} completed", task_id));
+            }
+            Ok(None) => logger.info(&format!("Audiobook task {} cancelled", task_id)),
+            Err(e) => {
+                let _ = update_task_state(&conn, &task_id, TaskState::Failed, None, Some(&e));
+                logger.error(&format!("Audiobook task {} failed: {}", task_id, e));
+            }
+        }
+    });
+
+    log_command_success(&logger, "start_audiobook_export", &task.id);
+    Ok(task)
+}
+
+fn load_sources(conn: &rusqlite::Connection, request: &AudiobookRequest) -> Result<Vec<AudiobookSource>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT c.id, c.title, c.content,
+                (SELECT m.pov FROM chapter_missions m WHERE m.chapter_id = c.id ORDER BY m.created_at DESC LIMIT 1)
+         FROM chapters c WHERE c.project_id = ?1 ORDER BY c.sort_order"
+    ).map_err(|e| e.to_string())?;
+
+    let sources = stmt.query_map(params![request.project_id], |row| {
+        Ok(AudiobookSource {
+            chapter_id: row.get(0)?,
+            title: row.get(1)?,
+            content: row.get(2)?,
+            pov: row.get(3)?,
+        })
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    Ok(match &request.chapter_ids {
+        Some(ids) if !ids.is_empty() => sources.into_iter().filter(|s| ids.contains(&s.chapter_id)).collect(),
+        _ => sources,
+    })
+}
+
+/// 逐章合成；返回 Ok(None) 表示任务被取消
+async fn run_audiobook_job(
+    app: &AppHandle,
+    db_path: &Path,
+    task_id: &str,
+    request: &AudiobookRequest,
+    project_name: &str,
+    sources: Vec<AudiobookSource>,
+    output_dir: &Path,
+) -> Result<Option<AudiobookResult>, String> {
+    let conn = crate::database::get_connection(db_path).map_err(|e| e.to_string())?;
+    update_task_state(&conn, task_id, TaskState::Running, None, None).map_err(|e| e.to_string())?;
+
+    let chapter_count = sources.len();
+    let mut chapters = Vec::new();
+
+    for (index, source) in sources.iter().enumerate() {
+        if is_task_cancelled(&conn, task_id) {
+            return Ok(None);
+        }
+
+        let voice = source.pov.as_ref()
+            .and_then(|pov| request.pov_voices.get(pov.trim()))
+            .unwrap_or(&request.default_voice)
+            .clone();
+
+        let text = format!("{}\n{}", source.title, source.content);
+        let audio = TtsEngine::synthesize(&request.provider, &voice, &text)
+            .await
+            .map_err(|e| format!("章节《{}》合成失败: {}", source.title, e))?;
+
+        let file_path = output_dir.join(format!("{:03}-{}.mp3", index + 1, sanitize_segment(&source.title)));
+        std::fs::write(&file_path, &audio).map_err(|e| e.to_string())?;
+
+        chapters.push(AudiobookChapter {
+            chapter_id: source.chapter_id.clone(),
+            title: source.title.clone(),
+            voice,
+            file_path: file_path.to_string_lossy().to_string(),
+            duration_ms: probe_duration_ms(&file_path).await,
+        });
+
+        // 合并 M4B 的步骤计入最后 10%
+        let scale = if request.format == AudiobookFormat::M4b { 90 } else { 100 };
+        let progress = ((index + 1) * scale / chapter_count) as u32;
+        let _ = update_task_progress(&conn, task_id, progress);
+        let _ = app.emit("audiobook://progress", AudiobookProgress {
+            task_id: task_id.to_string(),
+            chapter_index: index + 1,
+            chapter_count,
+            chapter_title: source.title.clone(),
+            progress,
+        });
+    }
+
+    let book_path = if request.format == AudiobookFormat::M4b {
+        if is_task_cancelled(&conn, task_id) {
+            return Ok(None);
+        }
+        let book_path = output_dir.join(format!("{}.m4b", sanitize_segment(project_name)));
+        build_m4b(project_name, "", &chapters, &book_path).await?;
+        Some(book_path.to_string_lossy().to_string())
+    } else {
+        None
+    };
+
+    Ok(Some(AudiobookResult {
+        output_dir: output_dir.to_string_lossy().to_string(),
+        chapters,
+        book_path,
+    }))
+}
+
+fn sanitize_segment(name: &str) -> String {
+    let cleaned: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            _ => c,
+        })
+        .collect();
+
+    if cleaned.is_empty() {
+        "untitled".to_string()
+    } else {
+        cleaned
+    }
+}
@@ -0,0 +1,371 @@
+use super::types::{AudiobookChapter, TtsProviderConfig, TtsProviderType, TtsVoice};
+use futures::{SinkExt, StreamExt};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message;
+
+const EDGE_TRUSTED_CLIENT_TOKEN: &str = "6A5AA1D4EAFF4E9FB37E23D68491D6F4";
+const EDGE_WSS_URL: &str = "wss://speech.platform.bing.com/consumer/speech/synthesize/readaloud/edge/v1";
+const EDGE_GEC_VERSION: &str = "1-130.0.2849.68";
+const MP3_OUTPUT_FORMAT: &str = "audio-24khz-48kbitrate-mono-mp3";
+
+/// 单次合成的最大字符数，超过后按段落切分
+const MAX_CHUNK_CHARS: usize = 1500;
+
+pub struct TtsEngine;
+
+impl TtsEngine {
+    /// 合成一段文本，返回 MP3 数据。长文本会分段合成后拼接（MP3 帧可直接串联）
+    pub async fn synthesize(config: &TtsProviderConfig, voice: &str, text: &str) -> Result<Vec<u8>, String> {
+        let mut audio = Vec::new();
+        for chunk in split_text(text, MAX_CHUNK_CHARS) {
+            let data = match config.provider {
+                TtsProviderType::EdgeTts => synthesize_edge(config, voice, &chunk).await?,
+                TtsProviderType::Azure => synthesize_azure(config, voice, &chunk).await?,
+                TtsProviderType::Piper => synthesize_piper(config, voice, &chunk).await?,
+            };
+            audio.extend_from_slice(&data);
+        }
+        Ok(audio)
+    }
+
+    pub fn default_voices() -> Vec<TtsVoice> {
+        [
+            ("zh-CN-XiaoxiaoNeural", "晓晓", "zh-CN", "Female"),
+            ("zh-CN-XiaoyiNeural", "晓伊", "zh-CN", "Female"),
+            ("zh-CN-YunxiNeural", "云希", "zh-CN", "Male"),
+            ("zh-CN-YunjianNeural", "云健", "zh-CN", "Male"),
+            ("zh-CN-YunyangNeural", "云扬", "zh-CN", "Male"),
+            ("zh-CN-liaoning-XiaobeiNeural", "晓北（东北话）", "zh-CN-liaoning", "Female"),
+            ("zh-TW-HsiaoChenNeural", "曉臻", "zh-TW", "Female"),
+            ("zh-HK-HiuMaanNeural", "曉曼", "zh-HK", "Female"),
+        ]
+        .iter()
+        .map(|(id, name, locale, gender)| TtsVoice {
+            id: id.to_string(),
+            name: name.to_string(),
+            locale: locale.to_string(),
+            gender: gender.to_string(),
+        })
+        .collect()
+    }
+}
+
+/// 按段落把文本切成不超过 max_chars 的片段，单个超长段落再按句号切分
+pub fn split_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    let pieces = text
+        .split('\n')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .flat_map(|paragraph| {
+            if paragraph.chars().count() <= max_chars {
+                vec![paragraph.to_string()]
+            } else {
+                paragraph
+                    .split_inclusive(['。', '！', '？', '!', '?', '.'])
+                    .map(|s| s.to_string())
+                    .collect()
+            }
+        });
+
+    for piece in pieces {
+        if !current.is_empty() && current.chars().count() + piece.chars().count() + 1 > max_chars {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(&piece);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+fn build_ssml(voice: &str, rate: &str, text: &str) -> String {
+    let locale = voice.splitn(3, '-').take(2).collect::<Vec<_>>().join("-");
+    format!(
+        "<speak version='1.0' xmlns='http://www.w3.org/2001/10/synthesis' xml:lang='{}'><voice name='{}'><prosody pitch='+0Hz' rate='{}' volume='+0%'>{}</prosody></voice></speak>",
+        locale,
+        voice,
+        rate,
+        escape_xml(text)
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Edge 朗读服务要求的 Sec-MS-GEC 令牌：按 5 分钟取整的 Windows 时间戳与客户端令牌拼接后做 SHA-256
+fn edge_sec_ms_gec() -> String {
+    let seconds = chrono::Utc::now().timestamp() as u64 + 11_644_473_600;
+    let ticks = (seconds - seconds % 300) * 10_000_000;
+    let digest = Sha256::digest(format!("{}{}", ticks, EDGE_TRUSTED_CLIENT_TOKEN).as_bytes());
+    digest.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+async fn synthesize_edge(config: &TtsProviderConfig, voice: &str, text: &str) -> Result<Vec<u8>, String> {
+    let connection_id = uuid::Uuid::new_v4().simple().to_string();
+    let url = format!(
+        "{}?TrustedClientToken={}&Sec-MS-GEC={}&Sec-MS-GEC-Version={}&ConnectionId={}",
+        EDGE_WSS_URL,
+        EDGE_TRUSTED_CLIENT_TOKEN,
+        edge_sec_ms_gec(),
+        EDGE_GEC_VERSION,
+        connection_id
+    );
+
+    let mut request = url.into_client_request().map_err(|e| e.to_string())?;
+    let headers = request.headers_mut();
+    headers.insert("Origin", "chrome-extension://jdiccldimpdaibmpdkjnbmckianbfold".parse().unwrap());
+    headers.insert("Pragma", "no-cache".parse().unwrap());
+    headers.insert("Cache-Control", "no-cache".parse().unwrap());
+    headers.insert(
+        "User-Agent",
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/130.0.0.0 Safari/537.36 Edg/130.0.0.0".parse().unwrap(),
+    );
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(|e| format!("无法连接 Edge TTS 服务: {}", e))?;
+
+    let timestamp = chrono::Utc::now().format("%a %b %d %Y %H:%M:%S GMT+0000 (Coordinated Universal Time)").to_string();
+    let speech_config = format!(
+        "X-Timestamp:{}\r\nContent-Type:application/json; charset=utf-8\r\nPath:speech.config\r\n\r\n{{\"context\":{{\"synthesis\":{{\"audio\":{{\"metadataoptions\":{{\"sentenceBoundaryEnabled\":\"false\",\"wordBoundaryEnabled\":\"false\"}},\"outputFormat\":\"{}\"}}}}}}}}\r\n",
+        timestamp, MP3_OUTPUT_FORMAT
+    );
+    socket.send(Message::Text(speech_config)).await.map_err(|e| e.to_string())?;
+
+    let rate = config.rate.as_deref().unwrap_or("+0%");
+    let ssml_message = format!(
+        "X-RequestId:{}\r\nContent-Type:application/ssml+xml\r\nX-Timestamp:{}Z\r\nPath:ssml\r\n\r\n{}",
+        uuid::Uuid::new_v4().simple(),
+        timestamp,
+        build_ssml(voice, rate, text)
+    );
+    socket.send(Message::Text(ssml_message)).await.map_err(|e| e.to_string())?;
+
+    let mut audio = Vec::new();
+    while let Some(message) = socket.next().await {
+        match message.map_err(|e| format!("Edge TTS 连接中断: {}", e))? {
+            Message::Binary(data) => {
+                // 二进制帧：前两字节为头部长度（大端），头部之后是音频数据
+                if data.len() < 2 {
+                    continue;
+                }
+                let header_len = u16::from_be_bytes([data[0], data[1]]) as usize;
+                if data.len() < 2 + header_len {
+                    continue;
+                }
+                let header = String::from_utf8_lossy(&data[2..2 + header_len]);
+                if header.contains("Path:audio") {
+                    audio.extend_from_slice(&data[2 + header_len..]);
+                }
+            }
+            Message::Text(text) if text.contains("Path:turn.end") => break,
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    let _ = socket.close(None).await;
+
+    if audio.is_empty() {
+        return Err("Edge TTS 未返回音频数据".to_string());
+    }
+    Ok(audio)
+}
+
+async fn synthesize_azure(config: &TtsProviderConfig, voice: &str, text: &str) -> Result<Vec<u8>, String> {
+    let api_key = config.api_key.as_deref().ok_or("Azure 语音服务需要配置 API 密钥")?;
+    let region = config.region.as_deref().unwrap_or("eastasia");
+    let url = format!("https://{}.tts.speech.microsoft.com/cognitiveservices/v1", region);
+
+    let response = reqwest::Client::new()
+        .post(&url)
+        .header("Ocp-Apim-Subscription-Key", api_key)
+        .header("Content-Type", "application/ssml+xml")
+        .header("X-Microsoft-OutputFormat", MP3_OUTPUT_FORMAT)
+        .header("User-Agent", "ai-novel-studio")
+        .body(build_ssml(voice, config.rate.as_deref().unwrap_or("+0%"), text))
+        .send()
+        .await
+        .map_err(|e| format!("Azure TTS 请求失败: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Azure TTS 返回错误 {}: {}", status, body));
+    }
+
+    response.bytes().await.map(|b| b.to_vec()).map_err(|e| e.to_string())
+}
+
+/// 调用本地 piper 生成 WAV，再用 ffmpeg 转为 MP3
+async fn synthesize_piper(config: &TtsProviderConfig, voice: &str, text: &str) -> Result<Vec<u8>, String> {
+    let binary = config.piper_binary.as_deref().unwrap_or("piper");
+    let model_path = match &config.piper_model_dir {
+        Some(dir) => Path::new(dir).join(format!("{}.onnx", voice)),
+        None => Path::new(voice).to_path_buf(),
+    };
+
+    let temp_dir = std::env::temp_dir();
+    let stem = uuid::Uuid::new_v4().simple().to_string();
+    let wav_path = temp_dir.join(format!("{}.wav", stem));
+    let mp3_path = temp_dir.join(format!("{}.mp3", stem));
+
+    let mut child = tokio::process::Command::new(binary)
+        .arg("--model")
+        .arg(&model_path)
+        .arg("--output_file")
+        .arg(&wav_path)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("无法启动 piper（{}）: {}", binary, e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes()).await.map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().await.map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!("piper 合成失败: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let result = run_ffmpeg(&[
+        "-y".as_ref(),
+        "-i".as_ref(),
+        wav_path.as_os_str(),
+        "-codec:a".as_ref(),
+        "libmp3lame".as_ref(),
+        "-b:a".as_ref(),
+        "64k".as_ref(),
+        mp3_path.as_os_str(),
+    ])
+    .await
+    .and_then(|_| std::fs::read(&mp3_path).map_err(|e| e.to_string()));
+
+    let _ = std::fs::remove_file(&wav_path);
+    let _ = std::fs::remove_file(&mp3_path);
+    result
+}
+
+async fn run_ffmpeg(args: &[&std::ffi::OsStr]) -> Result<(), String> {
+    let output = tokio::process::Command::new("ffmpeg")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| format!("无法启动 ffmpeg，请确认已安装并加入 PATH: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("ffmpeg 执行失败: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+    Ok(())
+}
+
+/// 用 ffprobe 读取音频时长（毫秒）
+pub async fn probe_duration_ms(path: &Path) -> Option<u64> {
+    let output = tokio::process::Command::new("ffprobe")
+        .args(["-v", "error", "-show_entries", "format=duration", "-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(path)
+        .output()
+        .await
+        .ok()?;
+    let seconds: f64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Some((seconds * 1000.0) as u64)
+}
+
+/// 将各章 MP3 合并为 M4B，并通过 ffmetadata 写入章节标记；缺少章节时长时无法定位章节，返回错误
+pub async fn build_m4b(
+    title: &str,
+    author: &str,
+    chapters: &[AudiobookChapter],
+    output_path: &Path,
+) -> Result<(), String> {
+    let work_dir = output_path.parent().unwrap_or(Path::new("."));
+    let list_path = work_dir.join(".audiobook_concat.txt");
+    let metadata_path = work_dir.join(".audiobook_metadata.txt");
+
+    let mut list = String::new();
+    let mut metadata = format!(";FFMETADATA1\ntitle={}\nartist={}\nalbum={}\ngenre=Audiobook\n", escape_ffmetadata(title), escape_ffmetadata(author), escape_ffmetadata(title));
+    let mut start_ms = 0u64;
+    for chapter in chapters {
+        list.push_str(&format!("file '{}'\n", chapter.file_path.replace('\'', "'\\''")));
+        let duration = chapter
+            .duration_ms
+            .filter(|ms| *ms > 0)
+            .ok_or_else(|| format!("无法读取章节《{}》的音频时长，请确认已安装 ffprobe", chapter.title))?;
+        metadata.push_str(&format!(
+            "\n[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}\n",
+            start_ms,
+            start_ms + duration,
+            escape_ffmetadata(&chapter.title)
+        ));
+        start_ms += duration;
+    }
+    std::fs::write(&list_path, list).map_err(|e| e.to_string())?;
+    std::fs::write(&metadata_path, metadata).map_err(|e| e.to_string())?;
+
+    let result = run_ffmpeg(&[
+        "-y".as_ref(),
+        "-f".as_ref(),
+        "concat".as_ref(),
+        "-safe".as_ref(),
+        "0".as_ref(),
+        "-i".as_ref(),
+        list_path.as_os_str(),
+        "-i".as_ref(),
+        metadata_path.as_os_str(),
+        "-map_metadata".as_ref(),
+        "1".as_ref(),
+        "-map_chapters".as_ref(),
+        "1".as_ref(),
+        "-vn".as_ref(),
+        "-c:a".as_ref(),
+        "aac".as_ref(),
+        "-b:a".as_ref(),
+        "64k".as_ref(),
+        "-f".as_ref(),
+        "mp4".as_ref(),
+        output_path.as_os_str(),
+    ])
+    .await;
+
+    let _ = std::fs::remove_file(&list_path);
+    let _ = std::fs::remove_file(&metadata_path);
+    result
+}
+
+fn escape_ffmetadata(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_text_respects_limit() {
+        let text = "第一段。\n\n第二段很长。第二段很长。第二段很长。\n第三段。";
+        let chunks = split_text(text, 12);
+        assert!(chunks.iter().all(|c| c.chars().count() <= 12));
+        assert_eq!(chunks.concat().replace('\n', ""), text.replace('\n', ""));
+    }
+}
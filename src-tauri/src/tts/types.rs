@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TtsProviderType {
+    EdgeTts,
+    Azure,
+    Piper,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TtsProviderConfig {
+    pub provider: TtsProviderType,
+    /// Azure 语音服务密钥
+    pub api_key: Option<String>,
+    /// Azure 区域，例如 eastasia
+    pub region: Option<String>,
+    /// piper 可执行文件路径，默认从 PATH 查找
+    pub piper_binary: Option<String>,
+    /// piper 语音模型（.onnx）所在目录
+    pub piper_model_dir: Option<String>,
+    /// 语速调整，例如 "+10%"
+    pub rate: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AudiobookFormat {
+    /// 每章一个 MP3 文件
+    Mp3,
+    /// 合并为带章节标记的 M4B 有声书（需要 ffmpeg）
+    M4b,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudiobookRequest {
+    pub project_id: String,
+    pub provider: TtsProviderConfig,
+    pub default_voice: String,
+    /// 视角角色名 → 语音，章节视角取自章节任务（chapter_missions.pov）
+    #[serde(default)]
+    pub pov_voices: HashMap<String, String>,
+    pub format: AudiobookFormat,
+    /// 仅导出指定章节，为空时导出全部
+    pub chapter_ids: Option<Vec<String>>,
+    pub output_dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudiobookChapter {
+    pub chapter_id: String,
+    pub title: String,
+    pub voice: String,
+    pub file_path: String,
+    pub duration_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudiobookResult {
+    pub output_dir: String,
+    pub chapters: Vec<AudiobookChapter>,
+    /// M4B 格式时的合并文件
+    pub book_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudiobookProgress {
+    pub task_id: String,
+    pub chapter_index: usize,
+    pub chapter_count: usize,
+    pub chapter_title: String,
+    pub progress: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TtsVoice {
+    pub id: String,
+    pub name: String,
+    pub locale: String,
+    pub gender: String,
+}
@@ -1,3 +1,4 @@
+use crate::text_diff::{self, ParagraphDiff};
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 
@@ -22,16 +23,41 @@ pub struct ChapterSnapshot {
     pub content: String,
     pub order: i32,
     pub word_count: i32,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub summary: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CharacterSnapshot {
     pub id: String,
     pub name: String,
+    /// 角色定位
     pub description: String,
     pub personality: String,
     pub appearance: String,
     pub background: String,
+    /// 完整的角色设定，旧快照中没有
+    #[serde(default)]
+    pub details: Option<CharacterDetails>,
+}
+
+/// 快照中角色的其余设定字段，恢复快照时按原样写回
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CharacterDetails {
+    pub race: Option<String>,
+    pub age: Option<i32>,
+    pub gender: Option<String>,
+    pub birth_date: Option<String>,
+    pub skills: Option<String>,
+    pub status: Option<String>,
+    pub bazi: Option<String>,
+    pub ziwei: Option<String>,
+    pub mbti: Option<String>,
+    pub enneagram: Option<String>,
+    pub items: Option<String>,
+    pub avatar_url: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +66,10 @@ pub struct WorldViewSnapshot {
     pub name: String,
     pub category: String,
     pub description: String,
+    #[serde(default)]
+    pub tags: Option<String>,
+    #[serde(default)]
+    pub status: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +79,15 @@ pub struct PlotPointSnapshot {
     pub content: String,
     pub chapter_id: Option<String>,
     pub order: i32,
+    /// 大纲中的上级节点
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub level: i32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -71,11 +110,16 @@ pub struct VersionDiff {
     pub plot_point_changes: Vec<PlotPointDiff>,
 }
 
+/// 章节的变化，`paragraphs` 为逐段、逐词的对比结果，供修订视图显示
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChapterDiff {
     pub id: String,
+    pub title: String,
     pub action: DiffAction,
-    pub changes: Vec<TextChange>,
+    pub title_change: Option<FieldChange>,
+    pub paragraphs: Vec<ParagraphDiff>,
+    pub inserted_chars: usize,
+    pub deleted_chars: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -112,13 +156,6 @@ pub enum DiffAction {
     Deleted,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TextChange {
-    pub position: i32,
-    pub removed: String,
-    pub added: String,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FieldChange {
     pub field: String,
@@ -130,21 +167,34 @@ pub struct FieldChange {
 pub struct VersionControlConfig {
     pub auto_save_enabled: bool,
     pub auto_save_interval_minutes: i32,
+    /// 距上次自动快照的字数变化达到这个值时，不等间隔到期也会生成快照
+    #[serde(default = "default_auto_save_word_delta")]
+    pub auto_save_word_delta: i32,
+    /// 手动快照的数量上限，自动快照按保留策略清理
     pub max_snapshots_per_project: i32,
+    /// 快照正文按内容分块去重并压缩存储
     pub compression_enabled: bool,
 }
 
+fn default_auto_save_word_delta() -> i32 {
+    500
+}
+
 impl Default for VersionControlConfig {
     fn default() -> Self {
         VersionControlConfig {
             auto_save_enabled: true,
             auto_save_interval_minutes: 30,
+            auto_save_word_delta: default_auto_save_word_delta(),
             max_snapshots_per_project: 50,
             compression_enabled: true,
         }
     }
 }
 
+const HOUR_SECS: i64 = 3600;
+const DAY_SECS: i64 = 24 * HOUR_SECS;
+
 pub struct VersionControlManager;
 
 impl VersionControlManager {
@@ -172,7 +222,8 @@ impl VersionControlManager {
             tags: Self::generate_tags(&chapters, &characters),
         };
 
-        let id = format!("{}_{}", project_id, timestamp);
+        // 同一秒内可能连续生成快照（如恢复前的还原点），加随机后缀避免冲突
+        let id = format!("{}_{}_{}", project_id, timestamp, &uuid::Uuid::new_v4().simple().to_string()[..8]);
 
         ProjectSnapshot {
             id,
@@ -211,37 +262,50 @@ impl VersionControlManager {
         let from_map: HashMap<&str, &ChapterSnapshot> = from.iter().map(|c| (c.id.as_str(), c)).collect();
         let to_map: HashMap<&str, &ChapterSnapshot> = to.iter().map(|c| (c.id.as_str(), c)).collect();
 
-        for (id, chapter) in &to_map {
-            if let Some(from_chapter) = from_map.get(id) {
+        for chapter in to {
+            if let Some(from_chapter) = from_map.get(chapter.id.as_str()) {
                 if chapter.content != from_chapter.content || chapter.title != from_chapter.title {
-                    changes.push(ChapterDiff {
-                        id: id.to_string(),
-                        action: DiffAction::Modified,
-                        changes: Self::compute_text_diff(&from_chapter.content, &chapter.content),
+                    let title_change = (chapter.title != from_chapter.title).then(|| FieldChange {
+                        field: "title".to_string(),
+                        old_value: Some(from_chapter.title.clone()),
+                        new_value: Some(chapter.title.clone()),
                     });
+                    changes.push(Self::chapter_diff(chapter, DiffAction::Modified, title_change, &from_chapter.content, &chapter.content));
                 }
             } else {
-                changes.push(ChapterDiff {
-                    id: id.to_string(),
-                    action: DiffAction::Created,
-                    changes: vec![],
-                });
+                changes.push(Self::chapter_diff(chapter, DiffAction::Created, None, "", &chapter.content));
             }
         }
 
-        for id in from_map.keys() {
-            if !to_map.contains_key(id) {
-                changes.push(ChapterDiff {
-                    id: id.to_string(),
-                    action: DiffAction::Deleted,
-                    changes: vec![],
-                });
+        for chapter in from {
+            if !to_map.contains_key(chapter.id.as_str()) {
+                changes.push(Self::chapter_diff(chapter, DiffAction::Deleted, None, &chapter.content, ""));
             }
         }
 
         changes
     }
 
+    fn chapter_diff(
+        chapter: &ChapterSnapshot,
+        action: DiffAction,
+        title_change: Option<FieldChange>,
+        from_content: &str,
+        to_content: &str,
+    ) -> ChapterDiff {
+        let paragraphs = text_diff::diff_paragraphs(from_content, to_content);
+        let (inserted_chars, deleted_chars) = text_diff::change_counts(&paragraphs);
+        ChapterDiff {
+            id: chapter.id.clone(),
+            title: chapter.title.clone(),
+            action,
+            title_change,
+            paragraphs,
+            inserted_chars,
+            deleted_chars,
+        }
+    }
+
     fn compare_characters(from: &[CharacterSnapshot], to: &[CharacterSnapshot]) -> Vec<CharacterDiff> {
         let mut changes = Vec::new();
 
@@ -463,27 +527,42 @@ impl VersionControlManager {
         changes
     }
 
-    fn compute_text_diff(from: &str, to: &str) -> Vec<TextChange> {
-        let mut changes = Vec::new();
-        let from_lines: Vec<&str> = from.lines().collect();
-        let to_lines: Vec<&str> = to.lines().collect();
-
-        let max_lines = from_lines.len().max(to_lines.len());
-
-        for i in 0..max_lines {
-            let from_line = from_lines.get(i).unwrap_or(&"");
-            let to_line = to_lines.get(i).unwrap_or(&"");
-
-            if from_line != to_line {
-                changes.push(TextChange {
-                    position: i as i32,
-                    removed: if from_line.is_empty() { String::new() } else { from_line.to_string() },
-                    added: if to_line.is_empty() { String::new() } else { to_line.to_string() },
-                });
+    /// 保存章节后是否需要自动快照。`last` 为上次自动快照的时间（秒）和总字数
+    pub fn should_auto_snapshot(config: &VersionControlConfig, last: Option<(i64, i32)>, now: i64, total_words: i32) -> bool {
+        if !config.auto_save_enabled {
+            return false;
+        }
+        match last {
+            None => true,
+            Some((timestamp, words)) => {
+                now - timestamp >= config.auto_save_interval_minutes as i64 * 60
+                    || (total_words - words).abs() >= config.auto_save_word_delta
             }
         }
+    }
 
-        changes
+    /// 自动快照的保留策略：24 小时内全部保留，一周内每小时保留最新一个，更早的每天保留最新一个。
+    /// `snapshots` 为 (id, 时间戳秒)，返回应删除的 id
+    pub fn snapshots_to_prune(snapshots: &[(String, i64)], now: i64) -> Vec<String> {
+        let mut sorted: Vec<&(String, i64)> = snapshots.iter().collect();
+        sorted.sort_by_key(|(_, timestamp)| std::cmp::Reverse(*timestamp));
+
+        let mut kept_buckets = std::collections::HashSet::new();
+        let mut pruned = Vec::new();
+        for (id, timestamp) in sorted {
+            let age = now - timestamp;
+            let bucket = if age < DAY_SECS {
+                continue;
+            } else if age < 7 * DAY_SECS {
+                (HOUR_SECS, timestamp.div_euclid(HOUR_SECS))
+            } else {
+                (DAY_SECS, timestamp.div_euclid(DAY_SECS))
+            };
+            if !kept_buckets.insert(bucket) {
+                pruned.push(id.clone());
+            }
+        }
+        pruned
     }
 
     fn generate_tags(chapters: &[ChapterSnapshot], characters: &[CharacterSnapshot]) -> Vec<String> {
@@ -511,3 +590,41 @@ impl VersionControlManager {
         tags
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_auto_snapshot() {
+        let config = VersionControlConfig::default();
+        let now = 1_700_000_000;
+        assert!(VersionControlManager::should_auto_snapshot(&config, None, now, 100));
+        assert!(!VersionControlManager::should_auto_snapshot(&config, Some((now - 60, 1000)), now, 1200));
+        assert!(VersionControlManager::should_auto_snapshot(&config, Some((now - 60, 1000)), now, 1500));
+        assert!(VersionControlManager::should_auto_snapshot(&config, Some((now - 30 * 60, 1000)), now, 1000));
+        let disabled = VersionControlConfig { auto_save_enabled: false, ..config };
+        assert!(!VersionControlManager::should_auto_snapshot(&disabled, None, now, 100));
+    }
+
+    #[test]
+    fn test_retention_policy() {
+        let now = 100 * DAY_SECS;
+        let snapshot = |id: &str, age: i64| (id.to_string(), now - age);
+        let snapshots = vec![
+            // 24 小时内全部保留
+            snapshot("recent-1", 10 * 60),
+            snapshot("recent-2", 20 * 60),
+            // 同一小时内只保留最新的
+            snapshot("hour-new", 2 * DAY_SECS + 10 * 60),
+            snapshot("hour-old", 2 * DAY_SECS + 20 * 60),
+            snapshot("other-hour", 2 * DAY_SECS + 2 * HOUR_SECS),
+            // 一周以前同一天只保留最新的
+            snapshot("day-new", 10 * DAY_SECS + HOUR_SECS),
+            snapshot("day-old", 10 * DAY_SECS + 5 * HOUR_SECS),
+        ];
+        let mut pruned = VersionControlManager::snapshots_to_prune(&snapshots, now);
+        pruned.sort();
+        assert_eq!(pruned, vec!["day-old", "hour-old"]);
+    }
+}
@@ -1,9 +1,16 @@
 use crate::version_control::{VersionControlManager, ProjectSnapshot, VersionDiff, VersionControlConfig};
-use crate::models::{Chapter, Character, WorldView, PlotPoint};
+use crate::chapter_branches;
+use crate::history_bundle;
+use crate::snapshot_store;
+use crate::snapshot_tags;
+use crate::text_merge::HunkResolution;
+use crate::text_diff;
+use crate::version_storage;
+use crate::worldview_history;
 use crate::logger::Logger;
 use std::path::PathBuf;
 use tauri::{AppHandle, Manager};
-use rusqlite::params;
+use rusqlite::{params, OptionalExtension};
 
 #[tauri::command]
 pub async fn create_snapshot(
@@ -20,54 +27,18 @@ pub async fn create_snapshot(
     let conn = crate::database::get_connection(&db_path)
         .map_err(|e| format!("Failed to get database connection: {}", e))?;
 
-    let chapters = load_chapters(&conn, &project_id)?;
-    let characters = load_characters(&conn, &project_id)?;
-    let world_views = load_world_views(&conn, &project_id)?;
-    let plot_points = load_plot_points(&conn, &project_id)?;
-
-    let snapshot = VersionControlManager::create_snapshot(
-        &project_id,
-        &version,
-        &description,
-        chapters,
-        characters,
-        world_views,
-        plot_points,
-        auto_generated,
-    );
-
-    let created_at = chrono::Utc::now().to_rfc3339();
-
-    conn.execute(
-        "INSERT INTO project_snapshots (id, project_id, version, timestamp, description, chapters_json, characters_json, world_views_json, plot_points_json, metadata_json, auto_generated, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
-        params![
-            snapshot.id,
-            snapshot.project_id,
-            snapshot.version,
-            snapshot.timestamp,
-            snapshot.description,
-            serde_json::to_string(&snapshot.chapters).unwrap_or_default(),
-            serde_json::to_string(&snapshot.characters).unwrap_or_default(),
-            serde_json::to_string(&snapshot.world_views).unwrap_or_default(),
-            serde_json::to_string(&snapshot.plot_points).unwrap_or_default(),
-            serde_json::to_string(&snapshot.metadata).unwrap_or_default(),
-            if snapshot.metadata.auto_generated { 1 } else { 0 },
-            created_at,
-        ],
-    ).map_err(|e| format!("Failed to save snapshot: {}", e))?;
-
-    let max_snapshots = get_max_snapshots(&conn);
-    cleanup_old_snapshots(&conn, &project_id, max_snapshots)
-        .map_err(|e| format!("Failed to cleanup old snapshots: {}", e))?;
+    let snapshot = save_project_snapshot(&conn, &project_id, &version, &description, auto_generated)?;
 
     logger.info("Snapshot created successfully");
     serde_json::to_string(&snapshot).map_err(|e| e.to_string())
 }
 
+/// 项目的快照列表，附带各快照的标签；指定 `tag` 时只返回带该标签的快照
 #[tauri::command]
 pub async fn get_snapshots(
     app: AppHandle,
     project_id: String,
+    tag: Option<String>,
 ) -> Result<String, String> {
     let logger = Logger::new().with_feature("version_control");
     logger.info(&format!("Getting snapshots for project {}", project_id));
@@ -83,7 +54,8 @@ pub async fn get_snapshots(
          ORDER BY timestamp DESC"
     ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-    let result: Vec<serde_json::Value> = stmt.query_map(params![project_id], |row| {
+    let mut tags = snapshot_tags::tags_by_snapshot(&conn, &project_id)?;
+    let rows: Vec<serde_json::Value> = stmt.query_map(params![project_id], |row| {
         Ok(serde_json::json!({
             "id": row.get::<_, String>(0)?,
             "project_id": row.get::<_, String>(1)?,
@@ -97,6 +69,17 @@ pub async fn get_snapshots(
     .collect::<Result<Vec<_>, _>>()
     .map_err(|e| format!("Failed to collect snapshots: {}", e))?;
 
+    let result: Vec<serde_json::Value> = rows.into_iter().filter_map(|mut snapshot| {
+        let snapshot_tags = snapshot["id"].as_str().and_then(|id| tags.remove(id)).unwrap_or_default();
+        if let Some(tag) = &tag {
+            if !snapshot_tags.iter().any(|t| &t.tag == tag) {
+                return None;
+            }
+        }
+        snapshot["tags"] = serde_json::to_value(snapshot_tags).ok()?;
+        Some(snapshot)
+    }).collect();
+
     serde_json::to_string(&result).map_err(|e| e.to_string())
 }
 
@@ -113,30 +96,38 @@ pub async fn get_snapshot(
         .map_err(|e| format!("Failed to get database connection: {}", e))?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, project_id, version, timestamp, description, chapters_json, characters_json, world_views_json, plot_points_json, metadata_json, auto_generated 
+        "SELECT id, project_id, version, timestamp, description, chapters_json, characters_json, world_views_json, plot_points_json, metadata_json, auto_generated, storage 
          FROM project_snapshots 
          WHERE id = ?1"
     ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-    let snapshot = stmt.query_row(params![snapshot_id], |row| {
-        Ok(serde_json::json!({
-            "id": row.get::<_, String>(0)?,
-            "project_id": row.get::<_, String>(1)?,
-            "version": row.get::<_, String>(2)?,
-            "timestamp": row.get::<_, i64>(3)?,
-            "description": row.get::<_, String>(4)?,
-            "chapters": row.get::<_, String>(5)?,
-            "characters": row.get::<_, String>(6)?,
-            "world_views": row.get::<_, String>(7)?,
-            "plot_points": row.get::<_, String>(8)?,
-            "metadata": row.get::<_, String>(9)?,
-            "auto_generated": row.get::<_, i32>(10)? != 0,
-        }))
+    let (mut snapshot, storage, bodies) = stmt.query_row(params![snapshot_id], |row| {
+        Ok((
+            serde_json::json!({
+                "id": row.get::<_, String>(0)?,
+                "project_id": row.get::<_, String>(1)?,
+                "version": row.get::<_, String>(2)?,
+                "timestamp": row.get::<_, i64>(3)?,
+                "description": row.get::<_, String>(4)?,
+                "metadata": row.get::<_, String>(9)?,
+                "auto_generated": row.get::<_, i32>(10)? != 0,
+            }),
+            row.get::<_, String>(11)?,
+            [row.get::<_, String>(5)?, row.get(6)?, row.get(7)?, row.get(8)?],
+        ))
     }).map_err(|e| format!("Failed to query snapshot: {}", e))?;
 
+    let [chapters, characters, world_views, plot_points] = snapshot_store::inline_bodies(&conn, &storage, bodies)
+        .map_err(|e| format!("Failed to load snapshot content: {}", e))?;
+    snapshot["chapters"] = chapters.into();
+    snapshot["characters"] = characters.into();
+    snapshot["world_views"] = world_views.into();
+    snapshot["plot_points"] = plot_points.into();
+
     serde_json::to_string(&snapshot).map_err(|e| e.to_string())
 }
 
+/// 用快照整体替换项目的章节、大纲、角色和世界观。恢复前先为当前状态建立还原点，可以撤销这次恢复
 #[tauri::command]
 pub async fn restore_snapshot(
     app: AppHandle,
@@ -149,130 +140,32 @@ pub async fn restore_snapshot(
     let conn = crate::database::get_connection(&db_path)
         .map_err(|e| format!("Failed to get database connection: {}", e))?;
 
-    let snapshot_json = get_snapshot(app.clone(), snapshot_id).await?;
-    let snapshot: serde_json::Value = serde_json::from_str(&snapshot_json)
-        .map_err(|e| format!("Failed to parse snapshot: {}", e))?;
-
-    let project_id = snapshot["project_id"].as_str()
-        .ok_or("Missing project_id")?;
-
-    conn.execute("DELETE FROM chapters WHERE project_id = ?1", params![project_id])
-        .map_err(|e| format!("Failed to delete chapters: {}", e))?;
-
-    conn.execute("DELETE FROM characters WHERE project_id = ?1", params![project_id])
-        .map_err(|e| format!("Failed to delete characters: {}", e))?;
-
-    conn.execute("DELETE FROM world_views WHERE project_id = ?1", params![project_id])
-        .map_err(|e| format!("Failed to delete world_views: {}", e))?;
-
-    conn.execute("DELETE FROM plot_points WHERE project_id = ?1", params![project_id])
-        .map_err(|e| format!("Failed to delete plot_points: {}", e))?;
-
-    if let Some(chapters) = snapshot["chapters"].as_str() {
-        let chapters_data: Vec<Chapter> = serde_json::from_str(chapters)
-            .map_err(|e| format!("Failed to parse chapters: {}", e))?;
-
-        for chapter in chapters_data {
-            conn.execute(
-                "INSERT INTO chapters (id, project_id, title, content, word_count, sort_order, status, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                params![
-                    chapter.id,
-                    chapter.project_id,
-                    chapter.title,
-                    chapter.content,
-                    chapter.word_count,
-                    chapter.sort_order,
-                    chapter.status,
-                    chapter.created_at,
-                    chapter.updated_at,
-                ],
-            ).map_err(|e| format!("Failed to insert chapter: {}", e))?;
-        }
-    }
+    let restore_point = restore_project_snapshot(&conn, &snapshot_id)?;
 
-    if let Some(characters) = snapshot["characters"].as_str() {
-        let characters_data: Vec<Character> = serde_json::from_str(characters)
-            .map_err(|e| format!("Failed to parse characters: {}", e))?;
-
-        for character in characters_data {
-            conn.execute(
-                "INSERT INTO characters (id, project_id, name, role_type, race, age, gender, birth_date, appearance, personality, background, skills, status, bazi, ziwei, mbti, enneagram, items, avatar_url, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)",
-                params![
-                    character.id,
-                    character.project_id,
-                    character.name,
-                    character.role_type,
-                    character.race,
-                    character.age,
-                    character.gender,
-                    character.birth_date,
-                    character.appearance,
-                    character.personality,
-                    character.background,
-                    character.skills,
-                    character.status,
-                    character.bazi,
-                    character.ziwei,
-                    character.mbti,
-                    character.enneagram,
-                    character.items,
-                    character.avatar_url,
-                    character.created_at,
-                    character.updated_at,
-                ],
-            ).map_err(|e| format!("Failed to insert character: {}", e))?;
-        }
-    }
+    logger.info("Snapshot restored successfully");
+    serde_json::to_string(&serde_json::json!({
+        "status": "success",
+        "restore_point_id": restore_point.id,
+    })).map_err(|e| e.to_string())
+}
 
-    if let Some(world_views) = snapshot["world_views"].as_str() {
-        let world_views_data: Vec<WorldView> = serde_json::from_str(world_views)
-            .map_err(|e| format!("Failed to parse world_views: {}", e))?;
-
-        for world_view in world_views_data {
-            conn.execute(
-                "INSERT INTO world_views (id, project_id, category, title, content, tags, status, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                params![
-                    world_view.id,
-                    world_view.project_id,
-                    world_view.category,
-                    world_view.title,
-                    world_view.content,
-                    world_view.tags,
-                    world_view.status,
-                    world_view.created_at,
-                    world_view.updated_at,
-                ],
-            ).map_err(|e| format!("Failed to insert world_view: {}", e))?;
-        }
-    }
+/// 为项目当前的全部内容建立还原点，适合在 AI 批量改写或导入前调用。还原点不会被自动清理
+#[tauri::command]
+pub async fn create_restore_point(
+    app: AppHandle,
+    project_id: String,
+    description: String,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("version_control");
+    logger.info(&format!("Creating restore point for project {}", project_id));
 
-    if let Some(plot_points) = snapshot["plot_points"].as_str() {
-        let plot_points_data: Vec<PlotPoint> = serde_json::from_str(plot_points)
-            .map_err(|e| format!("Failed to parse plot_points: {}", e))?;
-
-        for plot_point in plot_points_data {
-            conn.execute(
-                "INSERT INTO plot_points (id, project_id, parent_id, title, description, note, chapter_id, status, sort_order, level, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
-                params![
-                    plot_point.id,
-                    plot_point.project_id,
-                    plot_point.parent_id,
-                    plot_point.title,
-                    plot_point.description,
-                    plot_point.note,
-                    plot_point.chapter_id,
-                    plot_point.status,
-                    plot_point.sort_order,
-                    plot_point.level,
-                    plot_point.created_at,
-                    plot_point.updated_at,
-                ],
-            ).map_err(|e| format!("Failed to insert plot_point: {}", e))?;
-        }
-    }
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
 
-    logger.info("Snapshot restored successfully");
-    Ok("{\"status\":\"success\"}".to_string())
+    let snapshot = save_restore_point(&conn, &project_id, &description)?;
+
+    serde_json::to_string(&snapshot).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -289,11 +182,15 @@ pub async fn delete_snapshot(
 
     conn.execute("DELETE FROM project_snapshots WHERE id = ?1", params![snapshot_id])
         .map_err(|e| format!("Failed to delete snapshot: {}", e))?;
+    conn.execute("DELETE FROM snapshot_tags WHERE snapshot_id = ?1", params![snapshot_id])
+        .map_err(|e| format!("Failed to delete snapshot tags: {}", e))?;
+    snapshot_store::remove_orphan_chunks(&conn)?;
 
     logger.info("Snapshot deleted successfully");
     Ok("{\"status\":\"success\"}".to_string())
 }
 
+/// 比较两个快照，章节正文按段落、逐词给出差异
 #[tauri::command]
 pub async fn compare_snapshots(
     app: AppHandle,
@@ -303,26 +200,257 @@ pub async fn compare_snapshots(
     let logger = Logger::new().with_feature("version_control");
     logger.info(&format!("Comparing snapshots {} and {}", from_snapshot_id, to_snapshot_id));
 
-    let from_snapshot_json = get_snapshot(app.clone(), from_snapshot_id.clone()).await?;
-    let to_snapshot_json = get_snapshot(app.clone(), to_snapshot_id.clone()).await?;
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let from_snapshot = load_snapshot(&conn, &from_snapshot_id)?;
+    let to_snapshot = load_snapshot(&conn, &to_snapshot_id)?;
+    let diff: VersionDiff = VersionControlManager::compare_snapshots(&from_snapshot, &to_snapshot);
+
+    serde_json::to_string(&diff).map_err(|e| e.to_string())
+}
+
+/// 只把快照中选中的段落（按快照中的段落序号，区间不含 `end`）合并进章节当前正文。
+/// 返回合并后的草稿，不写入章节
+#[tauri::command]
+pub async fn restore_snapshot_paragraphs(
+    app: AppHandle,
+    snapshot_id: String,
+    chapter_id: String,
+    ranges: Vec<std::ops::Range<usize>>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("version_control");
+    logger.info(&format!("Restoring paragraphs of chapter {} from snapshot {}", chapter_id, snapshot_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let snapshot = load_snapshot(&conn, &snapshot_id)?;
+    let snapshot_chapter = snapshot.chapters.iter()
+        .find(|c| c.id == chapter_id)
+        .ok_or_else(|| format!("Chapter {} not found in snapshot", chapter_id))?;
+    let current: String = conn.query_row(
+        "SELECT content FROM chapters WHERE id = ?1",
+        params![chapter_id],
+        |row| row.get::<_, Option<String>>(0),
+    ).optional()
+    .map_err(|e| format!("Failed to query chapter: {}", e))?
+    .flatten()
+    .unwrap_or_default();
+
+    let (content, restored) = text_diff::restore_paragraphs(&current, &snapshot_chapter.content, &ranges);
+
+    serde_json::to_string(&serde_json::json!({
+        "chapter_id": chapter_id,
+        "word_count": content.chars().count(),
+        "content": content,
+        "restored": restored,
+    })).map_err(|e| e.to_string())
+}
+
+/// 章节当前正文逐段落的来源：手写、AI 续写、AI 改写或导入，以及生成它的模型
+#[tauri::command]
+pub async fn get_chapter_blame(
+    app: AppHandle,
+    chapter_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let blame = crate::provenance::blame_chapter(&conn, &chapter_id)?;
+
+    serde_json::to_string(&blame).map_err(|e| e.to_string())
+}
+
+/// 把章节在项目快照和分支中的全部历史导出为版本历史文件，返回文件大小
+#[tauri::command]
+pub async fn export_chapter_history(
+    app: AppHandle,
+    chapter_id: String,
+    output_path: String,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("version_control");
+    logger.info(&format!("Exporting history of chapter {} to {}", chapter_id, output_path));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let bundle = history_bundle::collect_history(&conn, &chapter_id)?;
+    let file_size = history_bundle::write_bundle(&bundle, std::path::Path::new(&output_path))?;
+
+    serde_json::to_string(&serde_json::json!({
+        "output_path": output_path,
+        "file_size": file_size,
+        "revisions": bundle.revisions.len(),
+        "branches": bundle.branches.len(),
+    })).map_err(|e| e.to_string())
+}
+
+/// 把版本历史文件导入到章节，不改动章节当前正文
+#[tauri::command]
+pub async fn import_chapter_history(
+    app: AppHandle,
+    chapter_id: String,
+    input_path: String,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("version_control");
+    logger.info(&format!("Importing history from {} into chapter {}", input_path, chapter_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let bundle = history_bundle::read_bundle(std::path::Path::new(&input_path))?;
+    let result = history_bundle::import_history(&conn, &chapter_id, &bundle)?;
+
+    serde_json::to_string(&result).map_err(|e| e.to_string())
+}
+
+/// 给快照打标签并附上备注，带标签的快照不会被自动清理
+#[tauri::command]
+pub async fn tag_snapshot(
+    app: AppHandle,
+    snapshot_id: String,
+    tag: String,
+    note: Option<String>,
+    milestone: bool,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let tag = snapshot_tags::tag_snapshot(&conn, &snapshot_id, &tag, note.as_deref(), milestone)?;
+
+    serde_json::to_string(&tag).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn untag_snapshot(
+    app: AppHandle,
+    tag_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    snapshot_tags::untag_snapshot(&conn, &tag_id)?;
+    Ok("{\"status\":\"success\"}".to_string())
+}
+
+#[tauri::command]
+pub async fn list_snapshot_tags(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
 
-    let from_snapshot: serde_json::Value = serde_json::from_str(&from_snapshot_json)
-        .map_err(|e| format!("Failed to parse from_snapshot: {}", e))?;
+    let tags = snapshot_tags::list_tags(&conn, &project_id)?;
 
-    let to_snapshot: serde_json::Value = serde_json::from_str(&to_snapshot_json)
-        .map_err(|e| format!("Failed to parse to_snapshot: {}", e))?;
+    serde_json::to_string(&tags).map_err(|e| e.to_string())
+}
 
-    let diff = serde_json::json!({
-        "from_version": from_snapshot["version"],
-        "to_version": to_snapshot["version"],
-        "from_timestamp": from_snapshot["timestamp"],
-        "to_timestamp": to_snapshot["timestamp"],
-        "has_changes": from_snapshot != to_snapshot,
-    });
+/// 世界观条目的历史版本，条目每次修改或删除前自动保存
+#[tauri::command]
+pub async fn get_worldview_revisions(
+    app: AppHandle,
+    worldview_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let revisions = worldview_history::list_revisions(&conn, &worldview_id)?;
+
+    serde_json::to_string(&revisions).map_err(|e| e.to_string())
+}
+
+/// 已删除、可以找回的世界观条目
+#[tauri::command]
+pub async fn get_deleted_world_views(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let revisions = worldview_history::deleted_world_views(&conn, &project_id)?;
+
+    serde_json::to_string(&revisions).map_err(|e| e.to_string())
+}
+
+/// 对比世界观条目的两个版本，不指定 `against_revision_id` 时与当前内容对比
+#[tauri::command]
+pub async fn diff_worldview_revision(
+    app: AppHandle,
+    revision_id: String,
+    against_revision_id: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let diff = worldview_history::diff_revision(&conn, &revision_id, against_revision_id.as_deref())?;
 
     serde_json::to_string(&diff).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn restore_worldview_revision(
+    app: AppHandle,
+    revision_id: String,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("version_control");
+    logger.info(&format!("Restoring world view revision {}", revision_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let world_view = worldview_history::restore_revision(&conn, &revision_id)?;
+
+    serde_json::to_string(&world_view).map_err(|e| e.to_string())
+}
+
+/// 快照存储的去重与压缩统计，不指定项目时统计全部快照
+#[tauri::command]
+pub async fn get_snapshot_storage_stats(
+    app: AppHandle,
+    project_id: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let stats = snapshot_store::storage_stats(&conn, project_id.as_deref())?;
+
+    serde_json::to_string(&stats).map_err(|e| e.to_string())
+}
+
+/// 各项目版本控制的存储占用和最近一次后台清理结果，供设置页显示
+#[tauri::command]
+pub async fn get_version_storage_stats(
+    app: AppHandle,
+    project_id: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let reports = match project_id {
+        Some(project_id) => vec![version_storage::project_report(&conn, &project_id)?],
+        None => version_storage::all_reports(&conn)?,
+    };
+
+    serde_json::to_string(&reports).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_version_config(
     app: AppHandle,
@@ -331,18 +459,7 @@ pub async fn get_version_config(
     let conn = crate::database::get_connection(&db_path)
         .map_err(|e| format!("Failed to get database connection: {}", e))?;
 
-    let config = conn.query_row(
-        "SELECT auto_save_enabled, auto_save_interval_minutes, max_snapshots_per_project, compression_enabled FROM version_control_config WHERE id = 'config'",
-        [],
-        |row| {
-            Ok(VersionControlConfig {
-                auto_save_enabled: row.get::<_, i32>(0)? != 0,
-                auto_save_interval_minutes: row.get::<_, i32>(1)?,
-                max_snapshots_per_project: row.get::<_, i32>(2)?,
-                compression_enabled: row.get::<_, i32>(3)? != 0,
-            })
-        }
-    ).unwrap_or_else(|_| VersionControlConfig::default());
+    let config = load_version_config(&conn);
 
     serde_json::to_string(&config).map_err(|e| e.to_string())
 }
@@ -362,10 +479,11 @@ pub async fn set_version_config(
     let updated_at = chrono::Utc::now().to_rfc3339();
 
     conn.execute(
-        "INSERT OR REPLACE INTO version_control_config (id, auto_save_enabled, auto_save_interval_minutes, max_snapshots_per_project, compression_enabled, updated_at) VALUES ('config', ?1, ?2, ?3, ?4, ?5)",
+        "INSERT OR REPLACE INTO version_control_config (id, auto_save_enabled, auto_save_interval_minutes, auto_save_word_delta, max_snapshots_per_project, compression_enabled, updated_at) VALUES ('config', ?1, ?2, ?3, ?4, ?5, ?6)",
         params![
             if config.auto_save_enabled { 1 } else { 0 },
             config.auto_save_interval_minutes,
+            config.auto_save_word_delta,
             config.max_snapshots_per_project,
             if config.compression_enabled { 1 } else { 0 },
             updated_at,
@@ -375,6 +493,322 @@ pub async fn set_version_config(
     Ok("{\"status\":\"success\"}".to_string())
 }
 
+/// 从章节当前正文建立分支，如“另一种结局”
+#[tauri::command]
+pub async fn create_chapter_branch(
+    app: AppHandle,
+    chapter_id: String,
+    name: String,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("version_control");
+    logger.info(&format!("Creating branch {} for chapter {}", name, chapter_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let branch = chapter_branches::create_branch(&conn, &chapter_id, &name)?;
+    serde_json::to_string(&branch).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_chapter_branches(
+    app: AppHandle,
+    chapter_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let branches = chapter_branches::list_branches(&conn, &chapter_id)?;
+    serde_json::to_string(&branches).map_err(|e| e.to_string())
+}
+
+/// 切换章节的当前分支，章节正文随之替换
+#[tauri::command]
+pub async fn switch_chapter_branch(
+    app: AppHandle,
+    branch_id: String,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("version_control");
+    logger.info(&format!("Switching to branch {}", branch_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let branch = chapter_branches::switch_branch(&conn, &branch_id)?;
+    serde_json::to_string(&branch).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_branch_snapshot(
+    app: AppHandle,
+    branch_id: String,
+    description: String,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("version_control");
+    logger.info(&format!("Creating snapshot on branch {}", branch_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let snapshot = chapter_branches::create_branch_snapshot(&conn, &branch_id, &description)?;
+    serde_json::to_string(&snapshot).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_branch_snapshots(
+    app: AppHandle,
+    branch_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let snapshots = chapter_branches::list_branch_snapshots(&conn, &branch_id)?;
+    serde_json::to_string(&snapshots).map_err(|e| e.to_string())
+}
+
+/// 把分支合并进目标分支。有冲突时返回冲突段而不写入，前端带上 `resolutions` 再次调用
+#[tauri::command]
+pub async fn merge_chapter_branch(
+    app: AppHandle,
+    source_branch_id: String,
+    target_branch_id: String,
+    resolutions: Option<Vec<HunkResolution>>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("version_control");
+    logger.info(&format!("Merging branch {} into {}", source_branch_id, target_branch_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let outcome = chapter_branches::merge_branch(&conn, &source_branch_id, &target_branch_id, resolutions.as_deref())?;
+    if !outcome.applied {
+        logger.info(&format!("Merge has {} conflicts awaiting resolution", outcome.result.conflict_count));
+    }
+    serde_json::to_string(&outcome).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_chapter_branch(
+    app: AppHandle,
+    branch_id: String,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("version_control");
+    logger.info(&format!("Deleting branch {}", branch_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    chapter_branches::delete_branch(&conn, &branch_id)?;
+    Ok("{\"status\":\"success\"}".to_string())
+}
+
+/// 读取项目当前的章节、角色、世界观和情节点，保存为快照
+pub(crate) fn save_project_snapshot(
+    conn: &rusqlite::Connection,
+    project_id: &str,
+    version: &str,
+    description: &str,
+    auto_generated: bool,
+) -> Result<ProjectSnapshot, String> {
+    let chapters = load_chapters(conn, project_id)?;
+    let characters = load_characters(conn, project_id)?;
+    let world_views = load_world_views(conn, project_id)?;
+    let plot_points = load_plot_points(conn, project_id)?;
+
+    let snapshot = VersionControlManager::create_snapshot(
+        project_id,
+        version,
+        description,
+        chapters,
+        characters,
+        world_views,
+        plot_points,
+        auto_generated,
+    );
+
+    let created_at = chrono::Utc::now().to_rfc3339();
+
+    let (storage, [chapters_json, characters_json, world_views_json, plot_points_json]) = if load_version_config(conn).compression_enabled {
+        (snapshot_store::CHUNKED, [
+            snapshot_store::store_items(conn, &snapshot.chapters)?,
+            snapshot_store::store_items(conn, &snapshot.characters)?,
+            snapshot_store::store_items(conn, &snapshot.world_views)?,
+            snapshot_store::store_items(conn, &snapshot.plot_points)?,
+        ])
+    } else {
+        (snapshot_store::INLINE, [
+            serde_json::to_string(&snapshot.chapters).unwrap_or_default(),
+            serde_json::to_string(&snapshot.characters).unwrap_or_default(),
+            serde_json::to_string(&snapshot.world_views).unwrap_or_default(),
+            serde_json::to_string(&snapshot.plot_points).unwrap_or_default(),
+        ])
+    };
+
+    conn.execute(
+        "INSERT INTO project_snapshots (id, project_id, version, timestamp, description, chapters_json, characters_json, world_views_json, plot_points_json, metadata_json, auto_generated, created_at, storage) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        params![
+            snapshot.id,
+            snapshot.project_id,
+            snapshot.version,
+            snapshot.timestamp,
+            snapshot.description,
+            chapters_json,
+            characters_json,
+            world_views_json,
+            plot_points_json,
+            serde_json::to_string(&snapshot.metadata).unwrap_or_default(),
+            if snapshot.metadata.auto_generated { 1 } else { 0 },
+            created_at,
+            storage,
+        ],
+    ).map_err(|e| format!("Failed to save snapshot: {}", e))?;
+
+    if !auto_generated {
+        let max_snapshots = get_max_snapshots(conn);
+        cleanup_old_snapshots(conn, project_id, max_snapshots)
+            .map_err(|e| format!("Failed to cleanup old snapshots: {}", e))?;
+    }
+
+    Ok(snapshot)
+}
+
+/// 还原点带有这个标签
+pub(crate) const RESTORE_POINT_TAG: &str = "还原点";
+
+pub(crate) fn save_restore_point(
+    conn: &rusqlite::Connection,
+    project_id: &str,
+    description: &str,
+) -> Result<ProjectSnapshot, String> {
+    let version = chrono::Utc::now().format("restore-%Y%m%d-%H%M%S").to_string();
+    let snapshot = save_project_snapshot(conn, project_id, &version, description, true)?;
+    snapshot_tags::tag_snapshot(conn, &snapshot.id, RESTORE_POINT_TAG, None, false)?;
+    Ok(snapshot)
+}
+
+/// 在一个事务中用快照替换项目内容，返回恢复前建立的还原点
+pub(crate) fn restore_project_snapshot(conn: &rusqlite::Connection, snapshot_id: &str) -> Result<ProjectSnapshot, String> {
+    let snapshot = load_snapshot(conn, snapshot_id)?;
+    let project_id = snapshot.project_id.as_str();
+    let now = chrono::Utc::now().to_rfc3339();
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let restore_point = save_restore_point(&tx, project_id, &format!("恢复到 {} 之前", snapshot.version))?;
+
+    for table in ["chapters", "characters", "world_views", "plot_points"] {
+        tx.execute(&format!("DELETE FROM {} WHERE project_id = ?1", table), params![project_id])
+            .map_err(|e| format!("Failed to delete {}: {}", table, e))?;
+    }
+
+    for chapter in &snapshot.chapters {
+        tx.execute(
+            "INSERT INTO chapters (id, project_id, title, content, word_count, sort_order, status, summary, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)",
+            params![
+                chapter.id,
+                project_id,
+                chapter.title,
+                chapter.content,
+                chapter.word_count,
+                chapter.order,
+                chapter.status.as_deref().unwrap_or("draft"),
+                chapter.summary,
+                now,
+            ],
+        ).map_err(|e| format!("Failed to insert chapter: {}", e))?;
+    }
+
+    for character in &snapshot.characters {
+        let details = character.details.clone().unwrap_or_default();
+        let text = |value: &str| (!value.is_empty()).then(|| value.to_string());
+        tx.execute(
+            "INSERT INTO characters (id, project_id, name, role_type, race, age, gender, birth_date, appearance, personality, background, skills, status, bazi, ziwei, mbti, enneagram, items, avatar_url, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?20)",
+            params![
+                character.id,
+                project_id,
+                character.name,
+                text(&character.description),
+                details.race,
+                details.age,
+                details.gender,
+                details.birth_date,
+                text(&character.appearance),
+                text(&character.personality),
+                text(&character.background),
+                details.skills,
+                details.status,
+                details.bazi,
+                details.ziwei,
+                details.mbti,
+                details.enneagram,
+                details.items,
+                details.avatar_url,
+                now,
+            ],
+        ).map_err(|e| format!("Failed to insert character: {}", e))?;
+    }
+
+    for world_view in &snapshot.world_views {
+        tx.execute(
+            "INSERT INTO world_views (id, project_id, category, title, content, tags, status, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)",
+            params![
+                world_view.id,
+                project_id,
+                world_view.category,
+                world_view.name,
+                world_view.description,
+                world_view.tags,
+                world_view.status.as_deref().unwrap_or("draft"),
+                now,
+            ],
+        ).map_err(|e| format!("Failed to insert world_view: {}", e))?;
+    }
+
+    for plot_point in &snapshot.plot_points {
+        tx.execute(
+            "INSERT INTO plot_points (id, project_id, parent_id, title, description, note, chapter_id, status, sort_order, level, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?11)",
+            params![
+                plot_point.id,
+                project_id,
+                plot_point.parent_id,
+                plot_point.title,
+                plot_point.content,
+                plot_point.note,
+                plot_point.chapter_id,
+                plot_point.status.as_deref().unwrap_or("draft"),
+                plot_point.order,
+                plot_point.level,
+                now,
+            ],
+        ).map_err(|e| format!("Failed to insert plot_point: {}", e))?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(restore_point)
+}
+
+pub(crate) fn load_version_config(conn: &rusqlite::Connection) -> VersionControlConfig {
+    conn.query_row(
+        "SELECT auto_save_enabled, auto_save_interval_minutes, auto_save_word_delta, max_snapshots_per_project, compression_enabled FROM version_control_config WHERE id = 'config'",
+        [],
+        |row| {
+            Ok(VersionControlConfig {
+                auto_save_enabled: row.get::<_, i32>(0)? != 0,
+                auto_save_interval_minutes: row.get::<_, i32>(1)?,
+                auto_save_word_delta: row.get::<_, i32>(2)?,
+                max_snapshots_per_project: row.get::<_, i32>(3)?,
+                compression_enabled: row.get::<_, i32>(4)? != 0,
+            })
+        }
+    ).unwrap_or_else(|_| VersionControlConfig::default())
+}
+
 fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
     if cfg!(debug_assertions) {
         let mut project_dir = std::env::current_dir()
@@ -388,9 +822,45 @@ fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
     }
 }
 
+pub(crate) fn load_snapshot(conn: &rusqlite::Connection, snapshot_id: &str) -> Result<ProjectSnapshot, String> {
+    let (id, project_id, version, timestamp, description, storage, bodies, metadata) = conn.query_row(
+        "SELECT id, project_id, version, timestamp, description, chapters_json, characters_json, world_views_json, plot_points_json, metadata_json, storage
+         FROM project_snapshots
+         WHERE id = ?1",
+        params![snapshot_id],
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(10)?,
+                [row.get::<_, String>(5)?, row.get(6)?, row.get(7)?, row.get(8)?],
+                row.get::<_, String>(9)?,
+            ))
+        },
+    ).map_err(|e| format!("Failed to query snapshot: {}", e))?;
+
+    let [chapters, characters, world_views, plot_points] = snapshot_store::inline_bodies(conn, &storage, bodies)?;
+    let parse_error = |e: serde_json::Error| format!("Failed to parse snapshot {}: {}", snapshot_id, e);
+    Ok(ProjectSnapshot {
+        id,
+        project_id,
+        version,
+        timestamp,
+        description,
+        chapters: serde_json::from_str(&chapters).map_err(parse_error)?,
+        characters: serde_json::from_str(&characters).map_err(parse_error)?,
+        world_views: serde_json::from_str(&world_views).map_err(parse_error)?,
+        plot_points: serde_json::from_str(&plot_points).map_err(parse_error)?,
+        metadata: serde_json::from_str(&metadata).map_err(parse_error)?,
+    })
+}
+
 fn load_chapters(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec<crate::version_control::ChapterSnapshot>, String> {
     let mut stmt = conn.prepare(
-        "SELECT id, title, content, sort_order, word_count FROM chapters WHERE project_id = ?1 ORDER BY sort_order"
+        "SELECT id, title, content, sort_order, word_count, status, summary FROM chapters WHERE project_id = ?1 ORDER BY sort_order"
     ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
     let chapters = stmt.query_map(params![project_id], |row| {
@@ -400,6 +870,8 @@ fn load_chapters(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec<cr
             content: row.get(2)?,
             order: row.get(3)?,
             word_count: row.get(4)?,
+            status: row.get(5)?,
+            summary: row.get(6)?,
         })
     }).map_err(|e| format!("Failed to query chapters: {}", e))?;
 
@@ -409,7 +881,9 @@ fn load_chapters(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec<cr
 
 fn load_characters(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec<crate::version_control::CharacterSnapshot>, String> {
     let mut stmt = conn.prepare(
-        "SELECT id, name, description, personality, appearance, background FROM characters WHERE project_id = ?1"
+        "SELECT id, name, COALESCE(role_type, ''), COALESCE(personality, ''), COALESCE(appearance, ''), COALESCE(background, ''),
+                race, age, gender, birth_date, skills, status, bazi, ziwei, mbti, enneagram, items, avatar_url
+         FROM characters WHERE project_id = ?1"
     ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
     let characters = stmt.query_map(params![project_id], |row| {
@@ -420,6 +894,20 @@ fn load_characters(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec<
             personality: row.get(3)?,
             appearance: row.get(4)?,
             background: row.get(5)?,
+            details: Some(crate::version_control::CharacterDetails {
+                race: row.get(6)?,
+                age: row.get(7)?,
+                gender: row.get(8)?,
+                birth_date: row.get(9)?,
+                skills: row.get(10)?,
+                status: row.get(11)?,
+                bazi: row.get(12)?,
+                ziwei: row.get(13)?,
+                mbti: row.get(14)?,
+                enneagram: row.get(15)?,
+                items: row.get(16)?,
+                avatar_url: row.get(17)?,
+            }),
         })
     }).map_err(|e| format!("Failed to query characters: {}", e))?;
 
@@ -429,7 +917,7 @@ fn load_characters(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec<
 
 fn load_world_views(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec<crate::version_control::WorldViewSnapshot>, String> {
     let mut stmt = conn.prepare(
-        "SELECT id, title, category, description FROM world_views WHERE project_id = ?1"
+        "SELECT id, title, category, content, tags, status FROM world_views WHERE project_id = ?1"
     ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
     let world_views = stmt.query_map(params![project_id], |row| {
@@ -438,6 +926,8 @@ fn load_world_views(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec
             name: row.get(1)?,
             category: row.get(2)?,
             description: row.get(3)?,
+            tags: row.get(4)?,
+            status: row.get(5)?,
         })
     }).map_err(|e| format!("Failed to query world_views: {}", e))?;
 
@@ -447,7 +937,7 @@ fn load_world_views(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec
 
 fn load_plot_points(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec<crate::version_control::PlotPointSnapshot>, String> {
     let mut stmt = conn.prepare(
-        "SELECT id, title, content, chapter_id, sort_order FROM plot_points WHERE project_id = ?1 ORDER BY sort_order"
+        "SELECT id, title, COALESCE(description, ''), chapter_id, sort_order, parent_id, note, status, level FROM plot_points WHERE project_id = ?1 ORDER BY sort_order"
     ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
     let plot_points = stmt.query_map(params![project_id], |row| {
@@ -457,6 +947,10 @@ fn load_plot_points(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec
             content: row.get(2)?,
             chapter_id: row.get(3)?,
             order: row.get(4)?,
+            parent_id: row.get(5)?,
+            note: row.get(6)?,
+            status: row.get(7)?,
+            level: row.get(8)?,
         })
     }).map_err(|e| format!("Failed to query plot_points: {}", e))?;
 
@@ -464,7 +958,7 @@ fn load_plot_points(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec
         .map_err(|e| format!("Failed to collect plot_points: {}", e))
 }
 
-fn get_max_snapshots(conn: &rusqlite::Connection) -> i32 {
+pub(crate) fn get_max_snapshots(conn: &rusqlite::Connection) -> i32 {
     conn.query_row(
         "SELECT max_snapshots_per_project FROM version_control_config WHERE id = 'config'",
         [],
@@ -472,10 +966,11 @@ fn get_max_snapshots(conn: &rusqlite::Connection) -> i32 {
     ).unwrap_or(50)
 }
 
-fn cleanup_old_snapshots(conn: &rusqlite::Connection, project_id: &str, max_snapshots: i32) -> Result<(), String> {
+/// 手动快照按数量上限清理，自动快照由保留策略清理，带标签的快照不计数也不清理
+pub(crate) fn cleanup_old_snapshots(conn: &rusqlite::Connection, project_id: &str, max_snapshots: i32) -> Result<usize, String> {
     let snapshots: Vec<(String, i64)> = {
         let mut stmt = conn.prepare(
-            "SELECT id, timestamp FROM project_snapshots WHERE project_id = ?1 ORDER BY timestamp DESC"
+            "SELECT id, timestamp FROM project_snapshots WHERE project_id = ?1 AND auto_generated = 0 AND id NOT IN (SELECT snapshot_id FROM snapshot_tags) ORDER BY timestamp DESC"
         ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
         let snapshots = stmt.query_map(params![project_id], |row| {
@@ -491,7 +986,59 @@ fn cleanup_old_snapshots(conn: &rusqlite::Connection, project_id: &str, max_snap
             conn.execute("DELETE FROM project_snapshots WHERE id = ?1", params![snapshot_id])
                 .map_err(|e| format!("Failed to delete old snapshot: {}", e))?;
         }
+        snapshot_store::remove_orphan_chunks(conn)?;
+        return Ok(snapshots.len() - max_snapshots as usize);
     }
 
-    Ok(())
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore_whole_project() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO chapters (id, project_id, title, content, word_count, sort_order, status, created_at, updated_at)
+                 VALUES ('c1', 'p1', '第一章', '雨夜', 2, 1, 'completed', 't0', 't0');
+             INSERT INTO characters (id, project_id, name, role_type, age, mbti, created_at, updated_at)
+                 VALUES ('r1', 'p1', '林舟', '主角', 27, 'INTJ', 't0', 't0');
+             INSERT INTO plot_points (id, project_id, parent_id, title, description, level, sort_order, created_at, updated_at)
+                 VALUES ('o1', 'p1', NULL, '第一幕', '相遇', 0, 1, 't0', 't0'),
+                        ('o2', 'p1', 'o1', '雨夜重逢', NULL, 1, 2, 't0', 't0');",
+        )
+        .unwrap();
+        let before = save_project_snapshot(&conn, "p1", "v1", "批量改写前", false).unwrap();
+
+        // 模拟一次批量改写
+        conn.execute_batch(
+            "UPDATE chapters SET content = '改写后的正文' WHERE id = 'c1';
+             DELETE FROM characters;
+             DELETE FROM plot_points WHERE id = 'o2';",
+        )
+        .unwrap();
+
+        let restore_point = restore_project_snapshot(&conn, &before.id).unwrap();
+        let chapter: (String, String) = conn
+            .query_row("SELECT content, status FROM chapters WHERE id = 'c1'", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!(chapter, ("雨夜".to_string(), "completed".to_string()));
+        let character: (String, i32, String) = conn
+            .query_row("SELECT role_type, age, mbti FROM characters WHERE id = 'r1'", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap();
+        assert_eq!(character, ("主角".to_string(), 27, "INTJ".to_string()));
+        let parent: Option<String> =
+            conn.query_row("SELECT parent_id FROM plot_points WHERE id = 'o2'", [], |row| row.get(0)).unwrap();
+        assert_eq!(parent.as_deref(), Some("o1"));
+
+        // 还原点保存了恢复前的内容，可以撤销这次恢复
+        let undo = load_snapshot(&conn, &restore_point.id).unwrap();
+        assert_eq!(undo.chapters[0].content, "改写后的正文");
+        assert!(undo.characters.is_empty());
+        assert_eq!(snapshot_tags::list_tags(&conn, "p1").unwrap()[0].tag, RESTORE_POINT_TAG);
+    }
 }
@@ -0,0 +1,143 @@
+use crate::auto_snapshot::prune_auto_snapshots;
+use crate::snapshot_store::{self, StorageStats};
+use crate::version_control_commands::{cleanup_old_snapshots, get_max_snapshots};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// 一个项目的版本控制存储占用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectStorageReport {
+    pub project_id: String,
+    pub project_name: String,
+    pub snapshots: StorageStats,
+    pub branch_count: i64,
+    pub branch_snapshot_count: i64,
+    /// 分支正文和分支快照占用的字节数
+    pub branch_bytes: i64,
+    /// 快照实际占用加上分支占用的字节数
+    pub total_bytes: i64,
+    /// 最近一次后台维护清理掉的快照数
+    pub last_pruned: i64,
+    pub last_maintained_at: Option<String>,
+}
+
+/// 统计项目当前的版本控制存储占用
+pub fn project_report(conn: &Connection, project_id: &str) -> Result<ProjectStorageReport, String> {
+    let project_name: String = conn
+        .query_row("SELECT name FROM projects WHERE id = ?", params![project_id], |row| row.get(0))
+        .map_err(|_| format!("项目不存在: {}", project_id))?;
+    let snapshots = snapshot_store::storage_stats(conn, Some(project_id))?;
+    let (branch_count, branch_content_bytes): (i64, i64) = conn
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(LENGTH(CAST(base_content AS BLOB)) + LENGTH(CAST(content AS BLOB))), 0)
+             FROM chapter_branches WHERE project_id = ?",
+            params![project_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    let (branch_snapshot_count, branch_snapshot_bytes): (i64, i64) = conn
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(LENGTH(CAST(s.content AS BLOB))), 0)
+             FROM chapter_branch_snapshots s JOIN chapter_branches b ON b.id = s.branch_id WHERE b.project_id = ?",
+            params![project_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    let (last_pruned, last_maintained_at) = conn
+        .query_row(
+            "SELECT pruned, reported_at FROM version_storage_reports WHERE project_id = ?",
+            params![project_id],
+            |row| Ok((row.get::<_, i64>(0)?, Some(row.get::<_, String>(1)?))),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .unwrap_or((0, None));
+
+    let branch_bytes = branch_content_bytes + branch_snapshot_bytes;
+    Ok(ProjectStorageReport {
+        project_id: project_id.to_string(),
+        project_name,
+        total_bytes: snapshots.stored_bytes + branch_bytes,
+        snapshots,
+        branch_count,
+        branch_snapshot_count,
+        branch_bytes,
+        last_pruned,
+        last_maintained_at,
+    })
+}
+
+/// 所有项目的存储占用，按占用从大到小排序
+pub fn all_reports(conn: &Connection) -> Result<Vec<ProjectStorageReport>, String> {
+    let project_ids: Vec<String> = conn
+        .prepare("SELECT id FROM projects")
+        .map_err(|e| e.to_string())?
+        .query_map([], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut reports = project_ids.iter().map(|id| project_report(conn, id)).collect::<Result<Vec<_>, _>>()?;
+    reports.sort_by_key(|r| std::cmp::Reverse(r.total_bytes));
+    Ok(reports)
+}
+
+/// 按配置的策略清理每个项目的快照（自动快照按保留策略，手动快照按数量上限），
+/// 记录清理数量并返回清理后的存储占用
+pub fn run_maintenance(conn: &Connection) -> Result<Vec<ProjectStorageReport>, String> {
+    let max_snapshots = get_max_snapshots(conn);
+    let now = Utc::now().to_rfc3339();
+    let mut reports = Vec::new();
+    for report in all_reports(conn)? {
+        let pruned = prune_auto_snapshots(conn, &report.project_id)?
+            + cleanup_old_snapshots(conn, &report.project_id, max_snapshots)?;
+        let mut report = if pruned > 0 { project_report(conn, &report.project_id)? } else { report };
+        conn.execute(
+            "INSERT OR REPLACE INTO version_storage_reports (project_id, pruned, stored_bytes, reported_at) VALUES (?, ?, ?, ?)",
+            params![report.project_id, pruned as i64, report.total_bytes, now],
+        )
+        .map_err(|e| e.to_string())?;
+        report.last_pruned = pruned as i64;
+        report.last_maintained_at = Some(now.clone());
+        reports.push(report);
+    }
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maintenance_enforces_snapshot_limit() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO projects (id, name, created_at, updated_at) VALUES ('p2', '短歌', 't0', 't0');
+             INSERT INTO chapters (id, project_id, title, content, created_at, updated_at) VALUES ('c1', 'p1', '第一章', '雨夜', 't0', 't0');",
+        )
+        .unwrap();
+        for (id, timestamp) in [("s1", 1), ("s2", 2), ("s3", 3)] {
+            conn.execute(
+                "INSERT INTO project_snapshots (id, project_id, version, timestamp, description, chapters_json, characters_json,
+                 world_views_json, plot_points_json, metadata_json, auto_generated, created_at)
+                 VALUES (?, 'p1', ?, ?, '', '[]', '[]', '[]', '[]', '{}', 0, 't0')",
+                params![id, id, timestamp],
+            )
+            .unwrap();
+        }
+        crate::chapter_branches::create_branch(&conn, "c1", "另一种结局").unwrap();
+        // 调低上限后，已有的超额手动快照由后台维护清理
+        conn.execute("INSERT OR REPLACE INTO version_control_config (id, max_snapshots_per_project, updated_at) VALUES ('config', 2, 't0')", [])
+            .unwrap();
+
+        let before = project_report(&conn, "p1").unwrap();
+        assert_eq!((before.snapshots.snapshot_count, before.branch_count, before.last_maintained_at), (3, 2, None));
+        assert_eq!(before.branch_bytes, 4 * "雨夜".len() as i64);
+
+        let reports = run_maintenance(&conn).unwrap();
+        assert_eq!(reports.iter().map(|r| r.project_id.as_str()).collect::<Vec<_>>(), vec!["p1", "p2"]);
+        assert_eq!((reports[0].snapshots.snapshot_count, reports[0].last_pruned), (2, 1));
+        assert!(reports[0].total_bytes < before.total_bytes);
+        assert!(reports[1].last_maintained_at.is_some());
+    }
+}
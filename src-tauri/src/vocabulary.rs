@@ -0,0 +1,452 @@
+use crate::character_presence::ChapterRef;
+use crate::entity_index::locate_terms;
+use crate::provenance::{self, ContentSource};
+use crate::tokenizer::{self, REFERENCE_TOTAL};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// 常见的口头禅、赘词，项目可以再补充
+const DEFAULT_CRUTCH_WORDS: &[&str] = &[
+    "突然", "然后", "于是", "不禁", "似乎", "仿佛", "微微", "淡淡", "缓缓", "轻轻", "不由得", "一丝", "一抹", "嘴角",
+    "竟然", "居然", "其实", "非常", "十分", "感觉", "好像", "显然", "顿时", "深深",
+];
+pub const LIST_IGNORE: &str = "ignore";
+pub const LIST_CRUTCH: &str = "crutch";
+/// 标准化类符/形符比按每这么多词一段计算
+const STTR_WINDOW: usize = 1000;
+/// 出现少于这么多次的词和短语不算过度使用
+const MIN_OVERUSE_COUNT: usize = 3;
+
+/// 比通用语料用得多的词或短语，`ratio` 是相对频率的倍数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverusedTerm {
+    pub term: String,
+    pub count: usize,
+    pub per_thousand: f64,
+    pub ratio: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorCount {
+    pub author: String,
+    pub count: usize,
+    pub per_thousand: f64,
+}
+
+/// 一个赘词在全书、各“作者”（手写、导入、各 AI 模型）和各章的使用次数；
+/// `by_chapter` 与 `VocabularyReport::chapters` 一一对应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrutchWordUsage {
+    pub word: String,
+    pub total: usize,
+    pub per_thousand: f64,
+    pub by_author: Vec<AuthorCount>,
+    pub by_chapter: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorVocabulary {
+    pub author: String,
+    pub total_words: usize,
+    pub unique_words: usize,
+    pub type_token_ratio: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VocabularyReport {
+    pub chapters: Vec<ChapterRef>,
+    pub total_words: usize,
+    pub unique_words: usize,
+    pub type_token_ratio: f64,
+    /// 每 1000 词一段的类符/形符比的平均值，不受篇幅影响，可以在作品之间比较
+    pub standardized_ttr: f64,
+    pub overused_words: Vec<OverusedTerm>,
+    pub overused_phrases: Vec<OverusedTerm>,
+    pub crutch_words: Vec<CrutchWordUsage>,
+    pub authors: Vec<AuthorVocabulary>,
+}
+
+/// 词或二元组的出现次数，`by_chapter` 与 `WordStats::chapters` 一一对应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NgramCount {
+    pub term: String,
+    pub count: usize,
+    pub per_thousand: f64,
+    pub by_chapter: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WordStats {
+    pub chapters: Vec<ChapterRef>,
+    /// 各章分词后的词数，用来把 `by_chapter` 换算成频率
+    pub chapter_words: Vec<usize>,
+    pub total_words: usize,
+    pub unique_words: usize,
+    /// 只出现一次的词
+    pub hapax_count: usize,
+    pub hapax_ratio: f64,
+    pub unigrams: Vec<NgramCount>,
+    pub bigrams: Vec<NgramCount>,
+}
+
+/// 项目的忽略词和自定义赘词
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VocabularyLists {
+    pub ignore: Vec<String>,
+    pub crutch: Vec<String>,
+}
+
+pub fn get_lists(conn: &Connection, project_id: &str) -> Result<VocabularyLists, String> {
+    let mut stmt = conn
+        .prepare("SELECT list, word FROM vocabulary_word_lists WHERE project_id = ? ORDER BY rowid")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![project_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+    let mut lists = VocabularyLists::default();
+    for row in rows {
+        let (list, word) = row.map_err(|e| e.to_string())?;
+        match list.as_str() {
+            LIST_IGNORE => lists.ignore.push(word),
+            LIST_CRUTCH => lists.crutch.push(word),
+            _ => {}
+        }
+    }
+    Ok(lists)
+}
+
+/// 整体替换项目的忽略词（`ignore`）或自定义赘词（`crutch`）列表
+pub fn set_list(conn: &Connection, project_id: &str, list: &str, words: &[String]) -> Result<VocabularyLists, String> {
+    if list != LIST_IGNORE && list != LIST_CRUTCH {
+        return Err(format!("未知的词表: {}", list));
+    }
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM vocabulary_word_lists WHERE project_id = ? AND list = ?", params![project_id, list])
+        .map_err(|e| e.to_string())?;
+    for word in words.iter().map(|w| w.trim()).filter(|w| !w.is_empty()) {
+        tx.execute(
+            "INSERT OR IGNORE INTO vocabulary_word_lists (project_id, list, word) VALUES (?, ?, ?)",
+            params![project_id, list, word],
+        )
+        .map_err(|e| format!("Failed to save word list: {}", e))?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    get_lists(conn, project_id)
+}
+
+fn author_of(source: ContentSource, model_id: Option<&str>) -> String {
+    match source {
+        ContentSource::Typing => "作者".to_string(),
+        ContentSource::Import => "导入".to_string(),
+        ContentSource::AiContinue | ContentSource::AiRewrite => format!("AI·{}", model_id.unwrap_or("未知模型")),
+    }
+}
+
+fn type_token_ratio(words: &[&str]) -> f64 {
+    if words.is_empty() {
+        return 0.0;
+    }
+    words.iter().collect::<HashSet<_>>().len() as f64 / words.len() as f64
+}
+
+fn per_thousand(count: usize, total: usize) -> f64 {
+    count as f64 / total.max(1) as f64 * 1000.0
+}
+
+/// 按相对通用语料的倍数和出现次数给候选词排序，取前 `top_n` 个
+fn rank_overused(counts: HashMap<String, usize>, total_words: usize, top_n: usize) -> Vec<OverusedTerm> {
+    let mut terms: Vec<(f64, OverusedTerm)> = counts
+        .into_iter()
+        .filter(|(_, count)| *count >= MIN_OVERUSE_COUNT)
+        .filter_map(|(term, count)| {
+            let expected = tokenizer::reference_frequency(&term).max(1) as f64 / REFERENCE_TOTAL;
+            let ratio = count as f64 / total_words as f64 / expected;
+            (ratio > 1.0).then(|| (count as f64 * ratio.ln(), OverusedTerm { per_thousand: per_thousand(count, total_words), term, count, ratio }))
+        })
+        .collect();
+    terms.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.term.cmp(&b.1.term)));
+    terms.into_iter().take(top_n).map(|(_, term)| term).collect()
+}
+
+/// 统计项目的词汇丰富度、相对通用语料过度使用的词和短语，以及赘词按作者和章节的分布；
+/// 角色名、别名、世界观条目名和项目忽略词不参与统计
+pub fn analyze(conn: &Connection, project_id: &str, top_n: usize) -> Result<VocabularyReport, String> {
+    let chapters: Vec<(String, String)> = conn
+        .prepare("SELECT id, title FROM chapters WHERE project_id = ? ORDER BY sort_order, created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let lists = get_lists(conn, project_id)?;
+    let mut ignored: HashSet<String> = lists.ignore.iter().cloned().collect();
+    for sql in [
+        "SELECT name FROM characters WHERE project_id = ? AND merged_into IS NULL",
+        "SELECT a.alias FROM character_aliases a JOIN characters c ON c.id = a.character_id WHERE c.project_id = ?",
+        "SELECT title FROM world_views WHERE project_id = ?",
+    ] {
+        let names: Vec<String> = conn
+            .prepare(sql)
+            .map_err(|e| e.to_string())?
+            .query_map(params![project_id], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+        ignored.extend(names);
+    }
+    let mut crutch: Vec<String> = Vec::new();
+    for word in DEFAULT_CRUTCH_WORDS.iter().map(|w| w.to_string()).chain(lists.crutch) {
+        if !ignored.contains(&word) && !crutch.contains(&word) {
+            crutch.push(word);
+        }
+    }
+    let crutch_terms: Vec<&str> = crutch.iter().map(String::as_str).collect();
+
+    let mut all_words: Vec<String> = Vec::new();
+    let mut phrase_counts: HashMap<String, usize> = HashMap::new();
+    let mut author_words: Vec<(String, Vec<String>)> = Vec::new();
+    // 赘词 × 作者、赘词 × 章节的次数
+    let mut crutch_by_author: Vec<HashMap<String, usize>> = vec![HashMap::new(); crutch.len()];
+    let mut crutch_by_chapter: Vec<Vec<usize>> = vec![vec![0; chapters.len()]; crutch.len()];
+
+    for (chapter_index, (chapter_id, _)) in chapters.iter().enumerate() {
+        for line in provenance::blame_chapter(conn, chapter_id)? {
+            let author = author_of(line.source, line.model_id.as_deref());
+            let words: Vec<&str> = tokenizer::words(&line.text).into_iter().filter(|w| !ignored.contains(*w)).collect();
+            for n in 2..=3 {
+                for window in words.windows(n) {
+                    let phrase = window.concat();
+                    if phrase.chars().count() >= 4 && !ignored.contains(&phrase) {
+                        *phrase_counts.entry(phrase).or_default() += 1;
+                    }
+                }
+            }
+            for (_, _, i) in locate_terms(&line.text, &crutch_terms) {
+                *crutch_by_author[i].entry(author.clone()).or_default() += 1;
+                crutch_by_chapter[i][chapter_index] += 1;
+            }
+            let owned = words.iter().map(|w| w.to_string());
+            match author_words.iter_mut().find(|(a, _)| *a == author) {
+                Some((_, list)) => list.extend(owned),
+                None => author_words.push((author, owned.collect())),
+            }
+            all_words.extend(words.iter().map(|w| w.to_string()));
+        }
+    }
+
+    let total_words = all_words.len();
+    let refs: Vec<&str> = all_words.iter().map(String::as_str).collect();
+    let type_token_ratio_all = type_token_ratio(&refs);
+    let standardized_ttr = if total_words < STTR_WINDOW {
+        type_token_ratio_all
+    } else {
+        let windows: Vec<f64> = refs.chunks_exact(STTR_WINDOW).map(type_token_ratio).collect();
+        windows.iter().sum::<f64>() / windows.len() as f64
+    };
+
+    let mut word_counts: HashMap<String, usize> = HashMap::new();
+    for word in all_words.iter().filter(|w| w.chars().count() >= 2) {
+        *word_counts.entry(word.clone()).or_default() += 1;
+    }
+
+    let authors: Vec<AuthorVocabulary> = author_words
+        .iter()
+        .map(|(author, words)| {
+            let refs: Vec<&str> = words.iter().map(String::as_str).collect();
+            AuthorVocabulary {
+                author: author.clone(),
+                total_words: words.len(),
+                unique_words: refs.iter().collect::<HashSet<_>>().len(),
+                type_token_ratio: type_token_ratio(&refs),
+            }
+        })
+        .collect();
+    let author_totals: HashMap<&str, usize> = authors.iter().map(|a| (a.author.as_str(), a.total_words)).collect();
+
+    let mut crutch_words: Vec<CrutchWordUsage> = crutch
+        .iter()
+        .zip(crutch_by_author.into_iter().zip(crutch_by_chapter))
+        .filter_map(|(word, (by_author, by_chapter))| {
+            let total: usize = by_chapter.iter().sum();
+            if total == 0 {
+                return None;
+            }
+            let mut by_author: Vec<AuthorCount> = by_author
+                .into_iter()
+                .map(|(author, count)| AuthorCount {
+                    per_thousand: per_thousand(count, author_totals.get(author.as_str()).copied().unwrap_or(0)),
+                    author,
+                    count,
+                })
+                .collect();
+            by_author.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.author.cmp(&b.author)));
+            Some(CrutchWordUsage { word: word.clone(), total, per_thousand: per_thousand(total, total_words), by_author, by_chapter })
+        })
+        .collect();
+    crutch_words.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.word.cmp(&b.word)));
+
+    Ok(VocabularyReport {
+        chapters: chapters.into_iter().map(|(chapter_id, title)| ChapterRef { chapter_id, title }).collect(),
+        total_words,
+        unique_words: refs.iter().collect::<HashSet<_>>().len(),
+        type_token_ratio: type_token_ratio_all,
+        standardized_ttr,
+        overused_words: rank_overused(word_counts, total_words, top_n),
+        overused_phrases: rank_overused(phrase_counts, total_words, top_n),
+        crutch_words,
+        authors,
+    })
+}
+
+/// 按次数取前 `top_n` 个，次数相同按词排序
+fn top_ngrams(counts: HashMap<String, Vec<usize>>, total_words: usize, top_n: usize) -> Vec<NgramCount> {
+    let mut ngrams: Vec<NgramCount> = counts
+        .into_iter()
+        .map(|(term, by_chapter)| {
+            let count = by_chapter.iter().sum();
+            NgramCount { term, count, per_thousand: per_thousand(count, total_words), by_chapter }
+        })
+        .collect();
+    ngrams.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.term.cmp(&b.term)));
+    ngrams.truncate(top_n);
+    ngrams
+}
+
+/// 分词后的词频和二元组统计，以及各高频词在章节间的变化。指定 `chapter_id` 时只统计这一章；
+/// 单字词和不足三字的二元组（多为虚词）只计入总数，不进排行；项目忽略词不参与统计
+pub fn word_stats(conn: &Connection, project_id: &str, chapter_id: Option<&str>, top_n: usize) -> Result<WordStats, String> {
+    let chapters: Vec<(String, String, String)> = conn
+        .prepare(
+            "SELECT id, title, COALESCE(content, '') FROM chapters WHERE project_id = ?1 AND (?2 IS NULL OR id = ?2)
+             ORDER BY sort_order, created_at",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id, chapter_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let ignored: HashSet<String> = get_lists(conn, project_id)?.ignore.into_iter().collect();
+
+    let mut word_counts: HashMap<String, usize> = HashMap::new();
+    let mut unigrams: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut bigrams: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut chapter_words = Vec::new();
+    for (index, (_, _, content)) in chapters.iter().enumerate() {
+        let mut words_in_chapter = 0;
+        for line in content.lines() {
+            let words: Vec<&str> = tokenizer::words(line).into_iter().filter(|w| !ignored.contains(*w)).collect();
+            words_in_chapter += words.len();
+            for word in &words {
+                *word_counts.entry(word.to_string()).or_default() += 1;
+                if word.chars().count() >= 2 {
+                    unigrams.entry(word.to_string()).or_insert_with(|| vec![0; chapters.len()])[index] += 1;
+                }
+            }
+            for pair in words.windows(2) {
+                let bigram = pair.concat();
+                if bigram.chars().count() >= 3 {
+                    bigrams.entry(bigram).or_insert_with(|| vec![0; chapters.len()])[index] += 1;
+                }
+            }
+        }
+        chapter_words.push(words_in_chapter);
+    }
+
+    let total_words = chapter_words.iter().sum();
+    let unique_words = word_counts.len();
+    let hapax_count = word_counts.values().filter(|&&c| c == 1).count();
+    Ok(WordStats {
+        chapters: chapters.into_iter().map(|(chapter_id, title, _)| ChapterRef { chapter_id, title }).collect(),
+        chapter_words,
+        total_words,
+        unique_words,
+        hapax_count,
+        hapax_ratio: if unique_words == 0 { 0.0 } else { hapax_count as f64 / unique_words as f64 },
+        unigrams: top_ngrams(unigrams, total_words, top_n),
+        bigrams: top_ngrams(bigrams, total_words, top_n),
+    })
+}
+
+/// 导出为 CSV：每行一个词或二元组，之后每章一列次数
+pub fn word_stats_csv(stats: &WordStats) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let mut header = vec!["type".to_string(), "term".to_string(), "count".to_string(), "per_thousand".to_string()];
+    header.extend(stats.chapters.iter().map(|c| c.title.clone()));
+    writer.write_record(&header).map_err(|e| e.to_string())?;
+    for (kind, ngrams) in [("unigram", &stats.unigrams), ("bigram", &stats.bigrams)] {
+        for ngram in ngrams {
+            let mut row = vec![kind.to_string(), ngram.term.clone(), ngram.count.to_string(), format!("{:.2}", ngram.per_thousand)];
+            row.extend(ngram.by_chapter.iter().map(|c| c.to_string()));
+            writer.write_record(&row).map_err(|e| e.to_string())?;
+        }
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provenance::{record_chapter_change, record_generation};
+
+    #[test]
+    fn test_crutch_words_tracked_per_author() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES ('r1', 'p1', '林舟', 't0', 't0');
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES ('c1', 'p1', '第一章', '', 1, 't0', 't0');",
+        )
+        .unwrap();
+
+        let typed = "林舟突然站起来，突然又坐下。";
+        let generated = "林舟嘴角微微上扬，嘴角微微上扬，嘴角微微上扬。";
+        record_chapter_change(&conn, "c1", "", typed, None).unwrap();
+        record_generation(&conn, Some("p1"), ContentSource::AiContinue, "gpt-4o", generated).unwrap();
+        let content = format!("{}\n{}", typed, generated);
+        conn.execute("UPDATE chapters SET content = ? WHERE id = 'c1'", params![content]).unwrap();
+        record_chapter_change(&conn, "c1", typed, &content, None).unwrap();
+
+        let report = analyze(&conn, "p1", 10).unwrap();
+        assert!(report.type_token_ratio > 0.0 && report.type_token_ratio < 1.0);
+        let usage = |word: &str| report.crutch_words.iter().find(|c| c.word == word).unwrap();
+        assert_eq!((usage("突然").total, usage("突然").by_author[0].author.as_str()), (2, "作者"));
+        assert_eq!(usage("嘴角").by_author[0].author, "AI·gpt-4o");
+        assert_eq!(usage("微微").by_chapter, vec![3]);
+        assert!(report.overused_words.iter().all(|w| w.term != "林舟"));
+        assert!(report.overused_phrases.iter().any(|p| p.term.contains("嘴角")));
+
+        set_list(&conn, "p1", LIST_IGNORE, &["突然".to_string()]).unwrap();
+        let lists = set_list(&conn, "p1", LIST_CRUTCH, &["站起来".to_string(), " ".to_string()]).unwrap();
+        assert_eq!((lists.ignore.len(), lists.crutch.len()), (1, 1));
+        let report = analyze(&conn, "p1", 10).unwrap();
+        assert!(report.crutch_words.iter().all(|c| c.word != "突然"));
+        assert!(report.crutch_words.iter().any(|c| c.word == "站起来"));
+        assert!(set_list(&conn, "p1", "other", &[]).is_err());
+    }
+
+    #[test]
+    fn test_word_stats_and_csv() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '师兄回到宗门。师兄回到宗门。', 1, 't0', 't0'),
+                 ('c2', 'p1', '第二章, 下', '师兄离开宗门。', 2, 't0', 't0');",
+        )
+        .unwrap();
+
+        let stats = word_stats(&conn, "p1", None, 10).unwrap();
+        assert_eq!(stats.chapter_words, vec![6, 3]);
+        let top: Vec<(&str, &[usize])> = stats.unigrams.iter().take(2).map(|n| (n.term.as_str(), n.by_chapter.as_slice())).collect();
+        assert_eq!(top, vec![("宗门", &[2, 1][..]), ("师兄", &[2, 1][..])]);
+        assert_eq!((stats.bigrams[0].term.as_str(), stats.bigrams[0].count), ("回到宗门", 2));
+        assert_eq!((stats.unique_words, stats.hapax_count), (4, 1));
+
+        let csv = word_stats_csv(&stats).unwrap();
+        assert!(csv.starts_with("type,term,count,per_thousand,第一章,\"第二章, 下\"\n"));
+        assert!(csv.contains("unigram,宗门,3,333.33,2,1\n"));
+
+        let chapter = word_stats(&conn, "p1", Some("c2"), 10).unwrap();
+        assert_eq!((chapter.chapters.len(), chapter.total_words), (1, 3));
+    }
+}
@@ -0,0 +1,304 @@
+use crate::character_presence::{aliases_by_character, speaker};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// 每处禁用词扣的分
+const FORBIDDEN_PENALTY: f64 = 0.5;
+/// 句长超出范围扣的分
+const LENGTH_PENALTY: f64 = 0.25;
+/// 说了这么多句台词仍没用过口头禅时提示
+const CATCHPHRASE_MIN_LINES: usize = 10;
+
+/// 角色的说话风格，`diction` 是对用词的自由描述，只用于提示词
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VoiceProfile {
+    pub character_id: String,
+    pub diction: Option<String>,
+    pub catchphrases: Vec<String>,
+    /// 台词平均句长（字）的范围
+    pub min_sentence_length: Option<usize>,
+    pub max_sentence_length: Option<usize>,
+    pub forbidden_words: Vec<String>,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+/// 一句不符合说话风格的台词，kind 为 forbidden_word、too_long、too_short 或 catchphrase_missing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceIssue {
+    pub chapter_id: Option<String>,
+    pub chapter_title: Option<String>,
+    pub line: String,
+    pub kind: String,
+    pub detail: String,
+}
+
+/// 一个角色的台词与说话风格的吻合度，`score` 是各句得分（0~1）的平均，没有台词时为 None
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceCheck {
+    pub character_id: String,
+    pub name: String,
+    pub lines: usize,
+    pub score: Option<f64>,
+    pub catchphrase_uses: usize,
+    pub issues: Vec<VoiceIssue>,
+}
+
+pub fn get_profile(conn: &Connection, character_id: &str) -> Result<Option<VoiceProfile>, String> {
+    conn.query_row(
+        "SELECT character_id, diction, catchphrases, min_sentence_length, max_sentence_length, forbidden_words, updated_at
+         FROM character_voice_profiles WHERE character_id = ?",
+        params![character_id],
+        |row| {
+            Ok(VoiceProfile {
+                character_id: row.get(0)?,
+                diction: row.get(1)?,
+                catchphrases: serde_json::from_str(&row.get::<_, String>(2)?).unwrap_or_default(),
+                min_sentence_length: row.get::<_, Option<i64>>(3)?.map(|n| n as usize),
+                max_sentence_length: row.get::<_, Option<i64>>(4)?.map(|n| n as usize),
+                forbidden_words: serde_json::from_str(&row.get::<_, String>(5)?).unwrap_or_default(),
+                updated_at: row.get(6)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// 保存说话风格，口头禅和禁用词去掉空白和重复项
+pub fn save_profile(conn: &Connection, profile: &VoiceProfile) -> Result<VoiceProfile, String> {
+    let clean = |words: &[String]| {
+        let mut cleaned: Vec<String> = Vec::new();
+        for word in words.iter().map(|w| w.trim()).filter(|w| !w.is_empty()) {
+            if !cleaned.iter().any(|w| w == word) {
+                cleaned.push(word.to_string());
+            }
+        }
+        cleaned
+    };
+    if let (Some(min), Some(max)) = (profile.min_sentence_length, profile.max_sentence_length) {
+        if min > max {
+            return Err("最短句长不能大于最长句长".to_string());
+        }
+    }
+    let saved = VoiceProfile {
+        character_id: profile.character_id.clone(),
+        diction: profile.diction.as_deref().map(str::trim).filter(|d| !d.is_empty()).map(str::to_string),
+        catchphrases: clean(&profile.catchphrases),
+        min_sentence_length: profile.min_sentence_length,
+        max_sentence_length: profile.max_sentence_length,
+        forbidden_words: clean(&profile.forbidden_words),
+        updated_at: Utc::now().to_rfc3339(),
+    };
+    conn.execute(
+        "INSERT OR REPLACE INTO character_voice_profiles
+         (character_id, diction, catchphrases, min_sentence_length, max_sentence_length, forbidden_words, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+        params![
+            saved.character_id,
+            saved.diction,
+            serde_json::to_string(&saved.catchphrases).map_err(|e| e.to_string())?,
+            saved.min_sentence_length.map(|n| n as i64),
+            saved.max_sentence_length.map(|n| n as i64),
+            serde_json::to_string(&saved.forbidden_words).map_err(|e| e.to_string())?,
+            saved.updated_at,
+        ],
+    )
+    .map_err(|e| format!("Failed to save voice profile: {}", e))?;
+    Ok(saved)
+}
+
+/// 写进角色扮演和续写提示词的说话风格说明，没有设置任何项时为空
+pub fn prompt_section(profile: &VoiceProfile) -> String {
+    let mut rules = Vec::new();
+    if let Some(diction) = &profile.diction {
+        rules.push(format!("- 用词：{}", diction));
+    }
+    if !profile.catchphrases.is_empty() {
+        rules.push(format!("- 口头禅：{}（自然地使用，不必每句都说）", profile.catchphrases.join("、")));
+    }
+    match (profile.min_sentence_length, profile.max_sentence_length) {
+        (Some(min), Some(max)) => rules.push(format!("- 句子长度：一般{}到{}字", min, max)),
+        (Some(min), None) => rules.push(format!("- 句子长度：一般不少于{}字", min)),
+        (None, Some(max)) => rules.push(format!("- 句子长度：一般不超过{}字", max)),
+        (None, None) => {}
+    }
+    if !profile.forbidden_words.is_empty() {
+        rules.push(format!("- 绝不使用：{}", profile.forbidden_words.join("、")));
+    }
+    if rules.is_empty() {
+        return String::new();
+    }
+    format!("说话风格:\n{}", rules.join("\n"))
+}
+
+/// 一行中引号里的内容
+fn quoted(line: &str) -> String {
+    let mut speech = String::new();
+    let mut depth = 0;
+    for c in line.chars() {
+        match c {
+            '“' | '「' | '『' => depth += 1,
+            '”' | '」' | '』' => depth = (depth - 1).max(0),
+            _ if depth > 0 => speech.push(c),
+            _ => {}
+        }
+    }
+    speech
+}
+
+/// 台词的平均句长（字），标点不计
+fn average_sentence_length(speech: &str) -> usize {
+    let lengths: Vec<usize> = speech
+        .split(['。', '！', '？', '!', '?', '…', '；'])
+        .map(|s| s.chars().filter(|c| c.is_alphanumeric()).count())
+        .filter(|&n| n > 0)
+        .collect();
+    if lengths.is_empty() {
+        0
+    } else {
+        lengths.iter().sum::<usize>() / lengths.len()
+    }
+}
+
+/// 对照说话风格给一句台词打分，返回得分、发现的问题和是否用了口头禅
+fn score_line(speech: &str, profile: &VoiceProfile) -> (f64, Vec<(String, String)>, bool) {
+    let mut score: f64 = 1.0;
+    let mut issues = Vec::new();
+    for word in profile.forbidden_words.iter().filter(|w| speech.contains(w.as_str())) {
+        score -= FORBIDDEN_PENALTY;
+        issues.push(("forbidden_word".to_string(), format!("用了禁用词“{}”", word)));
+    }
+    let length = average_sentence_length(speech);
+    if profile.max_sentence_length.is_some_and(|max| length > max) {
+        score -= LENGTH_PENALTY;
+        issues.push(("too_long".to_string(), format!("平均句长{}字，超过{}字", length, profile.max_sentence_length.unwrap_or(0))));
+    } else if profile.min_sentence_length.is_some_and(|min| length < min) {
+        score -= LENGTH_PENALTY;
+        issues.push(("too_short".to_string(), format!("平均句长{}字，不到{}字", length, profile.min_sentence_length.unwrap_or(0))));
+    }
+    let catchphrase = profile.catchphrases.iter().any(|c| speech.contains(c.as_str()));
+    (score.max(0.0), issues, catchphrase)
+}
+
+/// 检查项目（或某一章）中归给设置了说话风格的角色的台词，`character_id` 指定时只看这个角色
+pub fn check_voices(
+    conn: &Connection,
+    project_id: &str,
+    chapter_id: Option<&str>,
+    character_id: Option<&str>,
+) -> Result<Vec<VoiceCheck>, String> {
+    let chapters: Vec<(String, String, String)> = conn
+        .prepare(
+            "SELECT id, title, content FROM chapters WHERE project_id = ?1 AND (?2 IS NULL OR id = ?2)
+             ORDER BY sort_order, created_at",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id, chapter_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get::<_, Option<String>>(2)?.unwrap_or_default()))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let characters: Vec<(String, String)> = conn
+        .prepare("SELECT id, name FROM characters WHERE project_id = ? AND merged_into IS NULL ORDER BY created_at")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut aliases = aliases_by_character(conn, project_id)?;
+    // 说话人要在全部角色中判断，只给设置了说话风格的角色打分
+    let terms: Vec<(String, Vec<String>)> = characters
+        .iter()
+        .map(|(id, name)| {
+            let mut names = vec![name.clone()];
+            names.extend(aliases.remove(id).unwrap_or_default());
+            (id.clone(), names)
+        })
+        .collect();
+
+    let mut checks = Vec::new();
+    for (id, name) in characters.iter().filter(|(id, _)| character_id.is_none_or(|c| c == id)) {
+        let Some(profile) = get_profile(conn, id)? else { continue };
+        let mut check = VoiceCheck {
+            character_id: id.clone(),
+            name: name.clone(),
+            lines: 0,
+            score: None,
+            catchphrase_uses: 0,
+            issues: Vec::new(),
+        };
+        let mut total = 0.0;
+        for (chapter_id, title, content) in &chapters {
+            for line in content.lines().filter(|l| speaker(l, &terms) == Some(id.as_str())) {
+                let speech = quoted(line);
+                if speech.trim().is_empty() {
+                    continue;
+                }
+                let (score, issues, catchphrase) = score_line(&speech, &profile);
+                check.lines += 1;
+                total += score;
+                check.catchphrase_uses += catchphrase as usize;
+                check.issues.extend(issues.into_iter().map(|(kind, detail)| VoiceIssue {
+                    chapter_id: Some(chapter_id.clone()),
+                    chapter_title: Some(title.clone()),
+                    line: speech.clone(),
+                    kind,
+                    detail,
+                }));
+            }
+        }
+        if check.lines > 0 {
+            check.score = Some(total / check.lines as f64);
+        }
+        if !profile.catchphrases.is_empty() && check.lines >= CATCHPHRASE_MIN_LINES && check.catchphrase_uses == 0 {
+            check.issues.push(VoiceIssue {
+                chapter_id: None,
+                chapter_title: None,
+                line: String::new(),
+                kind: "catchphrase_missing".to_string(),
+                detail: format!("{}句台词中没有用过口头禅", check.lines),
+            });
+        }
+        checks.push(check);
+    }
+    Ok(checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_voices_scores_attributed_lines() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name, created_at, updated_at) VALUES
+                 ('r1', 'p1', '林舟', 't0', 't0'), ('r2', 'p1', '沈青', 't0', 't0');
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '林舟说：“走吧，天快黑了。”\n沈青笑道：“小舟，你这人真是无趣得很。”\n林舟道：“老子不管那些乱七八糟的事情，反正我今天晚上一定要赶回城里去。”', 1, 't0', 't0');",
+        )
+        .unwrap();
+        let profile = VoiceProfile {
+            character_id: "r1".to_string(),
+            diction: Some(" 简短冷淡 ".to_string()),
+            catchphrases: vec!["走吧".to_string(), "走吧".to_string()],
+            max_sentence_length: Some(10),
+            forbidden_words: vec!["老子".to_string()],
+            ..Default::default()
+        };
+        let saved = save_profile(&conn, &profile).unwrap();
+        assert_eq!(saved.catchphrases, vec!["走吧"]);
+        assert!(prompt_section(&saved).contains("- 用词：简短冷淡\n"));
+
+        let checks = check_voices(&conn, "p1", None, None).unwrap();
+        assert_eq!(checks.len(), 1);
+        let check = &checks[0];
+        assert_eq!((check.lines, check.catchphrase_uses), (2, 1));
+        let kinds: Vec<&str> = check.issues.iter().map(|i| i.kind.as_str()).collect();
+        assert_eq!(kinds, vec!["forbidden_word", "too_long"]);
+        assert!((check.score.unwrap() - 0.625).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,198 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// 每批送给 AI 的设定总字数上限
+const BATCH_CHARS: usize = 6000;
+/// 单个条目最多取的字数，过长的条目截断
+const ENTRY_CHARS: usize = 1500;
+
+/// 参与检查的设定条目，`source` 为 worldview 或 knowledge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoreEntry {
+    pub id: String,
+    pub source: String,
+    pub category: String,
+    pub title: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoreEntryRef {
+    pub id: String,
+    pub source: String,
+    pub title: String,
+}
+
+/// 设定之间的矛盾，如两处不同的建国年份、相互冲突的法术规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoreContradiction {
+    pub entries: Vec<LoreEntryRef>,
+    /// 矛盾涉及的事项，如“建国年份”
+    pub topic: String,
+    pub description: String,
+    pub severity: String,
+    /// 建议的调和方式
+    pub suggestion: String,
+}
+
+/// 项目的世界观条目和知识库条目；由世界观同步过来的知识条目不重复收录
+pub fn load_entries(conn: &Connection, project_id: &str, categories: Option<&[String]>) -> Result<Vec<LoreEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, 'worldview', category, title, content FROM world_views WHERE project_id = ?1
+             UNION ALL
+             SELECT k.id, 'knowledge', k.entry_type, k.title, k.content FROM knowledge_entries k
+             WHERE k.project_id = ?1
+               AND NOT EXISTS (SELECT 1 FROM world_views w WHERE w.id = k.source_id)
+             ORDER BY 3, 4",
+        )
+        .map_err(|e| e.to_string())?;
+    let entries = stmt
+        .query_map(params![project_id], |row| {
+            Ok(LoreEntry { id: row.get(0)?, source: row.get(1)?, category: row.get(2)?, title: row.get(3)?, content: row.get(4)? })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(entries
+        .into_iter()
+        .filter(|e| !e.content.trim().is_empty())
+        .filter(|e| categories.is_none_or(|c| c.contains(&e.category)))
+        .collect())
+}
+
+fn excerpt(content: &str) -> String {
+    let content = content.trim();
+    match content.char_indices().nth(ENTRY_CHARS) {
+        Some((i, _)) => format!("{}……", &content[..i]),
+        None => content.to_string(),
+    }
+}
+
+/// 按分类分组，同类条目尽量放在同一批里；小分类合并成一批，超过字数上限时另起一批
+pub fn group_entries(entries: &[LoreEntry]) -> Vec<Vec<usize>> {
+    let mut batches: Vec<Vec<usize>> = Vec::new();
+    let mut size = 0;
+    let mut category: Option<&str> = None;
+    for (i, entry) in entries.iter().enumerate() {
+        let len = excerpt(&entry.content).chars().count() + entry.title.chars().count();
+        // 换分类时，若当前批已过半则另起一批，避免一个分类被拆到两批里
+        let new_category = category != Some(entry.category.as_str()) && size > BATCH_CHARS / 2;
+        if batches.is_empty() || size + len > BATCH_CHARS || new_category {
+            batches.push(Vec::new());
+            size = 0;
+        }
+        batches.last_mut().unwrap().push(i);
+        size += len;
+        category = Some(&entry.category);
+    }
+    batches.retain(|b| b.len() >= 2);
+    batches
+}
+
+pub fn build_prompt(entries: &[LoreEntry], batch: &[usize]) -> String {
+    let listed: Vec<String> = batch
+        .iter()
+        .enumerate()
+        .map(|(n, &i)| format!("[E{}]【{}】{}\n{}", n + 1, entries[i].category, entries[i].title, excerpt(&entries[i].content)))
+        .collect();
+    format!(
+        "下面是同一部小说的设定条目。找出条目之间相互矛盾的地方，例如同一事件的年份不同、同一规则的说法冲突、\
+         人物或势力的归属不一致。\n\n{}\n\n\
+         返回 JSON 数组：[{{\"entries\": [\"E1\", \"E3\"], \"topic\": \"矛盾涉及的事项\", \"description\": \"各条目分别怎么说\", \
+         \"severity\": \"high、medium 或 low\", \"suggestion\": \"如何修改使设定一致\"}}]。\
+         只返回确实矛盾的内容，补充说明或不同角度的描述不算矛盾；没有矛盾时返回 []。",
+        listed.join("\n\n")
+    )
+}
+
+/// 解析 AI 的回答，每条矛盾要引用本批中至少两个不同的条目
+pub fn parse_contradictions(entries: &[LoreEntry], batch: &[usize], response: &str) -> Vec<LoreContradiction> {
+    let start = response.find('[').unwrap_or(0);
+    let end = response.rfind(']').map(|i| i + 1).unwrap_or(response.len());
+    let items: Vec<serde_json::Value> = serde_json::from_str(&response[start..end]).unwrap_or_default();
+    let field = |item: &serde_json::Value, key: &str| item.get(key).and_then(|v| v.as_str()).unwrap_or_default().trim().to_string();
+
+    let mut contradictions = Vec::new();
+    for item in &items {
+        let mut refs: Vec<usize> = item
+            .get("entries")
+            .and_then(|v| v.as_array())
+            .map(|codes| {
+                codes
+                    .iter()
+                    .filter_map(|c| c.as_str()?.trim().trim_start_matches(['E', 'e']).parse::<usize>().ok())
+                    .filter_map(|n| batch.get(n.checked_sub(1)?).copied())
+                    .collect()
+            })
+            .unwrap_or_default();
+        refs.sort_unstable();
+        refs.dedup();
+        let description = field(item, "description");
+        if refs.len() < 2 || description.is_empty() {
+            continue;
+        }
+        let severity = field(item, "severity");
+        contradictions.push(LoreContradiction {
+            entries: refs
+                .iter()
+                .map(|&i| LoreEntryRef { id: entries[i].id.clone(), source: entries[i].source.clone(), title: entries[i].title.clone() })
+                .collect(),
+            topic: field(item, "topic"),
+            description,
+            severity: if ["high", "medium", "low"].contains(&severity.as_str()) { severity } else { "medium".to_string() },
+            suggestion: field(item, "suggestion"),
+        });
+    }
+    contradictions
+}
+
+/// 逐批让 AI 检查设定矛盾
+pub async fn check_with_ai(service: &crate::ai::AIService, model_id: &str, entries: &[LoreEntry]) -> Result<Vec<LoreContradiction>, String> {
+    let system_prompt = "你是一位严谨的小说设定编辑，负责核对世界观设定是否自洽。只返回 JSON 数组，不要包含markdown代码块标记。";
+    let mut contradictions = Vec::new();
+    for batch in group_entries(entries) {
+        let response = service.complete(model_id, system_prompt, &build_prompt(entries, &batch)).await?;
+        contradictions.extend(parse_contradictions(entries, &batch, &response));
+    }
+    Ok(contradictions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_group_and_parse() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO world_views (id, project_id, category, title, content, created_at, updated_at) VALUES
+                 ('w1', 'p1', 'history', '大夏建国', '大夏建国于天启元年。', 't0', 't0'),
+                 ('w2', 'p1', 'magic', '灵脉', '灵脉每百年枯竭一次。', 't0', 't0'),
+                 ('w3', 'p1', 'magic', '空白', '  ', 't0', 't0');
+             INSERT INTO knowledge_entries (id, project_id, entry_type, title, content, source_type, source_id, created_at, updated_at) VALUES
+                 ('k1', 'p1', 'history', '开国皇帝', '太祖于天启三年登基建国。', 'manual', NULL, 't0', 't0'),
+                 ('k2', 'p1', 'magic', '灵脉', '灵脉每百年枯竭一次。', 'worldview', 'w2', 't0', 't0');",
+        )
+        .unwrap();
+
+        let entries = load_entries(&conn, "p1", None).unwrap();
+        assert_eq!(entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["w1", "k1", "w2"]);
+        assert_eq!(load_entries(&conn, "p1", Some(&["magic".to_string()])).unwrap().len(), 1);
+
+        let batches = group_entries(&entries);
+        assert_eq!(batches, vec![vec![0, 1, 2]]);
+        let prompt = build_prompt(&entries, &batches[0]);
+        assert!(prompt.contains("[E2]【history】开国皇帝"));
+
+        let response = r#"```json
+[{"entries": ["E1", "E2"], "topic": "建国年份", "description": "一处说天启元年，一处说天启三年", "severity": "high", "suggestion": "统一为天启元年"},
+ {"entries": ["E3", "E3"], "topic": "灵脉", "description": "自相矛盾", "severity": "low", "suggestion": ""},
+ {"entries": ["E1", "E9"], "topic": "无效", "description": "引用不存在的条目", "severity": "low", "suggestion": ""}]
+```"#;
+        let found = parse_contradictions(&entries, &batches[0], response);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["w1", "k1"]);
+        assert_eq!(found[0].suggestion, "统一为天启元年");
+    }
+}
@@ -0,0 +1,46 @@
+use crate::logger::Logger;
+use crate::worldview_conflicts;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// 让 AI 找出世界观和知识库条目之间的矛盾；`categories` 为空时检查全部分类
+#[tauri::command]
+pub async fn check_worldview_contradictions(
+    app: AppHandle,
+    project_id: String,
+    categories: Option<Vec<String>>,
+    model_id: Option<String>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("worldview_conflicts");
+    logger.info(&format!("Checking worldview contradictions for project {}", project_id));
+
+    let entries = {
+        let db_path = get_db_path(&app)?;
+        let conn = crate::database::get_connection(&db_path)
+            .map_err(|e| format!("Failed to get database connection: {}", e))?;
+        worldview_conflicts::load_entries(&conn, &project_id, categories.as_deref())?
+    };
+
+    let ai_service = app.state::<std::sync::Arc<tokio::sync::RwLock<crate::ai::AIService>>>();
+    let service = ai_service.read().await;
+    let model_id = model_id.unwrap_or_else(|| "glm-4-flash".to_string());
+    let contradictions = worldview_conflicts::check_with_ai(&service, &model_id, &entries).await.map_err(|e| {
+        logger.error(&format!("Failed to check worldview contradictions: {}", e));
+        e
+    })?;
+
+    serde_json::to_string(&contradictions).map_err(|e| e.to_string())
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
@@ -0,0 +1,223 @@
+use crate::snapshot_store;
+use crate::text_diff::{self, ParagraphDiff};
+use crate::version_control::{FieldChange, WorldViewSnapshot};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// 世界观条目被修改或删除前的一个版本，内容存在快照数据块中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldViewRevision {
+    pub id: String,
+    pub worldview_id: String,
+    pub project_id: String,
+    pub title: String,
+    /// 如“修改前”“删除前”“恢复前”
+    pub note: Option<String>,
+    pub created_at: String,
+}
+
+/// 两个版本之间的差异，正文逐段对比
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldViewRevisionDiff {
+    pub worldview_id: String,
+    pub field_changes: Vec<FieldChange>,
+    pub paragraphs: Vec<ParagraphDiff>,
+    pub inserted_chars: usize,
+    pub deleted_chars: usize,
+}
+
+fn current_state(conn: &Connection, worldview_id: &str) -> Result<Option<(String, WorldViewSnapshot)>, String> {
+    conn.query_row(
+        "SELECT project_id, id, title, category, content, tags, status FROM world_views WHERE id = ?",
+        params![worldview_id],
+        |row| {
+            Ok((
+                row.get(0)?,
+                WorldViewSnapshot {
+                    id: row.get(1)?,
+                    name: row.get(2)?,
+                    category: row.get(3)?,
+                    description: row.get(4)?,
+                    tags: row.get(5)?,
+                    status: row.get(6)?,
+                },
+            ))
+        },
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// 保存条目当前的内容，与最近一个版本相同时不重复保存；条目不存在时返回 None
+pub fn record_revision(conn: &Connection, worldview_id: &str, note: Option<&str>) -> Result<Option<WorldViewRevision>, String> {
+    let Some((project_id, state)) = current_state(conn, worldview_id)? else {
+        return Ok(None);
+    };
+    let hash = snapshot_store::store_item(conn, &state)?;
+    let latest: Option<String> = conn
+        .query_row(
+            "SELECT blob_hash FROM worldview_revisions WHERE worldview_id = ? ORDER BY created_at DESC, rowid DESC LIMIT 1",
+            params![worldview_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if latest.as_deref() == Some(hash.as_str()) {
+        return Ok(None);
+    }
+    let revision = WorldViewRevision {
+        id: uuid::Uuid::new_v4().to_string(),
+        worldview_id: worldview_id.to_string(),
+        project_id,
+        title: state.name,
+        note: note.map(str::to_string),
+        created_at: Utc::now().to_rfc3339(),
+    };
+    conn.execute(
+        "INSERT INTO worldview_revisions (id, worldview_id, project_id, blob_hash, title, note, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        params![revision.id, revision.worldview_id, revision.project_id, hash, revision.title, revision.note, revision.created_at],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(Some(revision))
+}
+
+const COLUMNS: &str = "id, worldview_id, project_id, title, note, created_at";
+
+fn read_revision(row: &rusqlite::Row) -> rusqlite::Result<WorldViewRevision> {
+    Ok(WorldViewRevision {
+        id: row.get(0)?,
+        worldview_id: row.get(1)?,
+        project_id: row.get(2)?,
+        title: row.get(3)?,
+        note: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+/// 条目的历史版本，最新的在前
+pub fn list_revisions(conn: &Connection, worldview_id: &str) -> Result<Vec<WorldViewRevision>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM worldview_revisions WHERE worldview_id = ? ORDER BY created_at DESC, rowid DESC",
+            COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let revisions = stmt.query_map(params![worldview_id], read_revision).map_err(|e| e.to_string())?;
+    revisions.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// 已删除条目各自的最后一个版本，用于找回
+pub fn deleted_world_views(conn: &Connection, project_id: &str) -> Result<Vec<WorldViewRevision>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM worldview_revisions r
+             WHERE r.project_id = ?1 AND NOT EXISTS (SELECT 1 FROM world_views w WHERE w.id = r.worldview_id)
+               AND r.rowid = (SELECT rowid FROM worldview_revisions WHERE worldview_id = r.worldview_id
+                              ORDER BY created_at DESC, rowid DESC LIMIT 1)
+             ORDER BY r.created_at DESC",
+            COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let revisions = stmt.query_map(params![project_id], read_revision).map_err(|e| e.to_string())?;
+    revisions.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn load_revision(conn: &Connection, revision_id: &str) -> Result<(String, WorldViewSnapshot), String> {
+    let (project_id, hash): (String, String) = conn
+        .query_row(
+            "SELECT project_id, blob_hash FROM worldview_revisions WHERE id = ?",
+            params![revision_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("版本不存在: {}", revision_id))?;
+    Ok((project_id, snapshot_store::load_item(conn, &hash)?))
+}
+
+fn field_change(changes: &mut Vec<FieldChange>, field: &str, old: Option<&str>, new: Option<&str>) {
+    if old != new {
+        changes.push(FieldChange {
+            field: field.to_string(),
+            old_value: old.map(str::to_string),
+            new_value: new.map(str::to_string),
+        });
+    }
+}
+
+/// 对比某个版本与另一个版本，`against` 为空时与条目当前内容对比（条目已删除时视为空）
+pub fn diff_revision(conn: &Connection, revision_id: &str, against: Option<&str>) -> Result<WorldViewRevisionDiff, String> {
+    let (_, from) = load_revision(conn, revision_id)?;
+    let to = match against {
+        Some(other) => Some(load_revision(conn, other)?.1),
+        None => current_state(conn, &from.id)?.map(|(_, state)| state),
+    };
+    let mut field_changes = Vec::new();
+    field_change(&mut field_changes, "title", Some(&from.name), to.as_ref().map(|t| t.name.as_str()));
+    field_change(&mut field_changes, "category", Some(&from.category), to.as_ref().map(|t| t.category.as_str()));
+    field_change(&mut field_changes, "tags", from.tags.as_deref(), to.as_ref().and_then(|t| t.tags.as_deref()));
+    field_change(&mut field_changes, "status", from.status.as_deref(), to.as_ref().and_then(|t| t.status.as_deref()));
+    let paragraphs = text_diff::diff_paragraphs(&from.description, to.as_ref().map(|t| t.description.as_str()).unwrap_or(""));
+    let (inserted_chars, deleted_chars) = text_diff::change_counts(&paragraphs);
+    Ok(WorldViewRevisionDiff { worldview_id: from.id, field_changes, paragraphs, inserted_chars, deleted_chars })
+}
+
+/// 把条目恢复为某个版本，已删除的条目会重新创建；恢复前的内容另存为一个版本，可以撤销
+pub fn restore_revision(conn: &Connection, revision_id: &str) -> Result<WorldViewSnapshot, String> {
+    let (project_id, state) = load_revision(conn, revision_id)?;
+    record_revision(conn, &state.id, Some("恢复前"))?;
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO world_views (id, project_id, category, title, content, tags, status, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)
+         ON CONFLICT(id) DO UPDATE SET category = excluded.category, title = excluded.title, content = excluded.content,
+             tags = excluded.tags, status = excluded.status, updated_at = excluded.updated_at",
+        params![state.id, project_id, state.category, state.name, state.description, state.tags, state.status, now],
+    )
+    .map_err(|e| e.to_string())?;
+    crate::tokenizer::add_words(&[&state.name]);
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_revisions_diff_and_restore_deleted_entry() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO world_views (id, project_id, category, title, content, created_at, updated_at)
+                 VALUES ('w1', 'p1', 'magic', '灵脉', '灵脉贯穿九州。\n\n每百年枯竭一次。', 't0', 't0');",
+        )
+        .unwrap();
+
+        let first = record_revision(&conn, "w1", Some("修改前")).unwrap().unwrap();
+        assert!(record_revision(&conn, "w1", Some("修改前")).unwrap().is_none());
+        conn.execute("UPDATE world_views SET title = '地脉', content = '灵脉贯穿九州。\n\n永不枯竭。' WHERE id = 'w1'", [])
+            .unwrap();
+
+        let diff = diff_revision(&conn, &first.id, None).unwrap();
+        assert_eq!(diff.field_changes.len(), 1);
+        assert_eq!(diff.field_changes[0].new_value.as_deref(), Some("地脉"));
+        assert!(diff.inserted_chars > 0 && diff.deleted_chars > 0);
+
+        record_revision(&conn, "w1", Some("删除前")).unwrap().unwrap();
+        conn.execute("DELETE FROM world_views WHERE id = 'w1'", []).unwrap();
+        let deleted = deleted_world_views(&conn, "p1").unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].title, "地脉");
+
+        restore_revision(&conn, &first.id).unwrap();
+        let (title, content): (String, String) = conn
+            .query_row("SELECT title, content FROM world_views WHERE id = 'w1'", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!((title.as_str(), content.as_str()), ("灵脉", "灵脉贯穿九州。\n\n每百年枯竭一次。"));
+        assert_eq!(list_revisions(&conn, "w1").unwrap().len(), 2);
+
+        // 清理无用数据块时保留版本引用的块
+        snapshot_store::remove_orphan_chunks(&conn).unwrap();
+        assert_eq!(diff_revision(&conn, &first.id, None).unwrap().field_changes.len(), 0);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::text_diff;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const STATUS_RUNNING: &str = "running";
+pub const STATUS_COMPLETED: &str = "completed";
+
+/// 一次限时写作。进行中时 `words_written`、`words_deleted` 为开始以来的实时数字
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WritingSprint {
+    pub id: String,
+    pub user_id: String,
+    pub project_id: String,
+    pub planned_minutes: i64,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub status: String,
+    pub words_written: i64,
+    pub words_deleted: i64,
+}
+
+impl WritingSprint {
+    /// 实际用时（分钟），进行中时按 `now` 计算
+    pub fn minutes(&self, now: DateTime<Utc>) -> f64 {
+        let end = self.ended_at.as_deref().and_then(|e| DateTime::parse_from_rfc3339(e).ok()).map(|e| e.with_timezone(&Utc));
+        let start = DateTime::parse_from_rfc3339(&self.started_at).map(|s| s.with_timezone(&Utc)).unwrap_or(now);
+        (end.unwrap_or(now) - start).num_seconds().max(0) as f64 / 60.0
+    }
+
+    pub fn words_per_minute(&self, now: DateTime<Utc>) -> f64 {
+        let minutes = self.minutes(now);
+        if minutes > 0.0 {
+            self.words_written as f64 / minutes
+        } else {
+            0.0
+        }
+    }
+}
+
+/// 结束冲刺的结果，`new_best` 列出这次刷新的个人纪录：words、speed、duration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SprintResult {
+    pub sprint: WritingSprint,
+    pub minutes: f64,
+    pub words_per_minute: f64,
+    pub new_best: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PersonalBests {
+    pub total_sprints: usize,
+    pub total_words: i64,
+    pub most_words: Option<WritingSprint>,
+    pub fastest: Option<WritingSprint>,
+    pub fastest_words_per_minute: f64,
+    pub longest: Option<WritingSprint>,
+    pub longest_minutes: f64,
+}
+
+const SPRINT_COLUMNS: &str = "id, user_id, project_id, planned_minutes, started_at, ended_at, status, words_written, words_deleted";
+
+fn sprint_from_row(row: &rusqlite::Row) -> rusqlite::Result<WritingSprint> {
+    Ok(WritingSprint {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        project_id: row.get(2)?,
+        planned_minutes: row.get(3)?,
+        started_at: row.get(4)?,
+        ended_at: row.get(5)?,
+        status: row.get(6)?,
+        words_written: row.get(7)?,
+        words_deleted: row.get(8)?,
+    })
+}
+
+fn load_sprint(conn: &Connection, sprint_id: &str) -> Result<(WritingSprint, Option<String>), String> {
+    conn.query_row(
+        &format!("SELECT {}, baseline_json FROM writing_sprints WHERE id = ?", SPRINT_COLUMNS),
+        params![sprint_id],
+        |row| Ok((sprint_from_row(row)?, row.get(9)?)),
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Sprint not found: {}", sprint_id))
+}
+
+fn chapter_contents(conn: &Connection, project_id: &str) -> Result<HashMap<String, String>, String> {
+    conn.prepare("SELECT id, COALESCE(content, '') FROM chapters WHERE project_id = ?")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 和开始时的正文比较，统计插入和删除的字数；开始后新建的章节按全部插入计
+fn count_changes(conn: &Connection, project_id: &str, baseline: &HashMap<String, String>) -> Result<(i64, i64), String> {
+    let (mut written, mut deleted) = (0, 0);
+    for (chapter_id, content) in chapter_contents(conn, project_id)? {
+        let before = baseline.get(&chapter_id).map(String::as_str).unwrap_or("");
+        if before != content {
+            let (inserted, removed) = text_diff::change_counts(&text_diff::diff_paragraphs(before, &content));
+            written += inserted as i64;
+            deleted += removed as i64;
+        }
+    }
+    Ok((written, deleted))
+}
+
+/// 开始冲刺，记下项目各章当前正文作为比较基准。同一用户同时只能有一个进行中的冲刺
+pub fn start_sprint(
+    conn: &Connection,
+    user_id: &str,
+    project_id: &str,
+    planned_minutes: i64,
+    now: DateTime<Utc>,
+) -> Result<WritingSprint, String> {
+    if planned_minutes <= 0 {
+        return Err("Sprint length must be positive".to_string());
+    }
+    let running: Option<String> = conn
+        .query_row("SELECT id FROM writing_sprints WHERE user_id = ? AND status = ?", params![user_id, STATUS_RUNNING], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some(running) = running {
+        return Err(format!("Sprint {} is still running", running));
+    }
+
+    let baseline = serde_json::to_string(&chapter_contents(conn, project_id)?).map_err(|e| e.to_string())?;
+    let sprint = WritingSprint {
+        id: uuid::Uuid::new_v4().to_string(),
+        user_id: user_id.to_string(),
+        project_id: project_id.to_string(),
+        planned_minutes,
+        started_at: now.to_rfc3339(),
+        ended_at: None,
+        status: STATUS_RUNNING.to_string(),
+        words_written: 0,
+        words_deleted: 0,
+    };
+    conn.execute(
+        &format!("INSERT INTO writing_sprints ({}, baseline_json) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)", SPRINT_COLUMNS),
+        params![
+            sprint.id,
+            sprint.user_id,
+            sprint.project_id,
+            sprint.planned_minutes,
+            sprint.started_at,
+            sprint.ended_at,
+            sprint.status,
+            0,
+            0,
+            baseline,
+        ],
+    )
+    .map_err(|e| format!("Failed to start sprint: {}", e))?;
+    Ok(sprint)
+}
+
+/// 进行中冲刺的实时字数；已结束的冲刺原样返回
+pub fn sprint_progress(conn: &Connection, sprint_id: &str) -> Result<WritingSprint, String> {
+    let (mut sprint, baseline) = load_sprint(conn, sprint_id)?;
+    if let Some(baseline) = baseline.filter(|_| sprint.status == STATUS_RUNNING) {
+        let baseline: HashMap<String, String> = serde_json::from_str(&baseline).map_err(|e| e.to_string())?;
+        (sprint.words_written, sprint.words_deleted) = count_changes(conn, &sprint.project_id, &baseline)?;
+    }
+    Ok(sprint)
+}
+
+/// 结束冲刺：保存字数，丢弃基准正文，并和以往的冲刺比较个人纪录
+pub fn stop_sprint(conn: &Connection, sprint_id: &str, now: DateTime<Utc>) -> Result<SprintResult, String> {
+    let mut sprint = sprint_progress(conn, sprint_id)?;
+    if sprint.status != STATUS_RUNNING {
+        return Err(format!("Sprint {} is not running", sprint_id));
+    }
+    let previous = personal_bests(conn, &sprint.user_id, now)?;
+
+    sprint.ended_at = Some(now.to_rfc3339());
+    sprint.status = STATUS_COMPLETED.to_string();
+    conn.execute(
+        "UPDATE writing_sprints SET ended_at = ?, status = ?, words_written = ?, words_deleted = ?, baseline_json = NULL WHERE id = ?",
+        params![sprint.ended_at, sprint.status, sprint.words_written, sprint.words_deleted, sprint.id],
+    )
+    .map_err(|e| format!("Failed to stop sprint: {}", e))?;
+
+    let (minutes, words_per_minute) = (sprint.minutes(now), sprint.words_per_minute(now));
+    let mut new_best = Vec::new();
+    if sprint.words_written > 0 {
+        if previous.most_words.as_ref().is_none_or(|best| sprint.words_written > best.words_written) {
+            new_best.push("words".to_string());
+        }
+        if previous.fastest.is_none() || words_per_minute > previous.fastest_words_per_minute {
+            new_best.push("speed".to_string());
+        }
+        if previous.longest.is_none() || minutes > previous.longest_minutes {
+            new_best.push("duration".to_string());
+        }
+    }
+    Ok(SprintResult { sprint, minutes, words_per_minute, new_best })
+}
+
+/// 用户的冲刺记录，最近的在前；指定项目时只看该项目
+pub fn sprint_history(conn: &Connection, user_id: &str, project_id: Option<&str>, limit: Option<usize>) -> Result<Vec<WritingSprint>, String> {
+    conn.prepare(&format!(
+        "SELECT {} FROM writing_sprints WHERE user_id = ?1 AND (?2 IS NULL OR project_id = ?2) ORDER BY started_at DESC LIMIT ?3",
+        SPRINT_COLUMNS
+    ))
+    .map_err(|e| e.to_string())?
+    .query_map(params![user_id, project_id, limit.map_or(-1, |l| l as i64)], sprint_from_row)
+    .map_err(|e| e.to_string())?
+    .collect::<Result<_, _>>()
+    .map_err(|e| e.to_string())
+}
+
+/// 用户已完成冲刺中字数最多、速度最快、用时最长的各一次
+pub fn personal_bests(conn: &Connection, user_id: &str, now: DateTime<Utc>) -> Result<PersonalBests, String> {
+    let completed: Vec<WritingSprint> = sprint_history(conn, user_id, None, None)?
+        .into_iter()
+        .filter(|s| s.status == STATUS_COMPLETED && s.words_written > 0)
+        .collect();
+    let mut bests = PersonalBests {
+        total_sprints: completed.len(),
+        total_words: completed.iter().map(|s| s.words_written).sum(),
+        ..Default::default()
+    };
+    for sprint in completed {
+        if bests.most_words.as_ref().is_none_or(|best| sprint.words_written > best.words_written) {
+            bests.most_words = Some(sprint.clone());
+        }
+        let (speed, minutes) = (sprint.words_per_minute(now), sprint.minutes(now));
+        if bests.fastest.is_none() || speed > bests.fastest_words_per_minute {
+            bests.fastest = Some(sprint.clone());
+            bests.fastest_words_per_minute = speed;
+        }
+        if bests.longest.is_none() || minutes > bests.longest_minutes {
+            bests.longest = Some(sprint);
+            bests.longest_minutes = minutes;
+        }
+    }
+    Ok(bests)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_sprint_counts_words_from_chapter_diffs() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '雨下了一整夜。', 1, 't0', 't0');",
+        )
+        .unwrap();
+        let start = DateTime::parse_from_rfc3339("2024-06-12T08:00:00Z").unwrap().with_timezone(&Utc);
+
+        let first = start_sprint(&conn, "u1", "p1", 25, start).unwrap();
+        assert!(start_sprint(&conn, "u1", "p1", 25, start).is_err());
+        conn.execute_batch(
+            "UPDATE chapters SET content = '雨下了一整夜。\n天亮了，他推开门。' WHERE id = 'c1';
+             INSERT INTO chapters (id, project_id, title, content, sort_order, created_at, updated_at) VALUES
+                 ('c2', 'p1', '第二章', '新的一天。', 2, 't0', 't0');",
+        )
+        .unwrap();
+        assert_eq!(sprint_progress(&conn, &first.id).unwrap().words_written, 14);
+
+        let result = stop_sprint(&conn, &first.id, start + Duration::minutes(20)).unwrap();
+        assert_eq!((result.sprint.words_written, result.minutes), (14, 20.0));
+        assert_eq!(result.new_best, vec!["words", "speed", "duration"]);
+        assert!(stop_sprint(&conn, &first.id, start + Duration::minutes(21)).is_err());
+
+        // 第二次写得少但更快
+        let later = start + Duration::hours(1);
+        let second = start_sprint(&conn, "u1", "p1", 5, later).unwrap();
+        conn.execute("UPDATE chapters SET content = '新的一天开始了。' WHERE id = 'c2'", []).unwrap();
+        let result = stop_sprint(&conn, &second.id, later + Duration::minutes(1)).unwrap();
+        assert_eq!(result.sprint.words_written, 3);
+        assert_eq!(result.new_best, vec!["speed"]);
+
+        let bests = personal_bests(&conn, "u1", later).unwrap();
+        assert_eq!((bests.total_sprints, bests.total_words), (2, 17));
+        assert_eq!(bests.most_words.unwrap().id, first.id);
+        assert_eq!(sprint_history(&conn, "u1", Some("p1"), Some(10)).unwrap()[0].id, second.id);
+    }
+}
@@ -0,0 +1,219 @@
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 计算写作速度时回看的天数
+const VELOCITY_DAYS: i64 = 14;
+/// 仪表盘显示的历史天数
+const HISTORY_DAYS: i64 = 30;
+
+/// 项目的写作目标，0 或 None 表示未设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WritingGoals {
+    pub daily_words: i64,
+    pub weekly_words: i64,
+    pub target_total_words: Option<i64>,
+    /// 截稿日期，YYYY-MM-DD
+    pub deadline: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyStat {
+    pub date: String,
+    pub words_added: i64,
+    pub words_deleted: i64,
+    pub net: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WritingDashboard {
+    pub goals: WritingGoals,
+    pub today_words: i64,
+    pub week_words: i64,
+    pub daily_progress: Option<f32>,
+    pub weekly_progress: Option<f32>,
+    /// 连续达成每日目标的天数（没有每日目标时按有新增字数计），今天还没达成时从昨天算起
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    /// 最近 14 天平均每天净增字数
+    pub velocity: f32,
+    pub total_words: i64,
+    pub projected_completion: Option<String>,
+    /// 设置了截稿日期时，按当前速度能否如期完成
+    pub on_track: Option<bool>,
+    pub history: Vec<DailyStat>,
+}
+
+fn date_key(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// 章节保存后调用，按字数变化累计到当天的统计
+pub fn record_delta(conn: &Connection, project_id: &str, date: NaiveDate, old_words: i64, new_words: i64) -> Result<(), String> {
+    let delta = new_words - old_words;
+    if delta == 0 {
+        return Ok(());
+    }
+    let (added, deleted) = if delta > 0 { (delta, 0) } else { (0, -delta) };
+    conn.execute(
+        "INSERT INTO writing_stats (project_id, date, words_added, words_deleted, updated_at) VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT(project_id, date) DO UPDATE SET words_added = words_added + excluded.words_added,
+             words_deleted = words_deleted + excluded.words_deleted, updated_at = excluded.updated_at",
+        params![project_id, date_key(date), added, deleted, Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| format!("Failed to record writing stats: {}", e))?;
+    Ok(())
+}
+
+pub fn get_goals(conn: &Connection, project_id: &str) -> Result<WritingGoals, String> {
+    conn.query_row(
+        "SELECT daily_words, weekly_words, target_total_words, deadline FROM writing_goals WHERE project_id = ?",
+        params![project_id],
+        |row| {
+            Ok(WritingGoals {
+                daily_words: row.get(0)?,
+                weekly_words: row.get(1)?,
+                target_total_words: row.get(2)?,
+                deadline: row.get(3)?,
+            })
+        },
+    )
+    .optional()
+    .map(Option::unwrap_or_default)
+    .map_err(|e| e.to_string())
+}
+
+pub fn set_goals(conn: &Connection, project_id: &str, goals: &WritingGoals) -> Result<WritingGoals, String> {
+    if let Some(deadline) = &goals.deadline {
+        NaiveDate::parse_from_str(deadline, "%Y-%m-%d").map_err(|e| format!("Invalid deadline {}: {}", deadline, e))?;
+    }
+    conn.execute(
+        "INSERT OR REPLACE INTO writing_goals (project_id, daily_words, weekly_words, target_total_words, deadline, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?)",
+        params![
+            project_id,
+            goals.daily_words.max(0),
+            goals.weekly_words.max(0),
+            goals.target_total_words,
+            goals.deadline,
+            Utc::now().to_rfc3339(),
+        ],
+    )
+    .map_err(|e| format!("Failed to save writing goals: {}", e))?;
+    get_goals(conn, project_id)
+}
+
+/// 每天的统计，按日期排序
+pub fn get_history(conn: &Connection, project_id: &str) -> Result<Vec<DailyStat>, String> {
+    conn.prepare("SELECT date, words_added, words_deleted FROM writing_stats WHERE project_id = ? ORDER BY date")
+        .map_err(|e| e.to_string())?
+        .query_map(params![project_id], |row| {
+            let (added, deleted): (i64, i64) = (row.get(1)?, row.get(2)?);
+            Ok(DailyStat { date: row.get(0)?, words_added: added, words_deleted: deleted, net: added - deleted })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 写作仪表盘：今日和本周（周一起）进度、连续天数、写作速度和预计完成日期
+pub fn dashboard(conn: &Connection, project_id: &str, today: NaiveDate) -> Result<WritingDashboard, String> {
+    let goals = get_goals(conn, project_id)?;
+    let history = get_history(conn, project_id)?;
+    let net: HashMap<&str, i64> = history.iter().map(|d| (d.date.as_str(), d.net)).collect();
+    let net_on = |date: NaiveDate| net.get(date_key(date).as_str()).copied().unwrap_or(0);
+    let met = |words: i64| if goals.daily_words > 0 { words >= goals.daily_words } else { words > 0 };
+
+    let today_words = net_on(today);
+    let week_start = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let week_words: i64 = (0..=(today - week_start).num_days()).map(|i| net_on(week_start + Duration::days(i))).sum();
+
+    let mut day = if met(today_words) { today } else { today - Duration::days(1) };
+    let mut current_streak = 0;
+    while met(net_on(day)) {
+        current_streak += 1;
+        day -= Duration::days(1);
+    }
+    let (mut longest_streak, mut run, mut previous): (u32, u32, Option<NaiveDate>) = (0, 0, None);
+    for stat in &history {
+        let Ok(date) = NaiveDate::parse_from_str(&stat.date, "%Y-%m-%d") else { continue };
+        if !met(stat.net) {
+            run = 0;
+            continue;
+        }
+        run = if previous == Some(date - Duration::days(1)) && run > 0 { run + 1 } else { 1 };
+        longest_streak = longest_streak.max(run);
+        previous = Some(date);
+    }
+
+    let velocity = (0..VELOCITY_DAYS).map(|i| net_on(today - Duration::days(i))).sum::<i64>() as f32 / VELOCITY_DAYS as f32;
+    let total_words: i64 = conn
+        .query_row("SELECT COALESCE(SUM(word_count), 0) FROM chapters WHERE project_id = ?", params![project_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    let projected = match goals.target_total_words {
+        Some(target) if target > total_words && velocity > 0.0 => {
+            Some(today + Duration::days(((target - total_words) as f32 / velocity).ceil() as i64))
+        }
+        Some(target) if target <= total_words => Some(today),
+        _ => None,
+    };
+    let deadline = goals.deadline.as_deref().and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+    let on_track = deadline.map(|deadline| projected.is_some_and(|p| p <= deadline));
+
+    let progress = |words: i64, goal: i64| (goal > 0).then(|| words as f32 / goal as f32);
+    let since = date_key(today - Duration::days(HISTORY_DAYS - 1));
+    Ok(WritingDashboard {
+        daily_progress: progress(today_words, goals.daily_words),
+        weekly_progress: progress(week_words, goals.weekly_words),
+        goals,
+        today_words,
+        week_words,
+        current_streak,
+        longest_streak,
+        velocity,
+        total_words,
+        projected_completion: projected.map(date_key),
+        on_track,
+        history: history.into_iter().filter(|d| d.date >= since).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streaks_velocity_and_projection() {
+        let (_dir, conn) = crate::test_support::project_db();
+        conn.execute_batch(
+            "INSERT INTO chapters (id, project_id, title, content, word_count, sort_order, created_at, updated_at) VALUES
+                 ('c1', 'p1', '第一章', '', 7000, 1, 't0', 't0');",
+        )
+        .unwrap();
+
+        // 周三（6 月 12 日）查看：周一、周二各写 1000 字，上周五写 1000 字后周末中断
+        let day = |d: u32| NaiveDate::from_ymd_opt(2024, 6, d).unwrap();
+        record_delta(&conn, "p1", day(7), 0, 1000).unwrap();
+        record_delta(&conn, "p1", day(10), 0, 1200).unwrap();
+        record_delta(&conn, "p1", day(10), 1200, 1000).unwrap();
+        record_delta(&conn, "p1", day(11), 1000, 2000).unwrap();
+        record_delta(&conn, "p1", day(12), 2000, 2300).unwrap();
+        set_goals(
+            &conn,
+            "p1",
+            &WritingGoals { daily_words: 1000, weekly_words: 5000, target_total_words: Some(10000), deadline: Some("2024-06-20".to_string()) },
+        )
+        .unwrap();
+
+        let dashboard = dashboard(&conn, "p1", day(12)).unwrap();
+        assert_eq!((dashboard.today_words, dashboard.week_words), (300, 2300));
+        assert_eq!(dashboard.history[1].words_deleted, 200);
+        assert_eq!((dashboard.current_streak, dashboard.longest_streak), (2, 2));
+        assert!((dashboard.velocity - 3300.0 / 14.0).abs() < 1e-3);
+        // 剩余 3000 字，按每天约 236 字需要 13 天
+        assert_eq!(dashboard.projected_completion.as_deref(), Some("2024-06-25"));
+        assert_eq!(dashboard.on_track, Some(false));
+        assert!(set_goals(&conn, "p1", &WritingGoals { deadline: Some("明天".to_string()), ..Default::default() }).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::writing_sprints;
+use crate::writing_stats::{self, WritingGoals};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// 写作仪表盘：今日、本周进度，连续天数，写作速度和预计完成日期
+#[tauri::command]
+pub async fn get_writing_dashboard(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let dashboard = writing_stats::dashboard(&conn, &project_id, chrono::Local::now().date_naive())?;
+    serde_json::to_string(&dashboard).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_writing_goals(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let goals = writing_stats::get_goals(&conn, &project_id)?;
+    serde_json::to_string(&goals).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_writing_goals(
+    app: AppHandle,
+    project_id: String,
+    goals: WritingGoals,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let goals = writing_stats::set_goals(&conn, &project_id, &goals)?;
+    serde_json::to_string(&goals).map_err(|e| e.to_string())
+}
+
+/// 项目每天的新增、删除字数
+#[tauri::command]
+pub async fn get_writing_history(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let history = writing_stats::get_history(&conn, &project_id)?;
+    serde_json::to_string(&history).map_err(|e| e.to_string())
+}
+
+/// 开始限时写作冲刺，`user_id` 为本机用户或协作中的用户
+#[tauri::command]
+pub async fn start_writing_sprint(
+    app: AppHandle,
+    user_id: String,
+    project_id: String,
+    planned_minutes: i64,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let sprint = writing_sprints::start_sprint(&conn, &user_id, &project_id, planned_minutes, chrono::Utc::now())?;
+    serde_json::to_string(&sprint).map_err(|e| e.to_string())
+}
+
+/// 冲刺开始以来写了多少字
+#[tauri::command]
+pub async fn get_writing_sprint_progress(
+    app: AppHandle,
+    sprint_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let sprint = writing_sprints::sprint_progress(&conn, &sprint_id)?;
+    serde_json::to_string(&sprint).map_err(|e| e.to_string())
+}
+
+/// 结束冲刺，返回字数、速度和刷新的个人纪录
+#[tauri::command]
+pub async fn stop_writing_sprint(
+    app: AppHandle,
+    sprint_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let result = writing_sprints::stop_sprint(&conn, &sprint_id, chrono::Utc::now())?;
+    serde_json::to_string(&result).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_writing_sprint_history(
+    app: AppHandle,
+    user_id: String,
+    project_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let history = writing_sprints::sprint_history(&conn, &user_id, project_id.as_deref(), Some(limit.unwrap_or(50)))?;
+    serde_json::to_string(&history).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_writing_sprint_bests(
+    app: AppHandle,
+    user_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let bests = writing_sprints::personal_bests(&conn, &user_id, chrono::Utc::now())?;
+    serde_json::to_string(&bests).map_err(|e| e.to_string())
+}
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
@@ -1,6 +1,13 @@
+use crate::entity_index::locate_terms;
+use crate::tokenizer;
+use regex::{Captures, Regex};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 
+pub const CLICHE_CUSTOM: &str = "custom";
+pub const CLICHE_SUPPRESSED: &str = "suppressed";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SensitiveWordDetection {
     pub sensitive_words: Vec<SensitiveWordMatch>,
@@ -8,12 +15,22 @@ pub struct SensitiveWordDetection {
     pub severity: String,
 }
 
+/// 命中的敏感词，`position` 是字符偏移，`dictionary` 是词所在词库的 id
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SensitiveWordMatch {
     pub word: String,
     pub position: usize,
     pub context: String,
     pub severity: String,
+    pub dictionary: String,
+}
+
+/// 词库中的一个敏感词，`severity` 为 low、medium 或 high
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SensitiveWord {
+    pub word: String,
+    pub severity: String,
+    pub dictionary: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +51,21 @@ pub struct TypoMatch {
 pub struct GrammarCheck {
     pub grammar_issues: Vec<GrammarIssue>,
     pub total_count: usize,
+    /// AI 精修给出的逐处修改，没有开启时为空
+    #[serde(default)]
+    pub line_edits: Vec<LineEdit>,
+}
+
+/// AI 精修的一处修改，`start`/`end` 是原文中的字符区间，应用前会核对 `original`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineEdit {
+    pub id: String,
+    pub start: usize,
+    pub end: usize,
+    pub original: String,
+    pub issue: String,
+    pub suggestion: String,
+    pub rationale: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,42 +91,122 @@ pub struct FormatChange {
     pub corrected: String,
 }
 
+/// 标点规范：`quote_style` 为 curly（“”‘’）或 corner（「」『』），
+/// `latin_spacing` 为中文与字母、数字之间的空格处理：add、remove 或 keep
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PunctuationProfile {
+    pub name: String,
+    /// 紧挨汉字的半角标点改为全角
+    pub full_width_punctuation: bool,
+    /// 全角字母、数字改为半角
+    pub half_width_alphanumerics: bool,
+    /// ...、。。。、… 统一为……
+    pub normalize_ellipsis: bool,
+    /// —、--、─ 统一为——
+    pub normalize_dash: bool,
+    pub quote_style: String,
+    pub latin_spacing: String,
+    /// 连续感叹号、问号最多保留的个数，0 表示不限制
+    pub max_repeated_marks: usize,
+}
+
+impl PunctuationProfile {
+    /// 预设：mainland 大陆国家标准、taiwan 台湾习惯、web_serial 网络连载
+    pub fn preset(name: &str) -> Result<Self, String> {
+        let (quote_style, latin_spacing, max_repeated_marks) = match name {
+            "mainland" => ("curly", "remove", 2),
+            "taiwan" => ("corner", "keep", 2),
+            "web_serial" => ("curly", "add", 3),
+            _ => return Err(format!("未知的标点规范: {}", name)),
+        };
+        Ok(Self {
+            name: name.to_string(),
+            full_width_punctuation: true,
+            half_width_alphanumerics: true,
+            normalize_ellipsis: true,
+            normalize_dash: true,
+            quote_style: quote_style.to_string(),
+            latin_spacing: latin_spacing.to_string(),
+            max_repeated_marks,
+        })
+    }
+}
+
+impl Default for PunctuationProfile {
+    fn default() -> Self {
+        Self::preset("mainland").unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClicheDetection {
+    pub cliches: Vec<ClicheMatch>,
+    pub total_count: usize,
+}
+
+/// 套话或赘语，`position` 是字符偏移，`category` 为 cliche（套话）、filler（赘语）或 custom（用户添加）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClicheMatch {
+    pub phrase: String,
+    pub category: String,
+    pub position: usize,
+    pub context: String,
+    pub suggestion: String,
+}
+
+/// 项目自定义的套话和不再提示的短语
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClicheSettings {
+    pub custom: Vec<String>,
+    pub suppressed: Vec<String>,
+}
+
+/// 中西文混排的排版修正，可按项目保存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypographySettings {
+    /// 汉字与字母、数字之间加空格
+    pub cjk_latin_spacing: bool,
+    /// 直引号改为弯引号，单词内的 ' 改为撇号
+    pub smart_quotes: bool,
+    /// 数字与单位之间统一空格，单位统一大小写，% 和 ° 紧跟数字
+    pub normalize_units: bool,
+}
+
+impl Default for TypographySettings {
+    fn default() -> Self {
+        Self { cjk_latin_spacing: true, smart_quotes: true, normalize_units: true }
+    }
+}
+
+/// 单位的规范写法，匹配时不区分大小写
+const UNITS: &[&str] = &["km", "cm", "mm", "kg", "mg", "mL", "kHz", "MHz", "GHz", "Hz", "KB", "MB", "GB", "TB", "kW"];
+
 pub struct WritingTools;
 
 impl WritingTools {
-    pub fn detect_sensitive_words(text: &str) -> SensitiveWordDetection {
-        let sensitive_word_list = Self::get_sensitive_word_list();
+    /// 按给定词表检测敏感词。白名单中的词不提示，也会屏蔽包含在其中的敏感词（如白名单“暴力美学”中的“暴力”）
+    pub fn detect_sensitive_words(text: &str, words: &[SensitiveWord], whitelist: &[String]) -> SensitiveWordDetection {
+        let words: Vec<&SensitiveWord> = words.iter().filter(|w| !whitelist.contains(&w.word)).collect();
+        let mut terms: Vec<&str> = words.iter().map(|w| w.word.as_str()).collect();
+        terms.extend(whitelist.iter().map(|w| w.as_str()));
+
         let mut matches = Vec::new();
         let mut severity = "low".to_string();
+        for (start, end, i) in locate_terms(text, &terms) {
+            let Some(word) = words.get(i) else { continue };
+            let position = text[..start].chars().count();
+            matches.push(SensitiveWordMatch {
+                word: word.word.clone(),
+                position,
+                context: Self::get_context(text, position, text[start..end].chars().count()),
+                severity: word.severity.clone(),
+                dictionary: word.dictionary.clone(),
+            });
 
-        let words: Vec<&str> = text.split_whitespace().collect();
-
-        for (i, word) in words.iter().enumerate() {
-            let trimmed_word = word.trim();
-            if let Some(severity_level) = sensitive_word_list.get(trimmed_word) {
-                let context_start = if i > 0 { i - 1 } else { 0 };
-                let context_end = if i < words.len() - 1 { i + 2 } else { i + 1 };
-                
-                let context: String = words.iter()
-                    .skip(context_start)
-                    .take(context_end - context_start + 1)
-                    .cloned()
-                    .collect::<Vec<_>>()
-                    .join(" ");
-
-                let severity_str = severity_level.to_string();
-                matches.push(SensitiveWordMatch {
-                    word: trimmed_word.to_string(),
-                    position: i,
-                    context,
-                    severity: severity_str.clone(),
-                });
-
-                if *severity_level == "high" {
-                    severity = "high".to_string();
-                } else if *severity_level == "medium" && severity != "high" {
-                    severity = "medium".to_string();
-                }
+            if word.severity == "high" {
+                severity = "high".to_string();
+            } else if word.severity == "medium" && severity != "high" {
+                severity = "medium".to_string();
             }
         }
 
@@ -106,11 +218,23 @@ impl WritingTools {
         }
     }
 
+    /// 内置敏感词库
+    pub fn builtin_sensitive_words() -> Vec<SensitiveWord> {
+        Self::get_sensitive_word_list()
+            .into_iter()
+            .map(|(word, severity)| SensitiveWord {
+                word: word.to_string(),
+                severity: severity.to_string(),
+                dictionary: crate::sensitive_dictionaries::BUILTIN_DICTIONARY.to_string(),
+            })
+            .collect()
+    }
+
     pub fn detect_typos(text: &str) -> TypoDetection {
         let common_typos = Self::get_common_typos();
         let mut typos = Vec::new();
 
-        let words: Vec<&str> = text.split_whitespace().collect();
+        let words = tokenizer::words(text);
 
         for (position, word) in words.iter().enumerate() {
             let lower_word = word.trim().to_lowercase();
@@ -178,66 +302,125 @@ impl WritingTools {
         GrammarCheck {
             grammar_issues: issues,
             total_count,
+            line_edits: Vec::new(),
         }
     }
 
-    pub fn normalize_format(text: &str) -> FormatNormalization {
-        let mut changes = Vec::new();
-        let mut normalized = text.to_string();
+    /// 检测网文常见套话和赘语，`custom` 为额外的短语，`suppressed` 中的短语不提示
+    pub fn detect_cliches(text: &str, custom: &[String], suppressed: &[String]) -> ClicheDetection {
+        let mut phrases: Vec<(&str, &str, &str)> = Self::get_cliche_list();
+        phrases.extend(custom.iter().map(|p| (p.as_str(), "custom", "换成更具体的描写")));
+        phrases.retain(|(phrase, _, _)| !suppressed.iter().any(|s| s == phrase));
+        let terms: Vec<&str> = phrases.iter().map(|(phrase, _, _)| *phrase).collect();
+
+        let cliches: Vec<ClicheMatch> = locate_terms(text, &terms)
+            .into_iter()
+            .map(|(start, end, i)| {
+                let (phrase, category, suggestion) = phrases[i];
+                let position = text[..start].chars().count();
+                ClicheMatch {
+                    phrase: phrase.to_string(),
+                    category: category.to_string(),
+                    position,
+                    context: Self::get_context(text, position, text[start..end].chars().count()),
+                    suggestion: suggestion.to_string(),
+                }
+            })
+            .collect();
 
-        let original = text.to_string();
+        let total_count = cliches.len();
+        ClicheDetection {
+            cliches,
+            total_count,
+        }
+    }
 
-        let mut position = 0;
+    /// 按标点规范整理文本，`changes` 中的 `position` 是该处在这一步整理前文本中的字符偏移
+    pub fn normalize_format(text: &str, profile: &PunctuationProfile) -> FormatNormalization {
+        let mut changes = Vec::new();
+        let original = text.to_string();
+        let mut normalized = text.to_string();
+        let rule = |pattern: &str| Regex::new(pattern).expect("valid punctuation rule");
 
-        while let Some(idx) = normalized.find("。。") {
-            let _context = Self::get_context(&normalized, idx, 2);
-            changes.push(FormatChange {
-                change_type: "multiple_period".to_string(),
-                position,
-                original: "。。".to_string(),
-                corrected: "。".to_string(),
-            });
-            normalized = normalized.replacen("。。", "。", 1);
-            position = idx + 1;
+        if profile.normalize_ellipsis {
+            normalized = Self::replace_rule(&normalized, "ellipsis", &rule(r"\.{3,}|。{3,}|…+|·{3,}"), |_| "……".to_string(), &mut changes);
         }
-
-        position = 0;
-        while let Some(idx) = normalized.find("，，") {
-            let _context = Self::get_context(&normalized, idx, 2);
-            changes.push(FormatChange {
-                change_type: "multiple_comma".to_string(),
-                position,
-                original: ",,".to_string(),
-                corrected: ",".to_string(),
-            });
-            normalized = normalized.replacen(",,", ",", 1);
-            position = idx + 1;
+        if profile.normalize_dash {
+            // 整行只有破折号时是分隔线，不改
+            let re = rule(r"(?m)(^|[^\n])(—+|-{2,}|─{2,}|－{2,})([^\n]|$)");
+            normalized = Self::replace_rule(&normalized, "dash", &re, |caps| {
+                if caps[1].is_empty() && caps[3].is_empty() {
+                    caps[0].to_string()
+                } else {
+                    format!("{}——{}", &caps[1], &caps[3])
+                }
+            }, &mut changes);
+        }
+        normalized = Self::replace_rule(&normalized, "multiple_period", &rule("。{2,}"), |_| "。".to_string(), &mut changes);
+        normalized = Self::replace_rule(&normalized, "multiple_comma", &rule("，{2,}"), |_| "，".to_string(), &mut changes);
+        if profile.max_repeated_marks > 0 {
+            let limit = profile.max_repeated_marks;
+            for (change_type, mark) in [("excessive_exclamation", '！'), ("excessive_question", '？')] {
+                let re = rule(&format!("{}{{{},}}", mark, limit + 1));
+                normalized = Self::replace_rule(&normalized, change_type, &re, |_| mark.to_string().repeat(limit), &mut changes);
+            }
         }
 
-        position = 0;
-        while let Some(idx) = normalized.find("！！！") {
-            let _context = Self::get_context(&normalized, idx, 3);
-            changes.push(FormatChange {
-                change_type: "excessive_exclamation".to_string(),
-                position,
-                original: "！！！".to_string(),
-                corrected: "！".to_string(),
-            });
-            normalized = normalized.replacen("！！！", "！", 1);
-            position = idx + 1;
+        if profile.full_width_punctuation {
+            let full_width = |c: &str| match c {
+                "," => "，",
+                "." => "。",
+                "!" => "！",
+                "?" => "？",
+                ":" => "：",
+                ";" => "；",
+                _ => "",
+            };
+            normalized = Self::replace_rule(&normalized, "full_width_punctuation", &rule(r"(\p{Han})([,.!?:;])"), |caps| {
+                format!("{}{}", &caps[1], full_width(&caps[2]))
+            }, &mut changes);
+            normalized = Self::replace_rule(&normalized, "full_width_punctuation", &rule(r"([,!?:;])(\p{Han})"), |caps| {
+                format!("{}{}", full_width(&caps[1]), &caps[2])
+            }, &mut changes);
+            normalized = Self::replace_rule(&normalized, "full_width_punctuation", &rule(r"\(([^()\n]*\p{Han}[^()\n]*)\)"), |caps| {
+                format!("（{}）", &caps[1])
+            }, &mut changes);
+        }
+        if profile.half_width_alphanumerics {
+            normalized = Self::replace_rule(&normalized, "half_width_alphanumeric", &rule("[０-９Ａ-Ｚａ-ｚ]+"), |caps| {
+                caps[0].chars().map(|c| char::from_u32(c as u32 - 0xFEE0).unwrap_or(c)).collect()
+            }, &mut changes);
         }
 
-        position = 0;
-        while let Some(idx) = normalized.find("？？？") {
-            let _context = Self::get_context(&normalized, idx, 3);
-            changes.push(FormatChange {
-                change_type: "excessive_question".to_string(),
-                position,
-                original: "？？？".to_string(),
-                corrected: "？".to_string(),
-            });
-            normalized = normalized.replacen("？？？", "？", 1);
-            position = idx + 1;
+        let (open, close, inner_open, inner_close) = if profile.quote_style == "corner" { ("「", "」", "『", "』") } else { ("“", "”", "‘", "’") };
+        normalized = Self::replace_rule(&normalized, "quote_style", &rule(r#""([^"\n]*\p{Han}[^"\n]*)""#), |caps| {
+            format!("{}{}{}", open, &caps[1], close)
+        }, &mut changes);
+        normalized = Self::replace_rule(&normalized, "quote_style", &rule("[“「]([^“”「」\n]*)[”」]"), |caps| {
+            format!("{}{}{}", open, &caps[1], close)
+        }, &mut changes);
+        normalized = Self::replace_rule(&normalized, "quote_style", &rule("[‘『]([^‘’『』\n]*)[’』]"), |caps| {
+            format!("{}{}{}", inner_open, &caps[1], inner_close)
+        }, &mut changes);
+
+        match profile.latin_spacing.as_str() {
+            "add" => {
+                normalized = Self::replace_rule(&normalized, "latin_spacing", &rule(r"(\p{Han})([A-Za-z0-9])"), |caps| {
+                    format!("{} {}", &caps[1], &caps[2])
+                }, &mut changes);
+                normalized = Self::replace_rule(&normalized, "latin_spacing", &rule(r"([A-Za-z0-9])(\p{Han})"), |caps| {
+                    format!("{} {}", &caps[1], &caps[2])
+                }, &mut changes);
+            }
+            "remove" => {
+                normalized = Self::replace_rule(&normalized, "latin_spacing", &rule(r"(\p{Han}) +([A-Za-z0-9])"), |caps| {
+                    format!("{}{}", &caps[1], &caps[2])
+                }, &mut changes);
+                normalized = Self::replace_rule(&normalized, "latin_spacing", &rule(r"([A-Za-z0-9]) +(\p{Han})"), |caps| {
+                    format!("{}{}", &caps[1], &caps[2])
+                }, &mut changes);
+            }
+            _ => {}
         }
 
         let lines: Vec<&str> = normalized.lines().collect();
@@ -262,25 +445,95 @@ impl WritingTools {
         }
     }
 
-    fn get_sensitive_word_list() -> HashMap<&'static str, &'static str> {
-        let mut map = HashMap::new();
-        
-        map.insert("暴力", "high");
-        map.insert("血腥", "high");
-        map.insert("恐怖", "medium");
-        map.insert("残忍", "high");
-        map.insert("酷刑", "high");
-        map.insert("谋杀", "high");
-        map.insert("自杀", "high");
-        map.insert("性暴力", "high");
-        map.insert("性骚扰", "high");
-        map.insert("歧视", "medium");
-        map.insert("仇恨", "medium");
-        map.insert("种族歧视", "high");
-        map.insert("宗教歧视", "high");
-        map.insert("性别歧视", "high");
-        
-        map
+    /// 中西文混排修正，`changes` 的 `position` 与 `normalize_format` 相同，是这一步修正前的字符偏移
+    pub fn fix_typography(text: &str, settings: &TypographySettings) -> FormatNormalization {
+        let mut changes = Vec::new();
+        let mut fixed = text.to_string();
+        let rule = |pattern: &str| Regex::new(pattern).expect("valid typography rule");
+
+        if settings.smart_quotes {
+            fixed = Self::replace_rule(&fixed, "apostrophe", &rule("([A-Za-z])'([A-Za-z])"), |caps| {
+                format!("{}’{}", &caps[1], &caps[2])
+            }, &mut changes);
+            fixed = Self::replace_rule(&fixed, "smart_quote", &rule(r#""([^"\n]*)""#), |caps| format!("“{}”", &caps[1]), &mut changes);
+            fixed = Self::replace_rule(&fixed, "smart_quote", &rule(r"'([^'\n]*)'"), |caps| format!("‘{}’", &caps[1]), &mut changes);
+        }
+        if settings.normalize_units {
+            let units = UNITS.join("|");
+            let re = rule(&format!(r"(\d)[ \t]*(?i:({}))([^A-Za-z]|$)", units));
+            fixed = Self::replace_rule(&fixed, "unit", &re, |caps| {
+                let unit = UNITS.iter().find(|u| u.eq_ignore_ascii_case(&caps[2])).copied().unwrap_or_default();
+                format!("{} {}{}", &caps[1], unit, &caps[3])
+            }, &mut changes);
+            fixed = Self::replace_rule(&fixed, "unit", &rule(r"(\d)[ \t]+(%|°C|°F|°|℃|℉)"), |caps| {
+                format!("{}{}", &caps[1], &caps[2])
+            }, &mut changes);
+        }
+        if settings.cjk_latin_spacing {
+            fixed = Self::replace_rule(&fixed, "cjk_latin_spacing", &rule(r"(\p{Han})([A-Za-z0-9])"), |caps| {
+                format!("{} {}", &caps[1], &caps[2])
+            }, &mut changes);
+            fixed = Self::replace_rule(&fixed, "cjk_latin_spacing", &rule(r"([A-Za-z0-9%°])(\p{Han})"), |caps| {
+                format!("{} {}", &caps[1], &caps[2])
+            }, &mut changes);
+        }
+
+        FormatNormalization {
+            original: text.to_string(),
+            normalized: fixed,
+            changes,
+        }
+    }
+
+    /// 替换 `re` 的每处匹配，替换后不同的记为一条改动
+    fn replace_rule(
+        text: &str,
+        change_type: &str,
+        re: &Regex,
+        replace: impl Fn(&Captures) -> String,
+        changes: &mut Vec<FormatChange>,
+    ) -> String {
+        let mut result = String::with_capacity(text.len());
+        let (mut last, mut position) = (0, 0);
+        for caps in re.captures_iter(text) {
+            let whole = caps.get(0).expect("capture 0 always exists");
+            let corrected = replace(&caps);
+            if corrected == whole.as_str() {
+                continue;
+            }
+            position += text[last..whole.start()].chars().count();
+            changes.push(FormatChange {
+                change_type: change_type.to_string(),
+                position,
+                original: whole.as_str().to_string(),
+                corrected: corrected.clone(),
+            });
+            position += whole.as_str().chars().count();
+            result.push_str(&text[last..whole.start()]);
+            result.push_str(&corrected);
+            last = whole.end();
+        }
+        result.push_str(&text[last..]);
+        result
+    }
+
+    fn get_sensitive_word_list() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("暴力", "high"),
+            ("血腥", "high"),
+            ("恐怖", "medium"),
+            ("残忍", "high"),
+            ("酷刑", "high"),
+            ("谋杀", "high"),
+            ("自杀", "high"),
+            ("性暴力", "high"),
+            ("性骚扰", "high"),
+            ("歧视", "medium"),
+            ("仇恨", "medium"),
+            ("种族歧视", "high"),
+            ("宗教歧视", "high"),
+            ("性别歧视", "high"),
+        ]
     }
 
     fn get_common_typos() -> HashMap<&'static str, &'static str> {
@@ -306,6 +559,41 @@ impl WritingTools {
         map
     }
 
+    fn get_cliche_list() -> Vec<(&'static str, &'static str, &'static str)> {
+        vec![
+            ("倒吸一口凉气", "cliche", "写出具体的反应或动作"),
+            ("嘴角勾起一抹弧度", "cliche", "换成符合人物性格的表情"),
+            ("嘴角微微上扬", "cliche", "换成符合人物性格的表情"),
+            ("眼中闪过一丝", "cliche", "用行为表现情绪"),
+            ("眸子里闪过一丝", "cliche", "用行为表现情绪"),
+            ("恐怖如斯", "cliche", "让旁人的具体反应体现实力"),
+            ("一股强大的气息", "cliche", "描写气息带来的具体感受"),
+            ("空气仿佛凝固了", "cliche", "写出具体的沉默或紧张细节"),
+            ("时间仿佛静止了", "cliche", "写出具体的沉默或紧张细节"),
+            ("不知过了多久", "cliche", "交代具体的时间或变化"),
+            ("心中暗道", "cliche", "直接写出想法或省略"),
+            ("心中一凛", "cliche", "用动作表现警觉"),
+            ("如同蝼蚁一般", "cliche", "换一个新鲜的比喻"),
+            ("三十年河东，三十年河西", "cliche", "用人物自己的话表达"),
+            ("莫欺少年穷", "cliche", "用人物自己的话表达"),
+            ("不可思议的一幕", "cliche", "直接描写发生了什么"),
+            ("全场一片哗然", "cliche", "写几个具体的旁观者反应"),
+            ("众人纷纷", "cliche", "写几个具体的旁观者反应"),
+            ("不由得", "filler", "多数情况下可以删除"),
+            ("情不自禁地", "filler", "多数情况下可以删除"),
+            ("与此同时", "filler", "确认是否真的需要强调同时"),
+            ("就在这时", "filler", "直接写出事件"),
+            ("只见", "filler", "直接写出看到的内容"),
+            ("只听", "filler", "直接写出听到的内容"),
+            ("一时间", "filler", "多数情况下可以删除"),
+            ("可以说是", "filler", "直接下判断"),
+            ("某种程度上", "filler", "直接下判断"),
+            ("不得不说", "filler", "直接下判断"),
+            ("毫无疑问", "filler", "直接下判断"),
+            ("紧接着", "filler", "用动作衔接代替"),
+        ]
+    }
+
     fn get_context(text: &str, position: usize, length: usize) -> String {
         let chars: Vec<char> = text.chars().collect();
         let start = if position >= 10 { position - 10 } else { 0 };
@@ -318,3 +606,261 @@ impl WritingTools {
         chars[start..end].iter().collect()
     }
 }
+
+pub fn get_cliche_settings(conn: &Connection, project_id: &str) -> Result<ClicheSettings, String> {
+    let mut stmt = conn
+        .prepare("SELECT kind, phrase FROM cliche_phrases WHERE project_id = ? ORDER BY rowid")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![project_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+    let mut settings = ClicheSettings::default();
+    for row in rows {
+        let (kind, phrase) = row.map_err(|e| e.to_string())?;
+        match kind.as_str() {
+            CLICHE_CUSTOM => settings.custom.push(phrase),
+            CLICHE_SUPPRESSED => settings.suppressed.push(phrase),
+            _ => {}
+        }
+    }
+    Ok(settings)
+}
+
+/// 整体替换项目自定义的套话列表
+pub fn set_custom_cliches(conn: &Connection, project_id: &str, phrases: &[String]) -> Result<ClicheSettings, String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM cliche_phrases WHERE project_id = ? AND kind = ?", params![project_id, CLICHE_CUSTOM])
+        .map_err(|e| e.to_string())?;
+    for phrase in phrases.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        tx.execute(
+            "INSERT OR IGNORE INTO cliche_phrases (project_id, phrase, kind) VALUES (?, ?, ?)",
+            params![project_id, phrase, CLICHE_CUSTOM],
+        )
+        .map_err(|e| format!("Failed to save cliche phrase: {}", e))?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    get_cliche_settings(conn, project_id)
+}
+
+/// 在项目中关闭或恢复某个短语的提示，内置和自定义短语都适用
+pub fn set_cliche_suppressed(conn: &Connection, project_id: &str, phrase: &str, suppressed: bool) -> Result<ClicheSettings, String> {
+    let sql = if suppressed {
+        "INSERT OR IGNORE INTO cliche_phrases (project_id, phrase, kind) VALUES (?, ?, ?)"
+    } else {
+        "DELETE FROM cliche_phrases WHERE project_id = ? AND phrase = ? AND kind = ?"
+    };
+    conn.execute(sql, params![project_id, phrase.trim(), CLICHE_SUPPRESSED]).map_err(|e| e.to_string())?;
+    get_cliche_settings(conn, project_id)
+}
+
+/// 每次发给 AI 精修的最大字数，按段落切分
+const LINE_EDIT_CHUNK_CHARS: usize = 2000;
+
+/// 按段落把文本切成不超过 `LINE_EDIT_CHUNK_CHARS` 字的片段，返回（起始字符偏移, 片段）
+fn line_edit_chunks(text: &str) -> Vec<(usize, &str)> {
+    let mut chunks = Vec::new();
+    let (mut chunk_start, mut chunk_chars, mut byte_start) = (0, 0, 0);
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let chars = line.chars().count();
+        if chunk_chars > 0 && chunk_chars + chars > LINE_EDIT_CHUNK_CHARS {
+            let byte_end = byte_start + text[byte_start..].char_indices().nth(chunk_chars).map_or(text.len() - byte_start, |(i, _)| i);
+            chunks.push((chunk_start, &text[byte_start..byte_end]));
+            (chunk_start, chunk_chars, byte_start) = (offset, 0, byte_end);
+        }
+        chunk_chars += chars;
+        offset += chars;
+    }
+    if chunk_chars > 0 {
+        chunks.push((chunk_start, &text[byte_start..]));
+    }
+    chunks
+}
+
+/// 解析 AI 返回的修改列表。AI 给出的是原文片段，在 `chunk` 中按顺序定位后换算成字符区间，
+/// 找不到或与前一处重叠的丢弃
+fn parse_line_edits(chunk: &str, offset: usize, response: &str) -> Vec<LineEdit> {
+    let start = response.find('[').unwrap_or(0);
+    let end = response[start..].rfind(']').map(|i| start + i + 1).unwrap_or(response.len());
+    let items: Vec<serde_json::Value> = serde_json::from_str(&response[start..end]).unwrap_or_default();
+    let field = |item: &serde_json::Value, key: &str| item.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+
+    let mut edits = Vec::new();
+    let mut cursor = 0;
+    for item in &items {
+        let original = field(item, "original");
+        let suggestion = field(item, "suggestion");
+        if original.is_empty() || original == suggestion {
+            continue;
+        }
+        let Some(found) = chunk[cursor..].find(&original).map(|i| i + cursor) else { continue };
+        let start = offset + chunk[..found].chars().count();
+        let end = start + original.chars().count();
+        cursor = found + original.len();
+        edits.push(LineEdit {
+            id: format!("{}-{}", start, end),
+            start,
+            end,
+            original,
+            issue: field(item, "issue"),
+            suggestion,
+            rationale: field(item, "rationale"),
+        });
+    }
+    edits
+}
+
+/// AI 逐句精修：找出语病、搭配不当和冗余表达，返回可单独采纳的修改
+pub async fn line_edit_with_ai(service: &crate::ai::AIService, model_id: &str, text: &str) -> Result<Vec<LineEdit>, String> {
+    let system_prompt = "你是一位严谨的中文小说编辑，负责逐句精修。只返回 JSON 数组，不要包含markdown代码块标记。";
+    let mut edits = Vec::new();
+    for (offset, chunk) in line_edit_chunks(text) {
+        let user_prompt = format!(
+            "请找出以下小说片段中的语病、搭配不当、成分残缺和冗余表达，不要改动人物语气和作者风格。按在原文中出现的顺序返回：\
+             [{{\"original\": \"原文中需要修改的片段，必须与原文完全一致\", \"issue\": \"问题类型\", \"suggestion\": \"修改后的片段\", \"rationale\": \"修改理由\"}}]。\
+             没有问题时返回 []。\n\n{}",
+            chunk
+        );
+        let response = service.complete(model_id, system_prompt, &user_prompt).await?;
+        edits.extend(parse_line_edits(chunk, offset, &response));
+    }
+    Ok(edits)
+}
+
+/// 批量应用采纳的修改。区间内的文字与 `original` 不符（正文已改动）或修改互相重叠时报错，不做部分应用
+pub fn apply_line_edits(text: &str, edits: &[LineEdit]) -> Result<String, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut edits: Vec<&LineEdit> = edits.iter().collect();
+    edits.sort_by_key(|e| e.start);
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for edit in edits {
+        if edit.start < last || edit.end > chars.len() || edit.start > edit.end {
+            return Err(format!("修改 {} 的区间无效或与其他修改重叠", edit.id));
+        }
+        if chars[edit.start..edit.end].iter().collect::<String>() != edit.original {
+            return Err(format!("修改 {} 对应的原文已变化，请重新检查", edit.id));
+        }
+        result.extend(&chars[last..edit.start]);
+        result.push_str(&edit.suggestion);
+        last = edit.end;
+    }
+    result.extend(&chars[last..]);
+    Ok(result)
+}
+
+/// 项目的排版设置，没有保存过时用默认值
+pub fn get_typography_settings(conn: &Connection, project_id: &str) -> Result<TypographySettings, String> {
+    conn.query_row(
+        "SELECT cjk_latin_spacing, smart_quotes, normalize_units FROM typography_settings WHERE project_id = ?",
+        params![project_id],
+        |row| {
+            Ok(TypographySettings {
+                cjk_latin_spacing: row.get::<_, i32>(0)? != 0,
+                smart_quotes: row.get::<_, i32>(1)? != 0,
+                normalize_units: row.get::<_, i32>(2)? != 0,
+            })
+        },
+    )
+    .optional()
+    .map(Option::unwrap_or_default)
+    .map_err(|e| e.to_string())
+}
+
+pub fn set_typography_settings(conn: &Connection, project_id: &str, settings: &TypographySettings) -> Result<TypographySettings, String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO typography_settings (project_id, cjk_latin_spacing, smart_quotes, normalize_units, updated_at)
+         VALUES (?, ?, ?, ?, ?)",
+        params![
+            project_id,
+            settings.cjk_latin_spacing as i32,
+            settings.smart_quotes as i32,
+            settings.normalize_units as i32,
+            chrono::Utc::now().to_rfc3339(),
+        ],
+    )
+    .map_err(|e| format!("Failed to save typography settings: {}", e))?;
+    get_typography_settings(conn, project_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cliches_respect_custom_and_suppressed_phrases() {
+        let (_dir, conn) = crate::test_support::project_db();
+
+        let text = "众人倒吸一口凉气。只见他嘴角微微上扬，霸气侧漏。";
+        let builtin = WritingTools::detect_cliches(text, &[], &[]);
+        let phrases: Vec<&str> = builtin.cliches.iter().map(|c| c.phrase.as_str()).collect();
+        assert_eq!(phrases, vec!["倒吸一口凉气", "只见", "嘴角微微上扬"]);
+        assert_eq!((builtin.cliches[0].position, builtin.cliches[1].category.as_str()), (2, "filler"));
+
+        set_custom_cliches(&conn, "p1", &["霸气侧漏".to_string(), " ".to_string()]).unwrap();
+        let settings = set_cliche_suppressed(&conn, "p1", "只见", true).unwrap();
+        assert_eq!((settings.custom.len(), settings.suppressed.len()), (1, 1));
+        let detection = WritingTools::detect_cliches(text, &settings.custom, &settings.suppressed);
+        let phrases: Vec<&str> = detection.cliches.iter().map(|c| c.phrase.as_str()).collect();
+        assert_eq!(phrases, vec!["倒吸一口凉气", "嘴角微微上扬", "霸气侧漏"]);
+
+        assert!(set_cliche_suppressed(&conn, "p1", "只见", false).unwrap().suppressed.is_empty());
+    }
+
+    #[test]
+    fn test_normalize_format_profiles() {
+        let text = "他说:\"等等...\"然后--转身走了！！！！\n---\n“用iPhone拍的”，他说。";
+        let mainland = WritingTools::normalize_format(text, &PunctuationProfile::default());
+        assert_eq!(mainland.normalized, "他说：“等等……”然后——转身走了！！\n---\n“用iPhone拍的”，他说。");
+
+        let taiwan = WritingTools::normalize_format(text, &PunctuationProfile::preset("taiwan").unwrap());
+        assert!(taiwan.normalized.starts_with("他说：「等等……」"));
+        assert!(taiwan.normalized.ends_with("「用iPhone拍的」，他说。"));
+
+        let web = WritingTools::normalize_format("用ｉＰｈｏｎｅ 拍的", &PunctuationProfile::preset("web_serial").unwrap());
+        assert_eq!(web.normalized, "用 iPhone 拍的");
+        let types: Vec<&str> = web.changes.iter().map(|c| c.change_type.as_str()).collect();
+        assert_eq!(types, vec!["half_width_alphanumeric", "latin_spacing"]);
+        assert_eq!(web.changes[0].position, 1);
+        assert!(PunctuationProfile::preset("unknown").is_err());
+    }
+
+    #[test]
+    fn test_fix_typography() {
+        let (_dir, conn) = crate::test_support::project_db();
+
+        let text = "他用iPhone跑了5KM，气温30 ℃，电量剩下20 %。\n\"I don't know,\" she said. 'Fine.'";
+        let fixed = WritingTools::fix_typography(text, &get_typography_settings(&conn, "p1").unwrap());
+        assert_eq!(fixed.normalized, "他用 iPhone 跑了 5 km，气温 30℃，电量剩下 20%。\n“I don’t know,” she said. ‘Fine.’");
+        assert_eq!((fixed.changes[0].change_type.as_str(), fixed.changes[0].position), ("apostrophe", 36));
+
+        let settings = TypographySettings { cjk_latin_spacing: false, ..Default::default() };
+        assert!(!set_typography_settings(&conn, "p1", &settings).unwrap().cjk_latin_spacing);
+        let fixed = WritingTools::fix_typography("用iPhone跑了5km", &get_typography_settings(&conn, "p1").unwrap());
+        assert_eq!(fixed.normalized, "用iPhone跑了5 km");
+    }
+
+    #[test]
+    fn test_line_edits_parse_and_apply() {
+        let text = "他非常很高兴。\n大约三百人左右来了，他非常很高兴。";
+        let response = r#"```json
+[{"original": "非常很高兴", "issue": "重复修饰", "suggestion": "非常高兴", "rationale": "程度副词重复"},
+ {"original": "大约三百人左右", "issue": "语义重复", "suggestion": "三百人左右", "rationale": "大约与左右重复"},
+ {"original": "不存在的句子", "issue": "", "suggestion": "x", "rationale": ""},
+ {"original": "非常很高兴", "issue": "重复修饰", "suggestion": "非常高兴", "rationale": "程度副词重复"}]
+```"#;
+        let edits = parse_line_edits(text, 0, response);
+        let ranges: Vec<(usize, usize)> = edits.iter().map(|e| (e.start, e.end)).collect();
+        assert_eq!(ranges, vec![(1, 6), (8, 15), (19, 24)]);
+        // 回复中 `]` 出现在 `[` 之前时不能越界
+        assert!(parse_line_edits(text, 0, "没有问题] 见 [").is_empty());
+
+        let accepted = vec![edits[2].clone(), edits[1].clone()];
+        assert_eq!(apply_line_edits(text, &accepted).unwrap(), "他非常很高兴。\n三百人左右来了，他非常高兴。");
+        assert!(apply_line_edits("他很高兴。", &accepted).is_err());
+
+        let long = format!("{}\n{}", "甲".repeat(1500), "乙".repeat(1500));
+        let chunks = line_edit_chunks(&long);
+        assert_eq!((chunks.len(), chunks[1].0, chunks[1].1.chars().count()), (2, 1501, 1500));
+    }
+}
@@ -1,18 +1,179 @@
-use crate::writing_tools::WritingTools;
+use crate::autocorrect::{self, AutocorrectRule};
+use crate::numeral_style;
+use crate::sensitive_dictionaries;
+use crate::writing_tools::{self, ClicheSettings, LineEdit, PunctuationProfile, SensitiveWord, TypographySettings, WritingTools};
 use crate::logger::Logger;
 use serde_json;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// 项目启用的敏感词和白名单，没有指定项目时只用内置词库
+fn sensitive_words(app: &AppHandle, project_id: Option<&str>) -> Result<(Vec<SensitiveWord>, Vec<String>), String> {
+    let Some(project_id) = project_id else {
+        return Ok((WritingTools::builtin_sensitive_words(), Vec::new()));
+    };
+    let db_path = get_db_path(app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    sensitive_dictionaries::active_words(&conn, project_id)
+}
 
 #[tauri::command]
 pub async fn detect_sensitive_words(
+    app: AppHandle,
     text: String,
+    project_id: Option<String>,
 ) -> Result<String, String> {
     let logger = Logger::new().with_feature("writing_tools");
     logger.info("Detecting sensitive words");
 
-    let detection = WritingTools::detect_sensitive_words(&text);
+    let (words, whitelist) = sensitive_words(&app, project_id.as_deref())?;
+    let detection = WritingTools::detect_sensitive_words(&text, &words, &whitelist);
     serde_json::to_string(&detection).map_err(|e| e.to_string())
 }
 
+/// 所有敏感词库，指定项目时包含各词库在项目中是否启用
+#[tauri::command]
+pub async fn list_sensitive_dictionaries(
+    app: AppHandle,
+    project_id: Option<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let dictionaries = sensitive_dictionaries::list_dictionaries(&conn, project_id.as_deref())?;
+    serde_json::to_string(&dictionaries).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_sensitive_dictionary(
+    app: AppHandle,
+    name: String,
+    description: Option<String>,
+    enabled_by_default: Option<bool>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let dictionary = sensitive_dictionaries::create_dictionary(
+        &conn,
+        &name,
+        description.as_deref(),
+        enabled_by_default.unwrap_or(false),
+    )?;
+    serde_json::to_string(&dictionary).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_sensitive_dictionary(
+    app: AppHandle,
+    dictionary_id: String,
+) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    sensitive_dictionaries::delete_dictionary(&conn, &dictionary_id)
+}
+
+#[tauri::command]
+pub async fn get_sensitive_dictionary_words(
+    app: AppHandle,
+    dictionary_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let words = sensitive_dictionaries::get_words(&conn, &dictionary_id)?;
+    serde_json::to_string(&words).map_err(|e| e.to_string())
+}
+
+/// 添加或修改词库中的词，不传 severity 时删除该词
+#[tauri::command]
+pub async fn set_sensitive_word(
+    app: AppHandle,
+    dictionary_id: String,
+    word: String,
+    severity: Option<String>,
+) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    sensitive_dictionaries::set_word(&conn, &dictionary_id, &word, severity.as_deref())
+}
+
+/// 从 txt 文件导入词，每行一个词，可在词后用空格或逗号注明级别
+#[tauri::command]
+pub async fn import_sensitive_dictionary(
+    app: AppHandle,
+    dictionary_id: String,
+    file_path: String,
+    default_severity: Option<String>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("writing_tools");
+    logger.info(&format!("Importing sensitive words from {}", file_path));
+
+    let content = std::fs::read_to_string(&file_path)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let result = sensitive_dictionaries::import_words(
+        &conn,
+        &dictionary_id,
+        &content,
+        default_severity.as_deref().unwrap_or("medium"),
+    )?;
+    serde_json::to_string(&result).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_sensitive_dictionary_enabled(
+    app: AppHandle,
+    project_id: String,
+    dictionary_id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    sensitive_dictionaries::set_project_enabled(&conn, &project_id, &dictionary_id, enabled)
+}
+
+#[tauri::command]
+pub async fn get_sensitive_whitelist(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let whitelist = sensitive_dictionaries::get_whitelist(&conn, &project_id)?;
+    serde_json::to_string(&whitelist).map_err(|e| e.to_string())
+}
+
+/// 替换项目的敏感词白名单
+#[tauri::command]
+pub async fn set_sensitive_whitelist(
+    app: AppHandle,
+    project_id: String,
+    words: Vec<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let whitelist = sensitive_dictionaries::set_whitelist(&conn, &project_id, &words)?;
+    serde_json::to_string(&whitelist).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn detect_typos(
     text: String,
@@ -24,46 +185,315 @@ pub async fn detect_typos(
     serde_json::to_string(&detection).map_err(|e| e.to_string())
 }
 
+/// 规则检查语法；`deep` 为 true 时再让 AI 逐句精修，结果放在 `line_edits`，可用 `apply_line_edits` 批量应用
 #[tauri::command]
 pub async fn check_grammar(
+    app: AppHandle,
     text: String,
+    deep: Option<bool>,
+    model_id: Option<String>,
 ) -> Result<String, String> {
     let logger = Logger::new().with_feature("writing_tools");
     logger.info("Checking grammar");
 
-    let check = WritingTools::check_grammar(&text);
+    let mut check = WritingTools::check_grammar(&text);
+    if deep.unwrap_or(false) {
+        let ai_service = app.state::<std::sync::Arc<tokio::sync::RwLock<crate::ai::AIService>>>();
+        let service = ai_service.read().await;
+        let model_id = model_id.unwrap_or_else(|| "glm-4-flash".to_string());
+        check.line_edits = writing_tools::line_edit_with_ai(&service, &model_id, &text).await?;
+    }
     serde_json::to_string(&check).map_err(|e| e.to_string())
 }
 
+/// 应用采纳的 AI 精修修改，返回修改后的文本
+#[tauri::command]
+pub async fn apply_line_edits(
+    text: String,
+    edits: Vec<LineEdit>,
+) -> Result<String, String> {
+    let edited = writing_tools::apply_line_edits(&text, &edits)?;
+    serde_json::to_string(&edited).map_err(|e| e.to_string())
+}
+
+/// 自定义规范优先，其次是预设名，都没有时按大陆国家标准
+fn punctuation_profile(preset: Option<&str>, profile: Option<PunctuationProfile>) -> Result<PunctuationProfile, String> {
+    match (profile, preset) {
+        (Some(profile), _) => Ok(profile),
+        (None, Some(preset)) => PunctuationProfile::preset(preset),
+        (None, None) => Ok(PunctuationProfile::default()),
+    }
+}
+
+/// 按标点规范整理文本：全半角、省略号、破折号、引号风格和中英文间距
 #[tauri::command]
 pub async fn normalize_format(
     text: String,
+    preset: Option<String>,
+    profile: Option<PunctuationProfile>,
 ) -> Result<String, String> {
     let logger = Logger::new().with_feature("writing_tools");
     logger.info("Normalizing format");
 
-    let normalized = WritingTools::normalize_format(&text);
+    let profile = punctuation_profile(preset.as_deref(), profile)?;
+    let normalized = WritingTools::normalize_format(&text, &profile);
     serde_json::to_string(&normalized).map_err(|e| e.to_string())
 }
 
+/// 按数字风格（chinese 或 arabic，默认 chinese）检查数字写法和量词搭配，返回修改建议及修改后的文本
+#[tauri::command]
+pub async fn check_numerals(
+    text: String,
+    style: Option<String>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("writing_tools");
+    logger.info("Checking numerals and measure words");
+
+    let check = numeral_style::check(&text, style.as_deref().unwrap_or("chinese"))?;
+    serde_json::to_string(&check).map_err(|e| e.to_string())
+}
+
+/// 项目的排版设置，没有指定项目时用默认值
+fn typography_settings(app: &AppHandle, project_id: Option<&str>) -> Result<TypographySettings, String> {
+    let Some(project_id) = project_id else {
+        return Ok(TypographySettings::default());
+    };
+    let db_path = get_db_path(app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    writing_tools::get_typography_settings(&conn, project_id)
+}
+
+/// 中西文混排修正：中英文间距、弯引号和单位写法。传入 `settings` 时优先于项目设置
+#[tauri::command]
+pub async fn fix_typography(
+    app: AppHandle,
+    text: String,
+    project_id: Option<String>,
+    settings: Option<TypographySettings>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("writing_tools");
+    logger.info("Fixing typography");
+
+    let settings = match settings {
+        Some(settings) => settings,
+        None => typography_settings(&app, project_id.as_deref())?,
+    };
+    let fixed = WritingTools::fix_typography(&text, &settings);
+    serde_json::to_string(&fixed).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_typography_settings(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let settings = typography_settings(&app, Some(&project_id))?;
+    serde_json::to_string(&settings).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_typography_settings(
+    app: AppHandle,
+    project_id: String,
+    settings: TypographySettings,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let settings = writing_tools::set_typography_settings(&conn, &project_id, &settings)?;
+    serde_json::to_string(&settings).map_err(|e| e.to_string())
+}
+
+/// 项目的套话设置，没有指定项目时只用内置词表
+fn cliche_settings(app: &AppHandle, project_id: Option<&str>) -> Result<ClicheSettings, String> {
+    let Some(project_id) = project_id else {
+        return Ok(ClicheSettings::default());
+    };
+    let db_path = get_db_path(app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    writing_tools::get_cliche_settings(&conn, project_id)
+}
+
+#[tauri::command]
+pub async fn detect_cliches(
+    app: AppHandle,
+    text: String,
+    project_id: Option<String>,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("writing_tools");
+    logger.info("Detecting cliches");
+
+    let settings = cliche_settings(&app, project_id.as_deref())?;
+    let detection = WritingTools::detect_cliches(&text, &settings.custom, &settings.suppressed);
+    serde_json::to_string(&detection).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_cliche_settings(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let settings = cliche_settings(&app, Some(&project_id))?;
+    serde_json::to_string(&settings).map_err(|e| e.to_string())
+}
+
+/// 替换项目自定义的套话列表
+#[tauri::command]
+pub async fn set_custom_cliches(
+    app: AppHandle,
+    project_id: String,
+    phrases: Vec<String>,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let settings = writing_tools::set_custom_cliches(&conn, &project_id, &phrases)?;
+    serde_json::to_string(&settings).map_err(|e| e.to_string())
+}
+
+/// 在项目中关闭（或恢复）某个套话的提示
+#[tauri::command]
+pub async fn set_cliche_suppressed(
+    app: AppHandle,
+    project_id: String,
+    phrase: String,
+    suppressed: bool,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let settings = writing_tools::set_cliche_suppressed(&conn, &project_id, &phrase, suppressed)?;
+    serde_json::to_string(&settings).map_err(|e| e.to_string())
+}
+
+/// 对项目生效的自动更正规则，包括全局规则
+#[tauri::command]
+pub async fn list_autocorrect_rules(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let rules = autocorrect::list_rules(&conn, &project_id)?;
+    serde_json::to_string(&rules).map_err(|e| e.to_string())
+}
+
+/// 新建（id 为空）或修改自动更正规则
+#[tauri::command]
+pub async fn save_autocorrect_rule(
+    app: AppHandle,
+    rule: AutocorrectRule,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let rule = autocorrect::save_rule(&conn, rule)?;
+    serde_json::to_string(&rule).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_autocorrect_rule(
+    app: AppHandle,
+    rule_id: String,
+) -> Result<(), String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    autocorrect::delete_rule(&conn, &rule_id)
+}
+
+/// 用项目的规则更正一段文本，不写入数据库
+#[tauri::command]
+pub async fn apply_autocorrect(
+    app: AppHandle,
+    project_id: String,
+    text: String,
+) -> Result<String, String> {
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let (corrected, corrections) = autocorrect::apply_rules(&text, &autocorrect::list_rules(&conn, &project_id)?)?;
+    serde_json::to_string(&serde_json::json!({
+        "text": corrected,
+        "corrections": corrections,
+    })).map_err(|e| e.to_string())
+}
+
+/// 对项目所有章节运行自动更正，返回的还原点可用 `restore_snapshot` 撤销
+#[tauri::command]
+pub async fn run_autocorrect(
+    app: AppHandle,
+    project_id: String,
+) -> Result<String, String> {
+    let logger = Logger::new().with_feature("writing_tools");
+    logger.info(&format!("Running autocorrect rules on project {}", project_id));
+
+    let db_path = get_db_path(&app)?;
+    let conn = crate::database::get_connection(&db_path)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+    let result = autocorrect::correct_project(&conn, &project_id)?;
+    for chapter_id in &result.chapters_changed {
+        if let Err(e) = crate::entity_index::index_chapter(&conn, chapter_id, None) {
+            logger.warn(&format!("Failed to index entities: {}", e));
+        }
+    }
+    serde_json::to_string(&result).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn run_full_writing_tools(
+    app: AppHandle,
     text: String,
+    project_id: Option<String>,
+    punctuation_preset: Option<String>,
+    numeral_style: Option<String>,
 ) -> Result<String, String> {
     let logger = Logger::new().with_feature("writing_tools");
     logger.info("Running full writing tools analysis");
 
-    let sensitive_words = WritingTools::detect_sensitive_words(&text);
+    let (words, whitelist) = sensitive_words(&app, project_id.as_deref())?;
+    let sensitive_words = WritingTools::detect_sensitive_words(&text, &words, &whitelist);
     let typos = WritingTools::detect_typos(&text);
     let grammar = WritingTools::check_grammar(&text);
-    let format = WritingTools::normalize_format(&text);
+    let format = WritingTools::normalize_format(&text, &punctuation_profile(punctuation_preset.as_deref(), None)?);
+    let settings = cliche_settings(&app, project_id.as_deref())?;
+    let cliches = WritingTools::detect_cliches(&text, &settings.custom, &settings.suppressed);
+    let typography = WritingTools::fix_typography(&text, &typography_settings(&app, project_id.as_deref())?);
+    let numerals = numeral_style::check(&text, numeral_style.as_deref().unwrap_or("chinese"))?;
 
     let full_analysis = serde_json::json!({
         "sensitive_words": sensitive_words,
         "typos": typos,
         "grammar": grammar,
         "format": format,
+        "cliches": cliches,
+        "typography": typography,
+        "numerals": numerals,
     });
 
     serde_json::to_string(&full_analysis).map_err(|e| e.to_string())
 }
+
+fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(debug_assertions) {
+        let mut project_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        project_dir.push("novel_studio_dev.db");
+        Ok(std::fs::canonicalize(&project_dir).unwrap_or(project_dir))
+    } else {
+        let app_data_dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        Ok(app_data_dir.join("novel_studio.db"))
+    }
+}
@@ -152,6 +152,22 @@ function App() {
     loadProjects();
   }, []);
 
+  // 保存后的自动更正在后台完成；编辑器里还是保存时的内容才替换为更正后的正文
+  useEffect(() => {
+    const unlisten = chapterService.onAutocorrected(({ chapter_id, original, content }) => {
+      const chapter = useProjectStore.getState().chapters.find((ch) => ch.id === chapter_id);
+      if (chapter?.content === original) {
+        updateChapter(chapter_id, content);
+      }
+      if (useProjectStore.getState().currentChapter?.id === chapter_id) {
+        setEditorContent((current) => (current === original ? content : current));
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   useEffect(() => {
     if (currentProject) {
       loadChapters(currentProject.id);
@@ -37,10 +37,9 @@ export const CollaborationPanel: React.FC<CollaborationPanelProps> = ({ projectI
       setCurrentUser(user);
 
       if (projectId) {
-        const newSessionId = await collaborationService.createSession(projectId);
+        const newSessionId = await collaborationService.createSession(projectId, user);
         setSessionId(newSessionId);
 
-        await collaborationService.joinSession(newSessionId, user);
         await loadSession(newSessionId);
         setIsConnected(true);
       }
@@ -306,35 +306,54 @@ export const TextAnalysisPanel: React.FC<TextAnalysisPanelProps> = ({ text, char
                 <div className="space-y-3">
                   <div className="grid grid-cols-2 gap-3">
                     <div className="p-3 bg-muted rounded-md">
-                      <div className="text-xs text-muted-foreground mb-1">Flesch 评分</div>
+                      <div className="text-xs text-muted-foreground mb-1">可读性评分</div>
                       <div
-                        className={`text-lg font-semibold ${getScoreColor(analysis.readability.flesch_score)}`}
+                        className={`text-lg font-semibold ${getScoreColor(analysis.readability.overall.score)}`}
                       >
-                        {analysis.readability.flesch_score.toFixed(1)}
+                        {analysis.readability.overall.score.toFixed(1)}
                       </div>
                     </div>
                     <div className="p-3 bg-muted rounded-md">
-                      <div className="text-xs text-muted-foreground mb-1">阅读级别</div>
+                      <div className="text-xs text-muted-foreground mb-1">阅读难度</div>
                       <div className="text-lg font-semibold">
                         {analysis.readability.reading_level}
                       </div>
                     </div>
                     <div className="p-3 bg-muted rounded-md">
-                      <div className="text-xs text-muted-foreground mb-1">句子复杂度</div>
-                      <div
-                        className={`text-lg font-semibold ${getScoreColor(100 - analysis.readability.avg_sentence_complexity)}`}
-                      >
-                        {analysis.readability.avg_sentence_complexity.toFixed(1)}
+                      <div className="text-xs text-muted-foreground mb-1">平均句长</div>
+                      <div className="text-lg font-semibold">
+                        {analysis.readability.overall.avg_sentence_length.toFixed(1)} 字
                       </div>
                     </div>
                     <div className="p-3 bg-muted rounded-md">
                       <div className="text-xs text-muted-foreground mb-1">统计</div>
                       <div className="text-sm space-y-1">
-                        <div>{analysis.readability.word_count} 词</div>
-                        <div>{analysis.readability.syllable_count} 音节</div>
+                        <div>分句层次 {analysis.readability.overall.avg_clause_depth.toFixed(1)}</div>
+                        <div>
+                          生僻字 {(analysis.readability.overall.rare_char_rate * 100).toFixed(1)}%
+                        </div>
                       </div>
                     </div>
                   </div>
+                  {analysis.readability.paragraphs.some((p) => p.metrics.issues.length > 0) && (
+                    <div className="space-y-2">
+                      {analysis.readability.paragraphs
+                        .filter((p) => p.metrics.issues.length > 0)
+                        .map((p) => (
+                          <div key={p.index} className="p-2 bg-muted rounded-md text-sm">
+                            <div className="flex justify-between">
+                              <span className="truncate">{p.excerpt}</span>
+                              <span className={getScoreColor(p.metrics.score)}>
+                                {p.metrics.score.toFixed(0)}
+                              </span>
+                            </div>
+                            <div className="text-xs text-muted-foreground">
+                              {p.metrics.issues.join("；")}
+                            </div>
+                          </div>
+                        ))}
+                    </div>
+                  )}
                 </div>
               )}
 
@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import type {
   Project,
   CreateProjectRequest,
@@ -72,6 +73,12 @@ export const chapterService = {
     return await invoke("update_chapter", { chapterId: id, title, content });
   },
 
+  async onAutocorrected(callback: (event: { chapter_id: string; original: string; content: string }) => void) {
+    return await listen<{ chapter_id: string; original: string; content: string }>("chapter://autocorrected", (event) => {
+      callback(event.payload);
+    });
+  },
+
   async generateVersions(request: GenerateChapterVersionsRequest): Promise<Chapter> {
     return await invoke("generate_chapter_versions", { request });
   },
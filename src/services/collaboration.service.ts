@@ -30,6 +30,7 @@ export interface CollaborationSession {
   project_id: string;
   users: User[];
   active_cursors: Record<string, CursorPosition>;
+  roles: Record<string, "owner" | "editor" | "commenter" | "viewer">;
 }
 
 class CollaborationService {
@@ -37,14 +38,14 @@ class CollaborationService {
   private currentUserId: string | null = null;
   private currentUserColor: string | null = null;
 
-  async createSession(projectId: string): Promise<string> {
-    const sessionId = await invoke<string>("collab_create_session", { projectId });
+  async createSession(projectId: string, owner: User): Promise<string> {
+    const sessionId = await invoke<string>("collab_create_session", { projectId, owner });
     this.currentSessionId = sessionId;
     return sessionId;
   }
 
-  async joinSession(sessionId: string, user: User): Promise<void> {
-    await invoke("collab_join_session", { sessionId, user });
+  async joinSession(sessionId: string, user: User, inviteToken: string): Promise<void> {
+    await invoke("collab_join_session", { sessionId, user, inviteToken });
     this.currentSessionId = sessionId;
   }
 
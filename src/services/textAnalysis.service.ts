@@ -14,6 +14,17 @@ export interface RhythmAnalysis {
   pacing_segments: PacingSegment[];
   action_vs_description_ratio: number;
   dialogue_ratio: number;
+  dialogue_percentage: number;
+  avg_dialogue_run: number;
+  unattributed_dialogues: UnattributedDialogue[];
+}
+
+export interface UnattributedDialogue {
+  start_paragraph: number;
+  end_paragraph: number;
+  position: number;
+  paragraph_count: number;
+  excerpt: string;
 }
 
 export interface PacingSegment {
@@ -41,12 +52,41 @@ export interface EmotionScore {
   score: number;
 }
 
+export type ReadabilityAudience = "children" | "young_adult" | "web_novel" | "literary";
+
+export interface ReadabilityModel {
+  audience: string;
+  ideal_sentence_length: number;
+  max_sentence_length: number;
+  ideal_clause_depth: number;
+  max_clause_depth: number;
+  max_rare_char_rate: number;
+  sentence_weight: number;
+  clause_weight: number;
+  rare_char_weight: number;
+}
+
+export interface ReadabilityMetrics {
+  sentence_count: number;
+  char_count: number;
+  avg_sentence_length: number;
+  avg_clause_depth: number;
+  rare_char_rate: number;
+  score: number;
+  issues: string[];
+}
+
+export interface ParagraphReadability {
+  index: number;
+  excerpt: string;
+  metrics: ReadabilityMetrics;
+}
+
 export interface ReadabilityAnalysis {
-  flesch_score: number;
+  model: ReadabilityModel;
+  overall: ReadabilityMetrics;
   reading_level: string;
-  avg_sentence_complexity: number;
-  syllable_count: number;
-  word_count: number;
+  paragraphs: ParagraphReadability[];
 }
 
 export interface RepetitionDetection {
@@ -110,8 +150,12 @@ class TextAnalysisService {
     return await invoke<EmotionAnalysis>("analyze_emotion", { text });
   }
 
-  async analyzeReadability(text: string): Promise<ReadabilityAnalysis> {
-    return await invoke<ReadabilityAnalysis>("analyze_readability", { text });
+  async analyzeReadability(
+    text: string,
+    audience?: ReadabilityAudience,
+    model?: ReadabilityModel
+  ): Promise<ReadabilityAnalysis> {
+    return await invoke<ReadabilityAnalysis>("analyze_readability", { text, audience, model });
   }
 
   async detectRepetitions(text: string, minRepetitions = 3): Promise<RepetitionDetection> {
@@ -129,11 +173,16 @@ class TextAnalysisService {
     });
   }
 
-  async runFullAnalysis(text: string, characters?: any[]): Promise<FullAnalysis> {
+  async runFullAnalysis(
+    text: string,
+    characters?: any[],
+    audience?: ReadabilityAudience
+  ): Promise<FullAnalysis> {
     const charactersJson = characters ? JSON.stringify(characters) : undefined;
     return await invoke<FullAnalysis>("run_full_analysis", {
       text,
       charactersJson,
+      audience,
     });
   }
 }
@@ -7,6 +7,8 @@ export interface ChapterSnapshot {
   content: string;
   order: number;
   word_count: number;
+  status?: string | null;
+  summary?: string | null;
 }
 
 export interface CharacterSnapshot {
@@ -16,6 +18,7 @@ export interface CharacterSnapshot {
   personality: string;
   appearance: string;
   background: string;
+  details?: Record<string, string | number | null> | null;
 }
 
 export interface WorldViewSnapshot {
@@ -23,6 +26,8 @@ export interface WorldViewSnapshot {
   name: string;
   category: string;
   description: string;
+  tags?: string | null;
+  status?: string | null;
 }
 
 export interface PlotPointSnapshot {
@@ -31,6 +36,10 @@ export interface PlotPointSnapshot {
   content: string;
   chapter_id: string | null;
   order: number;
+  parent_id?: string | null;
+  note?: string | null;
+  status?: string | null;
+  level?: number;
 }
 
 export interface SnapshotMetadata {
@@ -53,21 +62,48 @@ export interface ProjectSnapshot {
   plot_points: PlotPointSnapshot[];
   metadata: SnapshotMetadata;
   auto_generated: boolean;
+  tags?: SnapshotTag[];
+}
+
+export interface SnapshotTag {
+  id: string;
+  snapshot_id: string;
+  project_id: string;
+  tag: string;
+  note: string | null;
+  milestone: boolean;
+  created_at: string;
+}
+
+export interface ParagraphDiff {
+  change: "unchanged" | "added" | "removed" | "modified";
+  old_index: number | null;
+  new_index: number | null;
+  runs: Array<{
+    op: "equal" | "insert" | "delete";
+    text: string;
+  }>;
+}
+
+export interface ChapterDiff {
+  id: string;
+  title: string;
+  action: "created" | "modified" | "deleted";
+  title_change: {
+    field: string;
+    old_value: string | null;
+    new_value: string | null;
+  } | null;
+  paragraphs: ParagraphDiff[];
+  inserted_chars: number;
+  deleted_chars: number;
 }
 
 export interface VersionDiff {
   from_version: string;
   to_version: string;
   timestamp: number;
-  chapter_changes: Array<{
-    id: string;
-    action: "created" | "modified" | "deleted";
-    changes: Array<{
-      position: number;
-      removed: string;
-      added: string;
-    }>;
-  }>;
+  chapter_changes: ChapterDiff[];
   character_changes: Array<{
     id: string;
     name: string;
@@ -103,6 +139,7 @@ export interface VersionDiff {
 export interface VersionControlConfig {
   auto_save_enabled: boolean;
   auto_save_interval_minutes: number;
+  auto_save_word_delta: number;
   max_snapshots_per_project: number;
   compression_enabled: boolean;
 }
@@ -122,16 +159,16 @@ class VersionControlService {
     });
   }
 
-  async getSnapshots(projectId: string): Promise<ProjectSnapshot[]> {
-    return await invoke<ProjectSnapshot[]>("get_snapshots", { projectId });
+  async getSnapshots(projectId: string, tag?: string): Promise<ProjectSnapshot[]> {
+    return await invoke<ProjectSnapshot[]>("get_snapshots", { projectId, tag });
   }
 
   async getSnapshot(snapshotId: string): Promise<ProjectSnapshot> {
     return await invoke<ProjectSnapshot>("get_snapshot", { snapshotId });
   }
 
-  async restoreSnapshot(snapshotId: string): Promise<{ status: string }> {
-    return await invoke<{ status: string }>("restore_snapshot", { snapshotId });
+  async restoreSnapshot(snapshotId: string): Promise<{ status: string; restore_point_id: string }> {
+    return await invoke<{ status: string; restore_point_id: string }>("restore_snapshot", { snapshotId });
   }
 
   async deleteSnapshot(snapshotId: string): Promise<{ status: string }> {
@@ -5,6 +5,28 @@ export interface SensitiveWordMatch {
   position: number;
   context: string;
   severity: string;
+  dictionary: string;
+}
+
+export interface SensitiveWord {
+  word: string;
+  severity: string;
+  dictionary: string;
+}
+
+export interface SensitiveDictionary {
+  id: string;
+  name: string;
+  description?: string;
+  builtin: boolean;
+  enabled_by_default: boolean;
+  enabled: boolean;
+  word_count: number;
+}
+
+export interface DictionaryImport {
+  imported: number;
+  invalid_lines: string[];
 }
 
 export interface SensitiveWordDetection {
@@ -32,9 +54,20 @@ export interface GrammarIssue {
   suggestion: string;
 }
 
+export interface LineEdit {
+  id: string;
+  start: number;
+  end: number;
+  original: string;
+  issue: string;
+  suggestion: string;
+  rationale: string;
+}
+
 export interface GrammarCheck {
   grammar_issues: GrammarIssue[];
   total_count: number;
+  line_edits: LineEdit[];
 }
 
 export interface FormatChange {
@@ -44,38 +77,194 @@ export interface FormatChange {
   corrected: string;
 }
 
+export interface PunctuationProfile {
+  name: string;
+  full_width_punctuation: boolean;
+  half_width_alphanumerics: boolean;
+  normalize_ellipsis: boolean;
+  normalize_dash: boolean;
+  quote_style: "curly" | "corner";
+  latin_spacing: "add" | "remove" | "keep";
+  max_repeated_marks: number;
+}
+
+export type PunctuationPreset = "mainland" | "taiwan" | "web_serial";
+
 export interface FormatNormalization {
   original: string;
   normalized: string;
   changes: FormatChange[];
 }
 
+export interface TypographySettings {
+  cjk_latin_spacing: boolean;
+  smart_quotes: boolean;
+  normalize_units: boolean;
+}
+
+export type NumeralStyle = "chinese" | "arabic";
+
+export interface NumeralIssue {
+  issue_type: "numeral_style" | "measure_word";
+  position: number;
+  original: string;
+  suggestion: string;
+  description: string;
+  context: string;
+}
+
+export interface NumeralCheck {
+  style: NumeralStyle;
+  issues: NumeralIssue[];
+  total_count: number;
+  fixed: string;
+}
+
+export interface ClicheMatch {
+  phrase: string;
+  category: string;
+  position: number;
+  context: string;
+  suggestion: string;
+}
+
+export interface ClicheDetection {
+  cliches: ClicheMatch[];
+  total_count: number;
+}
+
+export interface ClicheSettings {
+  custom: string[];
+  suppressed: string[];
+}
+
 export interface FullWritingToolsAnalysis {
   sensitive_words: SensitiveWordDetection;
   typos: TypoDetection;
   grammar: GrammarCheck;
   format: FormatNormalization;
+  cliches: ClicheDetection;
+  typography: FormatNormalization;
+  numerals: NumeralCheck;
 }
 
 class WritingToolsService {
-  async detectSensitiveWords(text: string): Promise<SensitiveWordDetection> {
-    return await invoke<SensitiveWordDetection>("detect_sensitive_words", { text });
+  async detectSensitiveWords(text: string, projectId?: string): Promise<SensitiveWordDetection> {
+    return await invoke<SensitiveWordDetection>("detect_sensitive_words", { text, projectId });
+  }
+
+  async listSensitiveDictionaries(projectId?: string): Promise<SensitiveDictionary[]> {
+    return await invoke<SensitiveDictionary[]>("list_sensitive_dictionaries", { projectId });
+  }
+
+  async createSensitiveDictionary(
+    name: string,
+    description?: string,
+    enabledByDefault?: boolean
+  ): Promise<SensitiveDictionary> {
+    return await invoke<SensitiveDictionary>("create_sensitive_dictionary", { name, description, enabledByDefault });
+  }
+
+  async deleteSensitiveDictionary(dictionaryId: string): Promise<void> {
+    await invoke("delete_sensitive_dictionary", { dictionaryId });
+  }
+
+  async getSensitiveDictionaryWords(dictionaryId: string): Promise<SensitiveWord[]> {
+    return await invoke<SensitiveWord[]>("get_sensitive_dictionary_words", { dictionaryId });
+  }
+
+  async setSensitiveWord(dictionaryId: string, word: string, severity?: string): Promise<void> {
+    await invoke("set_sensitive_word", { dictionaryId, word, severity });
+  }
+
+  async importSensitiveDictionary(
+    dictionaryId: string,
+    filePath: string,
+    defaultSeverity?: string
+  ): Promise<DictionaryImport> {
+    return await invoke<DictionaryImport>("import_sensitive_dictionary", { dictionaryId, filePath, defaultSeverity });
+  }
+
+  async setSensitiveDictionaryEnabled(projectId: string, dictionaryId: string, enabled: boolean): Promise<void> {
+    await invoke("set_sensitive_dictionary_enabled", { projectId, dictionaryId, enabled });
+  }
+
+  async getSensitiveWhitelist(projectId: string): Promise<string[]> {
+    return await invoke<string[]>("get_sensitive_whitelist", { projectId });
+  }
+
+  async setSensitiveWhitelist(projectId: string, words: string[]): Promise<string[]> {
+    return await invoke<string[]>("set_sensitive_whitelist", { projectId, words });
   }
 
   async detectTypos(text: string): Promise<TypoDetection> {
     return await invoke<TypoDetection>("detect_typos", { text });
   }
 
-  async checkGrammar(text: string): Promise<GrammarCheck> {
-    return await invoke<GrammarCheck>("check_grammar", { text });
+  async checkGrammar(text: string, deep?: boolean, modelId?: string): Promise<GrammarCheck> {
+    return await invoke<GrammarCheck>("check_grammar", { text, deep, modelId });
+  }
+
+  async applyLineEdits(text: string, edits: LineEdit[]): Promise<string> {
+    return await invoke<string>("apply_line_edits", { text, edits });
+  }
+
+  async normalizeFormat(
+    text: string,
+    preset?: PunctuationPreset,
+    profile?: PunctuationProfile
+  ): Promise<FormatNormalization> {
+    return await invoke<FormatNormalization>("normalize_format", { text, preset, profile });
+  }
+
+  async fixTypography(
+    text: string,
+    projectId?: string,
+    settings?: TypographySettings
+  ): Promise<FormatNormalization> {
+    return await invoke<FormatNormalization>("fix_typography", { text, projectId, settings });
+  }
+
+  async checkNumerals(text: string, style?: NumeralStyle): Promise<NumeralCheck> {
+    return await invoke<NumeralCheck>("check_numerals", { text, style });
+  }
+
+  async getTypographySettings(projectId: string): Promise<TypographySettings> {
+    return await invoke<TypographySettings>("get_typography_settings", { projectId });
+  }
+
+  async setTypographySettings(projectId: string, settings: TypographySettings): Promise<TypographySettings> {
+    return await invoke<TypographySettings>("set_typography_settings", { projectId, settings });
+  }
+
+  async detectCliches(text: string, projectId?: string): Promise<ClicheDetection> {
+    return await invoke<ClicheDetection>("detect_cliches", { text, projectId });
+  }
+
+  async getClicheSettings(projectId: string): Promise<ClicheSettings> {
+    return await invoke<ClicheSettings>("get_cliche_settings", { projectId });
+  }
+
+  async setCustomCliches(projectId: string, phrases: string[]): Promise<ClicheSettings> {
+    return await invoke<ClicheSettings>("set_custom_cliches", { projectId, phrases });
   }
 
-  async normalizeFormat(text: string): Promise<FormatNormalization> {
-    return await invoke<FormatNormalization>("normalize_format", { text });
+  async setClicheSuppressed(projectId: string, phrase: string, suppressed: boolean): Promise<ClicheSettings> {
+    return await invoke<ClicheSettings>("set_cliche_suppressed", { projectId, phrase, suppressed });
   }
 
-  async runFullWritingTools(text: string): Promise<FullWritingToolsAnalysis> {
-    return await invoke<FullWritingToolsAnalysis>("run_full_writing_tools", { text });
+  async runFullWritingTools(
+    text: string,
+    projectId?: string,
+    punctuationPreset?: PunctuationPreset,
+    numeralStyle?: NumeralStyle
+  ): Promise<FullWritingToolsAnalysis> {
+    return await invoke<FullWritingToolsAnalysis>("run_full_writing_tools", {
+      text,
+      projectId,
+      punctuationPreset,
+      numeralStyle,
+    });
   }
 }
 